@@ -1,6 +1,7 @@
-use crate::daemon::model::Coin;
+use crate::daemon::model::{Coin, GetInfoWarning};
 use liana::miniscript::bitcoin::Network;
-use std::path::PathBuf;
+use liana_ui::component::{amount::BitcoinDisplayUnit, amount_input::ExchangeRate};
+use std::{collections::BTreeMap, path::PathBuf, time::Instant};
 
 #[derive(Debug, Clone)]
 pub struct Cache {
@@ -14,6 +15,44 @@ pub struct Cache {
     pub last_poll_timestamp: Option<u32>,
     /// The `last_poll_timestamp` when starting the application.
     pub last_poll_at_startup: Option<u32>,
+    /// Feerate estimates in sat/vb by confirmation target in blocks. Empty if the daemon could
+    /// not provide any.
+    pub fee_estimates: BTreeMap<u16, u64>,
+    /// When `fee_estimates` were last successfully fetched from the daemon.
+    pub fee_estimates_fetched_at: Option<Instant>,
+    /// Unit amounts are displayed and entered in, as set in the wallet settings.
+    pub bitcoin_unit: BitcoinDisplayUnit,
+    /// Price of a bitcoin to display fiat values with, `None` until a price source is set up.
+    pub exchange_rate: Option<ExchangeRate>,
+    /// Whether the daemon can reach its Bitcoin backend, as of the last update.
+    pub backend_status: BackendStatus,
+}
+
+/// Whether the daemon can reach its Bitcoin backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackendStatus {
+    #[default]
+    Reachable,
+    /// Requests to the backend fail since the given timestamp, and are being retried.
+    Unreachable { since: u32 },
+}
+
+impl BackendStatus {
+    pub fn from_warnings(warnings: &[GetInfoWarning]) -> Self {
+        warnings
+            .iter()
+            .find_map(|w| match w {
+                GetInfoWarning::BackendUnreachable { since } => {
+                    Some(Self::Unreachable { since: *since })
+                }
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn is_reachable(&self) -> bool {
+        *self == Self::Reachable
+    }
 }
 
 /// only used for tests.
@@ -28,6 +67,11 @@ impl std::default::Default for Cache {
             sync_progress: 1.0,
             last_poll_timestamp: None,
             last_poll_at_startup: None,
+            fee_estimates: BTreeMap::new(),
+            fee_estimates_fetched_at: None,
+            bitcoin_unit: BitcoinDisplayUnit::default(),
+            exchange_rate: None,
+            backend_status: BackendStatus::default(),
         }
     }
 }
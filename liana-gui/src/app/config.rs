@@ -1,15 +1,21 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use tracing_subscriber::filter;
 
+use crate::app::shortcut;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     /// Path to lianad configuration file.
     pub daemon_config_path: Option<PathBuf>,
     /// Path to lianad_rpc socket file.
     pub daemon_rpc_path: Option<PathBuf>,
+    /// Name of the wallet to use if the lianad behind the socket serves several wallets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daemon_wallet: Option<String>,
     /// log level, can be "info", "debug", "trace".
     pub log_level: Option<String>,
     /// Use iced debug feature if true.
@@ -17,6 +23,9 @@ pub struct Config {
     /// Start internal bitcoind executable.
     #[serde(default)]
     pub start_internal_bitcoind: bool,
+    /// Keyboard shortcuts overriding the default ones, e.g. `"ctrl+h" = "home"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shortcuts: BTreeMap<String, shortcut::Action>,
 }
 
 pub const DEFAULT_FILE_NAME: &str = "gui.toml";
@@ -26,9 +35,11 @@ impl Config {
         Self {
             daemon_config_path: Some(daemon_config_path),
             daemon_rpc_path: None,
+            daemon_wallet: None,
             log_level: None,
             debug: None,
             start_internal_bitcoind,
+            shortcuts: BTreeMap::new(),
         }
     }
 
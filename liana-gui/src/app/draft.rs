@@ -0,0 +1,220 @@
+//! Draft is the module to keep on disk the spend being composed in the Send screen, so that it
+//! can be resumed after the GUI was closed or the user navigated away.
+//! The draft only holds what the user entered: it never contains a PSBT, let alone signatures.
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use liana::miniscript::bitcoin::{Network, OutPoint};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the draft file name, followed by the checksum of the wallet descriptor for the
+/// drafts of different wallets on the same network not to be mixed up.
+pub const DRAFT_FILE_PREFIX: &str = "spend_draft_";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SpendDraft {
+    /// When the draft was last saved, as a UNIX timestamp.
+    pub saved_at: u32,
+    pub recipients: Vec<DraftRecipient>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_label: Option<String>,
+    /// The coins selected by the user, empty if the coin selection is left to the wallet.
+    #[serde(default)]
+    pub coins: Vec<OutPoint>,
+    /// The feerate in sats/vbyte, as entered.
+    #[serde(default)]
+    pub feerate: String,
+    /// The timelock of the recovery path to spend through, `None` for the primary path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spend_path: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DraftRecipient {
+    pub address: String,
+    /// The amount in sats, if a valid one was entered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    #[serde(default)]
+    pub label: String,
+    /// Whether the recipient receives the max amount.
+    #[serde(default)]
+    pub send_max: bool,
+}
+
+impl DraftRecipient {
+    fn is_empty(&self) -> bool {
+        self.address.is_empty() && self.amount.is_none() && self.label.is_empty()
+    }
+}
+
+fn draft_path(datadir: PathBuf, network: Network, wallet_checksum: &str) -> PathBuf {
+    let mut path = datadir;
+    path.push(network.to_string());
+    path.push(format!("{}{}.json", DRAFT_FILE_PREFIX, wallet_checksum));
+    path
+}
+
+impl SpendDraft {
+    /// Whether nothing worth resuming was entered.
+    pub fn is_empty(&self) -> bool {
+        self.recipients.iter().all(|r| r.is_empty())
+            && self
+                .batch_label
+                .as_ref()
+                .map(|l| l.is_empty())
+                .unwrap_or(true)
+            && self.coins.is_empty()
+            && self.feerate.is_empty()
+    }
+
+    /// The total amount sent to the recipients, leaving aside the one receiving the max.
+    pub fn total_amount(&self) -> u64 {
+        self.recipients
+            .iter()
+            .filter(|r| !r.send_max)
+            .filter_map(|r| r.amount)
+            .sum()
+    }
+
+    /// Read the draft of the wallet, if any.
+    pub fn from_file(
+        datadir: PathBuf,
+        network: Network,
+        wallet_checksum: &str,
+    ) -> Result<Option<Self>, DraftError> {
+        match std::fs::read(draft_path(datadir, network, wallet_checksum)) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map(Some)
+                .map_err(|e| DraftError::ReadingFile(format!("Parsing draft file: {}", e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(DraftError::ReadingFile(format!(
+                "Reading draft file: {}",
+                e
+            ))),
+        }
+    }
+
+    pub fn to_file(
+        &self,
+        datadir: PathBuf,
+        network: Network,
+        wallet_checksum: &str,
+    ) -> Result<(), DraftError> {
+        let content = serde_json::to_string_pretty(&self)
+            .map_err(|e| DraftError::WritingFile(format!("Failed to serialize draft: {}", e)))?;
+
+        let mut draft_file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(draft_path(datadir, network, wallet_checksum))
+            .map_err(|e| DraftError::WritingFile(e.to_string()))?;
+
+        draft_file
+            .write_all(content.as_bytes())
+            .map_err(|e| DraftError::WritingFile(e.to_string()))
+    }
+
+    /// Remove the draft of the wallet, if any.
+    pub fn delete(
+        datadir: PathBuf,
+        network: Network,
+        wallet_checksum: &str,
+    ) -> Result<(), DraftError> {
+        match std::fs::remove_file(draft_path(datadir, network, wallet_checksum)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(DraftError::WritingFile(e.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DraftError {
+    ReadingFile(String),
+    WritingFile(String),
+}
+
+impl std::fmt::Display for DraftError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::ReadingFile(e) => write!(f, "Error while reading file: {}", e),
+            Self::WritingFile(e) => write!(f, "Error while writing file: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn draft_per_wallet() {
+        let datadir = std::env::temp_dir().join(format!("liana-draft-{}", std::process::id()));
+        std::fs::create_dir_all(datadir.join(Network::Regtest.to_string())).unwrap();
+
+        let draft = SpendDraft {
+            saved_at: 1_700_000_000,
+            recipients: vec![DraftRecipient {
+                address: "bcrt1qjx7z7e0wvajmywcxeqv0pz4zguzuvt7sq3kgh6".to_string(),
+                amount: Some(100_000),
+                label: "rent".to_string(),
+                send_max: false,
+            }],
+            batch_label: None,
+            coins: vec![OutPoint::from_str(
+                "f1fbe8d9b8e05b3d39e1b8b7ad4e2cd29c53e3e7f4a1a2c7e8ad4b1e3c8f0a55:1",
+            )
+            .unwrap()],
+            feerate: "2".to_string(),
+            spend_path: None,
+        };
+        assert!(!draft.is_empty());
+        assert_eq!(draft.total_amount(), 100_000);
+
+        draft
+            .to_file(datadir.clone(), Network::Regtest, "abcdefgh")
+            .unwrap();
+        assert_eq!(
+            SpendDraft::from_file(datadir.clone(), Network::Regtest, "abcdefgh").unwrap(),
+            Some(draft)
+        );
+        // Another wallet on the same network doesn't see it.
+        assert_eq!(
+            SpendDraft::from_file(datadir.clone(), Network::Regtest, "12345678").unwrap(),
+            None
+        );
+
+        SpendDraft::delete(datadir.clone(), Network::Regtest, "abcdefgh").unwrap();
+        assert_eq!(
+            SpendDraft::from_file(datadir.clone(), Network::Regtest, "abcdefgh").unwrap(),
+            None
+        );
+        // Deleting a missing draft is fine.
+        SpendDraft::delete(datadir.clone(), Network::Regtest, "abcdefgh").unwrap();
+
+        std::fs::remove_dir_all(datadir).unwrap();
+    }
+
+    #[test]
+    fn empty_draft() {
+        let draft = SpendDraft {
+            saved_at: 1_700_000_000,
+            recipients: vec![DraftRecipient {
+                address: String::new(),
+                amount: None,
+                label: String::new(),
+                send_max: false,
+            }],
+            batch_label: None,
+            coins: Vec::new(),
+            feerate: String::new(),
+            spend_path: None,
+        };
+        assert!(draft.is_empty());
+    }
+}
@@ -6,7 +6,9 @@ use lianad::config::ConfigError;
 
 use crate::{
     app::{settings::SettingsError, wallet::WalletError},
+    camera::CameraError,
     daemon::DaemonError,
+    profile::ProfileError,
 };
 
 #[derive(Debug)]
@@ -18,6 +20,8 @@ pub enum Error {
     HardwareWallet(async_hwi::Error),
     Desc(LianaDescError),
     Spend(SpendCreationError),
+    Camera(CameraError),
+    ManagedBitcoind(String),
 }
 
 impl std::fmt::Display for Error {
@@ -53,10 +57,13 @@ impl std::fmt::Display for Error {
                     write!(f, "[{:?}] {}", code, e)
                 }
                 DaemonError::CoinSelectionError => write!(f, "{}", e),
+                DaemonError::Broadcast(e) => write!(f, "{}", e.message),
             },
             Self::Unexpected(e) => write!(f, "Unexpected error: {}", e),
             Self::HardwareWallet(e) => write!(f, "error: {}\nPlease check if the device is still connected and unlocked with the correct firmware open for the current network and no other application is accessing the device.", e),
             Self::Desc(e) => write!(f, "Liana descriptor error: {}", e),
+            Self::Camera(e) => write!(f, "{}", e),
+            Self::ManagedBitcoind(e) => write!(f, "{}", e),
         }
     }
 }
@@ -67,6 +74,18 @@ impl From<ConfigError> for Error {
     }
 }
 
+impl From<CameraError> for Error {
+    fn from(error: CameraError) -> Self {
+        Error::Camera(error)
+    }
+}
+
+impl From<ProfileError> for Error {
+    fn from(error: ProfileError) -> Self {
+        Error::Config(error.to_string())
+    }
+}
+
 impl From<WalletError> for Error {
     fn from(error: WalletError) -> Self {
         Error::Wallet(error)
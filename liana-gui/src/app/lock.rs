@@ -0,0 +1,135 @@
+//! The lock screen hiding the wallet after some inactivity, and the PIN prompt confirming
+//! signing and broadcasting actions when the auto-lock is enabled.
+//! See [`AutoLockSettings`] for why it is a privacy screen rather than a security boundary.
+//! Also the prompt for the passphrase of a hot signer protected by one.
+use std::time::{Duration, Instant};
+
+use iced::widget::text_input;
+use liana::miniscript::bitcoin::bip32::Fingerprint;
+
+use crate::app::{settings::AutoLockSettings, view};
+
+/// The delay before another attempt is allowed can't be longer than this.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+
+pub fn pin_input_id() -> text_input::Id {
+    text_input::Id::new("lock-pin")
+}
+
+pub fn passphrase_input_id() -> text_input::Id {
+    text_input::Id::new("lock-signer-passphrase")
+}
+
+#[derive(Debug)]
+pub struct Lock {
+    pub pin: String,
+    /// The action confirmed by the PIN, if any. Otherwise the wallet was locked for inactivity.
+    pub pending: Option<view::Message>,
+}
+
+impl Lock {
+    pub fn new(pending: Option<view::Message>) -> Self {
+        Self {
+            pin: String::new(),
+            pending,
+        }
+    }
+}
+
+/// The prompt for the passphrase of a hot signer.
+#[derive(Debug)]
+pub struct SignerUnlock {
+    /// The fingerprint of the hot signer to unlock.
+    pub fingerprint: Fingerprint,
+    pub passphrase: String,
+    /// The signing action waiting for the signer to be unlocked, if any. Otherwise it is unlocked
+    /// when opening the wallet.
+    pub pending: Option<view::Message>,
+}
+
+impl SignerUnlock {
+    pub fn new(fingerprint: Fingerprint, pending: Option<view::Message>) -> Self {
+        Self {
+            fingerprint,
+            passphrase: String::new(),
+            pending,
+        }
+    }
+}
+
+/// The failed attempts at entering the PIN, or the passphrase of the hot signer. Each failure
+/// doubles the delay before the next attempt is allowed.
+#[derive(Debug, Default)]
+pub struct Attempts {
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Attempts {
+    pub fn has_failed(&self) -> bool {
+        self.failures > 0
+    }
+
+    /// The time left before another attempt is allowed, if any.
+    pub fn retry_in(&self, now: Instant) -> Option<Duration> {
+        self.retry_at
+            .filter(|retry_at| *retry_at > now)
+            .map(|retry_at| retry_at - now)
+    }
+
+    /// Whether the PIN is correct. Always false while another attempt is not allowed yet.
+    pub fn check(&mut self, settings: &AutoLockSettings, pin: &str, now: Instant) -> bool {
+        self.check_with(now, || settings.verify(pin))
+    }
+
+    /// Whether the attempt verified. It is not even tried while another attempt is not allowed
+    /// yet.
+    pub fn check_with<F: FnOnce() -> bool>(&mut self, now: Instant, verify: F) -> bool {
+        if self.retry_in(now).is_some() {
+            return false;
+        }
+        if verify() {
+            *self = Self::default();
+            return true;
+        }
+        self.failures += 1;
+        self.retry_at = Some(now + retry_delay(self.failures));
+        false
+    }
+}
+
+fn retry_delay(failures: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(failures.saturating_sub(1))).min(MAX_RETRY_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pin_attempts_backoff() {
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+        assert_eq!(retry_delay(5), Duration::from_secs(16));
+        assert_eq!(retry_delay(10), Duration::from_secs(512));
+        assert_eq!(retry_delay(11), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(100), MAX_RETRY_DELAY);
+
+        let settings = AutoLockSettings::new(5, "1234").unwrap();
+        let mut attempts = Attempts::default();
+        let now = Instant::now();
+        assert!(!attempts.check(&settings, "0000", now));
+        assert!(attempts.has_failed());
+        assert_eq!(attempts.retry_in(now), Some(Duration::from_secs(1)));
+        // The right PIN is refused until the delay is over.
+        assert!(!attempts.check(&settings, "1234", now));
+        let now = now + Duration::from_secs(1);
+        assert_eq!(attempts.retry_in(now), None);
+        assert!(!attempts.check(&settings, "0000", now));
+        assert_eq!(attempts.retry_in(now), Some(Duration::from_secs(2)));
+        let now = now + Duration::from_secs(2);
+        assert!(attempts.check(&settings, "1234", now));
+        assert!(!attempts.has_failed());
+        assert_eq!(attempts.retry_in(now), None);
+    }
+}
@@ -9,6 +9,8 @@ pub enum Menu {
     Settings,
     Coins,
     CreateSpendTx,
+    /// A new spend with these coins selected.
+    CreateSpendPreSelected(Vec<OutPoint>),
     Recovery,
     RefreshCoins(Vec<OutPoint>),
     PsbtPreSelected(Txid),
@@ -1,41 +1,75 @@
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
 
+use iced::keyboard;
 use liana::miniscript::bitcoin::{
     bip32::{ChildNumber, Fingerprint},
     psbt::Psbt,
-    Address, Txid,
+    Address, OutPoint, Txid,
 };
 use lianad::config::Config as DaemonConfig;
 
 use crate::{
     app::{cache::Cache, error::Error, view, wallet::Wallet},
+    camera::{Camera, ScanMessage},
     daemon::model::*,
+    download,
     export::ExportMessage,
     hw::HardwareWalletMessage,
+    node::bitcoind::Bitcoind,
 };
 
 #[derive(Debug)]
 pub enum Message {
     Tick,
+    AutoLockTick,
     UpdateCache(Result<Cache, Error>),
+    /// Whether the internal daemon still responds after a failed update of the cache, with the
+    /// error of the update if it does not.
+    DaemonChecked(Result<(), Error>),
     UpdatePanelCache(/* is current panel */ bool, Result<Cache, Error>),
+    KeyPressed(
+        keyboard::Key,
+        keyboard::Modifiers,
+        /* captured by a widget, e.g. a focused text input */ bool,
+    ),
     View(view::Message),
     LoadDaemonConfig(Box<DaemonConfig>),
     DaemonConfigLoaded(Result<(), Error>),
+    BitcoinBackendChecked(Result<(), Error>),
+    ManagedBitcoind(ManagedBitcoindProgress),
     LoadWallet(Wallet),
     Info(Result<GetInfoResult, Error>),
     ReceiveAddress(Result<(Address, ChildNumber), Error>),
+    AddressesUsage(Result<Vec<AddressInfo>, Error>),
+    ReusedAddresses(Txid, Result<Vec<Address>, Error>),
+    PendingSpendFeeInfo(Txid, Result<Option<PendingSpendFeeInfo>, Error>),
     Coins(Result<Vec<Coin>, Error>),
     Labels(Result<HashMap<String, String>, Error>),
+    CoinTagUpdated(Result<(OutPoint, Option<String>), Error>),
     SpendTxs(Result<Vec<SpendTx>, Error>),
     Psbt(Result<(Psbt, Vec<String>), Error>),
     RbfPsbt(Result<Txid, Error>),
+    /// The replacement created for the feerate, if any, to preview it before storing it.
+    RbfPreview(Option<u64>, Result<Box<RbfPreview>, Error>),
+    CpfpPsbt(Result<CpfpPackage, Error>),
     Recovery(Result<SpendTx, Error>),
     Signed(Fingerprint, Result<Psbt, Error>),
     WalletUpdated(Result<Arc<Wallet>, Error>),
+    ProfileExported(Result<Option<PathBuf>, Error>),
+    ProfileImported(Result<Option<Arc<Wallet>>, Error>),
+    PolicyDiagramExported(Result<Option<PathBuf>, Error>),
     Updated(Result<(), Error>),
+    /// The name of each PSBT file chosen, along with its content if it could be parsed.
+    PsbtFiles(Vec<(String, Result<Psbt, String>)>),
     Saved(Result<(), Error>),
+    SavedForLater(Result<i64, Error>),
+    SavedPsbts(Result<Vec<SavedPsbtEntry>, Error>),
+    Draft(Result<(SavedPsbtEntry, Vec<Coin>), Error>),
+    DraftDeleted(Result<i64, Error>),
+    ScheduledBroadcast(Txid, Result<(), Error>),
+    Notified(Result<(), Error>),
     Verified(Fingerprint, Result<(), Error>),
     StartRescan(Result<(), Error>),
     HardwareWallets(HardwareWalletMessage),
@@ -44,8 +78,22 @@ pub enum Message {
     Payments(Result<Vec<Payment>, Error>),
     PaymentsExtension(Result<Vec<Payment>, Error>),
     Payment(Result<(HistoryTransaction, usize), Error>),
-    LabelsUpdated(Result<HashMap<String, Option<String>>, Error>),
+    /// The labels given to the daemon, and whether it stored them.
+    LabelsUpdated(HashMap<String, Option<String>>, Result<(), Error>),
     BroadcastModal(Result<HashSet<Txid>, Error>),
     RbfModal(Box<HistoryTransaction>, bool, Result<HashSet<Txid>, Error>),
     Export(ExportMessage),
+    Cameras(Result<Vec<Camera>, Error>),
+    QrScan(ScanMessage),
+}
+
+/// The steps of an operation on the bitcoind managed by the GUI, reported as they complete.
+#[derive(Debug)]
+pub enum ManagedBitcoindProgress {
+    Downloaded(download::Progress),
+    Installed(Result<(), Error>),
+    Stopped(Result<(), Error>),
+    Applied(Result<(), Error>),
+    Restarted(Result<Bitcoind, Error>),
+    Checked(Result<(), Error>),
 }
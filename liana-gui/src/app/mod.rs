@@ -1,27 +1,32 @@
 pub mod cache;
 pub mod config;
+pub mod draft;
+pub mod lock;
 pub mod menu;
 pub mod message;
+pub mod notification;
 pub mod settings;
+pub mod shortcut;
 pub mod state;
 pub mod view;
 pub mod wallet;
 
 mod error;
 
+use std::collections::HashSet;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use iced::{clipboard, time, Command, Subscription};
+use iced::{clipboard, keyboard, time, widget::text_input, Command, Subscription};
 use tokio::runtime::Handle;
 use tracing::{error, info, warn};
 
 pub use liana::miniscript::bitcoin;
 use liana_ui::{
-    component::network_banner,
+    component::{amount, modal::Modal, network_banner, toast},
     widget::{Column, Element},
 };
 pub use lianad::{commands::CoinStatus, config::Config as DaemonConfig};
@@ -29,6 +34,9 @@ pub use lianad::{commands::CoinStatus, config::Config as DaemonConfig};
 pub use config::Config;
 pub use message::Message;
 
+use lock::{Attempts, Lock, SignerUnlock};
+use message::ManagedBitcoindProgress;
+use shortcut::{Action, CommandPalette, Shortcuts};
 use state::{
     CoinsPanel, CreateSpendPanel, Home, PsbtsPanel, ReceivePanel, RecoveryPanel, State,
     TransactionsPanel,
@@ -36,13 +44,23 @@ use state::{
 use wallet::{sync_status, SyncStatus};
 
 use crate::{
-    app::{cache::Cache, error::Error, menu::Menu, wallet::Wallet},
+    app::{
+        cache::{BackendStatus, Cache},
+        error::Error,
+        menu::Menu,
+        wallet::Wallet,
+    },
     daemon::{embedded::EmbeddedDaemon, Daemon, DaemonBackend},
+    loader::{self, Loader},
     node::bitcoind::Bitcoind,
+    shutdown::Shutdown,
 };
 
 use self::state::SettingsState;
 
+/// For how long the fee estimates are reused before being fetched again from the daemon.
+const FEE_ESTIMATES_TTL: Duration = Duration::from_secs(60);
+
 struct Panels {
     current: Menu,
     home: Home,
@@ -87,12 +105,14 @@ impl Panels {
                 &cache.coins,
                 cache.blockheight as u32,
                 cache.network,
+                cache.datadir_path.clone(),
             ),
             settings: state::SettingsState::new(
                 data_dir,
                 wallet.clone(),
+                cache.network,
                 daemon_backend,
-                internal_bitcoind.is_some(),
+                internal_bitcoind.cloned(),
             ),
         }
     }
@@ -107,6 +127,7 @@ impl Panels {
             Menu::Settings => &self.settings,
             Menu::Coins => &self.coins,
             Menu::CreateSpendTx => &self.create_spend,
+            Menu::CreateSpendPreSelected(_) => &self.create_spend,
             Menu::Recovery => &self.recovery,
             Menu::RefreshCoins(_) => &self.create_spend,
             Menu::PsbtPreSelected(_) => &self.psbts,
@@ -123,6 +144,7 @@ impl Panels {
             Menu::Settings => &mut self.settings,
             Menu::Coins => &mut self.coins,
             Menu::CreateSpendTx => &mut self.create_spend,
+            Menu::CreateSpendPreSelected(_) => &mut self.create_spend,
             Menu::Recovery => &mut self.recovery,
             Menu::RefreshCoins(_) => &mut self.create_spend,
             Menu::PsbtPreSelected(_) => &mut self.psbts,
@@ -138,17 +160,35 @@ pub struct App {
     internal_bitcoind: Option<Bitcoind>,
 
     panels: Panels,
+    /// Scheduled broadcasts currently being performed.
+    broadcasting: HashSet<bitcoin::Txid>,
+    /// Outcome of the scheduled broadcasts, with the error if any, until dismissed.
+    broadcast_notifications: Vec<(bitcoin::Txid, Option<String>)>,
+    shortcuts: Shortcuts,
+    /// The command palette, if opened.
+    palette: Option<CommandPalette>,
+    /// Last time the user interacted with the application, for the auto-lock.
+    last_activity: Instant,
+    /// Set while the application is locked or a PIN is asked to confirm an action.
+    lock: Option<Lock>,
+    pin_attempts: Attempts,
+    /// Set while the passphrase of the hot signer is asked for.
+    signer_unlock: Option<SignerUnlock>,
+    passphrase_attempts: Attempts,
+    /// The error the internal daemon stopped with, if it crashed.
+    daemon_crash: Option<Error>,
 }
 
 impl App {
     pub fn new(
-        cache: Cache,
+        mut cache: Cache,
         wallet: Arc<Wallet>,
         config: Config,
         daemon: Arc<dyn Daemon + Sync + Send>,
         data_dir: PathBuf,
         internal_bitcoind: Option<Bitcoind>,
     ) -> (App, Command<Message>) {
+        cache.bitcoin_unit = wallet.bitcoin_unit;
         let mut panels = Panels::new(
             &cache,
             wallet.clone(),
@@ -157,6 +197,18 @@ impl App {
             internal_bitcoind.as_ref(),
         );
         let cmd = panels.home.reload(daemon.clone(), wallet.clone());
+        let shortcuts = Shortcuts::new(&config.shortcuts);
+        // The hot signers protected by a passphrase are unlocked when opening the wallet, unless
+        // it was set to wait for the first signing attempt.
+        let signer_unlock = wallet
+            .locked_signer()
+            .filter(|_| wallet.hot_signer_unlock == settings::HotSignerUnlock::OnOpen)
+            .map(|fingerprint| SignerUnlock::new(fingerprint, None));
+        let cmd = if signer_unlock.is_some() {
+            Command::batch(vec![cmd, text_input::focus(lock::passphrase_input_id())])
+        } else {
+            cmd
+        };
         (
             Self {
                 panels,
@@ -165,12 +217,22 @@ impl App {
                 daemon,
                 wallet,
                 internal_bitcoind,
+                broadcasting: HashSet::new(),
+                broadcast_notifications: Vec::new(),
+                shortcuts,
+                palette: None,
+                last_activity: Instant::now(),
+                lock: None,
+                pin_attempts: Attempts::default(),
+                signer_unlock,
+                passphrase_attempts: Attempts::default(),
+                daemon_crash: None,
             },
             cmd,
         )
     }
 
-    fn set_current_panel(&mut self, menu: Menu) -> Command<Message> {
+    pub fn set_current_panel(&mut self, menu: Menu) -> Command<Message> {
         self.panels.current_mut().interrupt();
 
         match &menu {
@@ -181,9 +243,8 @@ impl App {
                         .await
                         .map(|txs| txs.first().cloned())
                 }) {
-                    self.panels.transactions.preselect(tx);
                     self.panels.current = menu;
-                    return Command::none();
+                    return self.panels.transactions.preselect(self.daemon.clone(), tx);
                 };
             }
             menu::Menu::PsbtPreSelected(txid) => {
@@ -202,7 +263,16 @@ impl App {
                 };
             }
             menu::Menu::RefreshCoins(preselected) => {
-                self.panels.create_spend = CreateSpendPanel::new_self_send(
+                self.panels.create_spend = CreateSpendPanel::new_refresh(
+                    self.wallet.clone(),
+                    &self.cache.coins,
+                    self.cache.blockheight as u32,
+                    preselected,
+                    self.cache.network,
+                );
+            }
+            menu::Menu::CreateSpendPreSelected(preselected) => {
+                self.panels.create_spend = CreateSpendPanel::new_preselected(
                     self.wallet.clone(),
                     &self.cache.coins,
                     self.cache.blockheight as u32,
@@ -218,6 +288,7 @@ impl App {
                         &self.cache.coins,
                         self.cache.blockheight as u32,
                         self.cache.network,
+                        self.cache.datadir_path.clone(),
                     );
                 }
             }
@@ -230,6 +301,158 @@ impl App {
             .reload(self.daemon.clone(), self.wallet.clone())
     }
 
+    pub fn record_activity(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    fn update_lock(&mut self, message: view::LockMessage) -> Command<Message> {
+        match message {
+            view::LockMessage::PinEdited(pin) => {
+                if let Some(lock) = &mut self.lock {
+                    lock.pin = pin;
+                }
+            }
+            view::LockMessage::Cancel => {
+                // Only the confirmation of an action can be cancelled.
+                if self
+                    .lock
+                    .as_ref()
+                    .map_or(false, |lock| lock.pending.is_some())
+                {
+                    self.lock = None;
+                }
+            }
+            view::LockMessage::Unlock => {
+                let (lock, settings) = match (&mut self.lock, &self.wallet.auto_lock) {
+                    (Some(lock), Some(settings)) => (lock, settings),
+                    _ => {
+                        // The auto-lock was disabled in the meantime.
+                        self.lock = None;
+                        return Command::none();
+                    }
+                };
+                if !self.pin_attempts.check(settings, &lock.pin, Instant::now()) {
+                    lock.pin.clear();
+                    return Command::none();
+                }
+                self.record_activity();
+                if let Some(pending) = self.lock.take().and_then(|lock| lock.pending) {
+                    return self.panels.current_mut().update(
+                        self.daemon.clone(),
+                        &self.cache,
+                        Message::View(pending),
+                    );
+                }
+            }
+        }
+        Command::none()
+    }
+
+    fn update_signer_unlock(&mut self, message: view::SignerUnlockMessage) -> Command<Message> {
+        match message {
+            view::SignerUnlockMessage::PassphraseEdited(passphrase) => {
+                if let Some(unlock) = &mut self.signer_unlock {
+                    unlock.passphrase = passphrase;
+                }
+            }
+            view::SignerUnlockMessage::Cancel => {
+                self.signer_unlock = None;
+            }
+            view::SignerUnlockMessage::Unlock => {
+                let (unlock, signer) = match self.signer_unlock.as_mut().and_then(|unlock| {
+                    let signer = self.wallet.signer(&unlock.fingerprint)?;
+                    Some((unlock, signer))
+                }) {
+                    Some(res) => res,
+                    None => {
+                        self.signer_unlock = None;
+                        return Command::none();
+                    }
+                };
+                let passphrase = &unlock.passphrase;
+                let unlocked = self.passphrase_attempts.check_with(Instant::now(), || {
+                    signer.unlock(passphrase).unwrap_or_else(|e| {
+                        warn!("Failed to derive the hot signer key: {}", e);
+                        false
+                    })
+                });
+                if !unlocked {
+                    unlock.passphrase.clear();
+                    return Command::none();
+                }
+                match self.signer_unlock.take().and_then(|unlock| unlock.pending) {
+                    Some(pending) => {
+                        return self.panels.current_mut().update(
+                            self.daemon.clone(),
+                            &self.cache,
+                            Message::View(pending),
+                        );
+                    }
+                    // When opening the wallet, the other protected hot signers are unlocked next.
+                    None => {
+                        if let Some(fingerprint) = self.wallet.locked_signer() {
+                            self.signer_unlock = Some(SignerUnlock::new(fingerprint, None));
+                            return text_input::focus(lock::passphrase_input_id());
+                        }
+                    }
+                }
+            }
+        }
+        Command::none()
+    }
+
+    /// Forget the keys of the hot signers derived using their passphrase, if any.
+    fn lock_signer(&mut self) {
+        self.signer_unlock = None;
+        for signer in &self.wallet.signers {
+            signer.lock();
+        }
+    }
+
+    fn toggle_palette(&mut self) -> Command<Message> {
+        if self.palette.take().is_some() {
+            Command::none()
+        } else {
+            self.palette = Some(CommandPalette::default());
+            text_input::focus(shortcut::palette_input_id())
+        }
+    }
+
+    fn run_action(&mut self, action: Action) -> Command<Message> {
+        if action == Action::CommandPalette {
+            return self.toggle_palette();
+        }
+        self.palette = None;
+        match action {
+            Action::NewSpend => {
+                self.panels.create_spend = CreateSpendPanel::new(
+                    self.wallet.clone(),
+                    &self.cache.coins,
+                    self.cache.blockheight as u32,
+                    self.cache.network,
+                    self.cache.datadir_path.clone(),
+                );
+                self.set_current_panel(Menu::CreateSpendTx)
+            }
+            Action::Refresh => self
+                .panels
+                .current_mut()
+                .reload(self.daemon.clone(), self.wallet.clone()),
+            Action::ToggleAmountsHidden => {
+                amount::set_amounts_hidden(!amount::amounts_hidden());
+                Command::none()
+            }
+            Action::LockSigner => {
+                self.lock_signer();
+                Command::none()
+            }
+            _ => match action.menu() {
+                Some(menu) => self.set_current_panel(menu),
+                None => Command::none(),
+            },
+        }
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
         Subscription::batch(vec![
             time::every(Duration::from_secs(
@@ -261,41 +484,144 @@ impl App {
                 },
             ))
             .map(|_| Message::Tick),
+            if self.wallet.auto_lock.is_some() {
+                // While locked, refresh every second the delay before the next PIN attempt.
+                time::every(Duration::from_secs(if self.lock.is_some() {
+                    1
+                } else {
+                    10
+                }))
+                .map(|_| Message::AutoLockTick)
+            } else {
+                Subscription::none()
+            },
             self.panels.current().subscription(),
+            // An operation on the managed bitcoind keeps going in the background.
+            self.panels.settings.managed_bitcoind_subscription(),
         ])
     }
 
-    pub fn stop(&mut self) {
+    /// What must be stopped before exiting.
+    pub fn datadir_path(&self) -> &PathBuf {
+        &self.cache.datadir_path
+    }
+
+    pub fn network(&self) -> bitcoin::Network {
+        self.cache.network
+    }
+
+    pub fn wallet_name(&self) -> &str {
+        &self.wallet.name
+    }
+
+    pub fn stop(&mut self) -> Shutdown {
         info!("Close requested");
+        let shutdown = Shutdown::new().with_daemon(self.daemon.clone());
         if self.daemon.backend().is_embedded() {
-            if let Err(e) = Handle::current().block_on(async { self.daemon.stop().await }) {
-                error!("{}", e);
-            } else {
-                info!("Internal daemon stopped");
-            }
-            if let Some(bitcoind) = &self.internal_bitcoind {
-                bitcoind.stop();
-            }
+            shutdown.with_bitcoind(self.internal_bitcoind.clone())
+        } else {
+            shutdown
         }
     }
 
     pub fn update(&mut self, message: Message) -> Command<Message> {
+        if self.lock.is_some() {
+            match message {
+                Message::View(view::Message::Lock(msg)) => return self.update_lock(msg),
+                // Only the PIN prompt can be interacted with while it is displayed, but the
+                // wallet keeps being updated in the background.
+                Message::View(_) | Message::KeyPressed(..) => return Command::none(),
+                _ => {}
+            }
+        }
+        if self.signer_unlock.is_some() {
+            match message {
+                Message::View(view::Message::SignerUnlock(msg)) => {
+                    return self.update_signer_unlock(msg)
+                }
+                Message::View(_) | Message::KeyPressed(..) => return Command::none(),
+                _ => {}
+            }
+        }
         match message {
+            Message::AutoLockTick => {
+                if let Some(settings) = &self.wallet.auto_lock {
+                    let timeout = Duration::from_secs(settings.timeout_minutes as u64 * 60);
+                    // An action waiting for its confirmation is dropped.
+                    if self
+                        .lock
+                        .as_ref()
+                        .map_or(true, |lock| lock.pending.is_some())
+                        && self.last_activity.elapsed() >= timeout
+                    {
+                        self.palette = None;
+                        self.lock_signer();
+                        self.lock = Some(Lock::new(None));
+                        return text_input::focus(lock::pin_input_id());
+                    }
+                }
+                Command::none()
+            }
+            Message::View(
+                msg @ view::Message::Spend(
+                    view::SpendTxMessage::Sign | view::SpendTxMessage::Broadcast,
+                ),
+            ) if self.wallet.auto_lock.is_some() => {
+                self.lock = Some(Lock::new(Some(msg)));
+                text_input::focus(lock::pin_input_id())
+            }
+            Message::View(
+                msg @ view::Message::Spend(view::SpendTxMessage::SelectHotSigner(fingerprint)),
+            ) if self
+                .wallet
+                .signer(&fingerprint)
+                .map_or(false, |signer| signer.is_locked()) =>
+            {
+                self.signer_unlock = Some(SignerUnlock::new(fingerprint, Some(msg)));
+                text_input::focus(lock::passphrase_input_id())
+            }
             Message::Tick => {
                 let daemon = self.daemon.clone();
                 let datadir_path = self.cache.datadir_path.clone();
                 let network = self.cache.network;
                 let last_poll_at_startup = self.cache.last_poll_at_startup;
-                Command::perform(
+                let bitcoin_unit = self.cache.bitcoin_unit;
+                let exchange_rate = self.cache.exchange_rate;
+                // Fee estimates don't change much, only refresh them once in a while.
+                let cached_fee_estimates = self
+                    .cache
+                    .fee_estimates_fetched_at
+                    .filter(|at| at.elapsed() < FEE_ESTIMATES_TTL)
+                    .map(|at| (self.cache.fee_estimates.clone(), at));
+                let previous_fee_estimates = (
+                    self.cache.fee_estimates.clone(),
+                    self.cache.fee_estimates_fetched_at,
+                );
+                let broadcasts = self.scheduled_broadcasts();
+                let update_cache = Command::perform(
                     async move {
                         // we check every 10 second if the daemon poller is alive
                         // or if the access token is not expired.
                         daemon.is_alive(&datadir_path, network).await?;
 
                         let info = daemon.get_info().await?;
+                        let backend_status = BackendStatus::from_warnings(&info.warnings);
                         let coins = daemon
                             .list_coins(&[CoinStatus::Unconfirmed, CoinStatus::Confirmed], &[])
                             .await?;
+                        // Fee estimates are only used as hints, don't fail the update if the
+                        // daemon can't provide them. Nor wait for an unreachable backend.
+                        let (fee_estimates, fee_estimates_fetched_at) =
+                            if let Some((estimates, at)) = cached_fee_estimates {
+                                (estimates, Some(at))
+                            } else if !backend_status.is_reachable() {
+                                previous_fee_estimates
+                            } else {
+                                match daemon.get_fee_estimates().await {
+                                    Ok(res) => (res.feerates, Some(Instant::now())),
+                                    Err(_) => (Default::default(), None),
+                                }
+                            };
                         Ok(Cache {
                             datadir_path,
                             coins: coins.coins,
@@ -305,19 +631,86 @@ impl App {
                             sync_progress: info.sync,
                             last_poll_timestamp: info.last_poll_timestamp,
                             last_poll_at_startup, // doesn't change
+                            fee_estimates,
+                            fee_estimates_fetched_at,
+                            bitcoin_unit,
+                            exchange_rate,
+                            backend_status,
                         })
                     },
                     Message::UpdateCache,
-                )
+                );
+                Command::batch(std::iter::once(update_cache).chain(broadcasts))
+            }
+            Message::ScheduledBroadcast(txid, res) => {
+                self.broadcasting.remove(&txid);
+                if let Err(e) = &res {
+                    error!("Scheduled broadcast of {} failed: {}", txid, e);
+                } else {
+                    info!("Scheduled broadcast of {} succeeded", txid);
+                }
+                self.broadcast_notifications
+                    .push((txid, res.err().map(|e| e.to_string())));
+                // Whatever the outcome, the schedule is done. A failed broadcast is up to
+                // the user to retry.
+                let scheduled = self
+                    .wallet
+                    .scheduled_broadcasts
+                    .iter()
+                    .filter(|scheduled| scheduled.txid != txid)
+                    .cloned()
+                    .collect();
+                match self.wallet.update_scheduled_broadcasts(
+                    &self.cache.datadir_path,
+                    self.cache.network,
+                    scheduled,
+                ) {
+                    Ok(wallet) => self.update(Message::WalletUpdated(Ok(Arc::new(wallet)))),
+                    Err(e) => {
+                        error!("Failed to remove the broadcast schedule of {}: {}", txid, e);
+                        Command::none()
+                    }
+                }
+            }
+            Message::Notified(res) => {
+                if let Err(e) = res {
+                    warn!("Failed to notify coins events: {}", e);
+                }
+                Command::none()
+            }
+            Message::View(view::Message::ToggleAmountsHidden) => {
+                self.run_action(Action::ToggleAmountsHidden)
+            }
+            Message::View(view::Message::DismissNotification(i)) => {
+                if i < self.broadcast_notifications.len() {
+                    self.broadcast_notifications.remove(i);
+                }
+                Command::none()
             }
             Message::UpdateCache(res) => {
                 match res {
-                    Ok(cache) => {
+                    Ok(mut cache) => {
+                        // The unit may have been changed since the update was requested.
+                        cache.bitcoin_unit = self.wallet.bitcoin_unit;
+                        if cache.backend_status != self.cache.backend_status {
+                            match cache.backend_status {
+                                BackendStatus::Unreachable { .. } => {
+                                    warn!("The Bitcoin backend is unreachable")
+                                }
+                                BackendStatus::Reachable => {
+                                    info!("The Bitcoin backend is reachable again")
+                                }
+                            }
+                        }
+                        let notify = self.notify_coins_events(&cache);
                         self.cache.clone_from(&cache);
                         let current = &self.panels.current;
                         let daemon = self.daemon.clone();
                         // These are the panels to update with the cache.
-                        let mut panels = [(&mut self.panels.home, Menu::Home)];
+                        let mut panels: [(&mut dyn State, Menu); 2] = [
+                            (&mut self.panels.home, Menu::Home),
+                            (&mut self.panels.receive, Menu::Receive),
+                        ];
                         let commands: Vec<_> = panels
                             .iter_mut()
                             .map(|(panel, menu)| {
@@ -328,9 +721,28 @@ impl App {
                                 )
                             })
                             .collect();
-                        return Command::batch(commands);
+                        return Command::batch(commands.into_iter().chain(notify));
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to update cache: {}", e);
+                        // The internal daemon only fails once stopped, but it may have been
+                        // replaced while the update was ongoing. Check the current one.
+                        if self.daemon.backend().is_embedded() && self.daemon_crash.is_none() {
+                            let daemon = self.daemon.clone();
+                            return Command::perform(
+                                async move { daemon.get_info().await.map(|_| ()).map_err(|_| e) },
+                                Message::DaemonChecked,
+                            );
+                        }
                     }
-                    Err(e) => tracing::error!("Failed to update cache: {}", e),
+                }
+                Command::none()
+            }
+            Message::DaemonChecked(res) => {
+                if let Err(e) = res {
+                    error!("Internal daemon stopped: {}", e);
+                    self.palette = None;
+                    self.daemon_crash = Some(e);
                 }
                 Command::none()
             }
@@ -342,6 +754,7 @@ impl App {
                 self.update(Message::DaemonConfigLoaded(res))
             }
             Message::WalletUpdated(Ok(wallet)) => {
+                self.cache.bitcoin_unit = wallet.bitcoin_unit;
                 self.wallet = wallet.clone();
                 self.panels.current_mut().update(
                     self.daemon.clone(),
@@ -349,6 +762,65 @@ impl App {
                     Message::WalletUpdated(Ok(wallet)),
                 )
             }
+            Message::KeyPressed(key, modifiers, captured) => {
+                if let Some(palette) = &mut self.palette {
+                    match key {
+                        keyboard::Key::Named(keyboard::key::Named::Escape) => {
+                            self.palette = None;
+                            return Command::none();
+                        }
+                        keyboard::Key::Named(keyboard::key::Named::ArrowDown) => {
+                            palette.select_next();
+                            return Command::none();
+                        }
+                        keyboard::Key::Named(keyboard::key::Named::ArrowUp) => {
+                            palette.select_previous();
+                            return Command::none();
+                        }
+                        _ => {}
+                    }
+                }
+                match self.shortcuts.action(&key, modifiers) {
+                    // Shortcuts are ignored while a text input is focused, except the one of
+                    // the command palette.
+                    Some(Action::CommandPalette) => self.toggle_palette(),
+                    Some(action) if !captured => self.run_action(action),
+                    _ => Command::none(),
+                }
+            }
+            Message::View(view::Message::CommandPalette(msg)) => match msg {
+                view::CommandPaletteMessage::Edited(query) => {
+                    if let Some(palette) = &mut self.palette {
+                        palette.edit_query(query);
+                    }
+                    Command::none()
+                }
+                view::CommandPaletteMessage::Run(action) => self.run_action(action),
+                view::CommandPaletteMessage::Submit => {
+                    match self.palette.as_ref().and_then(|p| p.selected_action()) {
+                        Some(action) => self.run_action(action),
+                        None => Command::none(),
+                    }
+                }
+                view::CommandPaletteMessage::Close => {
+                    self.palette = None;
+                    Command::none()
+                }
+            },
+            Message::ManagedBitcoind(progress) => {
+                // The node was restarted, keep the new handle to stop it on exit.
+                if let ManagedBitcoindProgress::Restarted(Ok(bitcoind)) = &progress {
+                    self.internal_bitcoind = Some(bitcoind.clone());
+                }
+                self.panels.settings.update(
+                    self.daemon.clone(),
+                    &self.cache,
+                    Message::ManagedBitcoind(progress),
+                )
+            }
+            Message::View(view::Message::RestartNode) => {
+                self.panels.settings.restart_managed_bitcoind()
+            }
             Message::View(view::Message::Menu(menu)) => self.set_current_panel(menu),
             Message::View(view::Message::Clipboard(text)) => clipboard::write(text),
             _ => self
@@ -358,13 +830,144 @@ impl App {
         }
     }
 
+    /// Broadcast the scheduled spends which are due. Schedules missed while the application
+    /// was closed are caught up here too, once the wallet is synced.
+    fn scheduled_broadcasts(&mut self) -> Vec<Command<Message>> {
+        let synced = sync_status(
+            self.daemon.backend(),
+            self.cache.blockheight,
+            self.cache.sync_progress,
+            self.cache.last_poll_timestamp,
+            self.cache.last_poll_at_startup,
+        )
+        .is_synced();
+        if !synced {
+            return Vec::new();
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32;
+        let due: Vec<_> = self
+            .wallet
+            .scheduled_broadcasts
+            .iter()
+            .filter(|scheduled| {
+                scheduled.schedule.is_due(now, self.cache.blockheight)
+                    && !self.broadcasting.contains(&scheduled.txid)
+            })
+            .map(|scheduled| scheduled.txid)
+            .collect();
+        due.into_iter()
+            .map(|txid| {
+                self.broadcasting.insert(txid);
+                let daemon = self.daemon.clone();
+                Command::perform(
+                    async move { daemon.broadcast_spend_tx(&txid).await.map_err(|e| e.into()) },
+                    move |res| Message::ScheduledBroadcast(txid, res),
+                )
+            })
+            .collect()
+    }
+
+    /// Notify the user of the changes of the coins since the previous cache update, if they
+    /// enabled it. Nothing is notified until the wallet is synced, to not notify every coin
+    /// found by the initial sync after startup.
+    fn notify_coins_events(&self, cache: &Cache) -> Option<Command<Message>> {
+        if !self.wallet.notifications.any_enabled() {
+            return None;
+        }
+        let is_synced = |cache: &Cache| {
+            sync_status(
+                self.daemon.backend(),
+                cache.blockheight,
+                cache.sync_progress,
+                cache.last_poll_timestamp,
+                cache.last_poll_at_startup,
+            )
+            .is_synced()
+        };
+        if !is_synced(&self.cache) || !is_synced(cache) {
+            return None;
+        }
+        let events = notification::coins_events(
+            &self.wallet.notifications,
+            &self.cache.coins,
+            self.cache.blockheight,
+            &cache.coins,
+            cache.blockheight,
+            &self.wallet.expiry_warning_days,
+        );
+        if events.is_empty() {
+            return None;
+        }
+        let daemon = self.daemon.clone();
+        let bitcoin_unit = cache.bitcoin_unit;
+        Some(Command::perform(
+            async move {
+                let labels = daemon
+                    .get_labels(&notification::label_items(&events))
+                    .await
+                    .map_err(Error::from);
+                // Still notify without the labels if they could not be fetched.
+                let shown_labels = labels.as_ref().cloned().unwrap_or_default();
+                tokio::task::spawn_blocking(move || {
+                    notification::notify(&events, &shown_labels, bitcoin_unit)
+                })
+                .await
+                .map_err(|e| Error::Unexpected(e.to_string()))?;
+                labels.map(|_| ())
+            },
+            Message::Notified,
+        ))
+    }
+
+    /// Stop the internal daemon and load the wallet again with a new one, reading its
+    /// configuration anew. The current panel is restored once loaded.
+    pub fn restart_daemon(&self) -> (Loader, Command<loader::Message>) {
+        Loader::restart(
+            self.cache.datadir_path.clone(),
+            self.config.clone(),
+            self.cache.network,
+            self.internal_bitcoind.clone(),
+            self.daemon.clone(),
+            self.panels.current.clone(),
+        )
+    }
+
     pub fn load_daemon_config(
         &mut self,
         daemon_config_path: &PathBuf,
         cfg: DaemonConfig,
     ) -> Result<(), Error> {
+        let previous_cfg = self.daemon.config().cloned();
         Handle::current().block_on(async { self.daemon.stop().await })?;
-        let daemon = EmbeddedDaemon::start(cfg)?;
+        let daemon = match EmbeddedDaemon::start(cfg) {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                // The configuration file was not written yet, restart the daemon with the
+                // settings it still contains so that the wallet remains usable.
+                warn!(
+                    "Failed to start the daemon with the new configuration: {}",
+                    e
+                );
+                match previous_cfg.map(EmbeddedDaemon::start) {
+                    Some(Ok(daemon)) => {
+                        self.daemon = Arc::new(daemon);
+                        return Err(Error::Config(format!(
+                            "{}. The previous settings were restored.",
+                            e
+                        )));
+                    }
+                    Some(Err(e)) => error!(
+                        "Failed to restart the daemon with the previous configuration: {}",
+                        e
+                    ),
+                    None => {}
+                }
+                return Err(e.into());
+            }
+        };
         self.daemon = Arc::new(daemon);
 
         let content =
@@ -383,9 +986,79 @@ impl App {
     }
 
     pub fn view(&self) -> Element<Message> {
-        let content = self.panels.current().view(&self.cache).map(Message::View);
-        if self.cache.network != bitcoin::Network::Bitcoin {
-            Column::with_children(vec![network_banner(self.cache.network).into(), content]).into()
+        let now = Instant::now();
+        if let Some(lock) = self.lock.as_ref().filter(|lock| lock.pending.is_none()) {
+            return view::lock::lock_screen(
+                &lock.pin,
+                self.pin_attempts.has_failed(),
+                self.pin_attempts.retry_in(now),
+            )
+            .map(Message::View);
+        }
+        let content = self.panels.current().view(&self.cache);
+        let content = if let Some(error) = &self.daemon_crash {
+            Modal::new(content, view::daemon_crash(error)).into()
+        } else if let Some(lock) = &self.lock {
+            Modal::new(
+                content,
+                view::lock::pin_prompt(
+                    &lock.pin,
+                    self.pin_attempts.has_failed(),
+                    self.pin_attempts.retry_in(now),
+                    true,
+                ),
+            )
+            .into()
+        } else if let Some(unlock) = &self.signer_unlock {
+            Modal::new(
+                content,
+                view::lock::passphrase_prompt(
+                    unlock.fingerprint,
+                    &unlock.passphrase,
+                    self.passphrase_attempts.has_failed(),
+                    self.passphrase_attempts.retry_in(now),
+                ),
+            )
+            .into()
+        } else if let Some(palette) = &self.palette {
+            Modal::new(content, view::command_palette(palette, &self.shortcuts))
+                .on_blur(Some(view::Message::CommandPalette(
+                    view::CommandPaletteMessage::Close,
+                )))
+                .into()
+        } else {
+            content
+        }
+        .map(Message::View);
+        let content = if self.broadcast_notifications.is_empty() {
+            content
+        } else {
+            toast::Manager::new(
+                content,
+                view::scheduled_broadcast_toasts(&self.broadcast_notifications)
+                    .into_iter()
+                    .map(|toast| toast.map(Message::View))
+                    .collect(),
+            )
+            .into()
+        };
+        let backend_banner = match self.cache.backend_status {
+            BackendStatus::Unreachable { since } => Some(
+                view::backend_unreachable_banner(since, self.internal_bitcoind.is_some())
+                    .map(Message::View),
+            ),
+            BackendStatus::Reachable => None,
+        };
+        if self.cache.network != bitcoin::Network::Bitcoin || backend_banner.is_some() {
+            Column::new()
+                .push_maybe(if self.cache.network != bitcoin::Network::Bitcoin {
+                    Some(network_banner(self.cache.network))
+                } else {
+                    None
+                })
+                .push_maybe(backend_banner)
+                .push(content)
+                .into()
         } else {
             content
         }
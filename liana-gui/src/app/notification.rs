@@ -0,0 +1,309 @@
+//! Desktop notifications about the activity of the wallet coins.
+use std::collections::{HashMap, HashSet};
+
+use liana::miniscript::bitcoin::{Amount, OutPoint};
+use liana_ui::component::amount::{amount_as_string, BitcoinDisplayUnit};
+use lianad::commands::LabelItem;
+use tracing::warn;
+
+use crate::{
+    app::settings::{NotificationKind, NotificationSettings},
+    daemon::model::{Coin, BLOCKS_PER_DAY},
+};
+
+/// Maximum number of coins detailed in the body of a single notification.
+const MAX_DETAILED_COINS: usize = 5;
+
+/// Coins which changed between two polls of the daemon, grouped by kind of notification so a
+/// burst of coins only triggers a single notification.
+#[derive(Debug, Clone)]
+pub struct CoinsEvent {
+    pub kind: NotificationKind,
+    pub coins: Vec<Coin>,
+    /// For `RecoveryApproaching`, the warning window in days the coins entered.
+    pub days: Option<u32>,
+}
+
+impl CoinsEvent {
+    pub fn label_items(&self) -> impl Iterator<Item = LabelItem> + '_ {
+        self.coins.iter().flat_map(|coin| {
+            [
+                LabelItem::OutPoint(coin.outpoint),
+                LabelItem::Txid(coin.outpoint.txid),
+            ]
+        })
+    }
+
+    fn summary(&self) -> String {
+        let n = self.coins.len();
+        match self.kind {
+            NotificationKind::Incoming if n == 1 => "Incoming transaction detected".to_string(),
+            NotificationKind::Incoming => format!("{} incoming payments detected", n),
+            NotificationKind::Confirmed if n == 1 => "Transaction confirmed".to_string(),
+            NotificationKind::Confirmed => format!("{} payments confirmed", n),
+            NotificationKind::RecoveryApproaching => format!(
+                "Recovery path available within {} day(s) for {} coin(s)",
+                self.days.unwrap_or_default(),
+                n
+            ),
+        }
+    }
+
+    fn body(&self, labels: &HashMap<String, String>, bitcoin_unit: BitcoinDisplayUnit) -> String {
+        let fmt_amount = |amount: Amount| {
+            format!(
+                "{} {}",
+                amount_as_string(amount, bitcoin_unit),
+                bitcoin_unit
+            )
+        };
+        let mut lines: Vec<String> = self
+            .coins
+            .iter()
+            .take(MAX_DETAILED_COINS)
+            .map(|coin| {
+                let label = labels
+                    .get(&coin.outpoint.to_string())
+                    .or_else(|| labels.get(&coin.outpoint.txid.to_string()));
+                match label {
+                    Some(label) => format!("{} - {}", fmt_amount(coin.amount), label),
+                    None => fmt_amount(coin.amount),
+                }
+            })
+            .collect();
+        if self.coins.len() > MAX_DETAILED_COINS {
+            lines.push(format!(
+                "and {} more, {} in total",
+                self.coins.len() - MAX_DETAILED_COINS,
+                fmt_amount(self.coins.iter().map(|coin| coin.amount).sum::<Amount>())
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Compare the coins between two polls of the daemon and return the events the user enabled
+/// notifications for. Coins received from the wallet itself (change, refreshes) are ignored.
+pub fn coins_events(
+    settings: &NotificationSettings,
+    previous_coins: &[Coin],
+    previous_tip: i32,
+    coins: &[Coin],
+    tip: i32,
+    warning_days: &[u32],
+) -> Vec<CoinsEvent> {
+    let previous: HashMap<OutPoint, &Coin> = previous_coins
+        .iter()
+        .map(|coin| (coin.outpoint, coin))
+        .collect();
+    let mut incoming = Vec::new();
+    let mut confirmed = Vec::new();
+    let mut approaching: HashMap<u32, Vec<Coin>> = HashMap::new();
+    for coin in coins.iter().filter(|coin| coin.spend_info.is_none()) {
+        let previous_coin = previous.get(&coin.outpoint);
+        if !coin.is_from_self {
+            if coin.block_height.is_none() && previous_coin.is_none() {
+                incoming.push(coin.clone());
+            } else if coin.block_height.is_some()
+                && previous_coin.map_or(true, |c| c.block_height.is_none())
+            {
+                confirmed.push(coin.clone());
+            }
+        }
+        // Only the smallest warning window the coin entered since the last poll is notified.
+        if previous_coin.is_some() {
+            let entered = warning_days
+                .iter()
+                .filter(|days| {
+                    let window = i64::from(**days * BLOCKS_PER_DAY);
+                    coin.recovery_heights.iter().any(|recovery| {
+                        let height = i64::from(recovery.height);
+                        height - i64::from(previous_tip) > window
+                            && height - i64::from(tip) <= window
+                    })
+                })
+                .min();
+            if let Some(days) = entered {
+                approaching.entry(*days).or_default().push(coin.clone());
+            }
+        }
+    }
+
+    let mut events = Vec::new();
+    if settings.is_enabled(NotificationKind::Incoming) && !incoming.is_empty() {
+        events.push(CoinsEvent {
+            kind: NotificationKind::Incoming,
+            coins: incoming,
+            days: None,
+        });
+    }
+    if settings.is_enabled(NotificationKind::Confirmed) && !confirmed.is_empty() {
+        events.push(CoinsEvent {
+            kind: NotificationKind::Confirmed,
+            coins: confirmed,
+            days: None,
+        });
+    }
+    if settings.is_enabled(NotificationKind::RecoveryApproaching) {
+        let mut approaching: Vec<_> = approaching.into_iter().collect();
+        approaching.sort_by_key(|(days, _)| *days);
+        events.extend(approaching.into_iter().map(|(days, coins)| CoinsEvent {
+            kind: NotificationKind::RecoveryApproaching,
+            coins,
+            days: Some(days),
+        }));
+    }
+    events
+}
+
+/// Show a native desktop notification for each event.
+pub fn notify(
+    events: &[CoinsEvent],
+    labels: &HashMap<String, String>,
+    bitcoin_unit: BitcoinDisplayUnit,
+) {
+    for event in events {
+        if let Err(e) = notify_rust::Notification::new()
+            .appname("Liana")
+            .summary(&event.summary())
+            .body(&event.body(labels, bitcoin_unit))
+            .show()
+        {
+            warn!("Failed to show desktop notification: {}", e);
+        }
+    }
+}
+
+/// Label items of all the coins of the events.
+pub fn label_items(events: &[CoinsEvent]) -> HashSet<LabelItem> {
+    events
+        .iter()
+        .flat_map(|event| event.label_items())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::model::RecoveryHeight;
+    use liana::miniscript::bitcoin::{bip32::ChildNumber, Address, Txid};
+    use std::str::FromStr;
+
+    fn coin(vout: u32, block_height: Option<i32>, recovery_height: Option<i32>) -> Coin {
+        Coin {
+            outpoint: OutPoint::new(
+                Txid::from_str("f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5")
+                    .unwrap(),
+                vout,
+            ),
+            amount: Amount::from_sat(1_000 * (vout as u64 + 1)),
+            address: Address::from_str("bc1qvrl2849aggm6qry9ea7xqp2kk39j8vaa8r3cwg")
+                .unwrap()
+                .assume_checked(),
+            derivation_index: ChildNumber::Normal { index: vout },
+            block_height,
+            is_immature: false,
+            is_change: false,
+            is_from_self: false,
+            spend_info: None,
+            recovery_heights: recovery_height
+                .map(|height| {
+                    vec![RecoveryHeight {
+                        timelock: 52_560,
+                        height,
+                    }]
+                })
+                .unwrap_or_default(),
+            tag: None,
+        }
+    }
+
+    fn outpoints(event: &CoinsEvent) -> Vec<u32> {
+        event.coins.iter().map(|coin| coin.outpoint.vout).collect()
+    }
+
+    #[test]
+    fn test_coins_events() {
+        let all = NotificationSettings {
+            incoming: true,
+            confirmed: true,
+            recovery_approaching: true,
+        };
+        let tip = 800_000;
+        let previous = vec![
+            coin(0, None, None),
+            coin(1, Some(700_000), Some(tip + 7 * 144 + 1)),
+            coin(2, Some(700_000), Some(tip + 31 * 144)),
+        ];
+        let mut from_self = coin(5, None, None);
+        from_self.is_from_self = true;
+        let current = vec![
+            // Got confirmed.
+            coin(0, Some(tip + 1), Some(tip + 1 + 52_560)),
+            // Entered the 7 days window.
+            coin(1, Some(700_000), Some(tip + 7 * 144 + 1)),
+            // Still out of any window.
+            coin(2, Some(700_000), Some(tip + 31 * 144)),
+            // Two new unconfirmed coins are notified at once.
+            coin(3, None, None),
+            coin(4, None, None),
+            // Received and confirmed between two polls.
+            coin(6, Some(tip + 1), Some(tip + 1 + 52_560)),
+            from_self,
+        ];
+
+        let events = coins_events(&all, &previous, tip, &current, tip + 1, &[30, 7, 1]);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind, NotificationKind::Incoming);
+        assert_eq!(outpoints(&events[0]), vec![3, 4]);
+        assert_eq!(events[0].summary(), "2 incoming payments detected");
+        assert_eq!(events[1].kind, NotificationKind::Confirmed);
+        assert_eq!(outpoints(&events[1]), vec![0, 6]);
+        assert_eq!(events[2].kind, NotificationKind::RecoveryApproaching);
+        assert_eq!(events[2].days, Some(7));
+        assert_eq!(outpoints(&events[2]), vec![1]);
+
+        // Nothing changed, nothing to notify.
+        assert!(coins_events(&all, &current, tip + 1, &current, tip + 1, &[30, 7, 1]).is_empty());
+
+        // Only the enabled kinds are notified.
+        let confirmed_only = NotificationSettings {
+            confirmed: true,
+            ..Default::default()
+        };
+        let events = coins_events(&confirmed_only, &previous, tip, &current, tip + 1, &[30, 7]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, NotificationKind::Confirmed);
+    }
+
+    #[test]
+    fn test_coins_event_body() {
+        let event = CoinsEvent {
+            kind: NotificationKind::Incoming,
+            coins: (0..7).map(|vout| coin(vout, None, None)).collect(),
+            days: None,
+        };
+        let labels: HashMap<String, String> = vec![
+            (
+                event.coins[0].outpoint.to_string(),
+                "Invoice #42".to_string(),
+            ),
+            (
+                event.coins[1].outpoint.txid.to_string(),
+                "Deposit".to_string(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let body = event.body(&labels, BitcoinDisplayUnit::Btc);
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), MAX_DETAILED_COINS + 1);
+        assert_eq!(lines[0], "0.00 001 000 BTC - Invoice #42");
+        // All the coins share the same txid.
+        assert_eq!(lines[2], "0.00 003 000 BTC - Deposit");
+        assert_eq!(lines[5], "and 2 more, 0.00 028 000 BTC in total");
+
+        let body = event.body(&labels, BitcoinDisplayUnit::Sats);
+        assert_eq!(body.lines().next(), Some("1 000 sats - Invoice #42"));
+    }
+}
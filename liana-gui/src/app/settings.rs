@@ -5,13 +5,33 @@ use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::PathBuf;
 
-use liana::miniscript::bitcoin::{bip32::Fingerprint, Network};
+use bitcoin_hashes::{sha256, Hash, HashEngine};
+use chrono::{DateTime, Local, Utc};
+use liana::{
+    miniscript::bitcoin::{bip32::Fingerprint, Network, Txid},
+    random,
+};
+use liana_ui::component::amount::BitcoinDisplayUnit;
 use serde::{Deserialize, Serialize};
 
 use crate::hw::HardwareWalletConfig;
 
 pub const DEFAULT_FILE_NAME: &str = "settings.json";
 
+/// Default warning windows, in days, before a recovery path becomes available for coins.
+pub const DEFAULT_EXPIRY_WARNING_DAYS: [u32; 3] = [30, 7, 1];
+
+fn default_expiry_warning_days() -> Vec<u32> {
+    DEFAULT_EXPIRY_WARNING_DAYS.to_vec()
+}
+
+/// Default share of their value, in percent, above which the fee to refresh coins is warned about.
+pub const DEFAULT_REFRESH_MAX_FEE_PERCENT: u32 = 5;
+
+fn default_refresh_max_fee_percent() -> u32 {
+    DEFAULT_REFRESH_MAX_FEE_PERCENT
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Settings {
     pub wallets: Vec<WalletSetting>,
@@ -79,6 +99,27 @@ pub struct WalletSetting {
     #[serde(default)]
     pub hardware_wallets: Vec<HardwareWalletConfig>,
     pub remote_backend_auth: Option<AuthConfig>,
+    /// Spends to broadcast later, only while the GUI is running.
+    #[serde(default)]
+    pub scheduled_broadcasts: Vec<ScheduledBroadcast>,
+    /// Warning windows, in days, before a recovery path becomes available for coins.
+    #[serde(default = "default_expiry_warning_days")]
+    pub expiry_warning_days: Vec<u32>,
+    /// Share of their value, in percent, above which the fee to refresh coins is warned about.
+    #[serde(default = "default_refresh_max_fee_percent")]
+    pub refresh_max_fee_percent: u32,
+    /// Desktop notifications, all disabled by default.
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// Unit amounts are displayed and entered in.
+    #[serde(default)]
+    pub bitcoin_unit: BitcoinDisplayUnit,
+    /// Lock screen displayed after some inactivity, disabled by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_lock: Option<AutoLockSettings>,
+    /// When to ask for the passphrase of a hot signer protected by one.
+    #[serde(default)]
+    pub hot_signer_unlock: HotSignerUnlock,
 }
 
 impl WalletSetting {
@@ -91,7 +132,158 @@ impl WalletSetting {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// A payment to the wallet was detected in the mempool.
+    Incoming,
+    /// A payment to the wallet was confirmed.
+    Confirmed,
+    /// A recovery path is about to become available for some coins.
+    RecoveryApproaching,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub incoming: bool,
+    pub confirmed: bool,
+    pub recovery_approaching: bool,
+}
+
+impl NotificationSettings {
+    pub fn is_enabled(&self, kind: NotificationKind) -> bool {
+        match kind {
+            NotificationKind::Incoming => self.incoming,
+            NotificationKind::Confirmed => self.confirmed,
+            NotificationKind::RecoveryApproaching => self.recovery_approaching,
+        }
+    }
+
+    pub fn set(&mut self, kind: NotificationKind, enabled: bool) {
+        match kind {
+            NotificationKind::Incoming => self.incoming = enabled,
+            NotificationKind::Confirmed => self.confirmed = enabled,
+            NotificationKind::RecoveryApproaching => self.recovery_approaching = enabled,
+        }
+    }
+
+    pub fn any_enabled(&self) -> bool {
+        self.incoming || self.confirmed || self.recovery_approaching
+    }
+}
+
+/// When to ask for the passphrase of a hot signer protected by one. The key derived using it is
+/// only kept in memory, until the signer is locked again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotSignerUnlock {
+    /// As soon as the wallet is opened.
+    #[default]
+    OnOpen,
+    /// Only before signing with it for the first time.
+    OnSigning,
+}
+
+impl HotSignerUnlock {
+    pub const ALL: [HotSignerUnlock; 2] = [HotSignerUnlock::OnOpen, HotSignerUnlock::OnSigning];
+}
+
+/// Number of hashing rounds of the PIN, to slow down guessing it from the settings file.
+const PIN_HASH_ROUNDS: u32 = 100_000;
+
+/// The lock screen hiding the wallet after some inactivity, unlocked with a PIN.
+/// It is a privacy screen, not an encryption boundary: the wallet data is not encrypted and a
+/// forgotten PIN is bypassed by restarting the application, which starts unlocked.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AutoLockSettings {
+    /// Minutes without any input before locking.
+    pub timeout_minutes: u32,
+    /// Hex-encoded random salt of the PIN hash.
+    pub salt: String,
+    /// Hex-encoded salted hash of the PIN.
+    pub pin_hash: String,
+}
+
+impl AutoLockSettings {
+    pub fn new(timeout_minutes: u32, pin: &str) -> Result<Self, SettingsError> {
+        let salt = random::random_bytes()
+            .map_err(|e| SettingsError::Unexpected(format!("Failed to generate salt: {}", e)))?;
+        Ok(Self {
+            timeout_minutes,
+            salt: hex::encode(salt),
+            pin_hash: hash_pin(&salt, pin).to_string(),
+        })
+    }
+
+    pub fn verify(&self, pin: &str) -> bool {
+        hex::decode(&self.salt)
+            .map(|salt| hash_pin(&salt, pin).to_string() == self.pin_hash)
+            .unwrap_or(false)
+    }
+}
+
+/// A PIN is made of 4 to 12 digits.
+pub fn is_valid_pin(pin: &str) -> bool {
+    (4..=12).contains(&pin.len()) && pin.chars().all(|c| c.is_ascii_digit())
+}
+
+fn hash_pin(salt: &[u8], pin: &str) -> sha256::Hash {
+    let mut engine = sha256::HashEngine::default();
+    engine.input(salt);
+    engine.input(pin.as_bytes());
+    let mut hash = sha256::Hash::from_engine(engine);
+    for _ in 1..PIN_HASH_ROUNDS {
+        let mut engine = sha256::HashEngine::default();
+        engine.input(salt);
+        engine.input(hash.as_byte_array());
+        hash = sha256::Hash::from_engine(engine);
+    }
+    hash
+}
+
+/// A saved spend transaction to broadcast once its schedule is due.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ScheduledBroadcast {
+    pub txid: Txid,
+    pub schedule: BroadcastSchedule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastSchedule {
+    /// Unix timestamp in seconds.
+    Time(u32),
+    BlockHeight(u32),
+}
+
+impl BroadcastSchedule {
+    /// Whether the broadcast should happen given the current time and tip height. A schedule
+    /// missed while the application was closed is due as soon as it is running again.
+    pub fn is_due(&self, now: u32, tip_height: i32) -> bool {
+        match self {
+            Self::Time(t) => *t <= now,
+            Self::BlockHeight(h) => tip_height >= 0 && *h <= tip_height as u32,
+        }
+    }
+}
+
+impl std::fmt::Display for BroadcastSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Time(t) => match DateTime::<Utc>::from_timestamp(*t as i64, 0) {
+                Some(date) => write!(
+                    f,
+                    "{}",
+                    date.with_timezone(&Local).format("%b. %d, %Y - %H:%M")
+                ),
+                None => write!(f, "timestamp {}", t),
+            },
+            Self::BlockHeight(h) => write!(f, "block {}", h),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct KeySetting {
     pub name: String,
     pub master_fingerprint: Fingerprint,
@@ -211,3 +403,91 @@ pub mod global {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn broadcast_schedule_is_due() {
+        let at_time = BroadcastSchedule::Time(1_700_000_000);
+        assert!(!at_time.is_due(1_699_999_999, 900_000));
+        assert!(at_time.is_due(1_700_000_000, 0));
+        // A missed schedule is still due.
+        assert!(at_time.is_due(1_800_000_000, 0));
+
+        let at_height = BroadcastSchedule::BlockHeight(850_000);
+        assert!(!at_height.is_due(u32::MAX, 849_999));
+        assert!(at_height.is_due(0, 850_000));
+        assert!(at_height.is_due(0, 850_001));
+        assert!(!at_height.is_due(0, -1));
+    }
+
+    #[test]
+    fn auto_lock_pin() {
+        let auto_lock = AutoLockSettings::new(5, "1234").unwrap();
+        assert!(auto_lock.verify("1234"));
+        assert!(!auto_lock.verify("1235"));
+        assert!(!auto_lock.verify(""));
+        // The same PIN is hashed differently with another salt.
+        let other = AutoLockSettings::new(5, "1234").unwrap();
+        assert_ne!(auto_lock.salt, other.salt);
+        assert_ne!(auto_lock.pin_hash, other.pin_hash);
+        assert!(other.verify("1234"));
+        // An invalid salt never verifies.
+        let invalid = AutoLockSettings {
+            salt: "zz".to_string(),
+            ..auto_lock
+        };
+        assert!(!invalid.verify("1234"));
+
+        assert!(is_valid_pin("1234"));
+        assert!(is_valid_pin("123456789012"));
+        assert!(!is_valid_pin("123"));
+        assert!(!is_valid_pin("1234567890123"));
+        assert!(!is_valid_pin("12a4"));
+    }
+
+    #[test]
+    fn wallet_setting_without_scheduled_broadcasts() {
+        let setting: WalletSetting = serde_json::from_str(
+            r#"{"name":"Liana","descriptor_checksum":"abcdefgh","remote_backend_auth":null}"#,
+        )
+        .unwrap();
+        assert!(setting.scheduled_broadcasts.is_empty());
+        assert_eq!(setting.expiry_warning_days, vec![30, 7, 1]);
+        assert!(!setting.notifications.any_enabled());
+        assert_eq!(setting.bitcoin_unit, BitcoinDisplayUnit::Btc);
+        assert!(setting.auto_lock.is_none());
+
+        let setting: WalletSetting = serde_json::from_str(
+            r#"{"name":"Liana","descriptor_checksum":"abcdefgh","remote_backend_auth":null,"bitcoin_unit":"sats"}"#,
+        )
+        .unwrap();
+        assert_eq!(setting.bitcoin_unit, BitcoinDisplayUnit::Sats);
+
+        let notifications: NotificationSettings =
+            serde_json::from_str(r#"{"confirmed":true}"#).unwrap();
+        assert!(notifications.is_enabled(NotificationKind::Confirmed));
+        assert!(!notifications.is_enabled(NotificationKind::Incoming));
+        assert!(!notifications.is_enabled(NotificationKind::RecoveryApproaching));
+
+        let scheduled = ScheduledBroadcast {
+            txid: Txid::from_str(
+                "f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5",
+            )
+            .unwrap(),
+            schedule: BroadcastSchedule::BlockHeight(850_000),
+        };
+        let json = serde_json::to_string(&scheduled).unwrap();
+        assert_eq!(
+            json,
+            r#"{"txid":"f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5","schedule":{"block_height":850000}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ScheduledBroadcast>(&json).unwrap(),
+            scheduled
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Keyboard shortcuts of the application and the command palette listing the actions they
+//! trigger. All shortcuts are pressed along with the command modifier: Ctrl, or Cmd on macOS.
+use std::collections::BTreeMap;
+
+use iced::{keyboard, widget::text_input};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{app::menu::Menu, i18n::tr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Home,
+    Send,
+    Receive,
+    Coins,
+    Transactions,
+    Psbts,
+    Settings,
+    NewSpend,
+    Refresh,
+    ToggleAmountsHidden,
+    /// Forget the key of the hot signer protected by a passphrase.
+    LockSigner,
+    CommandPalette,
+}
+
+impl Action {
+    /// The actions listed in the command palette, in the order they are displayed.
+    pub const PALETTE: [Action; 11] = [
+        Action::Home,
+        Action::Send,
+        Action::Receive,
+        Action::Coins,
+        Action::Transactions,
+        Action::Psbts,
+        Action::Settings,
+        Action::NewSpend,
+        Action::Refresh,
+        Action::ToggleAmountsHidden,
+        Action::LockSigner,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Home => tr("menu.home"),
+            Self::Send => tr("menu.send"),
+            Self::Receive => tr("menu.receive"),
+            Self::Coins => tr("menu.coins"),
+            Self::Transactions => tr("menu.transactions"),
+            Self::Psbts => tr("menu.psbts"),
+            Self::Settings => tr("menu.settings"),
+            Self::NewSpend => tr("palette.new_spend"),
+            Self::Refresh => tr("palette.refresh"),
+            Self::ToggleAmountsHidden => tr("palette.toggle_amounts"),
+            Self::LockSigner => tr("palette.lock_signer"),
+            Self::CommandPalette => tr("palette.open"),
+        }
+    }
+
+    /// The panel the action navigates to, if any.
+    pub fn menu(&self) -> Option<Menu> {
+        match self {
+            Self::Home => Some(Menu::Home),
+            Self::Send => Some(Menu::CreateSpendTx),
+            Self::Receive => Some(Menu::Receive),
+            Self::Coins => Some(Menu::Coins),
+            Self::Transactions => Some(Menu::Transactions),
+            Self::Psbts => Some(Menu::PSBTs),
+            Self::Settings => Some(Menu::Settings),
+            Self::NewSpend
+            | Self::Refresh
+            | Self::ToggleAmountsHidden
+            | Self::LockSigner
+            | Self::CommandPalette => None,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+const MODIFIER_NAME: &str = "Cmd";
+#[cfg(not(target_os = "macos"))]
+const MODIFIER_NAME: &str = "Ctrl";
+
+const DEFAULT_BINDINGS: [(char, Action); 12] = [
+    ('1', Action::Home),
+    ('2', Action::Send),
+    ('3', Action::Receive),
+    ('4', Action::Coins),
+    ('5', Action::Transactions),
+    ('6', Action::Psbts),
+    ('7', Action::Settings),
+    ('n', Action::NewSpend),
+    ('r', Action::Refresh),
+    ('h', Action::ToggleAmountsHidden),
+    ('l', Action::LockSigner),
+    ('k', Action::CommandPalette),
+];
+
+/// The key each action is bound to. An action has at most one binding.
+#[derive(Debug, Clone)]
+pub struct Shortcuts(Vec<(char, Action)>);
+
+impl Shortcuts {
+    /// The default bindings, overridden by the ones set in the GUI configuration file, which
+    /// maps a key such as `"ctrl+h"` (or `"cmd+h"`) to an action. Invalid keys are ignored.
+    pub fn new(overrides: &BTreeMap<String, Action>) -> Self {
+        let mut bindings = DEFAULT_BINDINGS.to_vec();
+        for (key, action) in overrides {
+            if let Some(c) = parse_key(key) {
+                bindings.retain(|(k, a)| *k != c && a != action);
+                bindings.push((c, *action));
+            } else {
+                warn!("Ignoring invalid keyboard shortcut '{}'", key);
+            }
+        }
+        Self(bindings)
+    }
+
+    pub fn action(&self, key: &keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Action> {
+        if !modifiers.command() || modifiers.alt() {
+            return None;
+        }
+        let c = match key {
+            keyboard::Key::Character(s) => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => c.to_ascii_lowercase(),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        self.0.iter().find(|(k, _)| *k == c).map(|(_, a)| *a)
+    }
+
+    /// The key the action is bound to, for display.
+    pub fn key(&self, action: Action) -> Option<String> {
+        self.0
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(k, _)| format!("{}+{}", MODIFIER_NAME, k.to_ascii_uppercase()))
+    }
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self(DEFAULT_BINDINGS.to_vec())
+    }
+}
+
+fn parse_key(s: &str) -> Option<char> {
+    let key = s.trim().to_ascii_lowercase();
+    let key = key
+        .strip_prefix("ctrl+")
+        .or_else(|| key.strip_prefix("cmd+"))?;
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphanumeric() => Some(c),
+        _ => None,
+    }
+}
+
+pub fn palette_input_id() -> text_input::Id {
+    text_input::Id::new("command-palette")
+}
+
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    pub query: String,
+    /// Index of the highlighted action among the matching ones.
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    /// The actions matching the query, the best matches first.
+    pub fn matches(&self) -> Vec<Action> {
+        let mut matches: Vec<(usize, Action)> = Action::PALETTE
+            .iter()
+            .filter_map(|action| fuzzy_score(&self.query, action.label()).map(|s| (s, *action)))
+            .collect();
+        // The sort is stable, so equal matches keep the palette order.
+        matches.sort_by_key(|(score, _)| *score);
+        matches.into_iter().map(|(_, action)| action).collect()
+    }
+
+    pub fn edit_query(&mut self, query: String) {
+        self.query = query;
+        self.selected = 0;
+    }
+
+    pub fn select_next(&mut self) {
+        let n = self.matches().len();
+        if n > 0 {
+            self.selected = (self.selected + 1) % n;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        let n = self.matches().len();
+        if n > 0 {
+            self.selected = (self.selected + n - 1) % n;
+        }
+    }
+
+    pub fn selected_action(&self) -> Option<Action> {
+        self.matches().get(self.selected).copied()
+    }
+}
+
+/// Whether all the characters of the query appear in order in the candidate, ignoring case.
+/// If so, returns a score which is lower the closer the matched characters are to each other
+/// and to the start of the candidate.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let offset = candidate[position..].iter().position(|c| *c == q)?;
+        score += offset;
+        position += offset + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matching() {
+        assert_eq!(fuzzy_score("", "Coins"), Some(0));
+        assert_eq!(fuzzy_score("coi", "Coins"), Some(0));
+        assert_eq!(fuzzy_score("CNS", "Coins"), Some(2));
+        assert_eq!(fuzzy_score("tns", "Transactions"), Some(2));
+        assert_eq!(fuzzy_score("nc", "Coins"), None);
+        assert_eq!(fuzzy_score("new spend", "New spend"), Some(1));
+
+        let mut palette = CommandPalette::default();
+        assert_eq!(palette.matches(), Action::PALETTE.to_vec());
+        palette.edit_query("se".to_string());
+        assert_eq!(
+            palette.matches(),
+            vec![
+                Action::Send,
+                Action::Settings,
+                Action::NewSpend,
+                Action::LockSigner
+            ]
+        );
+        palette.select_previous();
+        assert_eq!(palette.selected_action(), Some(Action::LockSigner));
+        palette.select_next();
+        assert_eq!(palette.selected_action(), Some(Action::Send));
+        palette.edit_query("zz".to_string());
+        assert!(palette.matches().is_empty());
+        palette.select_next();
+        assert_eq!(palette.selected_action(), None);
+    }
+
+    #[test]
+    fn shortcut_bindings() {
+        let ctrl = keyboard::Modifiers::COMMAND;
+        let key = |c: &str| keyboard::Key::Character(c.into());
+        let shortcuts = Shortcuts::default();
+        assert_eq!(shortcuts.action(&key("1"), ctrl), Some(Action::Home));
+        assert_eq!(
+            shortcuts.action(&key("K"), ctrl),
+            Some(Action::CommandPalette)
+        );
+        assert_eq!(
+            shortcuts.action(&key("1"), keyboard::Modifiers::empty()),
+            None
+        );
+        assert_eq!(
+            shortcuts.action(&key("1"), ctrl | keyboard::Modifiers::ALT),
+            None
+        );
+        assert_eq!(shortcuts.action(&key("z"), ctrl), None);
+
+        let overrides = BTreeMap::from([
+            ("Ctrl+P".to_string(), Action::CommandPalette),
+            ("ctrl+2".to_string(), Action::Refresh),
+            ("shift+x".to_string(), Action::Home),
+            ("ctrl+ab".to_string(), Action::Home),
+        ]);
+        let shortcuts = Shortcuts::new(&overrides);
+        assert_eq!(
+            shortcuts.action(&key("p"), ctrl),
+            Some(Action::CommandPalette)
+        );
+        assert_eq!(shortcuts.action(&key("k"), ctrl), None);
+        assert_eq!(shortcuts.action(&key("2"), ctrl), Some(Action::Refresh));
+        assert_eq!(shortcuts.action(&key("r"), ctrl), None);
+        assert_eq!(shortcuts.key(Action::Send), None);
+        assert_eq!(
+            shortcuts.key(Action::Home),
+            Some(format!("{}+1", MODIFIER_NAME))
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{cmp::Ordering, collections::HashSet};
 
-use iced::Command;
+use iced::{event, keyboard, time, Command, Event, Subscription};
 
-use liana_ui::widget::Element;
+use liana::miniscript::bitcoin::OutPoint;
+use liana_ui::{component::form, widget::Element};
 use lianad::commands::CoinStatus;
 
 use crate::daemon::model::LabelsLoader;
@@ -15,15 +17,21 @@ use crate::{
         menu::Menu,
         message::Message,
         state::{label::LabelsEdited, State},
-        view,
+        view::{
+            self,
+            message::{CoinsFilter, CoinsMessage, CoinsSortKey},
+        },
         wallet::Wallet,
     },
     daemon::{
-        model::{Coin, LabelItem, Labelled},
+        model::{remaining_sequence, Coin, LabelItem, Labelled},
         Daemon,
     },
 };
 
+/// How long the filter must not be edited before it is applied.
+pub const FILTER_DEBOUNCE: Duration = Duration::from_millis(300);
+
 #[derive(Debug, Default)]
 pub struct Coins {
     list: Vec<Coin>,
@@ -47,8 +55,22 @@ impl Labelled for Coins {
 
 pub struct CoinsPanel {
     coins: Coins,
+    /// Indexes in the list of the coins whose details are shown.
     selected: Vec<usize>,
+    sort: CoinsSortKey,
+    sort_descending: bool,
+    /// The filter as typed, and the one applied to the list along with when it was last edited.
+    filter: String,
+    applied_filter: String,
+    filter_edited: Option<Instant>,
+    filters: Vec<CoinsFilter>,
+    /// Coins checked for the bulk actions, and the last one checked or unchecked.
+    checked: Vec<OutPoint>,
+    last_checked: Option<OutPoint>,
+    shift: bool,
     labels_edited: LabelsEdited,
+    /// The coin whose privacy tag is being edited, along with the edited value.
+    tag_editing: Option<(OutPoint, form::Value<String>)>,
     warning: Option<Error>,
     /// timelock value to pass for the heir to consume a coin.
     timelock: u16,
@@ -60,6 +82,16 @@ impl CoinsPanel {
             labels_edited: LabelsEdited::default(),
             coins: Coins::default(),
             selected: Vec::new(),
+            sort: CoinsSortKey::Age,
+            sort_descending: false,
+            filter: String::new(),
+            applied_filter: String::new(),
+            filter_edited: None,
+            filters: Vec::new(),
+            checked: Vec::new(),
+            last_checked: None,
+            shift: false,
+            tag_editing: None,
             warning: None,
             timelock,
         };
@@ -87,8 +119,149 @@ impl CoinsPanel {
                 (None, Some(_)) => Ordering::Greater,
                 (Some(_), None) => Ordering::Less,
                 (None, None) => a.outpoint.vout.cmp(&b.outpoint.vout),
+            });
+        let list = &self.coins.list;
+        self.checked
+            .retain(|op| list.iter().any(|c| c.outpoint == *op));
+    }
+
+    /// The label shown for the coin, the one of its deposit transaction if it has none.
+    fn label(&self, coin: &Coin) -> Option<&String> {
+        self.coins
+            .labels
+            .get(&coin.outpoint.to_string())
+            .filter(|l| !l.is_empty())
+            .or_else(|| self.coins.labels.get(&coin.outpoint.txid.to_string()))
+            .filter(|l| !l.is_empty())
+    }
+
+    fn matches(&self, coin: &Coin, blockheight: u32) -> bool {
+        let query = self.applied_filter.trim().to_lowercase();
+        let matches_query = query.is_empty()
+            || coin.outpoint.to_string().contains(&query)
+            || coin.address.to_string().to_lowercase().contains(&query)
+            || [
+                coin.outpoint.to_string(),
+                coin.outpoint.txid.to_string(),
+                coin.address.to_string(),
+            ]
+            .iter()
+            .filter_map(|item| self.coins.labels.get(item))
+            .chain(coin.tag.as_ref())
+            .any(|label| label.to_lowercase().contains(&query));
+        matches_query
+            && self.filters.iter().all(|filter| match filter {
+                CoinsFilter::Unconfirmed => coin.block_height.is_none(),
+                CoinsFilter::Change => coin.is_change,
+                CoinsFilter::Expired => {
+                    coin.block_height.is_some()
+                        && remaining_sequence(coin, blockheight, self.timelock) == 0
+                }
             })
     }
+
+    /// The coins matching the filters, in the sort order, along with their index in the list.
+    fn visible_coins(&self, blockheight: u32) -> Vec<(usize, &Coin)> {
+        let mut coins: Vec<(usize, &Coin)> = self
+            .coins
+            .list
+            .iter()
+            .enumerate()
+            .filter(|(_, coin)| self.matches(coin, blockheight))
+            .collect();
+        // The list is already sorted by age, which the other orders fall back to.
+        match self.sort {
+            CoinsSortKey::Age => {}
+            CoinsSortKey::Amount => coins.sort_by_key(|(_, coin)| coin.amount),
+            CoinsSortKey::Label => {
+                coins.sort_by(|(_, a), (_, b)| match (self.label(a), self.label(b)) {
+                    (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                })
+            }
+            CoinsSortKey::Timelock => {
+                coins.sort_by_key(|(_, coin)| remaining_sequence(coin, blockheight, self.timelock))
+            }
+        }
+        if self.sort_descending {
+            coins.reverse();
+        }
+        coins
+    }
+
+    fn check(&mut self, outpoint: OutPoint, blockheight: u32) {
+        let checked = !self.checked.contains(&outpoint);
+        let mut outpoints = vec![outpoint];
+        if let Some(last) = self.last_checked.filter(|_| self.shift) {
+            let visible: Vec<OutPoint> = self
+                .visible_coins(blockheight)
+                .into_iter()
+                .map(|(_, coin)| coin.outpoint)
+                .collect();
+            if let (Some(i), Some(j)) = (
+                visible.iter().position(|op| *op == last),
+                visible.iter().position(|op| *op == outpoint),
+            ) {
+                outpoints = visible[i.min(j)..=i.max(j)].to_vec();
+            }
+        }
+        for op in outpoints {
+            if !checked {
+                self.checked.retain(|c| *c != op);
+            } else if !self.checked.contains(&op) {
+                self.checked.push(op);
+            }
+        }
+        self.last_checked = Some(outpoint);
+    }
+
+    fn update_coins_view(&mut self, message: CoinsMessage, blockheight: u32) {
+        match message {
+            CoinsMessage::SortBy(key) => {
+                if self.sort == key {
+                    self.sort_descending = !self.sort_descending;
+                } else {
+                    self.sort = key;
+                    self.sort_descending = false;
+                }
+            }
+            CoinsMessage::FilterEdited(filter) => {
+                self.filter = filter;
+                self.filter_edited = Some(Instant::now());
+            }
+            CoinsMessage::ApplyFilter => {
+                if self
+                    .filter_edited
+                    .map_or(false, |edited| edited.elapsed() >= FILTER_DEBOUNCE)
+                {
+                    self.applied_filter = self.filter.clone();
+                    self.filter_edited = None;
+                }
+            }
+            CoinsMessage::ToggleFilter(filter) => {
+                if let Some(i) = self.filters.iter().position(|f| *f == filter) {
+                    self.filters.remove(i);
+                } else {
+                    self.filters.push(filter);
+                }
+            }
+            CoinsMessage::Check(outpoint) => self.check(outpoint, blockheight),
+            CoinsMessage::CheckAll(checked) => {
+                self.checked = if checked {
+                    self.visible_coins(blockheight)
+                        .into_iter()
+                        .map(|(_, coin)| coin.outpoint)
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                self.last_checked = None;
+            }
+            CoinsMessage::ShiftChanged(shift) => self.shift = shift,
+        }
+    }
 }
 
 impl State for CoinsPanel {
@@ -99,11 +272,19 @@ impl State for CoinsPanel {
             self.warning.as_ref(),
             view::coins::coins_view(
                 cache,
-                &self.coins.list,
+                self.visible_coins(cache.blockheight as u32),
                 self.timelock,
                 &self.selected,
+                view::coins::CoinsListState {
+                    sort: self.sort,
+                    sort_descending: self.sort_descending,
+                    filter: &self.filter,
+                    filters: &self.filters,
+                    checked: &self.checked,
+                },
                 &self.coins.labels,
                 self.labels_edited.cache(),
+                self.tag_editing.as_ref(),
             ),
         )
     }
@@ -111,10 +292,13 @@ impl State for CoinsPanel {
     fn update(
         &mut self,
         daemon: Arc<dyn Daemon + Sync + Send>,
-        _cache: &Cache,
+        cache: &Cache,
         message: Message,
     ) -> Command<Message> {
         match message {
+            Message::View(view::Message::Coins(msg)) => {
+                self.update_coins_view(msg, cache.blockheight as u32);
+            }
             Message::Coins(res) => match res {
                 Err(e) => self.warning = Some(e),
                 Ok(coins) => {
@@ -129,7 +313,7 @@ impl State for CoinsPanel {
                     self.coins.labels = labels;
                 }
             },
-            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(_) => {
+            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(..) => {
                 match self.labels_edited.update(
                     daemon,
                     message,
@@ -141,6 +325,74 @@ impl State for CoinsPanel {
                     }
                 }
             }
+            Message::View(view::Message::CoinTag(outpoint, msg)) => match msg {
+                view::message::CoinTagMessage::Edit => {
+                    let tag = self
+                        .coins
+                        .list
+                        .iter()
+                        .find(|coin| coin.outpoint == outpoint)
+                        .and_then(|coin| coin.tag.clone())
+                        .unwrap_or_default();
+                    self.tag_editing = Some((
+                        outpoint,
+                        form::Value {
+                            value: tag,
+                            valid: true,
+                        },
+                    ));
+                }
+                view::message::CoinTagMessage::Edited(value) => {
+                    if let Some((_, tag)) =
+                        self.tag_editing.as_mut().filter(|(op, _)| *op == outpoint)
+                    {
+                        tag.valid = value.len() <= 100;
+                        tag.value = value;
+                    }
+                }
+                view::message::CoinTagMessage::Cancel => {
+                    self.tag_editing = None;
+                }
+                view::message::CoinTagMessage::Confirm => {
+                    if let Some((_, tag)) = self
+                        .tag_editing
+                        .as_ref()
+                        .filter(|(op, tag)| *op == outpoint && tag.valid)
+                    {
+                        let tag = tag.value.trim();
+                        let tag = if tag.is_empty() {
+                            None
+                        } else {
+                            Some(tag.to_string())
+                        };
+                        return Command::perform(
+                            async move {
+                                daemon
+                                    .set_coin_tags(&HashMap::from([(outpoint, tag.clone())]))
+                                    .await
+                                    .map(|_| (outpoint, tag))
+                                    .map_err(|e| e.into())
+                            },
+                            Message::CoinTagUpdated,
+                        );
+                    }
+                }
+            },
+            Message::CoinTagUpdated(res) => match res {
+                Err(e) => self.warning = Some(e),
+                Ok((outpoint, tag)) => {
+                    if let Some(coin) = self
+                        .coins
+                        .list
+                        .iter_mut()
+                        .find(|coin| coin.outpoint == outpoint)
+                    {
+                        coin.tag = tag;
+                    }
+                    self.tag_editing = None;
+                    self.warning = None;
+                }
+            },
             Message::View(view::Message::Select(i)) => {
                 if let Some(position) = self.selected.iter().position(|j| *j == i) {
                     self.selected.remove(position);
@@ -153,6 +405,25 @@ impl State for CoinsPanel {
         Command::none()
     }
 
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch(vec![
+            if self.filter_edited.is_some() {
+                time::every(FILTER_DEBOUNCE)
+                    .map(|_| Message::View(view::Message::Coins(CoinsMessage::ApplyFilter)))
+            } else {
+                Subscription::none()
+            },
+            event::listen_with(|event, _| match event {
+                Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                    Some(Message::View(view::Message::Coins(
+                        CoinsMessage::ShiftChanged(modifiers.shift()),
+                    )))
+                }
+                _ => None,
+            }),
+        ])
+    }
+
     fn reload(
         &mut self,
         daemon: Arc<dyn Daemon + Sync + Send>,
@@ -228,6 +499,8 @@ mod tests {
                 derivation_index: 0.into(),
                 is_change: false,
                 is_from_self: false,
+                recovery_heights: Vec::new(),
+                tag: None,
             },
             Coin {
                 outpoint: bitcoin::OutPoint { txid, vout: 3 },
@@ -239,6 +512,8 @@ mod tests {
                 derivation_index: 1.into(),
                 is_change: false,
                 is_from_self: false,
+                recovery_heights: Vec::new(),
+                tag: None,
             },
             Coin {
                 outpoint: bitcoin::OutPoint { txid, vout: 0 },
@@ -250,6 +525,8 @@ mod tests {
                 derivation_index: 2.into(),
                 is_change: false,
                 is_from_self: false,
+                recovery_heights: Vec::new(),
+                tag: None,
             },
             Coin {
                 outpoint: bitcoin::OutPoint { txid, vout: 1 },
@@ -261,6 +538,8 @@ mod tests {
                 derivation_index: 3.into(),
                 is_change: false,
                 is_from_self: false,
+                recovery_heights: Vec::new(),
+                tag: None,
             },
         ]);
 
@@ -279,4 +558,109 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_coins_panel_sort_filter_check() {
+        let txid = bitcoin::Txid::from_str(
+            "f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5",
+        )
+        .unwrap();
+        let dummy_address =
+            bitcoin::Address::from_str("bc1qvrl2849aggm6qry9ea7xqp2kk39j8vaa8r3cwg")
+                .unwrap()
+                .assume_checked();
+        let coin = |vout: u32, amount: u64, block_height: Option<i32>, is_change: bool| Coin {
+            outpoint: bitcoin::OutPoint { txid, vout },
+            amount: bitcoin::Amount::from_sat(amount),
+            block_height,
+            spend_info: None,
+            is_immature: false,
+            address: dummy_address.clone(),
+            derivation_index: vout.into(),
+            is_change,
+            is_from_self: false,
+            recovery_heights: Vec::new(),
+            tag: None,
+        };
+        let mut panel = CoinsPanel::new(
+            &[
+                coin(0, 300, Some(1), false),
+                coin(1, 100, Some(60), true),
+                coin(2, 200, None, false),
+                coin(3, 400, Some(90), false),
+            ],
+            100,
+        );
+        panel.coins.labels.insert(
+            bitcoin::OutPoint { txid, vout: 3 }.to_string(),
+            "Alice".to_string(),
+        );
+        panel.coins.labels.insert(
+            bitcoin::OutPoint { txid, vout: 1 }.to_string(),
+            "bob".to_string(),
+        );
+        let visible = |panel: &CoinsPanel| -> Vec<u32> {
+            panel
+                .visible_coins(150)
+                .into_iter()
+                .map(|(_, c)| c.outpoint.vout)
+                .collect()
+        };
+
+        assert_eq!(visible(&panel), vec![0, 1, 3, 2]);
+        panel.update_coins_view(CoinsMessage::SortBy(CoinsSortKey::Amount), 150);
+        assert_eq!(visible(&panel), vec![1, 2, 0, 3]);
+        panel.update_coins_view(CoinsMessage::SortBy(CoinsSortKey::Amount), 150);
+        assert_eq!(visible(&panel), vec![3, 0, 2, 1]);
+        panel.update_coins_view(CoinsMessage::SortBy(CoinsSortKey::Label), 150);
+        assert_eq!(visible(&panel), vec![3, 1, 0, 2]);
+        panel.update_coins_view(CoinsMessage::SortBy(CoinsSortKey::Timelock), 150);
+        assert_eq!(visible(&panel), vec![0, 1, 3, 2]);
+
+        // The filter is only applied once it was not edited for a while.
+        panel.update_coins_view(CoinsMessage::FilterEdited("BOB".to_string()), 150);
+        panel.update_coins_view(CoinsMessage::ApplyFilter, 150);
+        assert_eq!(visible(&panel).len(), 4);
+        panel.filter_edited = Some(Instant::now() - FILTER_DEBOUNCE);
+        panel.update_coins_view(CoinsMessage::ApplyFilter, 150);
+        assert_eq!(visible(&panel), vec![1]);
+        panel.update_coins_view(CoinsMessage::FilterEdited(String::new()), 150);
+        panel.filter_edited = Some(Instant::now() - FILTER_DEBOUNCE);
+        panel.update_coins_view(CoinsMessage::ApplyFilter, 150);
+
+        panel.update_coins_view(CoinsMessage::SortBy(CoinsSortKey::Age), 150);
+        panel.update_coins_view(CoinsMessage::ToggleFilter(CoinsFilter::Expired), 150);
+        assert_eq!(visible(&panel), vec![0]);
+        panel.update_coins_view(CoinsMessage::ToggleFilter(CoinsFilter::Expired), 150);
+        panel.update_coins_view(CoinsMessage::ToggleFilter(CoinsFilter::Unconfirmed), 150);
+        assert_eq!(visible(&panel), vec![2]);
+        panel.update_coins_view(CoinsMessage::ToggleFilter(CoinsFilter::Change), 150);
+        assert!(visible(&panel).is_empty());
+        panel.update_coins_view(CoinsMessage::ToggleFilter(CoinsFilter::Unconfirmed), 150);
+        assert_eq!(visible(&panel), vec![1]);
+        panel.update_coins_view(CoinsMessage::ToggleFilter(CoinsFilter::Change), 150);
+
+        // Shift-click checks all the coins listed in between.
+        panel.update_coins_view(
+            CoinsMessage::Check(bitcoin::OutPoint { txid, vout: 0 }),
+            150,
+        );
+        panel.update_coins_view(CoinsMessage::ShiftChanged(true), 150);
+        panel.update_coins_view(
+            CoinsMessage::Check(bitcoin::OutPoint { txid, vout: 3 }),
+            150,
+        );
+        assert_eq!(
+            panel.checked.iter().map(|op| op.vout).collect::<Vec<_>>(),
+            vec![0, 1, 3]
+        );
+        panel.update_coins_view(
+            CoinsMessage::Check(bitcoin::OutPoint { txid, vout: 1 }),
+            150,
+        );
+        assert_eq!(
+            panel.checked.iter().map(|op| op.vout).collect::<Vec<_>>(),
+            vec![0]
+        );
+    }
 }
@@ -12,12 +12,18 @@ use crate::{
 use iced::Command;
 use liana_ui::component::form;
 
+/// The labels being edited, along with the ones shown before being persisted by the daemon.
 #[derive(Default)]
-pub struct LabelsEdited(HashMap<String, form::Value<String>>);
+pub struct LabelsEdited {
+    editing: HashMap<String, form::Value<String>>,
+    /// The labels the items had before an update the daemon did not confirm yet, to roll back
+    /// to if it fails.
+    pending: HashMap<String, Option<String>>,
+}
 
 impl LabelsEdited {
     pub fn cache(&self) -> &HashMap<String, form::Value<String>> {
-        &self.0
+        &self.editing
     }
     pub fn update<'a, T: IntoIterator<Item = &'a mut dyn LabelsLoader>>(
         &mut self,
@@ -30,11 +36,11 @@ impl LabelsEdited {
                 view::LabelMessage::Edited(value) => {
                     let valid = value.len() <= 100;
                     for item in items {
-                        if let Some(label) = self.0.get_mut(&item) {
+                        if let Some(label) = self.editing.get_mut(&item) {
                             label.valid = valid;
                             label.value.clone_from(&value);
                         } else {
-                            self.0.insert(
+                            self.editing.insert(
                                 item,
                                 form::Value {
                                     valid,
@@ -46,46 +52,60 @@ impl LabelsEdited {
                 }
                 view::LabelMessage::Cancel => {
                     for item in items {
-                        self.0.remove(&item);
+                        self.editing.remove(&item);
                     }
                 }
                 view::LabelMessage::Confirm => {
                     let mut updated_labels = HashMap::<LabelItem, Option<String>>::new();
                     let mut updated_labels_str = HashMap::<String, Option<String>>::new();
                     for item in items {
-                        if let Some(label) = self.0.get(&item).cloned() {
-                            let item_str = label_item_from_str(&item);
-                            if label.value.is_empty() {
-                                updated_labels.insert(item_str, None);
-                                updated_labels_str.insert(item, None);
-                            } else {
-                                updated_labels.insert(item_str, Some(label.value.clone()));
-                                updated_labels_str.insert(item, Some(label.value));
-                            }
+                        if let Some(label) = self.editing.remove(&item) {
+                            let label = Some(label.value).filter(|l| !l.is_empty());
+                            updated_labels.insert(label_item_from_str(&item), label.clone());
+                            updated_labels_str.insert(item, label);
+                        }
+                    }
+                    // Show the new labels right away, they are rolled back if the daemon
+                    // fails to store them.
+                    for target in targets {
+                        for (item, previous) in target.load_labels(&updated_labels_str) {
+                            self.pending.entry(item).or_insert(previous);
                         }
                     }
                     return Ok(Command::perform(
                         async move {
-                            daemon.update_labels(&updated_labels).await?;
-                            Ok(updated_labels_str)
+                            let res: Result<(), Error> = daemon
+                                .update_labels(&updated_labels)
+                                .await
+                                .map_err(|e| e.into());
+                            (updated_labels_str, res)
                         },
-                        Message::LabelsUpdated,
+                        |(labels, res)| Message::LabelsUpdated(labels, res),
                     ));
                 }
             },
-            Message::LabelsUpdated(res) => match res {
-                Ok(new_labels) => {
+            Message::LabelsUpdated(labels, res) => {
+                let previous: HashMap<String, Option<String>> = labels
+                    .keys()
+                    .filter_map(|item| self.pending.remove(item).map(|label| (item.clone(), label)))
+                    .collect();
+                if let Err(e) = res {
                     for target in targets {
-                        target.load_labels(&new_labels);
+                        target.load_labels(&previous);
                     }
-                    for (labelled, _) in new_labels {
-                        self.0.remove(&labelled);
+                    // Let the user fix the label, the form telling it was not saved.
+                    for (item, label) in labels {
+                        self.editing.insert(
+                            item,
+                            form::Value {
+                                value: label.unwrap_or_default(),
+                                valid: false,
+                            },
+                        );
                     }
-                }
-                Err(e) => {
                     return Err(e);
                 }
-            },
+            }
             _ => {}
         };
         Ok(Command::none())
@@ -14,8 +14,11 @@ use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use iced::{Command, Subscription};
-use liana::miniscript::bitcoin::{Amount, OutPoint};
-use liana_ui::widget::*;
+use liana::{
+    miniscript::bitcoin::{secp256k1, Amount, OutPoint, Transaction},
+    spend::SpendCreationError,
+};
+use liana_ui::{component::modal, widget::*};
 use lianad::commands::CoinStatus;
 
 use super::{
@@ -29,10 +32,20 @@ use super::{
 
 pub const HISTORY_EVENT_PAGE_SIZE: u64 = 20;
 
+/// Number of confirmed coins from which the home panel suggests to consolidate them.
+const CONSOLIDATION_THRESHOLD: usize = 20;
+/// Maximum number of coins merged by a single consolidation.
+const CONSOLIDATION_MAX_INPUTS: usize = 50;
+/// Confirmation target of the feerate of a consolidation, which is not urgent.
+const CONSOLIDATION_TARGET_BLOCKS: u16 = 144;
+
 use crate::daemon::model::LabelsLoader;
 use crate::daemon::{
-    model::{remaining_sequence, Coin, HistoryTransaction, Payment},
-    Daemon,
+    model::{
+        recovery_path_expiries, remaining_sequence, Coin, CreateSpendResult, HistoryTransaction,
+        Payment, RecoveryPathExpiry, SavedPsbtEntry, SpendTx, BLOCKS_PER_DAY,
+    },
+    Daemon, DaemonError,
 };
 pub use coins::CoinsPanel;
 use label::LabelsEdited;
@@ -76,7 +89,7 @@ pub fn redirect(menu: Menu) -> Command<Message> {
 /// Returns the confirmed and unconfirmed balances from `coins`, as well
 /// as:
 /// - the `OutPoint`s of those coins, if any, for which the current
-///   `tip_height` is within 10% of the `timelock` expiring.
+///   `tip_height` is within `warning_blocks` of the `timelock` expiring.
 /// - the smallest number of blocks until the expiry of `timelock` among
 ///   all confirmed coins, if any.
 ///
@@ -86,6 +99,7 @@ fn coins_summary(
     coins: &[Coin],
     tip_height: u32,
     timelock: u16,
+    warning_blocks: u32,
 ) -> (Amount, Amount, Vec<OutPoint>, Option<u32>) {
     let mut balance = Amount::from_sat(0);
     let mut unconfirmed_balance = Amount::from_sat(0);
@@ -102,9 +116,8 @@ fn coins_summary(
                     continue;
                 }
                 let seq = remaining_sequence(coin, tip_height, timelock);
-                // Warn user for coins that are expiring in less than 10 percent of
-                // the timelock.
-                if seq <= timelock as u32 * 10 / 100 {
+                // Warn user for coins that are expiring within the warning window.
+                if seq <= warning_blocks {
                     expiring_coins.push(coin.outpoint);
                 }
                 if let Some(last) = &mut remaining_seq {
@@ -122,6 +135,42 @@ fn coins_summary(
     (balance, unconfirmed_balance, expiring_coins, remaining_seq)
 }
 
+/// The largest of the configured warning windows before expiry, in blocks.
+fn expiry_warning_blocks(warning_days: &[u32]) -> u32 {
+    warning_days
+        .iter()
+        .max()
+        .map(|days| days.saturating_mul(BLOCKS_PER_DAY))
+        .unwrap_or(0)
+}
+
+/// The number of unspent confirmed coins, which could be consolidated.
+fn confirmed_coins_count(coins: &[Coin]) -> usize {
+    coins
+        .iter()
+        .filter(|coin| coin.spend_info.is_none() && coin.block_height.is_some())
+        .count()
+}
+
+/// Whether a draft can still be signed and broadcast, that is none of its inputs is missing
+/// or spent by another transaction.
+fn draft_is_valid(tx: &Transaction, coins: &[Coin]) -> bool {
+    let txid = tx.txid();
+    tx.input.iter().all(|txin| {
+        coins.iter().any(|coin| {
+            coin.outpoint == txin.previous_output
+                && !matches!(&coin.spend_info, Some(info) if info.txid != txid)
+        })
+    })
+}
+
+/// A draft reopened from the home panel.
+struct Draft {
+    id: i64,
+    psbt: psbt::PsbtState,
+    is_valid: bool,
+}
+
 pub struct Home {
     wallet: Arc<Wallet>,
     sync_status: SyncStatus,
@@ -129,6 +178,10 @@ pub struct Home {
     unconfirmed_balance: Amount,
     remaining_sequence: Option<u32>,
     expiring_coins: Vec<OutPoint>,
+    recovery_expiries: Vec<RecoveryPathExpiry>,
+    confirmed_coins: usize,
+    drafts: Vec<SavedPsbtEntry>,
+    selected_draft: Option<Draft>,
     events: Vec<Payment>,
     is_last_page: bool,
     processing: bool,
@@ -148,7 +201,11 @@ impl Home {
             coins,
             tip_height as u32,
             wallet.main_descriptor.first_timelock_value(),
+            expiry_warning_blocks(&wallet.expiry_warning_days),
         );
+        let recovery_expiries =
+            recovery_path_expiries(coins, tip_height, &wallet.expiry_warning_days);
+        let confirmed_coins = confirmed_coins_count(coins);
 
         Self {
             wallet,
@@ -157,6 +214,10 @@ impl Home {
             unconfirmed_balance,
             remaining_sequence: remaining_seq,
             expiring_coins,
+            recovery_expiries,
+            confirmed_coins,
+            drafts: Vec::new(),
+            selected_draft: None,
             selected_event: None,
             events: Vec::new(),
             labels_edited: LabelsEdited::default(),
@@ -169,7 +230,19 @@ impl Home {
 
 impl State for Home {
     fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, view::Message> {
-        if let Some((tx, output_index)) = &self.selected_event {
+        if let Some(draft) = &self.selected_draft {
+            let content = draft.psbt.view(cache);
+            if draft.is_valid {
+                content
+            } else {
+                modal::Modal::new(
+                    content,
+                    view::home::invalid_draft_modal(draft.id, self.warning.as_ref()),
+                )
+                .on_blur(Some(view::Message::Close))
+                .into()
+            }
+        } else if let Some((tx, output_index)) = &self.selected_event {
             view::home::payment_view(
                 cache,
                 tx,
@@ -187,10 +260,19 @@ impl State for Home {
                     &self.unconfirmed_balance,
                     &self.remaining_sequence,
                     &self.expiring_coins,
+                    &self.recovery_expiries,
+                    cache.blockheight,
+                    cache.bitcoin_unit,
+                    &self.drafts,
                     &self.events,
                     self.is_last_page,
                     self.processing,
                     &self.sync_status,
+                    if self.confirmed_coins >= CONSOLIDATION_THRESHOLD {
+                        Some(self.confirmed_coins)
+                    } else {
+                        None
+                    },
                 ),
             )
         }
@@ -203,6 +285,13 @@ impl State for Home {
         message: Message,
     ) -> Command<Message> {
         match message {
+            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(..)
+                if self.selected_draft.is_some() =>
+            {
+                if let Some(draft) = &mut self.selected_draft {
+                    return draft.psbt.update(daemon, cache, message);
+                }
+            }
             Message::Coins(res) => match res {
                 Err(e) => self.warning = Some(e),
                 Ok(coins) => {
@@ -216,7 +305,122 @@ impl State for Home {
                         &coins,
                         cache.blockheight as u32,
                         self.wallet.main_descriptor.first_timelock_value(),
+                        expiry_warning_blocks(&self.wallet.expiry_warning_days),
                     );
+                    self.recovery_expiries = recovery_path_expiries(
+                        &coins,
+                        cache.blockheight,
+                        &self.wallet.expiry_warning_days,
+                    );
+                    self.confirmed_coins = confirmed_coins_count(&coins);
+                }
+            },
+            Message::SavedPsbts(res) => match res {
+                Err(e) => self.warning = Some(e),
+                Ok(psbts) => self.drafts = psbts,
+            },
+            Message::View(view::Message::SelectDraft(id)) => {
+                return Command::perform(
+                    async move {
+                        let draft = daemon.get_saved_psbt(id).await?;
+                        let outpoints: Vec<_> = draft
+                            .psbt
+                            .unsigned_tx
+                            .input
+                            .iter()
+                            .map(|txin| txin.previous_output)
+                            .collect();
+                        let coins = daemon.list_coins(&[], &outpoints).await?.coins;
+                        Ok((draft, coins))
+                    },
+                    Message::Draft,
+                );
+            }
+            Message::View(view::Message::Consolidate) => {
+                // Fall back to the next longest target if the estimate is missing.
+                let feerate_vb = cache
+                    .fee_estimates
+                    .get(&CONSOLIDATION_TARGET_BLOCKS)
+                    .or_else(|| cache.fee_estimates.values().next_back())
+                    .copied()
+                    .unwrap_or(1);
+                return Command::perform(
+                    async move {
+                        let psbt = match daemon
+                            .create_consolidation(feerate_vb, CONSOLIDATION_MAX_INPUTS)
+                            .await?
+                        {
+                            CreateSpendResult::Success { psbt, .. } => psbt,
+                            CreateSpendResult::InsufficientFunds { missing } => {
+                                return Err(SpendCreationError::CoinSelection(
+                                    liana::spend::InsufficientFunds { missing },
+                                )
+                                .into());
+                            }
+                        };
+                        let id = daemon.save_psbt(&psbt, "Consolidation").await?.id;
+                        let draft = daemon.get_saved_psbt(id).await?;
+                        let outpoints: Vec<_> = draft
+                            .psbt
+                            .unsigned_tx
+                            .input
+                            .iter()
+                            .map(|txin| txin.previous_output)
+                            .collect();
+                        let coins = daemon.list_coins(&[], &outpoints).await?.coins;
+                        Ok((draft, coins))
+                    },
+                    Message::Draft,
+                );
+            }
+            Message::Draft(res) => match res {
+                Err(e) => self.warning = Some(e),
+                Ok((draft, coins)) => {
+                    self.warning = None;
+                    // A consolidation was just saved as a new draft.
+                    if !self.drafts.iter().any(|d| d.id == draft.id) {
+                        self.drafts.push(draft.clone());
+                    }
+                    let is_valid = draft_is_valid(&draft.psbt.unsigned_tx, &coins);
+                    let mut tx = SpendTx::new(
+                        None,
+                        draft.psbt,
+                        coins,
+                        &self.wallet.main_descriptor,
+                        &secp256k1::Secp256k1::verification_only(),
+                        cache.network,
+                    );
+                    if !draft.label.is_empty() {
+                        tx.labels
+                            .insert(tx.psbt.unsigned_tx.txid().to_string(), draft.label);
+                    }
+                    self.selected_draft = Some(Draft {
+                        id: draft.id,
+                        psbt: psbt::PsbtState::new(self.wallet.clone(), tx, false),
+                        is_valid,
+                    });
+                }
+            },
+            Message::View(view::Message::DeleteDraft(id)) => {
+                return Command::perform(
+                    async move {
+                        daemon
+                            .delete_saved_psbt(id)
+                            .await
+                            .map(|_| id)
+                            .map_err(|e| e.into())
+                    },
+                    Message::DraftDeleted,
+                );
+            }
+            Message::DraftDeleted(res) => match res {
+                Err(e) => self.warning = Some(e),
+                Ok(id) => {
+                    self.warning = None;
+                    self.drafts.retain(|draft| draft.id != id);
+                    if self.selected_draft.as_ref().map(|draft| draft.id) == Some(id) {
+                        self.selected_draft = None;
+                    }
                 }
             },
             Message::Payments(res) => match res {
@@ -285,7 +489,7 @@ impl State for Home {
                     Message::Payment,
                 );
             }
-            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(_) => {
+            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(..) => {
                 match self.labels_edited.update(
                     daemon,
                     message,
@@ -311,6 +515,7 @@ impl State for Home {
             }
             Message::View(view::Message::Close) => {
                 self.selected_event = None;
+                self.selected_draft = None;
             }
 
             Message::View(view::Message::Next) => {
@@ -357,11 +562,32 @@ impl State for Home {
                     );
                 }
             }
-            _ => {}
+            _ => {
+                // An invalid draft must not be signed or broadcast, it can only be deleted.
+                if let Some(draft) = &mut self.selected_draft {
+                    if draft.is_valid {
+                        return draft.psbt.update(daemon, cache, message);
+                    }
+                }
+            }
         };
         Command::none()
     }
 
+    fn subscription(&self) -> Subscription<Message> {
+        if let Some(draft) = &self.selected_draft {
+            draft.psbt.subscription()
+        } else {
+            Subscription::none()
+        }
+    }
+
+    fn interrupt(&mut self) {
+        if let Some(draft) = &mut self.selected_draft {
+            draft.psbt.interrupt();
+        }
+    }
+
     fn reload(
         &mut self,
         daemon: Arc<dyn Daemon + Sync + Send>,
@@ -375,8 +601,10 @@ impl State for Home {
             return Command::none();
         }
         self.selected_event = None;
+        self.selected_draft = None;
         self.wallet = wallet;
         let daemon2 = daemon.clone();
+        let daemon3 = daemon.clone();
         let now: u32 = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -407,6 +635,17 @@ impl State for Home {
                 },
                 Message::Coins,
             ),
+            Command::perform(
+                async move {
+                    match daemon3.list_saved_psbts().await {
+                        Ok(res) => Ok(res.psbts),
+                        // The backend may not be able to store PSBTs for later.
+                        Err(DaemonError::ClientNotSupported) => Ok(Vec::new()),
+                        Err(e) => Err(e.into()),
+                    }
+                },
+                Message::SavedPsbts,
+            ),
         ])
     }
 }
@@ -439,10 +678,11 @@ mod tests {
 
         let tip_height = 800_000;
         let timelock = 10_000;
+        let warning_blocks = 1_000;
         let mut coins = Vec::new();
         // Without coins, all values are 0 / empty / None:
         assert_eq!(
-            coins_summary(&coins, tip_height, timelock),
+            coins_summary(&coins, tip_height, timelock, warning_blocks),
             (Amount::from_sat(0), Amount::from_sat(0), Vec::new(), None)
         );
         // Add a spending coin.
@@ -455,6 +695,8 @@ mod tests {
             is_immature: false,
             is_change: false,
             is_from_self: false,
+            recovery_heights: Vec::new(),
+            tag: None,
             spend_info: Some(LCSpendInfo {
                 txid: dummy_txid,
                 height: None,
@@ -462,7 +704,7 @@ mod tests {
         });
         // Spending coin is ignored.
         assert_eq!(
-            coins_summary(&coins, tip_height, timelock),
+            coins_summary(&coins, tip_height, timelock, warning_blocks),
             (Amount::from_sat(0), Amount::from_sat(0), Vec::new(), None)
         );
         // Add unconfirmed change coin not from self.
@@ -475,11 +717,13 @@ mod tests {
             is_immature: false,
             is_change: true,
             is_from_self: false,
+            recovery_heights: Vec::new(),
+            tag: None,
             spend_info: None,
         });
         // Included in unconfirmed balance. Other values remain the same.
         assert_eq!(
-            coins_summary(&coins, tip_height, timelock),
+            coins_summary(&coins, tip_height, timelock, warning_blocks),
             (Amount::from_sat(0), Amount::from_sat(109), Vec::new(), None)
         );
         // Add unconfirmed coin from self.
@@ -492,11 +736,13 @@ mod tests {
             is_immature: false,
             is_change: false,
             is_from_self: true,
+            recovery_heights: Vec::new(),
+            tag: None,
             spend_info: None,
         });
         // Included in confirmed balance. Other values remain the same.
         assert_eq!(
-            coins_summary(&coins, tip_height, timelock),
+            coins_summary(&coins, tip_height, timelock, warning_blocks),
             (
                 Amount::from_sat(111),
                 Amount::from_sat(109),
@@ -504,21 +750,23 @@ mod tests {
                 None
             )
         );
-        // Add a confirmed coin 1 more than 10% from expiry:
+        // Add a confirmed coin 1 more than the warning window from expiry:
         coins.push(Coin {
             outpoint: OutPoint::new(dummy_txid, 3),
             amount: Amount::from_sat(101),
             address: dummy_address.clone(),
             derivation_index: bitcoin::bip32::ChildNumber::Normal { index: 3 },
-            block_height: Some(791_001), // 791_001 + timelock - tip_height = 1_001 > 1_000 = (timelock / 10)
+            block_height: Some(791_001), // 791_001 + timelock - tip_height = 1_001 > 1_000 = warning_blocks
             is_immature: false,
             is_change: false,
             is_from_self: false,
+            recovery_heights: Vec::new(),
+            tag: None,
             spend_info: None,
         });
         // Coin is added to confirmed balance. Not expiring, but remaining seq is set.
         assert_eq!(
-            coins_summary(&coins, tip_height, timelock),
+            coins_summary(&coins, tip_height, timelock, warning_blocks),
             (
                 Amount::from_sat(212),
                 Amount::from_sat(109),
@@ -527,11 +775,11 @@ mod tests {
             )
         );
         // Now decrease the last coin's confirmation height by 1 so that
-        // it is within 10% of expiry:
+        // it is within the warning window:
         coins.last_mut().unwrap().block_height = Some(791_000);
         // Its outpoint has been added to expiring coins and remaining seq is lower.
         assert_eq!(
-            coins_summary(&coins, tip_height, timelock),
+            coins_summary(&coins, tip_height, timelock, warning_blocks),
             (
                 Amount::from_sat(212),
                 Amount::from_sat(109),
@@ -549,11 +797,13 @@ mod tests {
             is_immature: false,
             is_change: false,
             is_from_self: false,
+            recovery_heights: Vec::new(),
+            tag: None,
             spend_info: None,
         });
         // Only confirmed balance has changed.
         assert_eq!(
-            coins_summary(&coins, tip_height, timelock),
+            coins_summary(&coins, tip_height, timelock, warning_blocks),
             (
                 Amount::from_sat(317),
                 Amount::from_sat(109),
@@ -571,11 +821,13 @@ mod tests {
             is_immature: false,
             is_change: false,
             is_from_self: false,
+            recovery_heights: Vec::new(),
+            tag: None,
             spend_info: None,
         });
         // Confirmed balance updated, as well as expiring coins and the remaining seq.
         assert_eq!(
-            coins_summary(&coins, tip_height, timelock),
+            coins_summary(&coins, tip_height, timelock, warning_blocks),
             (
                 Amount::from_sat(425),
                 Amount::from_sat(109),
@@ -583,5 +835,89 @@ mod tests {
                 Some(500)
             )
         );
+        // With a larger window, the coin that was not yet expiring is now.
+        assert_eq!(
+            coins_summary(&coins, tip_height, timelock, 2_000),
+            (
+                Amount::from_sat(425),
+                Amount::from_sat(109),
+                vec![
+                    OutPoint::new(dummy_txid, 3),
+                    OutPoint::new(dummy_txid, 4),
+                    OutPoint::new(dummy_txid, 5)
+                ],
+                Some(500)
+            )
+        );
+        // Without any window, only the coins whose timelock expired are warned about.
+        assert_eq!(
+            coins_summary(&coins, 801_000, timelock, 0),
+            (
+                Amount::from_sat(425),
+                Amount::from_sat(109),
+                vec![OutPoint::new(dummy_txid, 3), OutPoint::new(dummy_txid, 5)],
+                Some(0)
+            )
+        );
+    }
+
+    #[test]
+    fn test_expiry_warning_blocks() {
+        assert_eq!(expiry_warning_blocks(&[]), 0);
+        assert_eq!(expiry_warning_blocks(&[7, 30, 1]), 30 * BLOCKS_PER_DAY);
+    }
+
+    #[test]
+    fn test_draft_is_valid() {
+        let dummy_address =
+            bitcoin::Address::from_str("bc1qvrl2849aggm6qry9ea7xqp2kk39j8vaa8r3cwg")
+                .unwrap()
+                .assume_checked();
+        let dummy_txid = bitcoin::Txid::from_str(
+            "f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5",
+        )
+        .unwrap();
+        let coin = |vout: u32, spend_info: Option<LCSpendInfo>| Coin {
+            outpoint: OutPoint::new(dummy_txid, vout),
+            amount: Amount::from_sat(100),
+            address: dummy_address.clone(),
+            derivation_index: bitcoin::bip32::ChildNumber::Normal { index: vout },
+            block_height: Some(1),
+            is_immature: false,
+            is_change: false,
+            is_from_self: false,
+            recovery_heights: Vec::new(),
+            tag: None,
+            spend_info,
+        };
+        let tx = Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: (0..2)
+                .map(|vout| bitcoin::TxIn {
+                    previous_output: OutPoint::new(dummy_txid, vout),
+                    ..Default::default()
+                })
+                .collect(),
+            output: Vec::new(),
+        };
+
+        // All inputs are unspent coins of the wallet.
+        let mut coins = vec![coin(0, None), coin(1, None)];
+        assert!(draft_is_valid(&tx, &coins));
+        // The draft itself was broadcast.
+        coins[1].spend_info = Some(LCSpendInfo {
+            txid: tx.txid(),
+            height: None,
+        });
+        assert!(draft_is_valid(&tx, &coins));
+        // One input was spent by another transaction.
+        coins[1].spend_info = Some(LCSpendInfo {
+            txid: dummy_txid,
+            height: None,
+        });
+        assert!(!draft_is_valid(&tx, &coins));
+        // One input is unknown.
+        assert!(!draft_is_valid(&tx, &coins[..1]));
     }
 }
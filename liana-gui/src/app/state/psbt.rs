@@ -1,7 +1,9 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::TryInto;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use iced::Subscription;
 
@@ -24,15 +26,20 @@ use crate::{
         cache::Cache,
         error::Error,
         message::Message,
-        state::label::{label_item_from_str, LabelsEdited},
+        settings::{BroadcastSchedule, ScheduledBroadcast},
+        state::{
+            label::{label_item_from_str, LabelsEdited},
+            settings::register_wallet,
+        },
         view,
         wallet::{Wallet, WalletError},
     },
+    camera::{self, multipart::Progress, Camera, CameraError, ScanMessage},
     daemon::{
         model::{LabelItem, Labelled, SpendStatus, SpendTx},
         Daemon,
     },
-    hw::{HardwareWallet, HardwareWallets},
+    hw::{is_wallet_not_registered, HardwareWallet, HardwareWallets},
 };
 
 pub trait Action {
@@ -55,8 +62,11 @@ pub trait Action {
 
 pub enum PsbtAction {
     Save(SaveAction),
+    SaveDraft(SaveDraftAction),
+    Schedule(ScheduleAction),
     Sign(SignAction),
     Update(UpdateAction),
+    ImportSignatures(ImportSignaturesAction),
     Broadcast(BroadcastAction),
     Delete(DeleteAction),
 }
@@ -65,8 +75,11 @@ impl<'a> AsRef<dyn Action + 'a> for PsbtAction {
     fn as_ref(&self) -> &(dyn Action + 'a) {
         match &self {
             Self::Save(a) => a,
+            Self::SaveDraft(a) => a,
+            Self::Schedule(a) => a,
             Self::Sign(a) => a,
             Self::Update(a) => a,
+            Self::ImportSignatures(a) => a,
             Self::Broadcast(a) => a,
             Self::Delete(a) => a,
         }
@@ -77,8 +90,11 @@ impl<'a> AsMut<dyn Action + 'a> for PsbtAction {
     fn as_mut(&mut self) -> &mut (dyn Action + 'a) {
         match self {
             Self::Save(a) => a,
+            Self::SaveDraft(a) => a,
+            Self::Schedule(a) => a,
             Self::Sign(a) => a,
             Self::Update(a) => a,
+            Self::ImportSignatures(a) => a,
             Self::Broadcast(a) => a,
             Self::Delete(a) => a,
         }
@@ -169,6 +185,19 @@ impl PsbtState {
                 self.action = Some(PsbtAction::Update(action));
                 return cmd;
             }
+            Message::View(view::Message::Spend(view::SpendTxMessage::ImportSignatures)) => {
+                return Command::perform(pick_psbt_files(), Message::PsbtFiles);
+            }
+            Message::PsbtFiles(files) => {
+                // The user may not have chosen any file.
+                if !files.is_empty() {
+                    let action =
+                        ImportSignaturesAction::new(self.wallet.clone(), &self.tx.psbt, files);
+                    let cmd = action.load(daemon);
+                    self.action = Some(PsbtAction::ImportSignatures(action));
+                    return cmd;
+                }
+            }
             Message::View(view::Message::Spend(view::SpendTxMessage::Broadcast)) => {
                 let outpoints: Vec<_> = self.tx.coins.keys().cloned().collect();
                 return Command::perform(
@@ -190,7 +219,49 @@ impl PsbtState {
             Message::View(view::Message::Spend(view::SpendTxMessage::Save)) => {
                 self.action = Some(PsbtAction::Save(SaveAction::default()));
             }
-            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(_) => {
+            Message::View(view::Message::Spend(view::SpendTxMessage::SaveDraft)) => {
+                self.action = Some(PsbtAction::SaveDraft(SaveDraftAction::new(&self.tx)));
+            }
+            Message::View(view::Message::Spend(view::SpendTxMessage::ScheduleBroadcast)) => {
+                self.action = Some(PsbtAction::Schedule(ScheduleAction::new(
+                    self.wallet.clone(),
+                    cache.datadir_path.clone(),
+                    cache.network,
+                    cache.blockheight,
+                )));
+            }
+            Message::View(view::Message::Spend(view::SpendTxMessage::CancelSchedule)) => {
+                let wallet = self.wallet.clone();
+                let datadir_path = cache.datadir_path.clone();
+                let network = cache.network;
+                let txid = self.tx.psbt.unsigned_tx.txid();
+                return Command::perform(
+                    async move {
+                        let scheduled = wallet
+                            .scheduled_broadcasts
+                            .iter()
+                            .filter(|scheduled| scheduled.txid != txid)
+                            .cloned()
+                            .collect();
+                        wallet
+                            .update_scheduled_broadcasts(&datadir_path, network, scheduled)
+                            .map(Arc::new)
+                            .map_err(|e| e.into())
+                    },
+                    Message::WalletUpdated,
+                );
+            }
+            Message::WalletUpdated(Ok(wallet)) => {
+                self.wallet = wallet.clone();
+                if let Some(action) = self.action.as_mut() {
+                    return action.as_mut().update(
+                        daemon.clone(),
+                        Message::WalletUpdated(Ok(wallet)),
+                        &mut self.tx,
+                    );
+                }
+            }
+            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(..) => {
                 match self.labels_edited.update(
                     daemon,
                     message,
@@ -239,6 +310,8 @@ impl PsbtState {
             cache,
             &self.tx,
             self.saved,
+            self.wallet
+                .scheduled_broadcast(&self.tx.psbt.unsigned_tx.txid()),
             &self.desc_policy,
             &self.wallet.keys_aliases,
             self.labels_edited.cache(),
@@ -302,6 +375,213 @@ impl Action for SaveAction {
     }
 }
 
+/// Save the PSBT as a draft, to be resumed later from the home panel.
+pub struct SaveDraftAction {
+    label: form::Value<String>,
+    saved: bool,
+    error: Option<Error>,
+}
+
+impl SaveDraftAction {
+    pub fn new(tx: &SpendTx) -> Self {
+        Self {
+            label: form::Value {
+                value: default_draft_label(tx),
+                valid: true,
+            },
+            saved: false,
+            error: None,
+        }
+    }
+}
+
+/// The label of a draft defaults to the current time and the amount sent.
+fn default_draft_label(tx: &SpendTx) -> String {
+    format!(
+        "{} - {}",
+        chrono::Local::now().format("%b. %d, %Y - %T"),
+        tx.spend_amount
+    )
+}
+
+impl Action for SaveDraftAction {
+    fn update(
+        &mut self,
+        daemon: Arc<dyn Daemon + Sync + Send>,
+        message: Message,
+        tx: &mut SpendTx,
+    ) -> Command<Message> {
+        match message {
+            Message::View(view::Message::Spend(view::SpendTxMessage::DraftLabelEdited(label))) => {
+                self.label.value = label;
+            }
+            Message::View(view::Message::Spend(view::SpendTxMessage::Confirm)) => {
+                let psbt = tx.psbt.clone();
+                let label = self.label.value.clone();
+                self.error = None;
+                return Command::perform(
+                    async move {
+                        daemon
+                            .save_psbt(&psbt, &label)
+                            .await
+                            .map(|res| res.id)
+                            .map_err(|e| e.into())
+                    },
+                    Message::SavedForLater,
+                );
+            }
+            Message::SavedForLater(res) => match res {
+                Ok(_) => self.saved = true,
+                Err(e) => self.error = Some(e),
+            },
+            _ => {}
+        }
+        Command::none()
+    }
+    fn view<'a>(&'a self, content: Element<'a, view::Message>) -> Element<'a, view::Message> {
+        modal::Modal::new(
+            content,
+            view::psbt::save_draft_action(self.error.as_ref(), self.saved, &self.label),
+        )
+        .on_blur(Some(view::Message::Spend(view::SpendTxMessage::Cancel)))
+        .into()
+    }
+}
+
+/// Schedule the broadcast of a fully signed spend at a given time or block height.
+pub struct ScheduleAction {
+    wallet: Arc<Wallet>,
+    datadir_path: PathBuf,
+    network: Network,
+    tip_height: i32,
+    by_height: bool,
+    value: form::Value<String>,
+    scheduled: bool,
+    error: Option<Error>,
+}
+
+impl ScheduleAction {
+    pub fn new(
+        wallet: Arc<Wallet>,
+        datadir_path: PathBuf,
+        network: Network,
+        tip_height: i32,
+    ) -> Self {
+        Self {
+            wallet,
+            datadir_path,
+            network,
+            tip_height,
+            by_height: false,
+            value: form::Value::default(),
+            scheduled: false,
+            error: None,
+        }
+    }
+}
+
+/// Parse a broadcast schedule from either a block height or a local date and time formatted
+/// as `YYYY-MM-DD HH:MM`. Returns `None` if the value is invalid or not in the future.
+fn parse_schedule(
+    value: &str,
+    by_height: bool,
+    now: u32,
+    tip_height: i32,
+) -> Option<BroadcastSchedule> {
+    let schedule = if by_height {
+        BroadcastSchedule::BlockHeight(value.trim().parse().ok()?)
+    } else {
+        let date = chrono::NaiveDateTime::parse_from_str(value.trim(), "%Y-%m-%d %H:%M").ok()?;
+        let timestamp = date.and_local_timezone(chrono::Local).single()?.timestamp();
+        BroadcastSchedule::Time(timestamp.try_into().ok()?)
+    };
+    if schedule.is_due(now, tip_height) {
+        None
+    } else {
+        Some(schedule)
+    }
+}
+
+impl Action for ScheduleAction {
+    fn update(
+        &mut self,
+        _daemon: Arc<dyn Daemon + Sync + Send>,
+        message: Message,
+        tx: &mut SpendTx,
+    ) -> Command<Message> {
+        match message {
+            Message::View(view::Message::Spend(view::SpendTxMessage::ScheduleByHeight(
+                by_height,
+            ))) => {
+                self.by_height = by_height;
+                self.value = form::Value::default();
+            }
+            Message::View(view::Message::Spend(view::SpendTxMessage::ScheduleEdited(value))) => {
+                self.value.value = value;
+                self.value.valid = true;
+            }
+            Message::View(view::Message::Spend(view::SpendTxMessage::Confirm)) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs() as u32;
+                let schedule =
+                    match parse_schedule(&self.value.value, self.by_height, now, self.tip_height) {
+                        Some(schedule) => schedule,
+                        None => {
+                            self.value.valid = false;
+                            return Command::none();
+                        }
+                    };
+                let txid = tx.psbt.unsigned_tx.txid();
+                let mut scheduled: Vec<_> = self
+                    .wallet
+                    .scheduled_broadcasts
+                    .iter()
+                    .filter(|scheduled| scheduled.txid != txid)
+                    .cloned()
+                    .collect();
+                scheduled.push(ScheduledBroadcast { txid, schedule });
+                let wallet = self.wallet.clone();
+                let datadir_path = self.datadir_path.clone();
+                let network = self.network;
+                self.error = None;
+                return Command::perform(
+                    async move {
+                        wallet
+                            .update_scheduled_broadcasts(&datadir_path, network, scheduled)
+                            .map(Arc::new)
+                            .map_err(|e| e.into())
+                    },
+                    Message::WalletUpdated,
+                );
+            }
+            Message::WalletUpdated(res) => match res {
+                Ok(wallet) => {
+                    self.wallet = wallet;
+                    self.scheduled = true;
+                }
+                Err(e) => self.error = Some(e),
+            },
+            _ => {}
+        }
+        Command::none()
+    }
+    fn view<'a>(&'a self, content: Element<'a, view::Message>) -> Element<'a, view::Message> {
+        modal::Modal::new(
+            content,
+            view::psbt::schedule_broadcast_action(
+                self.error.as_ref(),
+                self.scheduled,
+                self.by_height,
+                &self.value,
+            ),
+        )
+        .on_blur(Some(view::Message::Spend(view::SpendTxMessage::Cancel)))
+        .into()
+    }
+}
+
 #[derive(Default)]
 pub struct BroadcastAction {
     broadcast: bool,
@@ -406,9 +686,17 @@ impl Action for DeleteAction {
 pub struct SignAction {
     wallet: Arc<Wallet>,
     hws: HardwareWallets,
+    datadir_path: PathBuf,
+    network: Network,
     error: Option<Error>,
     signing: HashSet<Fingerprint>,
     signed: HashSet<Fingerprint>,
+    /// The devices which failed to sign, with the error. They may have been unplugged since.
+    failed: HashMap<Fingerprint, String>,
+    /// The devices which refused to sign as they don't recognize the wallet registration.
+    not_registered: HashSet<Fingerprint>,
+    /// The device the wallet is being registered on.
+    registering: Option<Fingerprint>,
     is_saved: bool,
     display_modal: bool,
 }
@@ -423,10 +711,15 @@ impl SignAction {
     ) -> Self {
         Self {
             signing: HashSet::new(),
-            hws: HardwareWallets::new(datadir_path, network).with_wallet(wallet.clone()),
+            hws: HardwareWallets::new(datadir_path.clone(), network).with_wallet(wallet.clone()),
+            datadir_path,
+            network,
             wallet,
             error: None,
             signed,
+            failed: HashMap::new(),
+            not_registered: HashSet::new(),
+            registering: None,
             is_saved,
             display_modal: true,
         }
@@ -454,6 +747,7 @@ impl Action for SignAction {
                 {
                     self.display_modal = false;
                     self.signing.insert(*fingerprint);
+                    self.failed.remove(fingerprint);
                     let psbt = tx.psbt.clone();
                     let fingerprint = *fingerprint;
                     return Command::perform(
@@ -462,22 +756,105 @@ impl Action for SignAction {
                     );
                 }
             }
-            Message::View(view::Message::Spend(view::SpendTxMessage::SelectHotSigner)) => {
+            Message::View(view::Message::Spend(view::SpendTxMessage::SelectHotSigner(
+                fingerprint,
+            ))) => {
+                return Command::perform(
+                    sign_psbt_with_hot_signer(self.wallet.clone(), fingerprint, tx.psbt.clone()),
+                    move |res| Message::Signed(fingerprint, res),
+                );
+            }
+            Message::View(view::Message::Spend(view::SpendTxMessage::SaveForLater)) => {
+                let psbt = tx.psbt.clone();
+                let label = tx
+                    .labels
+                    .get(&psbt.unsigned_tx.txid().to_string())
+                    .cloned()
+                    .unwrap_or_default();
                 return Command::perform(
-                    sign_psbt_with_hot_signer(self.wallet.clone(), tx.psbt.clone()),
-                    |(fg, res)| Message::Signed(fg, res),
+                    async move {
+                        daemon
+                            .save_psbt(&psbt, &label)
+                            .await
+                            .map(|res| res.id)
+                            .map_err(|e| e.into())
+                    },
+                    Message::SavedForLater,
                 );
             }
+            Message::SavedForLater(res) => match res {
+                Ok(_) => {
+                    self.error = None;
+                    self.display_modal = false;
+                }
+                Err(e) => self.error = Some(e),
+            },
+            Message::View(view::Message::Spend(view::SpendTxMessage::RegisterWallet(i))) => {
+                if let Some(HardwareWallet::Supported {
+                    fingerprint,
+                    device,
+                    ..
+                }) = self.hws.list.get(i)
+                {
+                    self.error = None;
+                    self.registering = Some(*fingerprint);
+                    return Command::perform(
+                        register_wallet(
+                            self.datadir_path.clone(),
+                            self.network,
+                            device.clone(),
+                            *fingerprint,
+                            self.wallet.clone(),
+                            daemon,
+                        ),
+                        Message::WalletUpdated,
+                    );
+                }
+            }
+            Message::WalletUpdated(res) => {
+                if let Some(fingerprint) = self.registering.take() {
+                    match res {
+                        Ok(wallet) => {
+                            self.not_registered.remove(&fingerprint);
+                            // The devices are connected again with the fresh registration.
+                            self.hws.set_wallet(wallet.clone());
+                            self.wallet = wallet;
+                        }
+                        Err(e) => {
+                            if !matches!(e, Error::HardwareWallet(async_hwi::Error::UserRefused)) {
+                                self.error = Some(e)
+                            }
+                        }
+                    }
+                }
+            }
             Message::Signed(fingerprint, res) => {
                 self.signing.remove(&fingerprint);
                 match res {
-                    Err(e) => {
-                        if !matches!(e, Error::HardwareWallet(async_hwi::Error::UserRefused)) {
-                            self.error = Some(e)
-                        }
+                    Err(Error::HardwareWallet(e))
+                        if self
+                            .hws
+                            .list
+                            .iter()
+                            .find(|hw| hw.fingerprint() == Some(fingerprint))
+                            .map_or(false, |hw| is_wallet_not_registered(hw.kind(), &e)) =>
+                    {
+                        // Offer to register the wallet again rather than showing the opaque error
+                        // of the device.
+                        self.not_registered.insert(fingerprint);
+                        self.display_modal = true;
                     }
+                    Err(Error::HardwareWallet(async_hwi::Error::UserRefused)) => {}
+                    // A device failing, for instance because it was unplugged while signing, is
+                    // reported in its row rather than as an error of the whole action.
+                    Err(Error::HardwareWallet(e)) => {
+                        self.failed.insert(fingerprint, e.to_string());
+                        self.display_modal = true;
+                    }
+                    Err(e) => self.error = Some(e),
                     Ok(psbt) => {
                         self.error = None;
+                        self.failed.remove(&fingerprint);
                         self.signed.insert(fingerprint);
                         let daemon = daemon.clone();
                         merge_signatures(&mut tx.psbt, &psbt);
@@ -538,13 +915,24 @@ impl Action for SignAction {
                 view::psbt::sign_action(
                     self.error.as_ref(),
                     &self.hws.list,
-                    self.wallet.signer.as_ref().map(|s| s.fingerprint()),
                     self.wallet
-                        .signer
-                        .as_ref()
-                        .and_then(|signer| self.wallet.keys_aliases.get(&signer.fingerprint)),
+                        .signers
+                        .iter()
+                        .map(|signer| {
+                            (
+                                signer.fingerprint(),
+                                self.wallet.keys_aliases.get(&signer.fingerprint),
+                            )
+                        })
+                        .collect(),
                     &self.signed,
                     &self.signing,
+                    &self.failed,
+                    &self.hws.aliases,
+                    &self.not_registered,
+                    self.registering,
+                    &self.wallet.name,
+                    &self.wallet.main_descriptor,
                 ),
             )
             .on_blur(Some(view::Message::Spend(view::SpendTxMessage::Cancel)))
@@ -577,21 +965,191 @@ fn merge_signatures(psbt: &mut Psbt, signed_psbt: &Psbt) {
     }
 }
 
+/// The fingerprints of the keys `signed_psbt` has signatures for which `psbt` doesn't. Both
+/// PSBTs must be for the same transaction.
+fn new_signers(psbt: &Psbt, signed_psbt: &Psbt) -> BTreeSet<Fingerprint> {
+    let mut signers = BTreeSet::new();
+    for (psbtin, signed_psbtin) in psbt.inputs.iter().zip(signed_psbt.inputs.iter()) {
+        for pubkey in signed_psbtin.partial_sigs.keys() {
+            if psbtin.partial_sigs.contains_key(pubkey) {
+                continue;
+            }
+            if let Some((fg, _)) = psbtin
+                .bip32_derivation
+                .get(&pubkey.inner)
+                .or_else(|| signed_psbtin.bip32_derivation.get(&pubkey.inner))
+            {
+                signers.insert(*fg);
+            }
+        }
+        for (xpk, leaf_hash) in signed_psbtin.tap_script_sigs.keys() {
+            if psbtin.tap_script_sigs.contains_key(&(*xpk, *leaf_hash)) {
+                continue;
+            }
+            if let Some((_, (fg, _))) = psbtin
+                .tap_key_origins
+                .get(xpk)
+                .or_else(|| signed_psbtin.tap_key_origins.get(xpk))
+            {
+                signers.insert(*fg);
+            }
+        }
+        if psbtin.tap_key_sig.is_none() && signed_psbtin.tap_key_sig.is_some() {
+            if let Some((_, (fg, _))) = psbtin
+                .tap_internal_key
+                .and_then(|key| psbtin.tap_key_origins.get(&key))
+            {
+                signers.insert(*fg);
+            }
+        }
+    }
+    signers
+}
+
+/// Let the user choose PSBT files and read them. A file may hold a PSBT in base64 or in binary.
+async fn pick_psbt_files() -> Vec<(String, Result<Psbt, String>)> {
+    let files = rfd::AsyncFileDialog::new()
+        .set_title("Choose the PSBT files to import the signatures from...")
+        .pick_files()
+        .await
+        .unwrap_or_default();
+    let mut psbts = Vec::with_capacity(files.len());
+    for file in files {
+        let content = file.read().await;
+        psbts.push((file.file_name(), parse_psbt_file(&content)));
+    }
+    psbts
+}
+
+fn parse_psbt_file(content: &[u8]) -> Result<Psbt, String> {
+    match std::str::from_utf8(content) {
+        Ok(s) => Psbt::from_str(s.trim()).map_err(|e| e.to_string()),
+        // A binary PSBT starts with a 0xff separator byte, which is never valid UTF-8.
+        Err(_) => Psbt::deserialize(content).map_err(|e| e.to_string()),
+    }
+}
+
+/// Merge the signatures of PSBT files into the spend, reporting what each file brought.
+pub struct ImportSignaturesAction {
+    wallet: Arc<Wallet>,
+    /// The name of each file, along with what it brought.
+    imported: Vec<(String, view::psbt::ImportedSignatures)>,
+    /// The spend with the new signatures, if any file brought some.
+    merged: Option<Psbt>,
+    processing: bool,
+    success: bool,
+    error: Option<Error>,
+}
+
+impl ImportSignaturesAction {
+    pub fn new(
+        wallet: Arc<Wallet>,
+        psbt: &Psbt,
+        files: Vec<(String, Result<Psbt, String>)>,
+    ) -> Self {
+        let txid = psbt.unsigned_tx.txid();
+        let mut merged = psbt.clone();
+        let imported: Vec<_> = files
+            .into_iter()
+            .map(|(name, res)| {
+                let imported = match res {
+                    Err(e) => view::psbt::ImportedSignatures::Invalid(e),
+                    Ok(signed) if signed.unsigned_tx.txid() != txid => {
+                        view::psbt::ImportedSignatures::OtherTransaction(signed.unsigned_tx.txid())
+                    }
+                    Ok(signed) => {
+                        let signers = new_signers(&merged, &signed);
+                        merge_signatures(&mut merged, &signed);
+                        view::psbt::ImportedSignatures::Signers(signers)
+                    }
+                };
+                (name, imported)
+            })
+            .collect();
+        let any_new = imported.iter().any(|(_, imported)| {
+            matches!(imported, view::psbt::ImportedSignatures::Signers(signers) if !signers.is_empty())
+        });
+        Self {
+            wallet,
+            imported,
+            merged: any_new.then_some(merged),
+            processing: any_new,
+            success: false,
+            error: None,
+        }
+    }
+}
+
+impl Action for ImportSignaturesAction {
+    fn load(&self, daemon: Arc<dyn Daemon + Sync + Send>) -> Command<Message> {
+        if let Some(merged) = self.merged.clone() {
+            Command::perform(
+                async move { daemon.update_spend_tx(&merged).await.map_err(|e| e.into()) },
+                Message::Updated,
+            )
+        } else {
+            Command::none()
+        }
+    }
+
+    fn update(
+        &mut self,
+        _daemon: Arc<dyn Daemon + Sync + Send>,
+        message: Message,
+        tx: &mut SpendTx,
+    ) -> Command<Message> {
+        if let Message::Updated(res) = message {
+            self.processing = false;
+            match res {
+                Ok(()) => {
+                    if let Some(merged) = self.merged.take() {
+                        tx.psbt = merged;
+                        tx.sigs = self
+                            .wallet
+                            .main_descriptor
+                            .partial_spend_info(&tx.psbt)
+                            .unwrap();
+                    }
+                    self.success = true;
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(e),
+            }
+        }
+        Command::none()
+    }
+
+    fn view<'a>(&'a self, content: Element<'a, view::Message>) -> Element<'a, view::Message> {
+        modal::Modal::new(
+            content,
+            view::psbt::import_signatures_view(
+                &self.imported,
+                &self.wallet.keys_aliases,
+                self.processing,
+                self.success,
+                self.error.as_ref(),
+            ),
+        )
+        .on_blur(Some(view::Message::Spend(view::SpendTxMessage::Cancel)))
+        .into()
+    }
+}
+
 async fn sign_psbt_with_hot_signer(
     wallet: Arc<Wallet>,
+    fingerprint: Fingerprint,
     psbt: Psbt,
-) -> (Fingerprint, Result<Psbt, Error>) {
-    if let Some(signer) = &wallet.signer {
-        let res = signer
-            .sign_psbt(psbt)
-            .map_err(|e| WalletError::HotSigner(format!("Hot signer failed to sign psbt: {}", e)))
-            .map_err(|e| e.into());
-        (signer.fingerprint(), res)
+) -> Result<Psbt, Error> {
+    if let Some(signer) = wallet.signer(&fingerprint) {
+        match signer.sign_psbt(psbt) {
+            Some(res) => res.map_err(|e| {
+                WalletError::HotSigner(format!("Hot signer failed to sign psbt: {}", e))
+            }),
+            None => Err(WalletError::HotSigner("Hot signer is locked".to_string())),
+        }
+        .map_err(|e| e.into())
     } else {
-        (
-            Fingerprint::default(),
-            Err(WalletError::HotSigner("Hot signer not loaded".to_string()).into()),
-        )
+        Err(WalletError::HotSigner("Hot signer not loaded".to_string()).into())
     }
 }
 
@@ -644,6 +1202,11 @@ pub struct UpdateAction {
     processing: bool,
     error: Option<Error>,
     success: bool,
+    /// Cameras to choose from if more than one is available.
+    cameras: Vec<Camera>,
+    /// The camera used to scan the updated PSBT, if a scan is in progress.
+    scanning: Option<Camera>,
+    scan_progress: Option<Progress>,
 }
 
 impl UpdateAction {
@@ -655,16 +1218,39 @@ impl UpdateAction {
             processing: false,
             error: None,
             success: false,
+            cameras: Vec::new(),
+            scanning: None,
+            scan_progress: None,
         }
     }
+
+    fn stop_scan(&mut self) {
+        self.cameras.clear();
+        self.scanning = None;
+        self.scan_progress = None;
+    }
 }
 
 impl Action for UpdateAction {
+    fn subscription(&self) -> Subscription<Message> {
+        if let Some(camera) = &self.scanning {
+            camera::scan(camera.clone()).map(Message::QrScan)
+        } else {
+            Subscription::none()
+        }
+    }
+
     fn view<'a>(&'a self, content: Element<'a, view::Message>) -> Element<'a, view::Message> {
         modal::Modal::new(
             content,
             if self.success {
                 view::psbt::update_spend_success_view()
+            } else if self.scanning.is_some() || !self.cameras.is_empty() {
+                view::psbt::scan_psbt_view(
+                    &self.cameras,
+                    self.scan_progress.as_ref(),
+                    self.error.as_ref(),
+                )
             } else {
                 view::psbt::update_spend_view(
                     self.psbt.clone(),
@@ -740,6 +1326,72 @@ impl Action for UpdateAction {
                     );
                 }
             }
+            Message::View(view::Message::ImportSpend(view::ImportSpendMessage::ScanQrCode)) => {
+                self.error = None;
+                return Command::perform(
+                    async move { camera::list().await.map_err(|e| e.into()) },
+                    Message::Cameras,
+                );
+            }
+            Message::Cameras(res) => match res {
+                Ok(mut cameras) => {
+                    if cameras.len() == 1 {
+                        self.scanning = cameras.pop();
+                    } else {
+                        self.cameras = cameras;
+                    }
+                }
+                Err(e) => self.error = Some(e),
+            },
+            Message::View(view::Message::ImportSpend(view::ImportSpendMessage::SelectCamera(
+                i,
+            ))) => {
+                if let Some(camera) = self.cameras.get(i).cloned() {
+                    self.cameras.clear();
+                    self.scanning = Some(camera);
+                }
+            }
+            Message::View(view::Message::ImportSpend(view::ImportSpendMessage::CancelScan)) => {
+                // Dropping the subscription releases the camera.
+                self.stop_scan();
+            }
+            Message::QrScan(msg) => match msg {
+                ScanMessage::Started => self.scan_progress = None,
+                ScanMessage::Progress(progress) => self.scan_progress = Some(progress),
+                ScanMessage::Error(e) => {
+                    self.stop_scan();
+                    self.error = Some(e.into());
+                }
+                ScanMessage::Scanned(bytes) => {
+                    self.stop_scan();
+                    match Psbt::deserialize(&bytes) {
+                        Ok(updated) if updated.unsigned_tx.txid() == tx.psbt.unsigned_tx.txid() => {
+                            // Go through the same path as a PSBT pasted by the user.
+                            self.updated.value = updated.to_string();
+                            self.updated.valid = true;
+                            self.processing = true;
+                            self.error = None;
+                            return Command::perform(
+                                async move {
+                                    daemon.update_spend_tx(&updated).await.map_err(|e| e.into())
+                                },
+                                Message::Updated,
+                            );
+                        }
+                        Ok(_) => {
+                            self.error = Some(
+                                CameraError::InvalidPsbt(
+                                    "it is not for this transaction".to_string(),
+                                )
+                                .into(),
+                            );
+                        }
+                        Err(e) => {
+                            self.error = Some(CameraError::InvalidPsbt(e.to_string()).into());
+                        }
+                    }
+                }
+            },
             _ => {}
         }
 
@@ -779,7 +1431,7 @@ mod tests {
             (
                 Some(json!({"method": "listspendtxs", "params": Option::<Request>::None})),
                 Ok(json!({ "spend_txs": [{
-                    "psbt": "cHNidP8BAIkCAAAAAc0x/jtWvFugrl8zc34KVIlWCugXT6JNtgir6UqX+Vv6AQAAAAD9////AkBCDwAAAAAAIgAgtQu/fA/8rQhJ0I6wUoBDO0vNa3lgsEpEIj7rTOMnBcXuIEkBAAAAACIAIOdCiXh7yL2V/f6S6KMTOzgqKkqyIXgmFuwDnmXbIiosAAAAAAABAP04AQIAAAAAAQKYYriMs/PtSqm6LPNWWFYskTL6nWZegJdwxYcVCRn8vwEAAAAA/f///87D7dkdgMd1Laj/v6xspNRtrQXGP+8BPFMLqkeBb6MRAQAAAAD9////AuGQDgAAAAAAIlEg7DgdNxI7WybaPUZXcMCh+uN1E4X8E5DzJIlj83S+tIMQZFgBAAAAACIAIJZAn7j5iOen7xo2sKzjMc24llTZIuS+RpdwcLHtE6ufAUCksqYUJBbHB9x8eHdoRvRqiGzG4wQXpmY96vh14zAJEM2CS/oZaNVC4Wj8rY2cdjAvZj9dlVZFPbOxx9g5tFxUAUA24s2KJ7sjSHUAcUSd4yqRK/G3CZM8qhkhyHhGDSS0zZvZaIcgoqOPe23gH32wAI9Aax1gJUDv4kKOqOx64ltg9BADAAEBKxBkWAEAAAAAIgAglkCfuPmI56fvGjawrOMxzbiWVNki5L5Gl3Bwse0Tq58BBYZSIQIeYxzruE4/cvi6zbRmB1asJO0bMfUutoH0bpubw1zAZSEDLZSmORZKW/k5A+4QxJR2/H+vcV8U0WPX9SvS+MRMffNSrnNkdqkUmNf1mL657o/oxxnHkIrtdNkbge+IrGt2qRSIigBO15eaB9dj93ihNpAX9HHDuoisbJNRiAP//wCyaCIGAh5jHOu4Tj9y+LrNtGYHVqwk7Rsx9S62gfRum5vDXMBlHPcUwigwAACAAQAAgAAAAIACAACAAAAAAAAAAAAiBgIr7HqsyKEvERWQsmsv6FleMuXThpI77+TVkQ3TSOOLURz3FMIoMAAAgAEAAIAAAACAAgAAgAIAAAAAAAAAIgYDLZSmORZKW/k5A+4QxJR2/H+vcV8U0WPX9SvS+MRMffMcJSLyPDAAAIABAACAAAAAgAIAAIAAAAAAAAAAACIGA/h0pUXGHq1+kSuTYVTO8RHKfQLJlhfNtm+qdcIIr09jHCUi8jwwAACAAQAAgAAAAIACAACAAgAAAAAAAAAAIgICGAO/4xFiX/S5DXTV6uARFTcMwP1hto8BtPkdn3gIjf0c9xTCKDAAAIABAACAAAAAgAIAAIACAAAAAgAAACICAuNOSbsNRv31XkF2ygwCOuCnsJNRLhV0isJ/VRdj1k7IHPcUwigwAACAAQAAgAAAAIACAACAAAAAAAIAAAAiAgOpBJHEchNOeXuQwuLHlwOfkAyfoGvrYfb4pCFLKEPw2hwlIvI8MAAAgAEAAIAAAACAAgAAgAIAAAACAAAAIgIDyLkJiZTjLCysDOQotYs9us5CEYev4kyTYW2uL2r5H1McJSLyPDAAAIABAACAAAAAgAIAAIAAAAAAAgAAAAAiAgIlvGBvHRPmmVP6sn9g/akW2VJAvbJagMnZ/24gLdITsxz3FMIoMAAAgAEAAIAAAACAAgAAgAMAAAADAAAAIgIDNmVQOMMezQgABjk1zjfc3I2eKFJ4xLqT55jG4BP4p0Ec9xTCKDAAAIABAACAAAAAgAIAAIABAAAAAwAAACICA4Subm7T6yYCMWLgDtMy92hOgjanJefukbCOSVEHlX0IHCUi8jwwAACAAQAAgAAAAIACAACAAQAAAAMAAAAiAgPpsETw12nxLEM6OSOPfxp4YYj8NtRcLdqBpi3S4/BTuRwlIvI8MAAAgAEAAIAAAACAAgAAgAMAAAADAAAAAA==",
+                    "psbt": PSBT,
                 }]})),
             ),
             (
@@ -852,4 +1504,99 @@ mod tests {
             )
             .await;
     }
+
+    const PSBT: &str = "cHNidP8BAIkCAAAAAc0x/jtWvFugrl8zc34KVIlWCugXT6JNtgir6UqX+Vv6AQAAAAD9////AkBCDwAAAAAAIgAgtQu/fA/8rQhJ0I6wUoBDO0vNa3lgsEpEIj7rTOMnBcXuIEkBAAAAACIAIOdCiXh7yL2V/f6S6KMTOzgqKkqyIXgmFuwDnmXbIiosAAAAAAABAP04AQIAAAAAAQKYYriMs/PtSqm6LPNWWFYskTL6nWZegJdwxYcVCRn8vwEAAAAA/f///87D7dkdgMd1Laj/v6xspNRtrQXGP+8BPFMLqkeBb6MRAQAAAAD9////AuGQDgAAAAAAIlEg7DgdNxI7WybaPUZXcMCh+uN1E4X8E5DzJIlj83S+tIMQZFgBAAAAACIAIJZAn7j5iOen7xo2sKzjMc24llTZIuS+RpdwcLHtE6ufAUCksqYUJBbHB9x8eHdoRvRqiGzG4wQXpmY96vh14zAJEM2CS/oZaNVC4Wj8rY2cdjAvZj9dlVZFPbOxx9g5tFxUAUA24s2KJ7sjSHUAcUSd4yqRK/G3CZM8qhkhyHhGDSS0zZvZaIcgoqOPe23gH32wAI9Aax1gJUDv4kKOqOx64ltg9BADAAEBKxBkWAEAAAAAIgAglkCfuPmI56fvGjawrOMxzbiWVNki5L5Gl3Bwse0Tq58BBYZSIQIeYxzruE4/cvi6zbRmB1asJO0bMfUutoH0bpubw1zAZSEDLZSmORZKW/k5A+4QxJR2/H+vcV8U0WPX9SvS+MRMffNSrnNkdqkUmNf1mL657o/oxxnHkIrtdNkbge+IrGt2qRSIigBO15eaB9dj93ihNpAX9HHDuoisbJNRiAP//wCyaCIGAh5jHOu4Tj9y+LrNtGYHVqwk7Rsx9S62gfRum5vDXMBlHPcUwigwAACAAQAAgAAAAIACAACAAAAAAAAAAAAiBgIr7HqsyKEvERWQsmsv6FleMuXThpI77+TVkQ3TSOOLURz3FMIoMAAAgAEAAIAAAACAAgAAgAIAAAAAAAAAIgYDLZSmORZKW/k5A+4QxJR2/H+vcV8U0WPX9SvS+MRMffMcJSLyPDAAAIABAACAAAAAgAIAAIAAAAAAAAAAACIGA/h0pUXGHq1+kSuTYVTO8RHKfQLJlhfNtm+qdcIIr09jHCUi8jwwAACAAQAAgAAAAIACAACAAgAAAAAAAAAAIgICGAO/4xFiX/S5DXTV6uARFTcMwP1hto8BtPkdn3gIjf0c9xTCKDAAAIABAACAAAAAgAIAAIACAAAAAgAAACICAuNOSbsNRv31XkF2ygwCOuCnsJNRLhV0isJ/VRdj1k7IHPcUwigwAACAAQAAgAAAAIACAACAAAAAAAIAAAAiAgOpBJHEchNOeXuQwuLHlwOfkAyfoGvrYfb4pCFLKEPw2hwlIvI8MAAAgAEAAIAAAACAAgAAgAIAAAACAAAAIgIDyLkJiZTjLCysDOQotYs9us5CEYev4kyTYW2uL2r5H1McJSLyPDAAAIABAACAAAAAgAIAAIAAAAAAAgAAAAAiAgIlvGBvHRPmmVP6sn9g/akW2VJAvbJagMnZ/24gLdITsxz3FMIoMAAAgAEAAIAAAACAAgAAgAMAAAADAAAAIgIDNmVQOMMezQgABjk1zjfc3I2eKFJ4xLqT55jG4BP4p0Ec9xTCKDAAAIABAACAAAAAgAIAAIABAAAAAwAAACICA4Subm7T6yYCMWLgDtMy92hOgjanJefukbCOSVEHlX0IHCUi8jwwAACAAQAAgAAAAIACAACAAQAAAAMAAAAiAgPpsETw12nxLEM6OSOPfxp4YYj8NtRcLdqBpi3S4/BTuRwlIvI8MAAAgAEAAIAAAACAAgAAgAMAAAADAAAAAA==";
+
+    #[test]
+    fn test_import_signatures() {
+        use liana::miniscript::bitcoin::{absolute, ecdsa, secp256k1, PublicKey};
+
+        let wallet = Arc::new(Wallet::new(LianaDescriptor::from_str(DESC).unwrap()));
+        let psbt = Psbt::from_str(PSBT).unwrap();
+        let fg = Fingerprint::from_str("f714c228").unwrap();
+        let mut signed = psbt.clone();
+        let pubkey = *signed.inputs[0]
+            .bip32_derivation
+            .iter()
+            .find(|(_, (key_fg, _))| *key_fg == fg)
+            .unwrap()
+            .0;
+        signed.inputs[0].partial_sigs.insert(
+            PublicKey::new(pubkey),
+            ecdsa::Signature::sighash_all(
+                secp256k1::ecdsa::Signature::from_compact(&[1; 64]).unwrap(),
+            ),
+        );
+        assert_eq!(new_signers(&psbt, &signed), BTreeSet::from([fg]));
+        assert!(new_signers(&signed, &signed).is_empty());
+
+        let mut other = psbt.clone();
+        other.unsigned_tx.lock_time = absolute::LockTime::from_consensus(1);
+        let action = ImportSignaturesAction::new(
+            wallet,
+            &psbt,
+            vec![
+                ("a.psbt".to_string(), Ok(signed.clone())),
+                // The same signature again is not new.
+                ("b.psbt".to_string(), Ok(signed.clone())),
+                ("c.psbt".to_string(), Err("invalid".to_string())),
+                ("d.psbt".to_string(), Ok(other.clone())),
+            ],
+        );
+        assert_eq!(
+            action.imported,
+            vec![
+                (
+                    "a.psbt".to_string(),
+                    view::psbt::ImportedSignatures::Signers(BTreeSet::from([fg]))
+                ),
+                (
+                    "b.psbt".to_string(),
+                    view::psbt::ImportedSignatures::Signers(BTreeSet::new())
+                ),
+                (
+                    "c.psbt".to_string(),
+                    view::psbt::ImportedSignatures::Invalid("invalid".to_string())
+                ),
+                (
+                    "d.psbt".to_string(),
+                    view::psbt::ImportedSignatures::OtherTransaction(other.unsigned_tx.txid())
+                ),
+            ]
+        );
+        assert_eq!(action.merged, Some(signed.clone()));
+        assert!(action.processing);
+
+        // A file may hold the PSBT in base64 or in binary.
+        assert_eq!(
+            parse_psbt_file(format!("{}\n", PSBT).as_bytes()),
+            Ok(psbt.clone())
+        );
+        assert_eq!(parse_psbt_file(&psbt.serialize()), Ok(psbt));
+        assert!(parse_psbt_file(b"not a psbt").is_err());
+    }
+
+    #[test]
+    fn test_parse_schedule() {
+        let now = 1_700_000_000;
+        assert_eq!(
+            parse_schedule("850000", true, now, 849_999),
+            Some(BroadcastSchedule::BlockHeight(850_000))
+        );
+        // The tip is already there.
+        assert_eq!(parse_schedule("850000", true, now, 850_000), None);
+        assert_eq!(parse_schedule("2023-11-14 22:13", true, now, 0), None);
+
+        let date = chrono::NaiveDateTime::parse_from_str("2030-01-04 09:00", "%Y-%m-%d %H:%M")
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .unwrap();
+        assert_eq!(
+            parse_schedule(" 2030-01-04 09:00 ", false, now, 0),
+            Some(BroadcastSchedule::Time(date.timestamp() as u32))
+        );
+        // In the past.
+        assert_eq!(parse_schedule("2020-01-04 09:00", false, now, 0), None);
+        assert_eq!(parse_schedule("tomorrow", false, now, 0), None);
+    }
 }
@@ -51,7 +51,11 @@ impl State for PsbtsPanel {
                 &Menu::PSBTs,
                 cache,
                 self.warning.as_ref(),
-                view::psbts::psbts_view(&self.spend_txs),
+                view::psbts::psbts_view(
+                    &self.spend_txs,
+                    &self.wallet.scheduled_broadcasts,
+                    cache.bitcoin_unit,
+                ),
             );
             if let Some(import_tx) = &self.import_tx {
                 modal::Modal::new(list_view, import_tx.view())
@@ -88,6 +92,12 @@ impl State for PsbtsPanel {
                     self.spend_txs = txs;
                 }
             },
+            Message::WalletUpdated(Ok(wallet)) => {
+                self.wallet = wallet.clone();
+                if let Some(tx) = &mut self.selected_tx {
+                    return tx.update(daemon, cache, Message::WalletUpdated(Ok(wallet)));
+                }
+            }
             Message::View(view::Message::ImportSpend(view::ImportSpendMessage::Import)) => {
                 if self.import_tx.is_none() {
                     self.import_tx = Some(ImportPsbtModal::new());
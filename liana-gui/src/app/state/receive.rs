@@ -2,12 +2,21 @@ use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use iced::{widget::qr_code, Command, Subscription};
+use async_hwi::DeviceKind;
+use iced::{Command, Subscription};
 use liana::miniscript::bitcoin::{
-    bip32::{ChildNumber, Fingerprint},
+    bip32::{ChildNumber, DerivationPath, Fingerprint},
     Address, Network,
 };
-use liana_ui::{component::modal, widget::*};
+use liana_ui::{
+    component::{
+        amount_input::AmountEdited,
+        form, modal,
+        qr::{bip21_uri, Qr},
+    },
+    widget::*,
+};
+use tracing::{info, warn};
 
 use crate::daemon::model::LabelsLoader;
 use crate::{
@@ -20,12 +29,12 @@ use crate::{
         view,
         wallet::Wallet,
     },
-    hw::{HardwareWallet, HardwareWallets},
+    hw::{supports_address_display, HardwareWallet, HardwareWallets, VerificationStatus},
 };
 
 use crate::daemon::{
     model::{LabelItem, Labelled},
-    Daemon,
+    Daemon, DaemonError,
 };
 
 pub enum Modal {
@@ -38,9 +47,21 @@ pub enum Modal {
 pub struct Addresses {
     list: Vec<Address>,
     derivation_indexes: Vec<ChildNumber>,
+    /// The full derivation path of each key of the address.
+    derivation_paths: Vec<Vec<(Fingerprint, DerivationPath)>>,
+    /// The descriptor for the single address, with checksum.
+    descriptors: Vec<String>,
+    /// The addresses which already received funds. An address is never removed from this set
+    /// so the warning doesn't flicker while the usage is refreshed.
+    used: HashSet<Address>,
+    /// The addresses which were confirmed on at least one hardware device.
+    verified: HashSet<Address>,
     labels: HashMap<String, String>,
 }
 
+/// How many already used addresses we skip at most when generating a new one.
+const MAX_USED_ADDRESSES_SKIPPED: usize = 20;
+
 impl Labelled for Addresses {
     fn labelled(&self) -> Vec<LabelItem> {
         self.list
@@ -83,6 +104,10 @@ impl State for ReceivePanel {
             self.warning.as_ref(),
             view::receive::receive(
                 &self.addresses.list,
+                &self.addresses.derivation_paths,
+                &self.addresses.descriptors,
+                &self.addresses.used,
+                &self.addresses.verified,
                 &self.addresses.labels,
                 self.labels_edited.cache(),
             ),
@@ -92,7 +117,7 @@ impl State for ReceivePanel {
             Modal::VerifyAddress(m) => modal::Modal::new(content, m.view())
                 .on_blur(Some(view::Message::Close))
                 .into(),
-            Modal::ShowQrCode(m) => modal::Modal::new(content, m.view())
+            Modal::ShowQrCode(m) => modal::Modal::new(content, m.view(cache))
                 .on_blur(Some(view::Message::Close))
                 .into(),
             Modal::None => content,
@@ -114,7 +139,7 @@ impl State for ReceivePanel {
         message: Message,
     ) -> Command<Message> {
         match message {
-            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(_) => {
+            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(..) => {
                 match self.labels_edited.update(
                     daemon,
                     message,
@@ -131,13 +156,34 @@ impl State for ReceivePanel {
                 match res {
                     Ok((address, derivation_index)) => {
                         self.warning = None;
+                        let desc = &self.wallet.main_descriptor;
                         self.addresses.list.push(address);
                         self.addresses.derivation_indexes.push(derivation_index);
+                        self.addresses
+                            .derivation_paths
+                            .push(desc.address_derivation_paths(derivation_index, false));
+                        self.addresses.descriptors.push(
+                            desc.single_address_descriptor(derivation_index, false)
+                                .to_string(),
+                        );
                     }
                     Err(e) => self.warning = Some(e),
                 }
                 Command::none()
             }
+            Message::AddressesUsage(res) => {
+                match res {
+                    Ok(addresses) => self.addresses.used.extend(
+                        addresses
+                            .into_iter()
+                            .filter(|a| a.receive_coins > 0)
+                            .map(|a| a.receive),
+                    ),
+                    Err(e) => self.warning = Some(e),
+                }
+                Command::none()
+            }
+            Message::UpdatePanelCache(true, Ok(_)) => self.refresh_usage(daemon),
             Message::View(view::Message::Close) => {
                 self.modal = Modal::None;
                 Command::none()
@@ -160,29 +206,57 @@ impl State for ReceivePanel {
                 let daemon = daemon.clone();
                 Command::perform(
                     async move {
-                        daemon
-                            .get_new_address()
-                            .await
-                            .map(|res| (res.address, res.derivation_index))
-                            .map_err(|e| e.into())
+                        // Skip the addresses which already received funds, for instance if
+                        // they were handed out by another instance of this wallet.
+                        let mut res = daemon.get_new_address().await?;
+                        for _ in 0..MAX_USED_ADDRESSES_SKIPPED {
+                            let index = res.derivation_index.into();
+                            let used = match daemon.list_addresses(Some(index), Some(1)).await {
+                                Ok(list) => list.addresses.iter().any(|a| a.receive_coins > 0),
+                                Err(DaemonError::ClientNotSupported) => false,
+                                Err(e) => return Err(e.into()),
+                            };
+                            if !used {
+                                break;
+                            }
+                            res = daemon.get_new_address().await?;
+                        }
+                        Ok((res.address, res.derivation_index))
                     },
                     Message::ReceiveAddress,
                 )
             }
             Message::View(view::Message::ShowQrCode(i)) => {
-                if let (Some(address), Some(index)) = (
+                if let (Some(address), Some(descriptor)) = (
                     self.addresses.list.get(i),
-                    self.addresses.derivation_indexes.get(i),
+                    self.addresses.descriptors.get(i),
                 ) {
-                    if let Some(modal) = ShowQrCodeModal::new(address, *index) {
+                    let label = self.addresses.labels.get(&address.to_string()).cloned();
+                    if let Some(modal) = ShowQrCodeModal::new(address, label, descriptor) {
                         self.modal = Modal::ShowQrCode(modal);
                     }
                 }
                 Command::none()
             }
+            Message::View(view::Message::ToggleDescriptorQrCode) => {
+                if let Modal::ShowQrCode(ref mut m) = self.modal {
+                    m.show_descriptor = !m.show_descriptor && m.descriptor.is_some();
+                }
+                Command::none()
+            }
+            Message::View(view::Message::QrCodeAmountEdited(edited)) => {
+                if let Modal::ShowQrCode(ref mut m) = self.modal {
+                    m.edit_amount(edited);
+                }
+                Command::none()
+            }
             _ => {
                 if let Modal::VerifyAddress(ref mut m) = self.modal {
-                    m.update(daemon, cache, message)
+                    let cmd = m.update(daemon, cache, message);
+                    if m.is_verified() {
+                        self.addresses.verified.insert(m.address.clone());
+                    }
+                    cmd
                 } else {
                     Command::none()
                 }
@@ -201,6 +275,34 @@ impl State for ReceivePanel {
     }
 }
 
+impl ReceivePanel {
+    // Check which of the displayed addresses already received funds.
+    fn refresh_usage(&self, daemon: Arc<dyn Daemon + Sync + Send>) -> Command<Message> {
+        let indexes = self
+            .addresses
+            .derivation_indexes
+            .iter()
+            .map(|i| u32::from(*i));
+        let (start, end) = match (indexes.clone().min(), indexes.max()) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Command::none(),
+        };
+        Command::perform(
+            async move {
+                match daemon
+                    .list_addresses(Some(start), Some(end - start + 1))
+                    .await
+                {
+                    Ok(list) => Ok(list.addresses),
+                    Err(DaemonError::ClientNotSupported) => Ok(Vec::new()),
+                    Err(e) => Err(e.into()),
+                }
+            },
+            Message::AddressesUsage,
+        )
+    }
+}
+
 impl From<ReceivePanel> for Box<dyn State> {
     fn from(s: ReceivePanel) -> Box<dyn State> {
         Box::new(s)
@@ -209,7 +311,7 @@ impl From<ReceivePanel> for Box<dyn State> {
 
 pub struct VerifyAddressModal {
     warning: Option<Error>,
-    chosen_hws: HashSet<Fingerprint>,
+    statuses: HashMap<Fingerprint, VerificationStatus>,
     hws: HardwareWallets,
     address: Address,
     derivation_index: ChildNumber,
@@ -225,7 +327,7 @@ impl VerifyAddressModal {
     ) -> Self {
         Self {
             warning: None,
-            chosen_hws: HashSet::new(),
+            statuses: HashMap::new(),
             hws: HardwareWallets::new(data_dir, network).with_wallet(wallet),
             address,
             derivation_index,
@@ -234,11 +336,18 @@ impl VerifyAddressModal {
 }
 
 impl VerifyAddressModal {
+    /// Whether the address was confirmed on at least one device.
+    fn is_verified(&self) -> bool {
+        self.statuses
+            .values()
+            .any(|status| *status == VerificationStatus::Confirmed)
+    }
+
     fn view(&self) -> Element<view::Message> {
         view::receive::verify_address_modal(
             self.warning.as_ref(),
             &self.hws.list,
-            &self.chosen_hws,
+            &self.statuses,
             &self.address,
             &self.derivation_index,
         )
@@ -255,65 +364,179 @@ impl VerifyAddressModal {
         message: Message,
     ) -> Command<Message> {
         match message {
-            Message::HardwareWallets(msg) => match self.hws.update(msg) {
-                Ok(cmd) => cmd.map(Message::HardwareWallets),
-                Err(e) => {
-                    self.warning = Some(e.into());
-                    Command::none()
+            Message::HardwareWallets(msg) => {
+                let res = self.hws.update(msg);
+                // A device may have been disconnected while waiting for its confirmation.
+                let connected: Vec<Fingerprint> = self
+                    .hws
+                    .list
+                    .iter()
+                    .filter_map(|hw| hw.fingerprint())
+                    .collect();
+                self.statuses.retain(|fg, _| connected.contains(fg));
+                match res {
+                    Ok(cmd) => cmd.map(Message::HardwareWallets),
+                    Err(e) => {
+                        self.warning = Some(e.into());
+                        Command::none()
+                    }
                 }
-            },
+            }
             Message::Verified(fg, res) => {
-                self.chosen_hws.remove(&fg);
-                if let Err(e) = res {
-                    self.warning = Some(e);
+                // The device was disconnected in the meantime.
+                if !self.statuses.contains_key(&fg) {
+                    return Command::none();
+                }
+                match &res {
+                    Ok(()) => info!(
+                        "Address {} (index {}) verified on device {}",
+                        self.address, self.derivation_index, fg
+                    ),
+                    Err(e) => warn!(
+                        "Address {} (index {}) not verified on device {}: {}",
+                        self.address, self.derivation_index, fg, e
+                    ),
                 }
+                let status = match res {
+                    Ok(()) => VerificationStatus::Confirmed,
+                    Err(Error::HardwareWallet(async_hwi::Error::UnimplementedMethod)) => {
+                        VerificationStatus::NotSupported
+                    }
+                    Err(Error::HardwareWallet(async_hwi::Error::UserRefused)) => {
+                        VerificationStatus::Rejected("Address was rejected".to_string())
+                    }
+                    Err(e) => VerificationStatus::Rejected(e.to_string()),
+                };
+                self.statuses.insert(fg, status);
                 Command::none()
             }
             Message::View(view::Message::SelectHardwareWallet(i)) => {
                 if let Some(HardwareWallet::Supported {
                     device,
                     fingerprint,
+                    kind,
                     ..
                 }) = self.hws.list.get(i)
                 {
+                    if self.statuses.get(fingerprint) == Some(&VerificationStatus::Waiting) {
+                        return Command::none();
+                    }
                     self.warning = None;
-                    self.chosen_hws.insert(*fingerprint);
-                    let fg = *fingerprint;
-                    Command::perform(
-                        verify_address(device.clone(), self.derivation_index),
-                        move |res| Message::Verified(fg, res),
-                    )
+                    self.verify(*fingerprint, kind, device.clone())
                 } else {
                     Command::none()
                 }
             }
+            Message::View(view::Message::VerifyAddressOnDevices) => {
+                self.warning = None;
+                let devices: Vec<_> = self
+                    .hws
+                    .list
+                    .iter()
+                    .filter_map(|hw| match hw {
+                        HardwareWallet::Supported {
+                            device,
+                            fingerprint,
+                            kind,
+                            ..
+                        } if self.statuses.get(fingerprint)
+                            != Some(&VerificationStatus::Waiting) =>
+                        {
+                            Some((*fingerprint, *kind, device.clone()))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                Command::batch(
+                    devices
+                        .into_iter()
+                        .map(|(fg, kind, device)| self.verify(fg, &kind, device)),
+                )
+            }
             _ => Command::none(),
         }
     }
+
+    fn verify(
+        &mut self,
+        fingerprint: Fingerprint,
+        kind: &DeviceKind,
+        device: Arc<dyn async_hwi::HWI + Send + Sync>,
+    ) -> Command<Message> {
+        if !supports_address_display(kind) {
+            self.statuses
+                .insert(fingerprint, VerificationStatus::NotSupported);
+            return Command::none();
+        }
+        self.statuses
+            .insert(fingerprint, VerificationStatus::Waiting);
+        Command::perform(verify_address(device, self.derivation_index), move |res| {
+            Message::Verified(fingerprint, res)
+        })
+    }
 }
 
 pub struct ShowQrCodeModal {
-    qr_code: qr_code::Data,
-    address: String,
+    address: Address,
+    label: Option<String>,
+    amount: form::Value<String>,
+    /// The BIP21 URI of the payment request and its QR code, regenerated as the amount changes.
+    uri: String,
+    qr_code: Qr,
+    /// The single-address descriptor and its QR code, if it fits in one.
+    descriptor: Option<(Qr, String)>,
+    show_descriptor: bool,
 }
 
 impl ShowQrCodeModal {
-    pub fn new(address: &Address, index: ChildNumber) -> Option<Self> {
-        qr_code::Data::new(format!("bitcoin:{}?index={}", address, index))
-            .ok()
-            .map(|qr_code| Self {
-                qr_code,
-                address: address.to_string(),
-            })
+    pub fn new(address: &Address, label: Option<String>, descriptor: &str) -> Option<Self> {
+        let uri = bip21_uri(address, None, label.as_deref());
+        Qr::new(&uri).map(|qr_code| Self {
+            address: address.clone(),
+            label,
+            amount: form::Value::default(),
+            uri,
+            qr_code,
+            descriptor: Qr::new(descriptor).map(|qr_code| (qr_code, descriptor.to_string())),
+            show_descriptor: false,
+        })
     }
 
-    fn view(&self) -> Element<view::Message> {
-        view::receive::qr_modal(&self.qr_code, &self.address)
+    fn edit_amount(&mut self, edited: AmountEdited) {
+        self.amount.value = edited.value;
+        self.amount.valid = true;
+        let uri = bip21_uri(&self.address, edited.amount, self.label.as_deref());
+        if uri != self.uri {
+            // A BIP21 URI of an address always fits in a QR code.
+            if let Some(qr_code) = Qr::new(&uri) {
+                self.qr_code = qr_code;
+                self.uri = uri;
+            }
+        }
+    }
+
+    fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, view::Message> {
+        match (&self.descriptor, self.show_descriptor) {
+            (Some((qr_code, descriptor)), true) => {
+                view::receive::qr_modal(qr_code, descriptor, true, true, None)
+            }
+            _ => view::receive::qr_modal(
+                &self.qr_code,
+                &self.uri,
+                self.descriptor.is_some(),
+                false,
+                Some(view::receive::QrAmount {
+                    value: &self.amount,
+                    unit: cache.bitcoin_unit,
+                    exchange_rate: cache.exchange_rate,
+                }),
+            ),
+        }
     }
 }
 
 async fn verify_address(
-    hw: std::sync::Arc<dyn async_hwi::HWI + Send + Sync>,
+    hw: Arc<dyn async_hwi::HWI + Send + Sync>,
     index: ChildNumber,
 ) -> Result<(), Error> {
     hw.display_address(&async_hwi::AddressScript::Miniscript {
@@ -348,13 +571,53 @@ mod tests {
             Address::from_str("tb1qkldgvljmjpxrjq2ev5qxe8dvhn0dph9q85pwtfkjeanmwdue2akqj4twxj")
                 .unwrap()
                 .assume_checked();
-        let daemon = Daemon::new(vec![(
-            Some(json!({"method": "getnewaddress", "params": Option::<Request>::None})),
-            Ok(json!(GetAddressResult::new(
-                addr.clone(),
-                ChildNumber::from_normal_idx(0).unwrap()
-            ))),
-        )]);
+        let used_addr =
+            Address::from_str("tb1q3yvjw0xp0cc7dv5ng4u8gl9f3pzt6zyy8wf7vqwwanzp8r5mwvjsw4vwwq")
+                .unwrap()
+                .assume_checked();
+        let address_info = |index, address: &Address, receive_coins| AddressInfo {
+            index,
+            receive: address.clone(),
+            change: address.clone(),
+            receive_coins,
+            change_coins: 0,
+        };
+        let daemon = Daemon::new(vec![
+            (
+                Some(json!({"method": "getnewaddress", "params": Option::<Request>::None})),
+                Ok(json!(GetAddressResult::new(
+                    used_addr.clone(),
+                    ChildNumber::from_normal_idx(0).unwrap()
+                ))),
+            ),
+            // The first address already received funds, it's skipped.
+            (
+                Some(json!({"method": "listaddresses", "params": {"start_index": 0, "count": 1}})),
+                Ok(json!(ListAddressesResult {
+                    addresses: vec![address_info(0, &used_addr, 2)]
+                })),
+            ),
+            (
+                Some(json!({"method": "getnewaddress", "params": Option::<Request>::None})),
+                Ok(json!(GetAddressResult::new(
+                    addr.clone(),
+                    ChildNumber::from_normal_idx(1).unwrap()
+                ))),
+            ),
+            (
+                Some(json!({"method": "listaddresses", "params": {"start_index": 1, "count": 1}})),
+                Ok(json!(ListAddressesResult {
+                    addresses: vec![address_info(1, &addr, 0)]
+                })),
+            ),
+            // Once the address received funds, it's marked as used.
+            (
+                Some(json!({"method": "listaddresses", "params": {"start_index": 1, "count": 1}})),
+                Ok(json!(ListAddressesResult {
+                    addresses: vec![address_info(1, &addr, 1)]
+                })),
+            ),
+        ]);
         let wallet = Arc::new(Wallet::new(LianaDescriptor::from_str(DESC).unwrap()));
         let sandbox: Sandbox<ReceivePanel> =
             Sandbox::new(ReceivePanel::new(PathBuf::new(), wallet.clone()));
@@ -362,10 +625,86 @@ mod tests {
         let cache = Cache::default();
         let sandbox = sandbox.load(client.clone(), &cache, wallet).await;
         let sandbox = sandbox
-            .update(client, &cache, Message::View(viewMessage::Next))
+            .update(client.clone(), &cache, Message::View(viewMessage::Next))
             .await;
 
         let panel = sandbox.state();
-        assert_eq!(panel.addresses.list, vec![addr]);
+        assert_eq!(panel.addresses.list, vec![addr.clone()]);
+        assert!(panel.addresses.used.is_empty());
+        assert_eq!(
+            panel.addresses.derivation_paths,
+            vec![vec![
+                (
+                    Fingerprint::from_str("ffd63c8d").unwrap(),
+                    DerivationPath::from_str("m/48'/1'/0'/2'/0/1").unwrap()
+                ),
+                (
+                    Fingerprint::from_str("de6eb005").unwrap(),
+                    DerivationPath::from_str("m/48'/1'/0'/2'/0/1").unwrap()
+                ),
+                (
+                    Fingerprint::from_str("ffd63c8d").unwrap(),
+                    DerivationPath::from_str("m/48'/1'/0'/2'/2/1").unwrap()
+                ),
+            ]]
+        );
+        assert!(panel.addresses.descriptors[0].starts_with(
+            "wsh(or_d(multi(2,[ffd63c8d/48'/1'/0'/2']tpubDExA3EC3iAsPxPhFn4j6gMiVup6V2eH3qKyk69RcTc9TTNRfFYVPad8bJD5FCHVQxyBT4izKsvr7Btd2R4xmQ1hZkvsqGBaeE82J71uTK4N/0/1,"
+        ));
+
+        let sandbox = sandbox
+            .update(
+                client,
+                &cache,
+                Message::UpdatePanelCache(true, Ok(Cache::default())),
+            )
+            .await;
+        assert!(sandbox.state().addresses.used.contains(&addr));
+    }
+
+    #[tokio::test]
+    async fn test_verify_address_disconnected_device() {
+        let addr =
+            Address::from_str("tb1qkldgvljmjpxrjq2ev5qxe8dvhn0dph9q85pwtfkjeanmwdue2akqj4twxj")
+                .unwrap()
+                .assume_checked();
+        let wallet = Arc::new(Wallet::new(LianaDescriptor::from_str(DESC).unwrap()));
+        let mut modal = VerifyAddressModal::new(
+            PathBuf::new(),
+            wallet,
+            Network::Testnet,
+            addr,
+            ChildNumber::from_normal_idx(0).unwrap(),
+        );
+        let client = Arc::new(Lianad::new(Daemon::new(Vec::new()).run()));
+        let cache = Cache::default();
+
+        // A result from a device which was disconnected in the meantime must be ignored.
+        let fg = Fingerprint::from_str("ffd63c8d").unwrap();
+        let _ = modal.update(client.clone(), &cache, Message::Verified(fg, Ok(())));
+        assert!(modal.statuses.is_empty());
+
+        modal.statuses.insert(fg, VerificationStatus::Waiting);
+        let _ = modal.update(
+            client.clone(),
+            &cache,
+            Message::Verified(
+                fg,
+                Err(Error::HardwareWallet(async_hwi::Error::UserRefused)),
+            ),
+        );
+        assert!(matches!(
+            modal.statuses.get(&fg),
+            Some(VerificationStatus::Rejected(_))
+        ));
+
+        assert!(!modal.is_verified());
+
+        let _ = modal.update(client, &cache, Message::Verified(fg, Ok(())));
+        assert_eq!(
+            modal.statuses.get(&fg),
+            Some(&VerificationStatus::Confirmed)
+        );
+        assert!(modal.is_verified());
     }
 }
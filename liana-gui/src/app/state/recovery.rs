@@ -82,6 +82,7 @@ impl State for RecoveryPanel {
                                 path.number_of_coins,
                                 &self.wallet.keys_aliases,
                                 self.selected_path == Some(i),
+                                cache.bitcoin_unit,
                             ))
                         } else {
                             None
@@ -16,7 +16,13 @@ use lianad::config::{
 use liana_ui::{component::form, widget::Element};
 
 use crate::{
-    app::{cache::Cache, error::Error, message::Message, state::settings::State, view},
+    app::{
+        cache::Cache,
+        error::Error,
+        message::Message,
+        state::settings::State,
+        view::{self, settings::ConnectionTest},
+    },
     daemon::Daemon,
     node::{
         bitcoind::{RpcAuthType, RpcAuthValues},
@@ -28,6 +34,11 @@ use crate::{
 pub struct BitcoindSettingsState {
     warning: Option<Error>,
     config_updated: bool,
+    bitcoind_is_internal: bool,
+    /// The configuration being replaced by the one sent to the daemon, if any.
+    replaced_config: Option<Config>,
+    /// The configuration before the last successful update, which can be restored.
+    previous_config: Option<Config>,
 
     bitcoind_settings: Option<BitcoindSettings>,
     electrum_settings: Option<ElectrumSettings>,
@@ -57,6 +68,9 @@ impl BitcoindSettingsState {
         BitcoindSettingsState {
             warning: None,
             config_updated: false,
+            bitcoind_is_internal,
+            replaced_config: None,
+            previous_config: None,
             bitcoind_settings: bitcoind_config.map(|bitcoind_config| {
                 BitcoindSettings::new(
                     configured_node_type,
@@ -94,27 +108,21 @@ impl State for BitcoindSettingsState {
         match message {
             Message::DaemonConfigLoaded(res) => match res {
                 Ok(()) => {
+                    // Display the new settings, keeping the replaced ones to offer to restore
+                    // them.
+                    let previous_config = self.replaced_config.take();
+                    *self = Self::new(
+                        daemon.config().cloned(),
+                        cache,
+                        !daemon.backend().is_embedded(),
+                        self.bitcoind_is_internal,
+                    );
                     self.config_updated = true;
-                    self.warning = None;
-                    if let Some(settings) = &mut self.bitcoind_settings {
-                        settings.edited(true);
-                        return Command::perform(async {}, |_| {
-                            Message::View(view::Message::Settings(
-                                view::SettingsMessage::EditBitcoindSettings,
-                            ))
-                        });
-                    }
-                    if let Some(settings) = &mut self.electrum_settings {
-                        settings.edited(true);
-                        return Command::perform(async {}, |_| {
-                            Message::View(view::Message::Settings(
-                                view::SettingsMessage::EditBitcoindSettings,
-                            ))
-                        });
-                    }
+                    self.previous_config = previous_config;
                 }
                 Err(e) => {
                     self.config_updated = false;
+                    self.replaced_config = None;
                     self.warning = Some(e);
                     if let Some(settings) = &mut self.bitcoind_settings {
                         settings.edited(false);
@@ -136,10 +144,24 @@ impl State for BitcoindSettingsState {
                 self.rescan_settings.past_possible_height = true;
                 self.rescan_settings.processing = false;
             }
+            Message::BitcoinBackendChecked(res) => {
+                let test = match res {
+                    Ok(()) => ConnectionTest::Succeeded,
+                    Err(e) => ConnectionTest::Failed(e.to_string()),
+                };
+                if let Some(settings) = &mut self.bitcoind_settings {
+                    settings.connection_tested(test);
+                } else if let Some(settings) = &mut self.electrum_settings {
+                    settings.connection_tested(test);
+                }
+            }
             Message::View(view::Message::Settings(view::SettingsMessage::BitcoindSettings(
                 msg,
             ))) => {
                 if let Some(settings) = &mut self.bitcoind_settings {
+                    if matches!(msg, view::SettingsEditMessage::Confirm) {
+                        self.replaced_config = daemon.config().cloned();
+                    }
                     return settings.update(daemon, cache, msg);
                 }
             }
@@ -147,9 +169,21 @@ impl State for BitcoindSettingsState {
                 msg,
             ))) => {
                 if let Some(settings) = &mut self.electrum_settings {
+                    if matches!(msg, view::SettingsEditMessage::Confirm) {
+                        self.replaced_config = daemon.config().cloned();
+                    }
                     return settings.update(daemon, cache, msg);
                 }
             }
+            Message::View(view::Message::Settings(
+                view::SettingsMessage::RevertBitcoinBackendSettings,
+            )) => {
+                if let Some(config) = self.previous_config.take() {
+                    return Command::perform(async move { config }, |cfg| {
+                        Message::LoadDaemonConfig(Box::new(cfg))
+                    });
+                }
+            }
             Message::View(view::Message::Settings(view::SettingsMessage::RescanSettings(msg))) => {
                 return self.rescan_settings.update(daemon, cache, msg);
             }
@@ -177,6 +211,7 @@ impl State for BitcoindSettingsState {
         view::settings::bitcoind_settings(
             cache,
             self.warning.as_ref(),
+            self.previous_config.is_some(),
             if self.bitcoind_settings.is_some() || self.electrum_settings.is_some() {
                 let mut setting_panels = Vec::new();
                 if let Some(settings) = self.bitcoind_settings.as_ref() {
@@ -227,6 +262,7 @@ pub struct BitcoindSettings {
     addr: form::Value<String>,
     daemon_is_external: bool,
     bitcoind_is_internal: bool,
+    connection_test: Option<ConnectionTest>,
 }
 
 impl BitcoindSettings {
@@ -283,6 +319,7 @@ impl BitcoindSettings {
                 valid: true,
                 value: addr,
             },
+            connection_test: None,
         }
     }
 }
@@ -295,6 +332,53 @@ impl BitcoindSettings {
         }
     }
 
+    fn connection_tested(&mut self, test: ConnectionTest) {
+        // The fields may have been edited since the test was started.
+        if self.connection_test == Some(ConnectionTest::Pending) {
+            self.connection_test = Some(test);
+        }
+    }
+
+    /// Check the edited fields, marking the invalid ones, and return the daemon configuration
+    /// using them if they are all valid.
+    fn edited_config(&mut self, daemon: &Arc<dyn Daemon + Sync + Send>) -> Option<Config> {
+        let new_addr = SocketAddr::from_str(&self.addr.value);
+        self.addr.valid = new_addr.is_ok();
+        let rpc_auth = match self.selected_auth_type {
+            RpcAuthType::CookieFile => {
+                let path = PathBuf::from(&self.rpc_auth_vals.cookie_path.value);
+                self.rpc_auth_vals.cookie_path.valid = path.is_file();
+                BitcoindRpcAuth::CookieFile(path)
+            }
+            RpcAuthType::UserPass => {
+                self.rpc_auth_vals.user.valid = !self.rpc_auth_vals.user.value.is_empty();
+                self.rpc_auth_vals.password.valid = !self.rpc_auth_vals.password.value.is_empty();
+                BitcoindRpcAuth::UserPass(
+                    self.rpc_auth_vals.user.value.clone(),
+                    self.rpc_auth_vals.password.value.clone(),
+                )
+            }
+        };
+        let auth_is_valid = match rpc_auth {
+            BitcoindRpcAuth::CookieFile(_) => self.rpc_auth_vals.cookie_path.valid,
+            BitcoindRpcAuth::UserPass(..) => {
+                self.rpc_auth_vals.user.valid && self.rpc_auth_vals.password.valid
+            }
+        };
+        match new_addr {
+            Ok(addr) if auth_is_valid => {
+                let mut daemon_config = daemon.config().cloned()?;
+                daemon_config.bitcoin_backend =
+                    Some(lianad::config::BitcoinBackend::Bitcoind(BitcoindConfig {
+                        rpc_auth,
+                        addr,
+                    }));
+                Some(daemon_config)
+            }
+            _ => None,
+        }
+    }
+
     fn update(
         &mut self,
         daemon: Arc<dyn Daemon + Sync + Send>,
@@ -310,51 +394,44 @@ impl BitcoindSettings {
             view::SettingsEditMessage::Cancel => {
                 if !self.processing {
                     self.edit = false;
+                    self.connection_test = None;
                 }
             }
             view::SettingsEditMessage::FieldEdited(field, value) => {
                 if !self.processing {
-                    match field {
-                        "socket_address" => self.addr.value = value,
-                        "cookie_file_path" => self.rpc_auth_vals.cookie_path.value = value,
-                        "user" => self.rpc_auth_vals.user.value = value,
-                        "password" => self.rpc_auth_vals.password.value = value,
-                        _ => {}
-                    }
+                    // The field is checked again when testing or saving the settings.
+                    let field = match field {
+                        "socket_address" => &mut self.addr,
+                        "cookie_file_path" => &mut self.rpc_auth_vals.cookie_path,
+                        "user" => &mut self.rpc_auth_vals.user,
+                        "password" => &mut self.rpc_auth_vals.password,
+                        _ => return Command::none(),
+                    };
+                    field.value = value;
+                    field.valid = true;
+                    self.connection_test = None;
                 }
             }
             view::SettingsEditMessage::BitcoindRpcAuthTypeSelected(auth_type) => {
                 if !self.processing {
                     self.selected_auth_type = auth_type;
+                    self.connection_test = None;
+                }
+            }
+            view::SettingsEditMessage::TestConnection => {
+                if let Some(daemon_config) = self.edited_config(&daemon) {
+                    self.connection_test = Some(ConnectionTest::Pending);
+                    return Command::perform(
+                        async move {
+                            lianad::check_bitcoin_backend(&daemon_config)
+                                .map_err(|e| Error::Config(e.to_string()))
+                        },
+                        Message::BitcoinBackendChecked,
+                    );
                 }
             }
             view::SettingsEditMessage::Confirm => {
-                let new_addr = SocketAddr::from_str(&self.addr.value);
-                self.addr.valid = new_addr.is_ok();
-                let rpc_auth = match self.selected_auth_type {
-                    RpcAuthType::CookieFile => {
-                        let new_path = PathBuf::from_str(&self.rpc_auth_vals.cookie_path.value);
-                        match new_path {
-                            Ok(path) => {
-                                self.rpc_auth_vals.cookie_path.valid = true;
-                                Some(BitcoindRpcAuth::CookieFile(path))
-                            }
-                            Err(_) => None,
-                        }
-                    }
-                    RpcAuthType::UserPass => Some(BitcoindRpcAuth::UserPass(
-                        self.rpc_auth_vals.user.value.clone(),
-                        self.rpc_auth_vals.password.value.clone(),
-                    )),
-                };
-
-                if let (true, Some(rpc_auth)) = (self.addr.valid, rpc_auth) {
-                    let mut daemon_config = daemon.config().cloned().unwrap();
-                    daemon_config.bitcoin_backend =
-                        Some(lianad::config::BitcoinBackend::Bitcoind(BitcoindConfig {
-                            rpc_auth,
-                            addr: new_addr.unwrap(),
-                        }));
+                if let Some(daemon_config) = self.edited_config(&daemon) {
                     self.processing = true;
                     return Command::perform(async move { daemon_config }, |cfg| {
                         Message::LoadDaemonConfig(Box::new(cfg))
@@ -362,6 +439,7 @@ impl BitcoindSettings {
                 }
             }
             view::SettingsEditMessage::Clipboard(text) => return clipboard::write(text),
+            view::SettingsEditMessage::SkipCertificateValidation(_) => {}
         };
         Command::none()
     }
@@ -376,6 +454,7 @@ impl BitcoindSettings {
                 &self.addr,
                 &self.rpc_auth_vals,
                 &self.selected_auth_type,
+                self.connection_test.as_ref(),
                 self.processing,
             )
         } else {
@@ -399,7 +478,10 @@ pub struct ElectrumSettings {
     edit: bool,
     processing: bool,
     addr: form::Value<String>,
+    socks5_proxy: form::Value<String>,
+    skip_certificate_validation: bool,
     daemon_is_external: bool,
+    connection_test: Option<ConnectionTest>,
 }
 
 impl ElectrumSettings {
@@ -413,6 +495,11 @@ impl ElectrumSettings {
         ElectrumSettings {
             configured_node_type,
             daemon_is_external,
+            socks5_proxy: form::Value {
+                valid: true,
+                value: electrum_config.socks5_proxy.clone().unwrap_or_default(),
+            },
+            skip_certificate_validation: electrum_config.skip_certificate_validation,
             electrum_config,
             bitcoin_config,
             edit: false,
@@ -421,6 +508,7 @@ impl ElectrumSettings {
                 valid: true,
                 value: addr,
             },
+            connection_test: None,
         }
     }
 }
@@ -433,6 +521,32 @@ impl ElectrumSettings {
         }
     }
 
+    fn connection_tested(&mut self, test: ConnectionTest) {
+        // The fields may have been edited since the test was started.
+        if self.connection_test == Some(ConnectionTest::Pending) {
+            self.connection_test = Some(test);
+        }
+    }
+
+    /// Check the edited fields, marking the invalid ones, and return the daemon configuration
+    /// using them if they are all valid.
+    fn edited_config(&mut self, daemon: &Arc<dyn Daemon + Sync + Send>) -> Option<Config> {
+        self.addr.valid = crate::node::electrum::is_electrum_address_valid(&self.addr.value);
+        self.socks5_proxy.valid = self.socks5_proxy.value.is_empty()
+            || crate::node::electrum::is_proxy_address_valid(&self.socks5_proxy.value);
+        if !self.addr.valid || !self.socks5_proxy.valid {
+            return None;
+        }
+        let mut daemon_config = daemon.config().cloned()?;
+        daemon_config.bitcoin_backend =
+            Some(lianad::config::BitcoinBackend::Electrum(ElectrumConfig {
+                addr: self.addr.value.clone(),
+                socks5_proxy: Some(self.socks5_proxy.value.clone()).filter(|p| !p.is_empty()),
+                skip_certificate_validation: self.skip_certificate_validation,
+            }));
+        Some(daemon_config)
+    }
+
     fn update(
         &mut self,
         daemon: Arc<dyn Daemon + Sync + Send>,
@@ -448,21 +562,47 @@ impl ElectrumSettings {
             view::SettingsEditMessage::Cancel => {
                 if !self.processing {
                     self.edit = false;
+                    self.connection_test = None;
                 }
             }
             view::SettingsEditMessage::FieldEdited(field, value) => {
-                if !self.processing && field == "address" {
-                    self.addr.valid = crate::node::electrum::is_electrum_address_valid(&value);
-                    self.addr.value = value;
+                if !self.processing {
+                    match field {
+                        "address" => {
+                            self.addr.valid =
+                                crate::node::electrum::is_electrum_address_valid(&value);
+                            self.addr.value = value;
+                        }
+                        "socks5_proxy" => {
+                            self.socks5_proxy.valid = value.is_empty()
+                                || crate::node::electrum::is_proxy_address_valid(&value);
+                            self.socks5_proxy.value = value;
+                        }
+                        _ => return Command::none(),
+                    }
+                    self.connection_test = None;
+                }
+            }
+            view::SettingsEditMessage::SkipCertificateValidation(skip) => {
+                if !self.processing {
+                    self.skip_certificate_validation = skip;
+                    self.connection_test = None;
+                }
+            }
+            view::SettingsEditMessage::TestConnection => {
+                if let Some(daemon_config) = self.edited_config(&daemon) {
+                    self.connection_test = Some(ConnectionTest::Pending);
+                    return Command::perform(
+                        async move {
+                            lianad::check_bitcoin_backend(&daemon_config)
+                                .map_err(|e| Error::Config(e.to_string()))
+                        },
+                        Message::BitcoinBackendChecked,
+                    );
                 }
             }
             view::SettingsEditMessage::Confirm => {
-                if self.addr.valid {
-                    let mut daemon_config = daemon.config().cloned().unwrap();
-                    daemon_config.bitcoin_backend =
-                        Some(lianad::config::BitcoinBackend::Electrum(ElectrumConfig {
-                            addr: self.addr.value.clone(),
-                        }));
+                if let Some(daemon_config) = self.edited_config(&daemon) {
                     self.processing = true;
                     return Command::perform(async move { daemon_config }, |cfg| {
                         Message::LoadDaemonConfig(Box::new(cfg))
@@ -483,6 +623,9 @@ impl ElectrumSettings {
                 self.bitcoin_config.network,
                 cache.blockheight,
                 &self.addr,
+                &self.socks5_proxy,
+                self.skip_certificate_validation,
+                self.connection_test.as_ref(),
                 self.processing,
             )
         } else {
@@ -632,6 +775,7 @@ impl RescanSetting {
             self.invalid_date,
             self.past_possible_height,
             self.future_date,
+            cache.backend_status.is_reachable(),
         )
     }
 }
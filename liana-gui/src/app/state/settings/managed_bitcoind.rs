@@ -0,0 +1,373 @@
+//! Settings of the bitcoind managed by the GUI: its prune target, the location of its data
+//! directory and its version. Changing any of them requires restarting the node, so an operation
+//! goes through several steps reported by `Message::ManagedBitcoind`.
+use std::path::{Path, PathBuf};
+
+use iced::{Command, Subscription};
+use tracing::info;
+
+use liana::miniscript::bitcoin::Network;
+use liana_ui::{component::form, widget::Element};
+
+use crate::{
+    app::{
+        cache::Cache,
+        error::Error,
+        message::{ManagedBitcoindProgress, Message},
+        view::{self, settings::ManagedBitcoindStep},
+    },
+    download,
+    node::bitcoind::{
+        self, install_bitcoind, internal_bitcoind_config_path, internal_bitcoind_current_datadir,
+        internal_bitcoind_datadir, internal_bitcoind_directory,
+        internal_bitcoind_installed_version, move_internal_bitcoind_datadir,
+        remove_bitcoind_version, remove_previous_bitcoind_versions, set_internal_bitcoind_prune,
+        Bitcoind, InternalBitcoindConfig, PRUNE_MIN, VERSION,
+    },
+};
+
+/// An operation requiring to restart the node.
+#[derive(Debug, Clone)]
+enum Operation {
+    SetPrune(u32),
+    MoveDatadir(PathBuf),
+    Upgrade,
+    /// Restoring the previous version after the upgraded one failed the checks.
+    Rollback,
+    /// Restarting the node as is, for instance when it became unreachable.
+    Restart,
+}
+
+pub struct ManagedBitcoindSettingsState {
+    liana_datadir: PathBuf,
+    network: Network,
+    is_taproot: bool,
+    bitcoind: Bitcoind,
+    prune: Option<u32>,
+    prune_form: form::Value<String>,
+    datadir: PathBuf,
+    datadir_form: form::Value<String>,
+    installed_version: Option<&'static str>,
+    /// The operation in progress, if any, and its current step.
+    operation: Option<(Operation, ManagedBitcoindStep)>,
+    warning: Option<Error>,
+}
+
+impl ManagedBitcoindSettingsState {
+    pub fn new(
+        liana_datadir: PathBuf,
+        network: Network,
+        is_taproot: bool,
+        bitcoind: Bitcoind,
+    ) -> Self {
+        let mut state = Self {
+            liana_datadir,
+            network,
+            is_taproot,
+            bitcoind,
+            prune: None,
+            prune_form: form::Value::default(),
+            datadir: PathBuf::new(),
+            datadir_form: form::Value::default(),
+            installed_version: None,
+            operation: None,
+            warning: None,
+        };
+        state.load();
+        state
+    }
+
+    /// Read the current settings of the node.
+    fn load(&mut self) {
+        self.prune = InternalBitcoindConfig::from_file(&internal_bitcoind_config_path(
+            &internal_bitcoind_datadir(&self.liana_datadir),
+        ))
+        .ok()
+        .and_then(|conf| conf.networks.get(&self.network).map(|c| c.prune));
+        self.prune_form = form::Value {
+            value: self.prune.map(|p| p.to_string()).unwrap_or_default(),
+            valid: true,
+        };
+        self.datadir = internal_bitcoind_current_datadir(&self.liana_datadir);
+        self.datadir_form = form::Value::default();
+        self.installed_version = internal_bitcoind_installed_version(&self.liana_datadir);
+    }
+
+    pub fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::View(view::Message::Settings(view::SettingsMessage::ManagedBitcoind(msg))) => {
+                // The settings are not editable until the running operation completes.
+                if self.operation.is_some() {
+                    return Command::none();
+                }
+                match msg {
+                    view::ManagedBitcoindMessage::PruneEdited(value) => {
+                        self.prune_form.valid =
+                            value.parse::<u32>().map_or(false, |p| p >= PRUNE_MIN);
+                        self.prune_form.value = value;
+                    }
+                    view::ManagedBitcoindMessage::SavePrune => {
+                        if let Ok(prune) = self.prune_form.value.parse::<u32>() {
+                            if self.prune_form.valid && Some(prune) != self.prune {
+                                self.warning = None;
+                                return self.stop(Operation::SetPrune(prune));
+                            }
+                        }
+                    }
+                    view::ManagedBitcoindMessage::DatadirEdited(value) => {
+                        self.datadir_form.valid = Path::new(&value).is_absolute();
+                        self.datadir_form.value = value;
+                    }
+                    view::ManagedBitcoindMessage::MoveDatadir => {
+                        if self.datadir_form.valid && !self.datadir_form.value.is_empty() {
+                            self.warning = None;
+                            let datadir = PathBuf::from(&self.datadir_form.value);
+                            return self.stop(Operation::MoveDatadir(datadir));
+                        }
+                    }
+                    view::ManagedBitcoindMessage::Upgrade => {
+                        if self.upgrade_available() {
+                            info!("Downloading bitcoind version {}...", VERSION);
+                            self.warning = None;
+                            self.operation =
+                                Some((Operation::Upgrade, ManagedBitcoindStep::Downloading(0.0)));
+                        }
+                    }
+                }
+                Command::none()
+            }
+            Message::ManagedBitcoind(progress) => self.progress(progress),
+            _ => Command::none(),
+        }
+    }
+
+    /// Restart the node without changing its settings. A node whose process already exited is
+    /// started right away.
+    pub fn restart_node(&mut self) -> Command<Message> {
+        if self.operation.is_some() {
+            return Command::none();
+        }
+        self.warning = None;
+        if self.bitcoind.has_exited() {
+            info!("bitcoind exited, starting it again");
+            self.operation = Some((Operation::Restart, ManagedBitcoindStep::Starting));
+            self.restart()
+        } else {
+            self.stop(Operation::Restart)
+        }
+    }
+
+    fn progress(&mut self, progress: ManagedBitcoindProgress) -> Command<Message> {
+        let operation = match &mut self.operation {
+            Some((operation, step)) => {
+                if let ManagedBitcoindProgress::Downloaded(progress) = progress {
+                    return match progress {
+                        download::Progress::Started => Command::none(),
+                        download::Progress::Advanced(transferred) => {
+                            *step = ManagedBitcoindStep::Downloading(transferred.percentage());
+                            Command::none()
+                        }
+                        download::Progress::Finished(bytes) => {
+                            info!("Download of bitcoind complete, installing it...");
+                            // The new version is installed beside the previous one, which is
+                            // kept until the new one passed the checks.
+                            *step = ManagedBitcoindStep::Installing;
+                            let install_dir = internal_bitcoind_directory(&self.liana_datadir);
+                            Command::perform(
+                                async move {
+                                    install_bitcoind(&install_dir, &bytes)
+                                        .map_err(|e| Error::ManagedBitcoind(e.to_string()))
+                                },
+                                |res| {
+                                    Message::ManagedBitcoind(ManagedBitcoindProgress::Installed(
+                                        res,
+                                    ))
+                                },
+                            )
+                        }
+                        download::Progress::Errored(e) => {
+                            self.fail(Error::ManagedBitcoind(e.to_string()));
+                            Command::none()
+                        }
+                    };
+                }
+                operation.clone()
+            }
+            None => return Command::none(),
+        };
+        match progress {
+            ManagedBitcoindProgress::Downloaded(_) => Command::none(),
+            ManagedBitcoindProgress::Installed(res) => match res {
+                Ok(()) => self.stop(operation),
+                Err(e) => {
+                    self.fail(e);
+                    Command::none()
+                }
+            },
+            ManagedBitcoindProgress::Stopped(res) => {
+                if let Err(e) = res {
+                    self.fail(e);
+                    return Command::none();
+                }
+                let liana_datadir = self.liana_datadir.clone();
+                let network = self.network;
+                match operation {
+                    Operation::SetPrune(prune) => self.apply(async move {
+                        set_internal_bitcoind_prune(&liana_datadir, network, prune)
+                    }),
+                    Operation::MoveDatadir(datadir) => self.apply(async move {
+                        move_internal_bitcoind_datadir(&liana_datadir, &datadir)
+                    }),
+                    Operation::Upgrade | Operation::Restart => self.restart(),
+                    Operation::Rollback => {
+                        remove_bitcoind_version(&self.liana_datadir, VERSION);
+                        self.restart()
+                    }
+                }
+            }
+            ManagedBitcoindProgress::Applied(res) => {
+                // The node must be restarted anyway, with the previous settings on error.
+                if let Err(e) = res {
+                    self.warning = Some(e);
+                }
+                self.restart()
+            }
+            ManagedBitcoindProgress::Restarted(res) => match res {
+                Ok(bitcoind) => {
+                    self.bitcoind = bitcoind;
+                    self.load();
+                    if let Operation::Upgrade = operation {
+                        self.set_step(ManagedBitcoindStep::Checking);
+                        let bitcoind = self.bitcoind.clone();
+                        let network = self.network;
+                        let is_taproot = self.is_taproot;
+                        Command::perform(
+                            async move {
+                                bitcoind
+                                    .sanity_check(network, is_taproot)
+                                    .map_err(|e| Error::ManagedBitcoind(e.to_string()))
+                            },
+                            |res| Message::ManagedBitcoind(ManagedBitcoindProgress::Checked(res)),
+                        )
+                    } else {
+                        self.operation = None;
+                        Command::none()
+                    }
+                }
+                Err(e) => {
+                    self.fail(e);
+                    Command::none()
+                }
+            },
+            ManagedBitcoindProgress::Checked(res) => match res {
+                Ok(()) => {
+                    info!("bitcoind was upgraded to version {}", VERSION);
+                    remove_previous_bitcoind_versions(&self.liana_datadir);
+                    self.operation = None;
+                    self.load();
+                    Command::none()
+                }
+                Err(e) => {
+                    self.warning = Some(Error::ManagedBitcoind(format!(
+                        "bitcoind {} failed the checks, the previous version is restored: {}",
+                        VERSION, e
+                    )));
+                    self.stop(Operation::Rollback)
+                }
+            },
+        }
+    }
+
+    fn set_step(&mut self, step: ManagedBitcoindStep) {
+        if let Some((_, s)) = &mut self.operation {
+            *s = step;
+        }
+    }
+
+    fn fail(&mut self, error: Error) {
+        self.operation = None;
+        self.warning = Some(error);
+        self.load();
+    }
+
+    fn stop(&mut self, operation: Operation) -> Command<Message> {
+        info!("Stopping bitcoind to apply the new settings");
+        self.operation = Some((operation, ManagedBitcoindStep::Stopping));
+        let bitcoind = self.bitcoind.clone();
+        Command::perform(
+            async move {
+                bitcoind
+                    .stop_and_wait()
+                    .map_err(|e| Error::ManagedBitcoind(e.to_string()))
+            },
+            |res| Message::ManagedBitcoind(ManagedBitcoindProgress::Stopped(res)),
+        )
+    }
+
+    fn apply<F, E>(&mut self, apply: F) -> Command<Message>
+    where
+        F: std::future::Future<Output = Result<(), E>> + Send + 'static,
+        E: std::fmt::Display,
+    {
+        self.set_step(ManagedBitcoindStep::Applying);
+        Command::perform(
+            async move {
+                apply
+                    .await
+                    .map_err(|e| Error::ManagedBitcoind(e.to_string()))
+            },
+            |res| Message::ManagedBitcoind(ManagedBitcoindProgress::Applied(res)),
+        )
+    }
+
+    fn restart(&mut self) -> Command<Message> {
+        self.set_step(ManagedBitcoindStep::Starting);
+        let network = self.network;
+        let config = self.bitcoind.config.clone();
+        let liana_datadir = self.liana_datadir.clone();
+        Command::perform(
+            async move {
+                Bitcoind::start(&network, config, &liana_datadir)
+                    .map_err(|e| Error::ManagedBitcoind(e.to_string()))
+            },
+            |res| Message::ManagedBitcoind(ManagedBitcoindProgress::Restarted(res)),
+        )
+    }
+
+    /// Whether a more recent version than the installed one is pinned. The previous version must
+    /// be installed to be restored if the new one fails the checks.
+    fn upgrade_available(&self) -> bool {
+        self.installed_version
+            .map_or(false, |installed| installed != VERSION)
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        if let Some((Operation::Upgrade, ManagedBitcoindStep::Downloading(_))) = &self.operation {
+            download::file(
+                "managed-bitcoind-upgrade",
+                bitcoind::download_url(),
+                bitcoind::partial_download_path(&self.liana_datadir),
+                bitcoind::download_hash(),
+            )
+            .map(|(_, progress)| {
+                Message::ManagedBitcoind(ManagedBitcoindProgress::Downloaded(progress))
+            })
+        } else {
+            Subscription::none()
+        }
+    }
+
+    pub fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, view::Message> {
+        view::settings::managed_bitcoind_section(
+            cache,
+            self.warning.as_ref(),
+            self.prune,
+            &self.prune_form,
+            &self.datadir,
+            &self.datadir_form,
+            self.installed_version,
+            self.upgrade_available(),
+            self.operation.as_ref().map(|(_, step)| *step),
+        )
+    }
+}
@@ -1,4 +1,6 @@
 mod bitcoind;
+mod managed_bitcoind;
+mod profile;
 mod wallet;
 
 use std::convert::From;
@@ -7,9 +9,13 @@ use std::sync::Arc;
 
 use iced::Command;
 
+use liana::miniscript::bitcoin::Network;
 use liana_ui::{component::form, widget::Element};
 
 use bitcoind::BitcoindSettingsState;
+use managed_bitcoind::ManagedBitcoindSettingsState;
+use profile::ProfileSettingsState;
+pub use wallet::register_wallet;
 use wallet::WalletSettingsState;
 
 use crate::{
@@ -22,6 +28,7 @@ use crate::{
         wallet::Wallet,
     },
     daemon::{Daemon, DaemonBackend},
+    node::bitcoind::Bitcoind,
 };
 
 pub struct SettingsState {
@@ -30,23 +37,50 @@ pub struct SettingsState {
     setting: Option<Box<dyn State>>,
     daemon_backend: DaemonBackend,
     internal_bitcoind: bool,
+    /// Kept out of `setting` for an operation on the node to keep going while the user
+    /// navigates elsewhere.
+    managed_bitcoind: Option<ManagedBitcoindSettingsState>,
+    managed_bitcoind_opened: bool,
 }
 
 impl SettingsState {
     pub fn new(
         data_dir: PathBuf,
         wallet: Arc<Wallet>,
+        network: Network,
         daemon_backend: DaemonBackend,
-        internal_bitcoind: bool,
+        internal_bitcoind: Option<Bitcoind>,
     ) -> Self {
+        let is_taproot = wallet.main_descriptor.is_taproot();
         Self {
+            managed_bitcoind: internal_bitcoind.clone().map(|bitcoind| {
+                ManagedBitcoindSettingsState::new(data_dir.clone(), network, is_taproot, bitcoind)
+            }),
+            managed_bitcoind_opened: false,
             data_dir,
             wallet,
             setting: None,
             daemon_backend,
-            internal_bitcoind,
+            internal_bitcoind: internal_bitcoind.is_some(),
         }
     }
+
+    /// The subscription of the managed bitcoind operation in progress, if any, which must be
+    /// kept whatever the panel displayed.
+    pub fn managed_bitcoind_subscription(&self) -> iced::Subscription<Message> {
+        self.managed_bitcoind
+            .as_ref()
+            .map(|s| s.subscription())
+            .unwrap_or_else(iced::Subscription::none)
+    }
+
+    /// Restart the managed bitcoind, if any, without changing its settings.
+    pub fn restart_managed_bitcoind(&mut self) -> Command<Message> {
+        self.managed_bitcoind
+            .as_mut()
+            .map(|s| s.restart_node())
+            .unwrap_or_else(Command::none)
+    }
 }
 
 impl State for SettingsState {
@@ -57,6 +91,19 @@ impl State for SettingsState {
         message: Message,
     ) -> Command<Message> {
         match &message {
+            Message::View(view::Message::Settings(
+                view::SettingsMessage::ManagedBitcoindSection,
+            )) => {
+                self.setting = None;
+                self.managed_bitcoind_opened = self.managed_bitcoind.is_some();
+                Command::none()
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::ManagedBitcoind(_)))
+            | Message::ManagedBitcoind(_) => self
+                .managed_bitcoind
+                .as_mut()
+                .map(|s| s.update(message))
+                .unwrap_or_else(Command::none),
             Message::View(view::Message::Settings(view::SettingsMessage::EditBitcoindSettings)) => {
                 self.setting = Some(
                     BitcoindSettingsState::new(
@@ -87,6 +134,12 @@ impl State for SettingsState {
                     .map(|s| s.reload(daemon, wallet))
                     .unwrap_or_else(Command::none)
             }
+            Message::View(view::Message::Settings(view::SettingsMessage::ProfileSection)) => {
+                self.setting = Some(
+                    ProfileSettingsState::new(self.data_dir.clone(), self.wallet.clone()).into(),
+                );
+                Command::none()
+            }
             Message::View(view::Message::Settings(view::SettingsMessage::EditWalletSettings)) => {
                 self.setting = Some(
                     WalletSettingsState::new(self.data_dir.clone(), self.wallet.clone()).into(),
@@ -123,8 +176,18 @@ impl State for SettingsState {
     fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, view::Message> {
         if let Some(setting) = &self.setting {
             setting.view(cache)
+        } else if let Some(managed_bitcoind) = self
+            .managed_bitcoind
+            .as_ref()
+            .filter(|_| self.managed_bitcoind_opened)
+        {
+            managed_bitcoind.view(cache)
         } else {
-            view::settings::list(cache, self.daemon_backend == DaemonBackend::RemoteBackend)
+            view::settings::list(
+                cache,
+                self.daemon_backend == DaemonBackend::RemoteBackend,
+                self.managed_bitcoind.is_some(),
+            )
         }
     }
 
@@ -134,6 +197,7 @@ impl State for SettingsState {
         wallet: Arc<Wallet>,
     ) -> Command<Message> {
         self.setting = None;
+        self.managed_bitcoind_opened = false;
         self.wallet = wallet;
         Command::none()
     }
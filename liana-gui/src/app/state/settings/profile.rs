@@ -0,0 +1,191 @@
+use std::convert::From;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use iced::Command;
+
+use liana::miniscript::bitcoin::Network;
+use liana_ui::widget::Element;
+
+use super::wallet::update_keys_aliases;
+
+use crate::{
+    app::{
+        cache::Cache, config, error::Error, message::Message, settings, state::State, view,
+        wallet::Wallet,
+    },
+    daemon::Daemon,
+    profile::Profile,
+};
+
+pub struct ProfileSettingsState {
+    data_dir: PathBuf,
+    wallet: Arc<Wallet>,
+    warning: Option<Error>,
+    processing: bool,
+    exported: Option<PathBuf>,
+    imported: bool,
+}
+
+impl ProfileSettingsState {
+    pub fn new(data_dir: PathBuf, wallet: Arc<Wallet>) -> Self {
+        Self {
+            data_dir,
+            wallet,
+            warning: None,
+            processing: false,
+            exported: None,
+            imported: false,
+        }
+    }
+}
+
+impl State for ProfileSettingsState {
+    fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, view::Message> {
+        view::settings::profile_section(
+            cache,
+            self.warning.as_ref(),
+            self.processing,
+            self.exported.as_ref(),
+            self.imported,
+        )
+    }
+
+    fn update(
+        &mut self,
+        daemon: Arc<dyn Daemon + Sync + Send>,
+        cache: &Cache,
+        message: Message,
+    ) -> Command<Message> {
+        match message {
+            Message::View(view::Message::Settings(view::SettingsMessage::ExportProfile)) => {
+                if self.processing {
+                    return Command::none();
+                }
+                self.processing = true;
+                self.warning = None;
+                self.exported = None;
+                self.imported = false;
+                let gui_config_path = gui_config_path(&self.data_dir, cache.network);
+                let daemon_config = daemon.config().cloned();
+                let keys = self
+                    .wallet
+                    .keys_aliases
+                    .iter()
+                    .map(|(fg, name)| settings::KeySetting {
+                        master_fingerprint: *fg,
+                        name: name.clone(),
+                    })
+                    .collect();
+                Command::perform(
+                    async move {
+                        let gui_config = config::Config::from_file(&gui_config_path)?;
+                        let profile = Profile::new(&gui_config, daemon_config.as_ref(), keys);
+                        if let Some(path) = get_path(true).await {
+                            profile.to_file(&path)?;
+                            Ok(Some(path))
+                        } else {
+                            Ok(None)
+                        }
+                    },
+                    Message::ProfileExported,
+                )
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::ImportProfile)) => {
+                if self.processing {
+                    return Command::none();
+                }
+                self.processing = true;
+                self.warning = None;
+                self.exported = None;
+                self.imported = false;
+                let data_dir = self.data_dir.clone();
+                let network = cache.network;
+                let wallet = self.wallet.clone();
+                Command::perform(
+                    async move {
+                        let path = match get_path(false).await {
+                            Some(path) => path,
+                            None => return Ok(None),
+                        };
+                        let profile = Profile::from_file(&path)?;
+
+                        let gui_config_path = gui_config_path(&data_dir, network);
+                        let mut gui_config = config::Config::from_file(&gui_config_path)?;
+                        profile.apply_to_gui_config(&mut gui_config);
+                        gui_config.to_file(&gui_config_path)?;
+
+                        let keys_aliases: Vec<_> = wallet
+                            .keys_aliases
+                            .iter()
+                            .map(|(fg, name)| (*fg, name.clone()))
+                            .collect();
+                        let keys_aliases =
+                            profile.apply_to_keys(&keys_aliases, &wallet.descriptor_keys());
+                        update_keys_aliases(data_dir, network, wallet, keys_aliases, daemon)
+                            .await
+                            .map(Some)
+                    },
+                    Message::ProfileImported,
+                )
+            }
+            Message::ProfileExported(res) => {
+                self.processing = false;
+                match res {
+                    Ok(path) => self.exported = path,
+                    Err(e) => self.warning = Some(e),
+                }
+                Command::none()
+            }
+            Message::ProfileImported(res) => {
+                self.processing = false;
+                match res {
+                    Ok(Some(wallet)) => {
+                        self.imported = true;
+                        // Let the application know about the new keys aliases.
+                        Command::perform(async move { Ok(wallet) }, Message::WalletUpdated)
+                    }
+                    Ok(None) => Command::none(),
+                    Err(e) => {
+                        self.warning = Some(e);
+                        Command::none()
+                    }
+                }
+            }
+            Message::WalletUpdated(Ok(wallet)) => {
+                self.wallet = wallet;
+                Command::none()
+            }
+            _ => Command::none(),
+        }
+    }
+}
+
+impl From<ProfileSettingsState> for Box<dyn State> {
+    fn from(s: ProfileSettingsState) -> Box<dyn State> {
+        Box::new(s)
+    }
+}
+
+fn gui_config_path(data_dir: &std::path::Path, network: Network) -> PathBuf {
+    data_dir
+        .join(network.to_string())
+        .join(config::DEFAULT_FILE_NAME)
+}
+
+async fn get_path(save: bool) -> Option<PathBuf> {
+    let dialog = rfd::AsyncFileDialog::new().add_filter("JSON", &["json"]);
+    let file = if save {
+        dialog
+            .set_title("Choose a location to export the profile...")
+            .set_file_name("liana-profile.json")
+            .save_file()
+            .await
+    } else {
+        dialog
+            .set_title("Choose a profile to import...")
+            .pick_file()
+            .await
+    };
+    file.map(|fh| fh.path().to_path_buf())
+}
@@ -20,9 +20,13 @@ use crate::{
         cache::Cache, error::Error, message::Message, settings, state::State, view, wallet::Wallet,
     },
     daemon::{Daemon, DaemonBackend},
+    export,
     hw::{HardwareWallet, HardwareWalletConfig, HardwareWallets},
+    i18n,
 };
 
+const DEFAULT_AUTO_LOCK_MINUTES: u32 = 5;
+
 pub struct WalletSettingsState {
     data_dir: PathBuf,
     warning: Option<Error>,
@@ -32,6 +36,10 @@ pub struct WalletSettingsState {
     modal: Option<RegisterWalletModal>,
     processing: bool,
     updated: bool,
+    diagram_exported: Option<PathBuf>,
+    /// Minutes of inactivity before the auto-lock.
+    auto_lock_timeout: form::Value<String>,
+    auto_lock_pin: form::Value<String>,
 }
 
 impl WalletSettingsState {
@@ -45,6 +53,21 @@ impl WalletSettingsState {
             modal: None,
             processing: false,
             updated: false,
+            diagram_exported: None,
+            auto_lock_timeout: Self::auto_lock_timeout(&wallet),
+            auto_lock_pin: form::Value::default(),
+        }
+    }
+
+    fn auto_lock_timeout(wallet: &Wallet) -> form::Value<String> {
+        form::Value {
+            value: wallet
+                .auto_lock
+                .as_ref()
+                .map(|auto_lock| auto_lock.timeout_minutes)
+                .unwrap_or(DEFAULT_AUTO_LOCK_MINUTES)
+                .to_string(),
+            valid: true,
         }
     }
 
@@ -82,8 +105,18 @@ impl State for WalletSettingsState {
             self.warning.as_ref(),
             &self.descriptor,
             &self.keys_aliases,
+            &self.wallet.notifications,
+            self.wallet
+                .signers
+                .iter()
+                .any(|signer| signer.is_protected())
+                .then_some(self.wallet.hot_signer_unlock),
+            self.wallet.auto_lock.as_ref(),
+            &self.auto_lock_timeout,
+            &self.auto_lock_pin,
             self.processing,
             self.updated,
+            self.diagram_exported.as_ref(),
         );
         if let Some(m) = &self.modal {
             modal::Modal::new(content, m.view())
@@ -117,6 +150,8 @@ impl State for WalletSettingsState {
                     match res {
                         Ok(wallet) => {
                             self.keys_aliases = Self::keys_aliases(&wallet);
+                            self.auto_lock_timeout = Self::auto_lock_timeout(&wallet);
+                            self.auto_lock_pin = form::Value::default();
                             self.wallet = wallet;
                             self.updated = true;
                         }
@@ -155,10 +190,156 @@ impl State for WalletSettingsState {
                     Message::WalletUpdated,
                 )
             }
+            Message::View(view::Message::Settings(view::SettingsMessage::NotificationToggled(
+                kind,
+                enabled,
+            ))) => {
+                let mut notifications = self.wallet.notifications;
+                notifications.set(kind, enabled);
+                let wallet = self.wallet.clone();
+                let data_dir = self.data_dir.clone();
+                let network = cache.network;
+                Command::perform(
+                    async move {
+                        wallet
+                            .update_notifications(&data_dir, network, notifications)
+                            .map(Arc::new)
+                            .map_err(|e| e.into())
+                    },
+                    Message::WalletUpdated,
+                )
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::BitcoinUnitSelected(
+                unit,
+            ))) => {
+                let wallet = self.wallet.clone();
+                let data_dir = self.data_dir.clone();
+                let network = cache.network;
+                Command::perform(
+                    async move {
+                        wallet
+                            .update_bitcoin_unit(&data_dir, network, unit)
+                            .map(Arc::new)
+                            .map_err(|e| e.into())
+                    },
+                    Message::WalletUpdated,
+                )
+            }
+            Message::View(view::Message::Settings(
+                view::SettingsMessage::HotSignerUnlockSelected(unlock),
+            )) => {
+                let wallet = self.wallet.clone();
+                let data_dir = self.data_dir.clone();
+                let network = cache.network;
+                Command::perform(
+                    async move {
+                        wallet
+                            .update_hot_signer_unlock(&data_dir, network, unlock)
+                            .map(Arc::new)
+                            .map_err(|e| e.into())
+                    },
+                    Message::WalletUpdated,
+                )
+            }
+            Message::View(view::Message::Settings(
+                view::SettingsMessage::AutoLockTimeoutEdited(value),
+            )) => {
+                self.auto_lock_timeout.valid = value
+                    .parse::<u32>()
+                    .map_or(false, |minutes| (1..=24 * 60).contains(&minutes));
+                self.auto_lock_timeout.value = value;
+                Command::none()
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::AutoLockPinEdited(
+                value,
+            ))) => {
+                self.auto_lock_pin.valid = value.is_empty() || settings::is_valid_pin(&value);
+                self.auto_lock_pin.value = value;
+                Command::none()
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::EnableAutoLock)) => {
+                let timeout = match self.auto_lock_timeout.value.parse::<u32>() {
+                    Ok(minutes) if self.auto_lock_timeout.valid => minutes,
+                    _ => return Command::none(),
+                };
+                if !settings::is_valid_pin(&self.auto_lock_pin.value) {
+                    return Command::none();
+                }
+                self.processing = true;
+                self.updated = false;
+                let pin = self.auto_lock_pin.value.clone();
+                let wallet = self.wallet.clone();
+                let data_dir = self.data_dir.clone();
+                let network = cache.network;
+                Command::perform(
+                    async move {
+                        let auto_lock = settings::AutoLockSettings::new(timeout, &pin)
+                            .map_err(|e| Error::Unexpected(e.to_string()))?;
+                        wallet
+                            .update_auto_lock(&data_dir, network, Some(auto_lock))
+                            .map(Arc::new)
+                            .map_err(|e| e.into())
+                    },
+                    Message::WalletUpdated,
+                )
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::DisableAutoLock)) => {
+                let wallet = self.wallet.clone();
+                let data_dir = self.data_dir.clone();
+                let network = cache.network;
+                Command::perform(
+                    async move {
+                        wallet
+                            .update_auto_lock(&data_dir, network, None)
+                            .map(Arc::new)
+                            .map_err(|e| e.into())
+                    },
+                    Message::WalletUpdated,
+                )
+            }
+            Message::View(view::Message::Settings(view::SettingsMessage::LanguageSelected(
+                language,
+            ))) => {
+                i18n::set_language(language);
+                if let Err(e) = i18n::save_language(&self.data_dir, language) {
+                    self.warning = Some(Error::Unexpected(format!(
+                        "Failed to save the language: {}",
+                        e
+                    )));
+                }
+                Command::none()
+            }
             Message::View(view::Message::Close) => {
                 self.modal = None;
                 Command::none()
             }
+            Message::View(view::Message::Settings(view::SettingsMessage::ExportPolicyDiagram)) => {
+                self.diagram_exported = None;
+                let diagram = view::policy::diagram(&self.descriptor.policy(), |fg| {
+                    self.keys_aliases
+                        .iter()
+                        .find(|(k, _)| k == fg)
+                        .map(|(_, alias)| alias.value.clone())
+                });
+                Command::perform(
+                    async move {
+                        export::export_policy_diagram(diagram).await.map_err(|e| {
+                            Error::Unexpected(format!(
+                                "Failed to export the policy diagram: {:?}",
+                                e
+                            ))
+                        })
+                    },
+                    Message::PolicyDiagramExported,
+                )
+            }
+            Message::PolicyDiagramExported(res) => {
+                match res {
+                    Ok(path) => self.diagram_exported = path,
+                    Err(e) => self.warning = Some(e),
+                }
+                Command::none()
+            }
             Message::View(view::Message::Settings(view::SettingsMessage::RegisterWallet)) => {
                 self.modal = Some(RegisterWalletModal::new(
                     self.data_dir.clone(),
@@ -232,6 +413,8 @@ impl RegisterWalletModal {
             self.processing,
             self.chosen_hw,
             &self.registered,
+            &self.wallet.name,
+            &self.wallet.main_descriptor,
         )
     }
 
@@ -306,7 +489,7 @@ impl RegisterWalletModal {
     }
 }
 
-async fn register_wallet(
+pub async fn register_wallet(
     data_dir: PathBuf,
     network: Network,
     hw: std::sync::Arc<dyn async_hwi::HWI + Send + Sync>,
@@ -369,7 +552,7 @@ async fn register_wallet(
     Ok(wallet)
 }
 
-async fn update_keys_aliases(
+pub(super) async fn update_keys_aliases(
     data_dir: PathBuf,
     network: Network,
     wallet: Arc<Wallet>,
@@ -1,9 +1,11 @@
 mod step;
 
 use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use iced::Command;
+use tracing::warn;
 
 use liana::miniscript::bitcoin::{Network, OutPoint};
 use liana_ui::widget::Element;
@@ -11,7 +13,10 @@ use lianad::commands::CoinStatus;
 
 use super::{redirect, State};
 use crate::{
-    app::{cache::Cache, error::Error, menu::Menu, message::Message, view, wallet::Wallet},
+    app::{
+        cache::Cache, draft::SpendDraft, error::Error, menu::Menu, message::Message, view,
+        wallet::Wallet,
+    },
     daemon::{
         model::{Coin, LabelItem},
         Daemon,
@@ -20,20 +25,38 @@ use crate::{
 
 pub struct CreateSpendPanel {
     draft: step::TransactionDraft,
+    /// Checksum of the wallet descriptor, the spend being composed is saved on disk under.
+    wallet_checksum: String,
     current: usize,
     steps: Vec<Box<dyn step::Step>>,
 }
 
 impl CreateSpendPanel {
-    pub fn new(wallet: Arc<Wallet>, coins: &[Coin], blockheight: u32, network: Network) -> Self {
+    /// Start a new spend, offering to resume the one saved on disk for the wallet if any.
+    pub fn new(
+        wallet: Arc<Wallet>,
+        coins: &[Coin],
+        blockheight: u32,
+        network: Network,
+        datadir_path: PathBuf,
+    ) -> Self {
         let descriptor = wallet.main_descriptor.clone();
         let timelock = descriptor.first_timelock_value();
+        let wallet_checksum = wallet.descriptor_checksum();
+        let saved_draft = SpendDraft::from_file(datadir_path, network, &wallet_checksum)
+            .unwrap_or_else(|e| {
+                warn!("Failed to read the spend draft: {}", e);
+                None
+            });
         Self {
             draft: step::TransactionDraft::new(network),
+            wallet_checksum,
             current: 0,
             steps: vec![
                 Box::new(
                     step::DefineSpend::new(network, descriptor, coins, timelock)
+                        .with_keys_aliases(wallet.keys_aliases.clone())
+                        .with_saved_draft(saved_draft)
                         .with_coins_sorted(blockheight),
                 ),
                 Box::new(step::SaveSpend::new(wallet)),
@@ -41,7 +64,8 @@ impl CreateSpendPanel {
         }
     }
 
-    pub fn new_self_send(
+    /// Spend the given coins, starting with the coin control shown.
+    pub fn new_preselected(
         wallet: Arc<Wallet>,
         coins: &[Coin],
         blockheight: u32,
@@ -52,13 +76,45 @@ impl CreateSpendPanel {
         let timelock = descriptor.first_timelock_value();
         Self {
             draft: step::TransactionDraft::new(network),
+            wallet_checksum: wallet.descriptor_checksum(),
             current: 0,
             steps: vec![
                 Box::new(
                     step::DefineSpend::new(network, descriptor, coins, timelock)
+                        .with_keys_aliases(wallet.keys_aliases.clone())
                         .with_preselected_coins(preselected_coins)
-                        .with_coins_sorted(blockheight)
-                        .self_send(),
+                        .with_coins_sorted(blockheight),
+                ),
+                Box::new(step::SaveSpend::new(wallet)),
+            ],
+        }
+    }
+
+    /// Refresh coins before their recovery path becomes available, starting with the given ones
+    /// selected.
+    pub fn new_refresh(
+        wallet: Arc<Wallet>,
+        coins: &[Coin],
+        blockheight: u32,
+        preselected_coins: &[OutPoint],
+        network: Network,
+    ) -> Self {
+        let descriptor = wallet.main_descriptor.clone();
+        let timelock = descriptor.first_timelock_value();
+        Self {
+            draft: step::TransactionDraft::new(network),
+            wallet_checksum: wallet.descriptor_checksum(),
+            current: 0,
+            steps: vec![
+                Box::new(
+                    step::RefreshCoins::new(
+                        descriptor,
+                        coins,
+                        timelock,
+                        wallet.refresh_max_fee_percent,
+                    )
+                    .with_preselected_coins(preselected_coins)
+                    .with_coins_sorted(blockheight),
                 ),
                 Box::new(step::SaveSpend::new(wallet)),
             ],
@@ -68,6 +124,29 @@ impl CreateSpendPanel {
     pub fn is_first_step(&self) -> bool {
         self.current == 0
     }
+
+    /// Keep the spend being composed on disk, or remove it if there is nothing to resume.
+    fn save_spend_draft(&self, cache: &Cache, draft: SpendDraft) {
+        if draft.is_empty() {
+            self.delete_spend_draft(cache);
+        } else if let Err(e) = draft.to_file(
+            cache.datadir_path.clone(),
+            cache.network,
+            &self.wallet_checksum,
+        ) {
+            warn!("Failed to save the spend draft: {}", e);
+        }
+    }
+
+    fn delete_spend_draft(&self, cache: &Cache) {
+        if let Err(e) = SpendDraft::delete(
+            cache.datadir_path.clone(),
+            cache.network,
+            &self.wallet_checksum,
+        ) {
+            warn!("Failed to delete the spend draft: {}", e);
+        }
+    }
 }
 
 impl State for CreateSpendPanel {
@@ -110,8 +189,19 @@ impl State for CreateSpendPanel {
             self.current -= 1;
         }
 
+        let edited = matches!(message, Message::View(view::Message::CreateSpend(_)));
+        let psbt_created = matches!(message, Message::Psbt(Ok(_)));
         if let Some(step) = self.steps.get_mut(self.current) {
-            return step.update(daemon, cache, message);
+            let command = step.update(daemon, cache, message);
+            if let Some(draft) = self.steps.get(self.current).and_then(|s| s.spend_draft()) {
+                if psbt_created {
+                    // The spend is not a draft anymore.
+                    self.delete_spend_draft(cache);
+                } else if edited {
+                    self.save_spend_draft(cache, draft);
+                }
+            }
+            return command;
         }
 
         Command::none()
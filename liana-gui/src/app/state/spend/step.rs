@@ -1,27 +1,45 @@
 use std::{
     cmp::Ordering,
     collections::{HashMap, HashSet},
-    iter::FromIterator,
     str::FromStr,
     sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use iced::{Command, Subscription};
 use liana::{
     descriptors::LianaDescriptor,
     miniscript::bitcoin::{
-        address, psbt::Psbt, secp256k1, Address, Amount, Denomination, Network, OutPoint,
+        address, bip32::Fingerprint, psbt::Psbt, secp256k1, Address, Amount, Network, OutPoint,
     },
     spend::{SpendCreationError, MAX_FEERATE},
 };
-use lianad::commands::ListCoinsEntry;
 
-use liana_ui::{component::form, widget::Element};
+use liana_ui::{
+    component::{
+        amount::{amount_as_input, parse_amount, BitcoinDisplayUnit},
+        amount_input::ExchangeRate,
+        form,
+    },
+    widget::Element,
+};
 
 use crate::{
-    app::{cache::Cache, error::Error, message::Message, state::psbt, view, wallet::Wallet},
+    app::{
+        cache::Cache,
+        draft::{DraftRecipient, SpendDraft},
+        error::Error,
+        menu::Menu,
+        message::Message,
+        state::{psbt, redirect},
+        view,
+        wallet::Wallet,
+    },
     daemon::{
-        model::{remaining_sequence, Coin, CreateSpendResult, SpendTx},
+        model::{
+            mixed_tags, remaining_sequence, Coin, CreateSpendResult, FeeratePreset, SpendTx,
+            FALLBACK_FEERATE,
+        },
         Daemon,
     },
 };
@@ -29,6 +47,40 @@ use crate::{
 /// See: https://github.com/wizardsardine/liana/blob/master/src/commands/mod.rs#L32
 const DUST_OUTPUT_SATS: u64 = 5_000;
 
+/// The maximum weight of a transaction relayed by the nodes with the default policy.
+const MAX_STANDARD_TX_WEIGHT: usize = 400_000;
+
+/// Sort the coins by amount descending, keeping the selected ones first. Coins of the
+/// same amount are sorted by how soon their timelock expires.
+fn sort_coins(coins: &mut [(Coin, bool)], blockheight: u32, timelock: u16) {
+    coins.sort_by(|(a, a_selected), (b, b_selected)| {
+        b_selected
+            .cmp(a_selected)
+            .then_with(|| b.amount.cmp(&a.amount))
+            .then_with(|| {
+                remaining_sequence(a, blockheight, timelock).cmp(&remaining_sequence(
+                    b,
+                    blockheight,
+                    timelock,
+                ))
+            })
+    });
+}
+
+/// Whether the coin can be spent through the recovery path with this timelock at the next block.
+fn is_recoverable(coin: &Coin, blockheight: u32, timelock: u16) -> bool {
+    coin.block_height.is_some() && remaining_sequence(coin, blockheight, timelock) <= 1
+}
+
+/// The spendable coins, that is neither spent nor immature, keeping the given ones selected.
+fn spendable_coins(coins: &[Coin], selected: &HashSet<OutPoint>) -> Vec<(Coin, bool)> {
+    coins
+        .iter()
+        .filter(|c| c.spend_info.is_none() && !c.is_immature)
+        .map(|c| (c.clone(), selected.contains(&c.outpoint)))
+        .collect()
+}
+
 #[derive(Clone)]
 pub struct TransactionDraft {
     network: Network,
@@ -63,6 +115,10 @@ pub trait Step {
     fn apply(&self, _draft: &mut TransactionDraft) {}
     fn interrupt(&mut self) {}
     fn load(&mut self, _draft: &TransactionDraft) {}
+    /// The spend being composed to keep on disk, if the step is worth saving.
+    fn spend_draft(&self) -> Option<SpendDraft> {
+        None
+    }
     fn subscription(&self) -> Subscription<Message> {
         Subscription::none()
     }
@@ -76,19 +132,35 @@ pub struct DefineSpend {
     /// Will be `true` if coins for spend were manually selected by user.
     /// Otherwise, will be `false` (including for self-send).
     is_user_coin_selection: bool,
+    /// Whether the coin control panel listing the coins is expanded.
+    show_coin_control: bool,
+    /// Whether the user confirmed spending together coins with different privacy tags.
+    mixed_tags_confirmed: bool,
     is_valid: bool,
     is_duplicate: bool,
 
     network: Network,
+    /// Unit the recipients amounts are entered in.
+    bitcoin_unit: BitcoinDisplayUnit,
     descriptor: LianaDescriptor,
     curve: secp256k1::Secp256k1<secp256k1::VerifyOnly>,
     timelock: u16,
+    /// The coins which can be spent through the spending path.
     coins: Vec<(Coin, bool)>,
+    /// The spendable coins, whatever the spending path.
+    spendable_coins: Vec<Coin>,
+    /// The timelock of the recovery path to spend through, or `None` for the primary path.
+    spend_path: Option<u16>,
+    keys_aliases: HashMap<Fingerprint, String>,
     coins_labels: HashMap<String, String>,
     batch_label: form::Value<String>,
     amount_left_to_select: Option<Amount>,
     feerate: form::Value<String>,
+    /// The preset the feerate was set from, if it wasn't entered manually.
+    feerate_preset: Option<FeeratePreset>,
     generated: Option<(Psbt, Vec<String>)>,
+    /// The spend saved on disk the user may resume, until they start composing another one.
+    saved_draft: Option<SpendDraft>,
     warning: Option<Error>,
 }
 
@@ -99,41 +171,52 @@ impl DefineSpend {
         coins: &[Coin],
         timelock: u16,
     ) -> Self {
-        let coins: Vec<(Coin, bool)> = coins
+        let spendable_coins: Vec<Coin> = coins
             .iter()
-            .filter_map(|c| {
-                if c.spend_info.is_none() && !c.is_immature {
-                    Some((c.clone(), false))
-                } else {
-                    None
-                }
-            })
+            .filter(|c| c.spend_info.is_none() && !c.is_immature)
+            .cloned()
             .collect();
 
         Self {
             network,
+            bitcoin_unit: BitcoinDisplayUnit::default(),
             descriptor,
             curve: secp256k1::Secp256k1::verification_only(),
             timelock,
             generated: None,
-            coins,
+            coins: spendable_coins(&spendable_coins, &HashSet::new()),
+            spendable_coins,
+            spend_path: None,
+            keys_aliases: HashMap::new(),
             coins_labels: HashMap::new(),
             batch_label: form::Value::default(),
             recipients: vec![Recipient::default()],
             send_max_to_recipient: None,
             is_user_coin_selection: false, // Start with auto-selection until user edits selection.
+            show_coin_control: false,
+            mixed_tags_confirmed: false,
             is_valid: false,
             is_duplicate: false,
             feerate: form::Value::default(),
+            feerate_preset: None,
             amount_left_to_select: None,
+            saved_draft: None,
             warning: None,
         }
     }
 
+    /// Offer to resume the given saved spend, unless nothing worth resuming was entered.
+    pub fn with_saved_draft(mut self, saved_draft: Option<SpendDraft>) -> Self {
+        self.saved_draft = saved_draft.filter(|draft| !draft.is_empty());
+        self
+    }
+
     pub fn with_preselected_coins(mut self, preselected_coins: &[OutPoint]) -> Self {
         for (coin, selected) in &mut self.coins {
             *selected = preselected_coins.contains(&coin.outpoint);
         }
+        self.is_user_coin_selection = self.coins.iter().any(|(_, selected)| *selected);
+        self.show_coin_control = self.is_user_coin_selection;
         self
     }
 
@@ -142,26 +225,105 @@ impl DefineSpend {
         self
     }
 
+    pub fn with_keys_aliases(mut self, keys_aliases: HashMap<Fingerprint, String>) -> Self {
+        self.keys_aliases = keys_aliases;
+        self
+    }
+
     fn sort_coins(&mut self, blockheight: u32) {
-        let timelock = self.timelock;
-        self.coins.sort_by(|(a, a_selected), (b, b_selected)| {
-            if *a_selected && !b_selected || !a_selected && *b_selected {
-                b_selected.cmp(a_selected)
-            } else if remaining_sequence(a, blockheight, timelock)
-                == remaining_sequence(b, blockheight, timelock)
-            {
-                // bigger amount first
-                b.amount.cmp(&a.amount)
-            } else {
-                // smallest blockheight (remaining_sequence) first
-                a.block_height.cmp(&b.block_height)
-            }
+        sort_coins(&mut self.coins, blockheight, self.timelock);
+    }
+
+    /// List the spendable coins which can be spent through the spending path, keeping the
+    /// selected ones selected.
+    fn set_path_coins(&mut self, blockheight: u32) {
+        let selected: HashSet<OutPoint> = self
+            .coins
+            .iter()
+            .filter_map(|(c, selected)| if *selected { Some(c.outpoint) } else { None })
+            .collect();
+        self.coins = spendable_coins(&self.spendable_coins, &selected)
+            .into_iter()
+            .filter(|(c, _)| {
+                self.spend_path
+                    .map(|timelock| is_recoverable(c, blockheight, timelock))
+                    .unwrap_or(true)
+            })
+            .collect();
+        self.sort_coins(blockheight);
+    }
+
+    /// Fill the form with the saved spend. The coins which can't be spent anymore are left
+    /// aside, and so is the recovery path if it isn't available anymore.
+    fn resume_draft(&mut self, draft: &SpendDraft, blockheight: u32) {
+        self.recipients = draft
+            .recipients
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let mut recipient = Recipient::default();
+                let amount = r
+                    .amount
+                    .map(|amount| amount_as_input(Amount::from_sat(amount), self.bitcoin_unit))
+                    .unwrap_or_default();
+                for (field, value) in [
+                    ("address", r.address.clone()),
+                    ("amount", amount),
+                    ("label", r.label.clone()),
+                ] {
+                    recipient.update(
+                        self.network,
+                        self.bitcoin_unit,
+                        view::CreateSpendMessage::RecipientEdited(i, field, value),
+                    );
+                }
+                recipient
+            })
+            .collect();
+        if self.recipients.is_empty() {
+            self.recipients.push(Recipient::default());
+        }
+        self.send_max_to_recipient = draft.recipients.iter().position(|r| r.send_max);
+
+        let label = draft.batch_label.clone().unwrap_or_default();
+        self.batch_label.valid = label.len() <= 100;
+        self.batch_label.value = label;
+
+        self.feerate.valid = draft.feerate.is_empty()
+            || draft
+                .feerate
+                .parse::<u64>()
+                .map(|value| value != 0 && value <= MAX_FEERATE)
+                .unwrap_or(false);
+        self.feerate.value.clone_from(&draft.feerate);
+        self.feerate_preset = None;
+
+        self.spend_path = draft.spend_path.filter(|timelock| {
+            self.spendable_coins
+                .iter()
+                .any(|c| is_recoverable(c, blockheight, *timelock))
         });
+        for (coin, selected) in &mut self.coins {
+            *selected = draft.coins.contains(&coin.outpoint);
+        }
+        self.set_path_coins(blockheight);
+        self.is_user_coin_selection = self.coins.iter().any(|(_, selected)| *selected);
+        self.show_coin_control = self.is_user_coin_selection;
+        self.mixed_tags_confirmed = false;
+        self.warning = None;
     }
 
-    pub fn self_send(mut self) -> Self {
-        self.recipients = Vec::new();
-        self
+    /// Convert the amounts already entered if the unit changed.
+    fn set_bitcoin_unit(&mut self, bitcoin_unit: BitcoinDisplayUnit) {
+        if bitcoin_unit == self.bitcoin_unit {
+            return;
+        }
+        for recipient in &mut self.recipients {
+            if let Ok(amount) = parse_amount(&recipient.amount.value, self.bitcoin_unit) {
+                recipient.amount.value = amount_as_input(amount, bitcoin_unit);
+            }
+        }
+        self.bitcoin_unit = bitcoin_unit;
     }
 
     // If `is_redraft`, the validation of recipients will take into account
@@ -190,13 +352,128 @@ impl DefineSpend {
     }
 
     fn check_valid(&mut self) {
-        self.is_valid =
-            self.form_values_are_valid(false) && self.coins.iter().any(|(_, selected)| *selected);
+        self.is_valid = self.form_values_are_valid(false)
+            && self.coins.iter().any(|(_, selected)| *selected)
+            && (self.mixed_tags_confirmed
+                || mixed_tags(self.coins.iter().filter_map(|(coin, selected)| {
+                    if *selected {
+                        Some(coin)
+                    } else {
+                        None
+                    }
+                }))
+                .is_empty());
         self.is_duplicate = self.exists_duplicate();
     }
+
+    /// The size in virtual bytes of the smallest transaction spending the selected coins, at
+    /// least one, through the spending path to the recipients and a change output.
+    fn min_tx_vbytes(&self) -> usize {
+        let n_inputs = self
+            .coins
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .count()
+            .max(1);
+        // The primary path is at index 0, the recovery paths follow by increasing timelock.
+        let path_index = self
+            .spend_path
+            .and_then(|timelock| {
+                self.descriptor
+                    .policy()
+                    .recovery_paths()
+                    .keys()
+                    .position(|t| *t == timelock)
+            })
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let weight = self.descriptor.minimum_spending_weight(
+            path_index,
+            n_inputs,
+            self.recipients.len() + 1,
+        );
+        weight.div_ceil(4)
+    }
+
+    /// The spending paths to choose from, if a recovery path is available at the next block
+    /// for any coin. Only the available paths can be selected.
+    fn spend_paths_view(&self, blockheight: u32) -> Vec<Element<view::Message>> {
+        let policy = self.descriptor.policy();
+        let recovery_paths: Vec<(u16, usize, Vec<String>, Option<u32>)> = policy
+            .recovery_paths()
+            .iter()
+            .map(|(&timelock, path)| {
+                let (threshold, origins) = path.thresh_origins();
+                // The number of blocks before the path is available for the first coin, if
+                // it isn't already.
+                let blocks_left = if self
+                    .spendable_coins
+                    .iter()
+                    .any(|c| is_recoverable(c, blockheight, timelock))
+                {
+                    None
+                } else {
+                    Some(
+                        self.spendable_coins
+                            .iter()
+                            .map(|c| remaining_sequence(c, blockheight, timelock))
+                            .min()
+                            .unwrap_or(timelock as u32)
+                            .saturating_sub(1)
+                            .max(1),
+                    )
+                };
+                (
+                    timelock,
+                    threshold,
+                    self.signers(origins.keys()),
+                    blocks_left,
+                )
+            })
+            .collect();
+        if self.spend_path.is_none() && recovery_paths.iter().all(|(.., b)| b.is_some()) {
+            return Vec::new();
+        }
+        let (threshold, origins) = policy.primary_path().thresh_origins();
+        std::iter::once(view::spend::spend_path_view(
+            None,
+            threshold,
+            self.signers(origins.keys()),
+            None,
+            self.spend_path.is_none(),
+        ))
+        .chain(
+            recovery_paths
+                .into_iter()
+                .map(|(timelock, threshold, signers, blocks_left)| {
+                    view::spend::spend_path_view(
+                        Some(timelock),
+                        threshold,
+                        signers,
+                        blocks_left,
+                        self.spend_path == Some(timelock),
+                    )
+                }),
+        )
+        .collect()
+    }
+
+    /// The names of the signers with these fingerprints, their alias if any.
+    fn signers<'a>(&self, fingerprints: impl Iterator<Item = &'a Fingerprint>) -> Vec<String> {
+        fingerprints
+            .map(|fg| {
+                self.keys_aliases
+                    .get(fg)
+                    .cloned()
+                    .unwrap_or_else(|| fg.to_string())
+            })
+            .collect()
+    }
+
     /// redraft calculates the amount left to select and auto selects coins
     /// if the user did not select a coin manually
     fn redraft(&mut self, daemon: Arc<dyn Daemon + Sync + Send>) {
+        let bitcoin_unit = self.bitcoin_unit;
         if !self.form_values_are_valid(true)
             || self.exists_duplicate()
             || self.recipients.is_empty()
@@ -215,6 +492,7 @@ impl DefineSpend {
                     .expect("max has been requested for this recipient so it must exist")
                     .update(
                         self.network,
+                        self.bitcoin_unit,
                         view::CreateSpendMessage::RecipientEdited(i, "amount", "".to_string()),
                     );
             }
@@ -236,7 +514,7 @@ impl DefineSpend {
                 } else {
                     Some((
                         Address::from_str(&recipient.address.value).expect("Checked before"),
-                        recipient.amount().expect("Checked before"),
+                        recipient.amount(self.bitcoin_unit).expect("Checked before"),
                     ))
                 }
             })
@@ -272,6 +550,7 @@ impl DefineSpend {
                 if let Some((i, recipient)) = recipient_with_max {
                     recipient.update(
                         self.network,
+                        self.bitcoin_unit,
                         view::CreateSpendMessage::RecipientEdited(i, "amount", "0".to_string()),
                     );
                 }
@@ -315,10 +594,18 @@ impl DefineSpend {
                     &destinations,
                     feerate_vb,
                     Some(change_address.clone()),
+                    // Let the automated coin selection keep coins with different privacy
+                    // tags apart.
+                    !self.is_user_coin_selection,
+                    self.spend_path,
                 )
                 .await
         }) {
-            Ok(CreateSpendResult::Success { psbt, .. }) => {
+            Ok(CreateSpendResult::Success {
+                psbt,
+                change_output,
+                ..
+            }) => {
                 self.warning = None;
                 if !self.is_user_coin_selection {
                     let selected_coins: Vec<OutPoint> = psbt
@@ -336,20 +623,25 @@ impl DefineSpend {
                 self.amount_left_to_select = Some(Amount::from_sat(0));
                 if let Some((i, recipient)) = recipient_with_max {
                     // If there's no change output, any excess must be below the dust threshold
-                    // and so the max available for this recipient is 0.
-                    let amount = psbt
-                        .unsigned_tx
-                        .output
-                        .iter()
-                        .find(|o| {
-                            o.script_pubkey
-                                == change_address.clone().assume_checked().script_pubkey()
+                    // and so the max available for this recipient is 0. Backends which don't
+                    // report the change output fall back to looking for the change address.
+                    let amount = change_output
+                        .map(|change| change.amount)
+                        .or_else(|| {
+                            psbt.unsigned_tx
+                                .output
+                                .iter()
+                                .find(|o| {
+                                    o.script_pubkey
+                                        == change_address.clone().assume_checked().script_pubkey()
+                                })
+                                .map(|o| o.value)
                         })
-                        .map(|change_output| change_output.value.to_btc())
-                        .unwrap_or(0.0)
-                        .to_string();
+                        .map(|amount| amount_as_input(amount, bitcoin_unit))
+                        .unwrap_or_else(|| "0".to_string());
                     recipient.update(
                         self.network,
+                        self.bitcoin_unit,
                         view::CreateSpendMessage::RecipientEdited(i, "amount", amount),
                     );
                 }
@@ -362,21 +654,23 @@ impl DefineSpend {
             Ok(CreateSpendResult::InsufficientFunds { missing }) => {
                 self.amount_left_to_select = Some(Amount::from_sat(missing));
                 if let Some((i, recipient)) = recipient_with_max {
-                    let amount = Amount::from_sat(if destinations.is_empty() {
-                        // If there are no other recipients, then the missing value will
-                        // be the amount left to select in order to create an output at the dust
-                        // threshold. Therefore, set this recipient's amount to this value so
-                        // that the information shown is consistent.
-                        // Otherwise, there are already insufficient funds for the other
-                        // recipients and so the max available for this recipient is 0.
-                        DUST_OUTPUT_SATS
-                    } else {
-                        0
-                    })
-                    .to_btc()
-                    .to_string();
+                    let amount = amount_as_input(
+                        Amount::from_sat(if destinations.is_empty() {
+                            // If there are no other recipients, then the missing value will
+                            // be the amount left to select in order to create an output at the dust
+                            // threshold. Therefore, set this recipient's amount to this value so
+                            // that the information shown is consistent.
+                            // Otherwise, there are already insufficient funds for the other
+                            // recipients and so the max available for this recipient is 0.
+                            DUST_OUTPUT_SATS
+                        } else {
+                            0
+                        }),
+                        self.bitcoin_unit,
+                    );
                     recipient.update(
                         self.network,
+                        self.bitcoin_unit,
                         view::CreateSpendMessage::RecipientEdited(i, "amount", amount),
                     );
                 }
@@ -395,8 +689,17 @@ impl Step for DefineSpend {
         cache: &Cache,
         message: Message,
     ) -> Command<Message> {
+        self.set_bitcoin_unit(cache.bitcoin_unit);
         match message {
             Message::View(view::Message::CreateSpend(msg)) => {
+                // Once the user starts composing another spend, the saved one is replaced.
+                if !matches!(msg, view::CreateSpendMessage::ToggleCoinControl) {
+                    if let Some(draft) = self.saved_draft.take() {
+                        if matches!(msg, view::CreateSpendMessage::ResumeDraft) {
+                            self.resume_draft(&draft, cache.blockheight as u32);
+                        }
+                    }
+                }
                 match msg {
                     view::CreateSpendMessage::BatchLabelEdited(label) => {
                         self.batch_label.valid = label.len() <= 100;
@@ -406,15 +709,22 @@ impl Step for DefineSpend {
                         *self = Self::new(
                             self.network,
                             self.descriptor.clone(),
-                            self.coins
-                                .iter()
-                                .map(|(c, _)| c.clone())
-                                .collect::<Vec<ListCoinsEntry>>()
-                                .as_slice(),
+                            &self.spendable_coins,
                             self.timelock,
-                        );
+                        )
+                        .with_keys_aliases(self.keys_aliases.clone())
+                        .with_coins_sorted(cache.blockheight as u32);
                         return Command::none();
                     }
+                    view::CreateSpendMessage::SelectSpendPath(spend_path) => {
+                        self.spend_path = spend_path;
+                        self.set_path_coins(cache.blockheight as u32);
+                        // The selection is only kept if some of the coins can still be spent.
+                        self.is_user_coin_selection =
+                            self.coins.iter().any(|(_, selected)| *selected);
+                        self.mixed_tags_confirmed = false;
+                        self.warning = None;
+                    }
                     view::CreateSpendMessage::AddRecipient => {
                         self.recipients.push(Recipient::default());
                     }
@@ -440,10 +750,11 @@ impl Step for DefineSpend {
                         }
                     }
                     view::CreateSpendMessage::RecipientEdited(i, _, _) => {
-                        self.recipients
-                            .get_mut(i)
-                            .unwrap()
-                            .update(cache.network, msg);
+                        self.recipients.get_mut(i).unwrap().update(
+                            cache.network,
+                            self.bitcoin_unit,
+                            msg,
+                        );
                     }
 
                     view::CreateSpendMessage::FeerateEdited(s) => {
@@ -456,6 +767,17 @@ impl Step for DefineSpend {
                         } else {
                             self.feerate.valid = false;
                         }
+                        self.feerate_preset = None;
+                        self.warning = None;
+                    }
+                    view::CreateSpendMessage::FeeratePresetSelected(preset) => {
+                        // Without estimates, fall back to the minimum. The view lets the user know.
+                        let feerate = preset
+                            .feerate(&cache.fee_estimates)
+                            .unwrap_or(FALLBACK_FEERATE);
+                        self.feerate.value = feerate.to_string();
+                        self.feerate.valid = feerate != 0 && feerate <= MAX_FEERATE;
+                        self.feerate_preset = Some(preset);
                         self.warning = None;
                     }
                     view::CreateSpendMessage::Generate => {
@@ -478,19 +800,22 @@ impl Step for DefineSpend {
                             outputs.insert(
                                 Address::from_str(&recipient.address.value)
                                     .expect("Checked before"),
-                                recipient.amount().expect("Checked before"),
+                                recipient.amount(self.bitcoin_unit).expect("Checked before"),
                             );
                         }
                         let feerate_vb = self.feerate.value.parse::<u64>().unwrap_or(0);
+                        let spend_path = self.spend_path;
                         self.warning = None;
                         return Command::perform(
                             async move {
                                 daemon
-                                    .create_spend_tx(&inputs, &outputs, feerate_vb, None)
+                                    .create_spend_tx(
+                                        &inputs, &outputs, feerate_vb, None, false, spend_path,
+                                    )
                                     .await
                                     .map_err(|e| e.into())
                                     .and_then(|res| match res {
-                                        CreateSpendResult::Success { psbt, warnings } => {
+                                        CreateSpendResult::Success { psbt, warnings, .. } => {
                                             Ok((psbt, warnings))
                                         }
                                         CreateSpendResult::InsufficientFunds { missing } => {
@@ -507,9 +832,28 @@ impl Step for DefineSpend {
                     view::CreateSpendMessage::SelectCoin(i) => {
                         if let Some(coin) = self.coins.get_mut(i) {
                             coin.1 = !coin.1;
-                            // Once user edits selection, auto-selection can no longer be used.
-                            self.is_user_coin_selection = true;
+                            // Once user edits selection, auto-selection can no longer be used,
+                            // unless all the coins were deselected.
+                            self.is_user_coin_selection =
+                                self.coins.iter().any(|(_, selected)| *selected);
+                            self.mixed_tags_confirmed = false;
+                        }
+                    }
+                    view::CreateSpendMessage::SelectAllCoins => {
+                        for (_, selected) in &mut self.coins {
+                            *selected = true;
                         }
+                        self.is_user_coin_selection = !self.coins.is_empty();
+                        self.mixed_tags_confirmed = false;
+                    }
+                    view::CreateSpendMessage::ConfirmMixedTags(confirmed) => {
+                        self.mixed_tags_confirmed = confirmed;
+                        self.check_valid();
+                        return Command::none();
+                    }
+                    view::CreateSpendMessage::ToggleCoinControl => {
+                        self.show_coin_control = !self.show_coin_control;
+                        return Command::none();
                     }
                     view::CreateSpendMessage::SendMaxToRecipient(i) => {
                         if self.recipients.get(i).is_some() {
@@ -547,26 +891,23 @@ impl Step for DefineSpend {
             },
             Message::Coins(res) => match res {
                 Ok(coins) => {
-                    let selected: HashSet<OutPoint> =
-                        HashSet::from_iter(self.coins.iter().filter_map(|(c, selected)| {
-                            if *selected {
-                                Some(c.outpoint)
-                            } else {
-                                None
-                            }
-                        }));
-                    self.coins = coins
+                    let blockheight = cache.blockheight as u32;
+                    self.spendable_coins = coins
                         .into_iter()
-                        .filter_map(|coin| {
-                            if coin.spend_info.is_none() && !coin.is_immature {
-                                let selected = selected.contains(&coin.outpoint);
-                                Some((coin, selected))
-                            } else {
-                                None
-                            }
-                        })
+                        .filter(|coin| coin.spend_info.is_none() && !coin.is_immature)
                         .collect();
-                    self.sort_coins(cache.blockheight as u32);
+                    // Fall back to the primary path if no coin can be spent through the
+                    // recovery path anymore.
+                    if let Some(timelock) = self.spend_path {
+                        if !self
+                            .spendable_coins
+                            .iter()
+                            .any(|c| is_recoverable(c, blockheight, timelock))
+                        {
+                            self.spend_path = None;
+                        }
+                    }
+                    self.set_path_coins(blockheight);
                     // In case some selected coins are not spendable anymore and
                     // new coins make more sense to be selected. A redraft is triggered
                     // if all forms are valid (checked in the redraft method)
@@ -580,6 +921,45 @@ impl Step for DefineSpend {
         Command::none()
     }
 
+    fn spend_draft(&self) -> Option<SpendDraft> {
+        // Do not overwrite the saved spend before the user decides what to do with it.
+        if self.saved_draft.is_some() {
+            return None;
+        }
+        Some(SpendDraft {
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() as u32)
+                .unwrap_or_default(),
+            recipients: self
+                .recipients
+                .iter()
+                .enumerate()
+                .map(|(i, r)| DraftRecipient {
+                    address: r.address.value.clone(),
+                    amount: r.amount(self.bitcoin_unit).ok(),
+                    label: r.label.value.clone(),
+                    send_max: self.send_max_to_recipient == Some(i),
+                })
+                .collect(),
+            batch_label: if self.recipients.len() > 1 && !self.batch_label.value.is_empty() {
+                Some(self.batch_label.value.clone())
+            } else {
+                None
+            },
+            coins: if self.is_user_coin_selection {
+                self.coins
+                    .iter()
+                    .filter_map(|(c, selected)| if *selected { Some(c.outpoint) } else { None })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            feerate: self.feerate.value.clone(),
+            spend_path: self.spend_path,
+        })
+    }
+
     fn apply(&self, draft: &mut TransactionDraft) {
         draft.inputs = self
             .coins
@@ -599,7 +979,7 @@ impl Step for DefineSpend {
                                 .unwrap()
                                 .payload()
                                 .matches_script_pubkey(&output.script_pubkey)
-                            && output.value.to_sat() == recipient.amount().unwrap()
+                            && output.value.to_sat() == recipient.amount(self.bitcoin_unit).unwrap()
                     })
                     .map(|recipient| recipient.label.value.to_string())
                 {
@@ -629,18 +1009,31 @@ impl Step for DefineSpend {
                 .enumerate()
                 .map(|(i, recipient)| {
                     recipient
-                        .view(i, self.send_max_to_recipient == Some(i))
+                        .view(
+                            i,
+                            self.send_max_to_recipient == Some(i),
+                            self.bitcoin_unit,
+                            cache.exchange_rate,
+                        )
                         .map(view::Message::CreateSpend)
                 })
                 .collect(),
             self.is_valid,
             self.is_duplicate,
+            self.spend_paths_view(cache.blockheight as u32),
             self.timelock,
             &self.coins,
+            self.show_coin_control,
+            self.mixed_tags_confirmed,
+            self.descriptor
+                .spender_input_size(self.spend_path.is_none()),
+            self.min_tx_vbytes(),
             &self.coins_labels,
             &self.batch_label,
             self.amount_left_to_select.as_ref(),
             &self.feerate,
+            self.feerate_preset,
+            self.saved_draft.as_ref(),
             self.warning.as_ref(),
         )
     }
@@ -654,12 +1047,12 @@ struct Recipient {
 }
 
 impl Recipient {
-    fn amount(&self) -> Result<u64, Error> {
+    fn amount(&self, bitcoin_unit: BitcoinDisplayUnit) -> Result<u64, Error> {
         if self.amount.value.is_empty() {
             return Err(Error::Unexpected("Amount should be non-zero".to_string()));
         }
 
-        let amount = Amount::from_str_in(&self.amount.value, Denomination::Bitcoin)
+        let amount = parse_amount(&self.amount.value, bitcoin_unit)
             .map_err(|_| Error::Unexpected("cannot parse output amount".to_string()))?;
 
         if amount.to_sat() == 0 {
@@ -692,14 +1085,19 @@ impl Recipient {
             && self.label.valid
     }
 
-    fn update(&mut self, network: Network, message: view::CreateSpendMessage) {
+    fn update(
+        &mut self,
+        network: Network,
+        bitcoin_unit: BitcoinDisplayUnit,
+        message: view::CreateSpendMessage,
+    ) {
         match message {
             view::CreateSpendMessage::RecipientEdited(_, "address", address) => {
                 self.address.value = address;
                 if let Ok(address) = Address::from_str(&self.address.value) {
                     self.address.valid = address.is_valid_for_network(network);
                     if !self.amount.value.is_empty() {
-                        self.amount.valid = self.amount().is_ok();
+                        self.amount.valid = self.amount(bitcoin_unit).is_ok();
                     }
                 } else if self.address.value.is_empty() {
                     // Make the error disappear if we deleted the invalid address
@@ -711,7 +1109,7 @@ impl Recipient {
             view::CreateSpendMessage::RecipientEdited(_, "amount", amount) => {
                 self.amount.value = amount;
                 if !self.amount.value.is_empty() {
-                    self.amount.valid = self.amount().is_ok();
+                    self.amount.valid = self.amount(bitcoin_unit).is_ok();
                 } else {
                     // Make the error disappear if we deleted the invalid amount
                     self.amount.valid = true;
@@ -725,8 +1123,300 @@ impl Recipient {
         };
     }
 
-    fn view(&self, i: usize, is_max_selected: bool) -> Element<view::CreateSpendMessage> {
-        view::spend::recipient_view(i, &self.address, &self.amount, &self.label, is_max_selected)
+    fn view(
+        &self,
+        i: usize,
+        is_max_selected: bool,
+        bitcoin_unit: BitcoinDisplayUnit,
+        exchange_rate: Option<ExchangeRate>,
+    ) -> Element<view::CreateSpendMessage> {
+        view::spend::recipient_view(
+            i,
+            &self.address,
+            &self.amount,
+            &self.label,
+            is_max_selected,
+            bitcoin_unit,
+            exchange_rate,
+        )
+    }
+}
+
+/// Spend coins back to the wallet to reset the timelock of their recovery path, before it
+/// becomes available.
+pub struct RefreshCoins {
+    descriptor: LianaDescriptor,
+    timelock: u16,
+    coins: Vec<(Coin, bool)>,
+    coins_labels: HashMap<String, String>,
+    /// Whether the coin control panel listing the coins is expanded.
+    show_coin_control: bool,
+    /// Whether the user confirmed refreshing together coins with different privacy tags.
+    mixed_tags_confirmed: bool,
+    feerate: form::Value<String>,
+    /// The preset the feerate was set from, if it wasn't entered manually.
+    feerate_preset: Option<FeeratePreset>,
+    /// The maximum number of coins a single transaction can spend.
+    max_inputs: usize,
+    /// Share of the refreshed value, in percent, above which the fee is warned about.
+    max_fee_percent: u32,
+    processing: bool,
+    generated: Option<(Psbt, Vec<String>)>,
+    warning: Option<Error>,
+}
+
+impl RefreshCoins {
+    pub fn new(
+        descriptor: LianaDescriptor,
+        coins: &[Coin],
+        timelock: u16,
+        max_fee_percent: u32,
+    ) -> Self {
+        let max_inputs = max_inputs_per_tx(&descriptor);
+        Self {
+            descriptor,
+            timelock,
+            coins: spendable_coins(coins, &HashSet::new()),
+            coins_labels: HashMap::new(),
+            show_coin_control: true,
+            mixed_tags_confirmed: false,
+            feerate: form::Value::default(),
+            feerate_preset: None,
+            max_inputs,
+            max_fee_percent,
+            processing: false,
+            generated: None,
+            warning: None,
+        }
+    }
+
+    pub fn with_preselected_coins(mut self, preselected_coins: &[OutPoint]) -> Self {
+        for (coin, selected) in &mut self.coins {
+            *selected = preselected_coins.contains(&coin.outpoint);
+        }
+        self
+    }
+
+    pub fn with_coins_sorted(mut self, blockheight: u32) -> Self {
+        sort_coins(&mut self.coins, blockheight, self.timelock);
+        self
+    }
+
+    fn selected_coins(&self) -> impl Iterator<Item = &Coin> {
+        self.coins
+            .iter()
+            .filter_map(|(coin, selected)| if *selected { Some(coin) } else { None })
+    }
+
+    /// The selected coins, split in as many transactions as needed for each of them to be
+    /// standard.
+    fn batches(&self) -> Vec<Vec<OutPoint>> {
+        self.selected_coins()
+            .map(|coin| coin.outpoint)
+            .collect::<Vec<_>>()
+            .chunks(self.max_inputs)
+            .map(|chunk| chunk.to_vec())
+            .collect()
+    }
+
+    /// The size in virtual bytes of the transactions refreshing the selected coins.
+    fn txs_vbytes(&self) -> usize {
+        self.batches()
+            .iter()
+            .map(|batch| {
+                self.descriptor
+                    .minimum_spending_weight(0, batch.len(), 1)
+                    .div_ceil(4)
+            })
+            .sum()
+    }
+
+    fn is_valid(&self) -> bool {
+        self.feerate.valid
+            && !self.feerate.value.is_empty()
+            && self.selected_coins().next().is_some()
+            && (self.mixed_tags_confirmed || mixed_tags(self.selected_coins()).is_empty())
+    }
+}
+
+/// The maximum number of coins a transaction to a single output can spend while staying below
+/// the standard weight.
+fn max_inputs_per_tx(descriptor: &LianaDescriptor) -> usize {
+    // The weight is estimated for a P2WPKH output, account for a second one as a margin for
+    // the larger scripts of the wallet.
+    let mut n_inputs = 1;
+    while descriptor.minimum_spending_weight(0, n_inputs + 1, 2) <= MAX_STANDARD_TX_WEIGHT {
+        n_inputs += 1;
+    }
+    n_inputs
+}
+
+/// Create a transaction refreshing each batch of coins. A single transaction is returned to go
+/// through the signing flow right away, several are saved to be signed from the list of PSBTs.
+async fn create_refresh_txs(
+    daemon: Arc<dyn Daemon + Sync + Send>,
+    batches: Vec<Vec<OutPoint>>,
+    feerate_vb: u64,
+) -> Result<Option<(Psbt, Vec<String>)>, Error> {
+    let mut psbts = Vec::with_capacity(batches.len());
+    for inputs in batches {
+        // A fresh address for each transaction, not to link them together on chain.
+        let address = daemon.get_new_address().await?.address;
+        match daemon
+            .create_spend_tx(
+                &inputs,
+                &HashMap::new(),
+                feerate_vb,
+                Some(address.as_unchecked().clone()),
+                false,
+                None,
+            )
+            .await?
+        {
+            CreateSpendResult::Success { psbt, warnings, .. } => psbts.push((psbt, warnings)),
+            CreateSpendResult::InsufficientFunds { missing } => {
+                return Err(
+                    SpendCreationError::CoinSelection(liana::spend::InsufficientFunds { missing })
+                        .into(),
+                );
+            }
+        }
+    }
+    if psbts.len() == 1 {
+        return Ok(psbts.pop());
+    }
+    for (psbt, _) in &psbts {
+        daemon.update_spend_tx(psbt).await?;
+    }
+    Ok(None)
+}
+
+impl Step for RefreshCoins {
+    fn update(
+        &mut self,
+        daemon: Arc<dyn Daemon + Sync + Send>,
+        cache: &Cache,
+        message: Message,
+    ) -> Command<Message> {
+        match message {
+            Message::View(view::Message::CreateSpend(msg)) => match msg {
+                view::CreateSpendMessage::FeerateEdited(s) => {
+                    if let Ok(value) = s.parse::<u64>() {
+                        self.feerate.value = s;
+                        self.feerate.valid = value != 0 && value <= MAX_FEERATE;
+                    } else if s.is_empty() {
+                        self.feerate.value = "".to_string();
+                        self.feerate.valid = true;
+                    } else {
+                        self.feerate.valid = false;
+                    }
+                    self.feerate_preset = None;
+                    self.warning = None;
+                }
+                view::CreateSpendMessage::FeeratePresetSelected(preset) => {
+                    // Without estimates, fall back to the minimum. The view lets the user know.
+                    let feerate = preset
+                        .feerate(&cache.fee_estimates)
+                        .unwrap_or(FALLBACK_FEERATE);
+                    self.feerate.value = feerate.to_string();
+                    self.feerate.valid = feerate != 0 && feerate <= MAX_FEERATE;
+                    self.feerate_preset = Some(preset);
+                    self.warning = None;
+                }
+                view::CreateSpendMessage::SelectCoin(i) => {
+                    if let Some(coin) = self.coins.get_mut(i) {
+                        coin.1 = !coin.1;
+                        self.mixed_tags_confirmed = false;
+                    }
+                }
+                view::CreateSpendMessage::SelectAllCoins => {
+                    for (_, selected) in &mut self.coins {
+                        *selected = true;
+                    }
+                    self.mixed_tags_confirmed = false;
+                }
+                view::CreateSpendMessage::ConfirmMixedTags(confirmed) => {
+                    self.mixed_tags_confirmed = confirmed;
+                }
+                view::CreateSpendMessage::ToggleCoinControl => {
+                    self.show_coin_control = !self.show_coin_control;
+                }
+                view::CreateSpendMessage::Generate if self.is_valid() && !self.processing => {
+                    let batches = self.batches();
+                    let feerate_vb = self.feerate.value.parse::<u64>().unwrap_or(0);
+                    self.processing = true;
+                    self.warning = None;
+                    return Command::perform(
+                        create_refresh_txs(daemon, batches, feerate_vb),
+                        |res| match res {
+                            Ok(Some(psbt)) => Message::Psbt(Ok(psbt)),
+                            Ok(None) => Message::Saved(Ok(())),
+                            Err(e) => Message::Psbt(Err(e)),
+                        },
+                    );
+                }
+                _ => {}
+            },
+            Message::Psbt(res) => {
+                self.processing = false;
+                match res {
+                    Ok(psbt) => {
+                        self.generated = Some(psbt);
+                        return Command::perform(async {}, |_| Message::View(view::Message::Next));
+                    }
+                    Err(e) => self.warning = Some(e),
+                }
+            }
+            Message::Saved(res) => {
+                self.processing = false;
+                match res {
+                    Ok(()) => return redirect(Menu::PSBTs),
+                    Err(e) => self.warning = Some(e),
+                }
+            }
+            Message::Labels(res) => match res {
+                Ok(labels) => {
+                    self.coins_labels = labels;
+                }
+                Err(e) => self.warning = Some(e),
+            },
+            Message::Coins(res) => match res {
+                Ok(coins) => {
+                    let selected: HashSet<OutPoint> =
+                        self.selected_coins().map(|coin| coin.outpoint).collect();
+                    self.coins = spendable_coins(&coins, &selected);
+                    sort_coins(&mut self.coins, cache.blockheight as u32, self.timelock);
+                }
+                Err(e) => self.warning = Some(e),
+            },
+            _ => {}
+        };
+        Command::none()
+    }
+
+    fn apply(&self, draft: &mut TransactionDraft) {
+        draft.inputs = self.selected_coins().cloned().collect();
+        draft.labels.clone_from(&self.coins_labels);
+        draft.generated.clone_from(&self.generated);
+    }
+
+    fn view<'a>(&'a self, cache: &'a Cache) -> Element<'a, view::Message> {
+        view::spend::refresh_coins(
+            cache,
+            self.is_valid() && !self.processing,
+            self.timelock,
+            &self.coins,
+            &self.coins_labels,
+            self.show_coin_control,
+            self.mixed_tags_confirmed,
+            self.descriptor.spender_input_size(true),
+            self.txs_vbytes(),
+            self.batches().len(),
+            &self.feerate,
+            self.feerate_preset,
+            self.max_fee_percent,
+            self.warning.as_ref(),
+        )
     }
 }
 
@@ -759,17 +1449,21 @@ impl Step for SaveSpend {
         );
         tx.labels.clone_from(&draft.labels);
 
-        if tx.is_batch() {
-            if let Some(label) = &draft.batch_label {
-                tx.labels
-                    .insert(tx.psbt.unsigned_tx.txid().to_string(), label.clone());
-            }
-        } else if let Some(recipient) = draft.recipients.first() {
-            if !recipient.label.value.is_empty() {
-                let label = recipient.label.value.clone();
+        let txid = tx.psbt.unsigned_tx.txid();
+        let tx_label = if tx.is_batch() {
+            draft.batch_label.clone()
+        } else {
+            draft.recipients.first().map(|r| r.label.value.clone())
+        }
+        .filter(|label| !label.is_empty());
+        if let Some(label) = tx_label {
+            // The change outputs are labelled after the payment they are the change of.
+            for i in &tx.change_indexes {
                 tx.labels
-                    .insert(tx.psbt.unsigned_tx.txid().to_string(), label);
+                    .entry(OutPoint::new(txid, *i as u32).to_string())
+                    .or_insert_with(|| label.clone());
             }
+            tx.labels.insert(txid.to_string(), label);
         }
 
         self.spend = Some((
@@ -825,3 +1519,18 @@ impl Step for SaveSpend {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESC: &str = "wsh(or_d(multi(2,[ffd63c8d/48'/1'/0'/2']tpubDExA3EC3iAsPxPhFn4j6gMiVup6V2eH3qKyk69RcTc9TTNRfFYVPad8bJD5FCHVQxyBT4izKsvr7Btd2R4xmQ1hZkvsqGBaeE82J71uTK4N/<0;1>/*,[de6eb005/48'/1'/0'/2']tpubDFGuYfS2JwiUSEXiQuNGdT3R7WTDhbaE6jbUhgYSSdhmfQcSx7ZntMPPv7nrkvAqjpj3jX9wbhSGMeKVao4qAzhbNyBi7iQmv5xxQk6H6jz/<0;1>/*),and_v(v:pkh([ffd63c8d/48'/1'/0'/2']tpubDExA3EC3iAsPxPhFn4j6gMiVup6V2eH3qKyk69RcTc9TTNRfFYVPad8bJD5FCHVQxyBT4izKsvr7Btd2R4xmQ1hZkvsqGBaeE82J71uTK4N/<2;3>/*),older(3))))#p9ax3xxp";
+
+    #[test]
+    fn refresh_max_inputs_per_tx() {
+        let descriptor = LianaDescriptor::from_str(DESC).unwrap();
+        let max_inputs = max_inputs_per_tx(&descriptor);
+        assert!(descriptor.minimum_spending_weight(0, max_inputs, 1) <= MAX_STANDARD_TX_WEIGHT);
+        assert!(descriptor.minimum_spending_weight(0, max_inputs + 2, 1) > MAX_STANDARD_TX_WEIGHT);
+    }
+}
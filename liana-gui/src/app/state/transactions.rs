@@ -1,13 +1,16 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryInto,
     sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 
-use iced::Command;
+use iced::{time, Command};
 use liana::{
-    miniscript::bitcoin::{OutPoint, Txid},
+    descriptors::LianaDescriptor,
+    miniscript::bitcoin::{
+        psbt::Psbt, secp256k1::Secp256k1, Address, Amount, OutPoint, Transaction, Txid,
+    },
     spend::{SpendCreationError, MAX_FEERATE},
 };
 use liana_ui::{
@@ -22,18 +25,19 @@ use crate::{
     app::{
         cache::Cache,
         error::Error,
+        menu::Menu,
         message::Message,
-        state::{label::LabelsEdited, State},
+        state::{coins::FILTER_DEBOUNCE, label::LabelsEdited, State},
         view,
         wallet::Wallet,
     },
-    daemon::model::{self, LabelsLoader},
+    daemon::model::{self, FeeratePreset, LabelsLoader, FALLBACK_FEERATE},
     export::ExportMessage,
 };
 
 use crate::daemon::{
     model::{CreateSpendResult, HistoryTransaction, LabelItem, Labelled},
-    Daemon,
+    Daemon, DaemonError,
 };
 
 use super::export::ExportModal;
@@ -41,6 +45,7 @@ use super::export::ExportModal;
 #[derive(Debug)]
 pub enum TransactionsModal {
     CreateRbf(CreateRbfModal),
+    CreateCpfp(CreateCpfpModal),
     Export(ExportModal),
     None,
 }
@@ -50,10 +55,18 @@ pub struct TransactionsPanel {
     txs: Vec<HistoryTransaction>,
     labels_edited: LabelsEdited,
     selected_tx: Option<HistoryTransaction>,
+    // The addresses paid by the selected transaction which also received other payments.
+    reused_addresses: Vec<Address>,
+    // The mempool fee information of the selected transaction, if it is a pending spend.
+    fee_info: Option<model::PendingSpendFeeInfo>,
     warning: Option<Error>,
     modal: TransactionsModal,
     is_last_page: bool,
     processing: bool,
+    /// The filter as typed, and the one applied to the list along with when it was last edited.
+    filter: String,
+    applied_filter: String,
+    filter_edited: Option<Instant>,
 }
 
 impl TransactionsPanel {
@@ -61,19 +74,123 @@ impl TransactionsPanel {
         Self {
             wallet,
             selected_tx: None,
+            reused_addresses: Vec::new(),
+            fee_info: None,
             txs: Vec::new(),
             labels_edited: LabelsEdited::default(),
             warning: None,
             modal: TransactionsModal::None,
             is_last_page: false,
             processing: false,
+            filter: String::new(),
+            applied_filter: String::new(),
+            filter_edited: None,
         }
     }
 
-    pub fn preselect(&mut self, tx: HistoryTransaction) {
+    /// Whether the transaction, or one of its outputs or the addresses they pay, has a label
+    /// containing the filter. The txid matches as well.
+    fn matches(&self, tx: &HistoryTransaction) -> bool {
+        let query = self.applied_filter.trim().to_lowercase();
+        query.is_empty()
+            || tx.txid.to_string().contains(&query)
+            || tx
+                .labels
+                .values()
+                .any(|label| label.to_lowercase().contains(&query))
+    }
+
+    /// The loaded transactions matching the filter, along with their index in the list.
+    fn visible_txs(&self) -> Vec<(usize, &HistoryTransaction)> {
+        self.txs
+            .iter()
+            .enumerate()
+            .filter(|(_, tx)| self.matches(tx))
+            .collect()
+    }
+
+    pub fn preselect(
+        &mut self,
+        daemon: Arc<dyn Daemon + Sync + Send>,
+        tx: HistoryTransaction,
+    ) -> Command<Message> {
         self.selected_tx = Some(tx);
         self.warning = None;
         self.modal = TransactionsModal::None;
+        Command::batch(vec![
+            self.check_address_reuse(daemon.clone()),
+            self.fetch_fee_info(daemon),
+        ])
+    }
+
+    // Get the effective feerate of the selected transaction if it is a spend of ours still in the
+    // mempool, to tell whether it should be fee-bumped.
+    fn fetch_fee_info(&mut self, daemon: Arc<dyn Daemon + Sync + Send>) -> Command<Message> {
+        self.fee_info = None;
+        let txid = match &self.selected_tx {
+            Some(tx) if !tx.is_external() && tx.time.is_none() && tx.replaced_by.is_none() => {
+                tx.txid
+            }
+            _ => return Command::none(),
+        };
+        Command::perform(
+            async move {
+                match daemon.pending_spend_fee_info(&txid).await {
+                    Ok(info) => Ok(info),
+                    Err(DaemonError::ClientNotSupported) => Ok(None),
+                    Err(e) => Err(e.into()),
+                }
+            },
+            move |res| Message::PendingSpendFeeInfo(txid, res),
+        )
+    }
+
+    // Look for the addresses paid by the selected incoming transaction which also received funds
+    // from other transactions.
+    fn check_address_reuse(&mut self, daemon: Arc<dyn Daemon + Sync + Send>) -> Command<Message> {
+        self.reused_addresses = Vec::new();
+        let tx = match &self.selected_tx {
+            Some(tx) if tx.is_external() => tx,
+            _ => return Command::none(),
+        };
+        let txid = tx.txid;
+        let outpoints: Vec<_> = tx
+            .change_indexes
+            .iter()
+            .map(|i| OutPoint::new(txid, *i as u32))
+            .collect();
+        Command::perform(
+            async move {
+                let coins = daemon.list_coins(&[], &outpoints).await?.coins;
+                let mut reused = Vec::new();
+                for coin in &coins {
+                    if reused.contains(&coin.address) {
+                        continue;
+                    }
+                    let index = coin.derivation_index.into();
+                    let info = match daemon.list_addresses(Some(index), Some(1)).await {
+                        Ok(list) => list.addresses.into_iter().next(),
+                        Err(DaemonError::ClientNotSupported) => return Ok(Vec::new()),
+                        Err(e) => return Err(e.into()),
+                    };
+                    let received = info
+                        .map(|a| {
+                            if coin.is_change {
+                                a.change_coins
+                            } else {
+                                a.receive_coins
+                            }
+                        })
+                        .unwrap_or(0);
+                    let in_this_tx = coins.iter().filter(|c| c.address == coin.address).count();
+                    if received > in_this_tx {
+                        reused.push(coin.address.clone());
+                    }
+                }
+                Ok(reused)
+            },
+            move |res| Message::ReusedAddresses(txid, res),
+        )
     }
 }
 
@@ -83,17 +200,22 @@ impl State for TransactionsPanel {
             let content = view::transactions::tx_view(
                 cache,
                 tx,
+                &self.wallet.main_descriptor.policy(),
+                &self.reused_addresses,
+                self.fee_info.as_ref(),
                 self.labels_edited.cache(),
                 self.warning.as_ref(),
             );
             match &self.modal {
-                TransactionsModal::CreateRbf(rbf) => rbf.view(content),
+                TransactionsModal::CreateRbf(rbf) => rbf.view(content, cache),
+                TransactionsModal::CreateCpfp(cpfp) => cpfp.view(content, cache),
                 _ => content,
             }
         } else {
             let content = view::transactions::transactions_view(
                 cache,
-                &self.txs,
+                self.visible_txs(),
+                &self.filter,
                 self.warning.as_ref(),
                 self.is_last_page,
                 self.processing,
@@ -108,7 +230,7 @@ impl State for TransactionsPanel {
     fn update(
         &mut self,
         daemon: Arc<dyn Daemon + Sync + Send>,
-        _cache: &Cache,
+        cache: &Cache,
         message: Message,
     ) -> Command<Message> {
         match message {
@@ -146,8 +268,25 @@ impl State for TransactionsPanel {
             },
             Message::RbfModal(tx, is_cancel, res) => match res {
                 Ok(descendant_txids) => {
-                    let modal = CreateRbfModal::new(*tx, is_cancel, descendant_txids);
+                    // Coins the replacement may spend in addition to the ones of the transaction.
+                    let available = cache
+                        .coins
+                        .iter()
+                        .filter(|c| {
+                            c.spend_info.is_none() && c.block_height.is_some() && !c.is_immature
+                        })
+                        .map(|c| c.amount)
+                        .sum();
+                    let mut modal = CreateRbfModal::new(
+                        *tx,
+                        is_cancel,
+                        descendant_txids,
+                        &cache.fee_estimates,
+                        available,
+                    );
+                    let cmd = modal.preview(daemon, self.wallet.main_descriptor.clone());
                     self.modal = TransactionsModal::CreateRbf(modal);
+                    return cmd;
                 }
                 Err(e) => {
                     self.warning = e.into();
@@ -156,23 +295,70 @@ impl State for TransactionsPanel {
             Message::View(view::Message::Reload) | Message::View(view::Message::Close) => {
                 return self.reload(daemon, self.wallet.clone());
             }
+            Message::View(view::Message::Transactions(msg)) => match msg {
+                view::TransactionsMessage::FilterEdited(filter) => {
+                    self.filter = filter;
+                    self.filter_edited = Some(Instant::now());
+                }
+                view::TransactionsMessage::ApplyFilter => {
+                    if self
+                        .filter_edited
+                        .map_or(false, |edited| edited.elapsed() >= FILTER_DEBOUNCE)
+                    {
+                        self.applied_filter = self.filter.clone();
+                        self.filter_edited = None;
+                    }
+                }
+            },
+            Message::ReusedAddresses(txid, res) => {
+                if self.selected_tx.as_ref().map(|tx| tx.txid) == Some(txid) {
+                    match res {
+                        Ok(addresses) => self.reused_addresses = addresses,
+                        Err(e) => self.warning = Some(e),
+                    }
+                }
+            }
+            Message::PendingSpendFeeInfo(txid, res) => {
+                if self.selected_tx.as_ref().map(|tx| tx.txid) == Some(txid) {
+                    match res {
+                        Ok(info) => self.fee_info = info,
+                        Err(e) => self.warning = Some(e),
+                    }
+                }
+            }
             Message::View(view::Message::Select(i)) => {
                 self.selected_tx = self.txs.get(i).cloned();
                 // Clear modal if it's for a different tx.
-                if let TransactionsModal::CreateRbf(modal) = &self.modal {
-                    if Some(modal.tx.tx.txid())
-                        != self.selected_tx.as_ref().map(|selected| selected.tx.txid())
-                    {
-                        self.modal = TransactionsModal::None;
-                    }
+                let modal_txid = match &self.modal {
+                    TransactionsModal::CreateRbf(modal) => Some(modal.tx.txid),
+                    TransactionsModal::CreateCpfp(modal) => Some(modal.tx.txid),
+                    _ => None,
+                };
+                if modal_txid.is_some()
+                    && modal_txid != self.selected_tx.as_ref().map(|selected| selected.txid)
+                {
+                    self.modal = TransactionsModal::None;
                 }
+                return Command::batch(vec![
+                    self.check_address_reuse(daemon.clone()),
+                    self.fetch_fee_info(daemon),
+                ]);
             }
             Message::View(view::Message::CreateRbf(view::CreateRbfMessage::Cancel)) => {
                 self.modal = TransactionsModal::None;
             }
+            // Open the replacement in the signing flow as soon as its PSBT is created.
+            Message::RbfPsbt(Ok(txid)) => {
+                self.modal = TransactionsModal::None;
+                return Command::perform(async move { txid }, |txid| {
+                    Message::View(view::Message::Menu(Menu::PsbtPreSelected(txid)))
+                });
+            }
             Message::View(view::Message::CreateRbf(view::CreateRbfMessage::New(is_cancel))) => {
                 if let Some(tx) = &self.selected_tx {
-                    if tx.fee_amount.is_some() {
+                    if tx.fee_amount.is_some()
+                        && view::transactions::rbf_unavailable_reason(tx).is_none()
+                    {
                         let tx = tx.clone();
                         let outpoints: Vec<_> = (0..tx.tx.output.len())
                             .map(|vout| {
@@ -201,7 +387,18 @@ impl State for TransactionsPanel {
                     }
                 }
             }
-            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(_) => {
+            Message::View(view::Message::CreateCpfp(view::CreateCpfpMessage::New)) => {
+                if let Some(tx) = &self.selected_tx {
+                    self.modal = TransactionsModal::CreateCpfp(CreateCpfpModal::new(
+                        tx.clone(),
+                        &cache.fee_estimates,
+                    ));
+                }
+            }
+            Message::View(view::Message::CreateCpfp(view::CreateCpfpMessage::Cancel)) => {
+                self.modal = TransactionsModal::None;
+            }
+            Message::View(view::Message::Label(_, _)) | Message::LabelsUpdated(..) => {
                 match self.labels_edited.update(
                     daemon,
                     message,
@@ -277,7 +474,10 @@ impl State for TransactionsPanel {
             }
             _ => {
                 return match &mut self.modal {
-                    TransactionsModal::CreateRbf(modal) => modal.update(daemon, _cache, message),
+                    TransactionsModal::CreateRbf(modal) => {
+                        modal.update(daemon, self.wallet.main_descriptor.clone(), message)
+                    }
+                    TransactionsModal::CreateCpfp(modal) => modal.update(daemon, message),
                     TransactionsModal::Export(modal) => modal.update(message),
                     TransactionsModal::None => Command::none(),
                 };
@@ -306,6 +506,9 @@ impl State for TransactionsPanel {
                 txs.sort_by(|a, b| a.compare(b));
 
                 let mut pending_txs = daemon.list_pending_txs().await?;
+                // Keep showing the transactions we replaced until the replacement confirms.
+                let replaced_txs = daemon.list_replaced_txs(&pending_txs).await?;
+                pending_txs.extend(replaced_txs);
                 pending_txs.extend(txs);
                 Ok(pending_txs)
             },
@@ -321,7 +524,15 @@ impl State for TransactionsPanel {
                 });
             }
         }
-        iced::Subscription::none()
+        if self.filter_edited.is_some() {
+            time::every(FILTER_DEBOUNCE).map(|_| {
+                Message::View(view::Message::Transactions(
+                    view::TransactionsMessage::ApplyFilter,
+                ))
+            })
+        } else {
+            iced::Subscription::none()
+        }
     }
 }
 
@@ -337,8 +548,12 @@ pub struct CreateRbfModal {
     tx: model::HistoryTransaction,
     /// Whether to cancel or bump fee.
     is_cancel: bool,
+    /// Feerate of the transaction to replace.
+    prev_feerate_vb: u64,
     /// Min feerate required for RBF.
     min_feerate_vb: u64,
+    /// Max feerate selectable with the slider.
+    max_feerate_vb: u64,
     /// IDs of any transactions from this wallet that are direct descendants of
     /// the transaction to be replaced.
     descendant_txids: HashSet<Txid>,
@@ -346,18 +561,22 @@ pub struct CreateRbfModal {
     feerate_val: form::Value<String>,
     /// Parsed feerate.
     feerate_vb: Option<u64>,
-    /// Replacement transaction ID.
-    replacement_txid: Option<Txid>,
+    /// The replacement created by the daemon for the parsed feerate, once received.
+    preview: Option<model::RbfPreview>,
 
     processing: bool,
     warning: Option<Error>,
 }
 
 impl CreateRbfModal {
+    /// `available` is the value of the confirmed coins the replacement may spend in addition to
+    /// the ones of the transaction, to bound the selectable feerates.
     fn new(
         tx: model::HistoryTransaction,
         is_cancel: bool,
         descendant_txids: HashSet<Txid>,
+        fee_estimates: &BTreeMap<u16, u64>,
+        available: Amount,
     ) -> Self {
         let prev_feerate_vb = tx
             .fee_amount
@@ -366,35 +585,86 @@ impl CreateRbfModal {
             .checked_div(tx.tx.vsize().try_into().expect("vsize must fit in u64"))
             .expect("transaction vsize must be positive");
         let min_feerate_vb = prev_feerate_vb.checked_add(1).unwrap();
+        // Suggest the feerate to confirm within about half an hour, if it is enough to replace
+        // the transaction.
+        let suggested_feerate_vb = FeeratePreset::HalfHour
+            .feerate(fee_estimates)
+            .unwrap_or(min_feerate_vb)
+            .clamp(min_feerate_vb, MAX_FEERATE.max(min_feerate_vb));
+        // The replacement can't pay more than the fee of the transaction, its change and the
+        // other coins available.
+        let affordable_feerate_vb = (tx.fee_amount.unwrap_or(Amount::ZERO)
+            + tx.change_indexes
+                .iter()
+                .filter_map(|i| tx.tx.output.get(*i))
+                .map(|txo| txo.value)
+                .sum::<Amount>()
+            + available)
+            .to_sat()
+            / tx.tx.vsize().max(1) as u64;
+        let max_feerate_vb = FeeratePreset::NextBlock
+            .feerate(fee_estimates)
+            .unwrap_or(suggested_feerate_vb)
+            .max(suggested_feerate_vb)
+            .saturating_mul(2)
+            .min(affordable_feerate_vb)
+            .clamp(min_feerate_vb, MAX_FEERATE.max(min_feerate_vb));
+        let suggested_feerate_vb = suggested_feerate_vb.min(max_feerate_vb);
         Self {
             tx,
             is_cancel,
+            prev_feerate_vb,
             min_feerate_vb,
+            max_feerate_vb,
             descendant_txids,
             feerate_val: form::Value {
                 valid: true,
-                value: min_feerate_vb.to_string(),
+                value: suggested_feerate_vb.to_string(),
             },
             // For cancel, we let `rbfpsbt` set the feerate.
             feerate_vb: if is_cancel {
                 None
             } else {
-                Some(min_feerate_vb)
+                Some(suggested_feerate_vb)
             },
-            replacement_txid: None,
+            preview: None,
             warning: None,
             processing: false,
         }
     }
 
+    /// Ask the daemon for the replacement at the current feerate, without storing it.
+    fn preview(
+        &mut self,
+        daemon: Arc<dyn Daemon + Sync + Send>,
+        descriptor: LianaDescriptor,
+    ) -> Command<Message> {
+        self.preview = None;
+        if !self.is_cancel && self.feerate_vb.is_none() {
+            return Command::none();
+        }
+        let feerate_vb = self.feerate_vb;
+        Command::perform(
+            rbf_preview(
+                daemon,
+                descriptor,
+                self.tx.tx.clone(),
+                self.is_cancel,
+                feerate_vb,
+            ),
+            move |res| Message::RbfPreview(feerate_vb, res.map(Box::new)),
+        )
+    }
+
     fn update(
         &mut self,
         daemon: Arc<dyn Daemon + Sync + Send>,
-        _cache: &Cache,
+        descriptor: LianaDescriptor,
         message: Message,
     ) -> Command<Message> {
         match message {
             Message::View(view::Message::CreateRbf(view::CreateRbfMessage::FeerateEdited(s))) => {
+                let previous_feerate_vb = self.feerate_vb;
                 self.warning = None;
                 if let Ok(value) = s.parse::<u64>() {
                     self.feerate_val.valid = value >= self.min_feerate_vb && value <= MAX_FEERATE;
@@ -408,37 +678,61 @@ impl CreateRbfModal {
                     self.feerate_vb = None;
                 }
                 self.feerate_val.value = s; // save form value even if it cannot be parsed
+                if self.feerate_vb != previous_feerate_vb {
+                    return self.preview(daemon, descriptor);
+                }
             }
-            Message::RbfPsbt(res) => {
-                self.processing = false;
-                match res {
-                    Ok(txid) => {
-                        self.replacement_txid = Some(txid);
+            // Previews requested for another feerate than the current one are outdated.
+            Message::RbfPreview(feerate_vb, res) => {
+                if feerate_vb == self.feerate_vb {
+                    match res {
+                        Ok(preview) => self.preview = Some(*preview),
+                        Err(e) => self.warning = Some(e),
                     }
-                    Err(e) => self.warning = Some(e),
                 }
             }
+            Message::RbfPsbt(Err(e)) => {
+                self.processing = false;
+                self.warning = Some(e);
+            }
             Message::View(view::Message::CreateRbf(view::CreateRbfMessage::Confirm)) => {
-                self.warning = None;
-                self.processing = true;
-                return Command::perform(
-                    rbf(daemon, self.tx.clone(), self.is_cancel, self.feerate_vb),
-                    Message::RbfPsbt,
-                );
+                if let Some(preview) = &self.preview {
+                    self.warning = None;
+                    self.processing = true;
+                    return Command::perform(
+                        rbf(
+                            daemon,
+                            self.tx.clone(),
+                            self.is_cancel,
+                            preview.psbt.clone(),
+                        ),
+                        Message::RbfPsbt,
+                    );
+                }
             }
             _ => {}
         }
         Command::none()
     }
-    fn view<'a>(&'a self, content: Element<'a, view::Message>) -> Element<view::Message> {
+    fn view<'a>(
+        &'a self,
+        content: Element<'a, view::Message>,
+        cache: &'a Cache,
+    ) -> Element<view::Message> {
         let modal = Modal::new(
             content,
             view::transactions::create_rbf_modal(
                 self.is_cancel,
                 &self.descendant_txids,
+                self.prev_feerate_vb,
                 &self.feerate_val,
-                self.replacement_txid,
+                (self.min_feerate_vb, self.max_feerate_vb),
+                self.feerate_vb,
+                self.tx.fee_amount,
+                self.preview.as_ref(),
+                self.processing,
                 self.warning.as_ref(),
+                cache,
             ),
         );
         if self.processing {
@@ -452,15 +746,166 @@ impl CreateRbfModal {
     }
 }
 
-async fn rbf(
+#[derive(Debug)]
+pub struct CreateCpfpModal {
+    /// Unconfirmed incoming transaction to bump.
+    tx: model::HistoryTransaction,
+    /// Feerates selectable with the slider.
+    feerate_range: (u64, u64),
+    /// Feerate form value.
+    feerate_val: form::Value<String>,
+    /// Parsed feerate.
+    feerate_vb: Option<u64>,
+    /// The child transaction and its ancestors, once created.
+    package: Option<model::CpfpPackage>,
+
+    processing: bool,
+    warning: Option<Error>,
+}
+
+impl CreateCpfpModal {
+    fn new(tx: model::HistoryTransaction, fee_estimates: &BTreeMap<u16, u64>) -> Self {
+        let suggested_feerate_vb = FeeratePreset::HalfHour
+            .feerate(fee_estimates)
+            .unwrap_or(FALLBACK_FEERATE)
+            .clamp(1, MAX_FEERATE);
+        let max_feerate_vb = FeeratePreset::NextBlock
+            .feerate(fee_estimates)
+            .unwrap_or(suggested_feerate_vb)
+            .max(suggested_feerate_vb)
+            .saturating_mul(2)
+            .clamp(1, MAX_FEERATE);
+        Self {
+            tx,
+            feerate_range: (1, max_feerate_vb),
+            feerate_val: form::Value {
+                valid: true,
+                value: suggested_feerate_vb.to_string(),
+            },
+            feerate_vb: Some(suggested_feerate_vb),
+            package: None,
+            processing: false,
+            warning: None,
+        }
+    }
+
+    fn update(
+        &mut self,
+        daemon: Arc<dyn Daemon + Sync + Send>,
+        message: Message,
+    ) -> Command<Message> {
+        match message {
+            Message::View(view::Message::CreateCpfp(view::CreateCpfpMessage::FeerateEdited(s))) => {
+                self.warning = None;
+                self.feerate_vb = s
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|value| *value >= 1 && *value <= MAX_FEERATE);
+                self.feerate_val.valid = self.feerate_vb.is_some();
+                self.feerate_val.value = s;
+            }
+            Message::View(view::Message::CreateCpfp(view::CreateCpfpMessage::Confirm)) => {
+                if let Some(feerate_vb) = self.feerate_vb {
+                    self.warning = None;
+                    self.processing = true;
+                    let outpoints = self
+                        .tx
+                        .change_indexes
+                        .iter()
+                        .map(|i| OutPoint::new(self.tx.txid, *i as u32))
+                        .collect();
+                    return Command::perform(
+                        cpfp(daemon, outpoints, feerate_vb),
+                        Message::CpfpPsbt,
+                    );
+                }
+            }
+            Message::CpfpPsbt(res) => {
+                self.processing = false;
+                match res {
+                    Ok(package) => self.package = Some(package),
+                    Err(e) => self.warning = Some(e),
+                }
+            }
+            _ => {}
+        }
+        Command::none()
+    }
+
+    fn view<'a>(
+        &'a self,
+        content: Element<'a, view::Message>,
+        cache: &'a Cache,
+    ) -> Element<view::Message> {
+        let modal = Modal::new(
+            content,
+            view::transactions::create_cpfp_modal(
+                &self.tx,
+                &self.feerate_val,
+                self.feerate_range,
+                self.feerate_vb,
+                self.package.as_ref(),
+                self.processing,
+                self.warning.as_ref(),
+                cache,
+            ),
+        );
+        if self.processing {
+            modal
+        } else {
+            modal.on_blur(Some(view::Message::CreateCpfp(
+                view::CreateCpfpMessage::Cancel,
+            )))
+        }
+        .into()
+    }
+}
+
+/// Create and store a transaction spending the given coins back to us, paying for the
+/// unconfirmed transaction which created them.
+async fn cpfp(
     daemon: Arc<dyn Daemon + Sync + Send>,
-    previous_tx: model::HistoryTransaction,
+    outpoints: Vec<OutPoint>,
+    feerate_vb: u64,
+) -> Result<model::CpfpPackage, Error> {
+    let res = daemon.create_cpfp(&outpoints, feerate_vb).await?;
+    let psbt = match res.spend {
+        CreateSpendResult::Success { psbt, .. } => psbt,
+        CreateSpendResult::InsufficientFunds { missing } => {
+            return Err(
+                SpendCreationError::CoinSelection(liana::spend::InsufficientFunds { missing })
+                    .into(),
+            );
+        }
+    };
+    daemon.update_spend_tx(&psbt).await?;
+
+    let child_txid = psbt.unsigned_tx.txid();
+    let child = daemon
+        .list_spend_transactions(Some(&[child_txid]))
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::Unexpected("The CPFP transaction was not stored.".to_string()))?;
+    Ok(model::CpfpPackage {
+        child_txid,
+        child_fee: child.fee_amount.unwrap_or(Amount::ZERO),
+        child_vsize: child.max_vbytes,
+        ancestors_fee: Amount::from_sat(res.ancestors_fee),
+        ancestors_vsize: res.ancestors_vsize,
+    })
+}
+
+/// Create the replacement of the transaction, without storing it.
+async fn rbf_preview(
+    daemon: Arc<dyn Daemon + Sync + Send>,
+    descriptor: LianaDescriptor,
+    previous_tx: Transaction,
     is_cancel: bool,
     feerate_vb: Option<u64>,
-) -> Result<Txid, Error> {
-    let previous_txid = previous_tx.tx.txid();
+) -> Result<model::RbfPreview, Error> {
     let psbt = match daemon
-        .rbf_psbt(&previous_txid, is_cancel, feerate_vb)
+        .rbf_psbt(&previous_tx.txid(), is_cancel, feerate_vb)
         .await?
     {
         CreateSpendResult::Success { psbt, .. } => psbt,
@@ -471,7 +916,25 @@ async fn rbf(
             );
         }
     };
+    let change_indexes: Vec<usize> = descriptor
+        .change_indexes(&psbt, &Secp256k1::verification_only())
+        .into_iter()
+        .map(|c| c.index())
+        .collect();
+    // The replacement spends the coins with the primary path, as the transaction it replaces.
+    let max_vbytes = descriptor.unsigned_tx_max_vbytes(&psbt.unsigned_tx, true);
+    model::RbfPreview::new(psbt, &previous_tx, &change_indexes, max_vbytes).ok_or_else(|| {
+        Error::Unexpected("The replacement is missing the value of its inputs.".to_string())
+    })
+}
 
+/// Store the replacement of the transaction, along with the labels of the transaction.
+async fn rbf(
+    daemon: Arc<dyn Daemon + Sync + Send>,
+    previous_tx: model::HistoryTransaction,
+    is_cancel: bool,
+    psbt: Psbt,
+) -> Result<Txid, Error> {
     if !is_cancel {
         let mut labels = HashMap::<LabelItem, Option<String>>::new();
         let new_txid = psbt.unsigned_tx.txid();
@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 
-use iced::{widget::Space, Alignment, Length};
+use iced::{
+    widget::{checkbox, Space},
+    Alignment, Length,
+};
+
+use liana::miniscript::bitcoin::OutPoint;
 
 use liana_ui::{
     color,
@@ -13,53 +18,238 @@ use crate::{
     app::{
         cache::Cache,
         menu::Menu,
-        view::{label, message::Message},
+        view::{
+            label,
+            message::{CoinTagMessage, CoinsFilter, CoinsMessage, CoinsSortKey, Message},
+        },
     },
     daemon::model::{remaining_sequence, Coin},
 };
 
+/// How the coins are listed, and which ones are checked for the bulk actions.
+pub struct CoinsListState<'a> {
+    pub sort: CoinsSortKey,
+    pub sort_descending: bool,
+    pub filter: &'a str,
+    pub filters: &'a [CoinsFilter],
+    pub checked: &'a [OutPoint],
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn coins_view<'a>(
     cache: &Cache,
-    coins: &'a [Coin],
+    coins: Vec<(usize, &'a Coin)>,
     timelock: u16,
     selected: &[usize],
+    list: CoinsListState<'a>,
     labels: &'a HashMap<String, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
+    tag_editing: Option<&'a (OutPoint, form::Value<String>)>,
 ) -> Element<'a, Message> {
+    let all_checked = !coins.is_empty()
+        && coins
+            .iter()
+            .all(|(_, c)| list.checked.contains(&c.outpoint));
     Column::new()
         .push(Container::new(h3("Coins")).width(Length::Fill))
         .push(
             Column::new()
                 .spacing(10)
-                .push(coins.iter().enumerate().fold(
-                    Column::new().spacing(10),
-                    |col, (i, coin)| {
-                        col.push(coin_list_view(
-                            coin,
-                            timelock,
-                            cache.blockheight as u32,
-                            i,
-                            selected.contains(&i),
-                            labels,
-                            labels_editing,
-                        ))
-                    },
-                )),
+                .push(
+                    TextInput::new("Filter by label, address or outpoint", list.filter)
+                        .on_input(|filter| Message::Coins(CoinsMessage::FilterEdited(filter)))
+                        .size(P1_SIZE)
+                        .padding(10),
+                )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(
+                            [
+                                (CoinsFilter::Unconfirmed, "Unconfirmed"),
+                                (CoinsFilter::Change, "Change"),
+                                (CoinsFilter::Expired, "Expired timelock"),
+                            ]
+                            .into_iter()
+                            .fold(
+                                Row::new().spacing(10).align_items(Alignment::Center),
+                                |row, (filter, name)| {
+                                    row.push(filter_chip(name, filter, list.filters))
+                                },
+                            ),
+                        )
+                        .push(Space::with_width(Length::Fill))
+                        .push(p2_regular("Sort by:").style(color::GREY_3))
+                        .push(
+                            [
+                                (CoinsSortKey::Age, "Age"),
+                                (CoinsSortKey::Amount, "Amount"),
+                                (CoinsSortKey::Label, "Label"),
+                                (CoinsSortKey::Timelock, "Timelock"),
+                            ]
+                            .into_iter()
+                            .fold(
+                                Row::new().spacing(5).align_items(Alignment::Center),
+                                |row, (key, name)| {
+                                    row.push(sort_button(
+                                        name,
+                                        key,
+                                        list.sort,
+                                        list.sort_descending,
+                                    ))
+                                },
+                            ),
+                        ),
+                )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(
+                            checkbox("", all_checked).on_toggle(|checked| {
+                                Message::Coins(CoinsMessage::CheckAll(checked))
+                            }),
+                        )
+                        .push(
+                            p2_regular(if list.checked.is_empty() {
+                                "Shift-click to check a range of coins".to_string()
+                            } else {
+                                format!(
+                                    "{} coin{} checked",
+                                    list.checked.len(),
+                                    if list.checked.len() > 1 { "s" } else { "" }
+                                )
+                            })
+                            .style(color::GREY_3),
+                        )
+                        .push(Space::with_width(Length::Fill))
+                        .push(
+                            button::secondary(Some(icon::clipboard_icon()), "Copy outpoints")
+                                .on_press_maybe(if list.checked.is_empty() {
+                                    None
+                                } else {
+                                    Some(Message::Clipboard(
+                                        list.checked
+                                            .iter()
+                                            .map(|op| op.to_string())
+                                            .collect::<Vec<_>>()
+                                            .join("\n"),
+                                    ))
+                                }),
+                        )
+                        .push(
+                            button::secondary(Some(icon::arrow_right()), "Add to new spend")
+                                .on_press_maybe(if list.checked.is_empty() {
+                                    None
+                                } else {
+                                    Some(Message::Menu(Menu::CreateSpendPreSelected(
+                                        list.checked.to_vec(),
+                                    )))
+                                }),
+                        ),
+                )
+                .push_maybe(if coins.is_empty() {
+                    Some(p1_regular("No coins match the filters").style(color::GREY_3))
+                } else {
+                    None
+                })
+                .push(
+                    coins
+                        .into_iter()
+                        .fold(Column::new().spacing(10), |col, (i, coin)| {
+                            col.push(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_items(Alignment::Center)
+                                    .push(
+                                        checkbox("", list.checked.contains(&coin.outpoint))
+                                            .on_toggle(move |_| {
+                                                Message::Coins(CoinsMessage::Check(coin.outpoint))
+                                            }),
+                                    )
+                                    .push(
+                                        coin_list_view(
+                                            coin,
+                                            timelock,
+                                            cache.blockheight as u32,
+                                            cache.bitcoin_unit,
+                                            i,
+                                            selected.contains(&i),
+                                            labels,
+                                            labels_editing,
+                                            tag_editing
+                                                .filter(|(outpoint, _)| *outpoint == coin.outpoint)
+                                                .map(|(_, tag)| tag),
+                                        )
+                                        .width(Length::Fill),
+                                    ),
+                            )
+                        }),
+                ),
         )
         .align_items(Alignment::Center)
         .spacing(30)
         .into()
 }
 
+fn filter_chip<'a>(
+    name: &'static str,
+    filter: CoinsFilter,
+    filters: &[CoinsFilter],
+) -> Element<'a, Message> {
+    Button::new(p2_regular(name))
+        .padding([5, 10])
+        .style(if filters.contains(&filter) {
+            theme::Button::Primary
+        } else {
+            theme::Button::Border
+        })
+        .on_press(Message::Coins(CoinsMessage::ToggleFilter(filter)))
+        .into()
+}
+
+fn sort_button<'a>(
+    name: &'static str,
+    key: CoinsSortKey,
+    sort: CoinsSortKey,
+    descending: bool,
+) -> Element<'a, Message> {
+    Button::new(
+        Row::new()
+            .align_items(Alignment::Center)
+            .push(p2_regular(name))
+            .push_maybe(if key == sort {
+                Some(if descending {
+                    icon::arrow_down()
+                } else {
+                    icon::arrow_up()
+                })
+            } else {
+                None
+            }),
+    )
+    .padding([5, 10])
+    .style(if key == sort {
+        theme::Button::Border
+    } else {
+        theme::Button::TransparentBorder
+    })
+    .on_press(Message::Coins(CoinsMessage::SortBy(key)))
+    .into()
+}
+
 #[allow(clippy::collapsible_else_if)]
 fn coin_list_view<'a>(
     coin: &'a Coin,
     timelock: u16,
     blockheight: u32,
+    bitcoin_unit: BitcoinDisplayUnit,
     index: usize,
     collapsed: bool,
     labels: &'a HashMap<String, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
+    tag_editing: Option<&'a form::Value<String>>,
 ) -> Container<'a, Message> {
     let outpoint = coin.outpoint.to_string();
     let address = coin.address.to_string();
@@ -114,6 +304,11 @@ fn coin_list_view<'a>(
                                     Container::new(Space::with_width(Length::Fill))
                                         .width(Length::Fill)
                                 })
+                                .push_maybe(coin.tag.as_ref().map(|tag| {
+                                    Container::new(p2_regular(tag))
+                                        .padding(10)
+                                        .style(theme::Container::Pill(theme::Pill::Simple))
+                                }))
                                 .push(if coin.spend_info.is_some() {
                                     badge::spent()
                                 } else if coin.block_height.is_none() {
@@ -126,7 +321,7 @@ fn coin_list_view<'a>(
                                 .align_items(Alignment::Center)
                                 .width(Length::Fill),
                         )
-                        .push(amount(&coin.amount))
+                        .push(amount(&coin.amount, bitcoin_unit))
                         .align_items(Alignment::Center)
                         .spacing(20),
                 )
@@ -151,6 +346,15 @@ fn coin_list_view<'a>(
                             })
                             .width(Length::Fill),
                         )
+                        .push_maybe(if coin.spend_info.is_none() {
+                            Some(if let Some(tag) = tag_editing {
+                                coin_tag_editing(coin.outpoint, tag)
+                            } else {
+                                coin_tag_editable(coin.outpoint, coin.tag.as_ref())
+                            })
+                        } else {
+                            None
+                        })
                         .push_maybe(if coin.spend_info.is_none() {
                             if let Some(b) = coin.block_height {
                                 if blockheight > b as u32 + timelock as u32 {
@@ -294,6 +498,46 @@ fn coin_list_view<'a>(
     .style(theme::Container::Card(theme::Card::Simple))
 }
 
+/// The privacy tag of a coin. Coins with different tags are not spent together by the
+/// automated coin selection, to avoid linking them onchain.
+fn coin_tag_editable(outpoint: OutPoint, tag: Option<&String>) -> Element<Message> {
+    Row::new()
+        .align_items(Alignment::Center)
+        .spacing(5)
+        .push(p2_regular("Privacy tag:").bold().style(color::GREY_2))
+        .push(if let Some(tag) = tag {
+            p2_regular(tag).style(color::GREY_2)
+        } else {
+            p2_regular("No tag").style(color::GREY_2)
+        })
+        .push(
+            Button::new(icon::pencil_icon())
+                .on_press(Message::CoinTag(outpoint, CoinTagMessage::Edit))
+                .style(theme::Button::TransparentBorder),
+        )
+        .into()
+}
+
+fn coin_tag_editing(outpoint: OutPoint, tag: &form::Value<String>) -> Element<Message> {
+    let e: Element<CoinTagMessage> = Row::new()
+        .align_items(Alignment::Center)
+        .spacing(5)
+        .push(
+            form::Form::new("Privacy tag", tag, CoinTagMessage::Edited)
+                .warning("Invalid tag length, cannot be superior to 100")
+                .size(P2_SIZE)
+                .padding(10),
+        )
+        .push(if tag.valid {
+            button::secondary(None, "Save").on_press(CoinTagMessage::Confirm)
+        } else {
+            button::secondary(None, "Save")
+        })
+        .push(button::secondary(None, "Cancel").on_press(CoinTagMessage::Cancel))
+        .into();
+    e.map(move |msg| Message::CoinTag(outpoint, msg))
+}
+
 pub fn coin_sequence_label<'a, T: 'a>(seq: u32, timelock: u32) -> Container<'a, T> {
     if seq == 0 {
         Container::new(
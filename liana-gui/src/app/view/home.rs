@@ -23,7 +23,11 @@ use crate::{
         view::{coins, dashboard, label, message::Message},
         wallet::SyncStatus,
     },
-    daemon::model::{HistoryTransaction, Payment, PaymentKind, TransactionKind},
+    daemon::model::{
+        HistoryTransaction, Payment, PaymentKind, RecoveryPathExpiry, SavedPsbtEntry,
+        TransactionKind,
+    },
+    i18n::{tr, tr_args},
 };
 
 #[allow(clippy::too_many_arguments)]
@@ -32,27 +36,33 @@ pub fn home_view<'a>(
     unconfirmed_balance: &'a bitcoin::Amount,
     remaining_sequence: &Option<u32>,
     expiring_coins: &[bitcoin::OutPoint],
+    recovery_expiries: &'a [RecoveryPathExpiry],
+    blockheight: i32,
+    bitcoin_unit: BitcoinDisplayUnit,
+    drafts: &'a [SavedPsbtEntry],
     events: &'a [Payment],
     is_last_page: bool,
     processing: bool,
     sync_status: &SyncStatus,
+    consolidable_coins: Option<usize>,
 ) -> Element<'a, Message> {
     Column::new()
-        .push(h3("Balance"))
+        .push(h3(tr("home.balance")))
         .push(
             Column::new()
                 .push(if sync_status.is_synced() {
-                    amount_with_size(balance, H1_SIZE)
+                    amount_with_size(balance, H1_SIZE, bitcoin_unit)
                 } else {
                     Row::new().push(spinner::Carousel::new(
                         Duration::from_millis(1000),
                         vec![
-                            amount_with_size(balance, H1_SIZE),
+                            amount_with_size(balance, H1_SIZE, bitcoin_unit),
                             amount_with_size_and_colors(
                                 balance,
                                 H1_SIZE,
                                 color::GREY_4,
                                 Some(color::GREY_2),
+                                bitcoin_unit,
                             ),
                         ],
                     ))
@@ -62,12 +72,15 @@ pub fn home_view<'a>(
                         Row::new()
                             .push(
                                 match sync_status {
-                                    SyncStatus::BlockchainSync(progress) => text(format!(
-                                        "Syncing blockchain ({:.2}%)",
-                                        100.0 * *progress
+                                    SyncStatus::BlockchainSync(progress) => text(tr_args(
+                                        "home.syncing_blockchain",
+                                        &[(
+                                            "progress",
+                                            format!("{:.2}", 100.0 * *progress).as_str(),
+                                        )],
                                     )),
-                                    SyncStatus::WalletFullScan => text("Syncing"),
-                                    _ => text("Checking for new transactions"),
+                                    SyncStatus::WalletFullScan => text(tr("home.syncing")),
+                                    _ => text(tr("home.checking_new_transactions")),
                                 }
                                 .style(color::GREY_2),
                             )
@@ -87,7 +100,11 @@ pub fn home_view<'a>(
                             Row::new()
                                 .spacing(10)
                                 .push(text("+").size(H3_SIZE).style(color::GREY_3))
-                                .push(unconfirmed_amount_with_size(unconfirmed_balance, H3_SIZE))
+                                .push(unconfirmed_amount_with_size(
+                                    unconfirmed_balance,
+                                    H3_SIZE,
+                                    bitcoin_unit,
+                                ))
                                 .push(text("unconfirmed").size(H3_SIZE).style(color::GREY_3)),
                         )
                     } else {
@@ -95,7 +112,15 @@ pub fn home_view<'a>(
                     },
                 ),
         )
-        .push_maybe(if expiring_coins.is_empty() {
+        .push_maybe(if !recovery_expiries.is_empty() {
+            Some(
+                recovery_expiries
+                    .iter()
+                    .fold(Column::new().spacing(10), |col, expiry| {
+                        col.push(recovery_expiry_view(expiry, blockheight, bitcoin_unit))
+                    }),
+            )
+        } else if expiring_coins.is_empty() {
             remaining_sequence.map(|sequence| {
                 Container::new(
                     Row::new()
@@ -121,35 +146,46 @@ pub fn home_view<'a>(
             })
         } else {
             Some(
-                Container::new(
-                    Row::new()
-                        .spacing(15)
-                        .align_items(Alignment::Center)
-                        .push(
-                            h4_regular(format!(
-                                "Recovery path is or will soon be available for {} coin(s).",
-                                expiring_coins.len(),
-                            ))
-                            .width(Length::Fill),
-                        )
-                        .push(
-                            button::secondary(Some(icon::arrow_repeat()), "Refresh coins")
-                                .on_press(Message::Menu(Menu::RefreshCoins(
-                                    expiring_coins.to_owned(),
-                                ))),
-                        ),
-                )
-                .padding(25)
-                .style(theme::Card::Invalid),
+                Column::new().push(
+                    Container::new(
+                        Row::new()
+                            .spacing(15)
+                            .align_items(Alignment::Center)
+                            .push(
+                                h4_regular(format!(
+                                    "Recovery path is or will soon be available for {} coin(s).",
+                                    expiring_coins.len(),
+                                ))
+                                .width(Length::Fill),
+                            )
+                            .push(
+                                button::primary(
+                                    Some(icon::arrow_repeat()),
+                                    tr("home.refresh_coins"),
+                                )
+                                .on_press(Message::Menu(
+                                    Menu::RefreshCoins(expiring_coins.to_owned()),
+                                )),
+                            ),
+                    )
+                    .padding(25)
+                    .style(theme::Card::Invalid),
+                ),
             )
         })
+        .push_maybe(consolidable_coins.map(consolidation_view))
+        .push_maybe(if drafts.is_empty() {
+            None
+        } else {
+            Some(drafts_view(drafts))
+        })
         .push(
             Column::new()
                 .spacing(10)
-                .push(h4_bold("Last payments"))
+                .push(h4_bold(tr("home.last_payments")))
                 .push(events.iter().fold(Column::new().spacing(10), |col, event| {
                     if event.kind != PaymentKind::SendToSelf {
-                        col.push(event_list_view(event))
+                        col.push(event_list_view(event, bitcoin_unit))
                     } else {
                         col
                     }
@@ -159,9 +195,9 @@ pub fn home_view<'a>(
                         Container::new(
                             Button::new(
                                 text(if processing {
-                                    "Fetching ..."
+                                    tr("home.fetching")
                                 } else {
-                                    "See more"
+                                    tr("home.see_more")
                                 })
                                 .width(Length::Fill)
                                 .horizontal_alignment(alignment::Horizontal::Center),
@@ -186,7 +222,189 @@ pub fn home_view<'a>(
         .into()
 }
 
-fn event_list_view(event: &Payment) -> Element<'_, Message> {
+/// Suggestion to merge the coins of a wallet holding many of them.
+fn consolidation_view<'a>(count: usize) -> Element<'a, Message> {
+    Container::new(
+        Row::new()
+            .spacing(15)
+            .align_items(Alignment::Center)
+            .push(
+                h4_regular(tr_args(
+                    "home.consolidation_hint",
+                    &[("count", count.to_string().as_str())],
+                ))
+                .width(Length::Fill),
+            )
+            .push(
+                button::secondary(Some(icon::arrow_repeat()), tr("home.consolidate_coins"))
+                    .on_press(Message::Consolidate),
+            ),
+    )
+    .padding(25)
+    .style(theme::Card::Border)
+    .into()
+}
+
+/// Summary of when a recovery path becomes available for the coins of the wallet, with a
+/// shortcut to refresh the coins at risk.
+fn recovery_expiry_view(
+    expiry: &RecoveryPathExpiry,
+    blockheight: i32,
+    bitcoin_unit: BitcoinDisplayUnit,
+) -> Element<'_, Message> {
+    let blocks_left = expiry.earliest_height.saturating_sub(blockheight).max(0) as u32;
+    let header = if blocks_left == 0 {
+        format!(
+            "Recovery path after {} blocks is available for some coins.",
+            expiry.timelock
+        )
+    } else {
+        let date = Local::now() + chrono::Duration::minutes(blocks_left as i64 * 10);
+        format!(
+            "Recovery path after {} blocks becomes available at block {} (≈ {}, {}).",
+            expiry.timelock,
+            expiry.earliest_height,
+            date.format("%b. %d, %Y"),
+            coins::expire_message_units(blocks_left).join(", ")
+        )
+    };
+    Container::new(
+        Column::new()
+            .spacing(10)
+            .push(
+                Row::new()
+                    .spacing(15)
+                    .align_items(Alignment::Center)
+                    .push(h4_regular(header).width(Length::Fill))
+                    .push_maybe(if expiry.at_risk_coins.is_empty() {
+                        None
+                    } else {
+                        Some(
+                            button::primary(Some(icon::arrow_repeat()), tr("home.refresh_coins"))
+                                .on_press(Message::Menu(Menu::RefreshCoins(
+                                    expiry.at_risk_coins.clone(),
+                                ))),
+                        )
+                    }),
+            )
+            .push(expiry.windows.iter().fold(
+                Column::new().spacing(5),
+                |col, (days, n_coins, value)| {
+                    col.push(
+                        Row::new()
+                            .spacing(5)
+                            .align_items(Alignment::Center)
+                            .push(
+                                p1_regular(format!(
+                                    "Within {} day{}: {} coin{}",
+                                    days,
+                                    if *days > 1 { "s" } else { "" },
+                                    n_coins,
+                                    if *n_coins > 1 { "s" } else { "" },
+                                ))
+                                .style(color::GREY_3),
+                            )
+                            .push_maybe(if *n_coins > 0 {
+                                Some(
+                                    Row::new()
+                                        .spacing(5)
+                                        .push(p1_regular("for").style(color::GREY_3))
+                                        .push(amount(value, bitcoin_unit)),
+                                )
+                            } else {
+                                None
+                            }),
+                    )
+                },
+            )),
+    )
+    .padding(25)
+    .style(if expiry.at_risk_coins.is_empty() {
+        theme::Card::Border
+    } else {
+        theme::Card::Invalid
+    })
+    .into()
+}
+
+fn drafts_view(drafts: &[SavedPsbtEntry]) -> Element<'_, Message> {
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push(h4_bold(tr("home.drafts")))
+            .push(drafts.iter().fold(Column::new().spacing(5), |col, draft| {
+                let date = DateTime::<Utc>::from_timestamp(draft.created_at as i64, 0)
+                    .unwrap()
+                    .with_timezone(&Local)
+                    .format("%b. %d, %Y - %T");
+                col.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(
+                            Button::new(
+                                Row::new()
+                                    .spacing(10)
+                                    .align_items(Alignment::Center)
+                                    .push(
+                                        p1_regular(if draft.label.is_empty() {
+                                            format!("Draft #{}", draft.id)
+                                        } else {
+                                            draft.label.clone()
+                                        })
+                                        .width(Length::Fill),
+                                    )
+                                    .push(text(format!("{}", date)).style(color::GREY_3)),
+                            )
+                            .padding(10)
+                            .width(Length::Fill)
+                            .style(theme::Button::TransparentBorder)
+                            .on_press(Message::SelectDraft(draft.id)),
+                        )
+                        .push(
+                            button::secondary(Some(icon::trash_icon()), tr("common.delete"))
+                                .on_press(Message::DeleteDraft(draft.id)),
+                        ),
+                )
+            })),
+    )
+    .width(Length::Fill)
+    .into()
+}
+
+/// Modal shown when reopening a draft which can no longer be broadcast.
+pub fn invalid_draft_modal<'a>(id: i64, warning: Option<&Error>) -> Element<'a, Message> {
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push_maybe(warning.map(|w| card::warning(w.to_string())))
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .push(icon::warning_icon().style(color::ORANGE))
+                    .push(text(tr("home.draft_invalid")).bold()),
+            )
+            .push(text(
+                "At least one of its inputs was spent by another transaction. \
+                 It can't be broadcast anymore.",
+            ))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(Space::with_width(Length::Fill))
+                    .push(button::transparent(None, tr("common.close")).on_press(Message::Close))
+                    .push(
+                        button::alert(None, tr("home.delete_draft"))
+                            .on_press(Message::DeleteDraft(id)),
+                    ),
+            ),
+    )
+    .width(Length::Fixed(400.0))
+    .into()
+}
+
+fn event_list_view(event: &Payment, bitcoin_unit: BitcoinDisplayUnit) -> Element<'_, Message> {
     let label = if let Some(label) = &event.label {
         Some(p1_regular(label))
     } else {
@@ -201,6 +419,7 @@ fn event_list_view(event: &Payment) -> Element<'_, Message> {
                 label,
                 t,
                 &event.amount,
+                bitcoin_unit,
                 Message::SelectPayment(event.outpoint),
             )
             .into()
@@ -208,6 +427,7 @@ fn event_list_view(event: &Payment) -> Element<'_, Message> {
             event::unconfirmed_incoming_event(
                 label,
                 &event.amount,
+                bitcoin_unit,
                 Message::SelectPayment(event.outpoint),
             )
             .into()
@@ -217,6 +437,7 @@ fn event_list_view(event: &Payment) -> Element<'_, Message> {
             label,
             t,
             &event.amount,
+            bitcoin_unit,
             Message::SelectPayment(event.outpoint),
         )
         .into()
@@ -224,6 +445,7 @@ fn event_list_view(event: &Payment) -> Element<'_, Message> {
         event::unconfirmed_outgoing_event(
             label,
             &event.amount,
+            bitcoin_unit,
             Message::SelectPayment(event.outpoint),
         )
         .into()
@@ -251,13 +473,13 @@ pub fn payment_view<'a>(
             .push(match tx.kind {
                 TransactionKind::OutgoingSinglePayment(_)
                 | TransactionKind::OutgoingPaymentBatch(_) => {
-                    Container::new(h3("Outgoing payment")).width(Length::Fill)
+                    Container::new(h3(tr("home.outgoing_payment"))).width(Length::Fill)
                 }
                 TransactionKind::IncomingSinglePayment(_)
                 | TransactionKind::IncomingPaymentBatch(_) => {
-                    Container::new(h3("Incoming payment")).width(Length::Fill)
+                    Container::new(h3(tr("home.incoming_payment"))).width(Length::Fill)
                 }
-                _ => Container::new(h3("Payment")).width(Length::Fill),
+                _ => Container::new(h3(tr("home.payment"))).width(Length::Fill),
             })
             .push(if tx.is_single_payment().is_some() {
                 // if the payment is a payment of a single payment transaction then
@@ -279,9 +501,10 @@ pub fn payment_view<'a>(
             .push(Container::new(amount_with_size(
                 &tx.tx.output[output_index].value,
                 H3_SIZE,
+                cache.bitcoin_unit,
             )))
             .push(Space::with_height(H3_SIZE))
-            .push(Container::new(h3("Transaction")).width(Length::Fill))
+            .push(Container::new(h3(tr("home.transaction"))).width(Length::Fill))
             .push_maybe(if tx.is_batch() {
                 if let Some(label) = labels_editing.get(&txid) {
                     Some(label::label_editing(vec![txid.clone()], label, H3_SIZE))
@@ -299,7 +522,7 @@ pub fn payment_view<'a>(
                 Row::new()
                     .align_items(Alignment::Center)
                     .push(h3("Miner fee: ").style(color::GREY_3))
-                    .push(amount_with_size(&fee_amount, H3_SIZE))
+                    .push(amount_with_size(&fee_amount, H3_SIZE, cache.bitcoin_unit))
                     .push(text(" ").size(H3_SIZE))
                     .push(
                         text(format!(
@@ -342,7 +565,7 @@ pub fn payment_view<'a>(
                     .spacing(5),
             ))
             .push(
-                button::secondary(None, "See transaction details")
+                button::secondary(None, tr("home.see_transaction_details"))
                     .on_press(Message::Menu(Menu::TransactionPreSelected(tx.tx.txid()))),
             )
             .spacing(20),
@@ -1,12 +1,44 @@
-use iced::Length;
+use iced::{
+    widget::{scrollable, Space},
+    Length,
+};
 
-use liana_ui::{component::hw, theme, widget::*};
+use async_hwi::{DeviceKind, Version};
+use liana::{descriptors::LianaDescriptor, miniscript::bitcoin::bip32::Fingerprint};
+use liana_ui::{
+    component::{button, hw, text::text},
+    theme,
+    widget::*,
+};
 
 use crate::{
     app::view::message::*,
-    hw::{HardwareWallet, UnsupportedReason},
+    hw::{
+        supports_address_display, version_status, HardwareWallet, UnsupportedReason,
+        VerificationStatus, VersionStatus,
+    },
 };
-use async_hwi::DeviceKind;
+
+/// A supported device, with a nudge to update it if its version is older than the recommended one.
+pub fn supported_hardware_wallet<'a, T: 'a>(
+    kind: &'a DeviceKind,
+    version: Option<&'a Version>,
+    fingerprint: &'a Fingerprint,
+    alias: Option<&'a String>,
+) -> Container<'a, T> {
+    match version_status(kind, version) {
+        VersionStatus::UpdateRecommended {
+            update_instructions,
+        } => hw::update_recommended_hardware_wallet(
+            kind,
+            version,
+            fingerprint,
+            alias,
+            update_instructions,
+        ),
+        _ => hw::supported_hardware_wallet(kind, version, fingerprint, alias),
+    }
+}
 
 pub fn hw_list_view(
     i: usize,
@@ -41,7 +73,7 @@ pub fn hw_list_view(
                     "The wallet descriptor is not registered on the device.\n You can register it in the settings.",
                 )
             } else {
-                hw::supported_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
+                supported_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
             }
         }
         HardwareWallet::Unsupported {
@@ -71,7 +103,8 @@ pub fn hw_list_view(
     })
     .style(theme::Button::Border)
     .width(Length::Fill);
-    if !signing {
+    // A device which already signed can't be selected again.
+    if !signing && !signed {
         if let HardwareWallet::Supported { registered, .. } = hw {
             if *registered != Some(false) {
                 bttn = bttn.on_press(Message::SelectHardwareWallet(i));
@@ -84,6 +117,99 @@ pub fn hw_list_view(
         .into()
 }
 
+/// A device which can sign only once the wallet is registered on it, along with the action to do
+/// so. The registration may be outdated, for instance if the device was restored from its seed.
+pub fn hw_list_view_to_register(i: usize, hw: &HardwareWallet, outdated: bool) -> Element<Message> {
+    let content = match hw {
+        HardwareWallet::Supported {
+            kind,
+            version,
+            fingerprint,
+            alias,
+            ..
+        } => hw::warning_hardware_wallet(
+            kind,
+            version.as_ref(),
+            fingerprint,
+            alias.as_ref(),
+            if outdated {
+                "The device does not recognize the wallet registration, for instance because it was restored or its firmware updated."
+            } else {
+                "The wallet descriptor is not registered on the device."
+            },
+        ),
+        _ => return hw_list_view(i, hw, false, false),
+    };
+    Container::new(
+        Column::new().spacing(10).push(content).push(
+            Row::new().push(Space::with_width(Length::Fill)).push(
+                button::secondary(
+                    None,
+                    if outdated {
+                        "Re-register wallet on device"
+                    } else {
+                        "Register wallet on device"
+                    },
+                )
+                .on_press(Message::Spend(SpendTxMessage::RegisterWallet(i))),
+            ),
+        ),
+    )
+    .padding(10)
+    .width(Length::Fill)
+    .style(theme::Container::Card(theme::Card::Simple))
+    .into()
+}
+
+/// A device which failed to sign, for instance because it was unplugged while signing. It can be
+/// selected to try again.
+pub fn hw_list_view_sign_failed(i: usize, hw: &HardwareWallet, error: String) -> Element<Message> {
+    match hw {
+        HardwareWallet::Supported {
+            kind,
+            version,
+            fingerprint,
+            alias,
+            ..
+        } => Container::new(
+            Button::new(hw::sign_error_hardware_wallet(
+                Some(kind),
+                version.as_ref(),
+                fingerprint,
+                alias.as_ref(),
+                "Failed to sign",
+                error,
+            ))
+            .on_press(Message::SelectHardwareWallet(i))
+            .style(theme::Button::Border)
+            .width(Length::Fill),
+        )
+        .width(Length::Fill)
+        .style(theme::Container::Card(theme::Card::Simple))
+        .into(),
+        _ => hw_list_view(i, hw, false, false),
+    }
+}
+
+/// A device which failed to sign and is not connected anymore.
+pub fn disconnected_hw_sign_failed<'a>(
+    fingerprint: Fingerprint,
+    alias: Option<&'a String>,
+    error: String,
+) -> Element<'a, Message> {
+    Container::new(hw::sign_error_hardware_wallet(
+        Option::<&DeviceKind>::None,
+        Option::<&Version>::None,
+        fingerprint,
+        alias,
+        "Disconnected",
+        error,
+    ))
+    .width(Length::Fill)
+    .style(theme::Container::Card(theme::Card::Simple))
+    .into()
+}
+
 pub fn hw_list_view_for_registration(
     i: usize,
     hw: &HardwareWallet,
@@ -109,7 +235,7 @@ pub fn hw_list_view_for_registration(
                     alias.as_ref(),
                 )
             } else {
-                hw::supported_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
+                supported_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
             }
         }
         HardwareWallet::Unsupported {
@@ -148,11 +274,11 @@ pub fn hw_list_view_for_registration(
         .into()
 }
 
-pub fn hw_list_view_verify_address(
+pub fn hw_list_view_verify_address<'a>(
     i: usize,
-    hw: &HardwareWallet,
-    chosen: bool,
-) -> Element<Message> {
+    hw: &'a HardwareWallet,
+    status: Option<&'a VerificationStatus>,
+) -> Element<'a, Message> {
     let (content, selectable) = match hw {
         HardwareWallet::Supported {
             kind,
@@ -161,32 +287,53 @@ pub fn hw_list_view_verify_address(
             alias,
             ..
         } => {
-            if chosen {
-                (
-                    hw::processing_hardware_wallet(
-                        kind,
-                        version.as_ref(),
-                        fingerprint,
-                        alias.as_ref(),
-                    ),
-                    false,
-                )
+            if !supports_address_display(kind) || status == Some(&VerificationStatus::NotSupported)
+            {
+                (hw::unimplemented_method_hardware_wallet(
+                    kind.to_string(),
+                    version.as_ref(),
+                    fingerprint,
+                    "Not supported: Liana cannot request the device to display the address. \n The verification must be done manually with the device control."
+                ), false)
             } else {
-                match kind {
-                    DeviceKind::Specter | DeviceKind::SpecterSimulator => {
-                        (hw::unimplemented_method_hardware_wallet(
-                            kind.to_string(),
+                match status {
+                    Some(VerificationStatus::Waiting) => (
+                        hw::processing_hardware_wallet(
+                            kind,
+                            version.as_ref(),
+                            fingerprint,
+                            alias.as_ref(),
+                        ),
+                        false,
+                    ),
+                    Some(VerificationStatus::Confirmed) => (
+                        hw::address_verified_hardware_wallet(
+                            kind,
+                            version.as_ref(),
+                            fingerprint,
+                            alias.as_ref(),
+                        ),
+                        true,
+                    ),
+                    Some(VerificationStatus::Rejected(reason)) => (
+                        hw::address_rejected_hardware_wallet(
+                            kind,
                             version.as_ref(),
                             fingerprint,
-                            "Liana cannot request the device to display the address. \n The verification must be done manually with the device control."
-                        ), false)
-                    }
-                    _ => (hw::supported_hardware_wallet(
-                        kind,
-                        version.as_ref(),
-                        fingerprint,
-                        alias.as_ref(),
-                    ), true),
+                            alias.as_ref(),
+                            reason.as_str(),
+                        ),
+                        true,
+                    ),
+                    _ => (
+                        supported_hardware_wallet(
+                            kind,
+                            version.as_ref(),
+                            fingerprint,
+                            alias.as_ref(),
+                        ),
+                        true,
+                    ),
                 }
             }
         }
@@ -232,3 +379,29 @@ pub fn hw_list_view_verify_address(
         .style(theme::Container::Card(theme::Card::Simple))
         .into()
 }
+
+/// The wallet about to be registered on a device, for the user to check the policy displayed
+/// by the device against it.
+pub fn wallet_to_register<'a>(name: &str, descriptor: &LianaDescriptor) -> Element<'a, Message> {
+    Column::new()
+        .spacing(5)
+        .push(
+            text(format!(
+                "The device will ask you to confirm the registration of the wallet '{}'. \
+                 Check the policy it displays matches this descriptor:",
+                name
+            ))
+            .small(),
+        )
+        .push(
+            scrollable(
+                Column::new()
+                    .push(text(descriptor.to_string()).small())
+                    .push(Space::with_height(Length::Fixed(5.0))),
+            )
+            .direction(scrollable::Direction::Horizontal(
+                scrollable::Properties::new().width(5).scroller_width(5),
+            )),
+        )
+        .into()
+}
@@ -47,13 +47,20 @@ pub fn label_editing(
     label: &form::Value<String>,
     size: u16,
 ) -> Element<view::Message> {
+    // A label of a valid length is only invalid if the daemon failed to store it.
+    let warning = if label.value.len() > 100 {
+        "Invalid label length, cannot be superior to 100"
+    } else {
+        "The label could not be saved"
+    };
     let e: Element<view::LabelMessage> = Container::new(
         row!(
             form::Form::new("Label", label, view::LabelMessage::Edited)
-                .warning("Invalid label length, cannot be superior to 100")
+                .warning(warning)
                 .size(size)
                 .padding(10),
-            if label.valid {
+            // Saving can be retried after the daemon failed to store the label.
+            if label.value.len() <= 100 {
                 button::secondary(None, "Save").on_press(view::message::LabelMessage::Confirm)
             } else {
                 button::secondary(None, "Save")
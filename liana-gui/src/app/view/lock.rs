@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use iced::{widget::Space, Alignment, Length};
+
+use liana::miniscript::bitcoin::bip32::Fingerprint;
+
+use liana_ui::{
+    color,
+    component::{button, card, text::*},
+    icon, theme,
+    widget::*,
+};
+
+use crate::{
+    app::{
+        lock::{passphrase_input_id, pin_input_id},
+        view::message::{LockMessage, Message, SignerUnlockMessage},
+    },
+    i18n::{tr, tr_args},
+};
+
+/// The screen replacing the whole application while it is locked for inactivity.
+pub fn lock_screen<'a>(
+    pin: &'a str,
+    failed: bool,
+    retry_in: Option<Duration>,
+) -> Element<'a, Message> {
+    Container::new(
+        Column::new()
+            .spacing(20)
+            .align_items(Alignment::Center)
+            .push(icon::lock_icon().size(50))
+            .push(h3(tr("lock.title")))
+            .push(pin_prompt(pin, failed, retry_in, false))
+            .push(p2_regular(tr("lock.forgotten_pin")).style(color::GREY_3)),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x()
+    .center_y()
+    .style(theme::Container::Background)
+    .into()
+}
+
+/// The card asking for the PIN, to unlock the application or to confirm an action, in which
+/// case it can be cancelled.
+pub fn pin_prompt<'a>(
+    pin: &'a str,
+    failed: bool,
+    retry_in: Option<Duration>,
+    cancellable: bool,
+) -> Element<'a, Message> {
+    let mut unlock = button::primary(None, tr("lock.unlock")).width(Length::Fixed(150.0));
+    if retry_in.is_none() && !pin.is_empty() {
+        unlock = unlock.on_press(Message::Lock(LockMessage::Unlock));
+    }
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push_maybe(if cancellable {
+                Some(p1_bold(tr("lock.confirm_with_pin")))
+            } else {
+                None
+            })
+            .push(
+                TextInput::new(tr("lock.pin"), pin)
+                    .id(pin_input_id())
+                    .secure(true)
+                    .on_input(|pin| Message::Lock(LockMessage::PinEdited(pin)))
+                    .on_submit(Message::Lock(LockMessage::Unlock))
+                    .size(P1_SIZE)
+                    .padding(10),
+            )
+            .push_maybe(if let Some(delay) = retry_in {
+                Some(
+                    p2_regular(tr_args(
+                        "lock.retry_in",
+                        // Round up, not to display a zero delay.
+                        &[("seconds", &(delay.as_secs() + 1).to_string())],
+                    ))
+                    .style(color::RED),
+                )
+            } else if failed {
+                Some(p2_regular(tr("lock.wrong_pin")).style(color::RED))
+            } else {
+                None
+            })
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(Space::with_width(Length::Fill))
+                    .push_maybe(if cancellable {
+                        Some(
+                            button::secondary(None, tr("common.cancel"))
+                                .width(Length::Fixed(150.0))
+                                .on_press(Message::Lock(LockMessage::Cancel)),
+                        )
+                    } else {
+                        None
+                    })
+                    .push(unlock),
+            ),
+    )
+    .width(Length::Fixed(400.0))
+    .into()
+}
+
+/// The card asking for the passphrase of the hot signer. The key derived using it is checked
+/// against the fingerprint in the descriptor.
+pub fn passphrase_prompt<'a>(
+    fingerprint: Fingerprint,
+    passphrase: &'a str,
+    failed: bool,
+    retry_in: Option<Duration>,
+) -> Element<'a, Message> {
+    let mut unlock = button::primary(None, tr("lock.unlock")).width(Length::Fixed(150.0));
+    if retry_in.is_none() && !passphrase.is_empty() {
+        unlock = unlock.on_press(Message::SignerUnlock(SignerUnlockMessage::Unlock));
+    }
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push(p1_bold(tr("lock.signer_title")))
+            .push(
+                p2_regular(tr_args(
+                    "lock.signer_passphrase",
+                    &[("fingerprint", &fingerprint.to_string())],
+                ))
+                .style(color::GREY_3),
+            )
+            .push(
+                TextInput::new(tr("lock.passphrase"), passphrase)
+                    .id(passphrase_input_id())
+                    .secure(true)
+                    .on_input(|passphrase| {
+                        Message::SignerUnlock(SignerUnlockMessage::PassphraseEdited(passphrase))
+                    })
+                    .on_submit(Message::SignerUnlock(SignerUnlockMessage::Unlock))
+                    .size(P1_SIZE)
+                    .padding(10),
+            )
+            .push_maybe(if let Some(delay) = retry_in {
+                Some(
+                    p2_regular(tr_args(
+                        "lock.retry_in",
+                        &[("seconds", &(delay.as_secs() + 1).to_string())],
+                    ))
+                    .style(color::RED),
+                )
+            } else if failed {
+                Some(p2_regular(tr("lock.wrong_passphrase")).style(color::RED))
+            } else {
+                None
+            })
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        button::secondary(None, tr("common.cancel"))
+                            .width(Length::Fixed(150.0))
+                            .on_press(Message::SignerUnlock(SignerUnlockMessage::Cancel)),
+                    )
+                    .push(unlock),
+            ),
+    )
+    .width(Length::Fixed(400.0))
+    .into()
+}
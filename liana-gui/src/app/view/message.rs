@@ -1,5 +1,16 @@
-use crate::{app::menu::Menu, export::ExportMessage, node::bitcoind::RpcAuthType};
+use crate::{
+    app::{
+        menu::Menu,
+        settings::{HotSignerUnlock, NotificationKind},
+        shortcut::Action,
+    },
+    daemon::model::FeeratePreset,
+    export::ExportMessage,
+    i18n::Language,
+    node::bitcoind::RpcAuthType,
+};
 use liana::miniscript::bitcoin::{bip32::Fingerprint, OutPoint};
+use liana_ui::component::{amount::BitcoinDisplayUnit, amount_input::AmountEdited};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -9,7 +20,15 @@ pub enum Message {
     Close,
     Select(usize),
     SelectPayment(OutPoint),
+    SelectDraft(i64),
+    DeleteDraft(i64),
+    /// Create a draft merging the oldest confirmed coins of the wallet.
+    Consolidate,
+    DismissNotification(usize),
     Label(Vec<String>, LabelMessage),
+    CoinTag(OutPoint, CoinTagMessage),
+    Coins(CoinsMessage),
+    Transactions(TransactionsMessage),
     Settings(SettingsMessage),
     CreateSpend(CreateSpendMessage),
     ImportSpend(ImportSpendMessage),
@@ -17,9 +36,25 @@ pub enum Message {
     Next,
     Previous,
     SelectHardwareWallet(usize),
+    VerifyAddressOnDevices,
     CreateRbf(CreateRbfMessage),
+    CreateCpfp(CreateCpfpMessage),
     ShowQrCode(usize),
+    ToggleDescriptorQrCode,
+    /// The amount requested in the QR code of an address was edited.
+    QrCodeAmountEdited(AmountEdited),
     Export(ExportMessage),
+    CommandPalette(CommandPaletteMessage),
+    Lock(LockMessage),
+    SignerUnlock(SignerUnlockMessage),
+    /// Hide or show all the amounts until the application is closed.
+    ToggleAmountsHidden,
+    /// Stop the internal daemon and start a new one, after it crashed.
+    RestartDaemon,
+    /// Restart the managed bitcoind, after it became unreachable.
+    RestartNode,
+    /// Close the wallet and go back to the launcher to open another one.
+    SwitchWallet,
 }
 
 #[derive(Debug, Clone)]
@@ -29,18 +64,97 @@ pub enum LabelMessage {
     Confirm,
 }
 
+#[derive(Debug, Clone)]
+pub enum CommandPaletteMessage {
+    Edited(String),
+    Run(Action),
+    Submit,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub enum LockMessage {
+    PinEdited(String),
+    Unlock,
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub enum SignerUnlockMessage {
+    PassphraseEdited(String),
+    Unlock,
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub enum CoinTagMessage {
+    Edit,
+    Edited(String),
+    Cancel,
+    Confirm,
+}
+
+#[derive(Debug, Clone)]
+pub enum TransactionsMessage {
+    FilterEdited(String),
+    /// Apply the edited filter once it has not changed for a short while.
+    ApplyFilter,
+}
+
+#[derive(Debug, Clone)]
+pub enum CoinsMessage {
+    /// Sort the coins by this column, or reverse the order if they already are.
+    SortBy(CoinsSortKey),
+    FilterEdited(String),
+    /// Apply the edited filter once it has not changed for a short while.
+    ApplyFilter,
+    ToggleFilter(CoinsFilter),
+    /// Check or uncheck a coin, along with the ones listed since the last checked one if shift
+    /// is held.
+    Check(OutPoint),
+    CheckAll(bool),
+    ShiftChanged(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoinsSortKey {
+    Age,
+    Amount,
+    Label,
+    /// Blocks left before a recovery path becomes available.
+    Timelock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoinsFilter {
+    Unconfirmed,
+    Change,
+    /// A recovery path is already available.
+    Expired,
+}
+
 #[derive(Debug, Clone)]
 pub enum CreateSpendMessage {
     AddRecipient,
     BatchLabelEdited(String),
     DeleteRecipient(usize),
     SelectCoin(usize),
+    SelectAllCoins,
+    ToggleCoinControl,
+    ConfirmMixedTags(bool),
     RecipientEdited(usize, &'static str, String),
     FeerateEdited(String),
+    FeeratePresetSelected(FeeratePreset),
     SelectPath(usize),
+    /// Spend through the recovery path with this timelock, or the primary path if `None`.
+    SelectSpendPath(Option<u16>),
     Generate,
     SendMaxToRecipient(usize),
     Clear,
+    /// Fill the form with the spend saved on disk.
+    ResumeDraft,
+    /// Forget the spend saved on disk.
+    DiscardDraft,
 }
 
 #[derive(Debug, Clone)]
@@ -48,6 +162,9 @@ pub enum ImportSpendMessage {
     Import,
     PsbtEdited(String),
     Confirm,
+    ScanQrCode,
+    SelectCamera(usize),
+    CancelScan,
 }
 
 #[derive(Debug, Clone)]
@@ -58,8 +175,19 @@ pub enum SpendTxMessage {
     Save,
     Confirm,
     Cancel,
-    SelectHotSigner,
+    SelectHotSigner(Fingerprint),
+    SaveForLater,
+    SaveDraft,
+    DraftLabelEdited(String),
+    ScheduleBroadcast,
+    ScheduleByHeight(bool),
+    ScheduleEdited(String),
+    CancelSchedule,
     EditPsbt,
+    /// Choose PSBT files to merge the signatures of into the spend.
+    ImportSignatures,
+    /// Register the wallet on the device at this index of the list, for it to be able to sign.
+    RegisterWallet(usize),
     PsbtEdited(String),
     Next,
 }
@@ -70,15 +198,39 @@ pub enum SettingsMessage {
     BitcoindSettings(SettingsEditMessage),
     ElectrumSettings(SettingsEditMessage),
     RescanSettings(SettingsEditMessage),
+    RevertBitcoinBackendSettings,
+    ManagedBitcoindSection,
+    ManagedBitcoind(ManagedBitcoindMessage),
     EditRemoteBackendSettings,
     RemoteBackendSettings(RemoteBackendSettingsMessage),
     EditWalletSettings,
     AboutSection,
+    ProfileSection,
+    ExportProfile,
+    ImportProfile,
     RegisterWallet,
+    ExportPolicyDiagram,
     FingerprintAliasEdited(Fingerprint, String),
+    NotificationToggled(NotificationKind, bool),
+    BitcoinUnitSelected(BitcoinDisplayUnit),
+    HotSignerUnlockSelected(HotSignerUnlock),
+    LanguageSelected(Language),
+    AutoLockTimeoutEdited(String),
+    AutoLockPinEdited(String),
+    EnableAutoLock,
+    DisableAutoLock,
     Save,
 }
 
+#[derive(Debug, Clone)]
+pub enum ManagedBitcoindMessage {
+    PruneEdited(String),
+    SavePrune,
+    DatadirEdited(String),
+    MoveDatadir,
+    Upgrade,
+}
+
 #[derive(Debug, Clone)]
 pub enum RemoteBackendSettingsMessage {
     EditInvitationEmail(String),
@@ -90,6 +242,8 @@ pub enum SettingsEditMessage {
     Select,
     FieldEdited(&'static str, String),
     BitcoindRpcAuthTypeSelected(RpcAuthType),
+    SkipCertificateValidation(bool),
+    TestConnection,
     Cancel,
     Confirm,
     Clipboard(String),
@@ -102,3 +256,11 @@ pub enum CreateRbfMessage {
     Cancel,
     Confirm,
 }
+
+#[derive(Debug, Clone)]
+pub enum CreateCpfpMessage {
+    New,
+    FeerateEdited(String),
+    Cancel,
+    Confirm,
+}
@@ -6,6 +6,8 @@ pub mod coins;
 pub mod export;
 pub mod home;
 pub mod hw;
+pub mod lock;
+pub mod policy;
 pub mod psbt;
 pub mod psbts;
 pub mod receive;
@@ -18,22 +20,35 @@ pub use message::*;
 use warning::warn;
 
 use iced::{
-    widget::{column, row, scrollable, Space},
-    Length,
+    widget::{column, row, scrollable, tooltip, Space},
+    Alignment, Length,
 };
 
+use chrono::{DateTime, Local, Utc};
+use liana::miniscript::bitcoin::Txid;
+
 use liana_ui::{
     color,
-    component::{button, text::*},
+    component::{amount::amounts_hidden, button, card, text::*},
+    icon,
     icon::{
-        coins_icon, cross_icon, history_icon, home_icon, receive_icon, send_icon, settings_icon,
+        coins_icon, cross_icon, eye_icon, eye_slash_icon, history_icon, home_icon, receive_icon,
+        send_icon, settings_icon, wallet_icon,
     },
     image::*,
     theme,
     widget::*,
 };
 
-use crate::app::{cache::Cache, error::Error, menu::Menu};
+use crate::{
+    app::{
+        cache::Cache,
+        error::Error,
+        menu::Menu,
+        shortcut::{palette_input_id, CommandPalette, Shortcuts},
+    },
+    i18n::{tr, tr_args},
+};
 
 fn menu_green_bar<'a, T: 'a>() -> Container<'a, T> {
     Container::new(Space::with_width(Length::Fixed(2.0)))
@@ -44,39 +59,39 @@ fn menu_green_bar<'a, T: 'a>() -> Container<'a, T> {
 pub fn sidebar<'a>(menu: &Menu, cache: &'a Cache) -> Container<'a, Message> {
     let home_button = if *menu == Menu::Home {
         row!(
-            button::menu_active(Some(home_icon()), "Home")
+            button::menu_active(Some(home_icon()), tr("menu.home"))
                 .on_press(Message::Reload)
                 .width(iced::Length::Fill),
             menu_green_bar(),
         )
     } else {
-        row!(button::menu(Some(home_icon()), "Home")
+        row!(button::menu(Some(home_icon()), tr("menu.home"))
             .on_press(Message::Menu(Menu::Home))
             .width(iced::Length::Fill),)
     };
 
     let transactions_button = if *menu == Menu::Transactions {
         row!(
-            button::menu_active(Some(history_icon()), "Transactions")
+            button::menu_active(Some(history_icon()), tr("menu.transactions"))
                 .on_press(Message::Reload)
                 .width(iced::Length::Fill),
             menu_green_bar()
         )
     } else {
-        row!(button::menu(Some(history_icon()), "Transactions")
+        row!(button::menu(Some(history_icon()), tr("menu.transactions"))
             .on_press(Message::Menu(Menu::Transactions))
             .width(iced::Length::Fill))
     };
 
     let coins_button = if *menu == Menu::Coins {
         row!(
-            button::menu_active(Some(coins_icon()), "Coins")
+            button::menu_active(Some(coins_icon()), tr("menu.coins"))
                 .on_press(Message::Reload)
                 .width(iced::Length::Fill),
             menu_green_bar()
         )
     } else {
-        row!(button::menu(Some(coins_icon()), "Coins")
+        row!(button::menu(Some(coins_icon()), tr("menu.coins"))
             .style(theme::Button::Menu(false))
             .on_press(Message::Menu(Menu::Coins))
             .width(iced::Length::Fill))
@@ -84,52 +99,56 @@ pub fn sidebar<'a>(menu: &Menu, cache: &'a Cache) -> Container<'a, Message> {
 
     let psbt_button = if *menu == Menu::PSBTs {
         row!(
-            button::menu_active(Some(history_icon()), "PSBTs")
+            button::menu_active(Some(history_icon()), tr("menu.psbts"))
                 .on_press(Message::Reload)
                 .width(iced::Length::Fill),
             menu_green_bar()
         )
     } else {
-        row!(button::menu(Some(history_icon()), "PSBTs")
+        row!(button::menu(Some(history_icon()), tr("menu.psbts"))
             .on_press(Message::Menu(Menu::PSBTs))
             .width(iced::Length::Fill))
     };
 
     let spend_button = if *menu == Menu::CreateSpendTx {
         row!(
-            button::menu_active(Some(send_icon()), "Send")
+            button::menu_active(Some(send_icon()), tr("menu.send"))
                 .on_press(Message::Reload)
                 .width(iced::Length::Fill),
             menu_green_bar()
         )
+    } else if !cache.backend_status.is_reachable() {
+        row!(unavailable_while_unreachable(
+            button::menu(Some(send_icon()), tr("menu.send")).width(iced::Length::Fill)
+        ))
     } else {
-        row!(button::menu(Some(send_icon()), "Send")
+        row!(button::menu(Some(send_icon()), tr("menu.send"))
             .on_press(Message::Menu(Menu::CreateSpendTx))
             .width(iced::Length::Fill))
     };
 
     let receive_button = if *menu == Menu::Receive {
         row!(
-            button::menu_active(Some(receive_icon()), "Receive")
+            button::menu_active(Some(receive_icon()), tr("menu.receive"))
                 .on_press(Message::Reload)
                 .width(iced::Length::Fill),
             menu_green_bar()
         )
     } else {
-        row!(button::menu(Some(receive_icon()), "Receive")
+        row!(button::menu(Some(receive_icon()), tr("menu.receive"))
             .on_press(Message::Menu(Menu::Receive))
             .width(iced::Length::Fill))
     };
 
     let settings_button = if *menu == Menu::Settings {
         row!(
-            button::menu_active(Some(settings_icon()), "Settings")
+            button::menu_active(Some(settings_icon()), tr("menu.settings"))
                 .on_press(Message::Menu(Menu::Settings))
                 .width(iced::Length::Fill),
             menu_green_bar()
         )
     } else {
-        row!(button::menu(Some(settings_icon()), "Settings")
+        row!(button::menu(Some(settings_icon()), tr("menu.settings"))
             .on_press(Message::Menu(Menu::Settings))
             .width(iced::Length::Fill))
     };
@@ -163,6 +182,11 @@ pub fn sidebar<'a>(menu: &Menu, cache: &'a Cache) -> Container<'a, Message> {
                                 .padding(5)
                                 .style(theme::Pill::Simple)
                         }))
+                        .push(
+                            button::menu(Some(wallet_icon()), tr("menu.switch_wallet"))
+                                .on_press(Message::SwitchWallet)
+                                .width(iced::Length::Fill),
+                        )
                         .push(settings_button),
                 )
                 .height(Length::Shrink),
@@ -186,6 +210,7 @@ pub fn dashboard<'a, T: Into<Element<'a, Message>>>(
         .push(
             Column::new()
                 .push(warn(warning))
+                .push(header())
                 .push(
                     Container::new(scrollable(row!(
                         Space::with_width(Length::FillPortion(1)),
@@ -206,6 +231,30 @@ pub fn dashboard<'a, T: Into<Element<'a, Message>>>(
         .into()
 }
 
+/// The toggle hiding the amounts, in the top right corner of the dashboard.
+fn header<'a>() -> Element<'a, Message> {
+    let (icon, help) = if amounts_hidden() {
+        (eye_slash_icon(), tr("common.show_amounts"))
+    } else {
+        (eye_icon(), tr("common.hide_amounts"))
+    };
+    Container::new(
+        Row::new().push(Space::with_width(Length::Fill)).push(
+            tooltip::Tooltip::new(
+                Button::new(icon)
+                    .style(theme::Button::Transparent)
+                    .on_press(Message::ToggleAmountsHidden),
+                help,
+                tooltip::Position::Left,
+            )
+            .style(theme::Container::Card(theme::Card::Simple)),
+        ),
+    )
+    .padding(10)
+    .style(theme::Container::Background)
+    .into()
+}
+
 pub fn modal<'a, T: Into<Element<'a, Message>>, F: Into<Element<'a, Message>>>(
     is_previous: bool,
     warning: Option<&Error>,
@@ -246,3 +295,196 @@ fn modal_section<'a, T: 'a>(menu: Container<'a, T>) -> Container<'a, T> {
         .width(Length::Fill)
         .height(Length::Fill)
 }
+
+/// The command palette, listing the actions matching the query along with their shortcut.
+pub fn command_palette<'a>(
+    palette: &'a CommandPalette,
+    shortcuts: &Shortcuts,
+) -> Element<'a, Message> {
+    let matches = palette.matches();
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push(
+                TextInput::new(tr("palette.placeholder"), &palette.query)
+                    .id(palette_input_id())
+                    .on_input(|query| Message::CommandPalette(CommandPaletteMessage::Edited(query)))
+                    .on_submit(Message::CommandPalette(CommandPaletteMessage::Submit))
+                    .size(P1_SIZE)
+                    .padding(10),
+            )
+            .push(if matches.is_empty() {
+                Column::new().push(p1_regular(tr("palette.no_match")).style(color::GREY_3))
+            } else {
+                matches.into_iter().enumerate().fold(
+                    Column::new().spacing(5),
+                    |col, (i, action)| {
+                        col.push(
+                            Button::new(
+                                Row::new()
+                                    .align_items(Alignment::Center)
+                                    .push(p1_regular(action.label()).width(Length::Fill))
+                                    .push_maybe(
+                                        shortcuts
+                                            .key(action)
+                                            .map(|key| p2_regular(key).style(color::GREY_3)),
+                                    ),
+                            )
+                            .padding(10)
+                            .width(Length::Fill)
+                            .style(theme::Button::Menu(i == palette.selected))
+                            .on_press(Message::CommandPalette(CommandPaletteMessage::Run(action))),
+                        )
+                    },
+                )
+            }),
+    )
+    .width(Length::Fixed(500.0))
+    .into()
+}
+
+/// The card blocking the application after the internal daemon crashed, until it is restarted.
+pub fn daemon_crash(error: &Error) -> Element<Message> {
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push(p1_bold(tr("backend.crashed")))
+            .push(p2_regular(error.to_string()).style(color::GREY_3))
+            .push(Row::new().push(Space::with_width(Length::Fill)).push(
+                button::primary(None, tr("backend.restart")).on_press(Message::RestartDaemon),
+            )),
+    )
+    .width(Length::Fixed(500.0))
+    .into()
+}
+
+/// The banner displayed while the daemon can't reach the Bitcoin backend. The managed bitcoind,
+/// if any, can be restarted from it.
+pub fn backend_unreachable_banner<'a>(since: u32, can_restart: bool) -> Element<'a, Message> {
+    let since = DateTime::<Utc>::from_timestamp(since as i64, 0)
+        .map(|date| date.with_timezone(&Local).format("%H:%M").to_string())
+        .unwrap_or_default();
+    Container::new(
+        Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(icon::warning_icon())
+            .push(text(tr_args(
+                "backend.node_unreachable",
+                &[("time", &since)],
+            )))
+            .push_maybe(if can_restart {
+                Some(
+                    button::secondary(Some(icon::arrow_repeat()), tr("backend.restart_node"))
+                        .on_press(Message::RestartNode),
+                )
+            } else {
+                None
+            }),
+    )
+    .padding(5)
+    .width(Length::Fill)
+    .center_x()
+    .style(theme::Container::Banner)
+    .into()
+}
+
+/// Wrap an action which is disabled while the Bitcoin backend is unreachable, to explain why.
+pub fn unavailable_while_unreachable<'a, T: 'a>(
+    content: impl Into<Element<'a, T>>,
+) -> Element<'a, T> {
+    tooltip::Tooltip::new(
+        content,
+        tr("backend.action_unavailable"),
+        tooltip::Position::Bottom,
+    )
+    .style(theme::Container::Card(theme::Card::Simple))
+    .into()
+}
+
+/// Toasts notifying the outcome of the scheduled broadcasts, until they are dismissed.
+pub fn scheduled_broadcast_toasts(
+    notifications: &[(Txid, Option<String>)],
+) -> Vec<Element<'_, Message>> {
+    notifications
+        .iter()
+        .enumerate()
+        .map(|(i, (txid, error))| {
+            Container::new(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Column::new()
+                            .spacing(5)
+                            .width(Length::Fill)
+                            .push(
+                                p1_bold(if error.is_some() {
+                                    "Scheduled broadcast failed"
+                                } else {
+                                    "Scheduled transaction broadcast"
+                                })
+                                .style(color::LIGHT_BLACK),
+                            )
+                            .push(p2_regular(txid.to_string()).style(color::LIGHT_BLACK))
+                            .push_maybe(
+                                error
+                                    .as_ref()
+                                    .map(|e| p2_regular(e).style(color::LIGHT_BLACK)),
+                            ),
+                    )
+                    .push(
+                        Button::new(cross_icon().style(color::LIGHT_BLACK))
+                            .style(theme::Button::Transparent)
+                            .on_press(Message::DismissNotification(i)),
+                    ),
+            )
+            .padding(15)
+            .max_width(400.0)
+            .style(theme::Container::Card(if error.is_some() {
+                theme::Card::Error
+            } else {
+                theme::Card::Simple
+            }))
+            .into()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    /// Amounts are hidden by the amount component of liana-ui, so views must not format them by
+    /// themselves.
+    #[test]
+    fn amounts_formatted_by_component() {
+        fn check_dir(dir: &Path) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    check_dir(&path);
+                } else if path.extension().map_or(false, |ext| ext == "rs") {
+                    let content = fs::read_to_string(&path).unwrap();
+                    for pattern in [
+                        ".to_btc()",
+                        ".to_sat().to_string()",
+                        ".display_in(",
+                        "Denomination::",
+                    ] {
+                        assert!(
+                            !content.contains(pattern),
+                            "{} formats an amount with '{}', use liana_ui::component::amount \
+                             instead for it to be hidden along with the others.",
+                            path.display(),
+                            pattern
+                        );
+                    }
+                }
+            }
+        }
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        check_dir(&src.join("app").join("view"));
+        check_dir(&src.join("installer").join("view"));
+    }
+}
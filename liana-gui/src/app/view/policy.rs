@@ -0,0 +1,81 @@
+use liana::{
+    descriptors::{LianaPolicy, PathInfo},
+    miniscript::bitcoin::bip32::Fingerprint,
+};
+
+use liana_ui::component::policy_diagram::{PolicyDiagram, SpendingPath};
+
+// Average number of minutes per month, as used to display coins expiration.
+const MINUTES_PER_MONTH: u32 = 43830;
+
+/// Build the diagram of the wallet policy. Keys are named after their alias, if any, or their
+/// fingerprint otherwise.
+pub fn diagram<F>(policy: &LianaPolicy, alias: F) -> PolicyDiagram
+where
+    F: Fn(&Fingerprint) -> Option<String>,
+{
+    let spending_path = |path: &PathInfo| {
+        let (threshold, keys) = path.thresh_origins();
+        // The iteration over an HashMap keys can have a different order at each refresh
+        let mut keys: Vec<Fingerprint> = keys.into_keys().collect();
+        keys.sort();
+        SpendingPath {
+            threshold,
+            keys: keys
+                .iter()
+                .map(|fg| {
+                    alias(fg)
+                        .filter(|a| !a.is_empty())
+                        .unwrap_or_else(|| fg.to_string())
+                })
+                .collect(),
+        }
+    };
+    PolicyDiagram::new(
+        spending_path(policy.primary_path()),
+        policy
+            .recovery_paths()
+            .iter()
+            .map(|(sequence, path)| (timelock_label(*sequence), spending_path(path)))
+            .collect(),
+    )
+}
+
+/// A rough estimate of when a recovery path with this relative timelock becomes available.
+fn timelock_label(sequence: u16) -> String {
+    // Blocks are mined every 10 minutes on average.
+    let minutes = sequence as u32 * 10;
+    let rounded_div = |n: u32, d: u32| (n + d / 2) / d;
+    let (n, unit) = if minutes < 60 {
+        (minutes, "minute")
+    } else if minutes < 24 * 60 {
+        (rounded_div(minutes, 60), "hour")
+    } else if minutes < MINUTES_PER_MONTH {
+        (rounded_div(minutes, 24 * 60), "day")
+    } else {
+        let months = rounded_div(minutes, MINUTES_PER_MONTH);
+        if months % 12 == 0 {
+            (months / 12, "year")
+        } else {
+            (months, "month")
+        }
+    };
+    format!("after ~{} {}{}", n, unit, if n > 1 { "s" } else { "" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_timelock_label() {
+        assert_eq!(timelock_label(1), "after ~10 minutes");
+        assert_eq!(timelock_label(6), "after ~1 hour");
+        assert_eq!(timelock_label(144), "after ~1 day");
+        assert_eq!(timelock_label(1000), "after ~7 days");
+        assert_eq!(timelock_label(4383), "after ~1 month");
+        assert_eq!(timelock_label(26298), "after ~6 months");
+        assert_eq!(timelock_label(52596), "after ~1 year");
+        assert_eq!(timelock_label(65535), "after ~15 months");
+    }
+}
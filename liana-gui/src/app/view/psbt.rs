@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use iced::{
     widget::{scrollable, tooltip, Space},
@@ -6,7 +6,7 @@ use iced::{
 };
 
 use liana::{
-    descriptors::{LianaPolicy, PathInfo, PathSpendInfo},
+    descriptors::{LianaDescriptor, LianaPolicy, PathInfo, PathSpendInfo},
     miniscript::bitcoin::{
         bip32::Fingerprint, blockdata::transaction::TxOut, Address, Network, OutPoint, Transaction,
         Txid,
@@ -31,8 +31,19 @@ use crate::{
         cache::Cache,
         error::Error,
         menu::Menu,
-        view::{dashboard, hw::hw_list_view, label, message::*, warning::warn},
+        settings::BroadcastSchedule,
+        view::{
+            dashboard,
+            hw::{
+                disconnected_hw_sign_failed, hw_list_view, hw_list_view_sign_failed,
+                hw_list_view_to_register, wallet_to_register,
+            },
+            label,
+            message::*,
+            warning::warn,
+        },
     },
+    camera::{multipart::Progress, Camera},
     daemon::model::{Coin, SpendStatus, SpendTx},
     hw::HardwareWallet,
 };
@@ -42,6 +53,7 @@ pub fn psbt_view<'a>(
     cache: &'a Cache,
     tx: &'a SpendTx,
     saved: bool,
+    schedule: Option<BroadcastSchedule>,
     desc_info: &'a LianaPolicy,
     key_aliases: &'a HashMap<Fingerprint, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
@@ -64,15 +76,34 @@ pub fn psbt_view<'a>(
                     } else {
                         None
                     })
+                    .push_maybe(schedule.map(|_| badge::scheduled()))
                     .push_maybe(match tx.status {
                         SpendStatus::Deprecated => Some(badge::deprecated()),
+                        SpendStatus::Superseded => Some(badge::superseded()),
                         SpendStatus::Broadcast => Some(badge::unconfirmed()),
                         SpendStatus::Spent => Some(badge::spent()),
                         _ => None,
                     }),
             )
-            .push(spend_header(tx, labels_editing))
-            .push(spend_overview_view(tx, desc_info, key_aliases))
+            .push(spend_header(tx, labels_editing, cache.bitcoin_unit))
+            .push(spend_overview_view(
+                tx,
+                desc_info,
+                key_aliases,
+                cache.backend_status.is_reachable(),
+            ))
+            .push_maybe(if tx.status == SpendStatus::Pending {
+                Some(signing_progress(tx, desc_info, key_aliases))
+            } else {
+                None
+            })
+            .push_maybe(
+                if saved && tx.status == SpendStatus::Pending && tx.path_ready().is_some() {
+                    Some(schedule_view(schedule))
+                } else {
+                    None
+                },
+            )
             .push(
                 Column::new()
                     .spacing(20)
@@ -81,6 +112,7 @@ pub fn psbt_view<'a>(
                         &tx.psbt.unsigned_tx,
                         &tx.labels,
                         labels_editing,
+                        cache.bitcoin_unit,
                     ))
                     .push(outputs_view(
                         &tx.psbt.unsigned_tx,
@@ -89,6 +121,7 @@ pub fn psbt_view<'a>(
                         &tx.labels,
                         labels_editing,
                         tx.is_single_payment().is_some(),
+                        cache.bitcoin_unit,
                     )),
             )
             .push(if saved {
@@ -140,6 +173,160 @@ pub fn save_action<'a>(warning: Option<&Error>, saved: bool) -> Element<'a, Mess
     }
 }
 
+pub fn save_draft_action<'a>(
+    warning: Option<&Error>,
+    saved: bool,
+    label: &'a form::Value<String>,
+) -> Element<'a, Message> {
+    if saved {
+        card::simple(text(
+            "Draft is saved. You can resume it from the home page.",
+        ))
+        .width(Length::Fixed(400.0))
+        .align_x(iced::alignment::Horizontal::Center)
+        .into()
+    } else {
+        card::simple(
+            Column::new()
+                .spacing(10)
+                .push_maybe(warning.map(|w| warn(Some(w))))
+                .push(text("Save this transaction as a draft to sign it later"))
+                .push(
+                    form::Form::new("Label", label, |s| {
+                        Message::Spend(SpendTxMessage::DraftLabelEdited(s))
+                    })
+                    .padding(10),
+                )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .push(Space::with_width(Length::Fill))
+                        .push(
+                            button::transparent(None, "Cancel")
+                                .on_press(Message::Spend(SpendTxMessage::Cancel)),
+                        )
+                        .push(
+                            button::primary(None, "Save draft")
+                                .on_press(Message::Spend(SpendTxMessage::Confirm)),
+                        ),
+                ),
+        )
+        .width(Length::Fixed(400.0))
+        .into()
+    }
+}
+
+/// The broadcast schedule of a fully signed transaction, or the button to set one.
+fn schedule_view<'a>(schedule: Option<BroadcastSchedule>) -> Element<'a, Message> {
+    if let Some(schedule) = schedule {
+        card::simple(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(icon::clock_icon())
+                .push(
+                    Column::new()
+                        .spacing(5)
+                        .push(text(format!("Broadcast scheduled at {}", schedule)).bold())
+                        .push(
+                            text("Liana broadcasts it only while it is running.")
+                                .style(color::GREY_3),
+                        )
+                        .width(Length::Fill),
+                )
+                .push(
+                    button::secondary(None, "Cancel schedule")
+                        .on_press(Message::Spend(SpendTxMessage::CancelSchedule)),
+                ),
+        )
+        .width(Length::Fill)
+        .into()
+    } else {
+        Row::new()
+            .push(Space::with_width(Length::Fill))
+            .push(
+                button::secondary(Some(icon::clock_icon()), "Broadcast later")
+                    .on_press(Message::Spend(SpendTxMessage::ScheduleBroadcast)),
+            )
+            .into()
+    }
+}
+
+pub fn schedule_broadcast_action<'a>(
+    warning: Option<&Error>,
+    scheduled: bool,
+    by_height: bool,
+    value: &'a form::Value<String>,
+) -> Element<'a, Message> {
+    if scheduled {
+        return card::simple(text("Broadcast is scheduled"))
+            .width(Length::Fixed(400.0))
+            .align_x(iced::alignment::Horizontal::Center)
+            .into();
+    }
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push_maybe(warning.map(|w| warn(Some(w))))
+            .push(text("Broadcast this transaction later").bold())
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(if by_height {
+                        button::transparent_border(None, "Date and time")
+                            .on_press(Message::Spend(SpendTxMessage::ScheduleByHeight(false)))
+                    } else {
+                        button::primary(None, "Date and time")
+                    })
+                    .push(if by_height {
+                        button::primary(None, "Block height")
+                    } else {
+                        button::transparent_border(None, "Block height")
+                            .on_press(Message::Spend(SpendTxMessage::ScheduleByHeight(true)))
+                    }),
+            )
+            .push(
+                form::Form::new(
+                    if by_height {
+                        "850000"
+                    } else {
+                        "YYYY-MM-DD HH:MM"
+                    },
+                    value,
+                    |s| Message::Spend(SpendTxMessage::ScheduleEdited(s)),
+                )
+                .warning(if by_height {
+                    "Please enter a block height above the current tip"
+                } else {
+                    "Please enter a future date and time formatted as YYYY-MM-DD HH:MM"
+                })
+                .padding(10),
+            )
+            .push(
+                text(
+                    "The transaction is only broadcast while Liana is running. If Liana is \
+                     closed at that time, it will be broadcast as soon as it is opened again.",
+                )
+                .style(color::GREY_3),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        button::transparent(None, "Cancel")
+                            .on_press(Message::Spend(SpendTxMessage::Cancel)),
+                    )
+                    .push(
+                        button::primary(None, "Schedule")
+                            .on_press(Message::Spend(SpendTxMessage::Confirm)),
+                    ),
+            ),
+    )
+    .width(Length::Fixed(400.0))
+    .into()
+}
+
 /// Return the modal view to broadcast a transaction.
 ///
 /// `conflicting_txids` contains the IDs of any directly conflicting transactions
@@ -267,6 +454,7 @@ pub fn delete_action<'a>(warning: Option<&Error>, deleted: bool) -> Element<'a,
 pub fn spend_header<'a>(
     tx: &'a SpendTx,
     labels_editing: &'a HashMap<String, form::Value<String>>,
+    bitcoin_unit: BitcoinDisplayUnit,
 ) -> Element<'a, Message> {
     let txid = tx.psbt.unsigned_tx.txid().to_string();
     Column::new()
@@ -292,7 +480,7 @@ pub fn spend_header<'a>(
                 .push(if tx.is_send_to_self() {
                     Container::new(h1("Self-transfer"))
                 } else {
-                    Container::new(amount_with_size(&tx.spend_amount, H1_SIZE))
+                    Container::new(amount_with_size(&tx.spend_amount, H1_SIZE, bitcoin_unit))
                 })
                 .push(
                     Row::new()
@@ -303,7 +491,10 @@ pub fn spend_header<'a>(
                         } else {
                             None
                         })
-                        .push_maybe(tx.fee_amount.map(|fee| amount_with_size(&fee, H3_SIZE)))
+                        .push_maybe(
+                            tx.fee_amount
+                                .map(|fee| amount_with_size(&fee, H3_SIZE, bitcoin_unit)),
+                        )
                         .push(text(" ").size(H3_SIZE))
                         .push_maybe(tx.min_feerate_vb().map(|rate| {
                             text(format!("(~{} sats/vbyte)", &rate))
@@ -319,6 +510,7 @@ pub fn spend_overview_view<'a>(
     tx: &'a SpendTx,
     desc_info: &'a LianaPolicy,
     key_aliases: &'a HashMap<Fingerprint, String>,
+    backend_reachable: bool,
 ) -> Element<'a, Message> {
     Column::new()
         .spacing(20)
@@ -349,7 +541,18 @@ pub fn spend_overview_view<'a>(
                                                     "Update",
                                                 )
                                                 .on_press(Message::Spend(SpendTxMessage::EditPsbt)),
-                                            ),
+                                            )
+                                            .push_maybe((tx.status == SpendStatus::Pending).then(
+                                                || {
+                                                    button::secondary(
+                                                        Some(icon::import_icon()),
+                                                        "Import signatures",
+                                                    )
+                                                    .on_press(Message::Spend(
+                                                        SpendTxMessage::ImportSignatures,
+                                                    ))
+                                                },
+                                            )),
                                     )
                                     .align_items(Alignment::Center),
                             )
@@ -382,14 +585,20 @@ pub fn spend_overview_view<'a>(
                         Some(
                             button::secondary(None, "Sign")
                                 .on_press(Message::Spend(SpendTxMessage::Sign))
-                                .width(Length::Fixed(150.0)),
+                                .width(Length::Fixed(150.0))
+                                .into(),
                         )
-                    } else {
+                    } else if backend_reachable {
                         Some(
                             button::secondary(None, "Broadcast")
                                 .on_press(Message::Spend(SpendTxMessage::Broadcast))
-                                .width(Length::Fixed(150.0)),
+                                .width(Length::Fixed(150.0))
+                                .into(),
                         )
+                    } else {
+                        Some(super::unavailable_while_unreachable(
+                            button::secondary(None, "Broadcast").width(Length::Fixed(150.0)),
+                        ))
                     })
                     .align_items(Alignment::Center)
                     .spacing(20),
@@ -511,6 +720,83 @@ pub fn signatures<'a>(
         .into()
 }
 
+/// The keys of the spending paths the transaction can be finalized through, along with whether
+/// the PSBT has their signature.
+pub fn signing_progress<'a>(
+    tx: &'a SpendTx,
+    desc_info: &'a LianaPolicy,
+    key_aliases: &'a HashMap<Fingerprint, String>,
+) -> Element<'a, Message> {
+    let paths = std::iter::once((
+        "Primary path".to_string(),
+        desc_info.primary_path(),
+        tx.sigs.primary_path(),
+    ))
+    .chain(tx.sigs.recovery_paths().iter().filter_map(|(seq, sigs)| {
+        desc_info
+            .recovery_paths()
+            .get(seq)
+            .map(|path| (format!("Recovery path after {} blocks", seq), path, sigs))
+    }));
+    let mut rows = Column::new().spacing(10).push(
+        Row::new()
+            .spacing(10)
+            .push(p2_regular("Key").style(color::GREY_3).width(Length::Fill))
+            .push(p2_regular("Alias").style(color::GREY_3).width(Length::Fill))
+            .push(
+                p2_regular("Spending path")
+                    .style(color::GREY_3)
+                    .width(Length::Fill),
+            )
+            .push(
+                p2_regular("Signature")
+                    .style(color::GREY_3)
+                    .width(Length::Fixed(100.0)),
+            ),
+    );
+    for (name, path, sigs) in paths {
+        let mut fgs: Vec<Fingerprint> = path.thresh_origins().1.into_keys().collect();
+        fgs.sort();
+        for fg in fgs {
+            let signed = sigs.signed_pubkeys.contains_key(&fg);
+            rows = rows.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(p1_regular(fg.to_string()).width(Length::Fill))
+                    .push(
+                        p1_regular(key_aliases.get(&fg).cloned().unwrap_or_default())
+                            .width(Length::Fill),
+                    )
+                    .push(p1_regular(name.clone()).width(Length::Fill))
+                    .push(
+                        Row::new()
+                            .spacing(5)
+                            .align_items(Alignment::Center)
+                            .width(Length::Fixed(100.0))
+                            .push(if signed {
+                                icon::circle_check_icon().style(color::GREEN)
+                            } else {
+                                icon::circle_cross_icon().style(color::GREY_3)
+                            })
+                            .push(if signed {
+                                p1_regular("Signed").style(color::GREEN)
+                            } else {
+                                p1_regular("Missing").style(color::GREY_3)
+                            }),
+                    ),
+            );
+        }
+    }
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push(text("Signing progress").bold())
+            .push(rows),
+    )
+    .into()
+}
+
 // Display a fingerprint first by its alias if there is any, or in hex otherwise.
 fn container_from_fg(
     fg: Fingerprint,
@@ -607,6 +893,7 @@ pub fn inputs_view<'a>(
     tx: &'a Transaction,
     labels: &'a HashMap<String, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
+    bitcoin_unit: BitcoinDisplayUnit,
 ) -> Element<'a, Message> {
     Container::new(Collapse::new(
         move || {
@@ -656,6 +943,7 @@ pub fn inputs_view<'a>(
                             coins.get(&input.previous_output),
                             labels,
                             labels_editing,
+                            bitcoin_unit,
                         ))
                     },
                 )
@@ -673,6 +961,7 @@ pub fn outputs_view<'a>(
     labels: &'a HashMap<String, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
     is_single_payment: bool,
+    bitcoin_unit: BitcoinDisplayUnit,
 ) -> Element<'a, Message> {
     let change_indexes_copy = change_indexes.clone();
     Column::new()
@@ -750,6 +1039,7 @@ pub fn outputs_view<'a>(
                                         labels,
                                         labels_editing,
                                         is_single_payment,
+                                        bitcoin_unit,
                                     ))
                                 },
                             )
@@ -802,7 +1092,11 @@ pub fn outputs_view<'a>(
                                 .fold(
                                     Column::new().padding(20),
                                     |col: Column<'a, Message>, (_, output)| {
-                                        col.spacing(10).push(change_view(output, network))
+                                        col.spacing(10).push(change_view(
+                                            output,
+                                            network,
+                                            bitcoin_unit,
+                                        ))
                                     },
                                 )
                                 .into()
@@ -822,6 +1116,7 @@ fn input_view<'a>(
     coin: Option<&'a Coin>,
     labels: &'a HashMap<String, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
+    bitcoin_unit: BitcoinDisplayUnit,
 ) -> Element<'a, Message> {
     let outpoint = outpoint.to_string();
     Column::new()
@@ -842,7 +1137,7 @@ fn input_view<'a>(
                     })
                     .width(Length::Fill),
                 )
-                .push_maybe(coin.map(|c| amount(&c.amount))),
+                .push_maybe(coin.map(|c| amount(&c.amount, bitcoin_unit))),
         )
         .push(
             Column::new()
@@ -858,6 +1153,11 @@ fn input_view<'a>(
                                 .style(theme::Button::TransparentBorder),
                         ),
                 )
+                .push_maybe(if coin.is_none() {
+                    Some(p2_regular("Not a coin of this wallet").style(color::GREY_3))
+                } else {
+                    None
+                })
                 .push_maybe(coin.map(|c| {
                     let addr = c.address.to_string();
                     Row::new()
@@ -905,6 +1205,7 @@ fn payment_view<'a>(
     labels: &'a HashMap<String, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
     is_single: bool,
+    bitcoin_unit: BitcoinDisplayUnit,
 ) -> Element<'a, Message> {
     let addr = Address::from_script(&output.script_pubkey, network)
         .ok()
@@ -936,7 +1237,7 @@ fn payment_view<'a>(
                     })
                     .width(Length::Fill),
                 )
-                .push(amount(&output.value)),
+                .push(amount(&output.value, bitcoin_unit)),
         )
         .push_maybe(addr.map(|addr| {
             Column::new()
@@ -975,7 +1276,11 @@ fn payment_view<'a>(
         .into()
 }
 
-fn change_view(output: &TxOut, network: Network) -> Element<Message> {
+fn change_view(
+    output: &TxOut,
+    network: Network,
+    bitcoin_unit: BitcoinDisplayUnit,
+) -> Element<Message> {
     let addr = Address::from_script(&output.script_pubkey, network)
         .unwrap()
         .to_string();
@@ -985,7 +1290,7 @@ fn change_view(output: &TxOut, network: Network) -> Element<Message> {
         .push(
             Row::new()
                 .push(Space::with_width(Length::Fill))
-                .push(amount(&output.value)),
+                .push(amount(&output.value, bitcoin_unit)),
         )
         .push(
             Row::new()
@@ -1008,14 +1313,23 @@ fn change_view(output: &TxOut, network: Network) -> Element<Message> {
         .into()
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn sign_action<'a>(
     warning: Option<&Error>,
     hws: &'a [HardwareWallet],
-    signer: Option<Fingerprint>,
-    signer_alias: Option<&'a String>,
+    signers: Vec<(Fingerprint, Option<&'a String>)>,
     signed: &HashSet<Fingerprint>,
     signing: &HashSet<Fingerprint>,
+    failed: &'a HashMap<Fingerprint, String>,
+    aliases: &'a HashMap<Fingerprint, String>,
+    not_registered: &HashSet<Fingerprint>,
+    registering: Option<Fingerprint>,
+    wallet_name: &str,
+    descriptor: &LianaDescriptor,
 ) -> Element<'a, Message> {
+    if let Some(fingerprint) = registering {
+        return register_wallet_action(warning, hws, fingerprint, wallet_name, descriptor);
+    }
     Column::new()
         .push_maybe(warning.map(|w| warn(Some(w))))
         .push(card::simple(
@@ -1031,31 +1345,78 @@ pub fn sign_action<'a>(
                         .push(hws.iter().enumerate().fold(
                             Column::new().spacing(10),
                             |col, (i, hw)| {
-                                col.push(hw_list_view(
-                                    i,
+                                let signed = hw
+                                    .fingerprint()
+                                    .map(|f| signed.contains(&f))
+                                    .unwrap_or(false);
+                                let outdated = hw
+                                    .fingerprint()
+                                    .map(|f| not_registered.contains(&f))
+                                    .unwrap_or(false);
+                                let unregistered = matches!(
                                     hw,
-                                    hw.fingerprint()
-                                        .map(|f| signed.contains(&f))
-                                        .unwrap_or(false),
-                                    hw.fingerprint()
-                                        .map(|f| signing.contains(&f))
-                                        .unwrap_or(false),
-                                ))
+                                    HardwareWallet::Supported {
+                                        registered: Some(false),
+                                        ..
+                                    }
+                                );
+                                let is_signing = hw
+                                    .fingerprint()
+                                    .map(|f| signing.contains(&f))
+                                    .unwrap_or(false);
+                                let error = hw.fingerprint().and_then(|f| failed.get(&f));
+                                if !signed && (outdated || unregistered) {
+                                    col.push(hw_list_view_to_register(i, hw, outdated))
+                                } else if let Some(error) = error.filter(|_| !is_signing) {
+                                    col.push(hw_list_view_sign_failed(i, hw, error.clone()))
+                                } else {
+                                    col.push(hw_list_view(i, hw, signed, is_signing))
+                                }
+                            },
+                        ))
+                        // The devices unplugged while signing.
+                        .push(
+                            failed
+                                .iter()
+                                .filter(|(fingerprint, _)| {
+                                    !hws.iter().any(|hw| hw.fingerprint() == Some(**fingerprint))
+                                })
+                                .fold(Column::new().spacing(10), |col, (fingerprint, error)| {
+                                    col.push(disconnected_hw_sign_failed(
+                                        *fingerprint,
+                                        aliases.get(fingerprint),
+                                        error.clone(),
+                                    ))
+                                }),
+                        )
+                        // The hot signers which already signed can't be selected again.
+                        .push(signers.into_iter().fold(
+                            Column::new().spacing(10),
+                            |col, (fingerprint, alias)| {
+                                col.push(
+                                    if signed.contains(&fingerprint) {
+                                        Button::new(hw::sign_success_hot_signer(fingerprint, alias))
+                                    } else {
+                                        Button::new(hw::hot_signer(fingerprint, alias)).on_press(
+                                            Message::Spend(SpendTxMessage::SelectHotSigner(
+                                                fingerprint,
+                                            )),
+                                        )
+                                    }
+                                    .padding(10)
+                                    .style(theme::Button::Border)
+                                    .width(Length::Fill),
+                                )
                             },
                         ))
-                        .push_maybe(signer.map(|fingerprint| {
-                            Button::new(if signed.contains(&fingerprint) {
-                                hw::sign_success_hot_signer(fingerprint, signer_alias)
-                            } else {
-                                hw::hot_signer(fingerprint, signer_alias)
-                            })
-                            .on_press(Message::Spend(SpendTxMessage::SelectHotSigner))
-                            .padding(10)
-                            .style(theme::Button::Border)
-                            .width(Length::Fill)
-                        }))
                         .width(Length::Fill),
                 )
+                .push(
+                    Row::new().push(Space::with_width(Length::Fill)).push(
+                        button::secondary(None, "Save for later")
+                            .on_press(Message::Spend(SpendTxMessage::SaveForLater)),
+                    ),
+                )
                 .spacing(20)
                 .width(Length::Fill)
                 .align_items(Alignment::Center),
@@ -1064,6 +1425,48 @@ pub fn sign_action<'a>(
         .into()
 }
 
+/// Registering the wallet on a device from the signing modal, waiting for the user to confirm it
+/// on the device.
+fn register_wallet_action<'a>(
+    warning: Option<&Error>,
+    hws: &'a [HardwareWallet],
+    fingerprint: Fingerprint,
+    wallet_name: &str,
+    descriptor: &LianaDescriptor,
+) -> Element<'a, Message> {
+    Column::new()
+        .push_maybe(warning.map(|w| warn(Some(w))))
+        .push(card::simple(
+            Column::new()
+                .spacing(20)
+                .width(Length::Fill)
+                .push(text("Register the wallet on the device").bold())
+                .push(wallet_to_register(wallet_name, descriptor))
+                .push_maybe(hws.iter().find_map(|hw| {
+                    match hw {
+                        HardwareWallet::Supported {
+                            kind,
+                            version,
+                            fingerprint: fg,
+                            alias,
+                            ..
+                        } if *fg == fingerprint => Some(
+                            hw::processing_hardware_wallet(
+                                kind,
+                                version.as_ref(),
+                                fg,
+                                alias.as_ref(),
+                            )
+                            .width(Length::Fill),
+                        ),
+                        _ => None,
+                    }
+                })),
+        ))
+        .width(Length::Fixed(500.0))
+        .into()
+}
+
 pub fn sign_action_toasts<'a>(
     error: Option<&Error>,
     hws: &'a [HardwareWallet],
@@ -1146,22 +1549,166 @@ pub fn update_spend_view<'a>(
                             .size(P1_SIZE)
                             .padding(10),
                         )
-                        .push(Row::new().push(Space::with_width(Length::Fill)).push(
-                            if updated.valid && !updated.value.is_empty() && !processing {
-                                button::secondary(None, "Update")
-                                    .on_press(Message::ImportSpend(ImportSpendMessage::Confirm))
-                            } else if processing {
-                                button::secondary(None, "Processing...")
-                            } else {
-                                button::secondary(None, "Update")
-                            },
-                        )),
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .push(Space::with_width(Length::Fill))
+                                .push(button::secondary(None, "Scan QR code").on_press_maybe(
+                                    if !processing {
+                                        Some(Message::ImportSpend(ImportSpendMessage::ScanQrCode))
+                                    } else {
+                                        None
+                                    },
+                                ))
+                                .push(
+                                    if updated.valid && !updated.value.is_empty() && !processing {
+                                        button::secondary(None, "Update").on_press(
+                                            Message::ImportSpend(ImportSpendMessage::Confirm),
+                                        )
+                                    } else if processing {
+                                        button::secondary(None, "Processing...")
+                                    } else {
+                                        button::secondary(None, "Update")
+                                    },
+                                ),
+                        ),
+                ),
+        ))
+        .max_width(400)
+        .into()
+}
+
+pub fn scan_psbt_view<'a>(
+    cameras: &'a [Camera],
+    progress: Option<&Progress>,
+    error: Option<&Error>,
+) -> Element<'a, Message> {
+    let content = if !cameras.is_empty() {
+        cameras.iter().enumerate().fold(
+            Column::new()
+                .spacing(10)
+                .push(text("Select a camera:").bold()),
+            |col, (i, camera)| {
+                col.push(
+                    Button::new(text(&camera.name))
+                        .width(Length::Fill)
+                        .padding(10)
+                        .style(theme::Button::TransparentBorder)
+                        .on_press(Message::ImportSpend(ImportSpendMessage::SelectCamera(i))),
+                )
+            },
+        )
+    } else {
+        Column::new()
+            .spacing(10)
+            .push(text("Scanning the QR code of the updated PSBT...").bold())
+            .push(text(match progress {
+                Some(progress) => format!("{}/{} parts", progress.received, progress.total),
+                None => "Show the QR code to the camera".to_string(),
+            }))
+    };
+    Column::new()
+        .push(warn(error))
+        .push(card::simple(
+            Column::new().spacing(20).push(content).push(
+                Row::new().push(Space::with_width(Length::Fill)).push(
+                    button::secondary(None, "Cancel")
+                        .on_press(Message::ImportSpend(ImportSpendMessage::CancelScan)),
                 ),
+            ),
         ))
         .max_width(400)
         .into()
 }
 
+/// What a PSBT file imported for its signatures brought to the spend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportedSignatures {
+    /// The file couldn't be read as a PSBT.
+    Invalid(String),
+    /// The PSBT is for another transaction.
+    OtherTransaction(Txid),
+    /// The keys the file brought new signatures for, possibly none.
+    Signers(BTreeSet<Fingerprint>),
+}
+
+pub fn import_signatures_view<'a>(
+    imported: &'a [(String, ImportedSignatures)],
+    key_aliases: &'a HashMap<Fingerprint, String>,
+    processing: bool,
+    success: bool,
+    error: Option<&Error>,
+) -> Element<'a, Message> {
+    let any_new = imported
+        .iter()
+        .any(|(_, i)| matches!(i, ImportedSignatures::Signers(signers) if !signers.is_empty()));
+    Column::new()
+        .push(warn(error))
+        .push(card::simple(
+            Column::new()
+                .spacing(20)
+                .push(text("Import signatures").bold())
+                .push(
+                    imported
+                        .iter()
+                        .fold(Column::new().spacing(10), |col, (name, imported)| {
+                            col.push(
+                                Column::new()
+                                    .spacing(5)
+                                    .push(p1_bold(name))
+                                    .push(match imported {
+                                        ImportedSignatures::Invalid(e) => Element::from(
+                                            p2_regular(format!("Not a valid PSBT: {}", e))
+                                                .style(color::RED),
+                                        ),
+                                        ImportedSignatures::OtherTransaction(txid) => {
+                                            p2_regular(format!("For another transaction: {}", txid))
+                                                .style(color::RED)
+                                                .into()
+                                        }
+                                        ImportedSignatures::Signers(signers)
+                                            if signers.is_empty() =>
+                                        {
+                                            p2_regular("No new signature")
+                                                .style(color::GREY_3)
+                                                .into()
+                                        }
+                                        ImportedSignatures::Signers(signers) => signers
+                                            .iter()
+                                            .fold(
+                                                Row::new()
+                                                    .spacing(5)
+                                                    .align_items(Alignment::Center)
+                                                    .push(p2_regular("New signatures from")),
+                                                |row, fg| {
+                                                    row.push(container_from_fg(*fg, key_aliases))
+                                                },
+                                            )
+                                            .into(),
+                                    }),
+                            )
+                        }),
+                )
+                .push_maybe(if success {
+                    Some(text("Spend transaction is updated").style(color::GREEN))
+                } else if processing {
+                    Some(text("Updating the spend transaction..."))
+                } else if !any_new {
+                    Some(text("No signature to add to the spend transaction").style(color::GREY_3))
+                } else {
+                    None
+                })
+                .push(
+                    Row::new().push(Space::with_width(Length::Fill)).push(
+                        button::secondary(None, "Close")
+                            .on_press(Message::Spend(SpendTxMessage::Cancel)),
+                    ),
+                ),
+        ))
+        .max_width(500)
+        .into()
+}
+
 pub fn update_spend_success_view<'a>() -> Element<'a, Message> {
     Column::new()
         .push(
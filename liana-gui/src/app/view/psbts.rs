@@ -8,7 +8,7 @@ use liana_ui::{
 };
 
 use crate::{
-    app::{error::Error, menu::Menu},
+    app::{error::Error, menu::Menu, settings::ScheduledBroadcast},
     daemon::model::{SpendStatus, SpendTx},
 };
 
@@ -58,7 +58,11 @@ pub fn import_psbt_success_view<'a>() -> Element<'a, Message> {
         .into()
 }
 
-pub fn psbts_view(spend_txs: &[SpendTx]) -> Element<'_, Message> {
+pub fn psbts_view<'a>(
+    spend_txs: &'a [SpendTx],
+    scheduled_broadcasts: &[ScheduledBroadcast],
+    bitcoin_unit: BitcoinDisplayUnit,
+) -> Element<'a, Message> {
     Column::new()
         .push(
             Row::new()
@@ -75,21 +79,29 @@ pub fn psbts_view(spend_txs: &[SpendTx]) -> Element<'_, Message> {
                 ),
         )
         .push(
-            Column::new().spacing(10).push(
-                spend_txs
-                    .iter()
-                    .enumerate()
-                    .fold(Column::new().spacing(10), |col, (i, tx)| {
-                        col.push(spend_tx_list_view(i, tx))
-                    }),
-            ),
+            Column::new()
+                .spacing(10)
+                .push(spend_txs.iter().enumerate().fold(
+                    Column::new().spacing(10),
+                    |col, (i, tx)| {
+                        let is_scheduled = scheduled_broadcasts
+                            .iter()
+                            .any(|scheduled| scheduled.txid == tx.psbt.unsigned_tx.txid());
+                        col.push(spend_tx_list_view(i, tx, is_scheduled, bitcoin_unit))
+                    },
+                )),
         )
         .align_items(Alignment::Center)
         .spacing(25)
         .into()
 }
 
-fn spend_tx_list_view(i: usize, tx: &SpendTx) -> Element<'_, Message> {
+fn spend_tx_list_view(
+    i: usize,
+    tx: &SpendTx,
+    is_scheduled: bool,
+    bitcoin_unit: BitcoinDisplayUnit,
+) -> Element<'_, Message> {
     Container::new(
         Button::new(
             Row::new()
@@ -137,8 +149,14 @@ fn spend_tx_list_view(i: usize, tx: &SpendTx) -> Element<'_, Message> {
                 } else {
                     None
                 })
+                .push_maybe(if is_scheduled && tx.status == SpendStatus::Pending {
+                    Some(badge::scheduled().width(120.0))
+                } else {
+                    None
+                })
                 .push_maybe(match tx.status {
                     SpendStatus::Deprecated => Some(badge::deprecated().width(120.0)),
+                    SpendStatus::Superseded => Some(badge::superseded().width(120.0)),
                     SpendStatus::Broadcast => Some(badge::unconfirmed().width(120.0)),
                     SpendStatus::Spent => Some(badge::spent().width(120.0)),
                     _ => None,
@@ -147,11 +165,14 @@ fn spend_tx_list_view(i: usize, tx: &SpendTx) -> Element<'_, Message> {
                     Column::new()
                         .align_items(Alignment::End)
                         .push(if !tx.is_send_to_self() {
-                            Container::new(amount(&tx.spend_amount))
+                            Container::new(amount(&tx.spend_amount, bitcoin_unit))
                         } else {
                             Container::new(p1_regular("Self-transfer"))
                         })
-                        .push_maybe(tx.fee_amount.map(|fee| amount_with_size(&fee, P2_SIZE)))
+                        .push_maybe(
+                            tx.fee_amount
+                                .map(|fee| amount_with_size(&fee, P2_SIZE, bitcoin_unit)),
+                        )
                         .width(Length::Fixed(140.0)),
                 )
                 .align_items(Alignment::Center)
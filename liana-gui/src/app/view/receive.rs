@@ -1,23 +1,25 @@
 use std::collections::{HashMap, HashSet};
 
 use iced::{
-    widget::{
-        qr_code::{self, QRCode},
-        scrollable, Space,
-    },
+    widget::{scrollable, Space},
     Alignment, Length,
 };
 
 use liana::miniscript::bitcoin::{
     self,
-    bip32::{ChildNumber, Fingerprint},
+    bip32::{ChildNumber, DerivationPath, Fingerprint},
     Address,
 };
 
 use liana_ui::{
     color,
     component::{
-        button, card, form,
+        amount::BitcoinDisplayUnit,
+        amount_input::{AmountInput, ExchangeRate},
+        badge, button, card,
+        copy_button::{copy_button, CopyButton},
+        form,
+        qr::Qr,
         text::{self, *},
     },
     icon, theme,
@@ -29,13 +31,17 @@ use crate::{
         error::Error,
         view::{hw, label, warning::warn},
     },
-    hw::HardwareWallet,
+    hw::{HardwareWallet, VerificationStatus},
 };
 
 use super::message::Message;
 
 pub fn receive<'a>(
     addresses: &'a [bitcoin::Address],
+    derivation_paths: &'a [Vec<(Fingerprint, DerivationPath)>],
+    descriptors: &'a [String],
+    used: &'a HashSet<bitcoin::Address>,
+    verified: &'a HashSet<bitcoin::Address>,
     labels: &'a HashMap<String, String>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
 ) -> Element<'a, Message> {
@@ -60,6 +66,19 @@ pub fn receive<'a>(
                         col.push(
                             card::simple(
                                 Column::new()
+                                    .push_maybe(if used.contains(address) {
+                                        Some(
+                                            card::warning(
+                                                "This address already received funds. Reusing \
+                                                 it harms your privacy and the privacy of the \
+                                                 payer, generate a new one."
+                                                    .to_string(),
+                                            )
+                                            .width(Length::Fill),
+                                        )
+                                    } else {
+                                        None
+                                    })
                                     .push(if let Some(label) = labels_editing.get(&addr) {
                                         label::label_editing(
                                             vec![addr.clone()],
@@ -100,15 +119,22 @@ pub fn receive<'a>(
                                                 )
                                                 .width(Length::Fill),
                                             )
-                                            .push(
-                                                Button::new(
-                                                    icon::clipboard_icon().style(color::GREY_3),
-                                                )
-                                                .on_press(Message::Clipboard(address.to_string()))
-                                                .style(theme::Button::TransparentBorder),
-                                            )
+                                            .push(copy_button(address.to_string()))
                                             .align_items(Alignment::Center),
                                     )
+                                    .push_maybe(address.address_type().map(|t| {
+                                        p2_regular(format!(
+                                            "Type: {}",
+                                            t.to_string().to_uppercase()
+                                        ))
+                                        .small()
+                                        .style(color::GREY_3)
+                                    }))
+                                    .push_maybe(
+                                        derivation_paths
+                                            .get(i)
+                                            .map(|paths| derivation_paths_view(paths)),
+                                    )
                                     .push(
                                         Row::new()
                                             .push(
@@ -118,11 +144,29 @@ pub fn receive<'a>(
                                                 )
                                                 .on_press(Message::Select(i)),
                                             )
+                                            .push_maybe(if verified.contains(address) {
+                                                Some(badge::verified())
+                                            } else {
+                                                None
+                                            })
                                             .push(Space::with_width(Length::Fill))
+                                            .push_maybe(descriptors.get(i).map(|desc| {
+                                                CopyButton::new(
+                                                    Row::new()
+                                                        .spacing(10)
+                                                        .align_items(Alignment::Center)
+                                                        .push(icon::clipboard_icon())
+                                                        .push(text("Copy descriptor")),
+                                                    desc.clone(),
+                                                )
+                                                .style(theme::Button::Secondary)
+                                                .padding(10)
+                                            }))
                                             .push(
                                                 button::secondary(None, "Show QR Code")
                                                     .on_press(Message::ShowQrCode(i)),
-                                            ),
+                                            )
+                                            .spacing(10),
                                     )
                                     .spacing(10),
                             )
@@ -135,10 +179,29 @@ pub fn receive<'a>(
         .into()
 }
 
+/// The full derivation path of each key of an address, with the key's master fingerprint.
+fn derivation_paths_view<'a>(paths: &[(Fingerprint, DerivationPath)]) -> Element<'a, Message> {
+    paths
+        .iter()
+        .fold(
+            Column::new()
+                .spacing(2)
+                .push(p2_regular("Derivation paths:").small().style(color::GREY_3)),
+            |col, (fingerprint, path)| {
+                col.push(
+                    p2_regular(format!("[{}] {}", fingerprint, path))
+                        .small()
+                        .style(color::GREY_3),
+                )
+            },
+        )
+        .into()
+}
+
 pub fn verify_address_modal<'a>(
     warning: Option<&Error>,
     hws: &'a [HardwareWallet],
-    chosen_hws: &HashSet<Fingerprint>,
+    statuses: &'a HashMap<Fingerprint, VerificationStatus>,
     address: &Address,
     derivation_index: &ChildNumber,
 ) -> Element<'a, Message> {
@@ -164,13 +227,7 @@ pub fn verify_address_modal<'a>(
                                                 .push(Container::new(
                                                     text(address.to_string()).small(),
                                                 ))
-                                                .push(
-                                                    Button::new(icon::clipboard_icon())
-                                                        .on_press(Message::Clipboard(
-                                                            address.to_string(),
-                                                        ))
-                                                        .style(theme::Button::TransparentBorder),
-                                                )
+                                                .push(copy_button(address.to_string()))
                                                 .width(Length::Shrink),
                                         ),
                                 )
@@ -191,19 +248,39 @@ pub fn verify_address_modal<'a>(
                                 )
                                 .spacing(5),
                         )
-                        .push(text("Select device to verify address on:").width(Length::Fill))
+                        .push(
+                            Row::new()
+                                .align_items(Alignment::Center)
+                                .push(
+                                    text("Select device to verify address on:").width(Length::Fill),
+                                )
+                                .push(button::secondary(None, "Verify on device").on_press_maybe(
+                                    if hws.iter().any(|hw| hw.is_supported()) {
+                                        Some(Message::VerifyAddressOnDevices)
+                                    } else {
+                                        None
+                                    },
+                                )),
+                        )
                         .spacing(10)
+                        .push_maybe(if hws.iter().any(|hw| hw.is_supported()) {
+                            None
+                        } else {
+                            Some(
+                                text(
+                                    "Connect and unlock a hardware device holding one of the \
+                                     keys of the wallet to verify the address on its screen.",
+                                )
+                                .style(color::GREY_3),
+                            )
+                        })
                         .push(hws.iter().enumerate().fold(
                             Column::new().spacing(10),
                             |col, (i, hw)| {
                                 col.push(hw::hw_list_view_verify_address(
                                     i,
                                     hw,
-                                    if let HardwareWallet::Supported { fingerprint, .. } = hw {
-                                        chosen_hws.contains(fingerprint)
-                                    } else {
-                                        false
-                                    },
+                                    hw.fingerprint().and_then(|fg| statuses.get(&fg)),
                                 ))
                             },
                         ))
@@ -218,24 +295,69 @@ pub fn verify_address_modal<'a>(
         .into()
 }
 
-pub fn qr_modal<'a>(qr: &'a qr_code::Data, address: &'a String) -> Element<'a, Message> {
+/// The amount requested in the QR code of an address.
+pub struct QrAmount<'a> {
+    pub value: &'a form::Value<String>,
+    pub unit: BitcoinDisplayUnit,
+    pub exchange_rate: Option<ExchangeRate>,
+}
+
+/// Show a QR code of the address payment request, or of its descriptor if `is_descriptor`. A
+/// button to toggle between the two is displayed if `has_descriptor`. The amount of the request
+/// can be edited if `amount` is given.
+pub fn qr_modal<'a>(
+    qr: &'a Qr,
+    data: &'a String,
+    has_descriptor: bool,
+    is_descriptor: bool,
+    amount: Option<QrAmount<'a>>,
+) -> Element<'a, Message> {
     Column::new()
         .push(
-            Row::new()
-                .push(Space::with_width(Length::Fill))
-                .push(
-                    Container::new(QRCode::<liana_ui::theme::Theme>::new(qr).cell_size(8))
-                        .padding(10)
-                        .style(theme::Container::QrCode),
-                )
-                .push(Space::with_width(Length::Fill)),
+            Container::new(qr.view(400.0).padding(10))
+                .width(Length::Fill)
+                .center_x(),
         )
+        .push_maybe(amount.map(|amount| {
+            Container::new(
+                AmountInput::new(
+                    "Amount requested (optional)",
+                    amount.value,
+                    amount.unit,
+                    amount.exchange_rate,
+                    Message::QrCodeAmountEdited,
+                )
+                .size(P1_SIZE)
+                .padding(10),
+            )
+            .padding([15, 0, 0, 0])
+        }))
         .push(Space::with_height(Length::Fixed(15.0)))
         .push(
-            Container::new(text(address).size(15))
+            Container::new(text(data).size(15))
                 .width(Length::Fill)
                 .center_x(),
         )
+        .push_maybe(if has_descriptor {
+            Some(
+                Container::new(
+                    button::secondary(
+                        None,
+                        if is_descriptor {
+                            "Show address"
+                        } else {
+                            "Show descriptor"
+                        },
+                    )
+                    .on_press(Message::ToggleDescriptorQrCode),
+                )
+                .padding(10)
+                .width(Length::Fill)
+                .center_x(),
+            )
+        } else {
+            None
+        })
         .width(Length::Fill)
         .max_width(400)
         .into()
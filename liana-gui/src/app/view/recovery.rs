@@ -141,6 +141,7 @@ pub fn recovery_path_view<'a>(
     number_of_coins: usize,
     key_aliases: &'a HashMap<Fingerprint, String>,
     selected: bool,
+    bitcoin_unit: BitcoinDisplayUnit,
 ) -> Element<'a, Message> {
     Row::new()
         .push(
@@ -192,7 +193,7 @@ pub fn recovery_path_view<'a>(
                             number_of_coins,
                             if number_of_coins > 0 { "s" } else { "" }
                         )))
-                        .push(amount(&total_amount)),
+                        .push(amount(&total_amount, bitcoin_unit)),
                 )
                 .spacing(5),
         )
@@ -1,9 +1,9 @@
 use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use iced::{
-    alignment,
-    widget::{radio, scrollable, tooltip as iced_tooltip, Space},
+    widget::{checkbox, radio, scrollable, tooltip as iced_tooltip, Space},
     Alignment, Length,
 };
 
@@ -13,11 +13,14 @@ use liana::{
 };
 use lianad::config::BitcoindRpcAuth;
 
-use super::{dashboard, message::*};
+use super::{dashboard, message::*, policy};
 
 use liana_ui::{
     color,
-    component::{badge, button, card, form, separation, text::*, tooltip::tooltip},
+    component::{
+        amount::BitcoinDisplayUnit, badge, button, card, form, policy_diagram::policy_diagram,
+        separation, text::*, tooltip::tooltip,
+    },
     icon, theme,
     widget::*,
 };
@@ -27,16 +30,24 @@ use crate::{
         cache::Cache,
         error::Error,
         menu::Menu,
+        settings::{
+            self, AutoLockSettings, HotSignerUnlock, NotificationKind, NotificationSettings,
+        },
         view::{hw, warning::warn},
     },
     hw::HardwareWallet,
+    i18n::{self, tr, Language},
     node::{
-        bitcoind::{RpcAuthType, RpcAuthValues},
+        bitcoind::{pruned_disk_space_estimate, RpcAuthType, RpcAuthValues},
         electrum,
     },
 };
 
-pub fn list(cache: &Cache, is_remote_backend: bool) -> Element<Message> {
+pub fn list(
+    cache: &Cache,
+    is_remote_backend: bool,
+    has_managed_bitcoind: bool,
+) -> Element<Message> {
     dashboard(
         &Menu::Settings,
         cache,
@@ -45,52 +56,71 @@ pub fn list(cache: &Cache, is_remote_backend: bool) -> Element<Message> {
             .spacing(20)
             .width(Length::Fill)
             .push(
-                Button::new(text("Settings").size(30).bold())
+                Button::new(text(tr("settings.title")).size(30).bold())
                     .style(theme::Button::Transparent)
-                    .on_press(Message::Menu(Menu::Settings)))
-            .push(
-                if !is_remote_backend {
-                    Container::new(
-                        Button::new(
-                            Row::new()
-                                .push(badge::Badge::new(icon::bitcoin_icon()))
-                                .push(text("Node").bold())
-                                .padding(10)
-                                .spacing(20)
-                                .align_items(Alignment::Center)
-                                .width(Length::Fill),
-                        )
-                        .width(Length::Fill)
-                        .style(theme::Button::TransparentBorder)
-                        .on_press(Message::Settings(SettingsMessage::EditBitcoindSettings))
+                    .on_press(Message::Menu(Menu::Settings)),
+            )
+            .push(if !is_remote_backend {
+                Container::new(
+                    Button::new(
+                        Row::new()
+                            .push(badge::Badge::new(icon::bitcoin_icon()))
+                            .push(text(tr("settings.node")).bold())
+                            .padding(10)
+                            .spacing(20)
+                            .align_items(Alignment::Center)
+                            .width(Length::Fill),
                     )
                     .width(Length::Fill)
-                    .style(theme::Container::Card(theme::Card::Simple))
-                } else {
-                    Container::new(
-                        Button::new(
-                            Row::new()
-                                .push(badge::Badge::new(icon::bitcoin_icon()))
-                                .push(text("Backend").bold())
-                                .padding(10)
-                                .spacing(20)
-                                .align_items(Alignment::Center)
-                                .width(Length::Fill),
-                        )
-                        .width(Length::Fill)
-                        .style(theme::Button::TransparentBorder)
-                        .on_press(Message::Settings(SettingsMessage::EditRemoteBackendSettings))
+                    .style(theme::Button::TransparentBorder)
+                    .on_press(Message::Settings(SettingsMessage::EditBitcoindSettings)),
+                )
+                .width(Length::Fill)
+                .style(theme::Container::Card(theme::Card::Simple))
+            } else {
+                Container::new(
+                    Button::new(
+                        Row::new()
+                            .push(badge::Badge::new(icon::bitcoin_icon()))
+                            .push(text(tr("settings.backend")).bold())
+                            .padding(10)
+                            .spacing(20)
+                            .align_items(Alignment::Center)
+                            .width(Length::Fill),
                     )
                     .width(Length::Fill)
-                    .style(theme::Container::Card(theme::Card::Simple))
-                }
-            )
+                    .style(theme::Button::TransparentBorder)
+                    .on_press(Message::Settings(
+                        SettingsMessage::EditRemoteBackendSettings,
+                    )),
+                )
+                .width(Length::Fill)
+                .style(theme::Container::Card(theme::Card::Simple))
+            })
+            .push_maybe(has_managed_bitcoind.then(|| {
+                Container::new(
+                    Button::new(
+                        Row::new()
+                            .push(badge::Badge::new(icon::bitcoin_icon()))
+                            .push(text(tr("settings.managed_bitcoind")).bold())
+                            .padding(10)
+                            .spacing(20)
+                            .align_items(Alignment::Center)
+                            .width(Length::Fill),
+                    )
+                    .width(Length::Fill)
+                    .style(theme::Button::TransparentBorder)
+                    .on_press(Message::Settings(SettingsMessage::ManagedBitcoindSection)),
+                )
+                .width(Length::Fill)
+                .style(theme::Container::Card(theme::Card::Simple))
+            }))
             .push(
                 Container::new(
                     Button::new(
                         Row::new()
                             .push(badge::Badge::new(icon::wallet_icon()))
-                            .push(text("Wallet").bold())
+                            .push(text(tr("settings.wallet")).bold())
                             .padding(10)
                             .spacing(20)
                             .align_items(Alignment::Center)
@@ -98,18 +128,18 @@ pub fn list(cache: &Cache, is_remote_backend: bool) -> Element<Message> {
                     )
                     .width(Length::Fill)
                     .style(theme::Button::TransparentBorder)
-                    .on_press(Message::Settings(SettingsMessage::EditWalletSettings))
+                    .on_press(Message::Settings(SettingsMessage::EditWalletSettings)),
                 )
                 .width(Length::Fill)
-                .style(theme::Container::Card(theme::Card::Simple))
+                .style(theme::Container::Card(theme::Card::Simple)),
             )
             .push(
                 Container::new(
                     Button::new(
                         Row::new()
                             .push(badge::Badge::new(icon::recovery_icon()))
-                            .push(text("Recovery").bold())
-                            .push(tooltip("In case of loss of the main key, the recovery key can move the funds after a certain time."))
+                            .push(text(tr("settings.recovery")).bold())
+                            .push(tooltip(tr("settings.recovery_tooltip")))
                             .padding(10)
                             .spacing(20)
                             .align_items(Alignment::Center)
@@ -117,17 +147,36 @@ pub fn list(cache: &Cache, is_remote_backend: bool) -> Element<Message> {
                     )
                     .width(Length::Fill)
                     .style(theme::Button::TransparentBorder)
-                    .on_press(Message::Menu(Menu::Recovery))
+                    .on_press(Message::Menu(Menu::Recovery)),
                 )
                 .width(Length::Fill)
-                .style(theme::Container::Card(theme::Card::Simple))
+                .style(theme::Container::Card(theme::Card::Simple)),
+            )
+            .push(
+                Container::new(
+                    Button::new(
+                        Row::new()
+                            .push(badge::Badge::new(icon::import_icon()))
+                            .push(text(tr("settings.profile")).bold())
+                            .push(tooltip(tr("settings.profile_tooltip")))
+                            .padding(10)
+                            .spacing(20)
+                            .align_items(Alignment::Center)
+                            .width(Length::Fill),
+                    )
+                    .width(Length::Fill)
+                    .style(theme::Button::TransparentBorder)
+                    .on_press(Message::Settings(SettingsMessage::ProfileSection)),
+                )
+                .width(Length::Fill)
+                .style(theme::Container::Card(theme::Card::Simple)),
             )
             .push(
                 Container::new(
                     Button::new(
                         Row::new()
                             .push(badge::Badge::new(icon::tooltip_icon()))
-                            .push(text("About").bold())
+                            .push(text(tr("settings.about")).bold())
                             .padding(10)
                             .spacing(20)
                             .align_items(Alignment::Center)
@@ -135,16 +184,240 @@ pub fn list(cache: &Cache, is_remote_backend: bool) -> Element<Message> {
                     )
                     .width(Length::Fill)
                     .style(theme::Button::TransparentBorder)
-                    .on_press(Message::Settings(SettingsMessage::AboutSection))
+                    .on_press(Message::Settings(SettingsMessage::AboutSection)),
                 )
                 .width(Length::Fill)
-                .style(theme::Container::Card(theme::Card::Simple))
+                .style(theme::Container::Card(theme::Card::Simple)),
+            ),
+    )
+}
+
+/// The step of an operation on the managed bitcoind.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ManagedBitcoindStep {
+    /// Downloading the new version, with the progress in percent.
+    Downloading(f32),
+    Installing,
+    Stopping,
+    Applying,
+    Starting,
+    Checking,
+}
+
+impl ManagedBitcoindStep {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Downloading(_) => "Downloading the new version of bitcoind...",
+            Self::Installing => "Installing the new version of bitcoind...",
+            Self::Stopping => "Stopping bitcoind...",
+            Self::Applying => "Applying the new settings...",
+            Self::Starting => "Restarting bitcoind...",
+            Self::Checking => "Checking bitcoind...",
+        }
+    }
+}
+
+fn managed_bitcoind_card<'a>(
+    title: &'static str,
+    content: impl Into<Element<'a, Message>>,
+) -> Element<'a, Message> {
+    card::simple(
+        Column::new()
+            .push(
+                Row::new()
+                    .push(badge::Badge::new(icon::bitcoin_icon()))
+                    .push(text(title).bold())
+                    .padding(10)
+                    .spacing(20)
+                    .align_items(Alignment::Center)
+                    .width(Length::Fill),
             )
+            .push(separation().width(Length::Fill))
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(content),
     )
+    .width(Length::Fill)
+    .into()
 }
+
+#[allow(clippy::too_many_arguments)]
+pub fn managed_bitcoind_section<'a>(
+    cache: &'a Cache,
+    warning: Option<&Error>,
+    prune: Option<u32>,
+    prune_form: &'a form::Value<String>,
+    datadir: &'a Path,
+    datadir_form: &'a form::Value<String>,
+    installed_version: Option<&'static str>,
+    upgrade_available: bool,
+    step: Option<ManagedBitcoindStep>,
+) -> Element<'a, Message> {
+    let idle = step.is_none();
+    let estimate = prune_form
+        .value
+        .parse::<u32>()
+        .ok()
+        .filter(|_| prune_form.valid)
+        .or(prune)
+        .map(|p| pruned_disk_space_estimate(cache.network, p));
+    dashboard(
+        &Menu::Settings,
+        cache,
+        warning,
+        Column::new()
+            .spacing(20)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Button::new(text(tr("settings.title")).size(30).bold())
+                            .style(theme::Button::Transparent)
+                            .on_press(Message::Menu(Menu::Settings)),
+                    )
+                    .push(icon::chevron_right().size(30))
+                    .push(
+                        Button::new(text(tr("settings.managed_bitcoind")).size(30).bold())
+                            .style(theme::Button::Transparent)
+                            .on_press(Message::Settings(SettingsMessage::ManagedBitcoindSection)),
+                    ),
+            )
+            .push_maybe(step.map(|step| {
+                card::simple(
+                    Column::new()
+                        .spacing(10)
+                        .push(text(step.label()).bold())
+                        .push_maybe(if let ManagedBitcoindStep::Downloading(p) = step {
+                            Some(ProgressBar::new(0.0..=100.0, p).width(Length::Fill))
+                        } else {
+                            None
+                        })
+                        .push(
+                            p1_regular(
+                                "The wallet is not synchronized while bitcoind is not running.",
+                            )
+                            .style(color::GREY_3),
+                        ),
+                )
+                .width(Length::Fill)
+            }))
+            .push(managed_bitcoind_card(
+                "Pruning",
+                Column::new()
+                    .spacing(10)
+                    .push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .push(text("Prune target (MB):").bold())
+                            .push(
+                                form::Form::new_trimmed("Prune target", prune_form, |value| {
+                                    Message::Settings(SettingsMessage::ManagedBitcoind(
+                                        ManagedBitcoindMessage::PruneEdited(value),
+                                    ))
+                                })
+                                .warning("The prune target must be at least 550 MB")
+                                .size(P1_SIZE)
+                                .padding(5),
+                            )
+                            .push(
+                                button::secondary(None, "Save").on_press_maybe(
+                                    (idle
+                                        && prune_form.valid
+                                        && prune_form.value.parse::<u32>().ok() != prune)
+                                        .then_some(Message::Settings(
+                                            SettingsMessage::ManagedBitcoind(
+                                                ManagedBitcoindMessage::SavePrune,
+                                            ),
+                                        )),
+                                ),
+                            ),
+                    )
+                    .push_maybe(estimate.map(|gb| {
+                        p1_regular(format!("Estimated disk usage: about {} GB", gb))
+                            .style(color::GREY_3)
+                    })),
+            ))
+            .push(managed_bitcoind_card(
+                "Data directory",
+                Column::new()
+                    .spacing(10)
+                    .push(
+                        Row::new()
+                            .spacing(10)
+                            .push(text("Current location:").bold())
+                            .push(text(datadir.to_string_lossy())),
+                    )
+                    .push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Alignment::Center)
+                            .push(
+                                form::Form::new_trimmed("New location", datadir_form, |value| {
+                                    Message::Settings(SettingsMessage::ManagedBitcoind(
+                                        ManagedBitcoindMessage::DatadirEdited(value),
+                                    ))
+                                })
+                                .warning("Please enter an absolute path")
+                                .size(P1_SIZE)
+                                .padding(5),
+                            )
+                            .push(
+                                button::secondary(None, "Move").on_press_maybe(
+                                    (idle && datadir_form.valid && !datadir_form.value.is_empty())
+                                        .then_some(Message::Settings(
+                                            SettingsMessage::ManagedBitcoind(
+                                                ManagedBitcoindMessage::MoveDatadir,
+                                            ),
+                                        )),
+                                ),
+                            ),
+                    )
+                    .push(
+                        p1_regular(
+                            "The new location must be an empty directory. \
+                            The block data is moved there, which may take a while.",
+                        )
+                        .style(color::GREY_3),
+                    ),
+            ))
+            .push(managed_bitcoind_card(
+                "Version",
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(text(format!(
+                        "bitcoind v{}",
+                        installed_version.unwrap_or("unknown")
+                    )))
+                    .push(Space::with_width(Length::Fill))
+                    .push_maybe(upgrade_available.then(|| {
+                        button::primary(None, "Upgrade").on_press_maybe(idle.then_some(
+                            Message::Settings(SettingsMessage::ManagedBitcoind(
+                                ManagedBitcoindMessage::Upgrade,
+                            )),
+                        ))
+                    }))
+                    .push_maybe(upgrade_available.then(|| {
+                        p1_regular(format!("v{} is available", crate::node::bitcoind::VERSION))
+                            .style(color::GREY_3)
+                    })),
+            )),
+    )
+}
+
+/// The result of testing the connection to the Bitcoin backend with the edited settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionTest {
+    Pending,
+    Succeeded,
+    Failed(String),
+}
+
 pub fn bitcoind_settings<'a>(
     cache: &'a Cache,
     warning: Option<&Error>,
+    can_revert: bool,
     settings: Vec<Element<'a, Message>>,
 ) -> Element<'a, Message> {
     dashboard(
@@ -158,7 +431,7 @@ pub fn bitcoind_settings<'a>(
                     .spacing(10)
                     .align_items(Alignment::Center)
                     .push(
-                        Button::new(text("Settings").size(30).bold())
+                        Button::new(text(tr("settings.title")).size(30).bold())
                             .style(theme::Button::Transparent)
                             .on_press(Message::Menu(Menu::Settings)),
                     )
@@ -169,6 +442,27 @@ pub fn bitcoind_settings<'a>(
                             .on_press(Message::Settings(SettingsMessage::EditBitcoindSettings)),
                     ),
             )
+            .push_maybe(if can_revert {
+                Some(card::simple(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(
+                            text(
+                                "The node settings were updated. If the wallet does not work \
+                                as expected with them, you can restore the previous ones.",
+                            )
+                            .width(Length::Fill),
+                        )
+                        .push(
+                            button::secondary(None, "Revert").on_press(Message::Settings(
+                                SettingsMessage::RevertBitcoinBackendSettings,
+                            )),
+                        ),
+                ))
+            } else {
+                None
+            })
             .push(Column::with_children(settings).spacing(20)),
     )
 }
@@ -189,7 +483,7 @@ pub fn about_section<'a>(
                     .spacing(10)
                     .align_items(Alignment::Center)
                     .push(
-                        Button::new(text("Settings").size(30).bold())
+                        Button::new(text(tr("settings.title")).size(30).bold())
                             .style(theme::Button::Transparent)
                             .on_press(Message::Menu(Menu::Settings)),
                     )
@@ -230,6 +524,86 @@ pub fn about_section<'a>(
     )
 }
 
+pub fn profile_section<'a>(
+    cache: &'a Cache,
+    warning: Option<&Error>,
+    processing: bool,
+    exported: Option<&PathBuf>,
+    imported: bool,
+) -> Element<'a, Message> {
+    dashboard(
+        &Menu::Settings,
+        cache,
+        warning,
+        Column::new()
+            .spacing(20)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(
+                        Button::new(text(tr("settings.title")).size(30).bold())
+                            .style(theme::Button::Transparent)
+                            .on_press(Message::Menu(Menu::Settings)),
+                    )
+                    .push(icon::chevron_right().size(30))
+                    .push(
+                        Button::new(text("Configuration profile").size(30).bold())
+                            .style(theme::Button::Transparent)
+                            .on_press(Message::Settings(SettingsMessage::ProfileSection)),
+                    ),
+            )
+            .push(
+                card::simple(
+                    Column::new()
+                        .spacing(20)
+                        .push(text(
+                            "A profile holds the log level, the node address and the aliases of the keys. \
+                            It never contains any credential or secret.",
+                        ))
+                        .push(
+                            text(
+                                "Once imported, the aliases of the keys are updated right away. \
+                                The log level applies after a restart and the node address \
+                                is used as default for new wallets.",
+                            )
+                            .small(),
+                        )
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push_maybe(exported.map(|path| {
+                                    text(format!("Profile exported to {}", path.to_string_lossy()))
+                                        .style(color::GREEN)
+                                }))
+                                .push_maybe(if imported {
+                                    Some(text("Profile imported").style(color::GREEN))
+                                } else {
+                                    None
+                                })
+                                .push(Space::with_width(Length::Fill))
+                                .push(button::secondary(None, "Import").on_press_maybe(
+                                    if !processing {
+                                        Some(Message::Settings(SettingsMessage::ImportProfile))
+                                    } else {
+                                        None
+                                    },
+                                ))
+                                .push(button::secondary(None, "Export").on_press_maybe(
+                                    if !processing {
+                                        Some(Message::Settings(SettingsMessage::ExportProfile))
+                                    } else {
+                                        None
+                                    },
+                                )),
+                        ),
+                )
+                .width(Length::Fill),
+            ),
+    )
+}
+
 pub fn remote_backend_section<'a>(
     cache: &'a Cache,
     email_form: &form::Value<String>,
@@ -248,7 +622,7 @@ pub fn remote_backend_section<'a>(
                     .spacing(10)
                     .align_items(Alignment::Center)
                     .push(
-                        Button::new(text("Settings").size(30).bold())
+                        Button::new(text(tr("settings.title")).size(30).bold())
                             .style(theme::Button::Transparent)
                             .on_press(Message::Menu(Menu::Settings)),
                     )
@@ -302,6 +676,54 @@ pub fn remote_backend_section<'a>(
     )
 }
 
+/// The result of the last connection test, if any.
+fn connection_test_result<'a>(
+    test: Option<&ConnectionTest>,
+) -> Option<Element<'a, SettingsEditMessage>> {
+    match test? {
+        ConnectionTest::Pending => Some(text("Testing the connection...").small().into()),
+        ConnectionTest::Succeeded => Some(
+            text("Connection successful")
+                .small()
+                .style(color::GREEN)
+                .into(),
+        ),
+        ConnectionTest::Failed(e) => Some(text(e.clone()).small().style(color::RED).into()),
+    }
+}
+
+/// The buttons to cancel the edition, test the connection and save the settings.
+fn edit_buttons<'a>(
+    test: Option<&ConnectionTest>,
+    processing: bool,
+) -> Element<'a, SettingsEditMessage> {
+    let mut cancel_button = button::transparent(None, " Cancel ").padding(5);
+    let mut test_button = button::secondary(None, " Test connection ").padding(5);
+    let mut confirm_button = button::secondary(None, " Save & reconnect ").padding(5);
+    if !processing {
+        cancel_button = cancel_button.on_press(SettingsEditMessage::Cancel);
+        confirm_button = confirm_button.on_press(SettingsEditMessage::Confirm);
+        if test != Some(&ConnectionTest::Pending) {
+            test_button = test_button.on_press(SettingsEditMessage::TestConnection);
+        }
+    }
+    Column::new()
+        .spacing(10)
+        .align_items(Alignment::End)
+        .push_maybe(connection_test_result(test))
+        .push(
+            Row::new()
+                .push(cancel_button)
+                .push(test_button)
+                .push(confirm_button)
+                .spacing(10)
+                .align_items(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .into()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn bitcoind_edit<'a>(
     is_configured_node_type: bool,
     network: Network,
@@ -309,6 +731,7 @@ pub fn bitcoind_edit<'a>(
     addr: &form::Value<String>,
     rpc_auth_vals: &RpcAuthValues,
     selected_auth_type: &RpcAuthType,
+    connection_test: Option<&ConnectionTest>,
     processing: bool,
 ) -> Element<'a, SettingsEditMessage> {
     let mut col = Column::new().spacing(20);
@@ -370,7 +793,7 @@ pub fn bitcoind_edit<'a>(
                         &rpc_auth_vals.cookie_path,
                         |value| SettingsEditMessage::FieldEdited("cookie_file_path", value),
                     )
-                    .warning("Please enter a valid filesystem path")
+                    .warning("No cookie file was found at this path")
                     .size(P1_SIZE)
                     .padding(5),
                 )
@@ -382,7 +805,7 @@ pub fn bitcoind_edit<'a>(
                             form::Form::new_trimmed("User", &rpc_auth_vals.user, |value| {
                                 SettingsEditMessage::FieldEdited("user", value)
                             })
-                            .warning("Please enter a valid user")
+                            .warning("Please enter a user")
                             .size(P1_SIZE)
                             .padding(5),
                         )
@@ -390,7 +813,7 @@ pub fn bitcoind_edit<'a>(
                             form::Form::new_trimmed("Password", &rpc_auth_vals.password, |value| {
                                 SettingsEditMessage::FieldEdited("password", value)
                             })
-                            .warning("Please enter a valid password")
+                            .warning("Please enter a password")
                             .size(P1_SIZE)
                             .padding(5),
                         )
@@ -412,13 +835,6 @@ pub fn bitcoind_edit<'a>(
                 .spacing(5),
         );
 
-    let mut cancel_button = button::transparent(None, " Cancel ").padding(5);
-    let mut confirm_button = button::secondary(None, " Save ").padding(5);
-    if !processing {
-        cancel_button = cancel_button.on_press(SettingsEditMessage::Cancel);
-        confirm_button = confirm_button.on_press(SettingsEditMessage::Confirm);
-    }
-
     card::simple(Container::new(
         Column::new()
             .push(
@@ -432,17 +848,7 @@ pub fn bitcoind_edit<'a>(
             )
             .push(separation().width(Length::Fill))
             .push(col)
-            .push(
-                Container::new(
-                    Row::new()
-                        .push(cancel_button)
-                        .push(confirm_button)
-                        .spacing(10)
-                        .align_items(Alignment::Center),
-                )
-                .width(Length::Fill)
-                .align_x(alignment::Horizontal::Right),
-            )
+            .push(edit_buttons(connection_test, processing))
             .spacing(20),
     ))
     .width(Length::Fill)
@@ -562,6 +968,9 @@ pub fn electrum_edit<'a>(
     network: Network,
     blockheight: i32,
     addr: &form::Value<String>,
+    socks5_proxy: &form::Value<String>,
+    skip_certificate_validation: bool,
+    connection_test: Option<&ConnectionTest>,
     processing: bool,
 ) -> Element<'a, SettingsEditMessage> {
     let mut col = Column::new().spacing(20);
@@ -595,27 +1004,42 @@ pub fn electrum_edit<'a>(
             .push(separation().width(Length::Fill));
     }
 
-    col = col.push(
-        Column::new()
-            .push(text("Address:").bold().small())
-            .push(
-                form::Form::new_trimmed("127:0.0.1:50001", addr, |value| {
-                    SettingsEditMessage::FieldEdited("address", value)
-                })
-                .warning("Please enter a valid address")
-                .size(P1_SIZE)
-                .padding(5),
+    col = col
+        .push(
+            Column::new()
+                .push(text("Address:").bold().small())
+                .push(
+                    form::Form::new_trimmed("127:0.0.1:50001", addr, |value| {
+                        SettingsEditMessage::FieldEdited("address", value)
+                    })
+                    .warning("Please enter a valid address")
+                    .size(P1_SIZE)
+                    .padding(5),
+                )
+                .push(text(electrum::ADDRESS_NOTES).size(P2_SIZE))
+                .spacing(5),
+        )
+        .push(
+            Column::new()
+                .push(text("SOCKS5 proxy (optional):").bold().small())
+                .push(
+                    form::Form::new_trimmed("127.0.0.1:9050", socks5_proxy, |value| {
+                        SettingsEditMessage::FieldEdited("socks5_proxy", value)
+                    })
+                    .warning("Please enter a valid proxy address, such as 127.0.0.1:9050")
+                    .size(P1_SIZE)
+                    .padding(5),
+                )
+                .spacing(5),
+        )
+        .push(
+            checkbox(
+                "Do not validate the TLS certificate of the server (needed for self-signed \
+            certificates, the server is then not authenticated)",
+                skip_certificate_validation,
             )
-            .push(text(electrum::ADDRESS_NOTES).size(P2_SIZE))
-            .spacing(5),
-    );
-
-    let mut cancel_button = button::transparent(None, " Cancel ").padding(5);
-    let mut confirm_button = button::secondary(None, " Save ").padding(5);
-    if !processing {
-        cancel_button = cancel_button.on_press(SettingsEditMessage::Cancel);
-        confirm_button = confirm_button.on_press(SettingsEditMessage::Confirm);
-    }
+            .on_toggle(SettingsEditMessage::SkipCertificateValidation),
+        );
 
     card::simple(Container::new(
         Column::new()
@@ -630,17 +1054,7 @@ pub fn electrum_edit<'a>(
             )
             .push(separation().width(Length::Fill))
             .push(col)
-            .push(
-                Container::new(
-                    Row::new()
-                        .push(cancel_button)
-                        .push(confirm_button)
-                        .spacing(10)
-                        .align_items(Alignment::Center),
-                )
-                .width(Length::Fill)
-                .align_x(alignment::Horizontal::Right),
-            )
+            .push(edit_buttons(connection_test, processing))
             .spacing(20),
     ))
     .width(Length::Fill)
@@ -687,7 +1101,11 @@ pub fn electrum<'a>(
     }
 
     let rows = if is_configured_node_type {
-        vec![("Address:", config.addr.to_string())]
+        let mut rows = vec![("Address:", config.addr.to_string())];
+        if let Some(proxy) = &config.socks5_proxy {
+            rows.push(("SOCKS5 proxy:", proxy.clone()));
+        }
+        rows
     } else {
         vec![]
     };
@@ -776,6 +1194,7 @@ pub fn rescan<'a>(
     invalid_date: bool,
     past_possible_height: bool,
     future_date: bool,
+    backend_reachable: bool,
 ) -> Element<'a, SettingsEditMessage> {
     card::simple(Container::new(
         Column::new()
@@ -853,6 +1272,7 @@ pub fn rescan<'a>(
                         })
                         .push(
                             if can_edit
+                                && backend_reachable
                                 && !invalid_date
                                 && !processing
                                 && (is_ok_and(&u32::from_str(&year.value), |&v| v > 0)
@@ -871,6 +1291,13 @@ pub fn rescan<'a>(
                                     button::secondary(None, "Starting rescan...")
                                         .width(Length::Shrink),
                                 )
+                            } else if !backend_reachable {
+                                Row::new().push(Column::new().width(Length::Fill)).push(
+                                    super::unavailable_while_unreachable(
+                                        button::secondary(None, "Start rescan")
+                                            .width(Length::Shrink),
+                                    ),
+                                )
                             } else {
                                 Row::new().push(Column::new().width(Length::Fill)).push(
                                     button::secondary(None, "Start rescan").width(Length::Shrink),
@@ -893,14 +1320,27 @@ fn is_ok_and<T, E>(res: &Result<T, E>, f: impl FnOnce(&T) -> bool) -> bool {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn wallet_settings<'a>(
     cache: &'a Cache,
     warning: Option<&Error>,
     descriptor: &'a LianaDescriptor,
     keys_aliases: &'a [(Fingerprint, form::Value<String>)],
+    notifications: &NotificationSettings,
+    hot_signer_unlock: Option<HotSignerUnlock>,
+    auto_lock: Option<&AutoLockSettings>,
+    auto_lock_timeout: &'a form::Value<String>,
+    auto_lock_pin: &'a form::Value<String>,
     processing: bool,
     updated: bool,
+    diagram_exported: Option<&PathBuf>,
 ) -> Element<'a, Message> {
+    let diagram = policy::diagram(&descriptor.policy(), |fg| {
+        keys_aliases
+            .iter()
+            .find(|(k, _)| k == fg)
+            .map(|(_, alias)| alias.value.clone())
+    });
     dashboard(
         &Menu::Settings,
         cache,
@@ -912,7 +1352,7 @@ pub fn wallet_settings<'a>(
                     .spacing(10)
                     .align_items(Alignment::Center)
                     .push(
-                        Button::new(text("Settings").size(30).bold())
+                        Button::new(text(tr("settings.title")).size(30).bold())
                             .style(theme::Button::Transparent)
                             .on_press(Message::Menu(Menu::Settings)),
                     )
@@ -960,7 +1400,49 @@ pub fn wallet_settings<'a>(
                 .width(Length::Fill),
             )
             .push(
-                card::simple(display_policy(descriptor.policy(), keys_aliases)).width(Length::Fill),
+                card::simple(
+                    Column::new()
+                        .spacing(10)
+                        .push(display_policy(descriptor.policy(), keys_aliases))
+                        .push(text(
+                            descriptor.human_readable_policy(
+                                &keys_aliases
+                                    .iter()
+                                    .map(|(fg, alias)| (*fg, alias.value.clone()))
+                                    .collect(),
+                            ),
+                        )),
+                )
+                .width(Length::Fill),
+            )
+            .push(
+                card::simple(
+                    Column::new()
+                        .spacing(10)
+                        .push(text("Policy diagram:").bold())
+                        .push(scrollable(policy_diagram(diagram)).direction(
+                            scrollable::Direction::Horizontal(
+                                scrollable::Properties::new().width(5).scroller_width(5),
+                            ),
+                        ))
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(Space::with_width(Length::Fill))
+                                .push_maybe(diagram_exported.map(|path| {
+                                    text(format!(
+                                        "Policy diagram exported to {}",
+                                        path.to_string_lossy()
+                                    ))
+                                    .style(color::GREEN)
+                                }))
+                                .push(button::secondary(None, "Export as PNG").on_press(
+                                    Message::Settings(SettingsMessage::ExportPolicyDiagram),
+                                )),
+                        ),
+                )
+                .width(Length::Fill),
             )
             .push(
                 card::simple(
@@ -1016,10 +1498,195 @@ pub fn wallet_settings<'a>(
                         .spacing(10),
                 )
                 .width(Length::Fill),
+            )
+            .push(card::simple(bitcoin_unit_settings(cache.bitcoin_unit)).width(Length::Fill))
+            .push(card::simple(language_settings(i18n::language())).width(Length::Fill))
+            .push(card::simple(notifications_settings(notifications)).width(Length::Fill))
+            .push_maybe(hot_signer_unlock.map(|selected| {
+                card::simple(hot_signer_unlock_settings(selected)).width(Length::Fill)
+            }))
+            .push(
+                card::simple(auto_lock_settings(
+                    auto_lock,
+                    auto_lock_timeout,
+                    auto_lock_pin,
+                    processing,
+                ))
+                .width(Length::Fill),
             ),
     )
 }
 
+fn bitcoin_unit_settings<'a>(selected: BitcoinDisplayUnit) -> Element<'a, Message> {
+    BitcoinDisplayUnit::ALL
+        .iter()
+        .fold(
+            Row::new()
+                .push(text(tr("settings.display_amounts_in")).bold())
+                .spacing(30)
+                .align_items(Alignment::Center),
+            |row, unit| {
+                row.push(radio(unit.to_string(), *unit, Some(selected), |unit| {
+                    Message::Settings(SettingsMessage::BitcoinUnitSelected(unit))
+                }))
+            },
+        )
+        .into()
+}
+
+fn language_settings<'a>(selected: Language) -> Element<'a, Message> {
+    Language::ALL
+        .iter()
+        .fold(
+            Row::new()
+                .push(text(tr("settings.language")).bold())
+                .spacing(30)
+                .align_items(Alignment::Center),
+            |row, language| {
+                row.push(radio(
+                    language.to_string(),
+                    *language,
+                    Some(selected),
+                    |language| Message::Settings(SettingsMessage::LanguageSelected(language)),
+                ))
+            },
+        )
+        .into()
+}
+
+fn notifications_settings<'a>(notifications: &NotificationSettings) -> Element<'a, Message> {
+    [
+        (
+            NotificationKind::Incoming,
+            "Incoming transaction detected (unconfirmed)",
+        ),
+        (NotificationKind::Confirmed, "Transaction confirmed"),
+        (
+            NotificationKind::RecoveryApproaching,
+            "Recovery path about to become available",
+        ),
+    ]
+    .iter()
+    .fold(
+        Column::new()
+            .spacing(10)
+            .push(text("Desktop notifications:").bold()),
+        |col, (kind, label)| {
+            let kind = *kind;
+            col.push(
+                checkbox(*label, notifications.is_enabled(kind)).on_toggle(move |enabled| {
+                    Message::Settings(SettingsMessage::NotificationToggled(kind, enabled))
+                }),
+            )
+        },
+    )
+    .into()
+}
+
+fn hot_signer_unlock_settings<'a>(selected: HotSignerUnlock) -> Element<'a, Message> {
+    HotSignerUnlock::ALL
+        .iter()
+        .fold(
+            Column::new()
+                .spacing(10)
+                .push(text(tr("settings.hot_signer_unlock")).bold())
+                .push(
+                    p2_regular(tr("settings.hot_signer_unlock_description")).style(color::GREY_3),
+                ),
+            |col, unlock| {
+                col.push(radio(
+                    match unlock {
+                        HotSignerUnlock::OnOpen => tr("settings.hot_signer_unlock_on_open"),
+                        HotSignerUnlock::OnSigning => tr("settings.hot_signer_unlock_on_signing"),
+                    },
+                    *unlock,
+                    Some(selected),
+                    |unlock| Message::Settings(SettingsMessage::HotSignerUnlockSelected(unlock)),
+                ))
+            },
+        )
+        .into()
+}
+
+fn auto_lock_settings<'a>(
+    auto_lock: Option<&AutoLockSettings>,
+    timeout: &'a form::Value<String>,
+    pin: &'a form::Value<String>,
+    processing: bool,
+) -> Element<'a, Message> {
+    let can_save = !processing
+        && timeout.valid
+        && !timeout.value.is_empty()
+        && settings::is_valid_pin(&pin.value);
+    Column::new()
+        .spacing(10)
+        .push(text("Auto-lock:").bold())
+        .push(
+            p2_regular(
+                "Hide the wallet behind a lock screen after some inactivity, and ask for the PIN \
+                before signing or broadcasting a transaction. This is a privacy screen: the wallet \
+                data is not encrypted and a forgotten PIN is bypassed by restarting the application.",
+            )
+            .style(color::GREY_3),
+        )
+        .push(text(match auto_lock {
+            Some(auto_lock) => format!(
+                "Enabled, locks after {} minute{} of inactivity.",
+                auto_lock.timeout_minutes,
+                if auto_lock.timeout_minutes > 1 { "s" } else { "" }
+            ),
+            None => "Disabled.".to_string(),
+        }))
+        .push(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    Container::new(
+                        form::Form::new("Minutes", timeout, |value| {
+                            Message::Settings(SettingsMessage::AutoLockTimeoutEdited(value))
+                        })
+                        .warning("Between 1 and 1440 minutes")
+                        .size(P1_SIZE)
+                        .padding(10),
+                    )
+                    .width(Length::Fixed(200.0)),
+                )
+                .push(
+                    form::Form::new(
+                        if auto_lock.is_some() { "New PIN" } else { "PIN" },
+                        pin,
+                        |value| Message::Settings(SettingsMessage::AutoLockPinEdited(value)),
+                    )
+                    .warning("The PIN must be made of 4 to 12 digits")
+                    .secure()
+                    .size(P1_SIZE)
+                    .padding(10),
+                ),
+        )
+        .push(
+            Row::new()
+                .spacing(10)
+                .push(Space::with_width(Length::Fill))
+                .push_maybe(auto_lock.map(|_| {
+                    button::secondary(None, "Disable").on_press_maybe(if processing {
+                        None
+                    } else {
+                        Some(Message::Settings(SettingsMessage::DisableAutoLock))
+                    })
+                }))
+                .push(
+                    button::secondary(None, if auto_lock.is_some() { "Update" } else { "Enable" })
+                        .on_press_maybe(if can_save {
+                            Some(Message::Settings(SettingsMessage::EnableAutoLock))
+                        } else {
+                            None
+                        }),
+                ),
+        )
+        .into()
+}
+
 fn display_policy(
     policy: LianaPolicy,
     keys_aliases: &[(Fingerprint, form::Value<String>)],
@@ -1195,14 +1862,24 @@ pub fn register_wallet_modal<'a>(
     processing: bool,
     chosen_hw: Option<usize>,
     registered: &HashSet<Fingerprint>,
+    wallet_name: &str,
+    descriptor: &LianaDescriptor,
 ) -> Element<'a, Message> {
     Column::new()
         .push_maybe(warning.map(|w| warn(Some(w))))
         .push(card::simple(
             Column::new()
+                .push(hw::wallet_to_register(wallet_name, descriptor))
                 .push(
                     Column::new()
                         .push(text("Select device:").bold().width(Length::Fill))
+                        .push(
+                            text(
+                                "A device already registered can be registered again, for \
+                                 instance if it was restored or its firmware updated.",
+                            )
+                            .small(),
+                        )
                         .spacing(10)
                         .push(hws.iter().enumerate().fold(
                             Column::new().spacing(10),
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Local, Utc};
 use iced::{
     alignment,
     widget::{checkbox, scrollable, tooltip, Space},
@@ -8,12 +9,17 @@ use iced::{
 
 use liana::{
     descriptors::LianaPolicy,
-    miniscript::bitcoin::{bip32::Fingerprint, Amount, Denomination, Network},
+    miniscript::bitcoin::{bip32::Fingerprint, Amount, Network},
 };
 
 use liana_ui::{
     color,
-    component::{amount::*, badge, button, form, text::*},
+    component::{
+        amount::*,
+        amount_input::{AmountInput, ExchangeRate},
+        badge, button, card, form,
+        text::*,
+    },
     icon, theme,
     widget::*,
 };
@@ -21,11 +27,16 @@ use liana_ui::{
 use crate::{
     app::{
         cache::Cache,
+        draft::SpendDraft,
         error::Error,
         menu::Menu,
         view::{coins, dashboard, message::*, psbt},
     },
-    daemon::model::{remaining_sequence, Coin, SpendTx},
+    daemon::model::{
+        confirmation_target, mixed_tags, remaining_sequence, Coin, FeeratePreset, SpendTx,
+        FALLBACK_FEERATE,
+    },
+    i18n::{tr, tr_args, tr_plural},
 };
 
 #[allow(clippy::too_many_arguments)]
@@ -46,8 +57,8 @@ pub fn spend_view<'a>(
         warning,
         Column::new()
             .spacing(20)
-            .push(Container::new(h3("Send")).width(Length::Fill))
-            .push(psbt::spend_header(tx, labels_editing))
+            .push(Container::new(h3(tr("send.title"))).width(Length::Fill))
+            .push(psbt::spend_header(tx, labels_editing, cache.bitcoin_unit))
             .push_maybe(if spend_warnings.is_empty() || saved {
                 None
             } else {
@@ -63,7 +74,13 @@ pub fn spend_view<'a>(
                     },
                 ))
             })
-            .push(psbt::spend_overview_view(tx, desc_info, key_aliases))
+            .push(psbt::spend_overview_view(
+                tx,
+                desc_info,
+                key_aliases,
+                cache.backend_status.is_reachable(),
+            ))
+            .push(psbt::signing_progress(tx, desc_info, key_aliases))
             .push(
                 Column::new()
                     .spacing(20)
@@ -72,6 +89,7 @@ pub fn spend_view<'a>(
                         &tx.psbt.unsigned_tx,
                         &tx.labels,
                         labels_editing,
+                        cache.bitcoin_unit,
                     ))
                     .push(psbt::outputs_view(
                         &tx.psbt.unsigned_tx,
@@ -80,12 +98,13 @@ pub fn spend_view<'a>(
                         &tx.labels,
                         labels_editing,
                         tx.is_single_payment().is_some(),
+                        cache.bitcoin_unit,
                     )),
             )
             .push(if saved {
                 Row::new()
                     .push(
-                        button::secondary(None, "Delete")
+                        button::secondary(None, tr("common.delete"))
                             .width(Length::Fixed(200.0))
                             .on_press(Message::Spend(SpendTxMessage::Delete)),
                     )
@@ -99,9 +118,18 @@ pub fn spend_view<'a>(
                     )
                     .push(Space::with_width(Length::Fill))
                     .push(
-                        button::secondary(None, "Save")
-                            .width(Length::Fixed(150.0))
-                            .on_press(Message::Spend(SpendTxMessage::Save)),
+                        Row::new()
+                            .spacing(10)
+                            .push(
+                                button::secondary(None, tr("send.save_draft"))
+                                    .width(Length::Fixed(150.0))
+                                    .on_press(Message::Spend(SpendTxMessage::SaveDraft)),
+                            )
+                            .push(
+                                button::secondary(None, tr("common.save"))
+                                    .width(Length::Fixed(150.0))
+                                    .on_press(Message::Spend(SpendTxMessage::Save)),
+                            ),
                     )
                     .width(Length::Fill)
             }),
@@ -114,12 +142,19 @@ pub fn create_spend_tx<'a>(
     recipients: Vec<Element<'a, Message>>,
     is_valid: bool,
     duplicate: bool,
+    spend_paths: Vec<Element<'a, Message>>,
     timelock: u16,
     coins: &[(Coin, bool)],
+    show_coin_control: bool,
+    mixed_tags_confirmed: bool,
+    input_size: usize,
+    min_tx_vbytes: usize,
     coins_labels: &'a HashMap<String, String>,
     batch_label: &form::Value<String>,
     amount_left: Option<&Amount>,
     feerate: &form::Value<String>,
+    feerate_preset: Option<FeeratePreset>,
+    saved_draft: Option<&SpendDraft>,
     error: Option<&Error>,
 ) -> Element<'a, Message> {
     let is_self_send = recipients.is_empty();
@@ -129,13 +164,14 @@ pub fn create_spend_tx<'a>(
         error,
         Column::new()
             .push(h3(if is_self_send {
-                "Self-transfer"
+                tr("send.self_transfer")
             } else {
-                "Send"
+                tr("send.title")
             }))
+            .push_maybe(saved_draft.map(|draft| saved_draft_view(cache, draft)))
             .push_maybe(if recipients.len() > 1 {
                 Some(
-                    form::Form::new("Batch label", batch_label, |s| {
+                    form::Form::new(tr("send.batch_label"), batch_label, |s| {
                         Message::CreateSpend(CreateSpendMessage::BatchLabelEdited(s))
                     })
                     .warning("Invalid label length, cannot be superior to 100")
@@ -153,8 +189,7 @@ pub fn create_spend_tx<'a>(
                             .push_maybe(if duplicate {
                                 Some(
                                     Container::new(
-                                        text("Two payment addresses are the same")
-                                            .style(color::RED),
+                                        text(tr("send.duplicate_addresses")).style(color::RED),
                                     )
                                     .padding(10),
                                 )
@@ -166,131 +201,54 @@ pub fn create_spend_tx<'a>(
                                 None
                             } else {
                                 Some(
-                                    button::secondary(Some(icon::plus_icon()), "Add payment")
-                                        .on_press(Message::CreateSpend(
-                                            CreateSpendMessage::AddRecipient,
-                                        )),
+                                    button::secondary(
+                                        Some(icon::plus_icon()),
+                                        tr("send.add_payment"),
+                                    )
+                                    .on_press(
+                                        Message::CreateSpend(CreateSpendMessage::AddRecipient),
+                                    ),
                                 )
                             }),
                     )
                     .spacing(20),
             )
-            .push(
-                Row::new()
-                    .push(
-                        Row::new()
-                            .push(Container::new(p1_bold("Feerate")).padding(10))
-                            .spacing(10)
-                            .push(
-                                form::Form::new_trimmed(
-                                    "42 (in sats/vbyte)",
-                                    feerate,
-                                    move |msg| {
-                                        Message::CreateSpend(CreateSpendMessage::FeerateEdited(msg))
-                                    },
-                                )
-                                .warning(
-                                    "Feerate must be an integer less than \
-                                    or equal to 1000 sats/vbyte",
-                                )
-                                .size(P1_SIZE)
-                                .padding(10),
-                            )
-                            .width(Length::FillPortion(1)),
-                    )
-                    .push(Space::with_width(Length::FillPortion(1))),
-            )
-            .push(
-                Container::new(
+            .push_maybe(if spend_paths.is_empty() {
+                None
+            } else {
+                Some(
                     Column::new()
                         .spacing(10)
-                        .push(
-                            Row::new()
-                                .align_items(Alignment::Center)
-                                .push(p1_bold("Coins selection").width(Length::Fill))
-                                .push(if is_self_send {
-                                    Row::new()
-                                        .spacing(5)
-                                        .push(amount_with_size(
-                                            &Amount::from_sat(
-                                                coins
-                                                    .iter()
-                                                    .filter_map(|(coin, selected)| {
-                                                        if *selected {
-                                                            Some(coin.amount.to_sat())
-                                                        } else {
-                                                            None
-                                                        }
-                                                    })
-                                                    .sum(),
-                                            ),
-                                            P2_SIZE,
-                                        ))
-                                        .push(p2_regular("selected").style(color::GREY_3))
-                                } else if let Some(amount_left) = amount_left {
-                                    if amount_left.to_sat() == 0 && !is_valid {
-                                        // If amount left is set, the current configuration must be redraftable.
-                                        // If it's not valid, either no coins are selected or there's a recipient
-                                        // with max selected and invalid amount.
-                                        if coins.iter().all(|(_, selected)| !selected) {
-                                            // This can happen if we have a single recipient
-                                            // and it has the max selected.
-                                            Row::new().push(
-                                                text("Select at least one coin.")
-                                                    .style(color::GREY_3),
-                                            )
-                                        } else {
-                                            // There must be a recipient with max selected and value 0.
-                                            Row::new().push(
-                                                text("Check max amount for recipient.")
-                                                    .style(color::GREY_3),
-                                            )
-                                        }
-                                    } else {
-                                        Row::new()
-                                            .spacing(5)
-                                            .push(amount_with_size(amount_left, P2_SIZE))
-                                            .push(p2_regular("left to select").style(color::GREY_3))
-                                    }
-                                } else {
-                                    Row::new().push(
-                                        text(if feerate.value.is_empty() || !feerate.valid {
-                                            "Feerate needs to be set."
-                                        } else {
-                                            "Add recipient details."
-                                        })
-                                        .style(color::GREY_3),
-                                    )
-                                })
-                                .width(Length::Fill),
-                        )
-                        .push(
-                            Container::new(scrollable(coins.iter().enumerate().fold(
-                                Column::new().spacing(10),
-                                |col, (i, (coin, selected))| {
-                                    col.push(coin_list_view(
-                                        i,
-                                        coin,
-                                        coins_labels,
-                                        timelock,
-                                        cache.blockheight as u32,
-                                        *selected,
-                                    ))
-                                },
-                            )))
-                            .max_height(300),
-                        ),
+                        .push(p1_bold(tr("send.spend_path")))
+                        .push(Column::with_children(spend_paths).spacing(10)),
                 )
-                .padding(20)
-                .style(theme::Card::Simple),
-            )
+            })
+            .push(feerate_selector(
+                cache,
+                feerate,
+                feerate_preset,
+                min_tx_vbytes,
+            ))
+            .push(coin_control(
+                cache,
+                is_self_send,
+                is_valid,
+                timelock,
+                coins,
+                coins_labels,
+                amount_left,
+                feerate,
+                show_coin_control,
+                mixed_tags_confirmed,
+                input_size,
+            ))
             .push(
                 Row::new()
                     .spacing(20)
                     .align_items(Alignment::Center)
                     .push(Space::with_width(Length::Fill))
                     .push(
-                        button::secondary(None, "Clear")
+                        button::secondary(None, tr("common.clear"))
                             .on_press(Message::CreateSpend(CreateSpendMessage::Clear))
                             .width(Length::Fixed(100.0)),
                     )
@@ -299,11 +257,11 @@ pub fn create_spend_tx<'a>(
                             && !duplicate
                             && (is_self_send || Some(&Amount::from_sat(0)) == amount_left)
                         {
-                            button::secondary(None, "Next")
+                            button::secondary(None, tr("common.next"))
                                 .on_press(Message::CreateSpend(CreateSpendMessage::Generate))
                                 .width(Length::Fixed(100.0))
                         } else {
-                            button::secondary(None, "Next").width(Length::Fixed(100.0))
+                            button::secondary(None, tr("common.next")).width(Length::Fixed(100.0))
                         },
                     ),
             )
@@ -312,12 +270,548 @@ pub fn create_spend_tx<'a>(
     )
 }
 
+/// How many recipients of the saved spend are previewed.
+const SAVED_DRAFT_PREVIEW_RECIPIENTS: usize = 5;
+
+/// The spend saved on disk, with its first recipients, to resume or discard.
+fn saved_draft_view<'a>(cache: &Cache, draft: &SpendDraft) -> Element<'a, Message> {
+    let saved_at = DateTime::<Utc>::from_timestamp(draft.saved_at as i64, 0)
+        .map(|date| {
+            date.with_timezone(&Local)
+                .format("%b. %d, %Y - %T")
+                .to_string()
+        })
+        .unwrap_or_default();
+    card::simple(
+        Column::new()
+            .spacing(10)
+            .push(p1_bold(tr_args(
+                "send.resume_draft",
+                &[("time", &saved_at)],
+            )))
+            .push(
+                Column::with_children(
+                    draft
+                        .recipients
+                        .iter()
+                        .filter(|r| !r.address.is_empty())
+                        .take(SAVED_DRAFT_PREVIEW_RECIPIENTS)
+                        .map(|r| {
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(p2_regular(r.address.clone()).style(color::GREY_3))
+                                .push(Space::with_width(Length::Fill))
+                                .push_maybe(r.amount.map(|a| {
+                                    amount_with_size(
+                                        &Amount::from_sat(a),
+                                        P2_SIZE,
+                                        cache.bitcoin_unit,
+                                    )
+                                }))
+                                .into()
+                        })
+                        .collect(),
+                )
+                .spacing(5),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(p2_regular(tr_plural(
+                        "send.draft_recipients",
+                        draft.recipients.len() as u64,
+                    )))
+                    .push(amount_with_size(
+                        &Amount::from_sat(draft.total_amount()),
+                        P2_SIZE,
+                        cache.bitcoin_unit,
+                    ))
+                    .push_maybe(if draft.feerate.is_empty() {
+                        None
+                    } else {
+                        Some(p2_regular(format!("{} sats/vbyte", draft.feerate)))
+                    })
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        button::secondary(None, tr("send.discard_draft"))
+                            .on_press(Message::CreateSpend(CreateSpendMessage::DiscardDraft)),
+                    )
+                    .push(
+                        button::primary(None, tr("send.resume"))
+                            .on_press(Message::CreateSpend(CreateSpendMessage::ResumeDraft)),
+                    ),
+            ),
+    )
+    .into()
+}
+
+/// A spending path of the wallet, the primary one if `timelock` is `None`, along with the
+/// signers it requires. `blocks_left` is set if the path isn't available yet for any coin, in
+/// which case it can't be selected.
+pub fn spend_path_view<'a>(
+    timelock: Option<u16>,
+    threshold: usize,
+    signers: Vec<String>,
+    blocks_left: Option<u32>,
+    selected: bool,
+) -> Element<'a, Message> {
+    Row::new()
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .push(if blocks_left.is_none() {
+            checkbox("", selected).on_toggle(move |_| {
+                Message::CreateSpend(CreateSpendMessage::SelectSpendPath(timelock))
+            })
+        } else {
+            checkbox("", selected)
+        })
+        .push(
+            Column::new()
+                .spacing(5)
+                .push(p1_bold(match timelock {
+                    None => tr("send.primary_path").to_string(),
+                    Some(timelock) => {
+                        tr_args("send.recovery_path", &[("blocks", &timelock.to_string())])
+                    }
+                }))
+                .push(
+                    p2_regular(tr_args(
+                        "send.path_signers",
+                        &[
+                            ("threshold", &threshold.to_string()),
+                            ("signers", &signers.join(", ")),
+                        ],
+                    ))
+                    .style(color::GREY_3),
+                ),
+        )
+        .push(Space::with_width(Length::Fill))
+        .push(match blocks_left {
+            None => p2_regular(tr("send.path_active")),
+            Some(blocks) => p2_regular(tr_args(
+                "send.path_inactive",
+                &[("blocks", &blocks.to_string())],
+            ))
+            .style(color::GREY_3),
+        })
+        .into()
+}
+
+/// The wizard refreshing coins by sending them back to the wallet. `txs_vbytes` is the total
+/// size of the `n_txs` transactions the selected coins are split into.
+#[allow(clippy::too_many_arguments)]
+pub fn refresh_coins<'a>(
+    cache: &'a Cache,
+    is_valid: bool,
+    timelock: u16,
+    coins: &[(Coin, bool)],
+    coins_labels: &'a HashMap<String, String>,
+    show_coin_control: bool,
+    mixed_tags_confirmed: bool,
+    input_size: usize,
+    txs_vbytes: usize,
+    n_txs: usize,
+    feerate: &form::Value<String>,
+    feerate_preset: Option<FeeratePreset>,
+    max_fee_percent: u32,
+    error: Option<&Error>,
+) -> Element<'a, Message> {
+    let refreshed_value = coins
+        .iter()
+        .filter(|(_, selected)| *selected)
+        .fold(Amount::from_sat(0), |total, (coin, _)| total + coin.amount);
+    // The share of the refreshed value paid in fees, if above the limit.
+    let high_fee_percent = feerate
+        .value
+        .parse::<u64>()
+        .ok()
+        .filter(|_| feerate.valid && refreshed_value.to_sat() > 0)
+        .map(|rate| (rate * txs_vbytes as u64) as f64 * 100.0 / refreshed_value.to_sat() as f64)
+        .filter(|percent| *percent > max_fee_percent as f64);
+    dashboard(
+        &Menu::CreateSpendTx,
+        cache,
+        error,
+        Column::new()
+            .push(h3(tr("send.refresh_title")))
+            .push(p1_regular(tr("send.refresh_description")).style(color::GREY_3))
+            .push(feerate_selector(cache, feerate, feerate_preset, txs_vbytes))
+            .push_maybe(high_fee_percent.map(|percent| {
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(icon::warning_icon().style(color::ORANGE))
+                    .push(
+                        p2_regular(tr_args(
+                            "send.refresh_high_fee",
+                            &[
+                                ("percent", &format!("{:.1}", percent)),
+                                ("max", &max_fee_percent.to_string()),
+                            ],
+                        ))
+                        .style(color::ORANGE),
+                    )
+            }))
+            .push_maybe(if n_txs > 1 {
+                Some(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(icon::warning_icon().style(color::ORANGE))
+                        .push(
+                            p2_regular(tr_args(
+                                "send.refresh_several_txs",
+                                &[("count", &n_txs.to_string())],
+                            ))
+                            .style(color::ORANGE),
+                        ),
+                )
+            } else {
+                None
+            })
+            .push(coin_control(
+                cache,
+                true,
+                is_valid,
+                timelock,
+                coins,
+                coins_labels,
+                None,
+                feerate,
+                show_coin_control,
+                mixed_tags_confirmed,
+                input_size,
+            ))
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Alignment::Center)
+                    .push(Space::with_width(Length::Fill))
+                    .push(
+                        button::secondary(None, tr("common.next"))
+                            .on_press_maybe(if is_valid {
+                                Some(Message::CreateSpend(CreateSpendMessage::Generate))
+                            } else {
+                                None
+                            })
+                            .width(Length::Fixed(100.0)),
+                    ),
+            )
+            .push(Space::with_height(Length::Fixed(20.0)))
+            .spacing(20),
+    )
+}
+
+/// The panel listing the coins that can be spent, to select manually the ones funding the
+/// transaction. As long as the user did not select any coin, they are selected automatically.
+#[allow(clippy::too_many_arguments)]
+fn coin_control<'a>(
+    cache: &'a Cache,
+    is_self_send: bool,
+    is_valid: bool,
+    timelock: u16,
+    coins: &[(Coin, bool)],
+    coins_labels: &'a HashMap<String, String>,
+    amount_left: Option<&Amount>,
+    feerate: &form::Value<String>,
+    expanded: bool,
+    mixed_tags_confirmed: bool,
+    input_size: usize,
+) -> Element<'a, Message> {
+    // The fee to pay to add a coin to the transaction at the current feerate.
+    let spend_cost = feerate
+        .value
+        .parse::<u64>()
+        .ok()
+        .filter(|_| feerate.valid)
+        .map(|rate| Amount::from_sat(rate * input_size as u64));
+    let (selected_count, selected_amount) = coins
+        .iter()
+        .filter(|(_, selected)| *selected)
+        .fold((0, Amount::from_sat(0)), |(count, total), (coin, _)| {
+            (count + 1, total + coin.amount)
+        });
+    let mixed_tags: Vec<&str> =
+        mixed_tags(
+            coins
+                .iter()
+                .filter_map(|(coin, selected)| if *selected { Some(coin) } else { None }),
+        )
+        .into_iter()
+        .map(|tag| tag.unwrap_or_else(|| tr("send.untagged")))
+        .collect();
+    Container::new(
+        Column::new()
+            .spacing(10)
+            .push(
+                Row::new()
+                    .align_items(Alignment::Center)
+                    .push(
+                        Button::new(
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(p1_bold(tr("send.coin_control")))
+                                .push(if expanded {
+                                    icon::collapsed_icon()
+                                } else {
+                                    icon::collapse_icon()
+                                }),
+                        )
+                        .style(theme::Button::Transparent)
+                        .on_press(Message::CreateSpend(CreateSpendMessage::ToggleCoinControl)),
+                    )
+                    .push(Space::with_width(Length::Fill))
+                    .push(if is_self_send {
+                        Row::new()
+                    } else if let Some(amount_left) = amount_left {
+                        if amount_left.to_sat() == 0 && !is_valid {
+                            // If amount left is set, the current configuration must be redraftable.
+                            // If it's not valid, either no coins are selected or there's a recipient
+                            // with max selected and invalid amount.
+                            if selected_count == 0 {
+                                // This can happen if we have a single recipient
+                                // and it has the max selected.
+                                Row::new()
+                                    .push(text(tr("send.select_one_coin")).style(color::GREY_3))
+                            } else {
+                                // There must be a recipient with max selected and value 0.
+                                Row::new()
+                                    .push(text(tr("send.check_max_amount")).style(color::GREY_3))
+                            }
+                        } else {
+                            Row::new()
+                                .spacing(5)
+                                .push(amount_with_size(amount_left, P2_SIZE, cache.bitcoin_unit))
+                                .push(p2_regular(tr("send.left_to_select")).style(color::GREY_3))
+                        }
+                    } else {
+                        Row::new().push(
+                            text(if feerate.value.is_empty() || !feerate.valid {
+                                tr("send.feerate_required")
+                            } else {
+                                tr("send.add_recipient_details")
+                            })
+                            .style(color::GREY_3),
+                        )
+                    }),
+            )
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .push(amount_with_size(
+                        &selected_amount,
+                        P2_SIZE,
+                        cache.bitcoin_unit,
+                    ))
+                    .push(
+                        p2_regular(format!(
+                            "· {}",
+                            tr_plural("send.coins_selected", selected_count)
+                        ))
+                        .style(color::GREY_3),
+                    )
+                    .push(Space::with_width(Length::Fill))
+                    .push_maybe(if expanded {
+                        Some(
+                            button::secondary(None, tr("send.select_all_coins")).on_press_maybe(
+                                if selected_count < coins.len() as u64 {
+                                    Some(Message::CreateSpend(CreateSpendMessage::SelectAllCoins))
+                                } else {
+                                    None
+                                },
+                            ),
+                        )
+                    } else {
+                        None
+                    }),
+            )
+            .push_maybe(if mixed_tags.is_empty() {
+                None
+            } else {
+                Some(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(icon::warning_icon().style(color::ORANGE))
+                        .push(
+                            p2_regular(tr_args(
+                                "send.mixed_tags",
+                                &[("tags", &mixed_tags.join(", "))],
+                            ))
+                            .style(color::ORANGE)
+                            .width(Length::Fill),
+                        )
+                        .push(
+                            checkbox(tr("send.confirm_mixed_tags"), mixed_tags_confirmed)
+                                .on_toggle(|confirmed| {
+                                    Message::CreateSpend(CreateSpendMessage::ConfirmMixedTags(
+                                        confirmed,
+                                    ))
+                                }),
+                        ),
+                )
+            })
+            .push_maybe(if expanded {
+                Some(
+                    Container::new(scrollable(coins.iter().enumerate().fold(
+                        Column::new().spacing(10),
+                        |col, (i, (coin, selected))| {
+                            col.push(coin_list_view(
+                                i,
+                                coin,
+                                coins_labels,
+                                timelock,
+                                cache.blockheight as u32,
+                                *selected,
+                                spend_cost.map(|cost| coin.amount <= cost).unwrap_or(false),
+                                cache.bitcoin_unit,
+                            ))
+                        },
+                    )))
+                    .max_height(300),
+                )
+            } else {
+                None
+            }),
+    )
+    .padding(20)
+    .style(theme::Card::Simple)
+    .into()
+}
+
+/// The feerate presets and input. `min_tx_vbytes` is the size of the smallest transaction
+/// with the current coins and recipients, to display the fee it would pay at least.
+fn feerate_selector<'a>(
+    cache: &'a Cache,
+    feerate: &form::Value<String>,
+    preset: Option<FeeratePreset>,
+    min_tx_vbytes: usize,
+) -> Element<'a, Message> {
+    let estimates = &cache.fee_estimates;
+    let presets = FeeratePreset::ALL
+        .iter()
+        .fold(Row::new().spacing(10), |row, p| {
+            let content = Column::new()
+                .align_items(Alignment::Center)
+                .push(p1_bold(p.to_string()))
+                .push_maybe(
+                    p.feerate(estimates)
+                        .map(|rate| p2_regular(format!("{} sats/vbyte", rate))),
+                );
+            row.push(
+                Button::new(content)
+                    .padding(10)
+                    .width(Length::Fixed(150.0))
+                    .style(if preset == Some(*p) {
+                        theme::Button::Primary
+                    } else {
+                        theme::Button::Secondary
+                    })
+                    .on_press(Message::CreateSpend(
+                        CreateSpendMessage::FeeratePresetSelected(*p),
+                    )),
+            )
+        });
+    let projection = feerate
+        .value
+        .parse::<u64>()
+        .ok()
+        .filter(|_| feerate.valid && !estimates.is_empty())
+        .map(|rate| {
+            p2_regular(match confirmation_target(rate, estimates) {
+                Some(1) => "Expected to confirm in the next block".to_string(),
+                Some(target) => format!(
+                    "Expected to confirm within ~{} blocks ({})",
+                    target,
+                    blocks_duration(target)
+                ),
+                None => "Below the typical minimum, may never confirm".to_string(),
+            })
+            .style(color::GREY_3)
+        });
+    Column::new()
+        .spacing(10)
+        .push(p1_bold(tr("send.feerate")))
+        .push(presets)
+        .push_maybe(
+            feerate
+                .value
+                .parse::<u64>()
+                .ok()
+                .filter(|_| feerate.valid)
+                .map(|rate| {
+                    Row::new()
+                        .spacing(5)
+                        .align_items(Alignment::Center)
+                        .push(p2_regular(tr("send.estimated_fee")).style(color::GREY_3))
+                        .push(amount_with_size(
+                            &Amount::from_sat(rate * min_tx_vbytes as u64),
+                            P2_SIZE,
+                            cache.bitcoin_unit,
+                        ))
+                }),
+        )
+        .push(
+            Row::new()
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .push(
+                    Container::new(
+                        form::Form::new_trimmed("Custom (in sats/vbyte)", feerate, move |msg| {
+                            Message::CreateSpend(CreateSpendMessage::FeerateEdited(msg))
+                        })
+                        .warning(
+                            "Feerate must be an integer less than \
+                            or equal to 1000 sats/vbyte",
+                        )
+                        .size(P1_SIZE)
+                        .padding(10),
+                    )
+                    .width(Length::Fixed(300.0)),
+                )
+                .push_maybe(projection),
+        )
+        .push_maybe(if preset.is_some() && estimates.is_empty() {
+            Some(
+                p2_regular(format!(
+                    "No fee estimates are available yet, the minimum feerate of {} sats/vbyte \
+                    is used. It may take a long time to confirm.",
+                    FALLBACK_FEERATE
+                ))
+                .style(color::ORANGE),
+            )
+        } else {
+            None
+        })
+        .into()
+}
+
+/// A rough duration for a number of blocks, mined every 10 minutes on average.
+fn blocks_duration(blocks: u16) -> String {
+    let minutes = blocks as u32 * 10;
+    if minutes < 60 {
+        format!("~{} min", minutes)
+    } else if minutes < 24 * 60 {
+        let hours = minutes / 60;
+        format!("~{} hour{}", hours, if hours > 1 { "s" } else { "" })
+    } else {
+        let days = minutes / (24 * 60);
+        format!("~{} day{}", days, if days > 1 { "s" } else { "" })
+    }
+}
+
 pub fn recipient_view<'a>(
     index: usize,
     address: &'a form::Value<String>,
     amount: &'a form::Value<String>,
     label: &'a form::Value<String>,
     is_max_selected: bool,
+    bitcoin_unit: BitcoinDisplayUnit,
+    exchange_rate: Option<ExchangeRate>,
 ) -> Element<'a, CreateSpendMessage> {
     Container::new(
         Column::new()
@@ -379,9 +873,9 @@ pub fn recipient_view<'a>(
                             .width(Length::Fixed(110.0)),
                     )
                     .push_maybe(if is_max_selected {
-                        let amount_txt = Amount::from_str_in(&amount.value, Denomination::Bitcoin)
+                        let amount_txt = parse_amount(&amount.value, bitcoin_unit)
                             .ok()
-                            .map(amount_as_string)
+                            .map(|a| amount_as_string(a, bitcoin_unit))
                             .unwrap_or(amount.value.clone());
                         Some(
                             Container::new(text(amount_txt).size(P1_SIZE).style(color::GREY_2))
@@ -392,14 +886,34 @@ pub fn recipient_view<'a>(
                         None
                     })
                     .push_maybe(if !is_max_selected {
-                        Some(form::Form::new_amount_btc("0.001 (in BTC)", amount, move |msg| {
-                            CreateSpendMessage::RecipientEdited(index, "amount", msg)
-                        })
-                        .warning(
-                            "Invalid amount. (Note amounts lower than 0.00005 BTC are invalid.)",
+                        let (placeholder, warning) = match bitcoin_unit {
+                            BitcoinDisplayUnit::Btc => (
+                                "0.001 (in BTC)",
+                                "Invalid amount. (Note amounts lower than 0.00005 BTC are invalid.)",
+                            ),
+                            BitcoinDisplayUnit::Sats => (
+                                "100 000 (in sats)",
+                                "Invalid amount. (Note amounts lower than 5 000 sats are invalid.)",
+                            ),
+                        };
+                        Some(
+                            AmountInput::new(
+                                placeholder,
+                                amount,
+                                bitcoin_unit,
+                                exchange_rate,
+                                move |edited| {
+                                    CreateSpendMessage::RecipientEdited(
+                                        index,
+                                        "amount",
+                                        edited.value,
+                                    )
+                                },
+                            )
+                            .warning(warning)
+                            .size(P1_SIZE)
+                            .padding(10),
                         )
-                        .size(P1_SIZE)
-                        .padding(10))
                     } else {
                         None
                     })
@@ -425,7 +939,10 @@ fn coin_list_view<'a>(
     timelock: u16,
     blockheight: u32,
     selected: bool,
+    uneconomical: bool,
+    bitcoin_unit: BitcoinDisplayUnit,
 ) -> Element<'a, Message> {
+    let address = coin.address.to_string();
     Row::new()
         .push(
             Row::new()
@@ -454,6 +971,30 @@ fn coin_list_view<'a>(
                         Container::new(p1_regular("")).width(Length::Fill)
                     },
                 )
+                .push(
+                    Column::new()
+                        .push(
+                            p2_regular(format!(
+                                "{}...{}",
+                                &address[..8],
+                                &address[address.len() - 8..]
+                            ))
+                            .style(color::GREY_3),
+                        )
+                        .push_maybe(coin.block_height.map(|height| {
+                            p2_regular(format!(
+                                "Block #{} ({} confirmations)",
+                                height,
+                                (blockheight as i32 - height + 1).max(1)
+                            ))
+                            .style(color::GREY_3)
+                        })),
+                )
+                .push_maybe(coin.tag.as_ref().map(|tag| {
+                    Container::new(p2_regular(tag.clone()))
+                        .padding(10)
+                        .style(theme::Container::Pill(theme::Pill::Simple))
+                }))
                 .push(if coin.spend_info.is_some() {
                     badge::spent()
                 } else if coin.block_height.is_none() {
@@ -466,7 +1007,16 @@ fn coin_list_view<'a>(
                 .align_items(Alignment::Center)
                 .width(Length::Fill),
         )
-        .push(amount(&coin.amount))
+        .push_maybe(if uneconomical {
+            Some(tooltip::Tooltip::new(
+                icon::warning_icon().style(color::ORANGE),
+                text(tr("send.uneconomical_coin")),
+                tooltip::Position::Bottom,
+            ))
+        } else {
+            None
+        })
+        .push(amount(&coin.amount, bitcoin_unit))
         // give some space for the scroll bar without using padding
         .push(Space::with_width(Length::Fixed(0.0)))
         .align_items(Alignment::Center)
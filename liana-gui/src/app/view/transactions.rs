@@ -3,13 +3,16 @@ use std::collections::{HashMap, HashSet};
 use chrono::{DateTime, Local, Utc};
 use iced::{
     alignment,
-    widget::{tooltip, Space},
+    widget::{slider, tooltip, Space},
     Alignment, Length,
 };
 
+use liana::{descriptors::LianaPolicy, miniscript::bitcoin::consensus};
 use liana_ui::{
     color,
-    component::{amount::*, badge, button, card, form, text::*},
+    component::{
+        amount::*, badge, button, card, collapse::Collapse, copy_button::copy_button, form, text::*,
+    },
     icon, theme,
     widget::*,
 };
@@ -21,21 +24,26 @@ use crate::{
         menu::Menu,
         view::{
             dashboard, label,
-            message::{CreateRbfMessage, Message},
+            message::{CreateCpfpMessage, CreateRbfMessage, Message, TransactionsMessage},
             warning::warn,
         },
     },
-    daemon::model::{HistoryTransaction, Txid},
+    daemon::model::{
+        Address, Amount, CpfpPackage, FeeratePreset, HistoryTransaction, MempoolPosition,
+        PendingSpendFeeInfo, RbfPreview, Txid,
+    },
     export::ExportMessage,
 };
 
 pub fn transactions_view<'a>(
     cache: &'a Cache,
-    txs: &'a [HistoryTransaction],
+    txs: Vec<(usize, &'a HistoryTransaction)>,
+    filter: &'a str,
     warning: Option<&'a Error>,
     is_last_page: bool,
     processing: bool,
 ) -> Element<'a, Message> {
+    let no_txs = txs.is_empty();
     dashboard(
         &Menu::Transactions,
         cache,
@@ -47,17 +55,33 @@ pub fn transactions_view<'a>(
                     .push(Space::with_width(Length::Fill))
                     .push(button::secondary(None, "Export").on_press(ExportMessage::Open.into())),
             )
+            .push(
+                TextInput::new("Filter by label or txid", filter)
+                    .on_input(|filter| {
+                        Message::Transactions(TransactionsMessage::FilterEdited(filter))
+                    })
+                    .size(P1_SIZE)
+                    .padding(10),
+            )
             .push(
                 Column::new()
                     .spacing(10)
+                    .push_maybe(if no_txs && !filter.is_empty() {
+                        Some(
+                            p1_regular("No loaded transaction matches the filter")
+                                .style(color::GREY_3),
+                        )
+                    } else {
+                        None
+                    })
                     .push(
-                        txs.iter()
-                            .enumerate()
+                        txs.into_iter()
                             .fold(Column::new().spacing(10), |col, (i, tx)| {
-                                col.push(tx_list_view(i, tx))
+                                col.push(tx_list_view(i, tx, cache))
                             }),
                     )
-                    .push_maybe(if !is_last_page && !txs.is_empty() {
+                    // Further pages may have transactions matching the filter.
+                    .push_maybe(if !is_last_page && (!no_txs || !filter.is_empty()) {
                         Some(
                             Container::new(
                                 Button::new(
@@ -90,7 +114,7 @@ pub fn transactions_view<'a>(
     )
 }
 
-fn tx_list_view(i: usize, tx: &HistoryTransaction) -> Element<'_, Message> {
+fn tx_list_view<'a>(i: usize, tx: &'a HistoryTransaction, cache: &Cache) -> Element<'a, Message> {
     Container::new(
         Button::new(
             Row::new()
@@ -128,11 +152,17 @@ fn tx_list_view(i: usize, tx: &HistoryTransaction) -> Element<'_, Message> {
                         .align_items(Alignment::Center)
                         .width(Length::Fill),
                 )
-                .push_maybe(if tx.time.is_none() {
+                .push_maybe(if tx.replaced_by.is_some() {
+                    Some(badge::replaced())
+                } else if tx.time.is_none() {
                     Some(badge::unconfirmed())
                 } else {
                     None
                 })
+                .push_maybe(
+                    tx.mempool_position(&cache.fee_estimates)
+                        .map(mempool_position_badge),
+                )
                 .push_maybe(if tx.is_batch() {
                     Some(badge::batch())
                 } else {
@@ -142,13 +172,13 @@ fn tx_list_view(i: usize, tx: &HistoryTransaction) -> Element<'_, Message> {
                     Row::new()
                         .spacing(5)
                         .push(text("+"))
-                        .push(amount(&tx.incoming_amount))
+                        .push(amount(&tx.incoming_amount, cache.bitcoin_unit))
                         .align_items(Alignment::Center)
                 } else if tx.outgoing_amount != Amount::from_sat(0) {
                     Row::new()
                         .spacing(5)
                         .push(text("-"))
-                        .push(amount(&tx.outgoing_amount))
+                        .push(amount(&tx.outgoing_amount, cache.bitcoin_unit))
                         .align_items(Alignment::Center)
                 } else {
                     Row::new().push(text("Self-transfer"))
@@ -164,19 +194,50 @@ fn tx_list_view(i: usize, tx: &HistoryTransaction) -> Element<'_, Message> {
     .into()
 }
 
+fn mempool_position_badge<'a, T: 'a>(position: MempoolPosition) -> Container<'a, T> {
+    match position {
+        MempoolPosition::NextBlock => badge::badge_pill(
+            "  Next block likely  ",
+            "The feerate of this transaction is high enough to be included in the next block",
+        ),
+        MempoolPosition::WithinFewBlocks => badge::badge_pill(
+            "  Within ~3 blocks  ",
+            "The feerate of this transaction is high enough to be included within about 3 blocks",
+        ),
+        MempoolPosition::MayTakeHours => badge::badge_pill(
+            "  May take hours  ",
+            "The feerate of this transaction is low, it may take hours to confirm",
+        ),
+        MempoolPosition::BelowMinimum => badge::badge_pill(
+            "  Below typical minimum  ",
+            "The feerate of this transaction is below the typical minimum, it may never confirm",
+        ),
+    }
+}
+
 /// Return the modal view for a new RBF transaction.
 ///
 /// `descendant_txids` contains the IDs of any transactions from this wallet that are
-/// direct descendants of the transaction to be replaced.
+/// direct descendants of the transaction to be replaced. `feerate_range` is the range of
+/// feerates selectable with the slider.
+#[allow(clippy::too_many_arguments)]
 pub fn create_rbf_modal<'a>(
     is_cancel: bool,
     descendant_txids: &HashSet<Txid>,
+    prev_feerate_vb: u64,
     feerate: &form::Value<String>,
-    replacement_txid: Option<Txid>,
+    feerate_range: (u64, u64),
+    feerate_vb: Option<u64>,
+    prev_fee: Option<Amount>,
+    preview: Option<&RbfPreview>,
+    processing: bool,
     warning: Option<&'a Error>,
+    cache: &'a Cache,
 ) -> Element<'a, Message> {
+    let bitcoin_unit = cache.bitcoin_unit;
     let mut confirm_button = button::secondary(None, "Confirm").width(Length::Fixed(200.0));
-    if feerate.valid || is_cancel {
+    // Only a previewed replacement can be confirmed.
+    if (feerate.valid || is_cancel) && preview.is_some() && !processing {
         confirm_button =
             confirm_button.on_press(Message::CreateRbf(super::CreateRbfMessage::Confirm));
     }
@@ -235,69 +296,314 @@ pub fn create_rbf_modal<'a>(
                                     .spacing(5)
                                     .align_items(Alignment::Center)
                                     .push(text(txid.to_string()))
-                                    .push(
-                                        Button::new(icon::clipboard_icon().style(color::GREY_3))
-                                            .on_press(Message::Clipboard(txid.to_string()))
-                                            .style(theme::Button::TransparentBorder),
-                                    ),
+                                    .push(copy_button(txid.to_string())),
                             )
                         },
                     ),
                 )
             })
+            .push(
+                Row::new()
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .push(text("Current feerate:").style(color::GREY_3))
+                    .push(text(format!("{} sats/vbyte", prev_feerate_vb))),
+            )
             .push_maybe(if !is_cancel {
                 Some(
-                    Row::new()
-                        .push(Container::new(p1_bold("Feerate")).padding(10))
+                    Column::new()
                         .spacing(10)
                         .push(
-                            form::Form::new_trimmed("", feerate, move |msg| {
-                                Message::CreateRbf(CreateRbfMessage::FeerateEdited(msg))
-                            })
-                            .warning(
-                                "Feerate must be greater than previous value and \
-                                less than or equal to 1000 sats/vbyte",
-                            )
-                            .size(P1_SIZE)
-                            .padding(10),
+                            Row::new()
+                                .push(Container::new(p1_bold("Feerate")).padding(10))
+                                .spacing(10)
+                                .push(
+                                    form::Form::new_trimmed("", feerate, move |msg| {
+                                        Message::CreateRbf(CreateRbfMessage::FeerateEdited(msg))
+                                    })
+                                    .warning(
+                                        "Feerate must be greater than previous value and \
+                                        less than or equal to 1000 sats/vbyte",
+                                    )
+                                    .size(P1_SIZE)
+                                    .padding(10),
+                                )
+                                .width(Length::Fill),
                         )
-                        .width(Length::Fill),
+                        .push(slider(
+                            feerate_range.0 as u32..=feerate_range.1 as u32,
+                            feerate_vb.unwrap_or(feerate_range.0) as u32,
+                            |v| Message::CreateRbf(CreateRbfMessage::FeerateEdited(v.to_string())),
+                        ))
+                        .push_maybe(
+                            feerate_vb
+                                .and_then(|f| MempoolPosition::new(f, &cache.fee_estimates))
+                                .map(|position| {
+                                    Row::new()
+                                        .align_items(Alignment::Center)
+                                        .spacing(10)
+                                        .push(text("Estimated confirmation:").style(color::GREY_3))
+                                        .push(mempool_position_badge(position))
+                                }),
+                        ),
                 )
             } else {
                 None
             })
+            .push_maybe(preview.map(|preview| rbf_preview_view(preview, prev_fee, bitcoin_unit)))
             .push(warn(warning))
-            .push(Row::new().push(if replacement_txid.is_none() {
-                Row::new().push(confirm_button)
-            } else {
+            .push(Row::new().push(confirm_button)),
+    )
+    .width(Length::Fixed(800.0))
+    .into()
+}
+
+/// The fee, inputs and change of the replacement, compared to the transaction it replaces.
+fn rbf_preview_view<'a>(
+    preview: &RbfPreview,
+    prev_fee: Option<Amount>,
+    bitcoin_unit: BitcoinDisplayUnit,
+) -> Column<'a, Message> {
+    Column::new()
+        .spacing(5)
+        .push(
+            Row::new()
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .push(text("New feerate:").style(color::GREY_3))
+                .push(text(format!("{} sats/vbyte", preview.feerate_vb))),
+        )
+        .push(
+            Row::new()
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .push(text("New fee:").style(color::GREY_3))
+                .push(amount(&preview.fee, bitcoin_unit))
+                .push_maybe(
+                    prev_fee
+                        .and_then(|prev_fee| preview.fee.checked_sub(prev_fee))
+                        .map(|bump| {
+                            Row::new()
+                                .spacing(5)
+                                .align_items(Alignment::Center)
+                                .push(text("(+").style(color::GREY_3))
+                                .push(amount_with_size(&bump, P2_SIZE, bitcoin_unit))
+                                .push(text(")").style(color::GREY_3))
+                        }),
+                ),
+        )
+        .push(if let Some(change) = preview.change {
+            Row::new()
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .push(text("New change:").style(color::GREY_3))
+                .push(amount(&change, bitcoin_unit))
+        } else {
+            Row::new().push(text("The replacement has no change output.").style(color::GREY_3))
+        })
+        .push_maybe(if preview.added_inputs.is_empty() {
+            None
+        } else {
+            Some(
+                preview.added_inputs.iter().fold(
+                    Column::new().spacing(5).push(
+                        text("Additional coins spent to pay for the fee increase:")
+                            .style(color::GREY_3),
+                    ),
+                    |col, (outpoint, value)| {
+                        col.push(
+                            Row::new()
+                                .padding([0, 30])
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(p2_regular(outpoint.to_string()).style(color::GREY_3))
+                                .push(amount_with_size(value, P2_SIZE, bitcoin_unit)),
+                        )
+                    },
+                ),
+            )
+        })
+}
+
+/// Return the modal view to speed up an incoming transaction with a child paying for it.
+///
+/// Once the child is created, `package` is the breakdown of the fees of the child and its
+/// unconfirmed ancestors.
+#[allow(clippy::too_many_arguments)]
+pub fn create_cpfp_modal<'a>(
+    tx: &HistoryTransaction,
+    feerate: &form::Value<String>,
+    feerate_range: (u64, u64),
+    feerate_vb: Option<u64>,
+    package: Option<&CpfpPackage>,
+    processing: bool,
+    warning: Option<&'a Error>,
+    cache: &'a Cache,
+) -> Element<'a, Message> {
+    let bitcoin_unit = cache.bitcoin_unit;
+    let mut col = Column::new()
+        .spacing(10)
+        .push(Container::new(h4_bold("Speed up the transaction")).width(Length::Fill))
+        .push(text(
+            "This transaction was not created by this wallet so its fee cannot be increased, \
+            but a new transaction spending the coins it pays you can pay a higher fee for both \
+            (child pays for parent). Miners will be incentivized to include them together \
+            once their combined feerate is high enough.",
+        ))
+        .push(
+            Row::new()
+                .spacing(5)
+                .push(text("Size of the transaction:").style(color::GREY_3))
+                .push(text(format!("{} vbytes", tx.tx.vsize()))),
+        )
+        .push_maybe(
+            FeeratePreset::Economy
+                .feerate(&cache.fee_estimates)
+                .map(|minimum| {
+                    Row::new()
+                        .spacing(5)
+                        .push(text("Current mempool minimum (estimate):").style(color::GREY_3))
+                        .push(text(format!("{} sats/vbyte", minimum)))
+                }),
+        );
+
+    if let Some(package) = package {
+        let feerate_row = |label: &'static str, fee: Amount, vsize: u64, feerate: Option<u64>| {
+            Row::new()
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .push(text(label).style(color::GREY_3).width(Length::Fixed(200.0)))
+                .push(amount(&fee, bitcoin_unit))
+                .push(text(format!(
+                    "for {} vbytes ({})",
+                    vsize,
+                    feerate
+                        .map(|f| format!("{} sats/vbyte", f))
+                        .unwrap_or_else(|| "unknown feerate".to_string())
+                )))
+        };
+        col = col
+            .push(p1_bold("Package fee"))
+            .push(feerate_row(
+                "Unconfirmed transactions:",
+                package.ancestors_fee,
+                package.ancestors_vsize,
+                package.ancestors_feerate(),
+            ))
+            .push(feerate_row(
+                "New transaction:",
+                package.child_fee,
+                package.child_vsize,
+                Some(package.child_feerate()),
+            ))
+            .push(feerate_row(
+                "Combined:",
+                package.ancestors_fee + package.child_fee,
+                package.ancestors_vsize + package.child_vsize,
+                Some(package.package_feerate()),
+            ))
+            .push(
+                text(
+                    "The new transaction pays a higher feerate than the combined one to make up \
+                    for the low fee of the transaction it speeds up.",
+                )
+                .style(color::GREY_3),
+            )
+            .push(warn(warning))
+            .push(
+                button::primary(None, "Sign")
+                    .width(Length::Fixed(200.0))
+                    .on_press(Message::Menu(Menu::PsbtPreSelected(package.child_txid))),
+            );
+    } else {
+        let mut confirm_button = button::secondary(None, "Confirm").width(Length::Fixed(200.0));
+        if feerate.valid && !processing {
+            confirm_button =
+                confirm_button.on_press(Message::CreateCpfp(CreateCpfpMessage::Confirm));
+        }
+        col = col
+            .push(
                 Row::new()
+                    .push(Container::new(p1_bold("Package feerate")).padding(10))
                     .spacing(10)
-                    .align_items(Alignment::Center)
-                    .push(icon::circle_check_icon().style(color::GREEN))
                     .push(
-                        text("Replacement PSBT created successfully and ready to be signed")
-                            .style(color::GREEN),
+                        form::Form::new_trimmed("", feerate, move |msg| {
+                            Message::CreateCpfp(CreateCpfpMessage::FeerateEdited(msg))
+                        })
+                        .warning("Feerate must be between 1 and 1000 sats/vbyte")
+                        .size(P1_SIZE)
+                        .padding(10),
                     )
-            }))
-            .push_maybe(replacement_txid.map(|id| {
-                Row::new().push(
-                    button::secondary(None, "Go to replacement")
-                        .width(Length::Fixed(200.0))
-                        .on_press(Message::Menu(Menu::PsbtPreSelected(id))),
-                )
-            })),
-    )
-    .width(Length::Fixed(800.0))
-    .into()
+                    .width(Length::Fill),
+            )
+            .push(slider(
+                feerate_range.0 as u32..=feerate_range.1 as u32,
+                feerate_vb.unwrap_or(feerate_range.0) as u32,
+                |v| Message::CreateCpfp(CreateCpfpMessage::FeerateEdited(v.to_string())),
+            ))
+            .push_maybe(
+                feerate_vb
+                    .and_then(|f| MempoolPosition::new(f, &cache.fee_estimates))
+                    .map(|position| {
+                        Row::new()
+                            .align_items(Alignment::Center)
+                            .spacing(10)
+                            .push(text("Estimated confirmation:").style(color::GREY_3))
+                            .push(mempool_position_badge(position))
+                    }),
+            )
+            .push(warn(warning))
+            .push(confirm_button);
+    }
+
+    card::simple(col).width(Length::Fixed(800.0)).into()
 }
 
 pub fn tx_view<'a>(
     cache: &'a Cache,
     tx: &'a HistoryTransaction,
+    policy: &LianaPolicy,
+    reused_addresses: &'a [Address],
+    fee_info: Option<&PendingSpendFeeInfo>,
     labels_editing: &'a HashMap<String, form::Value<String>>,
     warning: Option<&'a Error>,
 ) -> Element<'a, Message> {
     let txid = tx.tx.txid().to_string();
+    // Miners consider the transaction along with its unconfirmed ancestors, as reported by the
+    // mempool of the Bitcoin backend.
+    let mempool_position = match (tx.mempool_position(&cache.fee_estimates), fee_info) {
+        (Some(_), Some(info)) => {
+            MempoolPosition::new(info.effective_feerate_vb, &cache.fee_estimates)
+        }
+        (position, _) => position,
+    };
+    let fee_bump_suggested = fee_info.is_some()
+        && rbf_unavailable_reason(tx).is_none()
+        && matches!(
+            mempool_position,
+            Some(MempoolPosition::MayTakeHours) | Some(MempoolPosition::BelowMinimum)
+        );
+    // Only our own coins are spent using one of the paths of our policy.
+    let spending_path = if tx.is_external() {
+        None
+    } else if let Some(timelock) = tx.recovery_path_timelock(policy) {
+        let (threshold, keys) = policy.recovery_paths()[&timelock].thresh_origins();
+        Some(format!(
+            "Recovery path after {} blocks ({} of {} key{})",
+            timelock,
+            threshold,
+            keys.len(),
+            if keys.len() > 1 { "s" } else { "" }
+        ))
+    } else {
+        let (threshold, keys) = policy.primary_path().thresh_origins();
+        Some(format!(
+            "Primary path ({} of {} key{})",
+            threshold,
+            keys.len(),
+            if keys.len() > 1 { "s" } else { "" }
+        ))
+    };
     dashboard(
         &Menu::Transactions,
         cache,
@@ -310,6 +616,23 @@ pub fn tx_view<'a>(
             } else {
                 Container::new(h3("Outgoing transaction")).width(Length::Fill)
             })
+            .push_maybe(if reused_addresses.is_empty() {
+                None
+            } else {
+                Some(
+                    card::warning(format!(
+                        "This transaction pays to an address which already received funds \
+                         before: {}. Address reuse harms your privacy, always share a new \
+                         address to receive a payment.",
+                        reused_addresses
+                            .iter()
+                            .map(|a| a.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                    .width(Length::Fill),
+                )
+            })
             .push(if let Some(outpoint) = tx.is_single_payment() {
                 // if the payment is a payment of a single payment transaction then
                 // the label of the transaction is attached to the label of the payment outpoint
@@ -334,15 +657,23 @@ pub fn tx_view<'a>(
                         .push(if tx.is_send_to_self() {
                             Container::new(h1("Self-transfer"))
                         } else if tx.is_external() {
-                            Container::new(amount_with_size(&tx.incoming_amount, H1_SIZE))
+                            Container::new(amount_with_size(
+                                &tx.incoming_amount,
+                                H1_SIZE,
+                                cache.bitcoin_unit,
+                            ))
                         } else {
-                            Container::new(amount_with_size(&tx.outgoing_amount, H1_SIZE))
+                            Container::new(amount_with_size(
+                                &tx.outgoing_amount,
+                                H1_SIZE,
+                                cache.bitcoin_unit,
+                            ))
                         })
                         .push_maybe(tx.fee_amount.map(|fee_amount| {
                             Row::new()
                                 .align_items(Alignment::Center)
                                 .push(h3("Miner fee: ").style(color::GREY_3))
-                                .push(amount_with_size(&fee_amount, H3_SIZE))
+                                .push(amount_with_size(&fee_amount, H3_SIZE, cache.bitcoin_unit))
                                 .push(text(" ").size(H3_SIZE))
                                 .push(
                                     text(format!(
@@ -352,16 +683,81 @@ pub fn tx_view<'a>(
                                     .size(H4_SIZE)
                                     .style(color::GREY_3),
                                 )
+                        }))
+                        .push_maybe(fee_info.and_then(|info| {
+                            (Some(info.effective_feerate_vb) < tx.feerate()).then(|| {
+                                text(format!(
+                                    "Effective feerate with its unconfirmed ancestors: {} \
+                                     sats/vbyte",
+                                    info.effective_feerate_vb
+                                ))
+                                .style(color::GREY_3)
+                            })
+                        }))
+                        // Clicking the estimate leads to the options to speed up the transaction.
+                        .push_maybe(mempool_position.map(|position| {
+                            Row::new()
+                                .align_items(Alignment::Center)
+                                .spacing(10)
+                                .push(text("Estimated confirmation:").style(color::GREY_3))
+                                .push(
+                                    Button::new(mempool_position_badge(position))
+                                        .padding(0)
+                                        .style(theme::Button::Transparent)
+                                        .on_press_maybe(
+                                            rbf_unavailable_reason(tx).is_none().then_some(
+                                                Message::CreateRbf(CreateRbfMessage::New(false)),
+                                            ),
+                                        ),
+                                )
+                        }))
+                        .push_maybe(tx.replaced_by.map(|replacement_txid| {
+                            Row::new()
+                                .align_items(Alignment::Center)
+                                .spacing(10)
+                                .push(badge::replaced())
+                                .push(text("Replaced by:").style(color::GREY_3))
+                                .push(
+                                    Button::new(text(replacement_txid.to_string()).small())
+                                        .padding(0)
+                                        .style(theme::Button::Transparent)
+                                        .on_press(Message::Menu(Menu::TransactionPreSelected(
+                                            replacement_txid,
+                                        ))),
+                                )
                         })),
                 ),
             )
-            // If unconfirmed, give option to use RBF.
+            // An incoming transaction stuck in the mempool can be sped up by spending the
+            // outputs it pays us.
+            .push_maybe(
+                if tx.is_external() && tx.time.is_none() && !tx.change_indexes.is_empty() {
+                    Some(
+                        Row::new().push(
+                            button::secondary(None, "Speed up (CPFP)")
+                                .width(Length::Fixed(200.0))
+                                .on_press(Message::CreateCpfp(CreateCpfpMessage::New)),
+                        ),
+                    )
+                } else {
+                    None
+                },
+            )
+            .push_maybe(fee_bump_suggested.then(|| {
+                card::warning(
+                    "The feerate of this transaction is below the current estimates for a quick \
+                     confirmation. Consider increasing its fee."
+                        .to_string(),
+                )
+                .width(Length::Fill)
+            }))
+            // Give option to use RBF, disabled if the transaction cannot be replaced.
             // Check fee amount is some as otherwise we may be missing coins for this transaction.
-            .push_maybe(if tx.time.is_none() && tx.fee_amount.is_some() {
-                Some(
-                    Row::new()
+            .push_maybe(if tx.fee_amount.is_some() {
+                Some(match rbf_unavailable_reason(tx) {
+                    None => Row::new()
                         .push(
-                            button::secondary(None, "Bump fee")
+                            button::secondary(None, "Increase fee")
                                 .width(Length::Fixed(200.0))
                                 .on_press(Message::CreateRbf(super::CreateRbfMessage::New(false))),
                         )
@@ -375,7 +771,18 @@ pub fn tx_view<'a>(
                             )
                         )
                         .spacing(10),
-                )
+                    Some(reason) => Row::new().push(tooltip::Tooltip::new(
+                        Row::new()
+                            .push(button::secondary(None, "Increase fee").width(Length::Fixed(200.0)))
+                            .push(
+                                button::secondary(None, "Cancel transaction")
+                                    .width(Length::Fixed(200.0)),
+                            )
+                            .spacing(10),
+                        reason,
+                        tooltip::Position::Top,
+                    )),
+                })
             } else {
                 None
             })
@@ -391,6 +798,36 @@ pub fn tx_view<'a>(
                             .push(Container::new(text("Date:").bold()).width(Length::Fill))
                             .push(Container::new(text(format!("{}", date))).width(Length::Shrink))
                     }))
+                    .push(
+                        Row::new()
+                            .width(Length::Fill)
+                            .push(Container::new(text("Block height:").bold()).width(Length::Fill))
+                            .push(
+                                Container::new(text(
+                                    tx.height
+                                        .map(|h| h.to_string())
+                                        .unwrap_or_else(|| "Unconfirmed".to_string()),
+                                ))
+                                .width(Length::Shrink),
+                            ),
+                    )
+                    .push_maybe(tx.height.map(|h| {
+                        Row::new()
+                            .width(Length::Fill)
+                            .push(Container::new(text("Confirmations:").bold()).width(Length::Fill))
+                            .push(
+                                Container::new(text(
+                                    (cache.blockheight - h + 1).max(1).to_string(),
+                                ))
+                                .width(Length::Shrink),
+                            )
+                    }))
+                    .push_maybe(spending_path.map(|path| {
+                        Row::new()
+                            .width(Length::Fill)
+                            .push(Container::new(text("Spending path:").bold()).width(Length::Fill))
+                            .push(Container::new(text(path)).width(Length::Shrink))
+                    }))
                     .push(
                         Row::new()
                             .width(Length::Fill)
@@ -400,11 +837,7 @@ pub fn tx_view<'a>(
                                 Row::new()
                                     .align_items(Alignment::Center)
                                     .push(Container::new(text(txid.clone()).small()))
-                                    .push(
-                                        Button::new(icon::clipboard_icon())
-                                            .on_press(Message::Clipboard(txid.clone()))
-                                            .style(theme::Button::TransparentBorder),
-                                    )
+                                    .push(copy_button(txid.clone()))
                                     .width(Length::Shrink),
                             ),
                     )
@@ -413,17 +846,13 @@ pub fn tx_view<'a>(
             .push(
                 Column::new()
                     .spacing(20)
-                    // We do not need to display inputs for external incoming transactions
-                    .push_maybe(if tx.is_external() {
-                        None
-                    } else {
-                        Some(super::psbt::inputs_view(
-                            &tx.coins,
-                            &tx.tx,
-                            &tx.labels,
-                            labels_editing,
-                        ))
-                    })
+                    .push(super::psbt::inputs_view(
+                        &tx.coins,
+                        &tx.tx,
+                        &tx.labels,
+                        labels_editing,
+                        cache.bitcoin_unit,
+                    ))
                     .push(super::psbt::outputs_view(
                         &tx.tx,
                         cache.network,
@@ -435,8 +864,61 @@ pub fn tx_view<'a>(
                         &tx.labels,
                         labels_editing,
                         tx.is_single_payment().is_some(),
-                    )),
+                        cache.bitcoin_unit,
+                    ))
+                    .push(raw_tx_view(consensus::encode::serialize_hex(&tx.tx))),
             )
             .spacing(20),
     )
 }
+
+/// Why the transaction cannot be replaced, if it cannot.
+pub fn rbf_unavailable_reason(tx: &HistoryTransaction) -> Option<&'static str> {
+    if tx.replaced_by.is_some() {
+        Some("The transaction was already replaced, only its replacement can be bumped.")
+    } else if tx.time.is_some() {
+        Some("The transaction is already confirmed, it cannot be replaced anymore.")
+    } else if !tx.tx.is_explicitly_rbf() {
+        Some("The transaction does not signal replaceability, it cannot be replaced.")
+    } else {
+        None
+    }
+}
+
+fn raw_tx_view<'a>(hex: String) -> Element<'a, Message> {
+    Container::new(Collapse::new(
+        || {
+            Button::new(
+                Row::new()
+                    .align_items(Alignment::Center)
+                    .push(h4_bold("Show raw").width(Length::Fill))
+                    .push(icon::collapse_icon()),
+            )
+            .padding(20)
+            .width(Length::Fill)
+            .style(theme::Button::TransparentBorder)
+        },
+        || {
+            Button::new(
+                Row::new()
+                    .align_items(Alignment::Center)
+                    .push(h4_bold("Hide raw").width(Length::Fill))
+                    .push(icon::collapsed_icon()),
+            )
+            .padding(20)
+            .width(Length::Fill)
+            .style(theme::Button::TransparentBorder)
+        },
+        move || {
+            Row::new()
+                .padding(20)
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .push(p2_regular(hex.clone()).width(Length::Fill))
+                .push(copy_button(hex.clone()))
+                .into()
+        },
+    ))
+    .style(theme::Container::Card(theme::Card::Simple))
+    .into()
+}
@@ -41,11 +41,16 @@ impl From<&Error> for WarningMessage {
                 DaemonError::CoinSelectionError => {
                     WarningMessage("Error when selecting coins for spend".to_string())
                 }
+                DaemonError::Broadcast(e) => {
+                    WarningMessage(format!("The transaction was rejected: {}", e.reason))
+                }
             },
             Error::Unexpected(_) => WarningMessage("Unknown error".to_string()),
             Error::HardwareWallet(_) => WarningMessage("Hardware wallet error".to_string()),
             Error::Desc(e) => WarningMessage(format!("Descriptor analysis error: '{}'.", e)),
             Error::Spend(e) => WarningMessage(format!("Spend creation error: '{}'.", e)),
+            Error::Camera(e) => WarningMessage(e.to_string()),
+            Error::ManagedBitcoind(e) => WarningMessage(e.to_string()),
         }
     }
 }
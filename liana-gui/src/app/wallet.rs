@@ -7,6 +7,7 @@ use crate::{
 };
 
 use liana::{miniscript::bitcoin, signer::HotSigner};
+use liana_ui::component::amount::BitcoinDisplayUnit;
 
 use liana::descriptors::LianaDescriptor;
 use liana::miniscript::bitcoin::bip32::Fingerprint;
@@ -33,7 +34,15 @@ pub struct Wallet {
     pub main_descriptor: LianaDescriptor,
     pub keys_aliases: HashMap<Fingerprint, String>,
     pub hardware_wallets: Vec<HardwareWalletConfig>,
-    pub signer: Option<Arc<Signer>>,
+    /// The hot signers of keys of the descriptor.
+    pub signers: Vec<Arc<Signer>>,
+    pub scheduled_broadcasts: Vec<settings::ScheduledBroadcast>,
+    pub expiry_warning_days: Vec<u32>,
+    pub refresh_max_fee_percent: u32,
+    pub notifications: settings::NotificationSettings,
+    pub bitcoin_unit: BitcoinDisplayUnit,
+    pub auto_lock: Option<settings::AutoLockSettings>,
+    pub hot_signer_unlock: settings::HotSignerUnlock,
 }
 
 impl Wallet {
@@ -43,7 +52,14 @@ impl Wallet {
             main_descriptor,
             keys_aliases: HashMap::new(),
             hardware_wallets: Vec::new(),
-            signer: None,
+            signers: Vec::new(),
+            scheduled_broadcasts: Vec::new(),
+            expiry_warning_days: settings::DEFAULT_EXPIRY_WARNING_DAYS.to_vec(),
+            refresh_max_fee_percent: settings::DEFAULT_REFRESH_MAX_FEE_PERCENT,
+            notifications: settings::NotificationSettings::default(),
+            bitcoin_unit: BitcoinDisplayUnit::default(),
+            auto_lock: None,
+            hot_signer_unlock: settings::HotSignerUnlock::default(),
         }
     }
 
@@ -62,11 +78,64 @@ impl Wallet {
         self
     }
 
+    pub fn with_scheduled_broadcasts(
+        mut self,
+        scheduled_broadcasts: Vec<settings::ScheduledBroadcast>,
+    ) -> Self {
+        self.scheduled_broadcasts = scheduled_broadcasts;
+        self
+    }
+
+    pub fn with_expiry_warning_days(mut self, expiry_warning_days: Vec<u32>) -> Self {
+        self.expiry_warning_days = expiry_warning_days;
+        self
+    }
+
+    pub fn with_refresh_max_fee_percent(mut self, refresh_max_fee_percent: u32) -> Self {
+        self.refresh_max_fee_percent = refresh_max_fee_percent;
+        self
+    }
+
+    pub fn with_notifications(mut self, notifications: settings::NotificationSettings) -> Self {
+        self.notifications = notifications;
+        self
+    }
+
+    pub fn with_bitcoin_unit(mut self, bitcoin_unit: BitcoinDisplayUnit) -> Self {
+        self.bitcoin_unit = bitcoin_unit;
+        self
+    }
+
+    pub fn with_auto_lock(mut self, auto_lock: Option<settings::AutoLockSettings>) -> Self {
+        self.auto_lock = auto_lock;
+        self
+    }
+
+    pub fn with_hot_signer_unlock(mut self, hot_signer_unlock: settings::HotSignerUnlock) -> Self {
+        self.hot_signer_unlock = hot_signer_unlock;
+        self
+    }
+
     pub fn with_signer(mut self, signer: Signer) -> Self {
-        self.signer = Some(Arc::new(signer));
+        self.signers.push(Arc::new(signer));
         self
     }
 
+    /// The hot signer of the key with this fingerprint, if any.
+    pub fn signer(&self, fingerprint: &Fingerprint) -> Option<&Arc<Signer>> {
+        self.signers
+            .iter()
+            .find(|signer| signer.fingerprint() == *fingerprint)
+    }
+
+    /// The fingerprint of a hot signer waiting for its passphrase to be entered, if any.
+    pub fn locked_signer(&self) -> Option<Fingerprint> {
+        self.signers
+            .iter()
+            .find(|signer| signer.is_locked())
+            .map(|signer| signer.fingerprint())
+    }
+
     pub fn descriptor_keys(&self) -> HashSet<Fingerprint> {
         let info = self.main_descriptor.policy();
         let mut descriptor_keys = HashSet::new();
@@ -101,6 +170,13 @@ impl Wallet {
                     self.with_name(wallet_setting.name.clone())
                         .with_hardware_wallets(wallet_setting.hardware_wallets.clone())
                         .with_key_aliases(wallet_setting.keys_aliases())
+                        .with_scheduled_broadcasts(wallet_setting.scheduled_broadcasts.clone())
+                        .with_expiry_warning_days(wallet_setting.expiry_warning_days.clone())
+                        .with_refresh_max_fee_percent(wallet_setting.refresh_max_fee_percent)
+                        .with_notifications(wallet_setting.notifications)
+                        .with_bitcoin_unit(wallet_setting.bitcoin_unit)
+                        .with_auto_lock(wallet_setting.auto_lock.clone())
+                        .with_hot_signer_unlock(wallet_setting.hot_signer_unlock)
                 } else {
                     self
                 }
@@ -123,6 +199,13 @@ impl Wallet {
                         // Only local wallet from previous version of Liana GUI may not have a
                         // settings.json file
                         remote_backend_auth: None,
+                        scheduled_broadcasts: Vec::new(),
+                        expiry_warning_days: self.expiry_warning_days.clone(),
+                        refresh_max_fee_percent: self.refresh_max_fee_percent,
+                        notifications: self.notifications,
+                        bitcoin_unit: self.bitcoin_unit,
+                        auto_lock: self.auto_lock.clone(),
+                        hot_signer_unlock: self.hot_signer_unlock,
                     }],
                 };
 
@@ -136,12 +219,106 @@ impl Wallet {
         Ok(wallet)
     }
 
+    pub fn scheduled_broadcast(&self, txid: &bitcoin::Txid) -> Option<settings::BroadcastSchedule> {
+        self.scheduled_broadcasts
+            .iter()
+            .find(|scheduled| scheduled.txid == *txid)
+            .map(|scheduled| scheduled.schedule)
+    }
+
+    /// Store the given scheduled broadcasts in the settings file and return the updated wallet.
+    pub fn update_scheduled_broadcasts(
+        &self,
+        datadir_path: &Path,
+        network: bitcoin::Network,
+        scheduled_broadcasts: Vec<settings::ScheduledBroadcast>,
+    ) -> Result<Self, WalletError> {
+        self.update_setting(datadir_path, network, |wallet_setting| {
+            wallet_setting
+                .scheduled_broadcasts
+                .clone_from(&scheduled_broadcasts)
+        })?;
+        Ok(self.clone().with_scheduled_broadcasts(scheduled_broadcasts))
+    }
+
+    /// Store the given notification settings in the settings file and return the updated wallet.
+    pub fn update_notifications(
+        &self,
+        datadir_path: &Path,
+        network: bitcoin::Network,
+        notifications: settings::NotificationSettings,
+    ) -> Result<Self, WalletError> {
+        self.update_setting(datadir_path, network, |wallet_setting| {
+            wallet_setting.notifications = notifications
+        })?;
+        Ok(self.clone().with_notifications(notifications))
+    }
+
+    /// Store the given unit in the settings file and return the updated wallet.
+    pub fn update_bitcoin_unit(
+        &self,
+        datadir_path: &Path,
+        network: bitcoin::Network,
+        bitcoin_unit: BitcoinDisplayUnit,
+    ) -> Result<Self, WalletError> {
+        self.update_setting(datadir_path, network, |wallet_setting| {
+            wallet_setting.bitcoin_unit = bitcoin_unit
+        })?;
+        Ok(self.clone().with_bitcoin_unit(bitcoin_unit))
+    }
+
+    /// Store the given auto-lock settings in the settings file and return the updated wallet.
+    pub fn update_auto_lock(
+        &self,
+        datadir_path: &Path,
+        network: bitcoin::Network,
+        auto_lock: Option<settings::AutoLockSettings>,
+    ) -> Result<Self, WalletError> {
+        self.update_setting(datadir_path, network, |wallet_setting| {
+            wallet_setting.auto_lock.clone_from(&auto_lock)
+        })?;
+        Ok(self.clone().with_auto_lock(auto_lock))
+    }
+
+    /// Store when to ask for the hot signer passphrase in the settings file and return the
+    /// updated wallet.
+    pub fn update_hot_signer_unlock(
+        &self,
+        datadir_path: &Path,
+        network: bitcoin::Network,
+        hot_signer_unlock: settings::HotSignerUnlock,
+    ) -> Result<Self, WalletError> {
+        self.update_setting(datadir_path, network, |wallet_setting| {
+            wallet_setting.hot_signer_unlock = hot_signer_unlock
+        })?;
+        Ok(self.clone().with_hot_signer_unlock(hot_signer_unlock))
+    }
+
+    fn update_setting<F: FnOnce(&mut settings::WalletSetting)>(
+        &self,
+        datadir_path: &Path,
+        network: bitcoin::Network,
+        update: F,
+    ) -> Result<(), WalletError> {
+        let mut settings = settings::Settings::from_file(datadir_path.to_path_buf(), network)?;
+        let checksum = self.descriptor_checksum();
+        if let Some(wallet_setting) = settings
+            .wallets
+            .iter_mut()
+            .find(|w| w.descriptor_checksum == checksum)
+        {
+            update(wallet_setting);
+        }
+        settings.to_file(datadir_path.to_path_buf(), network)?;
+        Ok(())
+    }
+
     pub fn load_hotsigners(
         self,
         datadir_path: &Path,
         network: bitcoin::Network,
     ) -> Result<Self, WalletError> {
-        let hot_signers = match HotSigner::from_datadir(datadir_path, network) {
+        let hot_signers = match HotSigner::from_datadir_by_fingerprint(datadir_path, network) {
             Ok(signers) => signers,
             Err(e) => match e {
                 liana::signer::SignerError::MnemonicStorage(e) => {
@@ -157,14 +334,20 @@ impl Wallet {
 
         let curve = bitcoin::secp256k1::Secp256k1::signing_only();
         let keys = self.descriptor_keys();
-        if let Some(hot_signer) = hot_signers
+        let mut wallet = self;
+        for (fingerprint, hot_signer) in hot_signers
             .into_iter()
-            .find(|s| keys.contains(&s.fingerprint(&curve)))
+            .filter(|(fingerprint, _)| keys.contains(fingerprint))
         {
-            Ok(self.with_signer(Signer::new(hot_signer)))
-        } else {
-            Ok(self)
+            wallet = if hot_signer.fingerprint(&curve) == fingerprint {
+                wallet.with_signer(Signer::new(hot_signer))
+            } else {
+                // The mnemonic was stored under the fingerprint of the key derived using a
+                // passphrase, which must be entered before signing.
+                wallet.with_signer(Signer::protected(hot_signer, fingerprint))
+            };
         }
+        Ok(wallet)
     }
 }
 
@@ -0,0 +1,226 @@
+//! Scanning of QR codes with a camera.
+//!
+//! The frames are captured and decoded on a dedicated thread, which releases the camera as soon
+//! as the subscription is dropped.
+pub mod multipart;
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use iced::subscription;
+use nokhwa::{
+    pixel_format::LumaFormat,
+    utils::{ApiBackend, CameraIndex, RequestedFormat, RequestedFormatType},
+};
+use tokio::sync::mpsc;
+
+use multipart::{MultipartDecoder, MultipartError, Progress};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Camera {
+    pub index: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CameraError {
+    NoCamera,
+    Device(String),
+    Multipart(MultipartError),
+    InvalidPsbt(String),
+}
+
+impl std::fmt::Display for CameraError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::NoCamera => write!(f, "No camera was found"),
+            Self::Device(e) => write!(f, "Camera error: {}", e),
+            Self::Multipart(e) => write!(f, "{}", e),
+            Self::InvalidPsbt(e) => write!(f, "Scanned PSBT is invalid: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CameraError {}
+
+impl From<nokhwa::NokhwaError> for CameraError {
+    fn from(e: nokhwa::NokhwaError) -> Self {
+        Self::Device(e.to_string())
+    }
+}
+
+/// List the cameras available on this machine.
+pub async fn list() -> Result<Vec<Camera>, CameraError> {
+    let cameras = tokio::task::spawn_blocking(|| nokhwa::query(ApiBackend::Auto))
+        .await
+        .map_err(|e| CameraError::Device(e.to_string()))??;
+    let cameras: Vec<Camera> = cameras
+        .into_iter()
+        .filter_map(|info| match info.index() {
+            CameraIndex::Index(index) => Some(Camera {
+                index: *index,
+                name: info.human_name(),
+            }),
+            CameraIndex::String(_) => None,
+        })
+        .collect();
+    if cameras.is_empty() {
+        Err(CameraError::NoCamera)
+    } else {
+        Ok(cameras)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ScanMessage {
+    Started,
+    Progress(Progress),
+    Scanned(Vec<u8>),
+    Error(CameraError),
+}
+
+/// Scan QR codes with the given camera until a complete PSBT was reassembled.
+pub fn scan(camera: Camera) -> iced::Subscription<ScanMessage> {
+    subscription::unfold(
+        format!("camera-{}", camera.index),
+        State::Ready(camera.index),
+        scan_step,
+    )
+}
+
+enum State {
+    Ready(u32),
+    Scanning {
+        receiver: mpsc::UnboundedReceiver<Result<Vec<String>, CameraError>>,
+        _capture: CaptureHandle,
+        decoder: MultipartDecoder,
+    },
+    Finished,
+}
+
+// Stops the capture thread once dropped.
+struct CaptureHandle(Arc<AtomicBool>);
+
+impl Drop for CaptureHandle {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+async fn scan_step(state: State) -> (ScanMessage, State) {
+    match state {
+        State::Ready(index) => {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            let stop = Arc::new(AtomicBool::new(false));
+            let thread_stop = stop.clone();
+            std::thread::spawn(move || capture(index, sender, thread_stop));
+            (
+                ScanMessage::Started,
+                State::Scanning {
+                    receiver,
+                    _capture: CaptureHandle(stop),
+                    decoder: MultipartDecoder::default(),
+                },
+            )
+        }
+        State::Scanning {
+            mut receiver,
+            _capture,
+            mut decoder,
+        } => loop {
+            match receiver.recv().await {
+                Some(Ok(contents)) => {
+                    for content in contents {
+                        match decoder.receive(&content) {
+                            Ok(Ok(psbt)) => return (ScanMessage::Scanned(psbt), State::Finished),
+                            Ok(Err(progress)) => {
+                                return (
+                                    ScanMessage::Progress(progress),
+                                    State::Scanning {
+                                        receiver,
+                                        _capture,
+                                        decoder,
+                                    },
+                                )
+                            }
+                            // A QR code which is not part of the sequence may be in sight.
+                            Err(MultipartError::MixedSequences | MultipartError::UnknownFormat) => {
+                                log::debug!("Ignoring scanned QR code: {}", content);
+                            }
+                            Err(e) => {
+                                return (
+                                    ScanMessage::Error(CameraError::Multipart(e)),
+                                    State::Finished,
+                                )
+                            }
+                        }
+                    }
+                }
+                Some(Err(e)) => return (ScanMessage::Error(e), State::Finished),
+                None => {
+                    return (
+                        ScanMessage::Error(CameraError::Device("capture stopped".to_string())),
+                        State::Finished,
+                    )
+                }
+            }
+        },
+        State::Finished => {
+            // We do not let the stream die, as it would start scanning again. The camera was
+            // already released.
+            iced::futures::future::pending().await
+        }
+    }
+}
+
+/// Capture frames until asked to stop and send the contents of the QR codes found in them.
+fn capture(
+    index: u32,
+    sender: mpsc::UnboundedSender<Result<Vec<String>, CameraError>>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut camera = match nokhwa::Camera::new(
+        CameraIndex::Index(index),
+        RequestedFormat::new::<LumaFormat>(RequestedFormatType::AbsoluteHighestFrameRate),
+    )
+    .and_then(|mut camera| camera.open_stream().map(|_| camera))
+    {
+        Ok(camera) => camera,
+        Err(e) => {
+            let _ = sender.send(Err(e.into()));
+            return;
+        }
+    };
+
+    while !stop.load(Ordering::Relaxed) && !sender.is_closed() {
+        let image = match camera
+            .frame()
+            .and_then(|frame| frame.decode_image::<LumaFormat>())
+        {
+            Ok(image) => image,
+            Err(e) => {
+                let _ = sender.send(Err(e.into()));
+                break;
+            }
+        };
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let pixels = image.into_raw();
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(width, height, |x, y| {
+            pixels[y * width + x]
+        });
+        let contents: Vec<String> = prepared
+            .detect_grids()
+            .into_iter()
+            .filter_map(|grid| grid.decode().ok().map(|(_, content)| content))
+            .collect();
+        if !contents.is_empty() && sender.send(Ok(contents)).is_err() {
+            break;
+        }
+    }
+
+    if let Err(e) = camera.stop_stream() {
+        log::error!("Failed to release camera {}: {}", index, e);
+    }
+}
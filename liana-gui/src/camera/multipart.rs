@@ -0,0 +1,368 @@
+//! Reassembly of data transmitted through a sequence of QR codes.
+//!
+//! Two formats are supported:
+//! - BBQr (https://bbqr.org), used by Coldcard among others.
+//! - Uniform Resources (BCR-2020-005), in their single-part and fountain-encoded multi-part
+//!   variants, used by Keystone, Passport, Jade and many others.
+//!
+//! A single QR code containing a base64 encoded PSBT is also accepted.
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io::Read;
+
+use base64::Engine;
+
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+/// The progress of the reassembly of a multi-part payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub received: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultipartError {
+    UnknownFormat,
+    InvalidPart(String),
+    MixedSequences,
+    UnsupportedFileType(char),
+    UnsupportedEncoding(char),
+    Decoding(String),
+}
+
+impl std::fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnknownFormat => write!(f, "QR code does not contain a PSBT"),
+            Self::InvalidPart(e) => write!(f, "Invalid QR code part: {}", e),
+            Self::MixedSequences => write!(
+                f,
+                "QR code part does not belong to the sequence being scanned"
+            ),
+            Self::UnsupportedFileType(t) => write!(f, "Unsupported BBQr file type '{}'", t),
+            Self::UnsupportedEncoding(e) => write!(f, "Unsupported BBQr encoding '{}'", e),
+            Self::Decoding(e) => write!(f, "Failed to decode the scanned data: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MultipartError {}
+
+enum Sequence {
+    Bbqr {
+        encoding: char,
+        total: usize,
+        parts: BTreeMap<usize, String>,
+    },
+    Ur {
+        decoder: ur::Decoder,
+        total: usize,
+        // The indexes of the received parts which are not fountain-encoded, only used to report
+        // progress.
+        received: Vec<usize>,
+    },
+}
+
+/// Decoder of a PSBT from a sequence of QR codes, which may be received in any order and with
+/// duplicates.
+#[derive(Default)]
+pub struct MultipartDecoder {
+    sequence: Option<Sequence>,
+}
+
+impl MultipartDecoder {
+    /// Receive the content of a QR code. Returns the PSBT bytes once the sequence is complete,
+    /// otherwise the progress of the reassembly.
+    pub fn receive(&mut self, part: &str) -> Result<Result<Vec<u8>, Progress>, MultipartError> {
+        let part = part.trim();
+        if part.starts_with("B$") {
+            self.receive_bbqr(part)
+        } else if part.to_lowercase().starts_with("ur:") {
+            self.receive_ur(&part.to_lowercase())
+        } else if self.sequence.is_none() {
+            match base64::engine::general_purpose::STANDARD.decode(part) {
+                Ok(psbt) if psbt.starts_with(PSBT_MAGIC) => Ok(Ok(psbt)),
+                _ => Err(MultipartError::UnknownFormat),
+            }
+        } else {
+            Err(MultipartError::MixedSequences)
+        }
+    }
+
+    fn receive_bbqr(&mut self, part: &str) -> Result<Result<Vec<u8>, Progress>, MultipartError> {
+        let header: Vec<char> = part.chars().take(8).collect();
+        if header.len() != 8 || !part.is_char_boundary(8) {
+            return Err(MultipartError::InvalidPart(
+                "BBQr header too short".to_string(),
+            ));
+        }
+        let encoding = header[2];
+        if !['H', '2', 'Z'].contains(&encoding) {
+            return Err(MultipartError::UnsupportedEncoding(encoding));
+        }
+        if header[3] != 'P' {
+            return Err(MultipartError::UnsupportedFileType(header[3]));
+        }
+        let total = base36(&header[4..6])?;
+        let index = base36(&header[6..8])?;
+        if total == 0 || index >= total {
+            return Err(MultipartError::InvalidPart(format!(
+                "BBQr part {} out of {}",
+                index, total
+            )));
+        }
+
+        let sequence = self.sequence.get_or_insert_with(|| Sequence::Bbqr {
+            encoding,
+            total,
+            parts: BTreeMap::new(),
+        });
+        match sequence {
+            Sequence::Bbqr {
+                encoding: seq_encoding,
+                total: seq_total,
+                parts,
+            } if *seq_encoding == encoding && *seq_total == total => {
+                parts.insert(index, part[8..].to_string());
+                if parts.len() < total {
+                    return Ok(Err(Progress {
+                        received: parts.len(),
+                        total,
+                    }));
+                }
+                let data: String = parts.values().map(String::as_str).collect();
+                decode_bbqr(encoding, &data).map(Ok)
+            }
+            _ => Err(MultipartError::MixedSequences),
+        }
+    }
+
+    fn receive_ur(&mut self, part: &str) -> Result<Result<Vec<u8>, Progress>, MultipartError> {
+        let mut components = part.trim_start_matches("ur:").split('/');
+        let ur_type = components.next().unwrap_or_default();
+        if ur_type != "crypto-psbt" && ur_type != "psbt" {
+            return Err(MultipartError::InvalidPart(format!(
+                "unexpected UR type '{}'",
+                ur_type
+            )));
+        }
+        let seq = match (components.next(), components.next()) {
+            // Single-part UR: "ur:crypto-psbt/<payload>".
+            (Some(_), None) => {
+                if self.sequence.is_some() {
+                    return Err(MultipartError::MixedSequences);
+                }
+                let (_, cbor) =
+                    ur::decode(part).map_err(|e| MultipartError::Decoding(e.to_string()))?;
+                return cbor_bytes(&cbor).map(Ok);
+            }
+            // Multi-part UR: "ur:crypto-psbt/<seq>-<total>/<payload>".
+            (Some(seq), Some(_)) => seq,
+            _ => return Err(MultipartError::InvalidPart("empty UR".to_string())),
+        };
+        let (index, total) = seq
+            .split_once('-')
+            .and_then(|(i, t)| Some((i.parse::<usize>().ok()?, t.parse::<usize>().ok()?)))
+            .ok_or_else(|| MultipartError::InvalidPart(format!("invalid UR sequence '{}'", seq)))?;
+
+        let sequence = self.sequence.get_or_insert_with(|| Sequence::Ur {
+            decoder: ur::Decoder::default(),
+            total,
+            received: Vec::new(),
+        });
+        match sequence {
+            Sequence::Ur {
+                decoder,
+                total: seq_total,
+                received,
+            } if *seq_total == total => {
+                decoder
+                    .receive(part)
+                    .map_err(|e| MultipartError::InvalidPart(e.to_string()))?;
+                if index <= total && !received.contains(&index) {
+                    received.push(index);
+                }
+                if !decoder.complete() {
+                    return Ok(Err(Progress {
+                        // Fountain-encoded parts may complete the sequence before all of the
+                        // original parts were seen, so this is only an estimate.
+                        received: received.len().min(total.saturating_sub(1)),
+                        total,
+                    }));
+                }
+                match decoder
+                    .message()
+                    .map_err(|e| MultipartError::Decoding(e.to_string()))?
+                {
+                    Some(cbor) => cbor_bytes(&cbor).map(Ok),
+                    None => Err(MultipartError::Decoding("empty UR message".to_string())),
+                }
+            }
+            _ => Err(MultipartError::MixedSequences),
+        }
+    }
+}
+
+fn base36(chars: &[char]) -> Result<usize, MultipartError> {
+    let s: String = chars.iter().collect();
+    usize::from_str_radix(&s, 36)
+        .map_err(|_| MultipartError::InvalidPart(format!("invalid BBQr header '{}'", s)))
+}
+
+fn decode_bbqr(encoding: char, data: &str) -> Result<Vec<u8>, MultipartError> {
+    match encoding {
+        'H' => hex::decode(data).map_err(|e| MultipartError::Decoding(e.to_string())),
+        '2' => base32(data),
+        'Z' => {
+            // Raw deflate stream, without zlib header.
+            let compressed = base32(data)?;
+            let mut res = Vec::new();
+            flate2::read::DeflateDecoder::new(compressed.as_slice())
+                .read_to_end(&mut res)
+                .map_err(|e| MultipartError::Decoding(e.to_string()))?;
+            Ok(res)
+        }
+        e => Err(MultipartError::UnsupportedEncoding(e)),
+    }
+}
+
+/// RFC 4648 base32 decoding, without padding.
+fn base32(data: &str) -> Result<Vec<u8>, MultipartError> {
+    let mut res = Vec::with_capacity(data.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in data.trim_end_matches('=').chars() {
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => {
+                return Err(MultipartError::Decoding(format!(
+                    "invalid base32 character '{}'",
+                    c
+                )))
+            }
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(res)
+}
+
+/// Extract the content of a CBOR byte string, as the PSBT is wrapped in UR payloads.
+fn cbor_bytes(cbor: &[u8]) -> Result<Vec<u8>, MultipartError> {
+    let invalid = || MultipartError::Decoding("invalid CBOR byte string".to_string());
+    let (header, rest) = cbor.split_first().ok_or_else(invalid)?;
+    if header >> 5 != 2 {
+        return Err(invalid());
+    }
+    let (len, data) = match header & 0x1f {
+        n @ 0..=23 => (n as usize, rest),
+        n @ 24..=27 => {
+            let size = 1 << (n - 24);
+            if rest.len() < size {
+                return Err(invalid());
+            }
+            let len = rest[..size]
+                .iter()
+                .fold(0u64, |acc, b| (acc << 8) | *b as u64);
+            (usize::try_from(len).map_err(|_| invalid())?, &rest[size..])
+        }
+        _ => return Err(invalid()),
+    };
+    if data.len() != len {
+        return Err(invalid());
+    }
+    Ok(data.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base32_decoding() {
+        assert_eq!(base32("MZXW6YTBOI").unwrap(), b"foobar");
+        assert_eq!(base32("MZXW6YQ").unwrap(), b"foob");
+        assert!(base32("mzxw6ytboi").is_err());
+    }
+
+    #[test]
+    fn cbor_decoding() {
+        assert_eq!(cbor_bytes(&[0x43, 1, 2, 3]).unwrap(), vec![1, 2, 3]);
+        let mut long = vec![0x58, 30];
+        long.extend_from_slice(&[7; 30]);
+        assert_eq!(cbor_bytes(&long).unwrap(), vec![7; 30]);
+        // Not a byte string
+        assert!(cbor_bytes(&[0x63, 1, 2, 3]).is_err());
+        // Truncated
+        assert!(cbor_bytes(&[0x43, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn bbqr_sequence() {
+        // "foobarbaz" hex encoded, split in three parts.
+        let parts = ["B$HP0302626172", "B$HP0300666F6F", "B$HP0301"];
+        let mut decoder = MultipartDecoder::default();
+        assert_eq!(
+            decoder.receive(parts[0]).unwrap(),
+            Err(Progress {
+                received: 1,
+                total: 3
+            })
+        );
+        // Duplicates are ignored.
+        assert_eq!(
+            decoder.receive(parts[0]).unwrap(),
+            Err(Progress {
+                received: 1,
+                total: 3
+            })
+        );
+        assert_eq!(
+            decoder.receive(parts[1]).unwrap(),
+            Err(Progress {
+                received: 2,
+                total: 3
+            })
+        );
+        assert_eq!(
+            decoder.receive("B$2P0200MZXW6").unwrap_err(),
+            MultipartError::MixedSequences
+        );
+        assert_eq!(decoder.receive(parts[2]).unwrap(), Ok(b"foobar".to_vec()));
+
+        let mut decoder = MultipartDecoder::default();
+        assert_eq!(
+            decoder.receive("B$2P0100MZXW6YTBOI").unwrap(),
+            Ok(b"foobar".to_vec())
+        );
+        assert_eq!(
+            decoder.receive("B$HT0100AA").unwrap_err(),
+            MultipartError::UnsupportedFileType('T')
+        );
+    }
+
+    #[test]
+    fn single_qr_code() {
+        let mut decoder = MultipartDecoder::default();
+        assert_eq!(
+            decoder.receive("cHNidP8=").unwrap(),
+            Ok(b"psbt\xff".to_vec())
+        );
+        assert_eq!(
+            decoder.receive("not a psbt").unwrap_err(),
+            MultipartError::UnknownFormat
+        );
+        // Valid base64, but not a PSBT.
+        assert_eq!(
+            decoder.receive("Zm9vYmFy").unwrap_err(),
+            MultipartError::UnknownFormat
+        );
+    }
+}
@@ -38,6 +38,8 @@ use serde_json::Deserializer;
 #[cfg(not(windows))]
 use tracing::debug;
 
+use lianad::commands::BroadcastError;
+
 /// A handle to a remote JSONRPC server
 #[derive(Debug, Clone)]
 pub struct JsonRPCClient {
@@ -257,7 +259,21 @@ impl From<Error> for super::DaemonError {
             }
             Error::NoErrorOrResult => super::DaemonError::NoAnswer,
             Error::NotSupported => super::DaemonError::ClientNotSupported,
-            Error::Rpc(e) => super::DaemonError::Rpc(e.code, e.message),
+            Error::Rpc(e) => {
+                // The daemon tells why the Bitcoin backend refused to broadcast a transaction.
+                let reason = e
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.get("reason"))
+                    .and_then(|reason| serde_json::from_value(reason.clone()).ok());
+                match reason {
+                    Some(reason) => super::DaemonError::Broadcast(BroadcastError {
+                        reason,
+                        message: e.message,
+                    }),
+                    None => super::DaemonError::Rpc(e.code, e.message),
+                }
+            }
         }
     }
 }
@@ -32,11 +32,30 @@ pub trait Client {
 #[derive(Debug, Clone)]
 pub struct Lianad<C: Client> {
     client: C,
+    /// The wallet to call the methods of, if the daemon serves several wallets.
+    wallet: Option<String>,
 }
 
 impl<C: Client> Lianad<C> {
     pub fn new(client: C) -> Lianad<C> {
-        Lianad { client }
+        Lianad {
+            client,
+            wallet: None,
+        }
+    }
+
+    /// Call the methods of this wallet, on a daemon serving several wallets.
+    pub fn with_wallet(mut self, wallet: Option<String>) -> Lianad<C> {
+        self.wallet = wallet;
+        self
+    }
+
+    /// The names of the wallets served by the daemon. This fails if it serves a single wallet.
+    pub fn list_wallets(&self) -> Result<Vec<String>, DaemonError> {
+        self.client
+            .request::<Request, ListWalletsResult>("listwallets", None)
+            .map(|res| res.wallets)
+            .map_err(|e| e.into())
     }
 
     /// Generic call function for RPC calls.
@@ -45,8 +64,12 @@ impl<C: Client> Lianad<C> {
         method: &str,
         input: Option<T>,
     ) -> Result<U, DaemonError> {
+        let method = match &self.wallet {
+            Some(wallet) => format!("{}_{}", wallet, method),
+            None => method.to_string(),
+        };
         info!("{}", method);
-        self.client.request(method, input).map_err(|e| {
+        self.client.request(&method, input).map_err(|e| {
             error!("method {} failed: {:?}", method, e);
             e.into()
         })
@@ -105,15 +128,25 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
         destinations: &HashMap<Address<address::NetworkUnchecked>, u64>,
         feerate_vb: u64,
         change_address: Option<Address<address::NetworkUnchecked>>,
+        avoid_mixing_tags: bool,
+        spend_path: Option<u16>,
     ) -> Result<CreateSpendResult, DaemonError> {
         let mut input = vec![
             json!(destinations),
             json!(coins_outpoints),
             json!(feerate_vb),
         ];
-        if let Some(change_address) = change_address {
+        if change_address.is_some() || avoid_mixing_tags || spend_path.is_some() {
             input.push(json!(change_address));
         }
+        if avoid_mixing_tags || spend_path.is_some() {
+            // Use the coin selection algorithm from the daemon configuration.
+            input.push(json!(null));
+            input.push(json!(avoid_mixing_tags));
+        }
+        if let Some(timelock) = spend_path {
+            input.push(json!(timelock));
+        }
         self.call("createspend", Some(input))
     }
 
@@ -130,6 +163,28 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
         self.call("rbfpsbt", Some(input))
     }
 
+    async fn create_cpfp(
+        &self,
+        coins_outpoints: &[OutPoint],
+        feerate_vb: u64,
+    ) -> Result<CreateCpfpResult, DaemonError> {
+        self.call(
+            "createcpfp",
+            Some(vec![json!(coins_outpoints), json!(feerate_vb)]),
+        )
+    }
+
+    async fn create_consolidation(
+        &self,
+        feerate_vb: u64,
+        max_inputs: usize,
+    ) -> Result<CreateSpendResult, DaemonError> {
+        self.call(
+            "createconsolidation",
+            Some(vec![json!(feerate_vb), json!(max_inputs)]),
+        )
+    }
+
     async fn update_spend_tx(&self, psbt: &Psbt) -> Result<(), DaemonError> {
         let spend_tx = psbt.to_string();
         let _res: serde_json::value::Value = self.call("updatespend", Some(vec![spend_tx]))?;
@@ -142,6 +197,53 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
         Ok(())
     }
 
+    async fn save_psbt(&self, psbt: &Psbt, label: &str) -> Result<SavePsbtResult, DaemonError> {
+        self.call(
+            "savepsbt",
+            Some(vec![json!(psbt.to_string()), json!(label)]),
+        )
+    }
+
+    async fn list_saved_psbts(&self) -> Result<ListSavedPsbtsResult, DaemonError> {
+        self.call("listsavedpsbts", Option::<Request>::None)
+    }
+
+    async fn get_saved_psbt(&self, id: i64) -> Result<SavedPsbtEntry, DaemonError> {
+        self.call("getsavedpsbt", Some(vec![id]))
+    }
+
+    async fn delete_saved_psbt(&self, id: i64) -> Result<(), DaemonError> {
+        let _res: serde_json::value::Value = self.call("deletesavedpsbt", Some(vec![id]))?;
+        Ok(())
+    }
+
+    async fn get_fee_estimates(&self) -> Result<GetFeeEstimatesResult, DaemonError> {
+        self.call("getfeeestimates", Option::<Request>::None)
+    }
+
+    async fn pending_spend_fee_info(
+        &self,
+        txid: &Txid,
+    ) -> Result<Option<PendingSpendFeeInfo>, DaemonError> {
+        self.call("getpendingspendfeeinfo", Some(vec![txid.to_string()]))
+    }
+
+    async fn list_addresses(
+        &self,
+        start_index: Option<u32>,
+        count: Option<u32>,
+    ) -> Result<ListAddressesResult, DaemonError> {
+        // Only pass the parameters which are set, as null values are rejected.
+        let mut params = serde_json::Map::new();
+        if let Some(start_index) = start_index {
+            params.insert("start_index".to_string(), json!(start_index));
+        }
+        if let Some(count) = count {
+            params.insert("count".to_string(), json!(count));
+        }
+        self.call("listaddresses", Some(params))
+    }
+
     async fn broadcast_spend_tx(&self, txid: &Txid) -> Result<(), DaemonError> {
         let _res: serde_json::value::Value =
             self.call("broadcastspend", Some(vec![txid.to_string()]))?;
@@ -205,7 +307,22 @@ impl<C: Client + Send + Sync + Debug> Daemon for Lianad<C> {
         let _res: serde_json::value::Value = self.call("updatelabels", Some(vec![labels]))?;
         Ok(())
     }
+
+    async fn set_coin_tags(
+        &self,
+        tags: &HashMap<OutPoint, Option<String>>,
+    ) -> Result<(), DaemonError> {
+        let tags: HashMap<String, Option<String>> =
+            HashMap::from_iter(tags.iter().map(|(op, tag)| (op.to_string(), tag.clone())));
+        let _res: serde_json::value::Value = self.call("setcointags", Some(vec![tags]))?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Request {}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ListWalletsResult {
+    wallets: Vec<String>,
+}
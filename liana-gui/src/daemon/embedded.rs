@@ -1,16 +1,21 @@
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 use super::{model::*, node, Daemon, DaemonBackend, DaemonError};
 use async_trait::async_trait;
 use liana::miniscript::bitcoin::{address, psbt::Psbt, Address, Network, OutPoint, Txid};
 use lianad::{
-    commands::{CoinStatus, LabelItem},
+    commands::{CoinStatus, CommandError, LabelItem},
     config::Config,
     DaemonControl, DaemonHandle,
 };
 
+/// How long to wait for the daemon to finish its in-flight commands and stop, for a hung Bitcoin
+/// backend not to block forever.
+const DAEMON_STOP_TIMEOUT: Duration = Duration::from_secs(20);
+
 pub struct EmbeddedDaemon {
     config: Config,
     handle: Mutex<Option<DaemonHandle>>,
@@ -74,27 +79,52 @@ impl Daemon for EmbeddedDaemon {
         }
         // if the daemon poller is not alive, we try to terminate it to fetch the error.
         if let Some(h) = handle.take() {
-            h.stop()
+            h.stop_with_timeout(DAEMON_STOP_TIMEOUT)
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))?;
         }
-        Ok(())
+        Err(DaemonError::DaemonStopped)
     }
 
     async fn stop(&self) -> Result<(), DaemonError> {
         let mut handle = self.handle.lock().await;
         if let Some(h) = handle.take() {
-            h.stop()
+            h.stop_with_timeout(DAEMON_STOP_TIMEOUT)
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))?;
         }
         Ok(())
     }
 
     async fn get_info(&self) -> Result<GetInfoResult, DaemonError> {
-        self.command(|daemon| Ok(daemon.get_info())).await
+        self.command(|daemon| {
+            daemon
+                .get_info()
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    // Read the progress shared by the poller rather than querying the Bitcoin backend.
+    async fn sync_progress(&self) -> Result<BlockchainSync, DaemonError> {
+        match self.handle.lock().await.as_ref() {
+            Some(h) if h.is_alive() => {
+                let (blocks, headers) = h.sync_heights().unwrap_or((0, 0));
+                Ok(BlockchainSync {
+                    progress: h.sync_progress().map(f64::from).unwrap_or(0.0),
+                    blocks,
+                    headers,
+                })
+            }
+            _ => Err(DaemonError::DaemonStopped),
+        }
     }
 
     async fn get_new_address(&self) -> Result<GetAddressResult, DaemonError> {
-        self.command(|daemon| Ok(daemon.get_new_address())).await
+        self.command(|daemon| {
+            daemon
+                .get_new_address()
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
     }
 
     async fn list_coins(
@@ -102,8 +132,12 @@ impl Daemon for EmbeddedDaemon {
         statuses: &[CoinStatus],
         outpoints: &[OutPoint],
     ) -> Result<ListCoinsResult, DaemonError> {
-        self.command(|daemon| Ok(daemon.list_coins(statuses, outpoints)))
-            .await
+        self.command(|daemon| {
+            daemon
+                .list_coins(statuses, outpoints)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
     }
 
     async fn list_spend_txs(&self) -> Result<ListSpendResult, DaemonError> {
@@ -121,13 +155,21 @@ impl Daemon for EmbeddedDaemon {
         end: u32,
         limit: u64,
     ) -> Result<ListTransactionsResult, DaemonError> {
-        self.command(|daemon| Ok(daemon.list_confirmed_transactions(start, end, limit)))
-            .await
+        self.command(|daemon| {
+            daemon
+                .list_confirmed_transactions(start, end, limit)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
     }
 
     async fn list_txs(&self, txids: &[Txid]) -> Result<ListTransactionsResult, DaemonError> {
-        self.command(|daemon| Ok(daemon.list_transactions(txids)))
-            .await
+        self.command(|daemon| {
+            daemon
+                .list_transactions(txids)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
     }
 
     async fn create_spend_tx(
@@ -136,10 +178,20 @@ impl Daemon for EmbeddedDaemon {
         destinations: &HashMap<Address<address::NetworkUnchecked>, u64>,
         feerate_vb: u64,
         change_address: Option<Address<address::NetworkUnchecked>>,
+        avoid_mixing_tags: bool,
+        spend_path: Option<u16>,
     ) -> Result<CreateSpendResult, DaemonError> {
         self.command(|daemon| {
             daemon
-                .create_spend(destinations, coins_outpoints, feerate_vb, change_address)
+                .create_spend(
+                    destinations,
+                    coins_outpoints,
+                    feerate_vb,
+                    change_address,
+                    None,
+                    avoid_mixing_tags,
+                    spend_path,
+                )
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
         .await
@@ -159,6 +211,32 @@ impl Daemon for EmbeddedDaemon {
         .await
     }
 
+    async fn create_cpfp(
+        &self,
+        coins_outpoints: &[OutPoint],
+        feerate_vb: u64,
+    ) -> Result<CreateCpfpResult, DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .create_cpfp(coins_outpoints, feerate_vb)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn create_consolidation(
+        &self,
+        feerate_vb: u64,
+        max_inputs: usize,
+    ) -> Result<CreateSpendResult, DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .create_consolidation(feerate_vb, max_inputs)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
     async fn update_spend_tx(&self, psbt: &Psbt) -> Result<(), DaemonError> {
         self.command(|daemon| {
             daemon
@@ -170,21 +248,89 @@ impl Daemon for EmbeddedDaemon {
 
     async fn delete_spend_tx(&self, txid: &Txid) -> Result<(), DaemonError> {
         self.command(|daemon| {
-            daemon.delete_spend(txid);
-            Ok(())
+            daemon
+                .delete_spend(txid)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
         .await
     }
 
-    async fn broadcast_spend_tx(&self, txid: &Txid) -> Result<(), DaemonError> {
+    async fn save_psbt(&self, psbt: &Psbt, label: &str) -> Result<SavePsbtResult, DaemonError> {
         self.command(|daemon| {
             daemon
-                .broadcast_spend(txid)
+                .save_psbt(psbt, label)
                 .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
         .await
     }
 
+    async fn list_saved_psbts(&self) -> Result<ListSavedPsbtsResult, DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .list_saved_psbts()
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn get_saved_psbt(&self, id: i64) -> Result<SavedPsbtEntry, DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .get_saved_psbt(id)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn delete_saved_psbt(&self, id: i64) -> Result<(), DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .delete_saved_psbt(id)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn get_fee_estimates(&self) -> Result<GetFeeEstimatesResult, DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .get_fee_estimates()
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn pending_spend_fee_info(
+        &self,
+        txid: &Txid,
+    ) -> Result<Option<PendingSpendFeeInfo>, DaemonError> {
+        self.command(|daemon| Ok(daemon.pending_spend_fee_info(txid)))
+            .await
+    }
+
+    async fn list_addresses(
+        &self,
+        start_index: Option<u32>,
+        count: Option<u32>,
+    ) -> Result<ListAddressesResult, DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .list_addresses(start_index, count)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn broadcast_spend_tx(&self, txid: &Txid) -> Result<(), DaemonError> {
+        self.command(|daemon| {
+            daemon.broadcast_spend(txid).map_err(|e| match e {
+                CommandError::TxBroadcast(e) => DaemonError::Broadcast(e),
+                e => DaemonError::Unexpected(e.to_string()),
+            })
+        })
+        .await
+    }
+
     async fn start_rescan(&self, t: u32) -> Result<(), DaemonError> {
         self.command(|daemon| {
             daemon
@@ -213,8 +359,13 @@ impl Daemon for EmbeddedDaemon {
         &self,
         items: &HashSet<LabelItem>,
     ) -> Result<HashMap<String, String>, DaemonError> {
-        self.command(|daemon| Ok(daemon.get_labels(items).labels))
-            .await
+        self.command(|daemon| {
+            daemon
+                .get_labels(items)
+                .map(|res| res.labels)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
     }
 
     async fn update_labels(
@@ -222,8 +373,21 @@ impl Daemon for EmbeddedDaemon {
         items: &HashMap<LabelItem, Option<String>>,
     ) -> Result<(), DaemonError> {
         self.command(|daemon| {
-            daemon.update_labels(items);
-            Ok(())
+            daemon
+                .update_labels(items)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
+        })
+        .await
+    }
+
+    async fn set_coin_tags(
+        &self,
+        tags: &HashMap<OutPoint, Option<String>>,
+    ) -> Result<(), DaemonError> {
+        self.command(|daemon| {
+            daemon
+                .set_coin_tags(tags)
+                .map_err(|e| DaemonError::Unexpected(e.to_string()))
         })
         .await
     }
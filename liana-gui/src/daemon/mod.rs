@@ -15,7 +15,7 @@ use liana::miniscript::bitcoin::{
     address, bip32::Fingerprint, psbt::Psbt, secp256k1, Address, Network, OutPoint, Txid,
 };
 use lianad::{
-    commands::{CoinStatus, LabelItem, TransactionInfo},
+    commands::{BroadcastError, CoinStatus, LabelItem, TransactionInfo},
     config::Config,
     StartupError,
 };
@@ -42,6 +42,8 @@ pub enum DaemonError {
     ClientNotSupported,
     /// Error when selecting coins for spend.
     CoinSelectionError,
+    /// The Bitcoin backend refused to broadcast the transaction.
+    Broadcast(BroadcastError),
 }
 
 impl std::fmt::Display for DaemonError {
@@ -56,6 +58,7 @@ impl std::fmt::Display for DaemonError {
             Self::Start(e) => write!(f, "Daemon did not start: {}", e),
             Self::ClientNotSupported => write!(f, "Daemon communication is not supported"),
             Self::CoinSelectionError => write!(f, "Coin selection error"),
+            Self::Broadcast(e) => write!(f, "Failed to broadcast transaction: {}", e),
         }
     }
 }
@@ -80,6 +83,12 @@ pub trait Daemon: Debug {
     async fn is_alive(&self, datadir: &Path, network: Network) -> Result<(), DaemonError>;
     async fn stop(&self) -> Result<(), DaemonError>;
     async fn get_info(&self) -> Result<model::GetInfoResult, DaemonError>;
+    /// The progress of the blockchain synchronisation.
+    async fn sync_progress(&self) -> Result<model::BlockchainSync, DaemonError> {
+        self.get_info()
+            .await
+            .map(|info| model::BlockchainSync::from(&info))
+    }
     async fn get_new_address(&self) -> Result<model::GetAddressResult, DaemonError>;
     async fn list_coins(
         &self,
@@ -93,6 +102,8 @@ pub trait Daemon: Debug {
         destinations: &HashMap<Address<address::NetworkUnchecked>, u64>,
         feerate_vb: u64,
         change_address: Option<Address<address::NetworkUnchecked>>,
+        avoid_mixing_tags: bool,
+        spend_path: Option<u16>,
     ) -> Result<model::CreateSpendResult, DaemonError>;
     async fn rbf_psbt(
         &self,
@@ -128,6 +139,60 @@ pub trait Daemon: Debug {
     async fn send_wallet_invitation(&self, _email: &str) -> Result<(), DaemonError> {
         Ok(())
     }
+    /// Set, for a set of coins (as key), their privacy tag (as value). A `None` value removes
+    /// the tag.
+    async fn set_coin_tags(
+        &self,
+        _tags: &HashMap<OutPoint, Option<String>>,
+    ) -> Result<(), DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn save_psbt(
+        &self,
+        _psbt: &Psbt,
+        _label: &str,
+    ) -> Result<model::SavePsbtResult, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn list_saved_psbts(&self) -> Result<model::ListSavedPsbtsResult, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn get_saved_psbt(&self, _id: i64) -> Result<model::SavedPsbtEntry, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn delete_saved_psbt(&self, _id: i64) -> Result<(), DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn get_fee_estimates(&self) -> Result<model::GetFeeEstimatesResult, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn pending_spend_fee_info(
+        &self,
+        _txid: &Txid,
+    ) -> Result<Option<model::PendingSpendFeeInfo>, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn create_cpfp(
+        &self,
+        _coins_outpoints: &[OutPoint],
+        _feerate_vb: u64,
+    ) -> Result<model::CreateCpfpResult, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn create_consolidation(
+        &self,
+        _feerate_vb: u64,
+        _max_inputs: usize,
+    ) -> Result<model::CreateSpendResult, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
+    async fn list_addresses(
+        &self,
+        _start_index: Option<u32>,
+        _count: Option<u32>,
+    ) -> Result<model::ListAddressesResult, DaemonError> {
+        Err(DaemonError::ClientNotSupported)
+    }
 
     // List spend transactions, optionally filtered to the specified `txids`.
     // Set `txids` to `None` for no filter (passing an empty slice returns no transactions).
@@ -324,6 +389,50 @@ pub trait Daemon: Debug {
         Ok(txs)
     }
 
+    // List the saved spend transactions which were broadcast and then replaced by one of the
+    // given `pending` transactions. They are kept until the replacement confirms, at which point
+    // their PSBT is superseded.
+    async fn list_replaced_txs(
+        &self,
+        pending: &[model::HistoryTransaction],
+    ) -> Result<Vec<model::HistoryTransaction>, DaemonError> {
+        let mut replaced = Vec::new();
+        for spend_tx in self.list_spend_transactions(None).await? {
+            if spend_tx.status != model::SpendStatus::Pending {
+                continue;
+            }
+            let tx = spend_tx.psbt.unsigned_tx;
+            let txid = tx.txid();
+            // A replacement must pay a higher fee than the transaction it replaces, this tells
+            // a replaced transaction apart from a replacement which was not broadcast yet.
+            let replacement = pending.iter().find(|p| {
+                p.txid != txid
+                    && p.time.is_none()
+                    && spend_tx.fee_amount.is_some()
+                    && p.fee_amount > spend_tx.fee_amount
+                    && tx.input.iter().any(|txin| {
+                        p.tx.input
+                            .iter()
+                            .any(|p_txin| p_txin.previous_output == txin.previous_output)
+                    })
+            });
+            if let Some(replacement) = replacement {
+                let mut history_tx = model::HistoryTransaction::new(
+                    tx,
+                    None,
+                    None,
+                    spend_tx.coins.into_values().collect(),
+                    spend_tx.change_indexes,
+                    spend_tx.network,
+                );
+                history_tx.labels = spend_tx.labels;
+                history_tx.replaced_by = Some(replacement.txid);
+                replaced.push(history_tx);
+            }
+        }
+        Ok(replaced)
+    }
+
     async fn list_pending_payments(&self) -> Result<Vec<model::Payment>, DaemonError> {
         let mut txs = self.list_pending_txs().await?;
         txs.sort_by(|a, b| b.time.cmp(&a.time));
@@ -1,5 +1,5 @@
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use liana::descriptors::LianaDescriptor;
 pub use liana::{
@@ -7,16 +7,39 @@ pub use liana::{
     miniscript::bitcoin::{
         bip32::{DerivationPath, Fingerprint},
         psbt::Psbt,
-        secp256k1, Address, Amount, Network, OutPoint, Transaction, Txid,
+        relative, secp256k1, Address, Amount, Network, OutPoint, Transaction, Txid,
     },
 };
 pub use lianad::commands::{
-    CreateSpendResult, GetAddressResult, GetInfoResult, GetLabelsResult, LabelItem, ListCoinsEntry,
-    ListCoinsResult, ListSpendEntry, ListSpendResult, ListTransactionsResult, TransactionInfo,
+    AddressInfo, CreateCpfpResult, CreateSpendResult, GetAddressResult, GetFeeEstimatesResult,
+    GetInfoResult, GetInfoWarning, GetLabelsResult, LabelItem, ListAddressesResult, ListCoinsEntry,
+    ListCoinsResult, ListSavedPsbtsResult, ListSpendEntry, ListSpendResult, ListTransactionsResult,
+    PendingSpendFeeInfo, RecoveryHeight, SavePsbtResult, SavedPsbtEntry, TransactionInfo,
 };
 
 pub type Coin = ListCoinsEntry;
 
+/// The progress of the blockchain synchronisation of the Bitcoin backend.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockchainSync {
+    /// Between 0 and 1.
+    pub progress: f64,
+    /// Number of blocks validated.
+    pub blocks: u64,
+    /// Number of headers of the best known chain, an estimate of the network tip.
+    pub headers: u64,
+}
+
+impl From<&GetInfoResult> for BlockchainSync {
+    fn from(info: &GetInfoResult) -> Self {
+        Self {
+            progress: info.sync,
+            blocks: info.backend_blocks,
+            headers: info.backend_headers,
+        }
+    }
+}
+
 pub fn remaining_sequence(coin: &Coin, blockheight: u32, timelock: u16) -> u32 {
     if let Some(coin_blockheight) = coin.block_height {
         if blockheight > coin_blockheight as u32 + timelock as u32 {
@@ -29,6 +52,19 @@ pub fn remaining_sequence(coin: &Coin, blockheight: u32, timelock: u16) -> u32 {
     }
 }
 
+/// Returns the distinct privacy tags of the given coins, `None` standing for the untagged
+/// ones, if spending them together would mix different tags. Returns an empty list otherwise.
+pub fn mixed_tags<'a>(coins: impl IntoIterator<Item = &'a Coin>) -> Vec<Option<&'a str>> {
+    let mut tags: Vec<Option<&str>> = coins.into_iter().map(|c| c.tag.as_deref()).collect();
+    tags.sort();
+    tags.dedup();
+    if tags.len() > 1 {
+        tags
+    } else {
+        Vec::new()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SpendTx {
     pub network: Network,
@@ -53,6 +89,8 @@ pub enum SpendStatus {
     Broadcast,
     Spent,
     Deprecated,
+    /// Another transaction spending the same coins, such as a replacement, was confirmed.
+    Superseded,
 }
 
 impl SpendTx {
@@ -102,9 +140,9 @@ impl SpendTx {
                 // that is currently spending the coin.
                 // The PSBT status should remain as Pending so that it can be signed and broadcast.
                 // Once the replacement transaction has been confirmed, the PSBT for the
-                // transaction currently spending this coin will be shown as Deprecated.
+                // transaction currently spending this coin will be shown as Superseded.
                 } else if info.height.is_some() {
-                    status = SpendStatus::Deprecated
+                    status = SpendStatus::Superseded
                 }
             }
             coins_map.insert(coin.outpoint, coin);
@@ -282,6 +320,8 @@ pub struct HistoryTransaction {
     pub height: Option<i32>,
     pub time: Option<u32>,
     pub kind: TransactionKind,
+    /// The unconfirmed transaction which replaced this one, if it was replaced.
+    pub replaced_by: Option<Txid>,
 }
 
 impl HistoryTransaction {
@@ -366,6 +406,7 @@ impl HistoryTransaction {
             height,
             time,
             network,
+            replaced_by: None,
         }
     }
 
@@ -413,6 +454,206 @@ impl HistoryTransaction {
             TransactionKind::IncomingPaymentBatch(_) | TransactionKind::OutgoingPaymentBatch(_)
         )
     }
+
+    /// The timelock of the recovery path used to spend our coins in this transaction, or `None`
+    /// if they were spent using the primary path.
+    pub fn recovery_path_timelock(&self, policy: &LianaPolicy) -> Option<u16> {
+        let recovery_paths = policy.recovery_paths();
+        self.tx
+            .input
+            .iter()
+            .filter(|txin| self.coins.contains_key(&txin.previous_output))
+            .filter_map(|txin| match txin.sequence.to_relative_lock_time() {
+                Some(relative::LockTime::Blocks(height)) => Some(height.value()),
+                _ => None,
+            })
+            .filter(|timelock| recovery_paths.contains_key(timelock))
+            .max()
+    }
+
+    /// The feerate of the transaction in sat/vb, if we know its fee.
+    pub fn feerate(&self) -> Option<u64> {
+        self.fee_amount
+            .map(|fee| fee.to_sat() / self.tx.vsize() as u64)
+    }
+
+    /// The estimated position in the mempool of this transaction, if it is an unconfirmed
+    /// transaction of ours whose fee is known.
+    pub fn mempool_position(&self, fee_estimates: &BTreeMap<u16, u64>) -> Option<MempoolPosition> {
+        if self.time.is_some() || self.is_external() || self.replaced_by.is_some() {
+            return None;
+        }
+        MempoolPosition::new(self.feerate()?, fee_estimates)
+    }
+}
+
+/// A qualitative estimate of when an unconfirmed transaction will confirm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MempoolPosition {
+    /// Likely to confirm in the next block.
+    NextBlock,
+    /// Likely to confirm within about 3 blocks.
+    WithinFewBlocks,
+    /// Pays at least the feerate of the least urgent estimate, may take hours.
+    MayTakeHours,
+    /// Pays less than the feerate of any estimate.
+    BelowMinimum,
+}
+
+impl MempoolPosition {
+    /// Classify a feerate in sat/vb against feerate estimates by confirmation target in blocks.
+    /// Returns `None` if there is no estimate.
+    pub fn new(feerate: u64, fee_estimates: &BTreeMap<u16, u64>) -> Option<Self> {
+        if fee_estimates.is_empty() {
+            return None;
+        }
+        Some(match confirmation_target(feerate, fee_estimates) {
+            Some(1) => Self::NextBlock,
+            Some(t) if t <= 3 => Self::WithinFewBlocks,
+            Some(_) => Self::MayTakeHours,
+            None => Self::BelowMinimum,
+        })
+    }
+}
+
+/// The most urgent confirmation target, in blocks, whose estimate the given feerate in sat/vb
+/// meets. Returns `None` if it doesn't meet any.
+pub fn confirmation_target(feerate: u64, fee_estimates: &BTreeMap<u16, u64>) -> Option<u16> {
+    fee_estimates
+        .iter()
+        .find(|(_, estimate)| feerate >= **estimate)
+        .map(|(target, _)| *target)
+}
+
+/// The feerate to use when the daemon has no estimate, in sat/vb.
+pub const FALLBACK_FEERATE: u64 = 1;
+
+/// A feerate chosen from the estimates for a given confirmation target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeratePreset {
+    NextBlock,
+    HalfHour,
+    Hour,
+    Economy,
+}
+
+impl FeeratePreset {
+    pub const ALL: [Self; 4] = [Self::NextBlock, Self::HalfHour, Self::Hour, Self::Economy];
+
+    /// The confirmation target in blocks.
+    pub fn target(&self) -> u16 {
+        match self {
+            Self::NextBlock => 1,
+            Self::HalfHour => 3,
+            Self::Hour => 6,
+            Self::Economy => 144,
+        }
+    }
+
+    /// The estimated feerate in sat/vb for this target, or `None` if there is no estimate.
+    /// If this target has no estimate, the one of the closest more urgent target is used, or the
+    /// most urgent available otherwise.
+    pub fn feerate(&self, fee_estimates: &BTreeMap<u16, u64>) -> Option<u64> {
+        fee_estimates
+            .range(..=self.target())
+            .next_back()
+            .or_else(|| fee_estimates.iter().next())
+            .map(|(_, feerate)| *feerate)
+    }
+}
+
+impl std::fmt::Display for FeeratePreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NextBlock => write!(f, "Next block"),
+            Self::HalfHour => write!(f, "30 min"),
+            Self::Hour => write!(f, "1 hour"),
+            Self::Economy => write!(f, "Economy"),
+        }
+    }
+}
+
+/// A child transaction paying for its unconfirmed ancestors (CPFP), along with them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpfpPackage {
+    pub child_txid: Txid,
+    pub child_fee: Amount,
+    /// Maximum virtual size of the child once signed.
+    pub child_vsize: u64,
+    pub ancestors_fee: Amount,
+    pub ancestors_vsize: u64,
+}
+
+impl CpfpPackage {
+    /// The feerate of the ancestors in sat/vb, or `None` if they aren't in the mempool.
+    pub fn ancestors_feerate(&self) -> Option<u64> {
+        self.ancestors_fee
+            .to_sat()
+            .checked_div(self.ancestors_vsize)
+    }
+
+    /// The feerate of the child alone in sat/vb.
+    pub fn child_feerate(&self) -> u64 {
+        self.child_fee.to_sat() / self.child_vsize.max(1)
+    }
+
+    /// The feerate of the child and its ancestors together in sat/vb.
+    pub fn package_feerate(&self) -> u64 {
+        (self.ancestors_fee + self.child_fee).to_sat()
+            / (self.ancestors_vsize + self.child_vsize).max(1)
+    }
+}
+
+/// A replacement paying a higher fee for an unconfirmed transaction (RBF), before it is stored.
+#[derive(Debug, Clone)]
+pub struct RbfPreview {
+    pub psbt: Psbt,
+    pub fee: Amount,
+    /// Feerate of the replacement in sat/vb, once signed.
+    pub feerate_vb: u64,
+    /// The coins spent by the replacement in addition to the ones of the replaced transaction.
+    pub added_inputs: Vec<(OutPoint, Amount)>,
+    /// The change of the replacement, if any.
+    pub change: Option<Amount>,
+}
+
+impl RbfPreview {
+    /// The preview of the replacement of `previous_tx`. `None` if the value of an input of the
+    /// replacement is missing from the PSBT.
+    pub fn new(
+        psbt: Psbt,
+        previous_tx: &Transaction,
+        change_indexes: &[usize],
+        max_vbytes: u64,
+    ) -> Option<Self> {
+        let mut inputs_amount = Amount::ZERO;
+        let mut added_inputs = Vec::new();
+        for (txin, input) in psbt.unsigned_tx.input.iter().zip(psbt.inputs.iter()) {
+            let value = input.witness_utxo.as_ref()?.value;
+            inputs_amount += value;
+            if !previous_tx
+                .input
+                .iter()
+                .any(|prev| prev.previous_output == txin.previous_output)
+            {
+                added_inputs.push((txin.previous_output, value));
+            }
+        }
+        let outputs_amount: Amount = psbt.unsigned_tx.output.iter().map(|o| o.value).sum();
+        let fee = inputs_amount.checked_sub(outputs_amount)?;
+        let change = change_indexes
+            .iter()
+            .filter_map(|i| psbt.unsigned_tx.output.get(*i))
+            .map(|o| o.value)
+            .reduce(|a, b| a + b);
+        Some(Self {
+            fee,
+            feerate_vb: fee.to_sat() / max_vbytes.max(1),
+            added_inputs,
+            change,
+            psbt,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -457,13 +698,24 @@ impl Payment {
 }
 
 impl LabelsLoader for Payment {
-    fn load_labels(&mut self, new_labels: &HashMap<String, Option<String>>) {
-        if let Some(label) = self.address.as_ref().and_then(|addr| new_labels.get(addr)) {
-            self.address_label = label.clone();
+    fn load_labels(
+        &mut self,
+        new_labels: &HashMap<String, Option<String>>,
+    ) -> HashMap<String, Option<String>> {
+        let mut previous = HashMap::new();
+        if let Some(addr) = &self.address {
+            if let Some(label) = new_labels.get(addr) {
+                previous.insert(
+                    addr.clone(),
+                    std::mem::replace(&mut self.address_label, label.clone()),
+                );
+            }
         }
-        if let Some(label) = new_labels.get(&self.outpoint.to_string()) {
-            self.label = label.clone();
+        let outpoint = self.outpoint.to_string();
+        if let Some(label) = new_labels.get(&outpoint) {
+            previous.insert(outpoint, std::mem::replace(&mut self.label, label.clone()));
         }
+        previous
     }
 }
 
@@ -555,25 +807,404 @@ pub trait Labelled {
 }
 
 pub trait LabelsLoader {
-    fn load_labels(&mut self, new_labels: &HashMap<String, Option<String>>);
+    /// Set the labels of the items we have among the given ones, a `None` label removing it.
+    /// Returns the labels these items had before.
+    fn load_labels(
+        &mut self,
+        new_labels: &HashMap<String, Option<String>>,
+    ) -> HashMap<String, Option<String>>;
 }
 
 impl<T: ?Sized> LabelsLoader for T
 where
     T: Labelled,
 {
-    fn load_labels(&mut self, new_labels: &HashMap<String, Option<String>>) {
+    fn load_labels(
+        &mut self,
+        new_labels: &HashMap<String, Option<String>>,
+    ) -> HashMap<String, Option<String>> {
         let items = self.labelled();
         let labels = self.labels();
+        let mut previous = HashMap::new();
         for item in items {
             let item_str = item.to_string();
             if let Some(label) = new_labels.get(&item_str) {
-                if let Some(l) = label {
-                    labels.insert(item_str, l.to_string());
+                let old = if let Some(l) = label {
+                    labels.insert(item_str.clone(), l.to_string())
                 } else {
-                    labels.remove(&item_str);
-                }
+                    labels.remove(&item_str)
+                };
+                // An item may be listed twice, for instance an address paid by two outputs.
+                previous.entry(item_str).or_insert(old);
             }
         }
+        previous
+    }
+}
+
+/// Approximate number of blocks mined in a day.
+pub const BLOCKS_PER_DAY: u32 = 144;
+
+/// When a recovery path becomes available for the unspent coins of the wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryPathExpiry {
+    pub timelock: u16,
+    /// The earliest height at which one of the coins is spendable through this path.
+    pub earliest_height: i32,
+    /// For each warning window in days, from the largest, the number and total value of the
+    /// coins which are spendable through this path within the window.
+    pub windows: Vec<(u32, usize, Amount)>,
+    /// The coins which are spendable through this path within the largest window.
+    pub at_risk_coins: Vec<OutPoint>,
+}
+
+/// Summarize, per recovery path, when it becomes available for the unspent confirmed coins
+/// using the recovery heights reported by the daemon.
+pub fn recovery_path_expiries(
+    coins: &[Coin],
+    tip_height: i32,
+    warning_days: &[u32],
+) -> Vec<RecoveryPathExpiry> {
+    let mut warning_days = warning_days.to_vec();
+    warning_days.sort_unstable_by(|a, b| b.cmp(a));
+    warning_days.dedup();
+    let within = |height: i32, days: u32| {
+        i64::from(height) - i64::from(tip_height) <= i64::from(days * BLOCKS_PER_DAY)
+    };
+
+    let mut heights = BTreeMap::<u16, Vec<(&Coin, i32)>>::new();
+    for coin in coins.iter().filter(|coin| coin.spend_info.is_none()) {
+        for recovery in &coin.recovery_heights {
+            heights
+                .entry(recovery.timelock)
+                .or_default()
+                .push((coin, recovery.height));
+        }
+    }
+    heights
+        .into_iter()
+        .filter_map(|(timelock, coins)| {
+            let earliest_height = coins.iter().map(|(_, height)| *height).min()?;
+            let windows = warning_days
+                .iter()
+                .map(|days| {
+                    coins
+                        .iter()
+                        .filter(|(_, height)| within(*height, *days))
+                        .fold((*days, 0, Amount::ZERO), |(days, n, value), (coin, _)| {
+                            (days, n + 1, value + coin.amount)
+                        })
+                })
+                .collect();
+            let at_risk_coins = warning_days
+                .first()
+                .map(|days| {
+                    coins
+                        .iter()
+                        .filter(|(_, height)| within(*height, *days))
+                        .map(|(coin, _)| coin.outpoint)
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(RecoveryPathExpiry {
+                timelock,
+                earliest_height,
+                windows,
+                at_risk_coins,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_recovery_path_expiries() {
+        let dummy_address = Address::from_str("bc1qvrl2849aggm6qry9ea7xqp2kk39j8vaa8r3cwg")
+            .unwrap()
+            .assume_checked();
+        let dummy_txid =
+            Txid::from_str("f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5")
+                .unwrap();
+        let coin = |vout: u32, sat: u64, heights: &[(u16, i32)]| Coin {
+            outpoint: OutPoint::new(dummy_txid, vout),
+            amount: Amount::from_sat(sat),
+            address: dummy_address.clone(),
+            derivation_index: liana::miniscript::bitcoin::bip32::ChildNumber::Normal {
+                index: vout,
+            },
+            block_height: Some(1),
+            is_immature: false,
+            is_change: false,
+            is_from_self: false,
+            spend_info: None,
+            recovery_heights: heights
+                .iter()
+                .map(|(timelock, height)| RecoveryHeight {
+                    timelock: *timelock,
+                    height: *height,
+                })
+                .collect(),
+            tag: None,
+        };
+        let tip = 800_000;
+        // No coin, or only unconfirmed ones.
+        assert!(recovery_path_expiries(&[], tip, &[30, 7, 1]).is_empty());
+        assert!(recovery_path_expiries(&[coin(0, 1_000, &[])], tip, &[30, 7, 1]).is_empty());
+
+        let mut coins = vec![
+            // Within a day for the first path, within 7 days for the second.
+            coin(0, 1_000, &[(1_000, tip + 100), (2_000, tip + 1_000)]),
+            // Within 30 days for the first path, far away for the second.
+            coin(1, 2_000, &[(1_000, tip + 3_000), (2_000, tip + 50_000)]),
+            // Already available through the first path.
+            coin(2, 4_000, &[(1_000, tip - 10), (2_000, tip + 990)]),
+        ];
+        // A spent coin is ignored.
+        let mut spent = coin(3, 8_000, &[(1_000, tip), (2_000, tip)]);
+        spent.spend_info = Some(lianad::commands::LCSpendInfo {
+            txid: dummy_txid,
+            height: None,
+        });
+        coins.push(spent);
+
+        // Windows are ordered from the largest, whatever the settings order.
+        let expiries = recovery_path_expiries(&coins, tip, &[1, 30, 7]);
+        assert_eq!(
+            expiries,
+            vec![
+                RecoveryPathExpiry {
+                    timelock: 1_000,
+                    earliest_height: tip - 10,
+                    windows: vec![
+                        (30, 3, Amount::from_sat(7_000)),
+                        (7, 2, Amount::from_sat(5_000)),
+                        (1, 2, Amount::from_sat(5_000)),
+                    ],
+                    at_risk_coins: vec![
+                        OutPoint::new(dummy_txid, 0),
+                        OutPoint::new(dummy_txid, 1),
+                        OutPoint::new(dummy_txid, 2),
+                    ],
+                },
+                RecoveryPathExpiry {
+                    timelock: 2_000,
+                    earliest_height: tip + 990,
+                    windows: vec![
+                        (30, 2, Amount::from_sat(5_000)),
+                        (7, 2, Amount::from_sat(5_000)),
+                        (1, 0, Amount::ZERO),
+                    ],
+                    at_risk_coins: vec![OutPoint::new(dummy_txid, 0), OutPoint::new(dummy_txid, 2)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn mempool_position() {
+        let estimates: BTreeMap<u16, u64> = [(1, 20), (3, 12), (6, 8), (144, 2)]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(MempoolPosition::new(10, &BTreeMap::new()), None);
+        assert_eq!(
+            MempoolPosition::new(25, &estimates),
+            Some(MempoolPosition::NextBlock)
+        );
+        assert_eq!(
+            MempoolPosition::new(20, &estimates),
+            Some(MempoolPosition::NextBlock)
+        );
+        assert_eq!(
+            MempoolPosition::new(19, &estimates),
+            Some(MempoolPosition::WithinFewBlocks)
+        );
+        assert_eq!(
+            MempoolPosition::new(12, &estimates),
+            Some(MempoolPosition::WithinFewBlocks)
+        );
+        assert_eq!(
+            MempoolPosition::new(8, &estimates),
+            Some(MempoolPosition::MayTakeHours)
+        );
+        assert_eq!(
+            MempoolPosition::new(2, &estimates),
+            Some(MempoolPosition::MayTakeHours)
+        );
+        assert_eq!(
+            MempoolPosition::new(1, &estimates),
+            Some(MempoolPosition::BelowMinimum)
+        );
+
+        // Missing targets are skipped.
+        let estimates: BTreeMap<u16, u64> = [(6, 8), (144, 2)].iter().copied().collect();
+        assert_eq!(
+            MempoolPosition::new(100, &estimates),
+            Some(MempoolPosition::MayTakeHours)
+        );
+        let estimates: BTreeMap<u16, u64> = [(3, 12)].iter().copied().collect();
+        assert_eq!(
+            MempoolPosition::new(12, &estimates),
+            Some(MempoolPosition::WithinFewBlocks)
+        );
+        assert_eq!(
+            MempoolPosition::new(11, &estimates),
+            Some(MempoolPosition::BelowMinimum)
+        );
+
+        // Estimates that are not monotonic still give the most urgent target met.
+        let estimates: BTreeMap<u16, u64> = [(1, 10), (3, 15), (144, 1)].iter().copied().collect();
+        assert_eq!(
+            MempoolPosition::new(12, &estimates),
+            Some(MempoolPosition::NextBlock)
+        );
+    }
+
+    #[test]
+    fn feerate_presets() {
+        let estimates: BTreeMap<u16, u64> = [(1, 20), (3, 12), (6, 8), (144, 2)]
+            .iter()
+            .copied()
+            .collect();
+        let feerates: Vec<_> = FeeratePreset::ALL
+            .iter()
+            .map(|p| p.feerate(&estimates))
+            .collect();
+        assert_eq!(feerates, vec![Some(20), Some(12), Some(8), Some(2)]);
+        assert_eq!(FeeratePreset::Hour.feerate(&BTreeMap::new()), None);
+
+        // Missing targets use the closest more urgent one, or the most urgent available.
+        let estimates: BTreeMap<u16, u64> = [(3, 12), (144, 2)].iter().copied().collect();
+        assert_eq!(FeeratePreset::NextBlock.feerate(&estimates), Some(12));
+        assert_eq!(FeeratePreset::Hour.feerate(&estimates), Some(12));
+        assert_eq!(FeeratePreset::Economy.feerate(&estimates), Some(2));
+
+        assert_eq!(confirmation_target(10, &estimates), Some(144));
+        assert_eq!(confirmation_target(1, &estimates), None);
+    }
+
+    #[test]
+    fn rbf_preview() {
+        use liana::miniscript::bitcoin::{
+            absolute, psbt, transaction, ScriptBuf, Sequence, TxIn, TxOut, Witness,
+        };
+
+        let txin = |vout| TxIn {
+            previous_output: OutPoint::new(
+                Txid::from_str("f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5")
+                    .unwrap(),
+                vout,
+            ),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::new(),
+        };
+        let txout = |sats| TxOut {
+            value: Amount::from_sat(sats),
+            script_pubkey: ScriptBuf::new(),
+        };
+        let previous_tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![txin(0)],
+            output: vec![txout(50_000), txout(9_000)],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![txin(0), txin(1)],
+            output: vec![txout(50_000), txout(12_000)],
+        })
+        .unwrap();
+        psbt.inputs = vec![
+            psbt::Input {
+                witness_utxo: Some(txout(60_000)),
+                ..Default::default()
+            },
+            psbt::Input {
+                witness_utxo: Some(txout(5_000)),
+                ..Default::default()
+            },
+        ];
+        let preview = RbfPreview::new(psbt.clone(), &previous_tx, &[1], 200).unwrap();
+        assert_eq!(preview.fee, Amount::from_sat(3_000));
+        assert_eq!(preview.feerate_vb, 15);
+        assert_eq!(
+            preview.added_inputs,
+            vec![(txin(1).previous_output, Amount::from_sat(5_000))]
+        );
+        assert_eq!(preview.change, Some(Amount::from_sat(12_000)));
+
+        // Without change, nor the value of an input.
+        assert!(RbfPreview::new(psbt.clone(), &previous_tx, &[], 200)
+            .unwrap()
+            .change
+            .is_none());
+        psbt.inputs[1].witness_utxo = None;
+        assert!(RbfPreview::new(psbt, &previous_tx, &[1], 200).is_none());
+    }
+
+    #[test]
+    fn cpfp_package_feerates() {
+        let mut package = CpfpPackage {
+            child_txid: Txid::from_str(
+                "f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5",
+            )
+            .unwrap(),
+            child_fee: Amount::from_sat(2_900),
+            child_vsize: 110,
+            ancestors_fee: Amount::from_sat(200),
+            ancestors_vsize: 200,
+        };
+        assert_eq!(package.ancestors_feerate(), Some(1));
+        assert_eq!(package.child_feerate(), 26);
+        assert_eq!(package.package_feerate(), 10);
+
+        // The parent left the mempool, the child pays for itself only.
+        package.ancestors_fee = Amount::ZERO;
+        package.ancestors_vsize = 0;
+        assert_eq!(package.ancestors_feerate(), None);
+        assert_eq!(package.package_feerate(), 26);
+    }
+
+    #[test]
+    fn payment_labels_rollback() {
+        let outpoint = OutPoint::new(
+            Txid::from_str("f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5")
+                .unwrap(),
+            1,
+        );
+        let address = "bc1qvrl2849aggm6qry9ea7xqp2kk39j8vaa8r3cwg".to_string();
+        let mut payment = Payment {
+            label: Some("rent".to_string()),
+            address: Some(address.clone()),
+            address_label: None,
+            amount: Amount::from_sat(10_000),
+            outpoint,
+            time: None,
+            kind: PaymentKind::Outgoing,
+        };
+
+        // The labels the payment had are returned, so they can be restored.
+        let new_labels: HashMap<String, Option<String>> = [
+            (outpoint.to_string(), None),
+            (address.clone(), Some("landlord".to_string())),
+        ]
+        .into_iter()
+        .collect();
+        let previous = payment.load_labels(&new_labels);
+        assert_eq!(payment.label, None);
+        assert_eq!(payment.address_label, Some("landlord".to_string()));
+        assert_eq!(previous.len(), 2);
+        assert_eq!(previous[&outpoint.to_string()], Some("rent".to_string()));
+        assert_eq!(previous[&address], None);
+
+        payment.load_labels(&previous);
+        assert_eq!(payment.label, Some("rent".to_string()));
+        assert_eq!(payment.address_label, None);
     }
 }
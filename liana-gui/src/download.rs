@@ -1,18 +1,49 @@
 // This is based on https://github.com/iced-rs/iced/blob/master/examples/download_progress/src/download.rs
-// with some modifications to store the downloaded bytes in `Progress::Finished` and `State::Downloading`
-// and to keep track of any download errors.
+// with some modifications to keep track of any download errors, to persist the downloaded bytes
+// so that an interrupted download can be resumed and to verify the hash of the downloaded file.
+use bitcoin_hashes::{sha256, Hash as _};
 use iced::subscription;
+use reqwest::{header, StatusCode};
+use tracing::{info, warn};
 
-use std::hash::Hash;
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    hash::Hash,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-// Just a little utility function
+/// How many times an interrupted download is resumed before giving up.
+const MAX_RETRIES: u32 = 10;
+
+/// How long to wait before resuming an interrupted download.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Download the file at `url`, checking it hashes to `sha256`.
+///
+/// The downloaded bytes are written to `path` as they are received, along with the expected size
+/// of the file, so that an interrupted download is resumed from where it stopped, if the server
+/// supports range requests. The partial file is removed once the download is complete.
 pub fn file<I: 'static + Hash + Copy + Send + Sync, T: ToString>(
     id: I,
     url: T,
+    path: PathBuf,
+    sha256: sha256::Hash,
 ) -> iced::Subscription<(I, Progress)> {
-    subscription::unfold(id, State::Ready(url.to_string()), move |state| {
-        download(id, state)
-    })
+    subscription::unfold(
+        id,
+        State::Ready {
+            target: Target {
+                url: url.to_string(),
+                path,
+                sha256,
+            },
+            retries: 0,
+        },
+        move |state| download(id, state),
+    )
 }
 
 #[derive(Debug, Hash, Clone)]
@@ -26,6 +57,8 @@ pub struct Download<I> {
 pub enum DownloadError {
     UnknownContentLength,
     RequestError(String),
+    Io(String),
+    HashMismatch,
 }
 
 impl std::fmt::Display for DownloadError {
@@ -37,74 +70,302 @@ impl std::fmt::Display for DownloadError {
             Self::RequestError(e) => {
                 write!(f, "Request error: '{}'.", e)
             }
+            Self::Io(e) => {
+                write!(f, "Error writing the downloaded file: '{}'.", e)
+            }
+            Self::HashMismatch => {
+                write!(f, "Downloaded file hash does not match the expected value.")
+            }
         }
     }
 }
 
+/// The file to download and where to store it meanwhile.
+#[derive(Debug, Clone)]
+pub struct Target {
+    url: String,
+    path: PathBuf,
+    sha256: sha256::Hash,
+}
+
+/// The file storing the expected size of the partially downloaded file at `path`.
+fn total_path(path: &Path) -> PathBuf {
+    let mut total_path = OsString::from(path.as_os_str());
+    total_path.push(".total");
+    PathBuf::from(total_path)
+}
+
+/// The size of the partially downloaded file and its expected size, if known. A partial file
+/// whose expected size is unknown cannot be resumed.
+fn partial(path: &Path) -> (u64, Option<u64>) {
+    let total = fs::read_to_string(total_path(path))
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok());
+    let downloaded = match total {
+        Some(_) => fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        None => 0,
+    };
+    (downloaded, total)
+}
+
+fn remove_partial(path: &Path) {
+    for p in &[path.to_path_buf(), total_path(path)] {
+        if p.exists() {
+            if let Err(e) = fs::remove_file(p) {
+                warn!("Failed to remove '{}': {}", p.to_string_lossy(), e);
+            }
+        }
+    }
+}
+
+/// The first and last positions of the range returned by the server and the complete size of
+/// the file, from the `Content-Range` header: `bytes <start>-<end>/<size>`.
+fn content_range(response: &reqwest::Response) -> Option<(u64, u64, u64)> {
+    let value = response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?, size.parse().ok()?))
+}
+
+/// Open the partial file to start the download from scratch, recording the size of the file.
+fn create_partial(path: &Path, total: u64) -> Result<File, DownloadError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| DownloadError::Io(e.to_string()))?;
+    }
+    fs::write(total_path(path), total.to_string()).map_err(|e| DownloadError::Io(e.to_string()))?;
+    File::create(path).map_err(|e| DownloadError::Io(e.to_string()))
+}
+
+/// Retry the download later, unless it was retried too many times already.
+fn retry<I>(id: I, target: Target, retries: u32, error: String) -> ((I, Progress), State) {
+    if retries >= MAX_RETRIES {
+        return (
+            (id, Progress::Errored(DownloadError::RequestError(error))),
+            State::Finished,
+        );
+    }
+    warn!("Download interrupted, resuming it: {}", error);
+    let (downloaded, total) = partial(&target.path);
+    (
+        (
+            id,
+            Progress::Advanced(Transferred {
+                downloaded,
+                total: total.unwrap_or(0),
+                retries: retries + 1,
+            }),
+        ),
+        State::Ready {
+            target,
+            retries: retries + 1,
+        },
+    )
+}
+
+/// Verify the hash of the complete file, and hand over its content.
+fn finish<I>(id: I, target: &Target) -> ((I, Progress), State) {
+    let res = fs::read(&target.path).map_err(|e| DownloadError::Io(e.to_string()));
+    // Either the file is complete, or it can't be trusted and must be downloaded again.
+    remove_partial(&target.path);
+    let progress = match res {
+        Ok(bytes) => {
+            let hash = sha256::Hash::hash(&bytes);
+            info!("Download hash: '{}'.", hash);
+            if hash == target.sha256 {
+                Progress::Finished(bytes)
+            } else {
+                Progress::Errored(DownloadError::HashMismatch)
+            }
+        }
+        Err(e) => Progress::Errored(e),
+    };
+    ((id, progress), State::Finished)
+}
+
 async fn download<I: Copy>(id: I, state: State) -> ((I, Progress), State) {
     match state {
-        State::Ready(url) => {
-            let response = reqwest::get(&url).await;
+        State::Ready { target, retries } => {
+            if retries > 0 {
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
 
-            match response {
-                Ok(response) => {
-                    if let Some(total) = response.content_length() {
-                        (
-                            (id, Progress::Started),
-                            State::Downloading {
-                                response,
-                                total,
-                                downloaded: 0,
-                                bytes: Vec::new(),
-                            },
+            let (downloaded, total) = partial(&target.path);
+            if total == Some(downloaded) {
+                return finish(id, &target);
+            }
+
+            let mut request = reqwest::Client::new().get(&target.url);
+            if downloaded > 0 {
+                request = request.header(header::RANGE, format!("bytes={}-", downloaded));
+            }
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(e) => return retry(id, target, retries, e.to_string()),
+            };
+
+            let status = response.status();
+            if downloaded > 0 && status == StatusCode::PARTIAL_CONTENT {
+                match (content_range(&response), total) {
+                    (Some((start, _, size)), Some(total))
+                        if start == downloaded && size == total =>
+                    {
+                        info!("Resuming download from byte {} of {}", downloaded, total);
+                        match OpenOptions::new().append(true).open(&target.path) {
+                            Ok(file) => (
+                                (
+                                    id,
+                                    Progress::Advanced(Transferred {
+                                        downloaded,
+                                        total,
+                                        retries,
+                                    }),
+                                ),
+                                State::Downloading {
+                                    target,
+                                    response,
+                                    file,
+                                    total,
+                                    downloaded,
+                                    retries,
+                                },
+                            ),
+                            Err(e) => (
+                                (id, Progress::Errored(DownloadError::Io(e.to_string()))),
+                                State::Finished,
+                            ),
+                        }
+                    }
+                    _ => {
+                        // The file may have changed on the server, start over.
+                        remove_partial(&target.path);
+                        retry(
+                            id,
+                            target,
+                            retries,
+                            "Unexpected range in response".to_string(),
                         )
-                    } else {
-                        (
+                    }
+                }
+            } else if status == StatusCode::RANGE_NOT_SATISFIABLE {
+                remove_partial(&target.path);
+                retry(
+                    id,
+                    target,
+                    retries,
+                    "Partial download does not match the file".to_string(),
+                )
+            } else if status.is_success() {
+                // The server sends the whole file, either because nothing was downloaded yet or
+                // because it does not support range requests.
+                if downloaded > 0 {
+                    info!("Server does not support resuming the download, starting over");
+                }
+                let total = match response.content_length() {
+                    Some(total) => total,
+                    None => {
+                        return (
                             (id, Progress::Errored(DownloadError::UnknownContentLength)),
                             State::Finished,
                         )
                     }
+                };
+                match create_partial(&target.path, total) {
+                    Ok(file) => (
+                        (
+                            id,
+                            if retries == 0 {
+                                Progress::Started
+                            } else {
+                                Progress::Advanced(Transferred {
+                                    downloaded: 0,
+                                    total,
+                                    retries,
+                                })
+                            },
+                        ),
+                        State::Downloading {
+                            target,
+                            response,
+                            file,
+                            total,
+                            downloaded: 0,
+                            retries,
+                        },
+                    ),
+                    Err(e) => ((id, Progress::Errored(e)), State::Finished),
                 }
-                Err(e) => (
+            } else {
+                (
                     (
                         id,
-                        Progress::Errored(DownloadError::RequestError(e.to_string())),
+                        Progress::Errored(DownloadError::RequestError(status.to_string())),
                     ),
                     State::Finished,
-                ),
+                )
             }
         }
         State::Downloading {
+            target,
             mut response,
+            mut file,
             total,
             downloaded,
-            mut bytes,
+            retries,
         } => match response.chunk().await {
             Ok(Some(chunk)) => {
+                if let Err(e) = file.write_all(&chunk) {
+                    return (
+                        (id, Progress::Errored(DownloadError::Io(e.to_string()))),
+                        State::Finished,
+                    );
+                }
                 let downloaded = downloaded + chunk.len() as u64;
-
-                let percentage = (downloaded as f32 / total as f32) * 100.0;
-
-                bytes.append(&mut chunk.to_vec());
-
                 (
-                    (id, Progress::Advanced(percentage)),
+                    (
+                        id,
+                        Progress::Advanced(Transferred {
+                            downloaded,
+                            total,
+                            retries,
+                        }),
+                    ),
                     State::Downloading {
+                        target,
                         response,
+                        file,
                         total,
                         downloaded,
-                        bytes,
+                        retries,
                     },
                 )
             }
-            Ok(None) => ((id, Progress::Finished(bytes)), State::Finished),
-            Err(e) => (
-                (
-                    id,
-                    Progress::Errored(DownloadError::RequestError(e.to_string())),
-                ),
-                State::Finished,
-            ),
+            Ok(None) => {
+                if let Err(e) = file.flush() {
+                    return (
+                        (id, Progress::Errored(DownloadError::Io(e.to_string()))),
+                        State::Finished,
+                    );
+                }
+                drop(file);
+                if downloaded < total {
+                    retry(
+                        id,
+                        target,
+                        retries,
+                        "Connection closed before the end of the file".to_string(),
+                    )
+                } else {
+                    finish(id, &target)
+                }
+            }
+            Err(e) => {
+                drop(file);
+                retry(id, target, retries, e.to_string())
+            }
         },
         State::Finished => {
             // We do not let the stream die, as it would start a
@@ -115,21 +376,206 @@ async fn download<I: Copy>(id: I, state: State) -> ((I, Progress), State) {
     }
 }
 
+/// How much of the file was downloaded, and how many times the download was resumed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Transferred {
+    pub downloaded: u64,
+    pub total: u64,
+    pub retries: u32,
+}
+
+impl Transferred {
+    pub fn percentage(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.downloaded as f32 / self.total as f32) * 100.0
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Progress {
     Started,
-    Advanced(f32),
+    Advanced(Transferred),
     Finished(Vec<u8>),
     Errored(DownloadError),
 }
 
 pub enum State {
-    Ready(String),
+    Ready {
+        target: Target,
+        retries: u32,
+    },
     Downloading {
+        target: Target,
         response: reqwest::Response,
+        file: File,
         total: u64,
         downloaded: u64,
-        bytes: Vec<u8>,
+        retries: u32,
     },
     Finished,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader},
+        net::{Shutdown, TcpListener},
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    /// Serve `body` on a local port. The first connection is killed once `cut_at` bytes of the
+    /// body were sent. The start position of the range requested by each connection is recorded.
+    fn serve(
+        body: Vec<u8>,
+        honor_ranges: bool,
+        cut_at: Option<usize>,
+    ) -> (String, Arc<Mutex<Vec<Option<u64>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/bitcoin.tar.gz", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let requested = ranges.clone();
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut range = None;
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("range: bytes=") {
+                        range = value.trim().trim_end_matches('-').parse::<u64>().ok();
+                    }
+                }
+                requested.lock().unwrap().push(range);
+
+                let start = if honor_ranges {
+                    range.unwrap_or(0) as usize
+                } else {
+                    0
+                };
+                let head = if honor_ranges && range.is_some() {
+                    format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                        body.len() - start,
+                        start,
+                        body.len() - 1,
+                        body.len()
+                    )
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    )
+                };
+                stream.write_all(head.as_bytes()).unwrap();
+                let end = match cut_at {
+                    Some(cut) if i == 0 => cut,
+                    _ => body.len(),
+                };
+                stream.write_all(&body[start..end]).unwrap();
+                stream.flush().unwrap();
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        });
+        (url, ranges)
+    }
+
+    fn partial_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "liana-download-{}-{}.part",
+            name,
+            std::process::id()
+        ))
+    }
+
+    /// Run the download to completion, returning the last progress and the retries it reported.
+    async fn run(url: String, path: PathBuf, sha256: sha256::Hash) -> (Progress, u32) {
+        let mut state = State::Ready {
+            target: Target { url, path, sha256 },
+            retries: 0,
+        };
+        let mut retries = 0;
+        loop {
+            let ((_, progress), next) = download(0, state).await;
+            match progress {
+                Progress::Finished(_) | Progress::Errored(_) => return (progress, retries),
+                Progress::Advanced(transferred) => retries = transferred.retries,
+                Progress::Started => {}
+            }
+            state = next;
+        }
+    }
+
+    fn body() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn resume_interrupted_download() {
+        let body = body();
+        let (url, ranges) = serve(body.clone(), true, Some(40_000));
+        let path = partial_path("resume");
+        let (progress, retries) = run(url, path.clone(), sha256::Hash::hash(&body)).await;
+        match progress {
+            Progress::Finished(bytes) => assert_eq!(bytes, body),
+            p => panic!("Unexpected progress: {:?}", p),
+        }
+        assert_eq!(retries, 1);
+        assert_eq!(*ranges.lock().unwrap(), vec![None, Some(40_000)]);
+        assert!(!path.exists());
+        assert!(!total_path(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn restart_without_range_support() {
+        let body = body();
+        let (url, ranges) = serve(body.clone(), false, Some(40_000));
+        let path = partial_path("restart");
+        let (progress, _) = run(url, path.clone(), sha256::Hash::hash(&body)).await;
+        match progress {
+            Progress::Finished(bytes) => assert_eq!(bytes, body),
+            p => panic!("Unexpected progress: {:?}", p),
+        }
+        // The range was requested, but the whole file was sent again.
+        assert_eq!(*ranges.lock().unwrap(), vec![None, Some(40_000)]);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn resume_persisted_partial() {
+        let body = body();
+        let (url, ranges) = serve(body.clone(), true, None);
+        let path = partial_path("persisted");
+        fs::write(&path, &body[..60_000]).unwrap();
+        fs::write(total_path(&path), body.len().to_string()).unwrap();
+        let (progress, retries) = run(url, path.clone(), sha256::Hash::hash(&body)).await;
+        match progress {
+            Progress::Finished(bytes) => assert_eq!(bytes, body),
+            p => panic!("Unexpected progress: {:?}", p),
+        }
+        assert_eq!(retries, 0);
+        assert_eq!(*ranges.lock().unwrap(), vec![Some(60_000)]);
+    }
+
+    #[tokio::test]
+    async fn hash_mismatch() {
+        let body = body();
+        let (url, _) = serve(body, true, None);
+        let path = partial_path("mismatch");
+        let (progress, _) = run(url, path.clone(), sha256::Hash::hash(b"not bitcoind")).await;
+        match progress {
+            Progress::Errored(e) => assert_eq!(e, DownloadError::HashMismatch),
+            p => panic!("Unexpected progress: {:?}", p),
+        }
+        assert!(!path.exists());
+        assert!(!total_path(&path).exists());
+    }
+}
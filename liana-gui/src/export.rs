@@ -12,6 +12,11 @@ use std::{
 
 use chrono::{DateTime, Duration, Utc};
 use liana::miniscript::bitcoin::{Amount, Txid};
+use liana_ui::{component::policy_diagram::PolicyDiagram, font};
+use resvg::{
+    tiny_skia,
+    usvg::{self, TreeParsing, TreeTextToPath},
+};
 use tokio::{
     task::{JoinError, JoinHandle},
     time::sleep,
@@ -91,6 +96,7 @@ pub enum Error {
     NoParentDir,
     Daemon(String),
     TxTimeMissing,
+    Png(String),
 }
 
 impl From<JoinError> for Error {
@@ -391,3 +397,46 @@ pub async fn get_path() -> Option<PathBuf> {
         .await
         .map(|fh| fh.path().to_path_buf())
 }
+
+/// Let the user choose where to save the policy diagram and write it there as a PNG image.
+/// Returns `None` if the user didn't choose any location.
+pub async fn export_policy_diagram(diagram: PolicyDiagram) -> Result<Option<PathBuf>, Error> {
+    let date = chrono::Local::now().format("%Y-%m-%dT%H-%M-%S");
+    let file_name = format!("liana-policy-{date}.png");
+    let path = match rfd::AsyncFileDialog::new()
+        .set_title("Choose a location to export...")
+        .set_file_name(file_name)
+        .save_file()
+        .await
+    {
+        Some(fh) => fh.path().to_path_buf(),
+        None => return Ok(None),
+    };
+    let png = policy_diagram_png(&diagram)?;
+    fs::write(&path, png)?;
+    Ok(Some(path))
+}
+
+/// Render the policy diagram as a PNG image, at twice its size on screen so it stays sharp
+/// when printed.
+pub fn policy_diagram_png(diagram: &PolicyDiagram) -> Result<Vec<u8>, Error> {
+    const SCALE: f32 = 2.0;
+    let mut tree = usvg::Tree::from_str(&diagram.to_svg(), &usvg::Options::default())
+        .map_err(|e| Error::Png(e.to_string()))?;
+    let mut fonts = usvg::fontdb::Database::new();
+    fonts.load_font_data(font::REGULAR_BYTES.to_vec());
+    fonts.load_font_data(font::BOLD_BYTES.to_vec());
+    tree.convert_text(&fonts);
+
+    let size = diagram.size();
+    let mut pixmap = tiny_skia::Pixmap::new(
+        (size.width * SCALE).ceil() as u32,
+        (size.height * SCALE).ceil() as u32,
+    )
+    .ok_or_else(|| Error::Png("Invalid image size".to_string()))?;
+    resvg::Tree::from_usvg(&tree).render(
+        tiny_skia::Transform::from_scale(SCALE, SCALE),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(|e| Error::Png(e.to_string()))
+}
@@ -3,6 +3,7 @@ use std::{
     collections::HashMap,
     path::PathBuf,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use crate::app::{settings, wallet::Wallet};
@@ -18,9 +19,7 @@ use tracing::{debug, warn};
 
 #[derive(Debug, Clone)]
 pub enum UnsupportedReason {
-    Version {
-        minimal_supported_version: &'static str,
-    },
+    Version { minimal_supported_version: String },
     Method(&'static str),
     NotPartOfWallet(Fingerprint),
     WrongNetwork,
@@ -114,6 +113,38 @@ impl HardwareWallet {
     }
 }
 
+/// Whether Liana can request the device to display an address of the wallet.
+pub fn supports_address_display(kind: &DeviceKind) -> bool {
+    !matches!(kind, DeviceKind::Specter | DeviceKind::SpecterSimulator)
+}
+
+/// Status of the verification of an address on a signing device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationStatus {
+    /// Waiting for the user to confirm the address on the device.
+    Waiting,
+    Confirmed,
+    /// The user rejected the address, or the device failed to display it.
+    Rejected(String),
+    /// The device cannot display an address of a miniscript descriptor.
+    NotSupported,
+}
+
+/// Whether the device refused to use the wallet as it is not registered on it, or as the
+/// registration we have for it is outdated. A Ledger restored from its seed, or whose Bitcoin app
+/// was reinstalled, does not know about the previous registrations and rejects their HMAC.
+pub fn is_wallet_not_registered(kind: &DeviceKind, e: &HWIError) -> bool {
+    match e {
+        HWIError::MissingPolicy => true,
+        // The Bitcoin app reports a signature failure when checking the HMAC.
+        HWIError::Device(msg) => {
+            matches!(kind, DeviceKind::Ledger | DeviceKind::LedgerSimulator)
+                && msg.contains("SignatureFail")
+        }
+        _ => false,
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HardwareWalletConfig {
     pub kind: String,
@@ -137,19 +168,19 @@ impl HardwareWalletConfig {
     }
 }
 
+/// Interval between two detections of the connected devices.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Clone)]
 pub enum HardwareWalletMessage {
     Error(String),
-    List(ConnectedList),
+    /// The devices newly detected, replacing the ones with the same id.
+    Connected(Vec<HardwareWallet>),
+    /// The ids of the devices which were unplugged.
+    Disconnected(Vec<String>),
     Unlocked(String, Result<HardwareWallet, async_hwi::Error>),
 }
 
-#[derive(Debug, Clone)]
-pub struct ConnectedList {
-    pub new: Vec<HardwareWallet>,
-    still: Vec<String>,
-}
-
 pub struct HardwareWallets {
     network: Network,
     pub list: Vec<HardwareWallet>,
@@ -181,6 +212,13 @@ impl HardwareWallets {
         self
     }
 
+    /// Use the given version of the wallet, for instance once it was registered again on a
+    /// device.
+    pub fn set_wallet(&mut self, wallet: Arc<Wallet>) {
+        self.aliases.clone_from(&wallet.keys_aliases);
+        self.wallet = Some(wallet);
+    }
+
     pub fn set_alias(&mut self, fg: Fingerprint, new_alias: String) {
         // remove all (fingerprint, alias) with same alias.
         self.aliases.retain(|_, a| *a != new_alias);
@@ -218,9 +256,15 @@ impl HardwareWallets {
     ) -> Result<Command<HardwareWalletMessage>, async_hwi::Error> {
         match message {
             HardwareWalletMessage::Error(e) => Err(async_hwi::Error::Device(e)),
-            HardwareWalletMessage::List(ConnectedList { still, mut new }) => {
-                // remove disconnected
-                self.list.retain(|hw| still.contains(hw.id()));
+            HardwareWalletMessage::Disconnected(ids) => {
+                self.list.retain(|hw| !ids.contains(hw.id()));
+                Ok(Command::none())
+            }
+            HardwareWalletMessage::Connected(mut new) => {
+                // Unsupported devices are detected again at each refresh, as the reason may go
+                // away, for instance once the app is opened on the device.
+                self.list
+                    .retain(|hw| !new.iter().any(|new_hw| new_hw.id() == hw.id()));
                 self.list.append(&mut new);
                 let mut cmds = Vec::new();
                 for hw in &mut self.list {
@@ -348,13 +392,27 @@ impl HardwareWallets {
     }
 
     pub fn refresh(&self) -> iced::Subscription<HardwareWalletMessage> {
+        // The registration tokens are part of the identifier for the devices to be connected
+        // again with the fresh ones once the wallet was registered again.
+        let tokens: String = self
+            .wallet
+            .as_ref()
+            .map(|w| {
+                w.hardware_wallets
+                    .iter()
+                    .map(|cfg| cfg.token.as_str())
+                    .collect()
+            })
+            .unwrap_or_default();
         iced::subscription::unfold(
-            format!("refresh-{}", self.network),
+            format!("refresh-{}-{}", self.network, tokens),
             State {
                 network: self.network,
                 keys_aliases: self.aliases.clone(),
                 wallet: self.wallet.clone(),
                 connected_supported_hws: Vec::new(),
+                presence: Presence::default(),
+                pending: None,
                 api: None,
                 datadir_path: self.datadir_path.clone(),
             },
@@ -367,28 +425,104 @@ struct State {
     network: Network,
     keys_aliases: HashMap<Fingerprint, String>,
     wallet: Option<Arc<Wallet>>,
+    // The devices which are not opened again at each refresh.
     connected_supported_hws: Vec<String>,
+    presence: Presence,
+    // A message to emit without detecting the devices again.
+    pending: Option<HardwareWalletMessage>,
     api: Option<ledger::HidApi>,
     datadir_path: PathBuf,
 }
 
+/// The ids of the devices reported as connected. A device is reported as disconnected only once
+/// it is missing from two detections in a row, as a device busy with an operation, for instance
+/// a signature, may not be enumerated for a short while.
+#[derive(Debug, Default)]
+struct Presence {
+    known: Vec<String>,
+    missing: Vec<String>,
+}
+
+impl Presence {
+    /// Update with the ids of the devices found by a detection and return the ones which were
+    /// disconnected.
+    fn update(&mut self, found: &[String]) -> Vec<String> {
+        let (disconnected, missing): (Vec<String>, Vec<String>) = self
+            .known
+            .iter()
+            .filter(|id| !found.contains(id))
+            .cloned()
+            .partition(|id| self.missing.contains(id));
+        self.known = found.iter().chain(missing.iter()).cloned().collect();
+        self.missing = missing;
+        disconnected
+    }
+
+    fn is_missing(&self, id: &String) -> bool {
+        self.missing.contains(id)
+    }
+}
+
 async fn refresh(mut state: State) -> (HardwareWalletMessage, State) {
-    let api = if let Some(api) = &mut state.api {
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-        if let Err(e) = api.refresh_devices() {
-            return (HardwareWalletMessage::Error(e.to_string()), state);
+    if let Some(msg) = state.pending.take() {
+        return (msg, state);
+    }
+    // Only emit a message once a device was detected or disconnected.
+    loop {
+        let (new, still) = match detect(&mut state).await {
+            Ok(res) => res,
+            Err(e) => return (HardwareWalletMessage::Error(e), state),
         };
+        let found: Vec<String> = still
+            .iter()
+            .chain(new.iter().map(|hw| hw.id()))
+            .cloned()
+            .collect();
+        let disconnected = state.presence.update(&found);
+
+        // A device which is missing for the first time is still considered connected, in order
+        // not to open it again if it shows up at the next detection.
+        let missing: Vec<String> = state
+            .connected_supported_hws
+            .iter()
+            .filter(|id| state.presence.is_missing(id))
+            .cloned()
+            .collect();
+        state.connected_supported_hws = still
+            .into_iter()
+            .chain(missing)
+            .chain(new.iter().filter_map(|hw| match hw {
+                HardwareWallet::Locked { id, .. } => Some(id.clone()),
+                HardwareWallet::Supported { id, .. } => Some(id.clone()),
+                HardwareWallet::Unsupported { .. } => None,
+            }))
+            .collect();
+
+        let connected = if new.is_empty() {
+            None
+        } else {
+            Some(HardwareWalletMessage::Connected(new))
+        };
+        if !disconnected.is_empty() {
+            state.pending = connected;
+            return (HardwareWalletMessage::Disconnected(disconnected), state);
+        } else if let Some(msg) = connected {
+            return (msg, state);
+        }
+    }
+}
+
+/// Detect the connected devices, returning the ones newly connected along with the ids of the
+/// already connected ones which are still there. Devices which are already connected are not
+/// opened again, not to interfere with an ongoing operation.
+async fn detect(state: &mut State) -> Result<(Vec<HardwareWallet>, Vec<String>), String> {
+    let api = if let Some(api) = &mut state.api {
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+        api.refresh_devices().map_err(|e| e.to_string())?;
         api
     } else {
-        match ledger::HidApi::new() {
-            Ok(api) => {
-                state.api = Some(api);
-                state.api.as_mut().unwrap()
-            }
-            Err(e) => {
-                return (HardwareWalletMessage::Error(e.to_string()), state);
-            }
-        }
+        state.api = Some(ledger::HidApi::new().map_err(|e| e.to_string())?);
+        state.api.as_mut().unwrap()
     };
 
     let mut hws: Vec<HardwareWallet> = Vec::new();
@@ -579,14 +713,19 @@ async fn refresh(mut state: State) -> (HardwareWalletMessage, State) {
                         device.get_version().await,
                     ) {
                         (Ok(fingerprint), Ok(version)) => {
-                            if version
-                                >= (Version {
-                                    major: 6,
-                                    minor: 2,
-                                    patch: 1,
-                                    prerelease: None,
-                                })
+                            if let VersionStatus::UpdateRequired {
+                                minimal_supported_version,
+                            } = version_status(&DeviceKind::Coldcard, Some(&version))
                             {
+                                hws.push(HardwareWallet::Unsupported {
+                                    id,
+                                    kind: device.device_kind(),
+                                    version: Some(version),
+                                    reason: UnsupportedReason::Version {
+                                        minimal_supported_version,
+                                    },
+                                });
+                            } else {
                                 hws.push(HardwareWallet::Supported {
                                     id,
                                     device,
@@ -596,15 +735,6 @@ async fn refresh(mut state: State) -> (HardwareWalletMessage, State) {
                                     registered: None,
                                     alias: state.keys_aliases.get(&fingerprint).cloned(),
                                 });
-                            } else {
-                                hws.push(HardwareWallet::Unsupported {
-                                    id,
-                                    kind: device.device_kind(),
-                                    version: Some(version),
-                                    reason: UnsupportedReason::Version {
-                                        minimal_supported_version: "Edge firmware v6.2.1",
-                                    },
-                                });
                             }
                         }
                         _ => tracing::error!("Failed to connect to coldcard"),
@@ -671,19 +801,7 @@ async fn refresh(mut state: State) -> (HardwareWalletMessage, State) {
         }
     }
 
-    state.connected_supported_hws = still
-        .iter()
-        .chain(hws.iter().filter_map(|hw| match hw {
-            HardwareWallet::Locked { id, .. } => Some(id),
-            HardwareWallet::Supported { id, .. } => Some(id),
-            HardwareWallet::Unsupported { .. } => None,
-        }))
-        .cloned()
-        .collect();
-    (
-        HardwareWalletMessage::List(ConnectedList { new: hws, still }),
-        state,
-    )
+    Ok((hws, still))
 }
 
 async fn handle_ledger_device<'a, T: async_hwi::ledger::Transport + Sync + Send + 'static>(
@@ -697,7 +815,19 @@ async fn handle_ledger_device<'a, T: async_hwi::ledger::Transport + Sync + Send
         device.get_version().await,
     ) {
         (Ok(fingerprint), Ok(version)) => {
-            if ledger_version_supported(&version) {
+            if let VersionStatus::UpdateRequired {
+                minimal_supported_version,
+            } = version_status(&DeviceKind::Ledger, Some(&version))
+            {
+                Ok(HardwareWallet::Unsupported {
+                    id,
+                    kind: device.device_kind(),
+                    version: Some(version),
+                    reason: UnsupportedReason::Version {
+                        minimal_supported_version,
+                    },
+                })
+            } else {
                 let mut registered = false;
                 if let Some(w) = &wallet {
                     if let Some(cfg) = w
@@ -720,15 +850,6 @@ async fn handle_ledger_device<'a, T: async_hwi::ledger::Transport + Sync + Send
                     registered: Some(registered),
                     alias: keys_aliases.get(&fingerprint).cloned(),
                 })
-            } else {
-                Ok(HardwareWallet::Unsupported {
-                    id,
-                    kind: device.device_kind(),
-                    version: Some(version),
-                    reason: UnsupportedReason::Version {
-                        minimal_supported_version: "2.1.0",
-                    },
-                })
             }
         }
         (_, _) => Ok(HardwareWallet::Unsupported {
@@ -838,15 +959,103 @@ impl<'a, T> AsRef<T> for AsRefWrap<'a, T> {
     }
 }
 
-fn ledger_version_supported(version: &Version) -> bool {
-    if version.major >= 2 {
-        if version.major == 2 {
-            version.minor >= 1
-        } else {
-            true
+const fn min_version(major: u32, minor: u32, patch: u32) -> Version {
+    Version {
+        major,
+        minor,
+        patch,
+        prerelease: None,
+    }
+}
+
+/// Versions of the firmware, or of the Bitcoin app for Ledger, a kind of device must run.
+struct MinimumVersions {
+    kind: DeviceKind,
+    /// Name of what is versioned on the device.
+    versioned: &'static str,
+    /// Older versions are not supported.
+    required: Option<Version>,
+    /// Older versions are supported, but the user is recommended to update.
+    recommended: Option<Version>,
+    /// Where to find how to update the device.
+    update_instructions: &'static str,
+}
+
+// Bump these as new versions fix issues with Liana descriptors.
+const MINIMUM_VERSIONS: [MinimumVersions; 5] = [
+    MinimumVersions {
+        kind: DeviceKind::Ledger,
+        versioned: "Bitcoin app",
+        required: Some(min_version(2, 1, 0)),
+        // Taproot miniscript support.
+        recommended: Some(min_version(2, 2, 0)),
+        update_instructions: "Update the Bitcoin app from Ledger Live: \
+            https://support.ledger.com/article/8458939792669-zd",
+    },
+    MinimumVersions {
+        kind: DeviceKind::Coldcard,
+        versioned: "Edge firmware",
+        required: Some(min_version(6, 2, 1)),
+        recommended: Some(min_version(6, 3, 3)),
+        update_instructions: "Follow the Coldcard upgrade instructions: \
+            https://coldcard.com/docs/upgrade",
+    },
+    MinimumVersions {
+        kind: DeviceKind::BitBox02,
+        versioned: "firmware",
+        required: None,
+        recommended: Some(min_version(9, 21, 0)),
+        update_instructions: "Update the firmware from the BitBoxApp: https://bitbox.swiss/app",
+    },
+    MinimumVersions {
+        kind: DeviceKind::Jade,
+        versioned: "firmware",
+        required: None,
+        recommended: None,
+        update_instructions: "Update the firmware from the Blockstream app: \
+            https://help.blockstream.com",
+    },
+    MinimumVersions {
+        kind: DeviceKind::Specter,
+        versioned: "firmware",
+        required: None,
+        recommended: None,
+        update_instructions: "Install the latest release: \
+            https://github.com/cryptoadvance/specter-diy/releases",
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionStatus {
+    UpToDate,
+    UpdateRecommended { update_instructions: &'static str },
+    UpdateRequired { minimal_supported_version: String },
+}
+
+/// Compare the version of a device against the minimum versions for its kind. A device whose
+/// version is unknown is considered up to date.
+pub fn version_status(kind: &DeviceKind, version: Option<&Version>) -> VersionStatus {
+    let (min, version) = match (
+        MINIMUM_VERSIONS.iter().find(|min| min.kind == *kind),
+        version,
+    ) {
+        (Some(min), Some(version)) => (min, version),
+        _ => return VersionStatus::UpToDate,
+    };
+    if let Some(required) = min.required.as_ref().filter(|required| version < *required) {
+        VersionStatus::UpdateRequired {
+            minimal_supported_version: format!("{} {}", min.versioned, required),
+        }
+    } else if min
+        .recommended
+        .as_ref()
+        .map_or(false, |recommended| version < recommended)
+    {
+        VersionStatus::UpdateRecommended {
+            update_instructions: min.update_instructions,
         }
     } else {
-        false
+        VersionStatus::UpToDate
     }
 }
 
@@ -900,3 +1109,108 @@ pub fn is_compatible_with_tapminiscript(
                 }
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallet_not_registered() {
+        assert!(is_wallet_not_registered(
+            &DeviceKind::Ledger,
+            &HWIError::Device("Device { command: 4, status: SignatureFail }".to_string())
+        ));
+        assert!(is_wallet_not_registered(
+            &DeviceKind::BitBox02,
+            &HWIError::MissingPolicy
+        ));
+        // Only the Ledger reports an unknown HMAC this way.
+        assert!(!is_wallet_not_registered(
+            &DeviceKind::Coldcard,
+            &HWIError::Device("SignatureFail".to_string())
+        ));
+        assert!(!is_wallet_not_registered(
+            &DeviceKind::Ledger,
+            &HWIError::UserRefused
+        ));
+    }
+
+    #[test]
+    fn test_presence_debounce() {
+        let mut presence = Presence::default();
+        let (a, b) = ("ledger-a".to_string(), "coldcard-b".to_string());
+        assert!(presence.update(&[a.clone(), b.clone()]).is_empty());
+        // Missing once, the device may just be busy.
+        assert!(presence.update(&[a.clone()]).is_empty());
+        assert!(presence.is_missing(&b));
+        // It shows up again.
+        assert!(presence.update(&[a.clone(), b.clone()]).is_empty());
+        assert!(!presence.is_missing(&b));
+        // Missing twice in a row, it was unplugged.
+        assert!(presence.update(&[a.clone()]).is_empty());
+        assert_eq!(presence.update(&[a.clone()]), vec![b.clone()]);
+        assert!(!presence.is_missing(&b));
+        assert!(presence.update(&[a.clone()]).is_empty());
+        assert!(presence.update(&[]).is_empty());
+        assert_eq!(presence.update(&[]), vec![a]);
+    }
+
+    #[test]
+    fn test_version_status() {
+        // Unknown versions and kinds without minimum versions are never nudged.
+        assert_eq!(
+            version_status(&DeviceKind::Ledger, None),
+            VersionStatus::UpToDate
+        );
+        assert_eq!(
+            version_status(&DeviceKind::Specter, Some(&min_version(1, 0, 0))),
+            VersionStatus::UpToDate
+        );
+        assert_eq!(
+            version_status(&DeviceKind::Jade, Some(&min_version(0, 1, 0))),
+            VersionStatus::UpToDate
+        );
+
+        // Ledger
+        assert_eq!(
+            version_status(&DeviceKind::Ledger, Some(&min_version(2, 0, 7))),
+            VersionStatus::UpdateRequired {
+                minimal_supported_version: "Bitcoin app 2.1.0".to_string()
+            }
+        );
+        assert!(matches!(
+            version_status(&DeviceKind::Ledger, Some(&min_version(2, 1, 3))),
+            VersionStatus::UpdateRecommended { .. }
+        ));
+        assert_eq!(
+            version_status(&DeviceKind::Ledger, Some(&min_version(2, 2, 0))),
+            VersionStatus::UpToDate
+        );
+
+        // Coldcard
+        assert_eq!(
+            version_status(&DeviceKind::Coldcard, Some(&min_version(6, 2, 0))),
+            VersionStatus::UpdateRequired {
+                minimal_supported_version: "Edge firmware 6.2.1".to_string()
+            }
+        );
+        assert!(matches!(
+            version_status(&DeviceKind::Coldcard, Some(&min_version(6, 2, 1))),
+            VersionStatus::UpdateRecommended { .. }
+        ));
+        assert_eq!(
+            version_status(&DeviceKind::Coldcard, Some(&min_version(6, 3, 3))),
+            VersionStatus::UpToDate
+        );
+
+        // BitBox02
+        assert!(matches!(
+            version_status(&DeviceKind::BitBox02, Some(&min_version(9, 15, 0))),
+            VersionStatus::UpdateRecommended { .. }
+        ));
+        assert_eq!(
+            version_status(&DeviceKind::BitBox02, Some(&min_version(9, 21, 0))),
+            VersionStatus::UpToDate
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! English strings, the reference locale: every key used by the GUI must be defined here.
+pub const STRINGS: &[(&str, &str)] = &[
+    // Common
+    ("common.accept", "Accept"),
+    ("common.cancel", "Cancel"),
+    ("common.clear", "Clear"),
+    ("common.close", "Close"),
+    ("common.closing_wallet", "Closing the wallet…"),
+    ("common.continue", "Continue"),
+    ("common.delete", "Delete"),
+    ("common.hide_amounts", "Hide amounts"),
+    ("common.next", "Next"),
+    ("common.previous", "Previous"),
+    ("common.save", "Save"),
+    ("common.select", "Select"),
+    ("common.show_amounts", "Show amounts"),
+    ("common.shutting_down", "Shutting down…"),
+    ("common.skip", "Skip"),
+    // Sidebar menu
+    ("menu.coins", "Coins"),
+    ("menu.home", "Home"),
+    ("menu.psbts", "PSBTs"),
+    ("menu.receive", "Receive"),
+    ("menu.send", "Send"),
+    ("menu.settings", "Settings"),
+    ("menu.switch_wallet", "Switch wallet"),
+    ("menu.transactions", "Transactions"),
+    // Command palette
+    ("palette.lock_signer", "Lock hot signer"),
+    ("palette.new_spend", "New spend"),
+    ("palette.no_match", "No matching command"),
+    ("palette.open", "Command palette"),
+    ("palette.placeholder", "Type a command"),
+    ("palette.refresh", "Refresh"),
+    ("palette.toggle_amounts", "Hide or show amounts"),
+    // Lock screen
+    ("lock.confirm_with_pin", "Enter your PIN to continue"),
+    (
+        "lock.forgotten_pin",
+        "Forgot your PIN? Restart the application to unlock it.",
+    ),
+    ("lock.passphrase", "Passphrase"),
+    ("lock.pin", "PIN"),
+    (
+        "lock.retry_in",
+        "Too many failed attempts, retry in {seconds}s",
+    ),
+    (
+        "lock.signer_passphrase",
+        "Enter the passphrase of the hot signer {fingerprint}. It is only kept in memory until the signer is locked.",
+    ),
+    ("lock.signer_title", "Unlock the hot signer"),
+    ("lock.title", "Wallet locked"),
+    ("lock.unlock", "Unlock"),
+    ("lock.wrong_passphrase", "Wrong passphrase"),
+    ("lock.wrong_pin", "Wrong PIN"),
+    // Wallet backend
+    ("backend.action_unavailable", "Unavailable while the node is unreachable"),
+    ("backend.crashed", "The wallet backend stopped unexpectedly"),
+    ("backend.node_unreachable", "Node unreachable since {time}, retrying"),
+    ("backend.restart", "Restart the wallet backend"),
+    ("backend.restart_node", "Restart node"),
+    // Launcher
+    ("launcher.add_wallet", "Add an existing Liana wallet"),
+    ("launcher.create_wallet", "Create a new Liana wallet"),
+    ("launcher.my_wallet", "My Liana {network} wallet"),
+    ("launcher.share_xpubs", "Share Xpubs"),
+    ("launcher.wallet_deleted", "Wallet successfully deleted"),
+    ("launcher.welcome", "Welcome"),
+    ("launcher.welcome_back", "Welcome back"),
+    // Installer
+    ("installer.check_connection", "Check connection"),
+    ("installer.import_mnemonic", "Import mnemonic"),
+    ("installer.load_from_descriptor", "Load a wallet from descriptor"),
+    ("installer.load_previous_wallet", "Load a previously used wallet"),
+    ("installer.load_shared_wallet", "Load a shared wallet"),
+    ("installer.no_wallets", "You have no current wallets"),
+    ("installer.resend_token", "Resend token"),
+    ("installer.use_liana_connect", "Use Liana Connect"),
+    ("installer.use_own_node", "Use your own node"),
+    // Home
+    ("home.balance", "Balance"),
+    ("home.checking_new_transactions", "Checking for new transactions"),
+    ("home.consolidate_coins", "Consolidate coins"),
+    (
+        "home.consolidation_hint",
+        "The wallet holds {count} confirmed coins. Merging the oldest ones while fees are low makes the next payments cheaper.",
+    ),
+    ("home.delete_draft", "Delete draft"),
+    ("home.draft_invalid", "This draft is no longer valid"),
+    ("home.drafts", "Drafts"),
+    ("home.fetching", "Fetching ..."),
+    ("home.incoming_payment", "Incoming payment"),
+    ("home.last_payments", "Last payments"),
+    ("home.outgoing_payment", "Outgoing payment"),
+    ("home.payment", "Payment"),
+    ("home.refresh_coins", "Refresh coins"),
+    ("home.see_more", "See more"),
+    ("home.see_transaction_details", "See transaction details"),
+    ("home.syncing", "Syncing"),
+    ("home.syncing_blockchain", "Syncing blockchain ({progress}%)"),
+    ("home.transaction", "Transaction"),
+    // Send
+    ("send.add_payment", "Add payment"),
+    ("send.add_recipient_details", "Add recipient details."),
+    ("send.batch_label", "Batch label"),
+    ("send.check_max_amount", "Check max amount for recipient."),
+    ("send.coin_control", "Coin control"),
+    ("send.coins_selected.one", "{n} coin selected"),
+    ("send.coins_selected.other", "{n} coins selected"),
+    ("send.confirm_mixed_tags", "Spend them together anyway"),
+    ("send.discard_draft", "Discard"),
+    ("send.draft_recipients.one", "{n} recipient"),
+    ("send.draft_recipients.other", "{n} recipients"),
+    ("send.duplicate_addresses", "Two payment addresses are the same"),
+    ("send.estimated_fee", "Estimated fee of at least"),
+    ("send.feerate", "Feerate"),
+    ("send.feerate_required", "Feerate needs to be set."),
+    ("send.left_to_select", "left to select"),
+    ("send.mixed_tags", "The selected coins have different privacy tags: {tags}."),
+    ("send.path_active", "Available"),
+    ("send.path_inactive", "Available in {blocks} blocks"),
+    ("send.path_signers", "{threshold} signature(s) from {signers}"),
+    ("send.primary_path", "Primary path"),
+    ("send.recovery_path", "Recovery path after {blocks} blocks"),
+    (
+        "send.refresh_description",
+        "Send the selected coins back to a new address of the wallet to reset the timelock of their recovery path.",
+    ),
+    (
+        "send.refresh_high_fee",
+        "The fee is {percent}% of the refreshed value, above the {max}% limit.",
+    ),
+    (
+        "send.refresh_several_txs",
+        "Too many coins for a single transaction: {count} transactions will be saved, to be signed from the PSBTs list.",
+    ),
+    ("send.refresh_title", "Refresh coins"),
+    ("send.resume", "Resume"),
+    ("send.resume_draft", "Resume draft from {time}"),
+    ("send.save_draft", "Save draft"),
+    ("send.select_all_coins", "Select all"),
+    ("send.select_one_coin", "Select at least one coin."),
+    ("send.self_transfer", "Self-transfer"),
+    ("send.spend_path", "Spending path"),
+    ("send.title", "Send"),
+    (
+        "send.uneconomical_coin",
+        "Spending this coin at the current feerate costs more in fees than its value.",
+    ),
+    ("send.untagged", "untagged"),
+    // Settings
+    ("settings.about", "About"),
+    ("settings.backend", "Backend"),
+    ("settings.display_amounts_in", "Display amounts in:"),
+    ("settings.hot_signer_unlock", "Hot signer passphrase:"),
+    (
+        "settings.hot_signer_unlock_description",
+        "The hot signer is protected by a passphrase. Lock it again from the command palette, or by letting the wallet auto-lock.",
+    ),
+    ("settings.hot_signer_unlock_on_open", "Ask when opening the wallet"),
+    ("settings.hot_signer_unlock_on_signing", "Ask before signing"),
+    ("settings.language", "Language:"),
+    ("settings.managed_bitcoind", "Managed bitcoind"),
+    ("settings.node", "Node"),
+    ("settings.profile", "Configuration profile"),
+    (
+        "settings.profile_tooltip",
+        "Export the settings of this installation to set up other ones identically, without any secret.",
+    ),
+    ("settings.recovery", "Recovery"),
+    (
+        "settings.recovery_tooltip",
+        "In case of loss of the main key, the recovery key can move the funds after a certain time.",
+    ),
+    ("settings.title", "Settings"),
+    ("settings.wallet", "Wallet"),
+];
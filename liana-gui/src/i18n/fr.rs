@@ -0,0 +1,205 @@
+//! French strings.
+pub const STRINGS: &[(&str, &str)] = &[
+    // Common
+    ("common.accept", "Accepter"),
+    ("common.cancel", "Annuler"),
+    ("common.clear", "Effacer"),
+    ("common.close", "Fermer"),
+    ("common.closing_wallet", "Fermeture du portefeuille…"),
+    ("common.continue", "Continuer"),
+    ("common.delete", "Supprimer"),
+    ("common.hide_amounts", "Masquer les montants"),
+    ("common.next", "Suivant"),
+    ("common.previous", "Précédent"),
+    ("common.save", "Enregistrer"),
+    ("common.select", "Choisir"),
+    ("common.show_amounts", "Afficher les montants"),
+    ("common.shutting_down", "Arrêt en cours…"),
+    ("common.skip", "Passer"),
+    // Sidebar menu
+    ("menu.coins", "Pièces"),
+    ("menu.home", "Accueil"),
+    ("menu.psbts", "PSBTs"),
+    ("menu.receive", "Recevoir"),
+    ("menu.send", "Envoyer"),
+    ("menu.settings", "Paramètres"),
+    ("menu.switch_wallet", "Changer de portefeuille"),
+    ("menu.transactions", "Transactions"),
+    // Command palette
+    ("palette.lock_signer", "Verrouiller le signataire logiciel"),
+    ("palette.new_spend", "Nouvelle dépense"),
+    ("palette.no_match", "Aucune commande correspondante"),
+    ("palette.open", "Palette de commandes"),
+    ("palette.placeholder", "Tapez une commande"),
+    ("palette.refresh", "Rafraîchir"),
+    ("palette.toggle_amounts", "Masquer ou afficher les montants"),
+    // Lock screen
+    (
+        "lock.confirm_with_pin",
+        "Saisissez votre code PIN pour continuer",
+    ),
+    (
+        "lock.forgotten_pin",
+        "Code PIN oublié ? Redémarrez l'application pour la déverrouiller.",
+    ),
+    ("lock.passphrase", "Phrase secrète"),
+    ("lock.pin", "Code PIN"),
+    (
+        "lock.retry_in",
+        "Trop de tentatives échouées, réessayez dans {seconds} s",
+    ),
+    (
+        "lock.signer_passphrase",
+        "Saisissez la phrase secrète du signataire logiciel {fingerprint}. Elle est uniquement conservée en mémoire jusqu'au verrouillage du signataire.",
+    ),
+    ("lock.signer_title", "Déverrouiller le signataire logiciel"),
+    ("lock.title", "Portefeuille verrouillé"),
+    ("lock.unlock", "Déverrouiller"),
+    ("lock.wrong_passphrase", "Phrase secrète incorrecte"),
+    ("lock.wrong_pin", "Code PIN incorrect"),
+    // Wallet backend
+    (
+        "backend.action_unavailable",
+        "Indisponible tant que le nœud est injoignable",
+    ),
+    ("backend.crashed", "Le moteur du portefeuille s'est arrêté de manière inattendue"),
+    (
+        "backend.node_unreachable",
+        "Nœud injoignable depuis {time}, nouvelle tentative en cours",
+    ),
+    ("backend.restart", "Redémarrer le moteur du portefeuille"),
+    ("backend.restart_node", "Redémarrer le nœud"),
+    // Launcher
+    ("launcher.add_wallet", "Ajouter un portefeuille Liana existant"),
+    ("launcher.create_wallet", "Créer un nouveau portefeuille Liana"),
+    ("launcher.my_wallet", "Mon portefeuille Liana {network}"),
+    ("launcher.share_xpubs", "Partager les xpubs"),
+    ("launcher.wallet_deleted", "Portefeuille supprimé"),
+    ("launcher.welcome", "Bienvenue"),
+    ("launcher.welcome_back", "Bon retour"),
+    // Installer
+    ("installer.check_connection", "Tester la connexion"),
+    ("installer.import_mnemonic", "Importer la phrase mnémonique"),
+    (
+        "installer.load_from_descriptor",
+        "Charger un portefeuille depuis un descripteur",
+    ),
+    (
+        "installer.load_previous_wallet",
+        "Charger un portefeuille déjà utilisé",
+    ),
+    ("installer.load_shared_wallet", "Charger un portefeuille partagé"),
+    ("installer.no_wallets", "Vous n'avez aucun portefeuille"),
+    ("installer.resend_token", "Renvoyer le code"),
+    ("installer.use_liana_connect", "Utiliser Liana Connect"),
+    ("installer.use_own_node", "Utiliser votre propre nœud"),
+    // Home
+    ("home.balance", "Solde"),
+    (
+        "home.checking_new_transactions",
+        "Recherche de nouvelles transactions",
+    ),
+    ("home.consolidate_coins", "Regrouper les pièces"),
+    (
+        "home.consolidation_hint",
+        "Le portefeuille contient {count} pièces confirmées. Regrouper les plus anciennes quand les frais sont bas rend les prochains paiements moins chers.",
+    ),
+    ("home.delete_draft", "Supprimer le brouillon"),
+    ("home.draft_invalid", "Ce brouillon n'est plus valide"),
+    ("home.drafts", "Brouillons"),
+    ("home.fetching", "Chargement ..."),
+    ("home.incoming_payment", "Paiement entrant"),
+    ("home.last_payments", "Derniers paiements"),
+    ("home.outgoing_payment", "Paiement sortant"),
+    ("home.payment", "Paiement"),
+    ("home.refresh_coins", "Rafraîchir les pièces"),
+    ("home.see_more", "Voir plus"),
+    (
+        "home.see_transaction_details",
+        "Voir les détails de la transaction",
+    ),
+    ("home.syncing", "Synchronisation"),
+    (
+        "home.syncing_blockchain",
+        "Synchronisation de la blockchain ({progress} %)",
+    ),
+    ("home.transaction", "Transaction"),
+    // Send
+    ("send.add_payment", "Ajouter un paiement"),
+    ("send.add_recipient_details", "Renseignez les détails du destinataire."),
+    ("send.batch_label", "Libellé du lot"),
+    ("send.check_max_amount", "Vérifiez le montant maximum du destinataire."),
+    ("send.coin_control", "Contrôle des pièces"),
+    ("send.coins_selected.one", "{n} pièce sélectionnée"),
+    ("send.coins_selected.other", "{n} pièces sélectionnées"),
+    ("send.confirm_mixed_tags", "Les dépenser ensemble malgré tout"),
+    ("send.discard_draft", "Abandonner"),
+    ("send.draft_recipients.one", "{n} destinataire"),
+    ("send.draft_recipients.other", "{n} destinataires"),
+    ("send.duplicate_addresses", "Deux adresses de paiement sont identiques"),
+    ("send.estimated_fee", "Frais estimés d'au moins"),
+    ("send.feerate", "Taux de frais"),
+    ("send.feerate_required", "Le taux de frais doit être défini."),
+    ("send.left_to_select", "restant à sélectionner"),
+    (
+        "send.mixed_tags",
+        "Les pièces sélectionnées ont des étiquettes de confidentialité différentes : {tags}.",
+    ),
+    ("send.path_active", "Disponible"),
+    ("send.path_inactive", "Disponible dans {blocks} blocs"),
+    ("send.path_signers", "{threshold} signature(s) parmi {signers}"),
+    ("send.primary_path", "Chemin principal"),
+    ("send.recovery_path", "Chemin de récupération après {blocks} blocs"),
+    (
+        "send.refresh_description",
+        "Renvoyer les pièces sélectionnées vers une nouvelle adresse du portefeuille pour réinitialiser le délai de leur chemin de récupération.",
+    ),
+    (
+        "send.refresh_high_fee",
+        "Les frais représentent {percent} % de la valeur rafraîchie, au-delà de la limite de {max} %.",
+    ),
+    (
+        "send.refresh_several_txs",
+        "Trop de pièces pour une seule transaction : {count} transactions seront enregistrées, à signer depuis la liste des PSBT.",
+    ),
+    ("send.refresh_title", "Rafraîchir les pièces"),
+    ("send.resume", "Reprendre"),
+    ("send.resume_draft", "Reprendre le brouillon du {time}"),
+    ("send.save_draft", "Enregistrer le brouillon"),
+    ("send.select_all_coins", "Tout sélectionner"),
+    ("send.select_one_coin", "Sélectionnez au moins une pièce."),
+    ("send.self_transfer", "Transfert à soi-même"),
+    ("send.spend_path", "Chemin de dépense"),
+    ("send.title", "Envoyer"),
+    (
+        "send.uneconomical_coin",
+        "Dépenser cette pièce au taux de frais actuel coûte plus en frais que sa valeur.",
+    ),
+    ("send.untagged", "sans étiquette"),
+    // Settings
+    ("settings.about", "À propos"),
+    ("settings.backend", "Serveur"),
+    ("settings.display_amounts_in", "Afficher les montants en :"),
+    ("settings.hot_signer_unlock", "Phrase secrète du signataire logiciel :"),
+    (
+        "settings.hot_signer_unlock_description",
+        "Le signataire logiciel est protégé par une phrase secrète. Verrouillez-le de nouveau depuis la palette de commandes, ou en laissant le portefeuille se verrouiller automatiquement.",
+    ),
+    ("settings.hot_signer_unlock_on_open", "Demander à l'ouverture du portefeuille"),
+    ("settings.hot_signer_unlock_on_signing", "Demander avant de signer"),
+    ("settings.language", "Langue :"),
+    ("settings.managed_bitcoind", "Bitcoind géré"),
+    ("settings.node", "Nœud"),
+    ("settings.profile", "Profil de configuration"),
+    (
+        "settings.profile_tooltip",
+        "Exportez les paramètres de cette installation pour en configurer d'autres à l'identique, sans aucun secret.",
+    ),
+    ("settings.recovery", "Récupération"),
+    (
+        "settings.recovery_tooltip",
+        "En cas de perte de la clé principale, la clé de récupération peut déplacer les fonds après un certain délai.",
+    ),
+    ("settings.title", "Paramètres"),
+    ("settings.wallet", "Portefeuille"),
+];
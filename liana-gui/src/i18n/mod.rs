@@ -0,0 +1,227 @@
+//! i18n is the module handling the translations of the GUI.
+//! Every locale is a table of keys to strings compiled in the binary, the
+//! English one being the reference: any key missing from another locale falls
+//! back to its English string.
+mod en;
+mod fr;
+
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Name of the file in the root of the datadir storing the language picked by the user.
+/// It is shared by all networks as the launcher is displayed before any network is selected.
+pub const LANGUAGE_FILE_NAME: &str = "language.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "fr")]
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    fn strings(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::English => en::STRINGS,
+            Self::French => fr::STRINGS,
+        }
+    }
+
+    /// Whether the given count takes the singular form.
+    fn is_singular(&self, n: u64) -> bool {
+        match self {
+            Self::English => n == 1,
+            // In French, zero takes the singular form as well.
+            Self::French => n <= 1,
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Languages are displayed in their own language, so that a user can find theirs
+        // whatever the current one is.
+        match self {
+            Self::English => write!(f, "English"),
+            Self::French => write!(f, "Français"),
+        }
+    }
+}
+
+static CURRENT_LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn language() -> Language {
+    match CURRENT_LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::French,
+        _ => Language::English,
+    }
+}
+
+pub fn set_language(language: Language) {
+    CURRENT_LANGUAGE.store(
+        match language {
+            Language::English => 0,
+            Language::French => 1,
+        },
+        Ordering::Relaxed,
+    );
+}
+
+fn lookup(language: Language, key: &str) -> Option<&'static str> {
+    language
+        .strings()
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| *v)
+}
+
+fn translate(language: Language, key: &'static str) -> &'static str {
+    lookup(language, key)
+        .or_else(|| lookup(Language::English, key))
+        .unwrap_or_else(|| {
+            warn!("Missing translation for key '{}'", key);
+            key
+        })
+}
+
+/// Returns the string of the given key in the current language.
+pub fn tr(key: &'static str) -> &'static str {
+    translate(language(), key)
+}
+
+/// Returns the string of the given key in the current language, with each `{name}`
+/// placeholder replaced by its value.
+pub fn tr_args(key: &'static str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(tr(key).to_string(), |s, (name, value)| {
+        s.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+fn translate_plural(language: Language, key: &'static str, n: u64) -> String {
+    let form = if language.is_singular(n) {
+        format!("{}.one", key)
+    } else {
+        format!("{}.other", key)
+    };
+    let s = lookup(language, &form)
+        .or_else(|| lookup(Language::English, &form))
+        .unwrap_or_else(|| {
+            warn!("Missing translation for key '{}'", form);
+            key
+        });
+    s.replace("{n}", &n.to_string())
+}
+
+/// Returns the singular (`<key>.one`) or plural (`<key>.other`) string of the given key
+/// according to the count and the rules of the current language, with `{n}` replaced
+/// by the count.
+pub fn tr_plural(key: &'static str, n: u64) -> String {
+    translate_plural(language(), key, n)
+}
+
+/// Loads the language stored in the datadir, if any.
+pub fn load_language(datadir: &Path) -> Option<Language> {
+    let content = std::fs::read(datadir.join(LANGUAGE_FILE_NAME)).ok()?;
+    serde_json::from_slice(&content)
+        .map_err(|e| warn!("Failed to parse language file: {}", e))
+        .ok()
+}
+
+pub fn save_language(datadir: &Path, language: Language) -> Result<(), std::io::Error> {
+    let content = serde_json::to_vec(&language)?;
+    std::fs::write(datadir.join(LANGUAGE_FILE_NAME), content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(s: &str) -> Vec<&str> {
+        let mut res: Vec<&str> = s
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn locales_complete() {
+        for language in Language::ALL {
+            let strings = language.strings();
+            for (key, value) in en::STRINGS {
+                let translated = lookup(language, key)
+                    .unwrap_or_else(|| panic!("{} misses key '{}'", language, key));
+                assert_eq!(
+                    placeholders(value),
+                    placeholders(translated),
+                    "{} has different placeholders for key '{}'",
+                    language,
+                    key
+                );
+            }
+            for (key, _) in strings {
+                assert!(
+                    lookup(Language::English, key).is_some(),
+                    "{} has unknown key '{}'",
+                    language,
+                    key
+                );
+                assert_eq!(
+                    strings.iter().filter(|(k, _)| k == key).count(),
+                    1,
+                    "{} has duplicated key '{}'",
+                    language,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn plural_forms() {
+        let key = "send.coins_selected";
+        assert_eq!(
+            translate_plural(Language::English, key, 0),
+            "0 coins selected"
+        );
+        assert_eq!(
+            translate_plural(Language::English, key, 1),
+            "1 coin selected"
+        );
+        assert_eq!(
+            translate_plural(Language::English, key, 2),
+            "2 coins selected"
+        );
+        assert_eq!(
+            translate_plural(Language::French, key, 0),
+            "0 pièce sélectionnée"
+        );
+        assert_eq!(
+            translate_plural(Language::French, key, 1),
+            "1 pièce sélectionnée"
+        );
+        assert_eq!(
+            translate_plural(Language::French, key, 12),
+            "12 pièces sélectionnées"
+        );
+    }
+
+    #[test]
+    fn language_serde() {
+        assert_eq!(serde_json::to_string(&Language::French).unwrap(), "\"fr\"");
+        assert_eq!(
+            serde_json::from_str::<Language>("\"en\"").unwrap(),
+            Language::English
+        );
+    }
+}
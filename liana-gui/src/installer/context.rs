@@ -6,6 +6,7 @@ use crate::{
     app::settings::KeySetting,
     lianalite::client::backend::{BackendClient, BackendWalletClient},
     node::bitcoind::{Bitcoind, InternalBitcoindConfig},
+    profile::Profile,
     signer::Signer,
 };
 use async_hwi::DeviceKind;
@@ -68,6 +69,8 @@ pub struct Context {
     pub internal_bitcoind_config: Option<InternalBitcoindConfig>,
     pub internal_bitcoind: Option<Bitcoind>,
     pub remote_backend: RemoteBackend,
+    // The configuration profile set with `--profile`, used for the default settings.
+    pub profile: Option<Profile>,
 }
 
 impl Context {
@@ -76,11 +79,13 @@ impl Context {
         data_dir: PathBuf,
         remote_backend: RemoteBackend,
     ) -> Self {
+        let profile = Profile::from_datadir(&data_dir);
         Self {
             descriptor_template: DescriptorTemplate::default(),
             bitcoin_config: BitcoinConfig {
                 network,
                 poll_interval_secs: Duration::from_secs(30),
+                max_backoff_secs: Duration::from_secs(5 * 60),
             },
             hws: Vec::new(),
             keys: Vec::new(),
@@ -94,6 +99,7 @@ impl Context {
             internal_bitcoind_config: None,
             internal_bitcoind: None,
             remote_backend,
+            profile,
         }
     }
 }
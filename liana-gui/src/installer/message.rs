@@ -44,6 +44,8 @@ pub enum Message {
     WalletRegistered(Result<(Fingerprint, Option<[u8; 32]>), Error>),
     MnemonicWord(usize, String),
     ImportMnemonic(bool),
+    ExportPolicyDiagram,
+    PolicyDiagramExported(Result<Option<PathBuf>, Error>),
 }
 
 #[derive(Debug, Clone)]
@@ -7,14 +7,15 @@ mod view;
 use iced::{clipboard, Command, Subscription};
 use liana::miniscript::bitcoin::{self, Network};
 use liana_ui::{
-    component::network_banner,
+    component::{amount::BitcoinDisplayUnit, network_banner},
     widget::{Column, Element},
 };
-use lianad::config::Config;
+use lianad::config::{Config, DEFAULT_MAX_DERIVATION_INDEX, DEFAULT_MIN_FEERATE};
 use tracing::{error, info, warn};
 
 use context::{Context, RemoteBackend};
 
+use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -22,7 +23,10 @@ use std::sync::{Arc, Mutex};
 use crate::{
     app::{
         config as gui_config, settings as gui_settings,
-        settings::{AuthConfig, Settings, SettingsError, WalletSetting},
+        settings::{
+            AuthConfig, HotSignerUnlock, NotificationSettings, Settings, SettingsError,
+            WalletSetting, DEFAULT_EXPIRY_WARNING_DAYS, DEFAULT_REFRESH_MAX_FEE_PERCENT,
+        },
         wallet::wallet_name,
     },
     daemon::DaemonError,
@@ -32,6 +36,7 @@ use crate::{
         auth::AuthError,
         backend::{BackendClient, BackendWalletClient},
     },
+    shutdown::Shutdown,
     signer::Signer,
 };
 
@@ -171,17 +176,15 @@ impl Installer {
             .subscription(&self.hws)
     }
 
-    pub fn stop(&mut self) {
+    /// What must be stopped before exiting.
+    pub fn stop(&mut self) -> Shutdown {
         // Use current step's `stop()` method for any changes not yet written to context.
         self.steps
             .get_mut(self.current)
             .expect("There is always a step")
             .stop();
         // Now use context to determine what to stop.
-        if let Some(bitcoind) = &self.context.internal_bitcoind {
-            bitcoind.stop();
-        }
-        self.context.internal_bitcoind = None;
+        Shutdown::new().with_bitcoind(self.context.internal_bitcoind.take())
     }
 
     fn skip_steps(&mut self) {
@@ -406,18 +409,22 @@ pub async fn install_local_wallet(
     }
 
     // create liana GUI configuration file
+    let mut gui_config = gui_config::Config::new(
+        daemon_config_path.canonicalize().map_err(|e| {
+            Error::Unexpected(format!("Failed to canonicalize daemon config path: {}", e))
+        })?,
+        // Installer started a bitcoind, it is expected that gui will start it on startup
+        ctx.internal_bitcoind.is_some(),
+    );
+    if let Some(profile) = &ctx.profile {
+        profile.apply_to_gui_config(&mut gui_config);
+    }
     let gui_config_path = create_and_write_file(
         network_datadir_path.clone(),
         gui_config::DEFAULT_FILE_NAME,
-        toml::to_string(&gui_config::Config::new(
-            daemon_config_path.canonicalize().map_err(|e| {
-                Error::Unexpected(format!("Failed to canonicalize daemon config path: {}", e))
-            })?,
-            // Installer started a bitcoind, it is expected that gui will start it on startup
-            ctx.internal_bitcoind.is_some(),
-        ))
-        .map_err(|e| Error::Unexpected(format!("Failed to serialize gui config: {}", e)))?
-        .as_bytes(),
+        toml::to_string(&gui_config)
+            .map_err(|e| Error::Unexpected(format!("Failed to serialize gui config: {}", e)))?
+            .as_bytes(),
     )?;
 
     info!("Gui configuration file created");
@@ -482,18 +489,24 @@ pub async fn create_remote_wallet(
     network_datadir_path.push(ctx.network.to_string());
 
     // create liana GUI configuration file
+    let mut gui_config = gui_config::Config {
+        daemon_config_path: None,
+        daemon_rpc_path: None,
+        daemon_wallet: None,
+        log_level: Some("info".to_string()),
+        debug: Some(false),
+        start_internal_bitcoind: false,
+        shortcuts: BTreeMap::new(),
+    };
+    if let Some(profile) = &ctx.profile {
+        profile.apply_to_gui_config(&mut gui_config);
+    }
     let gui_config_path = create_and_write_file(
         network_datadir_path.clone(),
         gui_config::DEFAULT_FILE_NAME,
-        toml::to_string(&gui_config::Config {
-            daemon_config_path: None,
-            daemon_rpc_path: None,
-            log_level: Some("info".to_string()),
-            debug: Some(false),
-            start_internal_bitcoind: false,
-        })
-        .map_err(|e| Error::Unexpected(format!("Failed to serialize gui config: {}", e)))?
-        .as_bytes(),
+        toml::to_string(&gui_config)
+            .map_err(|e| Error::Unexpected(format!("Failed to serialize gui config: {}", e)))?
+            .as_bytes(),
     )?;
 
     info!("Gui configuration file created");
@@ -583,18 +596,24 @@ pub async fn import_remote_wallet(
     info!("Settings file created");
 
     // create liana GUI configuration file
+    let mut gui_config = gui_config::Config {
+        daemon_config_path: None,
+        daemon_rpc_path: None,
+        daemon_wallet: None,
+        log_level: Some("info".to_string()),
+        debug: Some(false),
+        start_internal_bitcoind: false,
+        shortcuts: BTreeMap::new(),
+    };
+    if let Some(profile) = &ctx.profile {
+        profile.apply_to_gui_config(&mut gui_config);
+    }
     let gui_config_path = create_and_write_file(
         network_datadir_path.clone(),
         gui_config::DEFAULT_FILE_NAME,
-        toml::to_string(&gui_config::Config {
-            daemon_config_path: None,
-            daemon_rpc_path: None,
-            log_level: Some("info".to_string()),
-            debug: Some(false),
-            start_internal_bitcoind: false,
-        })
-        .map_err(|e| Error::Unexpected(format!("Failed to serialize gui config: {}", e)))?
-        .as_bytes(),
+        toml::to_string(&gui_config)
+            .map_err(|e| Error::Unexpected(format!("Failed to serialize gui config: {}", e)))?
+            .as_bytes(),
     )?;
 
     info!("Gui configuration file created");
@@ -644,6 +663,13 @@ pub async fn extract_remote_gui_settings(ctx: &Context, backend: &BackendWalletC
                 wallet_id: backend.wallet_id(),
                 refresh_token: auth.refresh_token.clone(),
             }),
+            scheduled_broadcasts: Vec::new(),
+            expiry_warning_days: DEFAULT_EXPIRY_WARNING_DAYS.to_vec(),
+            refresh_max_fee_percent: DEFAULT_REFRESH_MAX_FEE_PERCENT,
+            notifications: NotificationSettings::default(),
+            bitcoin_unit: BitcoinDisplayUnit::default(),
+            auto_lock: None,
+            hot_signer_unlock: HotSignerUnlock::default(),
         }],
     }
 }
@@ -677,6 +703,13 @@ pub async fn extract_local_gui_settings(ctx: &Context) -> Settings {
             keys: ctx.keys.clone(),
             hardware_wallets,
             remote_backend_auth: None,
+            scheduled_broadcasts: Vec::new(),
+            expiry_warning_days: DEFAULT_EXPIRY_WARNING_DAYS.to_vec(),
+            refresh_max_fee_percent: DEFAULT_REFRESH_MAX_FEE_PERCENT,
+            notifications: NotificationSettings::default(),
+            bitcoin_unit: BitcoinDisplayUnit::default(),
+            auto_lock: None,
+            hot_signer_unlock: HotSignerUnlock::default(),
         }],
     }
 }
@@ -689,8 +722,14 @@ pub fn extract_daemon_config(ctx: &Context) -> Config {
             .clone()
             .expect("Context must have a descriptor at this point"),
         data_dir: Some(ctx.data_dir.clone()),
+        coin_selection: Default::default(),
         bitcoin_config: ctx.bitcoin_config.clone(),
         bitcoin_backend: ctx.bitcoin_backend.clone(),
+        recovery_thresholds: Vec::new(),
+        warm_derivation_cache: false,
+        fee_sources: None,
+        max_derivation_index: DEFAULT_MAX_DERIVATION_INDEX,
+        min_feerate_sat_vb: DEFAULT_MIN_FEERATE,
     }
 }
 
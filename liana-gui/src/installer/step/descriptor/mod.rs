@@ -2,6 +2,7 @@ pub mod editor;
 
 use std::{
     collections::{HashMap, HashSet},
+    path::PathBuf,
     str::FromStr,
 };
 
@@ -16,7 +17,8 @@ use liana_ui::{component::form, widget::Element};
 use async_hwi::DeviceKind;
 
 use crate::{
-    app::wallet::wallet_name,
+    app::{view::policy, wallet::wallet_name},
+    export,
     hw::{HardwareWallet, HardwareWallets},
     installer::{
         message::{self, Message},
@@ -292,12 +294,38 @@ pub struct BackupDescriptor {
     done: bool,
     descriptor: Option<LianaDescriptor>,
     key_aliases: HashMap<Fingerprint, String>,
+    diagram_exported: Option<PathBuf>,
+    error: Option<Error>,
 }
 
 impl Step for BackupDescriptor {
     fn update(&mut self, _hws: &mut HardwareWallets, message: Message) -> Command<Message> {
-        if let Message::UserActionDone(done) = message {
-            self.done = done;
+        match message {
+            Message::UserActionDone(done) => {
+                self.done = done;
+            }
+            Message::ExportPolicyDiagram => {
+                if let Some(descriptor) = &self.descriptor {
+                    self.diagram_exported = None;
+                    self.error = None;
+                    let diagram = policy::diagram(&descriptor.policy(), |fg| {
+                        self.key_aliases.get(fg).cloned()
+                    });
+                    return Command::perform(
+                        async move {
+                            export::export_policy_diagram(diagram)
+                                .await
+                                .map_err(|e| Error::CannotWriteToFile(format!("{:?}", e)))
+                        },
+                        Message::PolicyDiagramExported,
+                    );
+                }
+            }
+            Message::PolicyDiagramExported(res) => match res {
+                Ok(path) => self.diagram_exported = path,
+                Err(e) => self.error = Some(e),
+            },
+            _ => {}
         }
         Command::none()
     }
@@ -305,6 +333,7 @@ impl Step for BackupDescriptor {
         if self.descriptor != ctx.descriptor {
             self.descriptor.clone_from(&ctx.descriptor);
             self.done = false;
+            self.diagram_exported = None;
         }
         self.key_aliases = ctx
             .keys
@@ -324,6 +353,8 @@ impl Step for BackupDescriptor {
             email,
             self.descriptor.as_ref().expect("Must be a descriptor"),
             &self.key_aliases,
+            self.diagram_exported.as_ref(),
+            self.error.as_ref(),
             self.done,
         )
     }
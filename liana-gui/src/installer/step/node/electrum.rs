@@ -28,6 +28,15 @@ impl DefineElectrum {
         !self.address.value.is_empty() && self.address.valid
     }
 
+    pub fn load_context(&mut self, ctx: &Context) {
+        if self.address.value.is_empty() {
+            if let Some(addr) = ctx.profile.as_ref().and_then(|p| p.electrum_addr()) {
+                self.address.value = addr.to_string();
+                self.address.valid = crate::node::electrum::is_electrum_address_valid(addr);
+            }
+        }
+    }
+
     pub fn update(&mut self, message: message::DefineNode) -> Command<Message> {
         if let message::DefineNode::DefineElectrum(msg) = message {
             match msg {
@@ -47,6 +56,8 @@ impl DefineElectrum {
         if self.can_try_ping() {
             ctx.bitcoin_backend = Some(lianad::config::BitcoinBackend::Electrum(ElectrumConfig {
                 addr: self.address.value.clone(),
+                socks5_proxy: None,
+                skip_certificate_validation: false,
             }));
             return true;
         }
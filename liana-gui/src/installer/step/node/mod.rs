@@ -56,9 +56,7 @@ impl NodeDefinition {
     fn load_context(&mut self, ctx: &Context) {
         match self {
             NodeDefinition::Bitcoind(def) => def.load_context(ctx),
-            NodeDefinition::Electrum(_) => {
-                // noop for now
-            }
+            NodeDefinition::Electrum(def) => def.load_context(ctx),
         }
     }
 
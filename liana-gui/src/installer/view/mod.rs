@@ -22,7 +22,9 @@ use liana::{
 use liana_ui::{
     color,
     component::{
-        button, card, collapse, form, hw, separation,
+        button, card, collapse, form, hw,
+        policy_diagram::policy_diagram,
+        separation,
         text::{h2, h3, h4_bold, h5_regular, p1_regular, text, Text},
     },
     icon, theme,
@@ -30,7 +32,9 @@ use liana_ui::{
 };
 
 use crate::{
+    app::view::{hw::supported_hardware_wallet, policy},
     hw::{is_compatible_with_tapminiscript, HardwareWallet, UnsupportedReason},
+    i18n::tr,
     installer::{
         message::{self, DefineBitcoind, DefineNode, Message},
         prompt,
@@ -55,7 +59,7 @@ pub fn import_wallet_or_descriptor<'a>(
 ) -> Element<'a, Message> {
     let mut col_wallets = Column::new()
         .spacing(20)
-        .push(h4_bold("Load a previously used wallet"));
+        .push(h4_bold(tr("installer.load_previous_wallet")));
     let no_wallets = wallets.is_empty();
     for (i, wallet) in wallets.into_iter().enumerate() {
         col_wallets = col_wallets.push(
@@ -66,7 +70,7 @@ pub fn import_wallet_or_descriptor<'a>(
         );
     }
     let card_wallets: Element<'a, Message> = if no_wallets {
-        h4_bold("You have no current wallets").into()
+        h4_bold(tr("installer.no_wallets")).into()
     } else {
         card::simple(col_wallets).into()
     };
@@ -76,7 +80,7 @@ pub fn import_wallet_or_descriptor<'a>(
             Button::new(
                 Column::new()
                     .spacing(5)
-                    .push(h4_bold("Load a shared wallet").style(color::WHITE))
+                    .push(h4_bold(tr("installer.load_shared_wallet")).style(color::WHITE))
                     .push(
                         text("If you received an invitation to join a shared wallet")
                             .style(color::GREY_3),
@@ -90,7 +94,7 @@ pub fn import_wallet_or_descriptor<'a>(
             Button::new(
                 Column::new()
                     .spacing(5)
-                    .push(h4_bold("Load a shared wallet").style(color::WHITE))
+                    .push(h4_bold(tr("installer.load_shared_wallet")).style(color::WHITE))
                     .push(
                         text("Type the invitation token you received by email")
                             .style(color::GREY_3),
@@ -113,7 +117,7 @@ pub fn import_wallet_or_descriptor<'a>(
                         )
                         .push(
                             Row::new().push(Space::with_width(Length::Fill)).push(
-                                button::secondary(None, "Accept")
+                                button::secondary(None, tr("common.accept"))
                                     .width(Length::Fixed(200.0))
                                     .on_press(Message::ImportRemoteWallet(
                                         message::ImportRemoteWallet::AcceptInvitation,
@@ -146,7 +150,7 @@ pub fn import_wallet_or_descriptor<'a>(
                             )
                             .push(
                                 Row::new().push(Space::with_width(Length::Fill)).push(
-                                    button::secondary(None, "Next")
+                                    button::secondary(None, tr("common.next"))
                                         .width(Length::Fixed(200.0))
                                         .on_press_maybe(if !invitation.value.is_empty() {
                                             Some(Message::ImportRemoteWallet(
@@ -170,7 +174,7 @@ pub fn import_wallet_or_descriptor<'a>(
             Button::new(
                 Column::new()
                     .spacing(5)
-                    .push(h4_bold("Load a wallet from descriptor").style(color::WHITE))
+                    .push(h4_bold(tr("installer.load_from_descriptor")).style(color::WHITE))
                     .push(text("Creates a new wallet from the descriptor").style(color::GREY_3)),
             )
             .padding(15)
@@ -181,7 +185,7 @@ pub fn import_wallet_or_descriptor<'a>(
             Button::new(
                 Column::new()
                     .spacing(5)
-                    .push(h4_bold("Load a wallet from descriptor").style(color::WHITE))
+                    .push(h4_bold(tr("installer.load_from_descriptor")).style(color::WHITE))
                     .push(text("Creates a new wallet from the descriptor").style(color::GREY_3)),
             )
             .padding(15)
@@ -216,7 +220,7 @@ pub fn import_wallet_or_descriptor<'a>(
                         )
                         .push(
                             Row::new().push(Space::with_width(Length::Fill)).push(
-                                button::secondary(None, "Next")
+                                button::secondary(None, tr("common.next"))
                                     .width(Length::Fixed(200.0))
                                     .on_press_maybe(
                                         if imported_descriptor.value.is_empty()
@@ -290,9 +294,9 @@ pub fn import_descriptor<'a>(
             )))
             .push(
                 if imported_descriptor.value.is_empty() || !imported_descriptor.valid {
-                    button::secondary(None, "Next").width(Length::Fixed(200.0))
+                    button::secondary(None, tr("common.next")).width(Length::Fixed(200.0))
                 } else {
-                    button::secondary(None, "Next")
+                    button::secondary(None, tr("common.next"))
                         .width(Length::Fixed(200.0))
                         .on_press(Message::Next)
                 },
@@ -420,7 +424,7 @@ pub fn hardware_wallet_xpubs<'a>(
             if processing {
                 hw::processing_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
             } else {
-                hw::supported_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
+                supported_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
             }
         }
         HardwareWallet::Unsupported {
@@ -662,11 +666,11 @@ pub fn register_descriptor<'a>(
                 done,
             ).on_toggle(Message::UserActionDone)))
             .push(if !created_desc || (done && !processing) {
-                button::secondary(None, "Next")
+                button::secondary(None, tr("common.next"))
                     .on_press(Message::Next)
                     .width(Length::Fixed(200.0))
             } else {
-                button::secondary(None, "Next").width(Length::Fixed(200.0))
+                button::secondary(None, tr("common.next")).width(Length::Fixed(200.0))
             })
             .spacing(50),
         true,
@@ -683,8 +687,11 @@ pub fn backup_descriptor<'a>(
     email: Option<&'a str>,
     descriptor: &'a LianaDescriptor,
     keys_aliases: &'a HashMap<Fingerprint, String>,
+    diagram_exported: Option<&PathBuf>,
+    error: Option<&Error>,
     done: bool,
 ) -> Element<'a, Message> {
+    let diagram = policy::diagram(&descriptor.policy(), |fg| keys_aliases.get(fg).cloned());
     layout(
         progress,
         email,
@@ -745,19 +752,59 @@ pub fn backup_descriptor<'a>(
                 .max_width(1500),
             )
             .push(
-                card::simple(display_policy(descriptor.policy(), keys_aliases))
-                    .width(Length::Fill)
-                    .max_width(1500),
+                card::simple(
+                    Column::new()
+                        .spacing(10)
+                        .push(display_policy(descriptor.policy(), keys_aliases))
+                        .push(text(descriptor.human_readable_policy(keys_aliases)).small()),
+                )
+                .width(Length::Fill)
+                .max_width(1500),
+            )
+            .push(
+                card::simple(
+                    Column::new()
+                        .spacing(10)
+                        .push(text("The policy diagram:").small().bold())
+                        .push(scrollable(policy_diagram(diagram)).direction(
+                            scrollable::Direction::Horizontal(
+                                Properties::new().width(5).scroller_width(5),
+                            ),
+                        ))
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Alignment::Center)
+                                .push(Space::with_width(Length::Fill))
+                                .push_maybe(diagram_exported.map(|path| {
+                                    text(format!(
+                                        "Policy diagram exported to {}",
+                                        path.to_string_lossy()
+                                    ))
+                                    .small()
+                                    .style(color::GREEN)
+                                }))
+                                .push(
+                                    button::secondary(None, "Export as PNG")
+                                        .on_press(Message::ExportPolicyDiagram),
+                                ),
+                        ),
+                )
+                .width(Length::Fill)
+                .max_width(1500),
+            )
+            .push_maybe(
+                error.map(|e| card::error("Failed to export the policy diagram", e.to_string())),
             )
             .push(
                 checkbox("I have backed up my descriptor", done).on_toggle(Message::UserActionDone),
             )
             .push(if done {
-                button::secondary(None, "Next")
+                button::secondary(None, tr("common.next"))
                     .on_press(Message::Next)
                     .width(Length::Fixed(200.0))
             } else {
-                button::secondary(None, "Next").width(Length::Fixed(200.0))
+                button::secondary(None, tr("common.next")).width(Length::Fixed(200.0))
             })
             .push(Space::with_height(20.0))
             .spacing(50),
@@ -996,7 +1043,7 @@ pub fn define_bitcoin_node<'a>(
             Row::new()
                 .spacing(10)
                 .push(Container::new(
-                    button::secondary(None, "Check connection")
+                    button::secondary(None, tr("installer.check_connection"))
                         .on_press_maybe(if can_try_ping && !waiting_for_ping_result {
                             Some(Message::DefineNode(DefineNode::Ping))
                         } else {
@@ -1005,11 +1052,11 @@ pub fn define_bitcoin_node<'a>(
                         .width(Length::Fixed(200.0)),
                 ))
                 .push(if is_running.map(|res| res.is_ok()).unwrap_or(false) {
-                    button::secondary(None, "Next")
+                    button::secondary(None, tr("common.next"))
                         .on_press(Message::Next)
                         .width(Length::Fixed(200.0))
                 } else {
-                    button::secondary(None, "Next").width(Length::Fixed(200.0))
+                    button::secondary(None, tr("common.next")).width(Length::Fixed(200.0))
                 }),
         )
         .spacing(50);
@@ -1236,7 +1283,7 @@ pub fn select_bitcoind_type<'a>(progress: (usize, usize)) -> Element<'a, Message
                                 .width(Length::Fixed(300.0))
                                 .align_items(Alignment::Center)
                                 .push(
-                                    button::secondary(None, "Select")
+                                    button::secondary(None, tr("common.select"))
                                         .width(Length::Fixed(300.0))
                                         .on_press(Message::SelectBitcoindType(
                                             message::SelectBitcoindTypeMsg::UseExternal(true),
@@ -1252,7 +1299,7 @@ pub fn select_bitcoind_type<'a>(progress: (usize, usize)) -> Element<'a, Message
                                 .width(Length::Fixed(300.0))
                                 .align_items(Alignment::Center)
                                 .push(
-                                    button::secondary(None, "Select")
+                                    button::secondary(None, tr("common.select"))
                                         .width(Length::Fixed(300.0))
                                         .on_press(Message::SelectBitcoindType(
                                             message::SelectBitcoindTypeMsg::UseExternal(false),
@@ -1288,12 +1335,26 @@ pub fn start_internal_bitcoind<'a>(
                         .align_items(Alignment::Center)
                         .push(icon::circle_check_icon().style(color::GREEN))
                         .push(text("Download complete").style(color::GREEN)),
-                    DownloadState::Downloading { progress } => Row::new()
+                    DownloadState::Downloading(transferred) => Row::new()
                         .spacing(10)
                         .align_items(Alignment::Center)
                         .push(text(format!(
-                            "Downloading Bitcoin Core {version}... {progress:.2}%"
-                        ))),
+                            "Downloading Bitcoin Core {version}... {:.2}% ({:.1} / {:.1} MB)",
+                            transferred.percentage(),
+                            transferred.downloaded as f64 / 1_000_000.0,
+                            transferred.total as f64 / 1_000_000.0,
+                        )))
+                        .push_maybe(if transferred.retries > 0 {
+                            Some(
+                                text(format!(
+                                    "Connection lost, resumed {} time(s)",
+                                    transferred.retries
+                                ))
+                                .style(color::GREY_3),
+                            )
+                        } else {
+                            None
+                        }),
                     DownloadState::Errored(e) => Row::new()
                         .spacing(10)
                         .align_items(Alignment::Center)
@@ -1325,11 +1386,11 @@ pub fn start_internal_bitcoind<'a>(
                     .align_items(Alignment::Center)
                     .push(icon::circle_check_icon().style(color::GREEN))
                     .push(text("Liana-managed bitcoind already installed").style(color::GREEN))
-            } else if let Some(DownloadState::Downloading { progress }) = download_state {
+            } else if let Some(DownloadState::Downloading(transferred)) = download_state {
                 Row::new()
                     .spacing(10)
                     .align_items(Alignment::Center)
-                    .push(progress_bar(0.0..=100.0, *progress))
+                    .push(progress_bar(0.0..=100.0, transferred.percentage()))
             } else {
                 Row::new().spacing(10).align_items(Alignment::Center)
             }))
@@ -1370,7 +1431,7 @@ pub fn start_internal_bitcoind<'a>(
             .spacing(50)
             .push(
                 Row::new().push(
-                    button::secondary(None, "Next")
+                    button::secondary(None, tr("common.next"))
                         .width(Length::Fixed(200.0))
                         .on_press_maybe(if let Some(Ok(_)) = started {
                             Some(Message::Next)
@@ -1586,7 +1647,7 @@ pub fn hw_list_view(
                     "Device firmware version does not support taproot miniscript",
                 )
             } else {
-                hw::supported_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
+                supported_hardware_wallet(kind, version.as_ref(), fingerprint, alias.as_ref())
             }
         }
         HardwareWallet::Unsupported {
@@ -1707,11 +1768,11 @@ pub fn backup_mnemonic<'a>(
             )
             .push(checkbox("I have backed up my mnemonic", done).on_toggle(Message::UserActionDone))
             .push(if done {
-                button::secondary(None, "Next")
+                button::secondary(None, tr("common.next"))
                     .on_press(Message::Next)
                     .width(Length::Fixed(200.0))
             } else {
-                button::secondary(None, "Next").width(Length::Fixed(200.0))
+                button::secondary(None, tr("common.next")).width(Length::Fixed(200.0))
             })
             .push(Space::with_height(20.0))
             .spacing(50),
@@ -1792,12 +1853,12 @@ pub fn recover_mnemonic<'a>(
                 Row::new()
                     .spacing(10)
                     .push(
-                        button::secondary(None, "Import mnemonic")
+                        button::secondary(None, tr("installer.import_mnemonic"))
                             .on_press(Message::ImportMnemonic(true))
                             .width(Length::Fixed(200.0)),
                     )
                     .push(
-                        button::secondary(None, "Skip")
+                        button::secondary(None, tr("common.skip"))
                             .on_press(Message::Skip)
                             .width(Length::Fixed(200.0)),
                     )
@@ -1805,15 +1866,15 @@ pub fn recover_mnemonic<'a>(
                 Row::new()
                     .spacing(10)
                     .push(
-                        button::secondary(None, "Cancel")
+                        button::secondary(None, tr("common.cancel"))
                             .on_press(Message::ImportMnemonic(false))
                             .width(Length::Fixed(200.0)),
                     )
                     .push(
                         if words.iter().any(|(_, valid)| !valid) || error.is_some() {
-                            button::secondary(None, "Next").width(Length::Fixed(200.0))
+                            button::secondary(None, tr("common.next")).width(Length::Fixed(200.0))
                         } else {
-                            button::secondary(None, "Next")
+                            button::secondary(None, tr("common.next"))
                                 .on_press(Message::Next)
                                 .width(Length::Fixed(200.0))
                         },
@@ -1838,14 +1899,14 @@ pub fn choose_backend(progress: (usize, usize)) -> Element<'static, Message> {
                         Column::new()
                             .spacing(20)
                             .width(Length::FillPortion(1))
-                            .push(h3("Use your own node"))
+                            .push(h3(tr("installer.use_own_node")))
                             .push(text::p2_medium(LOCAL_WALLET_DESC).style(color::GREY_3)),
                     )
                     .push(
                         Column::new()
                             .spacing(20)
                             .width(Length::FillPortion(1))
-                            .push(h3("Use Liana Connect"))
+                            .push(h3(tr("installer.use_liana_connect")))
                             .push(text::p2_medium(REMOTE_BACKEND_DESC).style(color::GREY_3)),
                     ),
             )
@@ -1854,7 +1915,7 @@ pub fn choose_backend(progress: (usize, usize)) -> Element<'static, Message> {
                     .spacing(20)
                     .push(
                         Container::new(
-                            button::secondary(None, "Select")
+                            button::secondary(None, tr("common.select"))
                                 .on_press(Message::SelectBackend(
                                     message::SelectBackend::ContinueWithLocalWallet(true),
                                 ))
@@ -1864,7 +1925,7 @@ pub fn choose_backend(progress: (usize, usize)) -> Element<'static, Message> {
                     )
                     .push(
                         Container::new(
-                            button::secondary(None, "Select")
+                            button::secondary(None, tr("common.select"))
                                 .on_press(Message::SelectBackend(
                                     message::SelectBackend::ContinueWithLocalWallet(false),
                                 ))
@@ -1922,7 +1983,7 @@ pub fn connection_step_enter_email<'a>(
             .warning("Email is not valid"),
         )
         .push(
-            button::secondary(None, "Next")
+            button::secondary(None, tr("common.next"))
                 .on_press_maybe(if processing || !email.valid {
                     None
                 } else {
@@ -1962,11 +2023,13 @@ pub fn connection_step_enter_otp<'a>(
                         .on_press(Message::SelectBackend(message::SelectBackend::EditEmail)),
                 )
                 .push(
-                    button::secondary(None, "Resend token").on_press_maybe(if processing {
-                        None
-                    } else {
-                        Some(Message::SelectBackend(message::SelectBackend::RequestOTP))
-                    }),
+                    button::secondary(None, tr("installer.resend_token")).on_press_maybe(
+                        if processing {
+                            None
+                        } else {
+                            Some(Message::SelectBackend(message::SelectBackend::RequestOTP))
+                        },
+                    ),
                 ),
         )
         .into()
@@ -1991,7 +2054,7 @@ pub fn connection_step_connected<'a>(
                         .on_press(Message::SelectBackend(message::SelectBackend::EditEmail)),
                 )
                 .push(
-                    button::secondary(None, "Continue").on_press_maybe(if processing {
+                    button::secondary(None, tr("common.continue")).on_press_maybe(if processing {
                         None
                     } else {
                         Some(Message::Next)
@@ -2013,7 +2076,7 @@ fn layout<'a>(
     padding_left: bool,
     previous_message: Option<Message>,
 ) -> Element<'a, Message> {
-    let mut prev_button = button::transparent(Some(icon::previous_icon()), "Previous");
+    let mut prev_button = button::transparent(Some(icon::previous_icon()), tr("common.previous"));
     if let Some(msg) = previous_message {
         prev_button = prev_button.on_press(msg);
     }
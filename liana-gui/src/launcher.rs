@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use iced::{
     alignment::Horizontal,
@@ -15,7 +16,17 @@ use liana_ui::{
 };
 use lianad::config::ConfigError;
 
-use crate::{app, installer::UserFlow};
+use crate::{
+    app,
+    daemon::client,
+    i18n::{tr, tr_args},
+    installer::UserFlow,
+    loader::socket_path,
+    shutdown::Shutdown,
+};
+
+/// How long to wait for an external daemon to list the wallets it serves.
+const LIST_WALLETS_TIMEOUT: Duration = Duration::from_secs(5);
 
 const NETWORKS: [Network; 4] = [
     Network::Bitcoin,
@@ -31,6 +42,8 @@ pub enum State {
         name: Option<String>,
         email: Option<String>,
         checksum: Option<String>,
+        /// The wallets served by the external daemon, if it serves several.
+        daemon_wallets: Vec<String>,
     },
     NoWallet,
 }
@@ -67,7 +80,9 @@ impl Launcher {
         )
     }
 
-    pub fn stop(&mut self) {}
+    pub fn stop(&mut self) -> Shutdown {
+        Shutdown::new()
+    }
 
     pub fn subscription(&self) -> Subscription<Message> {
         Subscription::none()
@@ -136,13 +151,16 @@ impl Launcher {
                     Command::none()
                 }
             },
-            Message::View(ViewMessage::Run) => {
+            Message::View(ViewMessage::Run(daemon_wallet)) => {
                 if matches!(self.state, State::Wallet { .. }) {
                     let datadir_path = self.datadir_path.clone();
                     let mut path = self.datadir_path.clone();
                     path.push(self.network.to_string());
                     path.push(app::config::DEFAULT_FILE_NAME);
-                    let cfg = app::Config::from_file(&path).expect("Already checked");
+                    let mut cfg = app::Config::from_file(&path).expect("Already checked");
+                    if daemon_wallet.is_some() {
+                        cfg.daemon_wallet = daemon_wallet;
+                    }
                     let network = self.network;
                     Command::perform(async move { (datadir_path.clone(), cfg, network) }, |m| {
                         Message::Run(m.0, m.1, m.2)
@@ -171,7 +189,7 @@ impl Launcher {
                                 .width(Length::Fill),
                         )
                         .push(
-                            button::secondary(None, "Share Xpubs")
+                            button::secondary(None, tr("launcher.share_xpubs"))
                                 .on_press(ViewMessage::ShareXpubs),
                         )
                         .push(
@@ -192,13 +210,44 @@ impl Launcher {
                             .align_items(Alignment::Center)
                             .spacing(30)
                             .push(if matches!(self.state, State::Wallet { .. }) {
-                                text("Welcome back").size(50).bold()
+                                text(tr("launcher.welcome_back")).size(50).bold()
                             } else {
-                                text("Welcome").size(50).bold()
+                                text(tr("launcher.welcome")).size(50).bold()
                             })
                             .push_maybe(self.error.as_ref().map(|e| card::simple(text(e))))
                             .push(match &self.state {
                                 State::Unchecked => Column::new(),
+                                State::Wallet { daemon_wallets, .. }
+                                    if !daemon_wallets.is_empty() =>
+                                {
+                                    Column::new().push(
+                                        Row::new()
+                                            .align_items(Alignment::Center)
+                                            .spacing(20)
+                                            .push(daemon_wallets.iter().fold(
+                                                Column::new().spacing(10).width(Length::Fill),
+                                                |col, name| {
+                                                    col.push(
+                                                        Button::new(p1_bold(name))
+                                                            .on_press(ViewMessage::Run(Some(
+                                                                name.clone(),
+                                                            )))
+                                                            .style(theme::Button::Border)
+                                                            .padding(10)
+                                                            .width(Length::Fill),
+                                                    )
+                                                },
+                                            ))
+                                            .push(
+                                                Button::new(icon::trash_icon())
+                                                    .style(theme::Button::Secondary)
+                                                    .padding(10)
+                                                    .on_press(ViewMessage::DeleteWallet(
+                                                        DeleteWalletMessage::ShowModal,
+                                                    )),
+                                            ),
+                                    )
+                                }
                                 State::Wallet {
                                     email, checksum, ..
                                 } => Column::new().push(
@@ -208,15 +257,18 @@ impl Launcher {
                                         .push(
                                             Button::new(
                                                 Column::new()
-                                                    .push(p1_bold(format!(
-                                                        "My Liana {} wallet",
-                                                        match self.network {
-                                                            Network::Bitcoin => "Bitcoin",
-                                                            Network::Signet => "Signet",
-                                                            Network::Testnet => "Testnet",
-                                                            Network::Regtest => "Regtest",
-                                                            _ => "",
-                                                        }
+                                                    .push(p1_bold(tr_args(
+                                                        "launcher.my_wallet",
+                                                        &[(
+                                                            "network",
+                                                            match self.network {
+                                                                Network::Bitcoin => "Bitcoin",
+                                                                Network::Signet => "Signet",
+                                                                Network::Testnet => "Testnet",
+                                                                Network::Regtest => "Regtest",
+                                                                _ => "",
+                                                            },
+                                                        )],
                                                     )))
                                                     .push_maybe(checksum.as_ref().map(|checksum| {
                                                         p1_regular(format!("Liana-{}", checksum))
@@ -231,7 +283,7 @@ impl Launcher {
                                                             )
                                                     })),
                                             )
-                                            .on_press(ViewMessage::Run)
+                                            .on_press(ViewMessage::Run(None))
                                             .style(theme::Button::Border)
                                             .padding(10)
                                             .width(Length::Fill),
@@ -260,15 +312,18 @@ impl Launcher {
                                                                 .width(Length::Fixed(100.0)),
                                                         )
                                                         .push(
-                                                            p1_regular("Create a new Liana wallet")
-                                                                .style(color::GREY_3),
+                                                            p1_regular(tr(
+                                                                "launcher.create_wallet",
+                                                            ))
+                                                            .style(color::GREY_3),
                                                         )
                                                         .push(
-                                                            button::secondary(None, "Select")
-                                                                .width(Length::Fixed(200.0))
-                                                                .on_press(
-                                                                    ViewMessage::CreateWallet,
-                                                                ),
+                                                            button::secondary(
+                                                                None,
+                                                                tr("common.select"),
+                                                            )
+                                                            .width(Length::Fixed(200.0))
+                                                            .on_press(ViewMessage::CreateWallet),
                                                         )
                                                         .align_items(Alignment::Center),
                                                 )
@@ -284,17 +339,16 @@ impl Launcher {
                                                                 .width(Length::Fixed(100.0)),
                                                         )
                                                         .push(
-                                                            p1_regular(
-                                                                "Add an existing Liana wallet",
-                                                            )
-                                                            .style(color::GREY_3),
+                                                            p1_regular(tr("launcher.add_wallet"))
+                                                                .style(color::GREY_3),
                                                         )
                                                         .push(
-                                                            button::secondary(None, "Select")
-                                                                .width(Length::Fixed(200.0))
-                                                                .on_press(
-                                                                    ViewMessage::ImportWallet,
-                                                                ),
+                                                            button::secondary(
+                                                                None,
+                                                                tr("common.select"),
+                                                            )
+                                                            .width(Length::Fixed(200.0))
+                                                            .on_press(ViewMessage::ImportWallet),
                                                         )
                                                         .align_items(Alignment::Center),
                                                 )
@@ -344,7 +398,8 @@ pub enum ViewMessage {
     SelectNetwork(Network),
     StartInstall(Network),
     Check,
-    Run,
+    /// Open the wallet, or this one on an external daemon serving several wallets.
+    Run(Option<String>),
     DeleteWallet(DeleteWalletMessage),
 }
 
@@ -435,7 +490,7 @@ impl DeleteWalletModal {
                             Row::new()
                                 .spacing(10)
                                 .push(icon::circle_check_icon().style(color::GREEN))
-                                .push(text("Wallet successfully deleted").style(color::GREEN))
+                                .push(text(tr("launcher.wallet_deleted")).style(color::GREEN))
                         })
                         .align_x(Horizontal::Center)
                         .width(Length::Fill),
@@ -466,6 +521,20 @@ async fn check_network_datadir(path: PathBuf, network: Network) -> Result<State,
         }
     };
 
+    // Without a daemon configuration the GUI connects to an external daemon, which may serve
+    // several wallets. The RPC client is blocking, don't stall the executor while it waits.
+    let daemon_wallets = if cfg.daemon_config_path.is_none() {
+        let socket_path = cfg
+            .daemon_rpc_path
+            .clone()
+            .unwrap_or_else(|| socket_path(&path, network));
+        tokio::task::spawn_blocking(move || list_daemon_wallets(socket_path))
+            .await
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
     if let Some(daemon_config_path) = cfg.daemon_config_path {
         lianad::config::Config::from_file(Some(daemon_config_path.clone())).map_err(|e| match e {
         ConfigError::FileNotFound
@@ -503,6 +572,7 @@ async fn check_network_datadir(path: PathBuf, network: Network) -> Result<State,
                 name: Some(wallet.name),
                 checksum: Some(wallet.descriptor_checksum),
                 email: wallet.remote_backend_auth.map(|auth| auth.email),
+                daemon_wallets,
             });
         }
     }
@@ -510,5 +580,16 @@ async fn check_network_datadir(path: PathBuf, network: Network) -> Result<State,
         name: None,
         checksum: None,
         email: None,
+        daemon_wallets,
     })
 }
+
+/// The wallets served by the daemon listening on this socket. Empty if it serves a single wallet
+/// or can't be reached.
+fn list_daemon_wallets(socket_path: PathBuf) -> Vec<String> {
+    let mut rpc_client = client::jsonrpc::JsonRPCClient::new(socket_path);
+    rpc_client.set_timeout(Some(LIST_WALLETS_TIMEOUT));
+    client::Lianad::new(rpc_client)
+        .list_wallets()
+        .unwrap_or_default()
+}
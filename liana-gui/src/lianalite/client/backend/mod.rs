@@ -13,7 +13,7 @@ use liana::{
     miniscript::bitcoin::{address, psbt::Psbt, Address, Network, OutPoint, Txid},
 };
 use lianad::{
-    commands::{CoinStatus, GetInfoDescriptors, LCSpendInfo, LabelItem},
+    commands::{CoinStatus, GetInfoDescriptors, LCSpendInfo, LabelItem, RecoveryHeight},
     config::Config,
 };
 use reqwest::{Error, IntoUrl, Method, RequestBuilder, Response};
@@ -584,10 +584,16 @@ impl Daemon for BackendWalletClient {
                 main: wallet.descriptor.to_owned(),
             },
             sync: 1.0,
+            backend_blocks: wallet.tip_height.unwrap_or(0) as u64,
+            backend_headers: wallet.tip_height.unwrap_or(0) as u64,
             rescan_progress: None,
             timestamp: wallet.created_at as u32,
             // We can ignore this field for remote backend as the wallet should remain synced.
             last_poll_timestamp: None,
+            // The database is not ours to maintain.
+            db_size: 0,
+            db_free_size: 0,
+            warnings: Vec::new(),
         })
     }
 
@@ -634,6 +640,15 @@ impl Daemon for BackendWalletClient {
         } else {
             self.list_wallet_coins(statuses, outpoints).await?.coins
         };
+        // The backend doesn't provide the recovery heights of the coins, derive them from
+        // the descriptor like lianad does.
+        let recovery_timelocks: Vec<u16> = self
+            .wallet_desc
+            .policy()
+            .recovery_paths()
+            .keys()
+            .copied()
+            .collect();
         Ok(ListCoinsResult {
             coins: coins
                 .into_iter()
@@ -650,6 +665,19 @@ impl Daemon for BackendWalletClient {
                         height: info.height,
                     }),
                     is_from_self: c.is_from_self,
+                    recovery_heights: c
+                        .block_height
+                        .map(|height| {
+                            recovery_timelocks
+                                .iter()
+                                .map(|timelock| RecoveryHeight {
+                                    timelock: *timelock,
+                                    height: height + i32::from(*timelock),
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    tag: None,
                 })
                 .collect(),
         })
@@ -664,6 +692,7 @@ impl Daemon for BackendWalletClient {
                 .map(|psbt| ListSpendEntry {
                     psbt: psbt.raw,
                     updated_at: Some(psbt.updated_at as u32),
+                    broadcast_time: None,
                 })
                 .collect(),
         })
@@ -714,7 +743,14 @@ impl Daemon for BackendWalletClient {
         destinations: &HashMap<Address<address::NetworkUnchecked>, u64>,
         feerate_vb: u64,
         change_address: Option<Address<address::NetworkUnchecked>>,
+        // Coins have no privacy tags on this backend.
+        _avoid_mixing_tags: bool,
+        spend_path: Option<u16>,
     ) -> Result<CreateSpendResult, DaemonError> {
+        // Spending through a recovery path goes through the recovery flow on this backend.
+        if spend_path.is_some() {
+            return Err(DaemonError::ClientNotSupported);
+        }
         let mut recipients: Vec<api::payload::Recipient> = destinations
             .iter()
             .map(|(addr, amt)| api::payload::Recipient {
@@ -753,6 +789,7 @@ impl Daemon for BackendWalletClient {
         match res {
             api::DraftPsbtResult::Success(draft) => Ok(CreateSpendResult::Success {
                 psbt: draft.raw,
+                change_output: None,
                 warnings: draft.warnings,
             }),
             api::DraftPsbtResult::InsufficientFunds(api::InsufficientFundsInfo { missing }) => {
@@ -793,6 +830,7 @@ impl Daemon for BackendWalletClient {
         match res {
             api::DraftPsbtResult::Success(draft) => Ok(CreateSpendResult::Success {
                 psbt: draft.raw,
+                change_output: None,
                 warnings: draft.warnings,
             }),
             api::DraftPsbtResult::InsufficientFunds(api::InsufficientFundsInfo { missing }) => {
@@ -1135,6 +1173,8 @@ fn history_tx_from_api(value: api::Transaction, network: Network) -> HistoryTran
                         height: info.height,
                     }),
                     is_from_self: c.is_from_self,
+                    recovery_heights: Vec::new(),
+                    tag: None,
                 });
             }
         }
@@ -1191,6 +1231,8 @@ fn spend_tx_from_api(
                         height: info.height,
                     }),
                     is_from_self: c.is_from_self,
+                    recovery_heights: Vec::new(),
+                    tag: None,
                 });
             }
         }
@@ -5,13 +5,16 @@ use iced::{Alignment, Command, Length};
 use liana::miniscript::bitcoin::Network;
 use liana_ui::{
     color,
-    component::{button, form, network_banner, notification, text::*},
+    component::{amount::BitcoinDisplayUnit, button, form, network_banner, notification, text::*},
     icon,
     widget::*,
 };
 
 use crate::{
-    app::settings::{AuthConfig, Settings, SettingsError, WalletSetting},
+    app::settings::{
+        AuthConfig, HotSignerUnlock, NotificationSettings, Settings, SettingsError, WalletSetting,
+        DEFAULT_EXPIRY_WARNING_DAYS, DEFAULT_REFRESH_MAX_FEE_PERCENT,
+    },
     daemon::DaemonError,
 };
 
@@ -519,6 +522,13 @@ async fn update_wallet_auth_settings(
                 keys: Vec::new(),
                 hardware_wallets: Vec::new(),
                 remote_backend_auth,
+                scheduled_broadcasts: Vec::new(),
+                expiry_warning_days: DEFAULT_EXPIRY_WARNING_DAYS.to_vec(),
+                refresh_max_fee_percent: DEFAULT_REFRESH_MAX_FEE_PERCENT,
+                notifications: NotificationSettings::default(),
+                bitcoin_unit: BitcoinDisplayUnit::default(),
+                auto_lock: None,
+                hot_signer_unlock: HotSignerUnlock::default(),
             },
         );
     }
@@ -1,15 +1,19 @@
 pub mod app;
+pub mod camera;
 pub mod daemon;
 pub mod datadir;
 pub mod download;
 pub mod export;
 pub mod hw;
+pub mod i18n;
 pub mod installer;
 pub mod launcher;
 pub mod lianalite;
 pub mod loader;
 pub mod logger;
 pub mod node;
+pub mod profile;
+pub mod shutdown;
 pub mod signer;
 pub mod utils;
 
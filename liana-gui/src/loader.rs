@@ -1,19 +1,19 @@
+use std::collections::VecDeque;
 use std::convert::From;
 use std::fs::File;
 use std::io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use iced::{Alignment, Command, Length, Subscription};
-use tokio::runtime::Handle;
+use iced::{widget::Space, Alignment, Command, Length, Subscription};
 use tracing::{debug, info, warn};
 
 use liana::miniscript::bitcoin;
 use liana_ui::{
     color,
     component::{button, notification, text::*},
-    icon,
+    icon, theme,
     widget::*,
 };
 use lianad::{
@@ -26,18 +26,21 @@ use crate::{
     app::{
         cache::Cache,
         config::Config as GUIConfig,
+        menu::Menu,
         wallet::{Wallet, WalletError},
     },
     daemon::{client, embedded::EmbeddedDaemon, model::*, Daemon, DaemonError},
-    node::bitcoind::{
-        internal_bitcoind_debug_log_path, stop_bitcoind, Bitcoind, StartInternalBitcoindError,
-    },
+    node::bitcoind::{internal_bitcoind_debug_log_path, Bitcoind, StartInternalBitcoindError},
+    shutdown::Shutdown,
 };
 
 const SYNCING_PROGRESS_1: &str = "Bitcoin Core is synchronising the blockchain. A full synchronisation typically takes a few days and is resource-intensive. Once the initial synchronisation is done, the next ones will be much faster.";
 const SYNCING_PROGRESS_2: &str = "Bitcoin Core is synchronising the blockchain. This will take a while, depending on the last time it was done, your internet connection, and your computer performance.";
 const SYNCING_PROGRESS_3: &str = "Bitcoin Core is synchronising the blockchain. This may take a few minutes, depending on the last time it was done, your internet connection, and your computer performance.";
 
+/// How far back the speed of the blockchain synchronisation is measured.
+const SYNC_SPEED_WINDOW: Duration = Duration::from_secs(10 * 60);
+
 type Lianad = client::Lianad<client::jsonrpc::JsonRPCClient>;
 type StartedResult = Result<
     (
@@ -55,16 +58,21 @@ pub struct Loader {
     pub daemon_started: bool,
     pub internal_bitcoind: Option<Bitcoind>,
     pub waiting_daemon_bitcoind: bool,
+    /// The panel of the application to go back to once loaded, if the daemon is restarted.
+    pub restore_menu: Option<Menu>,
 
     step: Step,
 }
 
 pub enum Step {
+    /// Waiting for the previous internal daemon to stop.
+    Restarting,
     Connecting,
     StartingDaemon,
     Syncing {
         daemon: Arc<dyn Daemon + Sync + Send>,
-        progress: f64,
+        sync: BlockchainSync,
+        speed: SyncSpeed,
         bitcoind_logs: String,
     },
     Error(Box<Error>),
@@ -74,7 +82,7 @@ pub enum Step {
 #[derive(Debug)]
 pub enum Message {
     View(ViewMessage),
-    Syncing(Result<GetInfoResult, DaemonError>),
+    Syncing(Result<BlockchainSync, DaemonError>),
     Synced(
         Result<
             (
@@ -87,6 +95,7 @@ pub enum Message {
         >,
     ),
     Started(StartedResult),
+    Stopped(Result<(), DaemonError>),
     Loaded(Result<(Arc<dyn Daemon + Sync + Send>, GetInfoResult), Error>),
     BitcoindLog(Option<String>),
     Failure(DaemonError),
@@ -104,6 +113,7 @@ impl Loader {
             .daemon_rpc_path
             .clone()
             .unwrap_or_else(|| socket_path(&datadir_path, network));
+        let wallet = gui_config.daemon_wallet.clone();
         (
             Loader {
                 network,
@@ -113,8 +123,35 @@ impl Loader {
                 daemon_started: false,
                 internal_bitcoind,
                 waiting_daemon_bitcoind: false,
+                restore_menu: None,
             },
-            Command::perform(connect(path), Message::Loaded),
+            Command::perform(connect(path, wallet), Message::Loaded),
+        )
+    }
+
+    /// Stop the internal daemon of a running application, then load it again with a new one.
+    /// The managed bitcoind, if any, keeps running.
+    pub fn restart(
+        datadir_path: PathBuf,
+        gui_config: GUIConfig,
+        network: bitcoin::Network,
+        internal_bitcoind: Option<Bitcoind>,
+        daemon: Arc<dyn Daemon + Sync + Send>,
+        menu: Menu,
+    ) -> (Self, Command<Message>) {
+        info!("Restarting internal daemon...");
+        (
+            Loader {
+                network,
+                datadir_path,
+                gui_config,
+                step: Step::Restarting,
+                daemon_started: false,
+                internal_bitcoind,
+                waiting_daemon_bitcoind: false,
+                restore_menu: Some(menu),
+            },
+            Command::perform(async move { daemon.stop().await }, Message::Stopped),
         )
     }
 
@@ -138,9 +175,13 @@ impl Loader {
             );
         }
         // Otherwise, show the sync progress on the loading screen.
+        let progress = BlockchainSync::from(&info);
+        let mut speed = SyncSpeed::default();
+        speed.record(Instant::now(), progress.blocks);
         self.step = Step::Syncing {
             daemon: daemon.clone(),
-            progress: 0.0,
+            sync: progress,
+            speed,
             bitcoind_logs: String::new(),
         };
         Command::perform(sync(daemon, false), Message::Syncing)
@@ -216,27 +257,21 @@ impl Loader {
         }
     }
 
-    fn on_sync(&mut self, res: Result<GetInfoResult, DaemonError>) -> Command<Message> {
+    fn on_sync(&mut self, res: Result<BlockchainSync, DaemonError>) -> Command<Message> {
         match &mut self.step {
             Step::Syncing {
-                daemon, progress, ..
+                daemon,
+                sync: progress,
+                speed,
+                ..
             } => {
                 match res {
-                    Ok(info) => {
-                        if (info.sync - 1.0_f64).abs() < f64::EPSILON {
-                            return Command::perform(
-                                load_application(
-                                    daemon.clone(),
-                                    info,
-                                    self.datadir_path.clone(),
-                                    self.network,
-                                    self.internal_bitcoind.clone(),
-                                ),
-                                Message::Synced,
-                            );
-                        } else {
-                            *progress = info.sync
+                    Ok(new_progress) => {
+                        if (new_progress.progress - 1.0_f64).abs() < f64::EPSILON {
+                            return self.load_application();
                         }
+                        speed.record(Instant::now(), new_progress.blocks);
+                        *progress = new_progress;
                     }
                     Err(e) => {
                         self.step = Step::Error(Box::new(e.into()));
@@ -249,49 +284,60 @@ impl Loader {
         }
     }
 
-    pub fn stop(&mut self) {
+    /// Open the application with the syncing daemon, even if the blockchain synchronisation is
+    /// not complete. It goes on in the background.
+    fn load_application(&mut self) -> Command<Message> {
+        if let Step::Syncing { daemon, .. } = &self.step {
+            let daemon = daemon.clone();
+            let datadir_path = self.datadir_path.clone();
+            let network = self.network;
+            let internal_bitcoind = self.internal_bitcoind.clone();
+            Command::perform(
+                async move {
+                    let info = daemon.get_info().await?;
+                    load_application(daemon, info, datadir_path, network, internal_bitcoind).await
+                },
+                Message::Synced,
+            )
+        } else {
+            Command::none()
+        }
+    }
+
+    /// What must be stopped before exiting.
+    pub fn stop(&mut self) -> Shutdown {
         info!("Close requested");
-        if let Step::Syncing { daemon, .. } = &mut self.step {
-            if daemon.backend().is_embedded() {
-                info!("Stopping internal daemon...");
-                if let Err(e) = Handle::current().block_on(async { daemon.stop().await }) {
-                    warn!("Internal daemon failed to stop: {}", e);
-                } else {
-                    info!("Internal daemon stopped");
-                }
-            }
+        let mut shutdown = Shutdown::new();
+        if let Step::Syncing { daemon, .. } = &self.step {
+            shutdown = shutdown.with_daemon(daemon.clone());
         }
 
         // NOTE: we take() the internal_bitcoind here to make sure the debug.log reader
         // subscription is dropped.
         if let Some(bitcoind) = self.internal_bitcoind.take() {
-            log::info!("Stopping managed bitcoind..");
-            bitcoind.stop();
-            log::info!("Managed bitcoind stopped.");
+            shutdown = shutdown.with_bitcoind(Some(bitcoind));
         } else if self.waiting_daemon_bitcoind && self.gui_config.start_internal_bitcoind {
             if let Ok(config) = Config::from_file(self.gui_config.daemon_config_path.clone()) {
-                if let Some(BitcoinBackend::Bitcoind(bitcoind_config)) = &config.bitcoin_backend {
-                    let mut retry = 0;
-                    while !stop_bitcoind(bitcoind_config) && retry < 10 {
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-                        retry += 1;
-                    }
+                if let Some(BitcoinBackend::Bitcoind(bitcoind_config)) = config.bitcoin_backend {
+                    shutdown = shutdown.with_bitcoind_config(bitcoind_config);
                 }
             }
         }
+        shutdown
     }
 
     pub fn update(&mut self, message: Message) -> Command<Message> {
         match message {
-            Message::View(ViewMessage::Retry) => {
-                let (loader, cmd) = Self::new(
-                    self.datadir_path.clone(),
-                    self.gui_config.clone(),
-                    self.network,
-                    self.internal_bitcoind.clone(),
-                );
-                *self = loader;
-                cmd
+            Message::View(ViewMessage::Retry) => self.reload(),
+            Message::View(ViewMessage::ContinueInBackground) => self.load_application(),
+            Message::Stopped(res) => {
+                // A crashed daemon was already stopped, its error was shown to the user.
+                if let Err(e) = res {
+                    warn!("Internal daemon failed to stop: {}", e);
+                } else {
+                    info!("Internal daemon stopped");
+                }
+                self.reload()
             }
             Message::Started(res) => self.on_start(res),
             Message::Loaded(res) => self.on_load(res),
@@ -310,6 +356,18 @@ impl Loader {
         }
     }
 
+    fn reload(&mut self) -> Command<Message> {
+        let (mut loader, cmd) = Self::new(
+            self.datadir_path.clone(),
+            self.gui_config.clone(),
+            self.network,
+            self.internal_bitcoind.clone(),
+        );
+        loader.restore_menu = self.restore_menu.take();
+        *self = loader;
+        cmd
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
         if self.internal_bitcoind.is_some() {
             let log_path = internal_bitcoind_debug_log_path(&self.datadir_path, self.network);
@@ -420,10 +478,19 @@ pub async fn load_application(
 pub enum ViewMessage {
     Retry,
     SwitchNetwork,
+    /// Open the application without waiting for the end of the blockchain synchronisation.
+    ContinueInBackground,
 }
 
 pub fn view(step: &Step) -> Element<ViewMessage> {
     match &step {
+        Step::Restarting => cover(
+            None,
+            Column::new()
+                .width(Length::Fill)
+                .push(ProgressBar::new(0.0..=1.0, 0.0).width(Length::Fill))
+                .push(text("Restarting wallet backend...")),
+        ),
         Step::StartingDaemon => cover(
             None,
             Column::new()
@@ -439,7 +506,8 @@ pub fn view(step: &Step) -> Element<ViewMessage> {
                 .push(text("Connecting to daemon...")),
         ),
         Step::Syncing {
-            progress,
+            sync,
+            speed,
             bitcoind_logs,
             ..
         } => cover(
@@ -447,16 +515,23 @@ pub fn view(step: &Step) -> Element<ViewMessage> {
             Column::new()
                 .width(Length::Fill)
                 .spacing(5)
-                .push(text(format!("Progress {:.2}%", 100.0 * *progress)))
-                .push(ProgressBar::new(0.0..=1.0, *progress as f32).width(Length::Fill))
-                .push(text(if *progress > 0.98 {
+                .push(text(format!("Progress {:.2}%", 100.0 * sync.progress)))
+                .push(ProgressBar::new(0.0..=1.0, sync.progress as f32).width(Length::Fill))
+                .push(text(if sync.progress > 0.98 {
                     SYNCING_PROGRESS_3
-                } else if *progress > 0.9 {
+                } else if sync.progress > 0.9 {
                     SYNCING_PROGRESS_2
                 } else {
                     SYNCING_PROGRESS_1
                 }))
-                .push(p2_regular(bitcoind_logs).style(color::GREY_3)),
+                .push(sync_details(sync, speed))
+                .push(p2_regular(bitcoind_logs).style(color::GREY_3))
+                .push(
+                    Row::new().push(Space::with_width(Length::Fill)).push(
+                        button::secondary(None, "Continue in background")
+                            .on_press(ViewMessage::ContinueInBackground),
+                    ),
+                ),
         ),
         Step::Error(error) => cover(
             Some(("Error while starting the internal daemon", error)),
@@ -492,6 +567,84 @@ pub fn view(step: &Step) -> Element<ViewMessage> {
     }
 }
 
+/// The heights of the blockchain and the estimated time left, once enough progress was
+/// measured.
+fn sync_details<'a>(sync: &BlockchainSync, speed: &SyncSpeed) -> Element<'a, ViewMessage> {
+    let blocks_per_minute = speed.blocks_per_minute();
+    Container::new(
+        Column::new()
+            .spacing(5)
+            .push(p1_regular(format!(
+                "Blocks validated: {} / {}",
+                sync.blocks, sync.headers
+            )))
+            .push(p1_regular(match blocks_per_minute {
+                Some(speed) => format!("Speed: {:.0} blocks/min", speed),
+                None => "Speed: measuring...".to_string(),
+            }))
+            .push_maybe(
+                speed
+                    .time_left(sync.blocks, sync.headers)
+                    .map(|eta| p1_regular(format!("Estimated time left: {}", format_eta(eta)))),
+            ),
+    )
+    .padding(15)
+    .width(Length::Fill)
+    .style(theme::Container::Card(theme::Card::Simple))
+    .into()
+}
+
+fn format_eta(eta: Duration) -> String {
+    let minutes = eta.as_secs() / 60;
+    if minutes == 0 {
+        "less than a minute".to_string()
+    } else if minutes < 60 {
+        format!("≈ {}min", minutes)
+    } else if minutes < 24 * 60 {
+        format!("≈ {}h {}min", minutes / 60, minutes % 60)
+    } else {
+        format!("≈ {}d {}h", minutes / (24 * 60), minutes / 60 % 24)
+    }
+}
+
+/// Measures the speed of the blockchain synchronisation over a sliding window.
+#[derive(Debug, Default)]
+pub struct SyncSpeed {
+    // The number of blocks validated at the time of each measure, oldest first.
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl SyncSpeed {
+    fn record(&mut self, now: Instant, blocks: u64) {
+        if self.samples.back().map(|(_, b)| *b) == Some(blocks) {
+            // The poller of the daemon updates the progress less often than we query it.
+            return;
+        }
+        self.samples.push_back((now, blocks));
+        while self.samples.len() > 2 && now.duration_since(self.samples[0].0) > SYNC_SPEED_WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The number of blocks validated per minute over the window, if it was measured.
+    pub fn blocks_per_minute(&self) -> Option<f64> {
+        let (first_time, first_blocks) = self.samples.front()?;
+        let (last_time, last_blocks) = self.samples.back()?;
+        let elapsed = last_time.duration_since(*first_time).as_secs_f64();
+        if elapsed < 1.0 || last_blocks <= first_blocks {
+            return None;
+        }
+        Some((last_blocks - first_blocks) as f64 * 60.0 / elapsed)
+    }
+
+    /// The time left to validate the blocks up to `headers` at the current speed.
+    pub fn time_left(&self, blocks: u64, headers: u64) -> Option<Duration> {
+        let speed = self.blocks_per_minute()?;
+        let minutes = headers.saturating_sub(blocks) as f64 / speed;
+        Some(Duration::from_secs_f64(minutes * 60.0))
+    }
+}
+
 pub fn cover<'a, T: 'a + Clone, C: Into<Element<'a, T>>>(
     warn: Option<(&'static str, &Error)>,
     content: C,
@@ -511,9 +664,10 @@ pub fn cover<'a, T: 'a + Clone, C: Into<Element<'a, T>>>(
 
 async fn connect(
     socket_path: PathBuf,
+    wallet: Option<String>,
 ) -> Result<(Arc<dyn Daemon + Sync + Send>, GetInfoResult), Error> {
     let client = client::jsonrpc::JsonRPCClient::new(socket_path);
-    let daemon = Lianad::new(client);
+    let daemon = Lianad::new(client).with_wallet(wallet);
 
     debug!("Searching for external daemon");
     let info = daemon.get_info().await?;
@@ -561,11 +715,11 @@ pub async fn start_bitcoind_and_daemon(
 async fn sync(
     daemon: Arc<dyn Daemon + Sync + Send>,
     sleep: bool,
-) -> Result<GetInfoResult, DaemonError> {
+) -> Result<BlockchainSync, DaemonError> {
     if sleep {
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
-    daemon.get_info().await
+    daemon.sync_progress().await
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -609,9 +763,40 @@ impl From<DaemonError> for Error {
 }
 
 /// default lianad socket path is .liana/bitcoin/lianad_rpc
-fn socket_path(datadir: &Path, network: bitcoin::Network) -> PathBuf {
+pub fn socket_path(datadir: &Path, network: bitcoin::Network) -> PathBuf {
     let mut path = datadir.to_path_buf();
     path.push(network.to_string());
     path.push("lianad_rpc");
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_speed() {
+        let start = Instant::now();
+        let mut speed = SyncSpeed::default();
+        speed.record(start, 1_000);
+        assert_eq!(speed.blocks_per_minute(), None);
+        assert_eq!(speed.time_left(1_000, 2_000), None);
+
+        // The same height reported again is not a new measure.
+        speed.record(start + Duration::from_secs(30), 1_000);
+        assert_eq!(speed.blocks_per_minute(), None);
+
+        speed.record(start + Duration::from_secs(60), 1_100);
+        assert_eq!(speed.blocks_per_minute(), Some(100.0));
+        assert_eq!(
+            speed.time_left(1_100, 2_100),
+            Some(Duration::from_secs(600))
+        );
+
+        // The measures older than the window are forgotten.
+        speed.record(start + SYNC_SPEED_WINDOW + Duration::from_secs(120), 1_200);
+        speed.record(start + SYNC_SPEED_WINDOW + Duration::from_secs(180), 1_500);
+        assert_eq!(speed.samples.len(), 2);
+        assert_eq!(speed.blocks_per_minute(), Some(300.0));
+    }
+}
@@ -8,23 +8,28 @@ use std::{
 use iced::window::settings::PlatformSpecific;
 use iced::{
     event::{self, Event},
-    executor, keyboard,
+    executor, keyboard, mouse,
     widget::{focus_next, focus_previous},
     Application, Command, Settings, Size, Subscription,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::filter::LevelFilter;
 extern crate serde;
 extern crate serde_json;
 
 use liana::miniscript::bitcoin;
-use liana_ui::{component::text, font, image, theme, widget::Element};
+use liana_ui::{
+    component::text,
+    font, image, theme,
+    widget::{Container, Element},
+};
 use lianad::config::Config as DaemonConfig;
 
 use liana_gui::{
     app::{self, cache::Cache, config::default_datadir, wallet::Wallet, App},
     datadir,
     hw::HardwareWalletConfig,
+    i18n::{self, tr},
     installer::{self, Installer},
     launcher::{self, Launcher},
     lianalite::{
@@ -33,6 +38,8 @@ use liana_gui::{
     },
     loader::{self, Loader},
     logger::Logger,
+    profile::Profile,
+    shutdown::{Shutdown, SHUTDOWN_TIMEOUT},
     VERSION,
 };
 
@@ -41,6 +48,7 @@ enum Arg {
     ConfigPath(PathBuf),
     DatadirPath(PathBuf),
     Network(bitcoin::Network),
+    ProfilePath(PathBuf),
 }
 
 fn parse_args(args: Vec<String>) -> Result<Vec<Arg>, Box<dyn Error>> {
@@ -59,6 +67,7 @@ Usage: liana-gui [OPTIONS]
 Options:
     --conf <PATH>       Path of configuration file (gui.toml)
     --datadir <PATH>    Path of liana datadir
+    --profile <PATH>    Path of a configuration profile to use for new wallets
     -v, --version       Display liana-gui version
     -h, --help          Print help
     --bitcoin           Use bitcoin network
@@ -83,6 +92,12 @@ Options:
             } else {
                 return Err("missing arg to --datadir".into());
             }
+        } else if arg == "--profile" {
+            if let Some(a) = args.get(i + 1) {
+                res.push(Arg::ProfilePath(PathBuf::from(a)));
+            } else {
+                return Err("missing arg to --profile".into());
+            }
         } else if arg.contains("--") {
             let network = bitcoin::Network::from_str(args[i].trim_start_matches("--"))?;
             res.push(Arg::Network(network));
@@ -97,6 +112,10 @@ pub struct GUI {
     logger: Logger,
     // if set up, it overrides the level filter of the logger.
     log_level: Option<LevelFilter>,
+    // Whether the wallet backend and the managed bitcoind are being stopped before exiting.
+    shutting_down: bool,
+    // Whether the running wallet is being stopped before going back to the launcher.
+    switching_wallet: bool,
 }
 
 enum State {
@@ -110,6 +129,9 @@ enum State {
 #[derive(Debug)]
 pub enum Key {
     Tab(bool),
+    /// A key pressed along with the command modifier, or one used to navigate the command
+    /// palette, and whether a widget captured it.
+    Shortcut(keyboard::Key, keyboard::Modifiers, bool),
 }
 
 #[derive(Debug)]
@@ -122,7 +144,13 @@ pub enum Message {
     Run(Box<app::Message>),
     Login(Box<login::Message>),
     KeyPressed(Key),
+    /// Any click, scroll or key press, delaying the auto-lock.
+    UserActivity,
     Event(iced::Event),
+    /// Whether everything was stopped in time before exiting.
+    ShutdownDone(bool),
+    /// Whether the wallet was stopped in time before going back to the launcher.
+    WalletStopped(bool),
 }
 
 impl From<Result<(), iced::font::Error>> for Message {
@@ -146,8 +174,9 @@ impl Application for GUI {
     type Theme = theme::Theme;
 
     fn title(&self) -> String {
-        match self.state {
+        match &self.state {
             State::Installer(_) => format!("Liana v{} Installer", VERSION),
+            State::App(a) => format!("Liana v{} - {}", VERSION, a.wallet_name()),
             _ => format!("Liana v{}", VERSION),
         }
     }
@@ -156,6 +185,12 @@ impl Application for GUI {
         let logger = Logger::setup(log_level.unwrap_or(LevelFilter::INFO));
         let mut cmds = font::loads();
         cmds.push(Command::perform(ctrl_c(), |_| Message::CtrlC));
+        let datadir_path = match &config {
+            Config::Launcher(datadir_path) | Config::Run(datadir_path, ..) => datadir_path,
+        };
+        if let Some(language) = i18n::load_language(datadir_path) {
+            i18n::set_language(language);
+        }
         let state = match config {
             Config::Launcher(datadir_path) => {
                 let (launcher, command) = Launcher::new(datadir_path, None);
@@ -178,24 +213,67 @@ impl Application for GUI {
                 state,
                 logger,
                 log_level,
+                shutting_down: false,
+                switching_wallet: false,
             },
             Command::batch(cmds),
         )
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        if let (State::App(a), Message::KeyPressed(_) | Message::UserActivity) =
+            (&mut self.state, &message)
+        {
+            a.record_activity();
+        }
         match (&mut self.state, message) {
             (_, Message::CtrlC)
             | (_, Message::Event(iced::Event::Window(_, iced::window::Event::CloseRequested))) => {
-                match &mut self.state {
+                if self.shutting_down {
+                    return Command::none();
+                }
+                self.shutting_down = true;
+                let shutdown = match &mut self.state {
                     State::Loader(s) => s.stop(),
                     State::Launcher(s) => s.stop(),
                     State::Installer(s) => s.stop(),
                     State::App(s) => s.stop(),
-                    State::Login(_) => {}
+                    State::Login(_) => Shutdown::new(),
                 };
+                Command::perform(shutdown.run(), Message::ShutdownDone)
+            }
+            (_, Message::ShutdownDone(stopped)) => {
+                if !stopped {
+                    warn!(
+                        "Could not stop everything within {} seconds, exiting anyway",
+                        SHUTDOWN_TIMEOUT.as_secs()
+                    );
+                }
                 iced::window::close(iced::window::Id::MAIN)
             }
+            // The state is being stopped, it must not start anything new.
+            (_, Message::Launch(_) | Message::Install(_) | Message::Load(_) | Message::Run(_))
+                if self.shutting_down =>
+            {
+                Command::none()
+            }
+            // The wallet is being stopped, it must not be interacted with anymore.
+            (State::App(_), Message::Run(_) | Message::KeyPressed(_)) if self.switching_wallet => {
+                Command::none()
+            }
+            (State::App(i), Message::WalletStopped(stopped)) => {
+                if !stopped {
+                    warn!(
+                        "Could not stop the wallet within {} seconds",
+                        SHUTDOWN_TIMEOUT.as_secs()
+                    );
+                }
+                self.switching_wallet = false;
+                let (launcher, command) =
+                    Launcher::new(i.datadir_path().clone(), Some(i.network()));
+                self.state = State::Launcher(Box::new(launcher));
+                command.map(|msg| Message::Launch(Box::new(msg)))
+            }
             (_, Message::KeyPressed(Key::Tab(shift))) => {
                 log::debug!("Tab pressed!");
                 if shift {
@@ -204,6 +282,9 @@ impl Application for GUI {
                     focus_next()
                 }
             }
+            (State::App(i), Message::KeyPressed(Key::Shortcut(key, modifiers, captured))) => i
+                .update(app::Message::KeyPressed(key, modifiers, captured))
+                .map(|msg| Message::Run(Box::new(msg))),
             (State::Launcher(l), Message::Launch(msg)) => match *msg {
                 launcher::Message::Install(datadir_path, network, init) => {
                     if !datadir_path.exists() {
@@ -358,7 +439,7 @@ impl Application for GUI {
                     command.map(|msg| Message::Launch(Box::new(msg)))
                 }
                 loader::Message::Synced(Ok((wallet, cache, daemon, bitcoind))) => {
-                    let (app, command) = App::new(
+                    let (mut app, command) = App::new(
                         cache,
                         wallet,
                         loader.gui_config.clone(),
@@ -366,13 +447,31 @@ impl Application for GUI {
                         loader.datadir_path.clone(),
                         bitcoind,
                     );
+                    let command = if let Some(menu) = loader.restore_menu.take() {
+                        Command::batch(vec![command, app.set_current_panel(menu)])
+                    } else {
+                        command
+                    };
                     self.state = State::App(app);
                     command.map(|msg| Message::Run(Box::new(msg)))
                 }
                 _ => loader.update(*msg).map(|msg| Message::Load(Box::new(msg))),
             },
             (State::App(i), Message::Run(msg)) => {
-                i.update(*msg).map(|msg| Message::Run(Box::new(msg)))
+                if let app::Message::View(app::view::Message::RestartDaemon) = *msg {
+                    // The application is dropped with all its clones of the daemon, only the
+                    // loader keeps one to stop it.
+                    let (loader, command) = i.restart_daemon();
+                    self.state = State::Loader(Box::new(loader));
+                    command.map(|msg| Message::Load(Box::new(msg)))
+                } else if let app::Message::View(app::view::Message::SwitchWallet) = *msg {
+                    // The wallet is stopped before the launcher is displayed, for it to be
+                    // opened again right away if need be.
+                    self.switching_wallet = true;
+                    Command::perform(i.stop().run(), Message::WalletStopped)
+                } else {
+                    i.update(*msg).map(|msg| Message::Run(Box::new(msg)))
+                }
             }
             _ => Command::none(),
         }
@@ -396,6 +495,30 @@ impl Application for GUI {
                     }),
                     event::Status::Ignored,
                 ) => Some(Message::KeyPressed(Key::Tab(modifiers.shift()))),
+                (Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }), _)
+                    if modifiers.command()
+                        || matches!(
+                            key,
+                            keyboard::Key::Named(
+                                keyboard::key::Named::Escape
+                                    | keyboard::key::Named::ArrowUp
+                                    | keyboard::key::Named::ArrowDown
+                            )
+                        ) =>
+                {
+                    Some(Message::KeyPressed(Key::Shortcut(
+                        key.clone(),
+                        *modifiers,
+                        status == event::Status::Captured,
+                    )))
+                }
+                (
+                    Event::Keyboard(keyboard::Event::KeyPressed { .. })
+                    | Event::Mouse(
+                        mouse::Event::ButtonPressed(_) | mouse::Event::WheelScrolled { .. },
+                    ),
+                    _,
+                ) => Some(Message::UserActivity),
                 (
                     iced::Event::Window(_, iced::window::Event::CloseRequested),
                     event::Status::Ignored,
@@ -408,11 +531,24 @@ impl Application for GUI {
                 event,
                 iced::Event::Window(_, iced::window::Event::CloseRequested)
                     | iced::Event::Keyboard(_)
+                    | iced::Event::Mouse(_)
             )
         })
     }
 
     fn view(&self) -> Element<Self::Message> {
+        if self.shutting_down || self.switching_wallet {
+            return Container::new(text::p1_regular(if self.shutting_down {
+                tr("common.shutting_down")
+            } else {
+                tr("common.closing_wallet")
+            }))
+            .width(iced::Length::Fill)
+            .height(iced::Length::Fill)
+            .center_x()
+            .center_y()
+            .into();
+        }
         match &self.state {
             State::Installer(v) => v.view().map(|msg| Message::Install(Box::new(msg))),
             State::App(v) => v.view().map(|msg| Message::Run(Box::new(msg))),
@@ -467,6 +603,11 @@ pub fn create_app_with_remote_backend(
             // We ignore last poll fields for remote backend.
             last_poll_timestamp: None,
             last_poll_at_startup: None,
+            fee_estimates: Default::default(),
+            fee_estimates_fetched_at: None,
+            bitcoin_unit: Default::default(),
+            exchange_rate: None,
+            backend_status: Default::default(),
         },
         Arc::new(
             Wallet::new(wallet.descriptor)
@@ -508,7 +649,9 @@ impl Config {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args = parse_args(std::env::args().collect())?;
+    let (profile_path, args): (Vec<Arg>, Vec<Arg>) = parse_args(std::env::args().collect())?
+        .into_iter()
+        .partition(|arg| matches!(arg, Arg::ProfilePath(_)));
     let config = match args.as_slice() {
         [] => {
             let datadir_path = default_datadir().unwrap();
@@ -561,6 +704,22 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }?;
 
+    // The profile is stored in the datadir before anything is shown, to be used by the installer
+    // for new wallets.
+    if let Some(Arg::ProfilePath(path)) = profile_path.last() {
+        let profile = Profile::from_file(path)
+            .map_err(|e| format!("Failed to read configuration profile: {}", e))?;
+        let datadir_path = match &config {
+            Config::Launcher(datadir_path) | Config::Run(datadir_path, ..) => datadir_path,
+        };
+        if !datadir_path.exists() {
+            datadir::create_directory(datadir_path)?;
+        }
+        profile
+            .store_in_datadir(datadir_path)
+            .map_err(|e| format!("Failed to store configuration profile: {}", e))?;
+    }
+
     let log_level = if let Ok(l) = std::env::var("LOG_LEVEL") {
         Some(LevelFilter::from_str(&l)?)
     } else {
@@ -631,6 +790,7 @@ mod tests {
         assert!(parse_args(vec!["--meth".into()]).is_err());
         assert!(parse_args(vec!["--datadir".into()]).is_err());
         assert!(parse_args(vec!["--conf".into()]).is_err());
+        assert!(parse_args(vec!["--profile".into()]).is_err());
         assert_eq!(
             Some(vec![
                 Arg::DatadirPath(PathBuf::from(".")),
@@ -674,5 +834,18 @@ mod tests {
             )
             .ok()
         );
+        assert_eq!(
+            Some(vec![
+                Arg::ProfilePath(PathBuf::from("profile.json")),
+                Arg::Network(bitcoin::Network::Testnet)
+            ]),
+            parse_args(
+                "--profile profile.json --testnet"
+                    .split(' ')
+                    .map(|a| a.to_string())
+                    .collect()
+            )
+            .ok()
+        );
     }
 }
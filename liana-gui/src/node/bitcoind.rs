@@ -8,11 +8,19 @@ use liana_ui::component::form;
 use lianad::config::BitcoindConfig;
 use std::collections::BTreeMap;
 use std::fmt;
+#[cfg(target_os = "windows")]
+use std::io::{self, Cursor};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time;
 
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use flate2::read::GzDecoder;
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use tar::Archive;
+
 use tracing::{info, warn};
 
 #[cfg(target_os = "windows")]
@@ -67,6 +75,16 @@ pub fn download_url() -> String {
     )
 }
 
+/// The expected hash of the downloaded archive.
+pub fn download_hash() -> sha256::Hash {
+    sha256::Hash::from_str(SHA256SUM).expect("This cannot fail.")
+}
+
+/// Where the archive is stored while it is downloaded, for an interrupted download to be resumed.
+pub fn partial_download_path(liana_datadir: &PathBuf) -> PathBuf {
+    internal_bitcoind_directory(liana_datadir).join(format!("{}.part", download_filename()))
+}
+
 pub fn internal_bitcoind_directory(liana_datadir: &PathBuf) -> PathBuf {
     let mut datadir = PathBuf::from(liana_datadir);
     datadir.push("bitcoind");
@@ -92,6 +110,59 @@ pub fn internal_bitcoind_exe_path(liana_datadir: &PathBuf, bitcoind_version: &st
         })
 }
 
+/// Data directory currently used by internal bitcoind: the one set in its configuration file if
+/// it was moved, the default one otherwise.
+pub fn internal_bitcoind_current_datadir(liana_datadir: &PathBuf) -> PathBuf {
+    let default_datadir = internal_bitcoind_datadir(liana_datadir);
+    InternalBitcoindConfig::from_file(&internal_bitcoind_config_path(&default_datadir))
+        .ok()
+        .and_then(|conf| conf.datadir)
+        .unwrap_or(default_datadir)
+}
+
+/// The most recent managed bitcoind version installed, if any.
+pub fn internal_bitcoind_installed_version(liana_datadir: &PathBuf) -> Option<&'static str> {
+    VERSIONS
+        .iter()
+        .find(|v| internal_bitcoind_exe_path(liana_datadir, v).exists())
+        .copied()
+}
+
+/// Remove the executables of the managed bitcoind versions older than the one installed.
+pub fn remove_previous_bitcoind_versions(liana_datadir: &PathBuf) {
+    if let Some(installed) = internal_bitcoind_installed_version(liana_datadir) {
+        for version in VERSIONS.iter().skip_while(|v| **v != installed).skip(1) {
+            remove_bitcoind_version(liana_datadir, version);
+        }
+    }
+}
+
+/// Remove the executable of a managed bitcoind version, if installed.
+pub fn remove_bitcoind_version(liana_datadir: &PathBuf, version: &str) {
+    let dir = internal_bitcoind_directory(liana_datadir).join(format!("bitcoin-{}", version));
+    if dir.exists() {
+        info!("Removing bitcoind version {}", version);
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            warn!("Could not remove '{}': {}", dir.to_string_lossy(), e);
+        }
+    }
+}
+
+/// Below this prune target, in MiB, bitcoind refuses to start.
+pub const PRUNE_MIN: u32 = 550;
+
+/// An estimate of the disk space, in GB, used by bitcoind on the network with the given prune
+/// target in MiB: the blocks it keeps plus the UTXO set and the block index.
+pub fn pruned_disk_space_estimate(network: Network, prune: u32) -> u64 {
+    let chainstate_gb = match network {
+        Network::Bitcoin => 12,
+        Network::Testnet => 4,
+        Network::Signet => 1,
+        _ => 0,
+    };
+    (u64::from(prune) * 1024 * 1024).div_ceil(1_000_000_000) + chainstate_gb
+}
+
 /// Path of the `bitcoin.conf` file used by internal bitcoind.
 pub fn internal_bitcoind_config_path(bitcoind_datadir: &PathBuf) -> PathBuf {
     let mut config_path = PathBuf::from(bitcoind_datadir);
@@ -109,9 +180,9 @@ pub fn internal_bitcoind_cookie_path(bitcoind_datadir: &Path, network: &Network)
     cookie_path
 }
 
-/// Path of the cookie file used by internal bitcoind on a given network.
+/// Path of the debug log file written by internal bitcoind on a given network.
 pub fn internal_bitcoind_debug_log_path(lianad_datadir: &PathBuf, network: Network) -> PathBuf {
-    let mut debug_log_path = internal_bitcoind_datadir(lianad_datadir);
+    let mut debug_log_path = internal_bitcoind_current_datadir(lianad_datadir);
     if let Some(dir) = bitcoind_network_dir(&network) {
         debug_log_path.push(dir);
     }
@@ -220,6 +291,9 @@ pub struct InternalBitcoindNetworkConfig {
 /// Represents the `bitcoin.conf` file to be used by internal bitcoind.
 #[derive(Debug, Clone)]
 pub struct InternalBitcoindConfig {
+    /// The data directory, if it was moved from the default location. The configuration file
+    /// itself is always kept at the default location.
+    pub datadir: Option<PathBuf>,
     pub networks: BTreeMap<Network, InternalBitcoindNetworkConfig>,
 }
 
@@ -232,6 +306,7 @@ pub enum InternalBitcoindConfigError {
     FileNotFound,
     ReadingFile(String),
     WritingFile(String),
+    MovingDatadir(String),
     Unexpected(String),
 }
 
@@ -247,6 +322,7 @@ impl std::fmt::Display for InternalBitcoindConfigError {
             Self::FileNotFound => write!(f, "File not found"),
             Self::ReadingFile(e) => write!(f, "Error while reading file: {}", e),
             Self::WritingFile(e) => write!(f, "Error while writing file: {}", e),
+            Self::MovingDatadir(e) => write!(f, "Error while moving the data directory: {}", e),
             Self::Unexpected(e) => write!(f, "Unexpected error: {}", e),
         }
     }
@@ -261,11 +337,13 @@ impl Default for InternalBitcoindConfig {
 impl InternalBitcoindConfig {
     pub fn new() -> Self {
         Self {
+            datadir: None,
             networks: BTreeMap::new(),
         }
     }
 
     pub fn from_ini(ini: &ini::Ini) -> Result<Self, InternalBitcoindConfigError> {
+        let mut datadir = None;
         let mut networks = BTreeMap::new();
         for (maybe_sec, prop) in ini {
             if let Some(sec) = maybe_sec {
@@ -309,13 +387,16 @@ impl InternalBitcoindConfig {
                         rpc_auth,
                     },
                 );
-            } else if !prop.is_empty() {
-                return Err(InternalBitcoindConfigError::UnexpectedSection(
-                    "General section should be empty".to_string(),
-                ));
+            } else {
+                datadir = prop.get("datadir").map(PathBuf::from);
+                if prop.len() > usize::from(datadir.is_some()) {
+                    return Err(InternalBitcoindConfigError::UnexpectedSection(
+                        "General section should only contain the datadir".to_string(),
+                    ));
+                }
             }
         }
-        Ok(Self { networks })
+        Ok(Self { datadir, networks })
     }
 
     pub fn from_file(path: &PathBuf) -> Result<Self, InternalBitcoindConfigError> {
@@ -330,6 +411,11 @@ impl InternalBitcoindConfig {
 
     pub fn to_ini(&self) -> ini::Ini {
         let mut conf_ini = ini::Ini::new();
+        if let Some(datadir) = &self.datadir {
+            conf_ini
+                .with_general_section()
+                .set("datadir", datadir.to_string_lossy());
+        }
 
         for (network, network_conf) in &self.networks {
             conf_ini
@@ -361,6 +447,120 @@ impl InternalBitcoindConfig {
     }
 }
 
+/// Set the prune target, in MiB, of internal bitcoind on the network. It applies once restarted.
+pub fn set_internal_bitcoind_prune(
+    liana_datadir: &PathBuf,
+    network: Network,
+    prune: u32,
+) -> Result<(), InternalBitcoindConfigError> {
+    let config_path = internal_bitcoind_config_path(&internal_bitcoind_datadir(liana_datadir));
+    let mut conf = InternalBitcoindConfig::from_file(&config_path)?;
+    conf.networks
+        .get_mut(&network)
+        .ok_or_else(|| InternalBitcoindConfigError::KeyNotFound(network.to_core_arg().into()))?
+        .prune = prune;
+    conf.to_file(&config_path)
+}
+
+/// Move the data directory of internal bitcoind, which must be stopped, to `new_datadir`. The
+/// configuration file is kept at the default location, and updated to point to the new one.
+pub fn move_internal_bitcoind_datadir(
+    liana_datadir: &PathBuf,
+    new_datadir: &Path,
+) -> Result<(), InternalBitcoindConfigError> {
+    let default_datadir = internal_bitcoind_datadir(liana_datadir);
+    let config_path = internal_bitcoind_config_path(&default_datadir);
+    let mut conf = InternalBitcoindConfig::from_file(&config_path)?;
+    let current_datadir = conf
+        .datadir
+        .clone()
+        .unwrap_or_else(|| default_datadir.clone());
+
+    std::fs::create_dir_all(new_datadir)
+        .map_err(|e| InternalBitcoindConfigError::MovingDatadir(e.to_string()))?;
+    let new_datadir = new_datadir
+        .canonicalize()
+        .map_err(|e| InternalBitcoindConfigError::MovingDatadir(e.to_string()))?;
+    let current_datadir = current_datadir
+        .canonicalize()
+        .map_err(|e| InternalBitcoindConfigError::MovingDatadir(e.to_string()))?;
+    if new_datadir == current_datadir {
+        return Ok(());
+    }
+    if new_datadir.starts_with(&current_datadir) {
+        return Err(InternalBitcoindConfigError::MovingDatadir(
+            "The new location must not be inside the current data directory.".to_string(),
+        ));
+    }
+    let is_default = new_datadir == default_datadir.canonicalize().ok().unwrap_or_default();
+    let is_conf = |path: &Path| path.file_name() == config_path.file_name();
+    let entries = std::fs::read_dir(&new_datadir)
+        .map_err(|e| InternalBitcoindConfigError::MovingDatadir(e.to_string()))?;
+    for entry in entries.flatten() {
+        // The default location always contains the configuration file.
+        if !(is_default && is_conf(&entry.path())) {
+            return Err(InternalBitcoindConfigError::MovingDatadir(format!(
+                "'{}' is not empty.",
+                new_datadir.to_string_lossy()
+            )));
+        }
+    }
+
+    let entries = std::fs::read_dir(&current_datadir)
+        .map_err(|e| InternalBitcoindConfigError::MovingDatadir(e.to_string()))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| InternalBitcoindConfigError::MovingDatadir(e.to_string()))?;
+        if conf.datadir.is_none() && is_conf(&entry.path()) {
+            continue;
+        }
+        info!(
+            "Moving '{}' to '{}'",
+            entry.path().to_string_lossy(),
+            new_datadir.to_string_lossy()
+        );
+        move_path(&entry.path(), &new_datadir.join(entry.file_name()))
+            .map_err(|e| InternalBitcoindConfigError::MovingDatadir(e.to_string()))?;
+    }
+    if conf.datadir.is_some() {
+        if let Err(e) = std::fs::remove_dir(&current_datadir) {
+            warn!(
+                "Could not remove previous data directory '{}': {}",
+                current_datadir.to_string_lossy(),
+                e
+            );
+        }
+    }
+
+    conf.datadir = if is_default { None } else { Some(new_datadir) };
+    conf.to_file(&config_path)
+}
+
+// Rename the file or directory, or copy it then remove it if it's moved to another file system.
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_recursively(from, to)?;
+    if from.is_dir() {
+        std::fs::remove_dir_all(from)
+    } else {
+        std::fs::remove_file(from)
+    }
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
 /// Possible errors when starting bitcoind.
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum StartInternalBitcoindError {
@@ -388,9 +588,100 @@ impl std::fmt::Display for StartInternalBitcoindError {
         }
     }
 }
+/// Possible errors when installing bitcoind.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum InstallBitcoindError {
+    HashMismatch,
+    UnpackingError(String),
+}
+
+impl std::fmt::Display for InstallBitcoindError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::HashMismatch => {
+                write!(f, "Hashes do not match.")
+            }
+            Self::UnpackingError(e) => {
+                write!(f, "Error unpacking: '{}'.", e)
+            }
+        }
+    }
+}
+
+// The functions below for unpacking the bitcoin download and verifying its hash are based on
+// https://github.com/RCasatta/bitcoind/blob/bada7ebb7197b89fd67e607f815ce1e43e76da7f/build.rs#L73.
+
+/// Unpack the downloaded bytes in the specified directory.
+fn unpack_bitcoind(install_dir: &PathBuf, bytes: &[u8]) -> Result<(), InstallBitcoindError> {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        let d = GzDecoder::new(bytes);
+
+        let mut archive = Archive::new(d);
+        for mut entry in archive
+            .entries()
+            .map_err(|e| InstallBitcoindError::UnpackingError(e.to_string()))?
+            .flatten()
+        {
+            if let Ok(file) = entry.path() {
+                if file.ends_with("bitcoind") {
+                    if let Err(e) = entry.unpack_in(install_dir) {
+                        return Err(InstallBitcoindError::UnpackingError(e.to_string()));
+                    }
+                }
+            }
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let cursor = Cursor::new(bytes);
+        let mut archive = zip::ZipArchive::new(cursor)
+            .map_err(|e| InstallBitcoindError::UnpackingError(e.to_string()))?;
+        for i in 0..zip::ZipArchive::len(&archive) {
+            let mut file = archive
+                .by_index(i)
+                .map_err(|e| InstallBitcoindError::UnpackingError(e.to_string()))?;
+            let outpath = match file.enclosed_name() {
+                Some(path) => path.to_owned(),
+                None => continue,
+            };
+            if outpath.file_name().map(|s| s.to_str()) == Some(Some("bitcoind.exe")) {
+                let mut exe_path = PathBuf::from(install_dir);
+                for d in outpath.iter() {
+                    exe_path.push(d);
+                }
+                let parent = exe_path.parent().expect("bitcoind.exe should have parent.");
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| InstallBitcoindError::UnpackingError(e.to_string()))?;
+                let mut outfile = std::fs::File::create(&exe_path)
+                    .map_err(|e| InstallBitcoindError::UnpackingError(e.to_string()))?;
+                io::copy(&mut file, &mut outfile)
+                    .map_err(|e| InstallBitcoindError::UnpackingError(e.to_string()))?;
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Verify the download hash against the expected value.
+fn verify_hash(bytes: &[u8]) -> bool {
+    let bytes_hash = sha256::Hash::hash(bytes);
+    info!("Download hash: '{}'.", bytes_hash);
+    download_hash() == bytes_hash
+}
+
+/// Install bitcoind by verifying the download hash and unpacking in the specified directory.
+pub fn install_bitcoind(install_dir: &PathBuf, bytes: &[u8]) -> Result<(), InstallBitcoindError> {
+    if !verify_hash(bytes) {
+        return Err(InstallBitcoindError::HashMismatch);
+    };
+    unpack_bitcoind(install_dir, bytes)
+}
+
 #[derive(Debug, Clone)]
 pub struct Bitcoind {
-    _process: Arc<std::process::Child>,
+    process: Arc<Mutex<std::process::Child>>,
     pub config: BitcoindConfig,
 }
 
@@ -401,43 +692,29 @@ impl Bitcoind {
         config: BitcoindConfig,
         liana_datadir: &PathBuf,
     ) -> Result<Self, StartInternalBitcoindError> {
-        let bitcoind_datadir = internal_bitcoind_datadir(liana_datadir);
+        let default_datadir = internal_bitcoind_datadir(liana_datadir);
+        let bitcoind_datadir = internal_bitcoind_current_datadir(liana_datadir);
         // Find most recent bitcoind version available.
-        let bitcoind_exe_path = VERSIONS
-            .iter()
-            .filter_map(|v| {
-                let path = internal_bitcoind_exe_path(liana_datadir, v);
-                if path.exists() {
-                    Some(path)
-                } else {
-                    None
-                }
-            })
-            .next()
+        let bitcoind_exe_path = internal_bitcoind_installed_version(liana_datadir)
+            .map(|v| internal_bitcoind_exe_path(liana_datadir, v))
             .ok_or(StartInternalBitcoindError::ExecutableNotFound)?;
         info!(
             "Found bitcoind executable at '{}'.",
             bitcoind_exe_path.to_string_lossy()
         );
-        let datadir_path_str = bitcoind_datadir
-            .canonicalize()
-            .map_err(|e| StartInternalBitcoindError::CouldNotCanonicalizeDataDir(e.to_string()))?
-            .to_str()
-            .ok_or_else(|| {
-                StartInternalBitcoindError::CouldNotCanonicalizeDataDir(
-                    "Couldn't convert path to str.".to_string(),
-                )
-            })?
-            .to_string();
-
-        // See https://github.com/rust-lang/rust/issues/42869.
-        #[cfg(target_os = "windows")]
-        let datadir_path_str = datadir_path_str.replace("\\\\?\\", "").replace("\\\\?", "");
+        let datadir_path_str = canonical_path_str(&bitcoind_datadir)?;
 
-        let args = vec![
+        let mut args = vec![
             format!("-chain={}", network.to_core_arg()),
             format!("-datadir={}", datadir_path_str),
         ];
+        // The configuration file is kept at the default location when the datadir is moved.
+        if bitcoind_datadir != default_datadir {
+            args.push(format!(
+                "-conf={}",
+                canonical_path_str(&internal_bitcoind_config_path(&default_datadir))?
+            ));
+        }
         let mut command = std::process::Command::new(bitcoind_exe_path);
 
         #[cfg(target_os = "windows")]
@@ -467,7 +744,7 @@ impl Bitcoind {
                     log::info!("Bitcoind seems to have successfully started.");
                     return Ok(Self {
                         config,
-                        _process: Arc::new(process),
+                        process: Arc::new(Mutex::new(process)),
                     });
                 }
                 Err(lianad::BitcoindError::CookieFile(_)) => {
@@ -494,6 +771,65 @@ impl Bitcoind {
     pub fn stop(&self) {
         stop_bitcoind(&self.config);
     }
+
+    /// Stop (internal) bitcoind and wait for its process to exit, for its data directory to be
+    /// safe to modify.
+    pub fn stop_and_wait(&self) -> Result<(), StartInternalBitcoindError> {
+        if !stop_bitcoind(&self.config) {
+            return Err(StartInternalBitcoindError::BitcoinDError(
+                "Could not request bitcoind to stop".to_string(),
+            ));
+        }
+        let mut process = self.process.lock().expect("Mutex must not be poisoned");
+        loop {
+            match process.try_wait() {
+                Ok(Some(status)) => {
+                    info!("Bitcoind exited with status '{}'", status);
+                    return Ok(());
+                }
+                Ok(None) => {}
+                Err(e) => return Err(StartInternalBitcoindError::CommandError(e.to_string())),
+            }
+            thread::sleep(time::Duration::from_millis(500));
+        }
+    }
+
+    /// Whether the bitcoind process exited, for instance after a crash.
+    pub fn has_exited(&self) -> bool {
+        let mut process = self.process.lock().expect("Mutex must not be poisoned");
+        matches!(process.try_wait(), Ok(Some(_)))
+    }
+
+    /// Check the node is fit to be used by the wallet, for instance after an upgrade.
+    pub fn sanity_check(
+        &self,
+        network: bitcoin::Network,
+        is_taproot: bool,
+    ) -> Result<(), StartInternalBitcoindError> {
+        lianad::BitcoinD::new(&self.config, "internal_bitcoind_check".to_string())
+            .and_then(|bitcoind| bitcoind.node_sanity_checks(network, is_taproot))
+            .map_err(|e| StartInternalBitcoindError::BitcoinDError(e.to_string()))
+    }
+}
+
+// The canonical form of the path, as expected by bitcoind's command line.
+fn canonical_path_str(path: &Path) -> Result<String, StartInternalBitcoindError> {
+    let path_str = path
+        .canonicalize()
+        .map_err(|e| StartInternalBitcoindError::CouldNotCanonicalizeDataDir(e.to_string()))?
+        .to_str()
+        .ok_or_else(|| {
+            StartInternalBitcoindError::CouldNotCanonicalizeDataDir(
+                "Couldn't convert path to str.".to_string(),
+            )
+        })?
+        .to_string();
+
+    // See https://github.com/rust-lang/rust/issues/42869.
+    #[cfg(target_os = "windows")]
+    let path_str = path_str.replace("\\\\?\\", "").replace("\\\\?", "");
+
+    Ok(path_str)
 }
 
 pub fn stop_bitcoind(config: &BitcoindConfig) -> bool {
@@ -558,6 +894,12 @@ mod tests {
     use ini::Ini;
     use liana::miniscript::bitcoin::Network;
 
+    #[test]
+    fn hash() {
+        let bytes = "this is not bitcoin".as_bytes().to_vec();
+        assert!(!verify_hash(&bytes));
+    }
+
     // Test the format of the internal bitcoind configuration file.
     #[test]
     fn internal_bitcoind_config() {
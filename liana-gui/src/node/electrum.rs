@@ -35,3 +35,31 @@ pub fn is_electrum_address_valid(value: &str) -> bool {
             .parse::<u16>() // check it is a port
             .is_ok()
 }
+
+/// Whether the value is the "host:port" of a proxy.
+pub fn is_proxy_address_valid(value: &str) -> bool {
+    match value.rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn electrum_and_proxy_addresses() {
+        assert!(is_electrum_address_valid("127.0.0.1:50001"));
+        assert!(is_electrum_address_valid(
+            "ssl://electrum.blockstream.info:50002"
+        ));
+        assert!(!is_electrum_address_valid("electrum.blockstream.info"));
+
+        assert!(is_proxy_address_valid("127.0.0.1:9050"));
+        assert!(is_proxy_address_valid("localhost:9050"));
+        assert!(!is_proxy_address_valid("127.0.0.1"));
+        assert!(!is_proxy_address_valid(":9050"));
+        assert!(!is_proxy_address_valid("127.0.0.1:90500"));
+    }
+}
@@ -0,0 +1,353 @@
+//! A configuration profile holds the GUI-level settings of an installation which may be shared
+//! with others, for instance to deploy Liana on many machines with identical settings.
+//!
+//! A profile never contains any secret: it is built field by field from the configuration and
+//! settings, and there is simply no field for authentication tokens, hardware wallet
+//! registration hmacs or RPC credentials.
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use liana::miniscript::bitcoin::bip32::Fingerprint;
+use lianad::config::{BitcoinBackend, Config as DaemonConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{config::Config as GUIConfig, settings::KeySetting};
+
+/// The version of the profile format we write. We refuse to import profiles of a newer version.
+pub const PROFILE_VERSION: u32 = 1;
+
+/// The name of the file in the Liana data directory holding the profile to be used for new
+/// installations, as set with the `--profile` command line option.
+pub const DEFAULT_FILE_NAME: &str = "profile.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Profile {
+    pub version: u32,
+    /// log level, can be "info", "debug", "trace".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    /// Use iced debug feature if true.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<bool>,
+    /// Address of the Bitcoin node to connect to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<NodeAddress>,
+    /// Aliases of the keys, by master fingerprint.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<KeySetting>,
+}
+
+/// The address of a Bitcoin node, without any credentials.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NodeAddress {
+    Bitcoind { addr: SocketAddr },
+    Electrum { addr: String },
+}
+
+// Only used to check the version of a profile before parsing the rest of it.
+#[derive(Deserialize)]
+struct ProfileVersion {
+    version: u32,
+}
+
+impl Profile {
+    /// Create a profile from the settings of a wallet.
+    pub fn new(
+        gui_config: &GUIConfig,
+        daemon_config: Option<&DaemonConfig>,
+        keys: Vec<KeySetting>,
+    ) -> Self {
+        let node = daemon_config
+            .and_then(|cfg| cfg.bitcoin_backend.as_ref())
+            .map(|backend| match backend {
+                BitcoinBackend::Bitcoind(cfg) => NodeAddress::Bitcoind { addr: cfg.addr },
+                BitcoinBackend::Electrum(cfg) => NodeAddress::Electrum {
+                    addr: cfg.addr.clone(),
+                },
+            });
+        Self {
+            version: PROFILE_VERSION,
+            log_level: gui_config.log_level.clone(),
+            debug: gui_config.debug,
+            node,
+            keys: keys.into_iter().filter(|k| !k.name.is_empty()).collect(),
+        }
+    }
+
+    /// Parse a profile. Unknown fields are ignored, but profiles of a newer version are refused.
+    pub fn from_slice(content: &[u8]) -> Result<Self, ProfileError> {
+        let ProfileVersion { version } = serde_json::from_slice(content)
+            .map_err(|e| ProfileError::ReadingFile(format!("Parsing profile: {}", e)))?;
+        if version > PROFILE_VERSION {
+            return Err(ProfileError::UnsupportedVersion(version));
+        }
+        let profile: Profile = serde_json::from_slice(content)
+            .map_err(|e| ProfileError::ReadingFile(format!("Parsing profile: {}", e)))?;
+        if let Some(log_level) = &profile.log_level {
+            if !["info", "debug", "trace"].contains(&log_level.as_str()) {
+                return Err(ProfileError::InvalidField(
+                    "log_level",
+                    format!("Unknown value '{}'", log_level),
+                ));
+            }
+        }
+        Ok(profile)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ProfileError> {
+        let content = std::fs::read(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ProfileError::NotFound,
+            _ => ProfileError::ReadingFile(format!("Reading profile: {}", e)),
+        })?;
+        Self::from_slice(&content)
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<(), ProfileError> {
+        let content = serde_json::to_string_pretty(&self).map_err(|e| {
+            ProfileError::WritingFile(format!("Failed to serialize profile: {}", e))
+        })?;
+
+        let mut profile_file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(|e| ProfileError::WritingFile(e.to_string()))?;
+
+        profile_file.write_all(content.as_bytes()).map_err(|e| {
+            tracing::warn!("failed to write to file: {:?}", e);
+            ProfileError::WritingFile(e.to_string())
+        })
+    }
+
+    /// The profile stored in the Liana data directory to be used for new installations, if any.
+    pub fn from_datadir(datadir: &Path) -> Option<Self> {
+        match Self::from_file(&path_in_datadir(datadir)) {
+            Ok(profile) => Some(profile),
+            Err(ProfileError::NotFound) => None,
+            Err(e) => {
+                tracing::error!("Ignoring the configuration profile: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Store this profile in the Liana data directory to be used for new installations.
+    pub fn store_in_datadir(&self, datadir: &Path) -> Result<(), ProfileError> {
+        self.to_file(&path_in_datadir(datadir))
+    }
+
+    /// Set the GUI-level settings of this profile in the given GUI configuration.
+    pub fn apply_to_gui_config(&self, config: &mut GUIConfig) {
+        if let Some(log_level) = &self.log_level {
+            config.log_level = Some(log_level.clone());
+        }
+        if let Some(debug) = self.debug {
+            config.debug = Some(debug);
+        }
+    }
+
+    /// Get the aliases of this profile for the given keys, overriding the current aliases.
+    pub fn apply_to_keys(
+        &self,
+        keys_aliases: &[(Fingerprint, String)],
+        descriptor_keys: &HashSet<Fingerprint>,
+    ) -> Vec<(Fingerprint, String)> {
+        let mut res: Vec<(Fingerprint, String)> = keys_aliases
+            .iter()
+            .filter(|(fg, _)| !self.keys.iter().any(|k| k.master_fingerprint == *fg))
+            .cloned()
+            .collect();
+        for key in &self.keys {
+            if descriptor_keys.contains(&key.master_fingerprint) {
+                res.push((key.master_fingerprint, key.name.clone()));
+            }
+        }
+        res.sort_by(|(fg1, _), (fg2, _)| fg1.cmp(fg2));
+        res
+    }
+
+    /// The address of the bitcoind node of this profile, if any.
+    pub fn bitcoind_addr(&self) -> Option<SocketAddr> {
+        if let Some(NodeAddress::Bitcoind { addr }) = &self.node {
+            Some(*addr)
+        } else {
+            None
+        }
+    }
+
+    /// The address of the Electrum server of this profile, if any.
+    pub fn electrum_addr(&self) -> Option<&str> {
+        if let Some(NodeAddress::Electrum { addr }) = &self.node {
+            Some(addr)
+        } else {
+            None
+        }
+    }
+}
+
+fn path_in_datadir(datadir: &Path) -> PathBuf {
+    datadir.join(DEFAULT_FILE_NAME)
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ProfileError {
+    NotFound,
+    UnsupportedVersion(u32),
+    InvalidField(&'static str, String),
+    ReadingFile(String),
+    WritingFile(String),
+}
+
+impl std::fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Profile file not found"),
+            Self::UnsupportedVersion(v) => write!(
+                f,
+                "Profile version {} is not supported by this version of Liana (latest supported: {})",
+                v, PROFILE_VERSION
+            ),
+            Self::InvalidField(field, message) => {
+                write!(f, "Profile field {} is invalid: {}", field, message)
+            }
+            Self::ReadingFile(e) => write!(f, "Error while reading file: {}", e),
+            Self::WritingFile(e) => write!(f, "Error while writing file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use lianad::config::{
+        BitcoinConfig, BitcoindConfig, BitcoindRpcAuth, DEFAULT_MAX_DERIVATION_INDEX,
+        DEFAULT_MIN_FEERATE,
+    };
+
+    const DESC: &str = "wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs";
+
+    fn daemon_config(auth: BitcoindRpcAuth) -> DaemonConfig {
+        DaemonConfig {
+            data_dir: None,
+            log_level: log::LevelFilter::Info,
+            main_descriptor: DESC.parse().unwrap(),
+            coin_selection: Default::default(),
+            bitcoin_config: BitcoinConfig {
+                network: liana::miniscript::bitcoin::Network::Testnet,
+                poll_interval_secs: std::time::Duration::from_secs(30),
+                max_backoff_secs: std::time::Duration::from_secs(5 * 60),
+            },
+            bitcoin_backend: Some(BitcoinBackend::Bitcoind(BitcoindConfig {
+                rpc_auth: auth,
+                addr: SocketAddr::from_str("127.0.0.1:18332").unwrap(),
+            })),
+            recovery_thresholds: Vec::new(),
+            warm_derivation_cache: false,
+            fee_sources: None,
+            max_derivation_index: DEFAULT_MAX_DERIVATION_INDEX,
+            min_feerate_sat_vb: DEFAULT_MIN_FEERATE,
+        }
+    }
+
+    #[test]
+    fn profile_roundtrip() {
+        let mut gui_config = GUIConfig::new(PathBuf::from("/home/user/.liana/daemon.toml"), false);
+        gui_config.log_level = Some("debug".to_string());
+        let keys = vec![
+            KeySetting {
+                name: "Alice".to_string(),
+                master_fingerprint: Fingerprint::from_str("f5acc2fd").unwrap(),
+            },
+            KeySetting {
+                name: "".to_string(),
+                master_fingerprint: Fingerprint::from_str("8a64f2a9").unwrap(),
+            },
+        ];
+        let daemon_config = daemon_config(BitcoindRpcAuth::UserPass(
+            "my_user".to_string(),
+            "my_password".to_string(),
+        ));
+        let profile = Profile::new(&gui_config, Some(&daemon_config), keys);
+        assert_eq!(profile.version, PROFILE_VERSION);
+        assert_eq!(profile.log_level.as_deref(), Some("debug"));
+        assert_eq!(
+            profile.bitcoind_addr(),
+            Some(SocketAddr::from_str("127.0.0.1:18332").unwrap())
+        );
+        // Keys without alias are not exported.
+        assert_eq!(profile.keys.len(), 1);
+
+        let content = serde_json::to_string(&profile).unwrap();
+        // The node credentials and the machine-specific paths aren't part of the profile.
+        assert!(!content.contains("my_user"));
+        assert!(!content.contains("my_password"));
+        assert!(!content.contains("daemon.toml"));
+        assert_eq!(Profile::from_slice(content.as_bytes()).unwrap(), profile);
+    }
+
+    #[test]
+    fn profile_parsing() {
+        // Unknown fields are ignored and all settings are optional.
+        let profile = Profile::from_slice(
+            br#"{"version": 1, "theme": "dark", "node": {"type": "electrum", "addr": "ssl://electrum.blockstream.info:60002", "proxy": "127.0.0.1:9050"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            profile.electrum_addr(),
+            Some("ssl://electrum.blockstream.info:60002")
+        );
+        assert!(profile.log_level.is_none() && profile.keys.is_empty());
+
+        // Profiles from the future are refused, whatever their content.
+        assert_eq!(
+            Profile::from_slice(br#"{"version": 2, "node": "something new"}"#),
+            Err(ProfileError::UnsupportedVersion(2))
+        );
+        // The version is mandatory.
+        assert!(matches!(
+            Profile::from_slice(br#"{"log_level": "info"}"#),
+            Err(ProfileError::ReadingFile(_))
+        ));
+        assert!(matches!(
+            Profile::from_slice(br#"{"version": 1, "log_level": "verbose"}"#),
+            Err(ProfileError::InvalidField("log_level", _))
+        ));
+    }
+
+    #[test]
+    fn profile_apply() {
+        let profile = Profile::from_slice(
+            br#"{"version": 1, "log_level": "trace", "keys": [{"name": "Alice", "master_fingerprint": "f5acc2fd"}, {"name": "Carol", "master_fingerprint": "aabbccdd"}]}"#,
+        )
+        .unwrap();
+
+        let mut gui_config = GUIConfig::new(PathBuf::from("/home/user/.liana/daemon.toml"), true);
+        gui_config.debug = Some(true);
+        profile.apply_to_gui_config(&mut gui_config);
+        assert_eq!(gui_config.log_level.as_deref(), Some("trace"));
+        assert_eq!(gui_config.debug, Some(true));
+        assert!(gui_config.start_internal_bitcoind);
+
+        // Only the aliases of the keys of the wallet are set.
+        let alice = Fingerprint::from_str("f5acc2fd").unwrap();
+        let bob = Fingerprint::from_str("8a64f2a9").unwrap();
+        let aliases = profile.apply_to_keys(
+            &[(alice, "A".to_string()), (bob, "Bob".to_string())],
+            &[alice, bob].iter().copied().collect(),
+        );
+        assert_eq!(
+            aliases,
+            vec![(bob, "Bob".to_string()), (alice, "Alice".to_string())]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Stopping the wallet backend and the managed bitcoind when the application exits.
+
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+use lianad::config::BitcoindConfig;
+use tokio::runtime::Handle;
+use tracing::{info, warn};
+
+use crate::{
+    daemon::Daemon,
+    node::bitcoind::{stop_bitcoind, Bitcoind},
+};
+
+/// How long to wait for everything to stop before exiting anyway.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What must be stopped before exiting, in this order: the embedded daemon first for it to
+/// finish its in-flight commands and database writes while its Bitcoin backend is still up,
+/// then the managed bitcoind.
+#[derive(Default)]
+pub struct Shutdown {
+    daemon: Option<Arc<dyn Daemon + Sync + Send>>,
+    bitcoind: Option<Bitcoind>,
+    bitcoind_config: Option<BitcoindConfig>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop this daemon, if it is embedded.
+    pub fn with_daemon(mut self, daemon: Arc<dyn Daemon + Sync + Send>) -> Self {
+        if daemon.backend().is_embedded() {
+            self.daemon = Some(daemon);
+        }
+        self
+    }
+
+    pub fn with_bitcoind(mut self, bitcoind: Option<Bitcoind>) -> Self {
+        self.bitcoind = bitcoind;
+        self
+    }
+
+    /// Stop the managed bitcoind using this configuration, for when it was started but its
+    /// process isn't known, for instance while the daemon is still waiting for it.
+    pub fn with_bitcoind_config(mut self, config: BitcoindConfig) -> Self {
+        self.bitcoind_config = Some(config);
+        self
+    }
+
+    /// Stop everything, giving up after [`SHUTDOWN_TIMEOUT`]. Returns whether everything was
+    /// stopped in time.
+    pub async fn run(self) -> bool {
+        tokio::task::spawn_blocking(move || self.run_blocking())
+            .await
+            .unwrap_or(false)
+    }
+
+    fn run_blocking(self) -> bool {
+        let runtime = Handle::current();
+        let (sender, receiver) = mpsc::sync_channel(1);
+        thread::Builder::new()
+            .name("Liana shutdown".to_string())
+            .spawn(move || {
+                self.stop(&runtime);
+                // We may have given up waiting already.
+                let _ = sender.send(());
+            })
+            .expect("Spawning the shutdown thread should never fail.");
+        receiver.recv_timeout(SHUTDOWN_TIMEOUT).is_ok()
+    }
+
+    fn stop(self, runtime: &Handle) {
+        if let Some(daemon) = self.daemon {
+            info!("Stopping internal daemon...");
+            if let Err(e) = runtime.block_on(daemon.stop()) {
+                warn!("Internal daemon failed to stop: {}", e);
+            } else {
+                info!("Internal daemon stopped");
+            }
+        }
+
+        if let Some(bitcoind) = self.bitcoind {
+            info!("Stopping managed bitcoind...");
+            bitcoind.stop();
+        } else if let Some(config) = self.bitcoind_config {
+            let mut retry = 0;
+            while !stop_bitcoind(&config) && retry < 10 {
+                thread::sleep(Duration::from_millis(500));
+                retry += 1;
+            }
+        }
+    }
+}
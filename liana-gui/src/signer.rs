@@ -1,5 +1,7 @@
 pub use liana::signer::SignerError;
 
+use std::sync::Mutex;
+
 use liana::{
     miniscript::bitcoin::{
         bip32::{DerivationPath, Fingerprint, Xpub},
@@ -13,6 +15,9 @@ pub struct Signer {
     curve: secp256k1::Secp256k1<secp256k1::All>,
     key: HotSigner,
     pub fingerprint: Fingerprint,
+    /// Set if the mnemonic is protected by a passphrase, with the key derived using it while the
+    /// signer is unlocked. This key is only ever kept in memory.
+    unlocked: Option<Mutex<Option<HotSigner>>>,
 }
 
 impl std::fmt::Debug for Signer {
@@ -29,6 +34,49 @@ impl Signer {
             key,
             curve,
             fingerprint,
+            unlocked: None,
+        }
+    }
+
+    /// A signer for a mnemonic protected by a passphrase, whose master key has the given
+    /// fingerprint. It is locked until the passphrase is entered.
+    pub fn protected(key: HotSigner, fingerprint: Fingerprint) -> Self {
+        Self {
+            key,
+            curve: secp256k1::Secp256k1::new(),
+            fingerprint,
+            unlocked: Some(Mutex::new(None)),
+        }
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.unlocked.is_some()
+    }
+
+    /// Whether the passphrase must be entered before signing.
+    pub fn is_locked(&self) -> bool {
+        self.unlocked
+            .as_ref()
+            .map_or(false, |unlocked| unlocked.lock().unwrap().is_none())
+    }
+
+    /// Unlock the signer if the key derived using this passphrase has the expected fingerprint.
+    /// Returns whether it did.
+    pub fn unlock(&self, passphrase: &str) -> Result<bool, SignerError> {
+        if let Some(unlocked) = &self.unlocked {
+            let key = self.key.with_passphrase(passphrase)?;
+            if key.fingerprint(&self.curve) != self.fingerprint {
+                return Ok(false);
+            }
+            *unlocked.lock().unwrap() = Some(key);
+        }
+        Ok(true)
+    }
+
+    /// Forget the key derived using the passphrase, if any.
+    pub fn lock(&self) {
+        if let Some(unlocked) = &self.unlocked {
+            *unlocked.lock().unwrap() = None;
         }
     }
 
@@ -52,8 +100,16 @@ impl Signer {
         self.key.xpub_at(path, &self.curve)
     }
 
-    pub fn sign_psbt(&self, psbt: Psbt) -> Result<Psbt, SignerError> {
-        self.key.sign_psbt(psbt, &self.curve)
+    /// Sign the PSBT, none if the signer is locked.
+    pub fn sign_psbt(&self, psbt: Psbt) -> Option<Result<Psbt, SignerError>> {
+        match &self.unlocked {
+            Some(unlocked) => unlocked
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|key| key.sign_psbt(psbt, &self.curve)),
+            None => Some(self.key.sign_psbt(psbt, &self.curve)),
+        }
     }
 
     pub fn store(
@@ -1,16 +1,64 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 pub use bitcoin::Amount;
+use bitcoin::{amount::ParseAmountError, Denomination};
 use iced::Color;
+use serde::{Deserialize, Serialize};
 
 use crate::{color, component::text::*, widget::*};
 
+/// The unit amounts are displayed and entered in. Fee rates are always in sat/vB.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BitcoinDisplayUnit {
+    #[default]
+    Btc,
+    Sats,
+}
+
+impl BitcoinDisplayUnit {
+    pub const ALL: [BitcoinDisplayUnit; 2] = [BitcoinDisplayUnit::Btc, BitcoinDisplayUnit::Sats];
+
+    fn denomination(self) -> Denomination {
+        match self {
+            BitcoinDisplayUnit::Btc => Denomination::Bitcoin,
+            BitcoinDisplayUnit::Sats => Denomination::Satoshi,
+        }
+    }
+}
+
+impl std::fmt::Display for BitcoinDisplayUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BitcoinDisplayUnit::Btc => write!(f, "BTC"),
+            BitcoinDisplayUnit::Sats => write!(f, "sats"),
+        }
+    }
+}
+
+/// Displayed in place of amounts while they are hidden.
+pub const HIDDEN_AMOUNT: &str = "*****";
+
+static AMOUNTS_HIDDEN: AtomicBool = AtomicBool::new(false);
+
+/// Whether amounts are currently hidden, for instance while demoing the application.
+pub fn amounts_hidden() -> bool {
+    AMOUNTS_HIDDEN.load(Ordering::Relaxed)
+}
+
+/// Hide or show all the amounts formatted by this module, from then on.
+pub fn set_amounts_hidden(hidden: bool) {
+    AMOUNTS_HIDDEN.store(hidden, Ordering::Relaxed);
+}
+
 /// Amount with default size and colors.
-pub fn amount<'a, T: 'a>(a: &Amount) -> Row<'a, T> {
-    amount_with_size(a, P1_SIZE)
+pub fn amount<'a, T: 'a>(a: &Amount, unit: BitcoinDisplayUnit) -> Row<'a, T> {
+    amount_with_size(a, P1_SIZE, unit)
 }
 
 /// Amount with default colors.
-pub fn amount_with_size<'a, T: 'a>(a: &Amount, size: u16) -> Row<'a, T> {
-    amount_with_size_and_colors(a, size, color::GREY_3, None)
+pub fn amount_with_size<'a, T: 'a>(a: &Amount, size: u16, unit: BitcoinDisplayUnit) -> Row<'a, T> {
+    amount_with_size_and_colors(a, size, color::GREY_3, None, unit)
 }
 
 /// Amount with the given size and colors.
@@ -26,20 +74,41 @@ pub fn amount_with_size_and_colors<'a, T: 'a>(
     size: u16,
     color_before: Color,
     color_after: Option<Color>,
+    unit: BitcoinDisplayUnit,
 ) -> Row<'a, T> {
-    render_amount(amount_as_string(*a), size, color_before, color_after)
+    render_amount(
+        amount_as_string(*a, unit),
+        size,
+        color_before,
+        color_after,
+        unit,
+    )
 }
 
-pub fn unconfirmed_amount_with_size<'a, T: 'a>(a: &Amount, size: u16) -> Row<'a, T> {
-    render_unconfirmed_amount(amount_as_string(*a), size)
+pub fn unconfirmed_amount_with_size<'a, T: 'a>(
+    a: &Amount,
+    size: u16,
+    unit: BitcoinDisplayUnit,
+) -> Row<'a, T> {
+    render_unconfirmed_amount(amount_as_string(*a, unit), size, unit)
 }
 
 //
 // Helpers
 //
 
-// Format a BTC amount as a string for display.
-pub fn amount_as_string(a: Amount) -> String {
+// Format an amount as a string for display in the given unit, unless amounts are hidden.
+pub fn amount_as_string(a: Amount, unit: BitcoinDisplayUnit) -> String {
+    format_amount(a, unit, amounts_hidden())
+}
+
+fn format_amount(a: Amount, unit: BitcoinDisplayUnit, hidden: bool) -> String {
+    if hidden {
+        return HIDDEN_AMOUNT.to_string();
+    }
+    if unit == BitcoinDisplayUnit::Sats {
+        return format_amount_number_part(&a.to_sat().to_string());
+    }
     let amount = a.to_btc().to_string();
 
     // Reformat the integer portion of the amount with space separation.
@@ -54,6 +123,27 @@ pub fn amount_as_string(a: Amount) -> String {
     format!("{integer}.{fraction}")
 }
 
+// Format an amount as a string to be edited in an input, without separators.
+pub fn amount_as_input(a: Amount, unit: BitcoinDisplayUnit) -> String {
+    match unit {
+        BitcoinDisplayUnit::Btc => a.to_btc().to_string(),
+        BitcoinDisplayUnit::Sats => a.to_sat().to_string(),
+    }
+}
+
+// Parse an amount entered in the given unit. Digits may be grouped with spaces or underscores,
+// and with commas for sats: a comma is ambiguous with a decimal separator for BTC amounts.
+// Amounts in sats can't have a fractional part.
+pub fn parse_amount(s: &str, unit: BitcoinDisplayUnit) -> Result<Amount, ParseAmountError> {
+    let s: String = s
+        .chars()
+        .filter(|c| {
+            !c.is_whitespace() && *c != '_' && (unit == BitcoinDisplayUnit::Btc || *c != ',')
+        })
+        .collect();
+    Amount::from_str_in(&s, unit.denomination())
+}
+
 // Format a "part" of a number string with spaces to fit display requirements.
 // Currently using French formatting rules so digits are space-separated in groups
 // of three, starting from the right side. Incidentally, this works for both the
@@ -86,18 +176,23 @@ fn split_at_first_non_zero(s: String) -> Option<(String, String)> {
 }
 
 // Build the rendering elements for displaying a Bitcoin amount.
-// The text should be bolded beginning where the BTC amount is non-zero.
+// The text should be bolded beginning where the amount is non-zero.
 fn render_amount<'a, T: 'a>(
     amount: String,
     size: u16,
     color_before: Color,
     color_after: Option<Color>,
+    unit: BitcoinDisplayUnit,
 ) -> Row<'a, T> {
     let spacing = if size > P1_SIZE { 10 } else { 5 };
 
-    let (before, after) = match split_at_first_non_zero(amount) {
-        Some((b, a)) => (b, a),
-        None => (String::from("0.00 000 000"), String::from("")),
+    let (before, after) = if amount == HIDDEN_AMOUNT {
+        (amount, String::new())
+    } else {
+        match split_at_first_non_zero(amount) {
+            Some((b, a)) => (b, a),
+            None => (amount_as_string(Amount::ZERO, unit), String::from("")),
+        }
     };
 
     let mut child_after = text(after).size(size).bold();
@@ -110,19 +205,26 @@ fn render_amount<'a, T: 'a>(
 
     Row::with_children(vec![
         row.into(),
-        text("BTC").size(size).style(color_before).into(),
+        text(unit.to_string()).size(size).style(color_before).into(),
     ])
     .spacing(spacing)
     .align_items(iced::Alignment::Center)
 }
 
 // Build the rendering elements for displaying a Bitcoin amount.
-fn render_unconfirmed_amount<'a, T: 'a>(amount: String, size: u16) -> Row<'a, T> {
+fn render_unconfirmed_amount<'a, T: 'a>(
+    amount: String,
+    size: u16,
+    unit: BitcoinDisplayUnit,
+) -> Row<'a, T> {
     let spacing = if size > P1_SIZE { 10 } else { 5 };
 
     Row::with_children(vec![
         text(amount).size(size).style(color::GREY_3).into(),
-        text("BTC").size(size).style(color::GREY_3).into(),
+        text(unit.to_string())
+            .size(size)
+            .style(color::GREY_3)
+            .into(),
     ])
     .spacing(spacing)
     .align_items(iced::Alignment::Center)
@@ -136,19 +238,85 @@ mod tests {
     fn test_amount_as_str() {
         assert_eq!(
             "0.00 799 800",
-            amount_as_string(bitcoin::Amount::from_btc(0.00799800).unwrap())
+            amount_as_string(
+                bitcoin::Amount::from_btc(0.00799800).unwrap(),
+                BitcoinDisplayUnit::Btc
+            )
         );
         assert_eq!(
             "1 000.00 799 800",
-            amount_as_string(bitcoin::Amount::from_btc(1000.00799800).unwrap())
+            amount_as_string(
+                bitcoin::Amount::from_btc(1000.00799800).unwrap(),
+                BitcoinDisplayUnit::Btc
+            )
         );
         assert_eq!(
             "1 000.00 000 000",
-            amount_as_string(bitcoin::Amount::from_btc(1000.0).unwrap())
+            amount_as_string(
+                bitcoin::Amount::from_btc(1000.0).unwrap(),
+                BitcoinDisplayUnit::Btc
+            )
         );
         assert_eq!(
             "0.00 012 340",
-            amount_as_string(bitcoin::Amount::from_btc(0.00012340).unwrap())
+            amount_as_string(
+                bitcoin::Amount::from_btc(0.00012340).unwrap(),
+                BitcoinDisplayUnit::Btc
+            )
         )
     }
+    #[test]
+    fn test_hidden_amount() {
+        for unit in BitcoinDisplayUnit::ALL {
+            assert_eq!(
+                format_amount(Amount::from_sat(799_800), unit, true),
+                HIDDEN_AMOUNT
+            );
+            assert_eq!(format_amount(Amount::ZERO, unit, true), HIDDEN_AMOUNT);
+        }
+    }
+
+    #[test]
+    fn test_amount_as_str_in_sats() {
+        assert_eq!(
+            "799 800",
+            amount_as_string(Amount::from_sat(799_800), BitcoinDisplayUnit::Sats)
+        );
+        assert_eq!(
+            "100 000 799 800",
+            amount_as_string(Amount::from_sat(100_000_799_800), BitcoinDisplayUnit::Sats)
+        );
+        assert_eq!(
+            "0",
+            amount_as_string(Amount::ZERO, BitcoinDisplayUnit::Sats)
+        );
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        for (s, unit, sats) in [
+            ("0.001", BitcoinDisplayUnit::Btc, 100_000),
+            ("1 000.00 799 800", BitcoinDisplayUnit::Btc, 100_000_799_800),
+            ("1_000", BitcoinDisplayUnit::Btc, 100_000_000_000),
+            ("100000", BitcoinDisplayUnit::Sats, 100_000),
+            ("100 000", BitcoinDisplayUnit::Sats, 100_000),
+            ("100,000", BitcoinDisplayUnit::Sats, 100_000),
+        ] {
+            assert_eq!(parse_amount(s, unit).unwrap(), Amount::from_sat(sats));
+        }
+        // No sub-sat amounts.
+        assert!(parse_amount("0.000000001", BitcoinDisplayUnit::Btc).is_err());
+        assert!(parse_amount("1.5", BitcoinDisplayUnit::Sats).is_err());
+        // A comma could be a decimal separator.
+        assert!(parse_amount("0,001", BitcoinDisplayUnit::Btc).is_err());
+        assert!(parse_amount("", BitcoinDisplayUnit::Sats).is_err());
+    }
+
+    #[test]
+    fn test_amount_as_input() {
+        let a = Amount::from_sat(100_000_799_800);
+        for unit in BitcoinDisplayUnit::ALL {
+            assert_eq!(parse_amount(&amount_as_input(a, unit), unit).unwrap(), a);
+        }
+    }
 }
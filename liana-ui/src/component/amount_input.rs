@@ -0,0 +1,262 @@
+use bitcoin::Amount;
+use iced::{widget::text_input, Alignment, Length};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color,
+    component::{
+        amount::{amounts_hidden, parse_amount, BitcoinDisplayUnit, HIDDEN_AMOUNT},
+        form::Value,
+        text,
+    },
+    theme,
+    widget::*,
+};
+
+/// Fiat currency amounts can be displayed in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Usd,
+    Eur,
+    Gbp,
+    Chf,
+    Jpy,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 5] = [
+        Currency::Usd,
+        Currency::Eur,
+        Currency::Gbp,
+        Currency::Chf,
+        Currency::Jpy,
+    ];
+
+    /// Number of decimals amounts in this currency are displayed with.
+    fn decimals(self) -> usize {
+        match self {
+            Currency::Jpy => 0,
+            _ => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Currency::Usd => write!(f, "USD"),
+            Currency::Eur => write!(f, "EUR"),
+            Currency::Gbp => write!(f, "GBP"),
+            Currency::Chf => write!(f, "CHF"),
+            Currency::Jpy => write!(f, "JPY"),
+        }
+    }
+}
+
+/// The price of one bitcoin in a fiat currency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExchangeRate {
+    pub currency: Currency,
+    pub price: f64,
+}
+
+impl ExchangeRate {
+    /// The fiat value of the amount, with the currency code. Hidden along with the amounts.
+    pub fn fiat_value(&self, amount: Amount) -> String {
+        if amounts_hidden() {
+            return format!("{} {}", HIDDEN_AMOUNT, self.currency);
+        }
+        format!(
+            "{:.*} {}",
+            self.currency.decimals(),
+            amount.to_btc() * self.price,
+            self.currency
+        )
+    }
+}
+
+/// Emitted by the [`AmountInput`] when its value changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmountEdited {
+    /// The text of the input, with a decimal comma replaced by a dot.
+    pub value: String,
+    /// The amount entered, `None` if the input is empty.
+    pub amount: Option<Amount>,
+    /// The fiat value of the amount, if an exchange rate is known.
+    pub fiat: Option<String>,
+}
+
+// Replace a decimal comma, as typed in many locales, by a dot. Amounts in sats have no decimals,
+// so commas are left as digit separators.
+fn normalize(s: &str, unit: BitcoinDisplayUnit) -> String {
+    if unit == BitcoinDisplayUnit::Btc && !s.contains('.') && s.matches(',').count() == 1 {
+        s.replace(',', ".")
+    } else {
+        s.to_string()
+    }
+}
+
+/// Parse an amount typed in an [`AmountInput`]. Negative amounts and amounts above the 21
+/// million bitcoins supply are refused.
+pub fn parse_amount_input(s: &str, unit: BitcoinDisplayUnit) -> Option<Amount> {
+    parse_amount(&normalize(s, unit), unit)
+        .ok()
+        .filter(|amount| *amount <= Amount::MAX_MONEY)
+}
+
+/// An amount input displaying the fiat equivalent of the amount as it is typed. Keystrokes that
+/// would make the amount invalid are ignored.
+pub struct AmountInput<'a, Message> {
+    input: TextInput<'a, Message>,
+    fiat: Option<String>,
+    warning: Option<&'a str>,
+    valid: bool,
+}
+
+impl<'a, Message: 'a> AmountInput<'a, Message>
+where
+    Message: Clone,
+{
+    /// Creates a new [`AmountInput`].
+    ///
+    /// It expects:
+    /// - a placeholder
+    /// - the current value
+    /// - the unit of the amount
+    /// - the exchange rate to display the fiat value with, if any
+    /// - a function that produces a message when the [`AmountInput`] changes
+    pub fn new<F>(
+        placeholder: &str,
+        value: &'a Value<String>,
+        unit: BitcoinDisplayUnit,
+        rate: Option<ExchangeRate>,
+        on_change: F,
+    ) -> Self
+    where
+        F: 'static + Fn(AmountEdited) -> Message,
+    {
+        let edited = move |value: String, amount: Option<Amount>| AmountEdited {
+            fiat: amount.and_then(|a| rate.map(|r| r.fiat_value(a))),
+            value,
+            amount,
+        };
+        Self {
+            input: text_input::TextInput::new(placeholder, &value.value).on_input(move |s| {
+                if s.is_empty() {
+                    return on_change(edited(s, None));
+                }
+                match parse_amount_input(&s, unit) {
+                    Some(amount) => on_change(edited(normalize(&s, unit), Some(amount))),
+                    None => on_change(edited(
+                        value.value.clone(),
+                        parse_amount_input(&value.value, unit),
+                    )),
+                }
+            }),
+            fiat: parse_amount_input(&value.value, unit)
+                .and_then(|amount| rate.map(|r| r.fiat_value(amount))),
+            warning: None,
+            valid: value.valid,
+        }
+    }
+
+    /// Sets the [`AmountInput`] with a warning message
+    pub fn warning(mut self, warning: &'a str) -> Self {
+        self.warning = Some(warning);
+        self
+    }
+
+    /// Sets the padding of the [`AmountInput`].
+    pub fn padding(mut self, units: u16) -> Self {
+        self.input = self.input.padding(units);
+        self
+    }
+
+    /// Sets the [`AmountInput`] with a text size
+    pub fn size(mut self, size: u16) -> Self {
+        self.input = self.input.size(size);
+        self
+    }
+}
+
+impl<'a, Message: 'a + Clone> From<AmountInput<'a, Message>> for Element<'a, Message> {
+    fn from(input: AmountInput<'a, Message>) -> Element<'a, Message> {
+        Container::new(
+            Column::new()
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Alignment::Center)
+                        .push(if !input.valid {
+                            input.input.style(theme::Form::Invalid)
+                        } else {
+                            input.input
+                        })
+                        .push_maybe(input.fiat.map(|fiat| {
+                            text::p1_regular(format!("≈ {}", fiat)).style(color::GREY_3)
+                        })),
+                )
+                .push_maybe(if !input.valid {
+                    input
+                        .warning
+                        .map(|message| text::caption(message).style(color::RED))
+                } else {
+                    None
+                })
+                .width(Length::Fill)
+                .spacing(5),
+        )
+        .width(Length::Fill)
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amount_input() {
+        for (s, unit, sats) in [
+            ("0.001", BitcoinDisplayUnit::Btc, Some(100_000)),
+            ("0,001", BitcoinDisplayUnit::Btc, Some(100_000)),
+            ("1 000,5", BitcoinDisplayUnit::Btc, Some(100_050_000_000)),
+            ("1,000,5", BitcoinDisplayUnit::Btc, None),
+            ("100,000", BitcoinDisplayUnit::Sats, Some(100_000)),
+            ("-0.1", BitcoinDisplayUnit::Btc, None),
+            ("-100", BitcoinDisplayUnit::Sats, None),
+            (
+                "21000000",
+                BitcoinDisplayUnit::Btc,
+                Some(2_100_000_000_000_000),
+            ),
+            ("21000000.00000001", BitcoinDisplayUnit::Btc, None),
+            ("2100000000000001", BitcoinDisplayUnit::Sats, None),
+        ] {
+            assert_eq!(
+                parse_amount_input(s, unit).map(|a| a.to_sat()),
+                sats,
+                "{}",
+                s
+            );
+        }
+        assert_eq!(normalize("0,5", BitcoinDisplayUnit::Btc), "0.5");
+        assert_eq!(normalize("500,000", BitcoinDisplayUnit::Sats), "500,000");
+    }
+
+    #[test]
+    fn test_fiat_value() {
+        let rate = ExchangeRate {
+            currency: Currency::Eur,
+            price: 60_000.0,
+        };
+        assert_eq!(rate.fiat_value(Amount::from_sat(150_000)), "90.00 EUR");
+        let rate = ExchangeRate {
+            currency: Currency::Jpy,
+            price: 9_000_000.0,
+        };
+        assert_eq!(rate.fiat_value(Amount::from_sat(150_000)), "13500 JPY");
+    }
+}
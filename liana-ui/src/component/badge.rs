@@ -96,6 +96,34 @@ pub fn deprecated<'a, T: 'a>() -> Container<'a, T> {
     )
 }
 
+pub fn superseded<'a, T: 'a>() -> Container<'a, T> {
+    badge_pill(
+        "  Superseded  ",
+        "A replacement of this transaction was included in the blockchain.",
+    )
+}
+
+pub fn replaced<'a, T: 'a>() -> Container<'a, T> {
+    badge_pill(
+        "  Replaced  ",
+        "This transaction was replaced by another one, which is not confirmed yet.",
+    )
+}
+
+pub fn scheduled<'a, T: 'a>() -> Container<'a, T> {
+    badge_pill(
+        "  Scheduled  ",
+        "The transaction will be broadcast later, while the application is running.",
+    )
+}
+
+pub fn verified<'a, T: 'a>() -> Container<'a, T> {
+    badge_pill(
+        "  Verified ✓  ",
+        "The address was displayed and confirmed on a hardware device.",
+    )
+}
+
 pub fn spent<'a, T: 'a>() -> Container<'a, T> {
     badge_pill(
         "  Spent  ",
@@ -103,6 +131,10 @@ pub fn spent<'a, T: 'a>() -> Container<'a, T> {
     )
 }
 
+pub fn update_recommended<'a, T: 'a>(update_instructions: &'a str) -> Container<'a, T> {
+    badge_pill("  Update recommended  ", update_instructions)
+}
+
 pub fn badge_pill<'a, T: 'a>(label: &'a str, tooltip: &'a str) -> Container<'a, T> {
     Container::new({
         tooltip::Tooltip::new(
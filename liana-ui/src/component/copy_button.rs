@@ -0,0 +1,302 @@
+//! A button copying a text to the clipboard. Once clicked, or activated with Enter or Space
+//! while focused, its content is replaced by a "Copied!" label for a moment.
+use std::time::Duration;
+
+use iced::{
+    advanced::{
+        layout, renderer,
+        widget::{
+            operation::{Focusable, Operation},
+            tree::{self, Tree},
+        },
+        Clipboard, Layout, Renderer as _, Shell, Widget,
+    },
+    event, keyboard, mouse,
+    time::Instant,
+    touch,
+    widget::button::StyleSheet,
+    window, Alignment, Background, Color, Event, Length, Padding, Rectangle, Size,
+};
+
+use crate::{
+    color,
+    component::text,
+    icon,
+    theme::{self, Theme},
+    widget::*,
+};
+
+/// For how long the "Copied!" label is displayed.
+const COPIED_LABEL_DURATION: Duration = Duration::from_secs(2);
+
+/// The indexes of the children: the content, and the label replacing it once copied.
+const CONTENT: usize = 0;
+const COPIED_LABEL: usize = 1;
+
+pub struct CopyButton<'a, Message> {
+    value: String,
+    children: [Element<'a, Message>; 2],
+    padding: Padding,
+    style: theme::Button,
+}
+
+/// A clipboard icon copying the value.
+pub fn copy_button<'a, Message: 'a>(value: impl Into<String>) -> CopyButton<'a, Message> {
+    CopyButton::new(icon::clipboard_icon().style(color::GREY_3), value)
+}
+
+impl<'a, Message: 'a> CopyButton<'a, Message> {
+    /// Creates a new [`CopyButton`] displaying the content, which copies the value.
+    pub fn new(content: impl Into<Element<'a, Message>>, value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            children: [
+                content.into(),
+                Row::new()
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .push(icon::check_icon().style(color::GREEN))
+                    .push(text::p2_regular("Copied!").style(color::GREEN))
+                    .into(),
+            ],
+            padding: Padding::new(5.0),
+            style: theme::Button::TransparentBorder,
+        }
+    }
+
+    /// Sets the padding of the [`CopyButton`].
+    pub fn padding(mut self, padding: impl Into<Padding>) -> Self {
+        self.padding = padding.into();
+        self
+    }
+
+    /// Sets the style of the [`CopyButton`].
+    pub fn style(mut self, style: theme::Button) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    is_focused: bool,
+    is_pressed: bool,
+    copied_at: Option<Instant>,
+}
+
+impl State {
+    fn displayed_child(&self) -> usize {
+        if self.copied_at.is_some() {
+            COPIED_LABEL
+        } else {
+            CONTENT
+        }
+    }
+}
+
+impl Focusable for State {
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
+
+    fn focus(&mut self) {
+        self.is_focused = true;
+    }
+
+    fn unfocus(&mut self) {
+        self.is_focused = false;
+    }
+}
+
+impl<'a, Message: 'a> CopyButton<'a, Message> {
+    fn copy(&self, state: &mut State, clipboard: &mut dyn Clipboard, shell: &mut Shell<Message>) {
+        clipboard.write(self.value.clone());
+        let now = Instant::now();
+        state.copied_at = Some(now);
+        shell.invalidate_layout();
+        shell.request_redraw(window::RedrawRequest::At(now + COPIED_LABEL_DURATION));
+    }
+}
+
+impl<'a, Message: 'a> Widget<Message, Theme, Renderer> for CopyButton<'a, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        self.children.iter().map(|child| Tree::new(child)).collect()
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(self.children.as_slice());
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size {
+            width: Length::Shrink,
+            height: Length::Shrink,
+        }
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let displayed = tree.state.downcast_ref::<State>().displayed_child();
+        layout::padded(
+            limits,
+            Length::Shrink,
+            Length::Shrink,
+            self.padding,
+            |limits| {
+                self.children[displayed].as_widget().layout(
+                    &mut tree.children[displayed],
+                    renderer,
+                    limits,
+                )
+            },
+        )
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        _layout: Layout<'_>,
+        _renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        let state = tree.state.downcast_mut::<State>();
+        operation.focusable(state, None);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        match event {
+            Event::Window(_, window::Event::RedrawRequested(now)) => {
+                if let Some(copied_at) = state.copied_at {
+                    if now.duration_since(copied_at) >= COPIED_LABEL_DURATION {
+                        state.copied_at = None;
+                        shell.invalidate_layout();
+                    } else {
+                        shell.request_redraw(window::RedrawRequest::At(
+                            copied_at + COPIED_LABEL_DURATION,
+                        ));
+                    }
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+            | Event::Touch(touch::Event::FingerPressed { .. }) => {
+                if cursor.is_over(layout.bounds()) {
+                    state.is_pressed = true;
+                    return event::Status::Captured;
+                }
+                state.is_focused = false;
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+            | Event::Touch(touch::Event::FingerLifted { .. }) => {
+                if state.is_pressed {
+                    state.is_pressed = false;
+                    if cursor.is_over(layout.bounds()) {
+                        self.copy(state, clipboard, shell);
+                    }
+                    return event::Status::Captured;
+                }
+            }
+            Event::Touch(touch::Event::FingerLost { .. }) => {
+                state.is_pressed = false;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(keyboard::key::Named::Enter | keyboard::key::Named::Space),
+                ..
+            }) if state.is_focused => {
+                self.copy(state, clipboard, shell);
+                return event::Status::Captured;
+            }
+            _ => {}
+        }
+        event::Status::Ignored
+    }
+
+    fn mouse_interaction(
+        &self,
+        _tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if cursor.is_over(layout.bounds()) {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        // A focused button looks hovered, for the keyboard users to know where they are.
+        let appearance = if state.is_pressed {
+            theme.pressed(&self.style)
+        } else if state.is_focused || cursor.is_over(bounds) {
+            theme.hovered(&self.style)
+        } else {
+            theme.active(&self.style)
+        };
+        if appearance.background.is_some() || appearance.border.width > 0.0 {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds,
+                    border: appearance.border,
+                    shadow: appearance.shadow,
+                },
+                appearance
+                    .background
+                    .unwrap_or(Background::Color(Color::TRANSPARENT)),
+            );
+        }
+        let displayed = state.displayed_child();
+        self.children[displayed].as_widget().draw(
+            &tree.children[displayed],
+            renderer,
+            theme,
+            &renderer::Style {
+                text_color: appearance.text_color,
+            },
+            layout.children().next().expect("The content is laid out"),
+            cursor,
+            viewport,
+        );
+    }
+}
+
+impl<'a, Message: 'a> From<CopyButton<'a, Message>> for Element<'a, Message> {
+    fn from(button: CopyButton<'a, Message>) -> Self {
+        Element::new(button)
+    }
+}
@@ -15,6 +15,7 @@ use chrono::{DateTime, Local, Utc};
 pub fn unconfirmed_outgoing_event<'a, T: Clone + 'a>(
     label: Option<Text<'a>>,
     amount: &Amount,
+    unit: amount::BitcoinDisplayUnit,
     msg: T,
 ) -> Container<'a, T> {
     Container::new(
@@ -25,7 +26,7 @@ pub fn unconfirmed_outgoing_event<'a, T: Clone + 'a>(
                     .align_items(Alignment::Center)
                     .width(Length::Fill),
                 badge::unconfirmed(),
-                row!(text::p1_regular("-"), amount::amount(amount))
+                row!(text::p1_regular("-"), amount::amount(amount, unit))
                     .spacing(5)
                     .align_items(Alignment::Center),
             )
@@ -43,6 +44,7 @@ pub fn confirmed_outgoing_event<'a, T: Clone + 'a>(
     label: Option<Text<'a>>,
     date: DateTime<Utc>,
     amount: &Amount,
+    unit: amount::BitcoinDisplayUnit,
     msg: T,
 ) -> Container<'a, T> {
     Container::new(
@@ -62,7 +64,7 @@ pub fn confirmed_outgoing_event<'a, T: Clone + 'a>(
                 .spacing(10)
                 .align_items(Alignment::Center)
                 .width(Length::Fill),
-                row!(text::p1_regular("-"), amount::amount(amount))
+                row!(text::p1_regular("-"), amount::amount(amount, unit))
                     .spacing(5)
                     .align_items(Alignment::Center),
             )
@@ -79,6 +81,7 @@ pub fn confirmed_outgoing_event<'a, T: Clone + 'a>(
 pub fn unconfirmed_incoming_event<'a, T: Clone + 'a>(
     label: Option<Text<'a>>,
     amount: &Amount,
+    unit: amount::BitcoinDisplayUnit,
     msg: T,
 ) -> Container<'a, T> {
     Container::new(
@@ -89,7 +92,7 @@ pub fn unconfirmed_incoming_event<'a, T: Clone + 'a>(
                     .align_items(Alignment::Center)
                     .width(Length::Fill),
                 badge::unconfirmed(),
-                row!(text::p1_regular("+"), amount::amount(amount))
+                row!(text::p1_regular("+"), amount::amount(amount, unit))
                     .spacing(5)
                     .align_items(Alignment::Center),
             )
@@ -107,6 +110,7 @@ pub fn confirmed_incoming_event<'a, T: Clone + 'a>(
     label: Option<Text<'a>>,
     date: DateTime<Utc>,
     amount: &Amount,
+    unit: amount::BitcoinDisplayUnit,
     msg: T,
 ) -> Container<'a, T> {
     Container::new(
@@ -126,7 +130,7 @@ pub fn confirmed_incoming_event<'a, T: Clone + 'a>(
                 .spacing(10)
                 .align_items(Alignment::Center)
                 .width(Length::Fill),
-                row!(text::p1_regular("+"), amount::amount(amount))
+                row!(text::p1_regular("+"), amount::amount(amount, unit))
                     .spacing(5)
                     .align_items(Alignment::Center),
             )
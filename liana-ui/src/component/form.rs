@@ -1,7 +1,14 @@
-use bitcoin::Denomination;
 use iced::{widget::text_input, Length};
 
-use crate::{color, component::text, theme, widget::*};
+use crate::{
+    color,
+    component::{
+        amount::{parse_amount, BitcoinDisplayUnit},
+        text,
+    },
+    theme,
+    widget::*,
+};
 
 #[derive(Debug, Clone)]
 pub struct Value<T> {
@@ -63,19 +70,25 @@ where
         }
     }
 
-    /// Creates a new [`Form`] that restrict input values to valid btc amount before applying the
-    /// `on_change` function.
+    /// Creates a new [`Form`] that restrict input values to valid amounts in the given unit
+    /// before applying the `on_change` function.
     /// It expects:
     /// - a placeholder
     /// - the current value
+    /// - the unit of the amount
     /// - a function that produces a message when the [`Form`] changes
-    pub fn new_amount_btc<F>(placeholder: &str, value: &'a Value<String>, on_change: F) -> Self
+    pub fn new_amount<F>(
+        placeholder: &str,
+        value: &'a Value<String>,
+        unit: BitcoinDisplayUnit,
+        on_change: F,
+    ) -> Self
     where
         F: 'static + Fn(String) -> Message,
     {
         Self {
             input: text_input::TextInput::new(placeholder, &value.value).on_input(move |s| {
-                if bitcoin::Amount::from_str_in(&s, Denomination::Bitcoin).is_ok() || s.is_empty() {
+                if parse_amount(&s, unit).is_ok() || s.is_empty() {
                     on_change(s)
                 } else {
                     on_change(value.value.clone())
@@ -103,6 +116,12 @@ where
         self.input = self.input.size(size);
         self
     }
+
+    /// Hides the value of the [`Form`], e.g. for a PIN.
+    pub fn secure(mut self) -> Self {
+        self.input = self.input.secure(true);
+        self
+    }
 }
 
 impl<'a, Message: 'a + Clone> From<Form<'a, Message>> for Element<'a, Message> {
@@ -1,4 +1,4 @@
-use crate::{color, component::text, icon, image, theme, widget::*};
+use crate::{color, component::badge, component::text, icon, image, theme, widget::*};
 use iced::{
     widget::{column, container, row, tooltip},
     Alignment, Length,
@@ -58,6 +58,36 @@ pub fn supported_hardware_wallet<'a, T: 'a, K: Display, V: Display, F: Display>(
     .padding(10)
 }
 
+pub fn update_recommended_hardware_wallet<'a, T: 'a, K: Display, V: Display, F: Display>(
+    kind: K,
+    version: Option<V>,
+    fingerprint: F,
+    alias: Option<impl Into<Cow<'a, str>>>,
+    update_instructions: &'a str,
+) -> Container<'a, T> {
+    container(
+        row(vec![
+            column(vec![
+                Row::new()
+                    .spacing(5)
+                    .push_maybe(alias.map(|a| text::p1_bold(a)))
+                    .push(text::p1_regular(format!("#{}", fingerprint)))
+                    .into(),
+                Row::new()
+                    .spacing(5)
+                    .push(text::caption(kind.to_string()))
+                    .push_maybe(version.map(|v| text::caption(v.to_string())))
+                    .into(),
+            ])
+            .width(Length::Fill)
+            .into(),
+            badge::update_recommended(update_instructions).into(),
+        ])
+        .align_items(Alignment::Center),
+    )
+    .padding(10)
+}
+
 pub fn warning_hardware_wallet<'a, T: 'a, K: Display, V: Display, F: Display>(
     kind: K,
     version: Option<V>,
@@ -289,6 +319,121 @@ pub fn registration_success_hardware_wallet<'a, T: 'a, K: Display, V: Display, F
     .padding(10)
 }
 
+pub fn address_verified_hardware_wallet<'a, T: 'a, K: Display, V: Display, F: Display>(
+    kind: K,
+    version: Option<V>,
+    fingerprint: F,
+    alias: Option<impl Into<Cow<'a, str>>>,
+) -> Container<'a, T> {
+    container(
+        row(vec![
+            column(vec![
+                Row::new()
+                    .spacing(5)
+                    .push_maybe(alias.map(|a| text::p1_bold(a)))
+                    .push(text::p1_regular(format!("#{}", fingerprint)))
+                    .into(),
+                Row::new()
+                    .spacing(5)
+                    .push(text::caption(kind.to_string()))
+                    .push_maybe(version.map(|v| text::caption(v.to_string())))
+                    .into(),
+            ])
+            .width(Length::Fill)
+            .into(),
+            row(vec![
+                text::p1_regular("Confirmed").style(color::GREEN).into(),
+                image::success_mark_icon().width(Length::Fixed(50.0)).into(),
+            ])
+            .align_items(Alignment::Center)
+            .spacing(5)
+            .into(),
+        ])
+        .align_items(Alignment::Center),
+    )
+    .padding(10)
+}
+
+pub fn address_rejected_hardware_wallet<'a, T: 'a, K: Display, V: Display, F: Display>(
+    kind: K,
+    version: Option<V>,
+    fingerprint: F,
+    alias: Option<impl Into<Cow<'a, str>>>,
+    reason: impl Into<Cow<'a, str>>,
+) -> Container<'a, T> {
+    container(
+        row(vec![
+            column(vec![
+                Row::new()
+                    .spacing(5)
+                    .push_maybe(alias.map(|a| text::p1_bold(a)))
+                    .push(text::p1_regular(format!("#{}", fingerprint)))
+                    .into(),
+                Row::new()
+                    .spacing(5)
+                    .push(text::caption(kind.to_string()))
+                    .push_maybe(version.map(|v| text::caption(v.to_string())))
+                    .into(),
+            ])
+            .width(Length::Fill)
+            .into(),
+            row(vec![
+                text::p1_regular(reason).style(color::RED).into(),
+                icon::warning_icon().style(color::RED).into(),
+            ])
+            .align_items(Alignment::Center)
+            .spacing(5)
+            .into(),
+        ])
+        .align_items(Alignment::Center),
+    )
+    .padding(10)
+}
+
+/// A device which failed to sign, with the error in a tooltip. The kind of the device is unknown
+/// if it was disconnected.
+pub fn sign_error_hardware_wallet<'a, T: 'a, K: Display, V: Display, F: Display>(
+    kind: Option<K>,
+    version: Option<V>,
+    fingerprint: F,
+    alias: Option<impl Into<Cow<'a, str>>>,
+    reason: impl Into<Cow<'a, str>>,
+    error: String,
+) -> Container<'a, T> {
+    container(
+        row(vec![
+            column(vec![
+                Row::new()
+                    .spacing(5)
+                    .push_maybe(alias.map(|a| text::p1_bold(a)))
+                    .push(text::p1_regular(format!("#{}", fingerprint)))
+                    .into(),
+                Row::new()
+                    .spacing(5)
+                    .push_maybe(kind.map(|k| text::caption(k.to_string())))
+                    .push_maybe(version.map(|v| text::caption(v.to_string())))
+                    .into(),
+            ])
+            .width(Length::Fill)
+            .into(),
+            tooltip::Tooltip::new(
+                row(vec![
+                    text::p1_regular(reason).style(color::RED).into(),
+                    icon::warning_icon().style(color::RED).into(),
+                ])
+                .align_items(Alignment::Center)
+                .spacing(5),
+                text::p1_regular(error),
+                tooltip::Position::Bottom,
+            )
+            .style(theme::Container::Card(theme::Card::Simple))
+            .into(),
+        ])
+        .align_items(Alignment::Center),
+    )
+    .padding(10)
+}
+
 pub fn wrong_network_hardware_wallet<'a, T: 'a, K: Display, V: Display>(
     kind: K,
     version: Option<V>,
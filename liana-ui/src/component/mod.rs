@@ -1,13 +1,17 @@
 pub mod amount;
+pub mod amount_input;
 pub mod badge;
 pub mod button;
 pub mod card;
 pub mod collapse;
+pub mod copy_button;
 pub mod event;
 pub mod form;
 pub mod hw;
 pub mod modal;
 pub mod notification;
+pub mod policy_diagram;
+pub mod qr;
 pub mod spinner;
 pub mod text;
 pub mod toast;
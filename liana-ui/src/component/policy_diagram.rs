@@ -0,0 +1,697 @@
+use iced::{
+    alignment, mouse,
+    widget::canvas::{self, Canvas, Frame, Path, Stroke},
+    Color, Length, Point, Rectangle, Size,
+};
+
+use crate::{color, font, theme::Theme, widget::*};
+
+const MARGIN: f32 = 20.0;
+const BOX_WIDTH: f32 = 220.0;
+const BOX_PADDING: f32 = 12.0;
+const HEADER_HEIGHT: f32 = 56.0;
+const KEY_HEIGHT: f32 = 28.0;
+const KEY_SPACING: f32 = 8.0;
+const BOX_SPACING: f32 = 24.0;
+// Horizontal room between the primary path and the recovery paths, where the arrows and their
+// timelock label are drawn.
+const ARROW_GAP: f32 = 200.0;
+const ARROW_HEAD_SIZE: f32 = 8.0;
+const LABEL_OFFSET: f32 = 6.0;
+
+const TITLE_SIZE: f32 = 16.0;
+const TEXT_SIZE: f32 = 14.0;
+
+const MAX_KEY_LABEL_CHARS: usize = 24;
+
+/// A spending path of the wallet policy: a threshold among a set of keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendingPath {
+    pub threshold: usize,
+    /// The name of each key, usually its alias or its fingerprint.
+    pub keys: Vec<String>,
+}
+
+/// A picture of the wallet policy: the primary path box with an arrow to each recovery path
+/// box, annotated with the time after which the recovery path becomes available.
+#[derive(Debug, Clone)]
+pub struct PolicyDiagram {
+    layout: Layout,
+}
+
+impl PolicyDiagram {
+    /// The recovery paths are given along with the label of their timelock (for instance
+    /// "after ~6 months"), in the order they should be displayed.
+    pub fn new(primary: SpendingPath, recovery: Vec<(String, SpendingPath)>) -> Self {
+        Self {
+            layout: Layout::new(&primary, &recovery),
+        }
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    pub fn size(&self) -> Size {
+        self.layout.size
+    }
+
+    /// Render the diagram as a standalone SVG document, for instance to export it.
+    pub fn to_svg(&self) -> String {
+        let Layout {
+            size,
+            boxes,
+            arrows,
+        } = &self.layout;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+            viewBox=\"0 0 {w} {h}\" font-family=\"IBM Plex Sans\">\n\
+            <rect width=\"{w}\" height=\"{h}\" fill=\"{}\"/>\n",
+            hex(color::LIGHT_BLACK),
+            w = size.width,
+            h = size.height,
+        );
+
+        for arrow in arrows {
+            let points: Vec<String> = arrow
+                .points
+                .iter()
+                .map(|p| format!("{},{}", p.x, p.y))
+                .collect();
+            svg.push_str(&format!(
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+                points.join(" "),
+                hex(color::GREY_3),
+            ));
+            let head: Vec<String> = arrow
+                .head()
+                .iter()
+                .map(|p| format!("{},{}", p.x, p.y))
+                .collect();
+            svg.push_str(&format!(
+                "<polygon points=\"{}\" fill=\"{}\"/>\n",
+                head.join(" "),
+                hex(color::GREY_3),
+            ));
+            svg.push_str(&svg_text(
+                &arrow.label,
+                arrow.label_anchor,
+                TextKind::Caption,
+                "middle",
+                alignment::Vertical::Bottom,
+            ));
+        }
+
+        for path_box in boxes {
+            let b = path_box.bounds;
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"8\" fill=\"{}\" \
+                stroke=\"{}\" stroke-width=\"2\"/>\n",
+                b.x,
+                b.y,
+                b.width,
+                b.height,
+                hex(color::GREY_6),
+                hex(path_box.border_color()),
+            ));
+            svg.push_str(&svg_text(
+                &path_box.title,
+                path_box.title_position(),
+                TextKind::Title,
+                "start",
+                alignment::Vertical::Top,
+            ));
+            svg.push_str(&svg_text(
+                &path_box.threshold,
+                path_box.threshold_position(),
+                TextKind::Caption,
+                "start",
+                alignment::Vertical::Top,
+            ));
+            for key in &path_box.keys {
+                let k = key.bounds;
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"{}\" \
+                    stroke=\"{}\"/>\n",
+                    k.x,
+                    k.y,
+                    k.width,
+                    k.height,
+                    hex(color::GREY_5),
+                    hex(color::GREY_4),
+                ));
+                svg.push_str(&svg_text(
+                    &key.label,
+                    k.center(),
+                    TextKind::Key,
+                    "middle",
+                    alignment::Vertical::Center,
+                ));
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Positions of all the elements of a policy diagram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub size: Size,
+    /// The primary path box comes first, followed by the recovery path boxes.
+    pub boxes: Vec<PathBox>,
+    /// One arrow per recovery path, in the same order as the recovery path boxes.
+    pub arrows: Vec<Arrow>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathBox {
+    pub bounds: Rectangle,
+    pub title: String,
+    pub threshold: String,
+    pub keys: Vec<KeyNode>,
+    pub is_primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyNode {
+    pub bounds: Rectangle,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arrow {
+    pub points: Vec<Point>,
+    pub label: String,
+    /// The label is drawn centered above this point.
+    pub label_anchor: Point,
+}
+
+impl Layout {
+    fn new(primary: &SpendingPath, recovery: &[(String, SpendingPath)]) -> Self {
+        let recovery_x = MARGIN + BOX_WIDTH + ARROW_GAP;
+        let mut boxes = Vec::with_capacity(recovery.len() + 1);
+        let mut arrows = Vec::with_capacity(recovery.len());
+
+        let mut y = MARGIN;
+        for (i, (_, path)) in recovery.iter().enumerate() {
+            let path_box = PathBox::new(
+                Point::new(recovery_x, y),
+                format!("Recovery path #{}", i + 1),
+                path,
+                false,
+            );
+            y += path_box.bounds.height + BOX_SPACING;
+            boxes.push(path_box);
+        }
+        let recovery_height = if recovery.is_empty() {
+            0.0
+        } else {
+            y - BOX_SPACING - MARGIN
+        };
+
+        // The primary path box is vertically centered next to the recovery path boxes.
+        let primary_height = PathBox::height(primary.keys.len());
+        let primary_box = PathBox::new(
+            Point::new(
+                MARGIN,
+                MARGIN + ((recovery_height - primary_height) / 2.0).max(0.0),
+            ),
+            "Primary path".to_string(),
+            primary,
+            true,
+        );
+
+        // The arrows leave the primary path box horizontally, turn toward their recovery path
+        // box and enter it horizontally. The label sits on the last segment, so labels of
+        // different arrows can't overlap as long as boxes don't.
+        let start = Point::new(
+            primary_box.bounds.x + primary_box.bounds.width,
+            primary_box.bounds.center_y(),
+        );
+        let turn_x = start.x + ARROW_GAP / 3.0;
+        for ((label, _), path_box) in recovery.iter().zip(boxes.iter()) {
+            let end = Point::new(path_box.bounds.x, path_box.bounds.center_y());
+            arrows.push(Arrow {
+                points: vec![
+                    start,
+                    Point::new(turn_x, start.y),
+                    Point::new(turn_x, end.y),
+                    end,
+                ],
+                label: label.clone(),
+                label_anchor: Point::new((turn_x + end.x) / 2.0, end.y - LABEL_OFFSET),
+            });
+        }
+        boxes.insert(0, primary_box);
+
+        let width = if recovery.is_empty() {
+            2.0 * MARGIN + BOX_WIDTH
+        } else {
+            recovery_x + BOX_WIDTH + MARGIN
+        };
+        let height = 2.0 * MARGIN + recovery_height.max(primary_height);
+
+        Self {
+            size: Size::new(width, height),
+            boxes,
+            arrows,
+        }
+    }
+}
+
+impl PathBox {
+    fn new(position: Point, title: String, path: &SpendingPath, is_primary: bool) -> Self {
+        let keys = path
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| KeyNode {
+                bounds: Rectangle::new(
+                    Point::new(
+                        position.x + BOX_PADDING,
+                        position.y + HEADER_HEIGHT + i as f32 * (KEY_HEIGHT + KEY_SPACING),
+                    ),
+                    Size::new(BOX_WIDTH - 2.0 * BOX_PADDING, KEY_HEIGHT),
+                ),
+                label: truncate(key, MAX_KEY_LABEL_CHARS),
+            })
+            .collect();
+        Self {
+            bounds: Rectangle::new(
+                position,
+                Size::new(BOX_WIDTH, Self::height(path.keys.len())),
+            ),
+            title,
+            threshold: threshold_label(path.threshold, path.keys.len()),
+            keys,
+            is_primary,
+        }
+    }
+
+    fn height(n_keys: usize) -> f32 {
+        let keys_height = if n_keys == 0 {
+            0.0
+        } else {
+            n_keys as f32 * KEY_HEIGHT + (n_keys - 1) as f32 * KEY_SPACING
+        };
+        HEADER_HEIGHT + keys_height + BOX_PADDING
+    }
+
+    fn title_position(&self) -> Point {
+        Point::new(self.bounds.x + BOX_PADDING, self.bounds.y + BOX_PADDING)
+    }
+
+    fn threshold_position(&self) -> Point {
+        Point::new(
+            self.bounds.x + BOX_PADDING,
+            self.bounds.y + BOX_PADDING + TITLE_SIZE + 6.0,
+        )
+    }
+
+    fn border_color(&self) -> Color {
+        if self.is_primary {
+            color::GREEN
+        } else {
+            color::ORANGE
+        }
+    }
+}
+
+impl Arrow {
+    /// The triangle at the end of the arrow, pointing in the direction of its last segment.
+    fn head(&self) -> [Point; 3] {
+        let end = self.points[self.points.len() - 1];
+        let before = self.points[self.points.len() - 2];
+        let (dx, dy) = (end.x - before.x, end.y - before.y);
+        let norm = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+        let (ux, uy) = (dx / norm, dy / norm);
+        let base = Point::new(end.x - ux * ARROW_HEAD_SIZE, end.y - uy * ARROW_HEAD_SIZE);
+        let half = ARROW_HEAD_SIZE / 2.0;
+        [
+            end,
+            Point::new(base.x - uy * half, base.y + ux * half),
+            Point::new(base.x + uy * half, base.y - ux * half),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextKind {
+    Title,
+    Caption,
+    Key,
+}
+
+impl TextKind {
+    fn size(self) -> f32 {
+        match self {
+            Self::Title => TITLE_SIZE,
+            Self::Caption | Self::Key => TEXT_SIZE,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Title | Self::Key => color::WHITE,
+            Self::Caption => color::GREY_2,
+        }
+    }
+
+    fn is_bold(self) -> bool {
+        matches!(self, Self::Title)
+    }
+}
+
+fn threshold_label(threshold: usize, n_keys: usize) -> String {
+    if n_keys == 1 {
+        "1 key".to_string()
+    } else {
+        format!("{} of {} keys", threshold, n_keys)
+    }
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        s.to_string()
+    } else {
+        let mut truncated: String = s.chars().take(max_chars - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn hex(c: Color) -> String {
+    let [r, g, b, _] = c.into_rgba8();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn svg_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn svg_text(
+    content: &str,
+    position: Point,
+    kind: TextKind,
+    anchor: &str,
+    vertical: alignment::Vertical,
+) -> String {
+    // SVG positions text by its baseline, adjust it to match the canvas vertical alignment.
+    let size = kind.size();
+    let y = match vertical {
+        alignment::Vertical::Top => position.y + size * 0.8,
+        alignment::Vertical::Center => position.y + size * 0.35,
+        alignment::Vertical::Bottom => position.y,
+    };
+    format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"{}\"{} fill=\"{}\" text-anchor=\"{}\">{}</text>\n",
+        position.x,
+        y,
+        size,
+        if kind.is_bold() {
+            " font-weight=\"bold\""
+        } else {
+            ""
+        },
+        hex(kind.color()),
+        anchor,
+        svg_escape(content),
+    )
+}
+
+fn fill_text(
+    frame: &mut Frame,
+    content: &str,
+    position: Point,
+    kind: TextKind,
+    horizontal: alignment::Horizontal,
+    vertical: alignment::Vertical,
+) {
+    frame.fill_text(canvas::Text {
+        content: content.to_string(),
+        position,
+        color: kind.color(),
+        size: kind.size().into(),
+        font: if kind.is_bold() {
+            font::BOLD
+        } else {
+            font::REGULAR
+        },
+        horizontal_alignment: horizontal,
+        vertical_alignment: vertical,
+        ..Default::default()
+    });
+}
+
+impl<Message> canvas::Program<Message, Theme> for PolicyDiagram {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        for arrow in &self.layout.arrows {
+            let line = Path::new(|b| {
+                b.move_to(arrow.points[0]);
+                for p in &arrow.points[1..] {
+                    b.line_to(*p);
+                }
+            });
+            frame.stroke(
+                &line,
+                Stroke::default().with_width(2.0).with_color(color::GREY_3),
+            );
+            let [tip, left, right] = arrow.head();
+            let head = Path::new(|b| {
+                b.move_to(tip);
+                b.line_to(left);
+                b.line_to(right);
+                b.close();
+            });
+            frame.fill(&head, color::GREY_3);
+            fill_text(
+                &mut frame,
+                &arrow.label,
+                arrow.label_anchor,
+                TextKind::Caption,
+                alignment::Horizontal::Center,
+                alignment::Vertical::Bottom,
+            );
+        }
+
+        for path_box in &self.layout.boxes {
+            let b = Path::rectangle(path_box.bounds.position(), path_box.bounds.size());
+            frame.fill(&b, color::GREY_6);
+            frame.stroke(
+                &b,
+                Stroke::default()
+                    .with_width(2.0)
+                    .with_color(path_box.border_color()),
+            );
+            fill_text(
+                &mut frame,
+                &path_box.title,
+                path_box.title_position(),
+                TextKind::Title,
+                alignment::Horizontal::Left,
+                alignment::Vertical::Top,
+            );
+            fill_text(
+                &mut frame,
+                &path_box.threshold,
+                path_box.threshold_position(),
+                TextKind::Caption,
+                alignment::Horizontal::Left,
+                alignment::Vertical::Top,
+            );
+            for key in &path_box.keys {
+                let k = Path::rectangle(key.bounds.position(), key.bounds.size());
+                frame.fill(&k, color::GREY_5);
+                frame.stroke(
+                    &k,
+                    Stroke::default().with_width(1.0).with_color(color::GREY_4),
+                );
+                fill_text(
+                    &mut frame,
+                    &key.label,
+                    key.bounds.center(),
+                    TextKind::Key,
+                    alignment::Horizontal::Center,
+                    alignment::Vertical::Center,
+                );
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Draw the policy diagram, at its natural size.
+pub fn policy_diagram<'a, Message: 'a>(diagram: PolicyDiagram) -> Element<'a, Message> {
+    let size = diagram.size();
+    Canvas::new(diagram)
+        .width(Length::Fixed(size.width))
+        .height(Length::Fixed(size.height))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(threshold: usize, n_keys: usize) -> SpendingPath {
+        SpendingPath {
+            threshold,
+            keys: (0..n_keys).map(|i| format!("Key #{}", i)).collect(),
+        }
+    }
+
+    fn contains(outer: &Rectangle, inner: &Rectangle) -> bool {
+        inner.x >= outer.x
+            && inner.y >= outer.y
+            && inner.x + inner.width <= outer.x + outer.width
+            && inner.y + inner.height <= outer.y + outer.height
+    }
+
+    fn overlap(a: &Rectangle, b: &Rectangle) -> bool {
+        a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+    }
+
+    fn check_layout(primary: SpendingPath, recovery: Vec<(String, SpendingPath)>) {
+        let diagram = PolicyDiagram::new(primary.clone(), recovery.clone());
+        let layout = diagram.layout();
+        let canvas = Rectangle::new(Point::ORIGIN, layout.size);
+
+        assert_eq!(layout.boxes.len(), recovery.len() + 1);
+        assert_eq!(layout.arrows.len(), recovery.len());
+        assert!(layout.boxes[0].is_primary);
+        assert!(layout.boxes[1..].iter().all(|b| !b.is_primary));
+
+        let paths = std::iter::once(&primary).chain(recovery.iter().map(|(_, p)| p));
+        for (path_box, path) in layout.boxes.iter().zip(paths) {
+            // Everything is drawn within the canvas, and keys within their own box.
+            assert!(contains(&canvas, &path_box.bounds));
+            assert_eq!(path_box.keys.len(), path.keys.len());
+            for (i, key) in path_box.keys.iter().enumerate() {
+                assert!(contains(&path_box.bounds, &key.bounds));
+                assert_eq!(key.label, path.keys[i]);
+                for other in &path_box.keys[i + 1..] {
+                    assert!(!overlap(&key.bounds, &other.bounds));
+                }
+            }
+        }
+
+        // Boxes never overlap.
+        for (i, a) in layout.boxes.iter().enumerate() {
+            for b in &layout.boxes[i + 1..] {
+                assert!(!overlap(&a.bounds, &b.bounds));
+            }
+        }
+
+        // Each arrow goes from the primary path box to its recovery path box, and is labelled
+        // with the recovery path timelock.
+        let primary_box = &layout.boxes[0].bounds;
+        for ((arrow, path_box), (label, _)) in
+            layout.arrows.iter().zip(&layout.boxes[1..]).zip(&recovery)
+        {
+            let start = arrow.points[0];
+            let end = arrow.points[arrow.points.len() - 1];
+            assert_eq!(start.x, primary_box.x + primary_box.width);
+            assert!(start.y > primary_box.y && start.y < primary_box.y + primary_box.height);
+            assert_eq!(end.x, path_box.bounds.x);
+            assert!(
+                end.y > path_box.bounds.y && end.y < path_box.bounds.y + path_box.bounds.height
+            );
+            assert!(arrow.points.iter().all(|p| canvas.contains(*p)));
+            assert_eq!(&arrow.label, label);
+            assert!(arrow.label_anchor.x > primary_box.x + primary_box.width);
+            assert!(arrow.label_anchor.x < path_box.bounds.x);
+        }
+
+        // Labels are far enough from each other to not overlap.
+        for (i, a) in layout.arrows.iter().enumerate() {
+            for b in &layout.arrows[i + 1..] {
+                assert!((a.label_anchor.y - b.label_anchor.y).abs() >= TEXT_SIZE);
+            }
+        }
+    }
+
+    #[test]
+    fn policy_diagram_layout() {
+        // A single key with a single recovery key.
+        check_layout(
+            path(1, 1),
+            vec![("after ~6 months".to_string(), path(1, 1))],
+        );
+
+        // Up to 5 paths with up to 10 keys each, with tall and short boxes on both sides.
+        for n_recovery in 1..=4 {
+            for n_keys in [1, 2, 3, 5, 10] {
+                let recovery: Vec<_> = (0..n_recovery)
+                    .map(|i| {
+                        let n = if i % 2 == 0 { n_keys } else { 11 - n_keys };
+                        (format!("after ~{} months", 6 * (i + 1)), path(1, n))
+                    })
+                    .collect();
+                check_layout(path(n_keys.min(3), n_keys), recovery.clone());
+                check_layout(path(1, 11 - n_keys), recovery);
+            }
+        }
+
+        // The primary box is centered next to the recovery boxes.
+        let diagram = PolicyDiagram::new(
+            path(2, 2),
+            vec![
+                ("after ~3 months".to_string(), path(1, 10)),
+                ("after ~1 year".to_string(), path(1, 10)),
+            ],
+        );
+        let layout = diagram.layout();
+        let primary = &layout.boxes[0].bounds;
+        assert_eq!(primary.center_y(), layout.size.height / 2.0);
+        assert_eq!(layout.boxes[0].threshold, "2 of 2 keys");
+        assert_eq!(layout.boxes[1].threshold, "1 of 10 keys");
+        assert_eq!(layout.boxes[1].title, "Recovery path #1");
+
+        // A primary path taller than all the recovery paths sets the diagram height.
+        let diagram =
+            PolicyDiagram::new(path(3, 10), vec![("after ~1 day".to_string(), path(1, 1))]);
+        let layout = diagram.layout();
+        assert_eq!(
+            layout.size.height,
+            layout.boxes[0].bounds.height + 2.0 * MARGIN
+        );
+        assert_eq!(layout.boxes[1].threshold, "1 key");
+    }
+
+    #[test]
+    fn policy_diagram_key_labels() {
+        let long_alias = "A very long alias for the key of my lawyer".to_string();
+        let diagram = PolicyDiagram::new(
+            SpendingPath {
+                threshold: 1,
+                keys: vec![long_alias, "<Bob & co>".to_string()],
+            },
+            vec![],
+        );
+        let label = &diagram.layout().boxes[0].keys[0].label;
+        assert_eq!(label.chars().count(), MAX_KEY_LABEL_CHARS);
+        assert!(label.ends_with('…'));
+
+        // The SVG export escapes the labels.
+        let svg = diagram.to_svg();
+        assert!(svg.contains("&lt;Bob &amp; co&gt;"));
+        assert!(!svg.contains("<Bob"));
+    }
+}
@@ -0,0 +1,159 @@
+//! QR codes of bitcoin payment requests, rendered as SVG images scaling with their container.
+use bitcoin::{Address, Amount};
+use iced::{widget::svg, Length};
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::{theme, widget::*};
+
+/// The error correction levels tried in turn, until the data fits in a QR code. Short data is
+/// encoded with the highest redundancy, long data with less so that it still fits.
+const EC_LEVELS: [EcLevel; 4] = [EcLevel::H, EcLevel::Q, EcLevel::M, EcLevel::L];
+
+/// The width of the blank margin around the code, in modules, as required by the specification.
+const QUIET_ZONE: usize = 4;
+
+/// A BIP21 URI requesting a payment to the address, of the amount and with the label if any.
+pub fn bip21_uri(address: &Address, amount: Option<Amount>, label: Option<&str>) -> String {
+    let mut params = Vec::new();
+    if let Some(amount) = amount.filter(|a| *a > Amount::ZERO) {
+        params.push(format!("amount={}", btc_decimal(amount)));
+    }
+    if let Some(label) = label.filter(|l| !l.is_empty()) {
+        params.push(format!("label={}", percent_encode(label)));
+    }
+    if params.is_empty() {
+        format!("bitcoin:{}", address)
+    } else {
+        format!("bitcoin:{}?{}", address, params.join("&"))
+    }
+}
+
+// The amount in bitcoins, without trailing zeros, as BIP21 requires.
+fn btc_decimal(amount: Amount) -> String {
+    let sats = amount.to_sat();
+    let decimals = format!("{:08}", sats % 100_000_000);
+    let decimals = decimals.trim_end_matches('0');
+    if decimals.is_empty() {
+        format!("{}", sats / 100_000_000)
+    } else {
+        format!("{}.{}", sats / 100_000_000, decimals)
+    }
+}
+
+// Percent-encode all but the unreserved characters of RFC 3986.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// A QR code of some data. It is encoded once, keep it in the state and create a new one when the
+/// data changes.
+#[derive(Debug, Clone)]
+pub struct Qr {
+    handle: svg::Handle,
+    ec_level: EcLevel,
+}
+
+impl Qr {
+    /// Encode the data, `None` if it's too long to fit in a QR code.
+    pub fn new(data: &str) -> Option<Self> {
+        EC_LEVELS.iter().find_map(|ec_level| {
+            QrCode::with_error_correction_level(data, *ec_level)
+                .ok()
+                .map(|code| Self {
+                    handle: svg::Handle::from_memory(to_svg(&code).into_bytes()),
+                    ec_level: *ec_level,
+                })
+        })
+    }
+
+    /// The error correction level the data could be encoded with.
+    pub fn ec_level(&self) -> EcLevel {
+        self.ec_level
+    }
+
+    /// The QR code filling the available width, up to `max_width`.
+    pub fn view<'a, Message: 'a>(&self, max_width: f32) -> Container<'a, Message> {
+        Container::new(Svg::new(self.handle.clone()).width(Length::Fill))
+            .max_width(max_width)
+            .style(theme::Container::QrCode)
+    }
+}
+
+// Draw the dark modules as one path on a white background.
+fn to_svg(code: &QrCode) -> String {
+    let width = code.width();
+    let size = width + 2 * QUIET_ZONE;
+    let mut path = String::new();
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Dark {
+            path.push_str(&format!(
+                "M{},{}h1v1h-1z",
+                i % width + QUIET_ZONE,
+                i / width + QUIET_ZONE
+            ));
+        }
+    }
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {size} {size}\" \
+        shape-rendering=\"crispEdges\"><rect width=\"{size}\" height=\"{size}\" fill=\"#fff\"/>\
+        <path d=\"{path}\" fill=\"#000\"/></svg>",
+        size = size,
+        path = path
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_bip21_uri() {
+        let address = Address::from_str("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")
+            .unwrap()
+            .assume_checked();
+        assert_eq!(
+            bip21_uri(&address, None, None),
+            "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"
+        );
+        assert_eq!(
+            bip21_uri(&address, Some(Amount::ZERO), Some("")),
+            "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"
+        );
+        assert_eq!(
+            bip21_uri(
+                &address,
+                Some(Amount::from_sat(150_000)),
+                Some("Luke's rent & co")
+            ),
+            "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq\
+            ?amount=0.0015&label=Luke%27s%20rent%20%26%20co"
+        );
+        assert_eq!(
+            bip21_uri(&address, Some(Amount::from_sat(2_100_000_000)), None),
+            "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=21"
+        );
+        assert_eq!(btc_decimal(Amount::from_sat(1)), "0.00000001");
+    }
+
+    #[test]
+    fn test_qr_ec_level() {
+        assert_eq!(
+            Qr::new("bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")
+                .unwrap()
+                .ec_level(),
+            EcLevel::H
+        );
+        // Too long for the highest error correction level, but still fits with less.
+        let long = "a".repeat(2500);
+        assert_eq!(Qr::new(&long).unwrap().ec_level(), EcLevel::L);
+        assert!(Qr::new(&"a".repeat(5000)).is_none());
+    }
+}
@@ -19,6 +19,10 @@ pub fn arrow_down() -> Text<'static> {
     bootstrap_icon('\u{F128}')
 }
 
+pub fn arrow_up() -> Text<'static> {
+    bootstrap_icon('\u{F148}')
+}
+
 pub fn arrow_right() -> Text<'static> {
     bootstrap_icon('\u{F138}')
 }
@@ -123,6 +127,18 @@ pub fn round_key_icon() -> Text<'static> {
     bootstrap_icon('\u{F44E}')
 }
 
+pub fn lock_icon() -> Text<'static> {
+    bootstrap_icon('\u{F47A}')
+}
+
+pub fn eye_icon() -> Text<'static> {
+    bootstrap_icon('\u{F341}')
+}
+
+pub fn eye_slash_icon() -> Text<'static> {
+    bootstrap_icon('\u{F340}')
+}
+
 const ICONEX_ICONS: Font = Font::with_name("Untitled1");
 
 fn iconex_icon(unicode: char) -> Text<'static> {
@@ -234,61 +234,30 @@ impl LianaDescriptor {
             .0
     }
 
+    /// Describe the spending conditions of this descriptor in plain English, such as "Spend with
+    /// Alice's key, OR with Bob's key after 6 months (26,280 blocks)." Keys are named after their
+    /// alias if any, else after their master fingerprint.
+    pub fn human_readable_policy(&self, aliases: &HashMap<bip32::Fingerprint, String>) -> String {
+        let policy = self.policy();
+        let mut paths = vec![describe_path(policy.primary_path(), aliases)];
+        for (timelock, path) in policy.recovery_paths() {
+            paths.push(format!(
+                "{} after {} ({} blocks)",
+                describe_path(path, aliases),
+                approximate_duration(*timelock),
+                thousands_separated(*timelock as u32),
+            ));
+        }
+        format!("Spend {}.", paths.join(", OR "))
+    }
+
     /// Get the maximum size difference of a transaction input spending a Script derived from this
     /// descriptor before and after satisfaction. The returned value is in weight units.
     /// Callers are expected to account for the Segwit marker (2 WU). This takes into account the
     /// size of the witness stack length varint.
     pub fn max_sat_weight(&self, use_primary_path: bool) -> usize {
         if use_primary_path {
-            // Get the keys from the primary path, to get a satisfaction size estimation only
-            // considering those.
-            let keys = self
-                .policy()
-                .primary_path
-                .thresh_origins()
-                .1
-                .into_iter()
-                .fold(BTreeSet::new(), |mut keys, (fg, der_paths)| {
-                    for der_path in der_paths {
-                        keys.insert(((fg, der_path), CanSign::default()));
-                    }
-                    keys
-                });
-            let assets = Assets {
-                keys,
-                ..Default::default()
-            };
-
-            // Unfortunately rust-miniscript satisfaction size estimation is inconsistent. For
-            // Taproot it considers the whole witness (except the control block size + the
-            // script size), while under P2WSH it does not consider the witscript! Therefore we
-            // manually add the size of the witscript under P2WSH by means of the
-            // `explicit_script()` helper, which gives an error for Taproot, and for Taproot
-            // we add the sizes of the control block and script.
-            let der_desc = self
-                .receive_desc
-                .0
-                .at_derivation_index(0)
-                .expect("unhardened index");
-            let witscript_size = der_desc
-                .explicit_script()
-                .map(|s| varint_len(s.len()) + s.len());
-
-            // Finally, compute the satisfaction template for the primary path and get its size.
-            let plan = der_desc.plan(&assets).expect("Always satisfiable");
-            plan.witness_size()
-                + witscript_size.unwrap_or_else(|_| {
-                    plan.witness_template()
-                        .iter()
-                        .map(|elem| match elem {
-                            // We need to calculate the size manually before calculating the varint length.
-                            // See https://docs.rs/miniscript/11.0.0/src/miniscript/util.rs.html#35-36.
-                            Placeholder::TapScript(s) => varint_len(s.len()),
-                            Placeholder::TapControlBlock(cb) => varint_len(cb.serialize().len()),
-                            _ => 0,
-                        })
-                        .sum()
-                })
+            self.path_sat_weight(&self.policy().primary_path, None)
         } else {
             // We add one to account for the witness stack size, as the values above give the
             // difference in size for a satisfied input that was *already* in a transaction
@@ -316,6 +285,95 @@ impl LianaDescriptor {
             .unwrap()
     }
 
+    /// Get the size difference of a transaction input spending a Script derived from this
+    /// descriptor through the given spending path, with its timelock if it's a recovery path,
+    /// before and after satisfaction. The returned value is in weight units.
+    fn path_sat_weight(&self, path: &PathInfo, timelock: Option<u16>) -> usize {
+        // Get the keys from the path, to get a satisfaction size estimation only considering
+        // those.
+        let keys = path.thresh_origins().1.into_iter().fold(
+            BTreeSet::new(),
+            |mut keys, (fg, der_paths)| {
+                for der_path in der_paths {
+                    keys.insert(((fg, der_path), CanSign::default()));
+                }
+                keys
+            },
+        );
+        let assets = Assets {
+            keys,
+            relative_timelock: timelock.map(bitcoin::relative::LockTime::from_height),
+            ..Default::default()
+        };
+
+        // Unfortunately rust-miniscript satisfaction size estimation is inconsistent. For
+        // Taproot it considers the whole witness (except the control block size + the
+        // script size), while under P2WSH it does not consider the witscript! Therefore we
+        // manually add the size of the witscript under P2WSH by means of the
+        // `explicit_script()` helper, which gives an error for Taproot, and for Taproot
+        // we add the sizes of the control block and script.
+        let der_desc = self
+            .receive_desc
+            .0
+            .at_derivation_index(0)
+            .expect("unhardened index");
+        let witscript_size = der_desc
+            .explicit_script()
+            .map(|s| varint_len(s.len()) + s.len());
+
+        // Finally, compute the satisfaction template for the path and get its size.
+        let plan = der_desc.plan(&assets).expect("Always satisfiable");
+        plan.witness_size()
+            + witscript_size.unwrap_or_else(|_| {
+                plan.witness_template()
+                    .iter()
+                    .map(|elem| match elem {
+                        // We need to calculate the size manually before calculating the varint length.
+                        // See https://docs.rs/miniscript/11.0.0/src/miniscript/util.rs.html#35-36.
+                        Placeholder::TapScript(s) => varint_len(s.len()),
+                        Placeholder::TapControlBlock(cb) => varint_len(cb.serialize().len()),
+                        _ => 0,
+                    })
+                    .sum()
+            })
+    }
+
+    /// Get the minimum weight of a transaction spending `n_inputs` coins of this descriptor
+    /// through the given spending path, and paying to `n_outputs` P2WPKH outputs. The spending
+    /// path index is 0 for the primary path, and `i` for the `i`-th recovery path by increasing
+    /// timelock. This is useful to estimate the fee of a transaction before selecting its coins.
+    ///
+    /// # Panics
+    /// - If there is no spending path at this index.
+    pub fn minimum_spending_weight(
+        &self,
+        spending_path_index: usize,
+        n_inputs: usize,
+        n_outputs: usize,
+    ) -> usize {
+        let policy = self.policy();
+        let sat_weight = if spending_path_index == 0 {
+            self.path_sat_weight(&policy.primary_path, None)
+        } else {
+            let (timelock, path) = policy
+                .recovery_paths
+                .iter()
+                .nth(spending_path_index - 1)
+                .expect("There is no spending path at this index.");
+            self.path_sat_weight(path, Some(*timelock))
+        };
+        // version + locktime + inputs and outputs counts
+        let base_size = 4 + 4 + varint_len(n_inputs) + varint_len(n_outputs);
+        // txid + vout + empty scriptSig + nSequence
+        let input_size = 32 + 4 + 1 + 4;
+        // value + scriptPubKey of a P2WPKH
+        let output_size = 8 + 1 + 22;
+        (base_size + n_inputs * input_size + n_outputs * output_size) * WITNESS_SCALE_FACTOR
+            // Segwit marker and flag.
+            + if n_inputs > 0 { 2 } else { 0 }
+            + n_inputs * sat_weight
+    }
+
     /// Get the maximum size in virtual bytes of the whole input in a transaction spending
     /// a coin with this Script.
     pub fn spender_input_size(&self, use_primary_path: bool) -> usize {
@@ -581,6 +639,130 @@ impl LianaDescriptor {
             .checked_div(witness_factor)
             .unwrap()
     }
+
+    /// Get the descriptor for the single address at this derivation index of the receive or
+    /// change keychain. Its keys are the xpubs, with their origin, at a definite derivation path.
+    /// It serializes with a checksum.
+    ///
+    /// # Panics
+    /// - If the given index is hardened.
+    pub fn single_address_descriptor(
+        &self,
+        index: bip32::ChildNumber,
+        is_change: bool,
+    ) -> descriptor::Descriptor<descriptor::DefiniteDescriptorKey> {
+        assert!(index.is_normal());
+        let desc = if is_change {
+            &self.change_desc
+        } else {
+            &self.receive_desc
+        };
+        desc.0
+            .at_derivation_index(index.into())
+            .expect("We ruled out hardened derivation indexes and disallow multipath keys.")
+    }
+
+    /// Get the master fingerprint and full derivation path of each key of the address at this
+    /// derivation index of the receive or change keychain.
+    ///
+    /// # Panics
+    /// - If the given index is hardened.
+    pub fn address_derivation_paths(
+        &self,
+        index: bip32::ChildNumber,
+        is_change: bool,
+    ) -> Vec<(bip32::Fingerprint, bip32::DerivationPath)> {
+        let mut paths = Vec::new();
+        self.single_address_descriptor(index, is_change)
+            .for_each_key(|key| {
+                let path = (
+                    key.master_fingerprint(),
+                    key.full_derivation_path()
+                        .expect("We disallow multipath keys."),
+                );
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+                true
+            });
+        paths
+    }
+}
+
+fn key_name(
+    key: &descriptor::DescriptorPublicKey,
+    aliases: &HashMap<bip32::Fingerprint, String>,
+) -> String {
+    let fg = key.master_fingerprint();
+    match aliases.get(&fg).filter(|alias| !alias.is_empty()) {
+        Some(alias) => format!("{}'s key", alias),
+        None => format!("key [{}]", fg),
+    }
+}
+
+/// Join the items as "a, b and c".
+fn enumerate(items: &[String]) -> String {
+    match items.split_last() {
+        Some((last, firsts)) if !firsts.is_empty() => format!("{} and {}", firsts.join(", "), last),
+        Some((last, _)) => last.clone(),
+        None => String::new(),
+    }
+}
+
+fn describe_path(path: &PathInfo, aliases: &HashMap<bip32::Fingerprint, String>) -> String {
+    match path {
+        PathInfo::Single(key) => format!("with {}", key_name(key, aliases)),
+        PathInfo::Multi(k, keys) => {
+            // Sorted for the description not to depend on the order of the keys in the Script.
+            let mut names: Vec<String> = keys.iter().map(|key| key_name(key, aliases)).collect();
+            names.sort();
+            if *k == keys.len() && keys.len() == 2 {
+                format!("with both {}", enumerate(&names))
+            } else if *k == keys.len() {
+                format!("with all of {}", enumerate(&names))
+            } else {
+                format!("with {} of {}", k, enumerate(&names))
+            }
+        }
+    }
+}
+
+/// Approximate calendar duration of a relative timelock in blocks, assuming a block every 10
+/// minutes. Rounded to the month above four weeks, to the day above a day.
+fn approximate_duration(blocks: u16) -> String {
+    const MINUTES_PER_MONTH: u32 = 43_830;
+    const MINUTES_PER_DAY: u32 = 1_440;
+    let plural = |n: u32, unit: &str| format!("{} {}{}", n, unit, if n > 1 { "s" } else { "" });
+    let minutes = blocks as u32 * 10;
+    let months = (minutes + MINUTES_PER_MONTH / 2) / MINUTES_PER_MONTH;
+    if months >= 12 {
+        let (years, months) = (months / 12, months % 12);
+        if months == 0 {
+            plural(years, "year")
+        } else {
+            format!("{} and {}", plural(years, "year"), plural(months, "month"))
+        }
+    } else if minutes >= 28 * MINUTES_PER_DAY {
+        plural(months, "month")
+    } else if minutes >= MINUTES_PER_DAY {
+        plural((minutes + MINUTES_PER_DAY / 2) / MINUTES_PER_DAY, "day")
+    } else if minutes >= 60 {
+        plural((minutes + 30) / 60, "hour")
+    } else {
+        plural(minutes, "minute")
+    }
+}
+
+fn thousands_separated(n: u32) -> String {
+    let digits = n.to_string();
+    let mut res = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            res.push(',');
+        }
+        res.push(c);
+    }
+    res
 }
 
 impl SinglePathLianaDesc {
@@ -1239,6 +1421,217 @@ mod tests {
         assert_eq!(desc.max_sat_weight(false), desc.max_sat_weight(true) + 2);
     }
 
+    // A 3-of-5 primary path, degrading into a 2-of-3 after ~6 months, a 1-of-2 after ~12 months
+    // and a single key after 65535 blocks (the largest CSV value, ~15 months).
+    fn many_recovery_paths_policy(
+        secp: &secp256k1::Secp256k1<impl secp256k1::Signing>,
+        is_taproot: bool,
+    ) -> LianaPolicy {
+        let keys = |n: usize| -> Vec<_> { (0..n).map(|_| random_desc_key(secp)).collect() };
+        let primary_path = PathInfo::Multi(3, keys(5));
+        let recovery_paths: BTreeMap<u16, PathInfo> = vec![
+            (26280, PathInfo::Multi(2, keys(3))),
+            (52560, PathInfo::Multi(1, keys(2))),
+            (65535, PathInfo::Single(keys(1).pop().unwrap())),
+        ]
+        .into_iter()
+        .collect();
+        if is_taproot {
+            LianaPolicy::new(primary_path, recovery_paths).unwrap()
+        } else {
+            LianaPolicy::new_legacy(primary_path, recovery_paths).unwrap()
+        }
+    }
+
+    #[test]
+    fn descriptor_many_recovery_paths() {
+        let secp = secp256k1::Secp256k1::new();
+
+        for is_taproot in [false, true] {
+            let policy = many_recovery_paths_policy(&secp, is_taproot);
+            let desc = LianaDescriptor::new(policy.clone());
+            assert_eq!(desc.is_taproot(), is_taproot);
+
+            // The spending paths are all recovered when parsing the descriptor back.
+            let desc = LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            let parsed_policy = desc.policy();
+            assert_eq!(
+                parsed_policy.primary_path().thresh_origins(),
+                policy.primary_path().thresh_origins()
+            );
+            assert_eq!(
+                parsed_policy
+                    .recovery_paths()
+                    .keys()
+                    .copied()
+                    .collect::<Vec<_>>(),
+                vec![26280, 52560, 65535]
+            );
+            for (timelock, path) in parsed_policy.recovery_paths() {
+                assert_eq!(
+                    path.thresh_origins(),
+                    policy.recovery_paths()[timelock].thresh_origins()
+                );
+            }
+            assert_eq!(desc.first_timelock_value(), 26280);
+
+            // The worst case satisfaction accounts for all the spending paths.
+            assert!(desc.max_sat_weight(false) >= desc.max_sat_weight(true));
+
+            // Create a PSBT spending a coin from this descriptor, and check the spend info and the
+            // pruning of the BIP32 derivations for each spending path.
+            let der_desc = desc.receive_descriptor().derive(0.into(), &secp);
+            let mut psbt_in = PsbtIn::default();
+            der_desc.update_psbt_in(&mut psbt_in);
+            let mut psbt = Psbt {
+                unsigned_tx: bitcoin::Transaction {
+                    version: bitcoin::transaction::Version::TWO,
+                    lock_time: bitcoin::absolute::LockTime::ZERO,
+                    input: vec![bitcoin::TxIn::default()],
+                    output: vec![bitcoin::TxOut {
+                        value: bitcoin::Amount::from_sat(10_000),
+                        script_pubkey: der_desc.script_pubkey(),
+                    }],
+                },
+                version: 0,
+                xpub: BTreeMap::new(),
+                proprietary: BTreeMap::new(),
+                unknown: BTreeMap::new(),
+                inputs: vec![psbt_in],
+                outputs: vec![PsbtOut::default()],
+            };
+            let origins_count = |psbt: &Psbt| {
+                if is_taproot {
+                    psbt.inputs[0].tap_key_origins.len()
+                } else {
+                    psbt.inputs[0].bip32_derivation.len()
+                }
+            };
+            for (sequence, available_paths, path_keys) in [
+                (Sequence::ENABLE_RBF_NO_LOCKTIME, vec![], 5),
+                (Sequence::from_height(26279), vec![], 5),
+                (Sequence::from_height(26280), vec![26280], 3),
+                (Sequence::from_height(60000), vec![26280, 52560], 2),
+                (Sequence::from_height(65535), vec![26280, 52560, 65535], 1),
+            ] {
+                psbt.unsigned_tx.input[0].sequence = sequence;
+                let info = desc.partial_spend_info(&psbt).unwrap();
+                assert_eq!(info.primary_path().threshold, 3);
+                assert_eq!(
+                    info.recovery_paths().keys().copied().collect::<Vec<_>>(),
+                    available_paths
+                );
+                let pruned_psbt = desc.prune_bip32_derivs_last_avail(psbt.clone()).unwrap();
+                assert_eq!(origins_count(&pruned_psbt), path_keys);
+            }
+        }
+    }
+
+    #[test]
+    fn taproot_many_recovery_paths_sat_weight() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let desc = LianaDescriptor::new(many_recovery_paths_policy(&secp, true));
+        let tap_tree = match desc.multi_desc {
+            descriptor::Descriptor::Tr(ref tr) => tr.tap_tree().clone().unwrap(),
+            _ => unreachable!(),
+        };
+        // The internal key is unspendable, all the spending paths are in the Taptree.
+        assert_eq!(tap_tree.iter().count(), 4);
+
+        // The primary path is the only leaf with 5 keys. Spending through it requires 3
+        // signatures and 2 empty vectors, the script and a control block for its depth.
+        let (prim_depth, prim_ms) = tap_tree
+            .iter()
+            .find(|(_, ms)| ms.iter_pk().count() == 5)
+            .unwrap();
+        assert_eq!(prim_ms.script_size(), 34 * 5 + 2);
+        assert_eq!(
+            desc.max_sat_weight(true),
+            1 + 3 * (1 + 64)
+                + 2
+                + (1 + prim_ms.script_size())
+                + (1 + 33 + 32 * prim_depth as usize)
+        );
+
+        // The worst case must account for the script and the control block of the deepest leaves.
+        for (depth, ms) in tap_tree.iter() {
+            assert!(
+                desc.max_sat_weight(false)
+                    > 1 + (1 + ms.script_size()) + (1 + 33 + 32 * depth as usize)
+            );
+        }
+    }
+
+    #[test]
+    fn minimum_spending_weight() {
+        let secp = secp256k1::Secp256k1::new();
+
+        for is_taproot in [false, true] {
+            let desc = LianaDescriptor::new(many_recovery_paths_policy(&secp, is_taproot));
+            // version + locktime + counts + txin + P2WPKH txout, the segwit marker and flag, and
+            // the satisfaction of the input.
+            let primary_weight = desc.minimum_spending_weight(0, 1, 1);
+            assert_eq!(
+                primary_weight,
+                (4 + 4 + 1 + 1 + 41 + 31) * 4 + 2 + desc.max_sat_weight(true)
+            );
+            // Each additional input or output only adds its own size.
+            assert_eq!(
+                desc.minimum_spending_weight(0, 2, 1),
+                primary_weight + 41 * 4 + desc.max_sat_weight(true)
+            );
+            assert_eq!(
+                desc.minimum_spending_weight(0, 1, 3),
+                primary_weight + 2 * 31 * 4
+            );
+            // Recovery paths are never more expensive than the worst case, and the single key
+            // one is cheaper than the 3-of-5 primary path.
+            for index in 1..=3 {
+                assert!(
+                    desc.minimum_spending_weight(index, 1, 1)
+                        <= (4 + 4 + 1 + 1 + 41 + 31) * 4 + 2 + desc.max_sat_weight(false)
+                );
+            }
+            assert!(desc.minimum_spending_weight(3, 1, 1) < primary_weight);
+        }
+    }
+
+    #[test]
+    fn descriptor_twenty_keys() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let keys = |n: usize| -> Vec<_> { (0..n).map(|_| random_desc_key(&secp)).collect() };
+        // 10 keys in the primary path, 6, 3 and 1 in the recovery paths.
+        let primary_path = PathInfo::Multi(7, keys(10));
+        let recovery_paths: BTreeMap<u16, PathInfo> = vec![
+            (26280, PathInfo::Multi(4, keys(6))),
+            (52560, PathInfo::Multi(2, keys(3))),
+            (65535, PathInfo::Single(keys(1).pop().unwrap())),
+        ]
+        .into_iter()
+        .collect();
+
+        for policy in [
+            LianaPolicy::new_legacy(primary_path.clone(), recovery_paths.clone()).unwrap(),
+            LianaPolicy::new(primary_path.clone(), recovery_paths.clone()).unwrap(),
+        ] {
+            let desc = LianaDescriptor::new(policy);
+            let desc = LianaDescriptor::from_str(&desc.to_string()).unwrap();
+            let policy = desc.policy();
+            assert_eq!(policy.primary_path().thresh_origins().0, 7);
+            assert_eq!(policy.primary_path().thresh_origins().1.len(), 10);
+            assert_eq!(
+                policy
+                    .recovery_paths()
+                    .values()
+                    .map(|path| path.thresh_origins().1.len())
+                    .sum::<usize>(),
+                10
+            );
+            assert!(desc.max_sat_weight(false) >= desc.max_sat_weight(true));
+            assert!(desc.max_sat_vbytes(false) >= desc.max_sat_vbytes(true));
+        }
+    }
+
     #[test]
     fn liana_desc_keys() {
         let secp = secp256k1::Secp256k1::signing_only();
@@ -2190,10 +2583,164 @@ mod tests {
         run_change_detection(desc, &secp);
     }
 
+    #[test]
+    fn single_address_descriptor() {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let desc = LianaDescriptor::from_str("wsh(or_d(pk([abcdef01]xpub6Eze7yAT3Y1wGrnzedCNVYDXUqa9NmHVWck5emBaTbXtURbe1NWZbK9bsz1TiVE7Cz341PMTfYgFw1KdLWdzcM1UMFTcdQfCYhhXZ2HJvTW/<0;1>/*),and_v(v:pkh([aabb0012/48'/0'/0'/2']xpub688Hn4wScQAAiYJLPg9yH27hUpfZAUnmJejRQBCiwfP5PEDzjWMNW1wChcninxr5gyavFqbbDjdV1aK5USJz8NDVjUy7FRQaaqqXHh5SbXe/<0;1>/*),older(52560))))").unwrap();
+        let index = bip32::ChildNumber::from_normal_idx(42).unwrap();
+
+        for is_change in [false, true] {
+            let single_desc = desc.single_address_descriptor(index, is_change);
+            let single_desc_str = single_desc.to_string();
+            let keychain = if is_change { 1 } else { 0 };
+            assert!(single_desc_str.contains(&format!("HJvTW/{}/42)", keychain)));
+            assert!(single_desc_str.contains("[aabb0012/48'/0'/0'/2']"));
+            // It is serialized with a checksum, and can be parsed back.
+            assert_eq!(single_desc_str.split_once('#').unwrap().1.len(), 8);
+            let parsed = descriptor::Descriptor::<descriptor::DescriptorPublicKey>::from_str(
+                &single_desc_str,
+            )
+            .unwrap();
+            assert!(!parsed.has_wildcard());
+
+            // It's the descriptor of the address at this index.
+            let desc_keychain = if is_change {
+                desc.change_descriptor()
+            } else {
+                desc.receive_descriptor()
+            };
+            assert_eq!(
+                single_desc.script_pubkey(),
+                desc_keychain.derive(index, &secp).script_pubkey()
+            );
+
+            assert_eq!(
+                desc.address_derivation_paths(index, is_change),
+                vec![
+                    (
+                        bip32::Fingerprint::from_str("abcdef01").unwrap(),
+                        bip32::DerivationPath::from_str(&format!("m/{}/42", keychain)).unwrap()
+                    ),
+                    (
+                        bip32::Fingerprint::from_str("aabb0012").unwrap(),
+                        bip32::DerivationPath::from_str(&format!("m/48'/0'/0'/2'/{}/42", keychain))
+                            .unwrap()
+                    ),
+                ]
+            );
+        }
+    }
+
     #[test]
     fn unliftable_miniscript() {
         LianaDescriptor::from_str("wsh(0)").unwrap_err();
     }
 
+    #[test]
+    fn human_readable_policy() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let (alice, bob, charlie) = (
+            random_desc_key(&secp),
+            random_desc_key(&secp),
+            random_desc_key(&secp),
+        );
+        let aliases: HashMap<_, _> = [
+            (alice.master_fingerprint(), "Alice".to_string()),
+            (bob.master_fingerprint(), "Bob".to_string()),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        // Single key in each path, the key without alias is named after its fingerprint.
+        let policy = LianaPolicy::new(
+            PathInfo::Single(alice.clone()),
+            [(26_280, PathInfo::Single(bob.clone()))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            LianaDescriptor::new(policy).human_readable_policy(&aliases),
+            "Spend with Alice's key, OR with Bob's key after 6 months (26,280 blocks)."
+        );
+        let policy = LianaPolicy::new(
+            PathInfo::Single(alice.clone()),
+            [(144, PathInfo::Single(charlie.clone()))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            LianaDescriptor::new(policy).human_readable_policy(&aliases),
+            format!(
+                "Spend with Alice's key, OR with key [{}] after 1 day (144 blocks).",
+                charlie.master_fingerprint()
+            )
+        );
+
+        // A 2-of-2 primary path.
+        let policy = LianaPolicy::new(
+            PathInfo::Multi(2, vec![bob.clone(), alice.clone()]),
+            [(52_560, PathInfo::Single(charlie.clone()))]
+                .iter()
+                .cloned()
+                .collect(),
+        )
+        .unwrap();
+        let aliases: HashMap<_, _> = aliases
+            .into_iter()
+            .chain([(charlie.master_fingerprint(), "Charlie".to_string())])
+            .collect();
+        assert_eq!(
+            LianaDescriptor::new(policy).human_readable_policy(&aliases),
+            "Spend with both Alice's key and Bob's key, OR with Charlie's key after 1 year (52,560 blocks)."
+        );
+
+        // Multiple recovery paths, with multisigs.
+        let (dave, eve, frank, grace) = (
+            random_desc_key(&secp),
+            random_desc_key(&secp),
+            random_desc_key(&secp),
+            random_desc_key(&secp),
+        );
+        let aliases: HashMap<_, _> = aliases
+            .into_iter()
+            .chain([
+                (dave.master_fingerprint(), "Dave".to_string()),
+                (eve.master_fingerprint(), "Eve".to_string()),
+                (frank.master_fingerprint(), "Frank".to_string()),
+                (grace.master_fingerprint(), "Grace".to_string()),
+            ])
+            .collect();
+        let policy = LianaPolicy::new(
+            PathInfo::Single(alice.clone()),
+            [
+                (26_280, PathInfo::Single(bob.clone())),
+                (52_560, PathInfo::Multi(2, vec![eve, charlie, dave])),
+                (65_535, PathInfo::Multi(2, vec![grace, frank])),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            LianaDescriptor::new(policy).human_readable_policy(&aliases),
+            "Spend with Alice's key, OR with Bob's key after 6 months (26,280 blocks), OR with 2 of \
+            Charlie's key, Dave's key and Eve's key after 1 year (52,560 blocks), OR with both \
+            Frank's key and Grace's key after 1 year and 3 months (65,535 blocks)."
+        );
+
+        assert_eq!(approximate_duration(1), "10 minutes");
+        assert_eq!(approximate_duration(6), "1 hour");
+        assert_eq!(approximate_duration(1_008), "7 days");
+        assert_eq!(approximate_duration(4_380), "1 month");
+        assert_eq!(thousands_separated(999), "999");
+        assert_eq!(thousands_separated(1_000), "1,000");
+    }
+
     // TODO: test error conditions of deserialization.
 }
@@ -103,9 +103,10 @@ impl HotSigner {
     fn from_mnemonic(
         network: bitcoin::Network,
         mnemonic: bip39::Mnemonic,
+        passphrase: &str,
     ) -> Result<Self, SignerError> {
-        let master_xpriv =
-            bip32::Xpriv::new_master(network, &mnemonic.to_seed("")).map_err(SignerError::Bip32)?;
+        let master_xpriv = bip32::Xpriv::new_master(network, &mnemonic.to_seed(passphrase))
+            .map_err(SignerError::Bip32)?;
         Ok(Self {
             mnemonic,
             master_xpriv,
@@ -118,12 +119,27 @@ impl HotSigner {
         let random_32bytes = random::random_bytes().map_err(SignerError::Randomness)?;
         let mnemonic =
             bip39::Mnemonic::from_entropy(&random_32bytes[..16]).map_err(SignerError::Mnemonic)?;
-        Self::from_mnemonic(network, mnemonic)
+        Self::from_mnemonic(network, mnemonic, "")
     }
 
     pub fn from_str(network: bitcoin::Network, s: &str) -> Result<Self, SignerError> {
+        Self::from_str_with_passphrase(network, s, "")
+    }
+
+    /// Create a hot signer from a mnemonic protected by a BIP39 passphrase.
+    pub fn from_str_with_passphrase(
+        network: bitcoin::Network,
+        s: &str,
+        passphrase: &str,
+    ) -> Result<Self, SignerError> {
         let mnemonic = bip39::Mnemonic::from_str(s).map_err(SignerError::Mnemonic)?;
-        Self::from_mnemonic(network, mnemonic)
+        Self::from_mnemonic(network, mnemonic, passphrase)
+    }
+
+    /// The signer for the same mnemonic, with its master key derived using the given BIP39
+    /// passphrase.
+    pub fn with_passphrase(&self, passphrase: &str) -> Result<Self, SignerError> {
+        Self::from_mnemonic(self.master_xpriv.network, self.mnemonic.clone(), passphrase)
     }
 
     fn mnemonics_folder(datadir_root: &path::Path, network: bitcoin::Network) -> path::PathBuf {
@@ -141,14 +157,35 @@ impl HotSigner {
         datadir_root: &path::Path,
         network: bitcoin::Network,
     ) -> Result<Vec<Self>, SignerError> {
+        Ok(Self::from_datadir_by_fingerprint(datadir_root, network)?
+            .into_iter()
+            .map(|(_, signer)| signer)
+            .collect())
+    }
+
+    /// Read all the mnemonics from the datadir for the given network, along with the fingerprint
+    /// they were stored under. For a mnemonic protected by a passphrase, this is the fingerprint
+    /// of the master key derived using the passphrase, not the one of the returned signer.
+    pub fn from_datadir_by_fingerprint(
+        datadir_root: &path::Path,
+        network: bitcoin::Network,
+    ) -> Result<Vec<(bip32::Fingerprint, Self)>, SignerError> {
+        let secp = secp256k1::Secp256k1::signing_only();
         let mut signers = Vec::new();
 
         let mnemonic_paths = fs::read_dir(Self::mnemonics_folder(datadir_root, network))
             .map_err(SignerError::MnemonicStorage)?;
         for entry in mnemonic_paths {
-            let mnemonic = fs::read_to_string(entry.map_err(SignerError::MnemonicStorage)?.path())
-                .map_err(SignerError::MnemonicStorage)?;
-            signers.push(Self::from_str(network, &mnemonic)?);
+            let path = entry.map_err(SignerError::MnemonicStorage)?.path();
+            let mnemonic = fs::read_to_string(&path).map_err(SignerError::MnemonicStorage)?;
+            let signer = Self::from_str(network, &mnemonic)?;
+            let fingerprint = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.strip_prefix("mnemonic-"))
+                .and_then(|fg| bip32::Fingerprint::from_str(fg).ok())
+                .unwrap_or_else(|| signer.fingerprint(&secp));
+            signers.push((fingerprint, signer));
         }
 
         Ok(signers)
@@ -485,6 +522,39 @@ mod tests {
         fs::remove_dir_all(tmp_dir).unwrap();
     }
 
+    #[test]
+    fn hot_signer_passphrase() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let tmp_dir = tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let network = bitcoin::Network::Bitcoin;
+
+        // The passphrase changes the master key but not the mnemonic.
+        let signer = HotSigner::generate(network).unwrap();
+        let protected = signer.with_passphrase("correct horse").unwrap();
+        assert_eq!(signer.words(), protected.words());
+        assert_ne!(signer.fingerprint(&secp), protected.fingerprint(&secp));
+        assert_eq!(
+            HotSigner::from_str_with_passphrase(network, &signer.mnemonic_str(), "correct horse")
+                .unwrap()
+                .fingerprint(&secp),
+            protected.fingerprint(&secp)
+        );
+        assert_eq!(
+            signer.with_passphrase("").unwrap().fingerprint(&secp),
+            signer.fingerprint(&secp)
+        );
+
+        // The fingerprint it was stored under is read back along with the mnemonic.
+        protected.store(&tmp_dir, network, &secp).unwrap();
+        let read = HotSigner::from_datadir_by_fingerprint(&tmp_dir, network).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].0, protected.fingerprint(&secp));
+        assert_eq!(read[0].1.fingerprint(&secp), signer.fingerprint(&secp));
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
     #[test]
     fn hot_signer_sign_p2wsh() {
         let secp = secp256k1::Secp256k1::new();
@@ -1,7 +1,11 @@
-use crate::descriptors;
+mod coin_selection;
+
+use crate::{descriptors, random};
+
+pub use coin_selection::CoinSelectionAlgorithm;
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryInto,
     fmt,
     time::Duration,
@@ -17,8 +21,10 @@ use miniscript::bitcoin::{
     absolute::{Height, LockTime},
     bip32,
     constants::WITNESS_SCALE_FACTOR,
+    key::XOnlyPublicKey,
     psbt::{Input as PsbtIn, Output as PsbtOut, Psbt},
     secp256k1,
+    taproot::TapLeafHash,
 };
 use serde::{Deserialize, Serialize};
 
@@ -99,9 +105,47 @@ fn check_output_value(value: bitcoin::Amount) -> Result<(), SpendCreationError>
     }
 }
 
+// Whether the PSBT input for a Taproot coin contains what signers need to spend it using any of
+// the scripts: every leaf script along with the control block proving its inclusion in the output
+// key, and the leaf hashes of the keys only referring to those leaves. Always true for other coins.
+fn has_tap_scripts(
+    secp: &secp256k1::Secp256k1<impl secp256k1::Verification>,
+    psbtin: &PsbtIn,
+) -> bool {
+    let spk = match psbtin.witness_utxo.as_ref() {
+        Some(txo) => &txo.script_pubkey,
+        None => return false,
+    };
+    if !spk.is_p2tr() {
+        return true;
+    }
+    let output_key = match XOnlyPublicKey::from_slice(&spk.as_bytes()[2..]) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    // A Liana descriptor always has a script tree, for the recovery path(s).
+    if psbtin.tap_internal_key.is_none() || psbtin.tap_scripts.is_empty() {
+        return false;
+    }
+    let mut leaf_hashes = HashSet::with_capacity(psbtin.tap_scripts.len());
+    for (control_block, (script, leaf_version)) in &psbtin.tap_scripts {
+        if control_block.leaf_version != *leaf_version
+            || !control_block.verify_taproot_commitment(secp, output_key, script)
+        {
+            return false;
+        }
+        leaf_hashes.insert(TapLeafHash::from_script(script, *leaf_version));
+    }
+    psbtin
+        .tap_key_origins
+        .values()
+        .all(|(hashes, _)| hashes.iter().all(|h| leaf_hashes.contains(h)))
+}
+
 // Apply some sanity checks on a created transaction's PSBT.
 // TODO: add more sanity checks from revault_tx
 fn sanity_check_psbt(
+    secp: &secp256k1::Secp256k1<impl secp256k1::Verification>,
     spent_desc: &descriptors::LianaDescriptor,
     psbt: &Psbt,
     use_primary_path: bool,
@@ -123,6 +167,9 @@ fn sanity_check_psbt(
         if psbtin.bip32_derivation.is_empty() && psbtin.tap_key_origins.is_empty() {
             return Err(SpendCreationError::SanityCheckFailure(psbt.clone()));
         }
+        if !has_tap_scripts(secp, psbtin) {
+            return Err(SpendCreationError::SanityCheckFailure(psbt.clone()));
+        }
         value_in += psbtin
             .witness_utxo
             .as_ref()
@@ -182,6 +229,8 @@ pub struct CandidateCoin {
     pub deriv_index: bip32::ChildNumber,
     /// Whether this coin pays to a scriptpubkey derived from the internal keychain.
     pub is_change: bool,
+    /// The height of the block this coin was confirmed in, if any.
+    pub block_height: Option<i32>,
     /// Whether or not this coin must be selected by the coin selection algorithm.
     pub must_select: bool,
     /// The nSequence field to set for an input spending this coin.
@@ -263,6 +312,9 @@ impl bdk_coin_select::BnbMetric for LowestFeeChangeCondition {
 ///
 /// `must_have_change` indicates whether the transaction must have a change output.
 /// If `true`, the returned change amount will be positive.
+///
+/// `algorithm` is the strategy used to select among the optional candidates.
+#[allow(clippy::too_many_arguments)]
 fn select_coins_for_spend(
     candidate_coins: &[CandidateCoin],
     base_tx: bitcoin::Transaction,
@@ -271,6 +323,7 @@ fn select_coins_for_spend(
     replaced_fee: Option<u64>,
     max_sat_weight: u32,
     must_have_change: bool,
+    algorithm: CoinSelectionAlgorithm,
 ) -> Result<CoinSelectionRes, InsufficientFunds> {
     let out_value_nochange = base_tx.output.iter().map(|o| o.value.to_sat()).sum();
     let out_weight_nochange: u32 = {
@@ -367,8 +420,9 @@ fn select_coins_for_spend(
         long_term_feerate,
     );
 
-    // Finally, run the coin selection algorithm. We use an opportunistic BnB and if it couldn't
-    // find any solution we fall back to selecting coins by descending value.
+    // Finally, run the coin selection algorithm. By default we use an opportunistic BnB and if it
+    // couldn't find any solution we fall back to selecting coins by descending value. The other
+    // algorithms simply select coins in a fixed order until the target is met.
     let replace = replaced_fee.map(Replace::new);
     let target_fee = TargetFee {
         rate: feerate,
@@ -401,12 +455,31 @@ fn select_coins_for_spend(
     };
     #[cfg(debug_assertions)]
     let bnb_rounds = bnb_rounds / 1_000;
-    if let Err(e) = selector.run_bnb(lowest_fee_change_cond, bnb_rounds) {
-        log::debug!(
-            "Coin selection error: '{}'. Selecting coins by descending value per weight unit...",
-            e.to_string()
-        );
-        selector.sort_candidates_by_descending_value_pwu();
+    let needs_greedy_selection = match algorithm {
+        CoinSelectionAlgorithm::BranchAndBound => {
+            if let Err(e) = selector.run_bnb(lowest_fee_change_cond, bnb_rounds) {
+                log::debug!(
+                    "Coin selection error: '{}'. Selecting coins by descending value per weight unit...",
+                    e.to_string()
+                );
+                selector.sort_candidates_by_descending_value_pwu();
+                true
+            } else {
+                false
+            }
+        }
+        CoinSelectionAlgorithm::Fifo
+        | CoinSelectionAlgorithm::LargestFirst
+        | CoinSelectionAlgorithm::SmallestFirst => {
+            log::debug!("Selecting coins using the '{}' algorithm...", algorithm);
+            selector.sort_candidates_by(|(i, _), (j, _)| {
+                coin_selection::greedy_order(algorithm, &candidate_coins[i], &candidate_coins[j])
+                    .expect("Only called for greedy algorithms")
+            });
+            true
+        }
+    };
+    if needs_greedy_selection {
         // Select more coins until target is met and change condition satisfied.
         loop {
             let drain = selector.drain(target, change_policy);
@@ -520,6 +593,18 @@ pub fn anti_fee_sniping_locktime(
         .unwrap_or(LockTime::Blocks(Height::ZERO))
 }
 
+/// Get a random index in `[0, n_outputs)` at which to insert an output. Falls back to the last
+/// position if we could not get any randomness.
+fn random_output_index(n_outputs: usize) -> usize {
+    assert!(n_outputs > 0);
+    random::random_bytes()
+        .map(|bytes| {
+            let rand = u64::from_be_bytes(bytes[..8].try_into().expect("8 bytes"));
+            (rand % n_outputs as u64) as usize
+        })
+        .unwrap_or(n_outputs - 1)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct AddrInfo {
     pub index: bip32::ChildNumber,
@@ -583,8 +668,8 @@ impl fmt::Display for CreateSpendWarning {
 pub struct CreateSpendRes {
     /// The created PSBT.
     pub psbt: Psbt,
-    /// Whether the created PSBT has a change output.
-    pub has_change: bool,
+    /// The index of the change output in the created PSBT, if it has one.
+    pub change_index: Option<usize>,
     /// Warnings relating to the PSBT.
     pub warnings: Vec<CreateSpendWarning>,
 }
@@ -613,6 +698,8 @@ pub struct CreateSpendRes {
 ///   an external address (if combined with an empty list of `destinations` it's useful to sweep some
 ///   or all coins of a wallet to an external address).
 /// * `locktime`: the locktime to use for the transaction.
+/// * `coin_selection`: the algorithm to use when automatically selecting coins among the
+///   `candidate_coins`.
 #[allow(clippy::too_many_arguments)]
 pub fn create_spend(
     main_descriptor: &descriptors::LianaDescriptor,
@@ -623,6 +710,7 @@ pub fn create_spend(
     fees: SpendTxFees,
     change_addr: SpendOutputAddress,
     locktime: LockTime,
+    coin_selection: CoinSelectionAlgorithm,
 ) -> Result<CreateSpendRes, SpendCreationError> {
     // This method does quite a few things. In addition, we support different modes (coin control
     // vs automated coin selection, self-spend, sweep, etc..) which make the logic a bit more
@@ -728,14 +816,14 @@ pub fn create_spend(
             replaced_fee,
             max_sat_wu,
             is_self_send,
+            coin_selection,
         )
         .map_err(SpendCreationError::CoinSelection)?
     };
     // If necessary, add a change output.
     // For a self-send, coin selection will only find solutions with change and will otherwise
     // return an error. In any case, the PSBT sanity check will catch a transaction with no outputs.
-    let has_change = change_amount.to_sat() > 0;
-    if has_change {
+    let change_index = if change_amount.to_sat() > 0 {
         check_output_value(change_amount)?;
 
         // If the change address is ours, tell the signers by setting the BIP32 derivations in the
@@ -751,15 +839,21 @@ pub fn create_spend(
                 .update_change_psbt_out(&mut psbt_out);
         }
 
-        // TODO: shuffle once we have Taproot
+        // Insert the change output at a random position among the recipients' outputs, so it
+        // can't be identified by its index. The PSBT output must follow it.
         change_txo.value = change_amount;
-        tx.output.push(change_txo);
-        psbt_outs.push(psbt_out);
-    } else if max_change_amount.to_sat() > 0 {
-        warnings.push(CreateSpendWarning::ChangeAddedToFee(
-            max_change_amount.to_sat(),
-        ));
-    }
+        let change_index = random_output_index(tx.output.len() + 1);
+        tx.output.insert(change_index, change_txo);
+        psbt_outs.insert(change_index, psbt_out);
+        Some(change_index)
+    } else {
+        if max_change_amount.to_sat() > 0 {
+            warnings.push(CreateSpendWarning::ChangeAddedToFee(
+                max_change_amount.to_sat(),
+            ));
+        }
+        None
+    };
 
     if fee_for_ancestors.to_sat() > 0 {
         warnings.push(CreateSpendWarning::AdditionalFeeForAncestors(
@@ -804,12 +898,12 @@ pub fn create_spend(
         inputs: psbt_ins,
         outputs: psbt_outs,
     };
-    sanity_check_psbt(main_descriptor, &psbt, use_primary_path)?;
+    sanity_check_psbt(secp, main_descriptor, &psbt, use_primary_path)?;
     // TODO: maybe check for common standardness rules (max size, ..)?
 
     Ok(CreateSpendRes {
         psbt,
-        has_change,
+        change_index,
         warnings,
     })
 }
@@ -818,10 +912,56 @@ pub fn create_spend(
 mod tests {
     use super::*;
 
-    use std::time::Duration;
+    use std::{str::FromStr, time::Duration};
 
     use miniscript::bitcoin::absolute::{Height, LockTime};
 
+    #[test]
+    fn random_change_index() {
+        assert_eq!(random_output_index(1), 0);
+
+        // All positions get used, and only valid ones.
+        let mut seen = [false; 3];
+        for _ in 0..100 {
+            seen[random_output_index(3)] = true;
+        }
+        assert_eq!(seen, [true; 3]);
+    }
+
+    #[test]
+    fn taproot_psbt_in_tap_scripts() {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let desc = descriptors::LianaDescriptor::from_str("tr([00000000/1/2/3]tpubD6NzVbkrYhZ4YdBUPkUhDYj6Sd1QK8vgiCf5RwHnAnSNK5ozemAZzPTYZbgQq4diod7oxFJJYGa8FNRHzRo7URkixzQTuudh38xRRdSc4Hu/<0;1>/*,{and_v(v:multi_a(1,[ffd63c8d/48'/1'/0'/2']tpubDExA3EC3iAsPxPhFn4j6gMiVup6V2eH3qKyk69RcTc9TTNRfFYVPad8bJD5FCHVQxyBT4izKsvr7Btd2R4xmQ1hZkvsqGBaeE82J71uTK4N/<2;3>/*,[da2ee873/48'/1'/0'/2']tpubDEbXY6RbN9mxAvQW797WxReGGkrdyRfdYcehVVaQQcQ3kyfhxSMcnU9qGpUVRHXXALvBtc99jcuxx5tkzcLaJbAukSNpP9h2ti4XFRosv1g/<2;3>/*),older(2)),multi_a(2,[ffd63c8d/48'/1'/0'/2']tpubDExA3EC3iAsPxPhFn4j6gMiVup6V2eH3qKyk69RcTc9TTNRfFYVPad8bJD5FCHVQxyBT4izKsvr7Btd2R4xmQ1hZkvsqGBaeE82J71uTK4N/<0;1>/*,[da2ee873/48'/1'/0'/2']tpubDEbXY6RbN9mxAvQW797WxReGGkrdyRfdYcehVVaQQcQ3kyfhxSMcnU9qGpUVRHXXALvBtc99jcuxx5tkzcLaJbAukSNpP9h2ti4XFRosv1g/<0;1>/*)})").unwrap();
+        let der_desc = desc.receive_descriptor().derive(0.into(), &secp);
+        let mut psbt_in = PsbtIn::default();
+        der_desc.update_psbt_in(&mut psbt_in);
+        psbt_in.witness_utxo = Some(bitcoin::TxOut {
+            value: bitcoin::Amount::from_sat(10_000),
+            script_pubkey: der_desc.script_pubkey(),
+        });
+
+        // Both leaves come with a control block committing to the output key.
+        assert_eq!(psbt_in.tap_scripts.len(), 2);
+        assert!(psbt_in.tap_internal_key.is_some());
+        assert!(has_tap_scripts(&secp, &psbt_in));
+
+        // A leaf script which isn't the one committed to.
+        let mut wrong_script = psbt_in.clone();
+        let (_, (script, _)) = wrong_script.tap_scripts.iter_mut().next().unwrap();
+        *script = bitcoin::ScriptBuf::new();
+        assert!(!has_tap_scripts(&secp, &wrong_script));
+
+        // A leaf missing, which some of the keys refer to.
+        let mut missing_leaf = psbt_in.clone();
+        missing_leaf.tap_scripts.pop_first();
+        assert!(!has_tap_scripts(&secp, &missing_leaf));
+
+        // No script tree at all.
+        let mut no_scripts = psbt_in;
+        no_scripts.tap_scripts.clear();
+        assert!(!has_tap_scripts(&secp, &no_scripts));
+    }
+
     #[test]
     fn test_anti_fee_sniping_locktime() {
         // If we have no tip time, locktime is 0.
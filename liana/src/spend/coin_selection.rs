@@ -0,0 +1,161 @@
+//! Coin selection strategies available when creating a spend transaction.
+//!
+//! The default strategy is an opportunistic Branch-and-Bound minimizing the fees, as implemented
+//! in [`super::select_coins_for_spend`]. The other strategies are simple greedy algorithms which
+//! select candidates in a fixed order until the target is met.
+
+use super::CandidateCoin;
+
+use std::{cmp, fmt, str};
+
+use serde::{Deserialize, Serialize};
+
+/// The algorithm used to select the coins to be spent by a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoinSelectionAlgorithm {
+    /// Search for the selection minimizing the fees, falling back to selecting coins by
+    /// descending value per weight unit if no solution could be found.
+    #[default]
+    BranchAndBound,
+    /// Select the oldest coins first, by confirmation height. Unconfirmed coins come last.
+    Fifo,
+    /// Select the coins with the largest value first.
+    LargestFirst,
+    /// Select the coins with the smallest value first. Useful to consolidate small coins.
+    SmallestFirst,
+}
+
+impl CoinSelectionAlgorithm {
+    /// Whether this is the default algorithm. Used to avoid serializing it.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for CoinSelectionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BranchAndBound => write!(f, "branch_and_bound"),
+            Self::Fifo => write!(f, "fifo"),
+            Self::LargestFirst => write!(f, "largest_first"),
+            Self::SmallestFirst => write!(f, "smallest_first"),
+        }
+    }
+}
+
+impl str::FromStr for CoinSelectionAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "branch_and_bound" | "bnb" => Ok(Self::BranchAndBound),
+            "fifo" => Ok(Self::Fifo),
+            "largest_first" => Ok(Self::LargestFirst),
+            "smallest_first" => Ok(Self::SmallestFirst),
+            _ => Err(format!("Unknown coin selection algorithm '{}'.", s)),
+        }
+    }
+}
+
+/// Compare two candidates according to the order in which they should be selected by a greedy
+/// algorithm. Returns `None` for algorithms which are not greedy.
+pub(super) fn greedy_order(
+    algorithm: CoinSelectionAlgorithm,
+    a: &CandidateCoin,
+    b: &CandidateCoin,
+) -> Option<cmp::Ordering> {
+    let ord = match algorithm {
+        CoinSelectionAlgorithm::BranchAndBound => return None,
+        // Unconfirmed coins sort after all the confirmed ones.
+        CoinSelectionAlgorithm::Fifo => a
+            .block_height
+            .unwrap_or(i32::MAX)
+            .cmp(&b.block_height.unwrap_or(i32::MAX)),
+        CoinSelectionAlgorithm::LargestFirst => b.amount.cmp(&a.amount),
+        CoinSelectionAlgorithm::SmallestFirst => a.amount.cmp(&b.amount),
+    };
+    // Make the ordering deterministic for candidates which compare equal.
+    Some(ord.then_with(|| a.outpoint.cmp(&b.outpoint)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use miniscript::bitcoin::{self, bip32};
+
+    fn candidate(vout: u32, amount: u64, block_height: Option<i32>) -> CandidateCoin {
+        CandidateCoin {
+            outpoint: bitcoin::OutPoint::new(
+                bitcoin::Txid::from_str(
+                    "f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5",
+                )
+                .unwrap(),
+                vout,
+            ),
+            amount: bitcoin::Amount::from_sat(amount),
+            deriv_index: bip32::ChildNumber::from_normal_idx(vout).unwrap(),
+            is_change: false,
+            block_height,
+            must_select: false,
+            sequence: None,
+            ancestor_info: None,
+        }
+    }
+
+    fn sorted(algorithm: CoinSelectionAlgorithm, cands: &[CandidateCoin]) -> Vec<u32> {
+        let mut cands = cands.to_vec();
+        cands.sort_by(|a, b| greedy_order(algorithm, a, b).unwrap());
+        cands.iter().map(|c| c.outpoint.vout).collect()
+    }
+
+    #[test]
+    fn greedy_ordering() {
+        let cands = [
+            candidate(0, 20_000, Some(150)),
+            candidate(1, 5_000, None),
+            candidate(2, 100_000, Some(100)),
+            candidate(3, 10_000, Some(120)),
+            candidate(4, 10_000, Some(100)),
+        ];
+
+        assert!(
+            greedy_order(CoinSelectionAlgorithm::BranchAndBound, &cands[0], &cands[1]).is_none()
+        );
+        assert_eq!(
+            sorted(CoinSelectionAlgorithm::Fifo, &cands),
+            vec![2, 4, 3, 0, 1]
+        );
+        assert_eq!(
+            sorted(CoinSelectionAlgorithm::LargestFirst, &cands),
+            vec![2, 0, 3, 4, 1]
+        );
+        assert_eq!(
+            sorted(CoinSelectionAlgorithm::SmallestFirst, &cands),
+            vec![1, 3, 4, 0, 2]
+        );
+    }
+
+    #[test]
+    fn algorithm_roundtrip() {
+        for algo in [
+            CoinSelectionAlgorithm::BranchAndBound,
+            CoinSelectionAlgorithm::Fifo,
+            CoinSelectionAlgorithm::LargestFirst,
+            CoinSelectionAlgorithm::SmallestFirst,
+        ] {
+            assert_eq!(
+                CoinSelectionAlgorithm::from_str(&algo.to_string()),
+                Ok(algo)
+            );
+        }
+        assert_eq!(
+            CoinSelectionAlgorithm::from_str("bnb"),
+            Ok(CoinSelectionAlgorithm::BranchAndBound)
+        );
+        CoinSelectionAlgorithm::from_str("random").unwrap_err();
+    }
+}
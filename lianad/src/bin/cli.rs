@@ -1,6 +1,9 @@
 #![cfg(not(target_os = "windows"))]
 
-use lianad::config::{config_folder_path, Config};
+use lianad::{
+    config::{config_folder_path, Config},
+    method_docs, method_names,
+};
 
 use std::{
     env,
@@ -20,6 +23,32 @@ fn show_usage() {
     process::exit(1);
 }
 
+// Print the usage along with the list of commands, and exit successfully.
+fn show_help() {
+    println!("Usage:");
+    println!(" liana-cli [--conf conf_path] [--raw] <command> [<param 1> <param 2> ...]");
+    println!();
+    println!("Commands:");
+    let docs = method_docs();
+    for name in method_names() {
+        let doc = &docs[name];
+        let params: Vec<String> = doc
+            .params
+            .iter()
+            .map(|p| {
+                if p.optional {
+                    format!("[{}]", p.name)
+                } else {
+                    format!("<{}>", p.name)
+                }
+            })
+            .collect();
+        println!(" {} {}", name, params.join(" "));
+        println!("     {}", doc.description);
+    }
+    process::exit(0);
+}
+
 // Returns (Maybe(special conf file), Raw, Method name, Maybe(List of parameters))
 fn parse_args(mut args: Vec<String>) -> (Option<PathBuf>, bool, String, Vec<String>) {
     if args.len() < 2 {
@@ -43,6 +72,7 @@ fn parse_args(mut args: Vec<String>) -> (Option<PathBuf>, bool, String, Vec<Stri
 
                 conf_file = Some(PathBuf::from(args.next().expect("Just checked")));
             }
+            Some("--help") | Some("-h") => show_help(),
             Some("--raw") => {
                 if args.len() < 1 {
                     eprintln!("Not enough arguments.");
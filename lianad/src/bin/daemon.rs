@@ -1,17 +1,19 @@
 use std::{
     env,
     io::{self, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process, thread, time,
 };
 
-use lianad::{config::Config, DaemonHandle, VERSION};
+use lianad::{config::Config, DaemonHandle, MultiDaemonHandle, VERSION};
 
 fn print_help_exit(code: i32) {
     eprintln!("lianad version {}", VERSION);
     eprintln!("A TOML configuration file is required to run lianad. By default lianad looks for a 'config.toml' file in its data directory. A different one may be provided like so: '--conf <config file path>'.");
     eprintln!("A documented sample is available at 'contrib/lianad_config_example.toml' in the source tree (https://github.com/wizardsardine/liana/blob/v1.0/contrib/lianad_config_example.toml).");
     eprintln!("The default data directory path is a 'liana/' folder in the XDG standard configuration directory for all OSes but Linux ones, where it's '~/.liana/'.");
+    eprintln!("To check the configuration file without starting the daemon, pass '--check-config' (or 'validate'). It exits with a non-zero code if the configuration is invalid.");
+    eprintln!("Several wallets may be served by a single daemon by passing '--conf' once per wallet. Each wallet is named after its configuration file, without extension, and its JSONRPC methods are called by prefixing them with this name and an underscore (for instance 'mywallet_listcoins'). The JSONRPC socket is the one of the first wallet.");
     process::exit(code);
 }
 
@@ -20,25 +22,108 @@ fn print_version() {
     process::exit(0);
 }
 
-fn parse_args(args: Vec<String>) -> Option<PathBuf> {
-    if args.len() == 1 {
-        return None;
+struct Args {
+    conf_files: Vec<PathBuf>,
+    check_config: bool,
+}
+
+fn parse_args(args: Vec<String>) -> Args {
+    let mut conf_files = Vec::new();
+    let mut check_config = false;
+
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => print_help_exit(0),
+            "--version" | "-v" => print_version(),
+            "--check-config" | "validate" => check_config = true,
+            "--conf" => match args.next() {
+                Some(path) => conf_files.push(PathBuf::from(path)),
+                None => print_help_exit(1),
+            },
+            _ => {
+                eprintln!("Only the --conf and --check-config command line arguments are supported. All other configuration parameters must be specified in the configuration file.");
+                print_help_exit(1);
+            }
+        }
     }
 
-    if args[1] == "--help" || args[1] == "-h" {
-        print_help_exit(0)
-    } else if args[1] == "--version" || args[1] == "-v" {
-        print_version()
-    } else if args[1] != "--conf" {
-        eprintln!("Only a single command line argument is supported: --conf. All other configuration parameters must be specified in the configuration file.");
-        print_help_exit(1);
+    Args {
+        conf_files,
+        check_config,
     }
+}
 
-    if args.len() != 3 {
-        print_help_exit(1);
+// Print the errors and warnings about the configuration, and return whether it is valid.
+fn check_config(conf_file: Option<PathBuf>) -> bool {
+    let config = match Config::from_file(conf_file) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return false;
+        }
+    };
+    match config.validate_without_starting() {
+        Ok(warnings) => {
+            for warning in warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            println!("The configuration is valid.");
+            true
+        }
+        Err(errors) => {
+            for error in errors {
+                eprintln!("Error: {}", error);
+            }
+            false
+        }
     }
+}
+
+// The name of the wallet configured by this file, when serving several wallets.
+fn wallet_name(conf_file: &Path) -> String {
+    conf_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn parse_config(conf_file: Option<PathBuf>) -> Config {
+    Config::from_file(conf_file).unwrap_or_else(|e| {
+        eprintln!("Error parsing config: {}", e);
+        print_help_exit(1);
+        unreachable!();
+    })
+}
+
+// Serve several wallets until one of them, or the JSONRPC server, stops.
+fn run_multi(conf_files: Vec<PathBuf>) {
+    let wallets: Vec<(String, Config)> = conf_files
+        .into_iter()
+        .map(|path| (wallet_name(&path), parse_config(Some(path))))
+        .collect();
+    // The log level of the first wallet applies to all of them.
+    setup_logger(wallets[0].1.log_level).unwrap_or_else(|e| {
+        eprintln!("Error setting up logger: {}", e);
+        process::exit(1);
+    });
+    let mut rpc_socket = wallets[0].1.data_dir().unwrap_or_else(|| {
+        log::error!("No data directory could be determined for the first wallet.");
+        process::exit(1);
+    });
+    rpc_socket.push(wallets[0].1.bitcoin_config.network.to_string());
+    rpc_socket.push("lianad_rpc");
 
-    Some(PathBuf::from(args[2].to_owned()))
+    let handle = MultiDaemonHandle::start(wallets, rpc_socket).unwrap_or_else(|e| {
+        log::error!("Error starting Liana daemon: {}", e);
+        process::exit(1);
+    });
+    while handle.is_alive() {
+        thread::sleep(time::Duration::from_millis(500));
+    }
+    if let Err(e) = handle.stop() {
+        log::error!("Error stopping Liana daemon: {}", e);
+    }
 }
 
 fn setup_logger(log_level: log::LevelFilter) -> Result<(), fern::InitError> {
@@ -68,13 +153,28 @@ fn setup_logger(log_level: log::LevelFilter) -> Result<(), fern::InitError> {
 
 fn main() {
     let args = env::args().collect();
-    let conf_file = parse_args(args);
+    let Args {
+        conf_files,
+        check_config: check_only,
+    } = parse_args(args);
+    if check_only {
+        let valid = if conf_files.is_empty() {
+            check_config(None)
+        } else {
+            conf_files
+                .into_iter()
+                .fold(true, |valid, path| check_config(Some(path)) && valid)
+        };
+        process::exit(if valid { 0 } else { 1 });
+    }
 
-    let config = Config::from_file(conf_file).unwrap_or_else(|e| {
-        eprintln!("Error parsing config: {}", e);
-        print_help_exit(1);
-        unreachable!();
-    });
+    if conf_files.len() > 1 {
+        run_multi(conf_files);
+        io::stdout().flush().expect("Flushing stdout");
+        return;
+    }
+
+    let config = parse_config(conf_files.into_iter().next());
     setup_logger(config.log_level).unwrap_or_else(|e| {
         eprintln!("Error setting up logger: {}", e);
         process::exit(1);
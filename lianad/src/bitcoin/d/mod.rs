@@ -4,7 +4,10 @@
 
 mod utils;
 use crate::{
-    bitcoin::{Block, BlockChainTip},
+    bitcoin::{
+        fee_oracle::{feerate_from_btc_per_kvb, feerate_from_f64},
+        BackendHealth, Block, BlockChainTip,
+    },
     config,
 };
 use liana::descriptors::LianaDescriptor;
@@ -16,7 +19,7 @@ use std::{
     convert::TryInto,
     fs, io,
     str::FromStr,
-    thread,
+    sync, thread,
     time::Duration,
 };
 
@@ -62,6 +65,8 @@ pub enum BitcoindError {
     NetworkMismatch(String /*config*/, String /*bitcoind*/),
     StartRescan,
     RescanPastPruneHeight,
+    /// The interface was disconnected and must not be used anymore.
+    Disconnected,
 }
 
 impl BitcoindError {
@@ -155,6 +160,7 @@ impl std::fmt::Display for BitcoindError {
                     "Trying to rescan the block chain past the prune block height."
                 )
             }
+            BitcoindError::Disconnected => write!(f, "Disconnected from bitcoind."),
         }
     }
 }
@@ -219,6 +225,10 @@ pub struct BitcoinD {
     watchonly_wallet_path: String,
     /// How many times we'll retry upon failure to send a request.
     retries: usize,
+    /// Whether bitcoind could be reached as of the last request.
+    health: sync::Arc<BackendHealth>,
+    /// Set on shutdown, after which no request is sent to bitcoind anymore.
+    disconnected: bool,
 }
 
 macro_rules! params {
@@ -284,6 +294,8 @@ impl BitcoinD {
             watchonly_client: dummy_wo_client,
             watchonly_wallet_path: watchonly_wallet_path.clone(),
             retries: 0,
+            health: sync::Arc::new(BackendHealth::default()),
+            disconnected: false,
         };
         log::info!("Checking the connection to bitcoind.");
         dummy_bitcoind.check_connection()?;
@@ -319,9 +331,18 @@ impl BitcoinD {
             watchonly_client,
             watchonly_wallet_path,
             retries: BITCOIND_RETRY_LIMIT,
+            health: sync::Arc::new(BackendHealth::default()),
+            disconnected: false,
         })
     }
 
+    /// Stop sending requests to bitcoind. The RPC clients don't keep a connection open, so this
+    /// only makes any later request fail right away.
+    pub fn disconnect(&mut self) {
+        log::info!("Disconnecting from bitcoind.");
+        self.disconnected = true;
+    }
+
     fn check_client(&self, client: &Client) -> Result<(), BitcoindError> {
         if let Err(e) = self.make_request(client, "echo", None) {
             if e.is_warming_up() {
@@ -352,7 +373,10 @@ impl BitcoinD {
         let mut error: Option<BitcoindError> = None;
         for i in 0..self.retries + 1 {
             match request() {
-                Ok(res) => return Ok(res),
+                Ok(res) => {
+                    self.health.set_reachable();
+                    return Ok(res);
+                }
                 Err(e) => {
                     if e.is_warming_up() {
                         // Always retry when bitcoind is warming up, it'll be available eventually.
@@ -363,10 +387,12 @@ impl BitcoinD {
                         // refresh the credentials when this happens. Unfortunately this means
                         // making the BitcoinD struct mutable...
                         log::error!("Denied access to bitcoind. Most likely bitcoind was restarted from under us and the cookie changed.");
+                        self.health.set_unreachable();
                         return Err(e);
                     } else if e.is_transient() {
                         // If we start hitting transient errors retry requests for a limited time.
                         log::warn!("Transient error when sending request to bitcoind: {}", e);
+                        self.health.set_unreachable();
                         if i <= self.retries {
                             std::thread::sleep(Duration::from_secs(1));
                             log::debug!("Retrying RPC request to bitcoind: attempt #{}", i);
@@ -383,6 +409,9 @@ impl BitcoinD {
     }
 
     fn try_request(&self, client: &Client, req: jsonrpc::Request) -> Result<Json, BitcoindError> {
+        if self.disconnected {
+            return Err(BitcoindError::Disconnected);
+        }
         log::trace!("Sending to bitcoind: {:#?}", req);
         match client.send_request(req) {
             Ok(resp) => {
@@ -1192,6 +1221,27 @@ impl BitcoinD {
 
     /// Get mempool entry of the given transaction.
     /// Returns `None` if it is not in the mempool.
+    /// Get the feerate estimate, in sat/vb, for this confirmation target in blocks.
+    pub fn estimate_feerate(&self, target: u16) -> Option<u64> {
+        let res = self
+            .make_fallible_node_request("estimatesmartfee", params!(Json::Number(target.into())))
+            .map_err(|e| log::error!("Error estimating feerate: {}", e))
+            .ok()?;
+        // The feerate is in BTC/kvb, and absent if bitcoind has not enough data.
+        let btc_per_kvb = res.get("feerate").and_then(Json::as_f64)?;
+        feerate_from_f64(btc_per_kvb * 100_000.0)
+    }
+
+    /// The lowest feerate, in sat/vb, of the transactions bitcoind relays.
+    pub fn min_relay_feerate(&self) -> Option<u64> {
+        let res = self
+            .make_fallible_node_request("getmempoolinfo", None)
+            .map_err(|e| log::error!("Error getting mempool info: {}", e))
+            .ok()?;
+        let btc_per_kvb = res.get("minrelaytxfee").and_then(Json::as_f64)?;
+        feerate_from_btc_per_kvb(btc_per_kvb)
+    }
+
     pub fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
         match self
             .make_fallible_node_request("getmempoolentry", params!(Json::String(txid.to_string())))
@@ -1230,6 +1280,27 @@ impl BitcoinD {
             .collect()
     }
 
+    /// Get the addresses of the watchonly wallet which ever received coins, including in
+    /// unconfirmed transactions.
+    pub fn addresses_with_history(&self) -> Result<HashSet<bitcoin::Address>, BitcoindError> {
+        // Include unconfirmed transactions but not the addresses which never received anything.
+        let res = self.make_faillible_wallet_request(
+            "listreceivedbyaddress",
+            params!(Json::Number(0.into()), Json::Bool(false)),
+        )?;
+        Ok(res
+            .as_array()
+            .expect("Always returns an array")
+            .iter()
+            .filter_map(|entry| {
+                let addr = entry.get("address").and_then(Json::as_str)?;
+                bitcoin::Address::from_str(addr)
+                    .ok()
+                    .map(|addr| addr.assume_checked())
+            })
+            .collect())
+    }
+
     /// Stop bitcoind.
     pub fn stop(&self) {
         self.make_node_request("stop", None);
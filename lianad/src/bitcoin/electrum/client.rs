@@ -15,7 +15,11 @@ use super::utils::{
     block_id_from_tip, height_i32_from_usize, height_usize_from_i32, outpoints_from_tx,
 };
 use crate::{
-    bitcoin::{electrum::utils::tip_from_block_id, BlockChainTip, MempoolEntry, MempoolEntryFees},
+    bitcoin::{
+        electrum::utils::tip_from_block_id,
+        fee_oracle::{feerate_from_btc_per_kvb, feerate_from_f64},
+        BlockChainTip, MempoolEntry, MempoolEntryFees,
+    },
     config,
 };
 
@@ -34,6 +38,8 @@ const RETRY_LIMIT: u8 = 6;
 pub enum Error {
     Server(electrum_client::Error),
     TipChanged(BlockId, BlockId),
+    /// The client was disconnected and must not be used anymore.
+    Disconnected,
 }
 
 impl std::fmt::Display for Error {
@@ -46,35 +52,57 @@ impl std::fmt::Display for Error {
                 tip_from_block_id(*expected),
                 tip_from_block_id(*actual),
             ),
+            Error::Disconnected => write!(f, "Disconnected from the Electrum server."),
         }
     }
 }
 
-pub struct Client(electrum_client::Client);
+/// The connection to the server, `None` once disconnected.
+pub struct Client(Option<electrum_client::Client>);
 
 impl Client {
     /// Create a new client and perform sanity checks.
     pub fn new(electrum_config: &config::ElectrumConfig) -> Result<Self, Error> {
+        let builder = || {
+            Config::builder()
+                .socks5(
+                    electrum_config
+                        .socks5_proxy
+                        .as_ref()
+                        .map(electrum_client::Socks5Config::new),
+                )
+                .validate_domain(!electrum_config.skip_certificate_validation)
+        };
         // First use a dummy config to check connectivity (no retries, short timeout).
-        let dummy_config = Config::builder().retry(0).timeout(Some(3)).build();
+        let dummy_config = builder().retry(0).timeout(Some(3)).build();
         // Try to ping the server.
         bdk_electrum::electrum_client::Client::from_config(&electrum_config.addr, dummy_config)
             .and_then(|dummy_client| dummy_client.ping())
             .map_err(Error::Server)?;
 
         // Now connection has been checked, create client with required retries and timeout.
-        let config = Config::builder()
+        let config = builder()
             .retry(RETRY_LIMIT)
             .timeout(Some(RPC_SOCKET_TIMEOUT))
             .build();
         let client =
             bdk_electrum::electrum_client::Client::from_config(&electrum_config.addr, config)
                 .map_err(Error::Server)?;
-        Ok(Self(client))
+        Ok(Self(Some(client)))
+    }
+
+    fn inner(&self) -> Result<&electrum_client::Client, Error> {
+        self.0.as_ref().ok_or(Error::Disconnected)
+    }
+
+    /// Close the connection to the server.
+    pub fn disconnect(&mut self) {
+        log::info!("Disconnecting from the Electrum server.");
+        self.0.take();
     }
 
     pub fn chain_tip(&self) -> Result<BlockChainTip, Error> {
-        self.0
+        self.inner()?
             .block_headers_subscribe()
             .map_err(Error::Server)
             .map(|notif| BlockChainTip {
@@ -84,7 +112,7 @@ impl Client {
     }
 
     fn genesis_block_header(&self) -> Result<bitcoin::block::Header, Error> {
-        self.0.block_header(0).map_err(Error::Server)
+        self.inner()?.block_header(0).map_err(Error::Server)
     }
 
     pub fn genesis_block_timestamp(&self) -> Result<u32, Error> {
@@ -99,12 +127,41 @@ impl Client {
     }
 
     pub fn broadcast_tx(&self, tx: &bitcoin::Transaction) -> Result<bitcoin::Txid, Error> {
-        self.0.transaction_broadcast(tx).map_err(Error::Server)
+        self.inner()?
+            .transaction_broadcast(tx)
+            .map_err(Error::Server)
+    }
+
+    /// Get the feerate estimate, in sat/vb, for this confirmation target in blocks.
+    pub fn estimate_feerate(&self, target: u16) -> Option<u64> {
+        // The server returns a feerate in BTC/kvb, or a negative value if it has no estimate.
+        let btc_per_kvb = self.inner().ok()?.estimate_fee(target.into()).ok()?;
+        feerate_from_f64(btc_per_kvb * 100_000.0)
+    }
+
+    /// The lowest feerate, in sat/vb, of the transactions the server relays.
+    pub fn min_relay_feerate(&self) -> Option<u64> {
+        let btc_per_kvb = self.inner().ok()?.relay_fee().ok()?;
+        feerate_from_btc_per_kvb(btc_per_kvb)
+    }
+
+    /// Whether any of these scripts was ever paid to, including in unconfirmed transactions.
+    pub fn scripts_have_history(&self, scripts: &[bitcoin::ScriptBuf]) -> Result<bool, Error> {
+        for chunk in scripts.chunks(DEFAULT_BATCH_SIZE) {
+            let histories = self
+                .inner()?
+                .batch_script_get_history(chunk.iter().map(|spk| spk.as_script()))
+                .map_err(Error::Server)?;
+            if histories.iter().any(|history| !history.is_empty()) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
 
     pub fn tip_time(&self) -> Result<u32, Error> {
         let tip_height = self.chain_tip()?.height;
-        self.0
+        self.inner()?
             .block_header(height_usize_from_i32(tip_height))
             .map_err(Error::Server)
             .map(|bh| bh.time)
@@ -116,7 +173,7 @@ impl Client {
         fetch_prev_txouts: bool,
     ) -> Result<SyncResult<ConfirmationHeightAnchor>, Error> {
         Ok(self
-            .0
+            .inner()?
             .sync(request, DEFAULT_BATCH_SIZE, fetch_prev_txouts)
             .map_err(Error::Server)?
             .with_confirmation_height_anchor())
@@ -128,10 +185,10 @@ impl Client {
         request: SyncRequest,
         fetch_prev_txouts: bool,
     ) -> Result<SyncResult, Error> {
-        self.0
+        self.inner()?
             .sync(request, DEFAULT_BATCH_SIZE, fetch_prev_txouts)
             .map_err(Error::Server)?
-            .with_confirmation_time_height_anchor(&self.0)
+            .with_confirmation_time_height_anchor(self.inner()?)
             .map_err(Error::Server)
     }
 
@@ -142,10 +199,10 @@ impl Client {
         stop_gap: usize,
         fetch_prev_txouts: bool,
     ) -> Result<FullScanResult<K>, Error> {
-        self.0
+        self.inner()?
             .full_scan(request, stop_gap, DEFAULT_BATCH_SIZE, fetch_prev_txouts)
             .map_err(Error::Server)?
-            .with_confirmation_time_height_anchor(&self.0)
+            .with_confirmation_time_height_anchor(self.inner()?)
             .map_err(Error::Server)
     }
 
@@ -86,6 +86,11 @@ impl Electrum {
         &self.client
     }
 
+    /// Close the connection to the Electrum server.
+    pub fn disconnect(&mut self) {
+        self.client.disconnect()
+    }
+
     fn local_chain(&self) -> &LocalChain {
         self.bdk_wallet.local_chain()
     }
@@ -0,0 +1,253 @@
+//! Fee rate estimates aggregated from multiple sources.
+//!
+//! The Bitcoin backend is always queried. Optionally, a list of Esplora servers and a server
+//! exposing the `mempool.space` "recommended fees" API may be configured. The estimate for each
+//! target is the median of those returned by the sources which responded.
+
+use crate::{bitcoin::BitcoinInterface, config::FeeSourcesConfig};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync, time,
+};
+
+use serde::Deserialize;
+
+/// The confirmation targets, in blocks, we estimate a feerate for.
+pub const FEE_ESTIMATE_TARGETS: [u16; 4] = [1, 3, 6, 144];
+
+/// For how long estimates are served from the cache.
+const CACHE_TTL: time::Duration = time::Duration::from_secs(60);
+
+/// How long to wait for an external source to respond, in seconds.
+const HTTP_TIMEOUT_SECS: u64 = 10;
+
+/// Feerate estimates in sat/vb, by confirmation target in blocks.
+pub type FeeEstimates = BTreeMap<u16, u64>;
+
+// The response of the `mempool.space/api/v1/fees/recommended` endpoint.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecommendedFees {
+    fastest_fee: u64,
+    half_hour_fee: u64,
+    hour_fee: u64,
+    economy_fee: u64,
+}
+
+impl From<RecommendedFees> for FeeEstimates {
+    fn from(fees: RecommendedFees) -> FeeEstimates {
+        vec![
+            (1, fees.fastest_fee),
+            (3, fees.half_hour_fee),
+            (6, fees.hour_fee),
+            (144, fees.economy_fee),
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct FeeOracle {
+    esplora_urls: Vec<String>,
+    recommended_fees_url: Option<String>,
+    cache: sync::Mutex<Option<(time::Instant, FeeEstimates)>>,
+}
+
+impl FeeOracle {
+    pub fn new(sources: Option<&FeeSourcesConfig>) -> FeeOracle {
+        FeeOracle {
+            esplora_urls: sources.map(|s| s.esplora_urls.clone()).unwrap_or_default(),
+            recommended_fees_url: sources.and_then(|s| s.recommended_fees_url.clone()),
+            cache: sync::Mutex::new(None),
+        }
+    }
+
+    /// Get the feerate estimates for each of [`FEE_ESTIMATE_TARGETS`].
+    ///
+    /// Returns `None` if no source could provide an estimate. An expired estimate is never
+    /// returned.
+    pub fn estimates(&self, bit: &impl BitcoinInterface) -> Option<FeeEstimates> {
+        let mut cache = self.cache.lock().unwrap();
+        if let Some((fetched_at, estimates)) = cache.as_ref() {
+            if fetched_at.elapsed() < CACHE_TTL {
+                return Some(estimates.clone());
+            }
+        }
+
+        let mut sources = vec![bit.fee_estimates(&FEE_ESTIMATE_TARGETS)];
+        for url in &self.esplora_urls {
+            match esplora_estimates(url) {
+                Ok(estimates) => sources.push(estimates),
+                Err(e) => log::warn!("Error fetching fee estimates from '{}': {}", url, e),
+            }
+        }
+        if let Some(url) = &self.recommended_fees_url {
+            match recommended_fees(url) {
+                Ok(estimates) => sources.push(estimates),
+                Err(e) => log::warn!("Error fetching fee estimates from '{}': {}", url, e),
+            }
+        }
+
+        let estimates = aggregate(&sources);
+        *cache = estimates.clone().map(|e| (time::Instant::now(), e));
+        estimates
+    }
+}
+
+// Query the `/fee-estimates` endpoint of an Esplora server.
+fn esplora_estimates(url: &str) -> Result<FeeEstimates, minreq::Error> {
+    let res: HashMap<String, f64> =
+        minreq::get(format!("{}/fee-estimates", url.trim_end_matches('/')))
+            .with_timeout(HTTP_TIMEOUT_SECS)
+            .send()?
+            .json()?;
+    Ok(FEE_ESTIMATE_TARGETS
+        .iter()
+        .filter_map(|target| {
+            let feerate = res.get(&target.to_string())?;
+            Some((*target, feerate_from_f64(*feerate)?))
+        })
+        .collect())
+}
+
+// Query a server returning the `mempool.space/api/v1/fees/recommended` format.
+fn recommended_fees(url: &str) -> Result<FeeEstimates, minreq::Error> {
+    let res: RecommendedFees = minreq::get(url)
+        .with_timeout(HTTP_TIMEOUT_SECS)
+        .send()?
+        .json()?;
+    Ok(res.into())
+}
+
+/// Convert a feerate in sat/vb to an integer, rounding up. Returns `None` for an insane value.
+pub fn feerate_from_f64(feerate: f64) -> Option<u64> {
+    if feerate.is_finite() && feerate > 0.0 {
+        Some((feerate.ceil() as u64).max(1))
+    } else {
+        None
+    }
+}
+
+/// Convert a feerate in BTC/kvb, as reported by the backends, to sat/vb rounding up. Returns
+/// `None` for an insane value.
+pub fn feerate_from_btc_per_kvb(btc_per_kvb: f64) -> Option<u64> {
+    // Round to the satoshi first, not to round up a floating point imprecision.
+    let sat_per_kvb = (btc_per_kvb * 100_000_000.0).round();
+    if sat_per_kvb.is_finite() && sat_per_kvb > 0.0 {
+        Some(((sat_per_kvb as u64 + 999) / 1_000).max(1))
+    } else {
+        None
+    }
+}
+
+// The median of these values, the average of the two middle ones (rounded up) if there is an
+// even number of them.
+fn median(mut values: Vec<u64>) -> Option<u64> {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.is_empty() {
+        None
+    } else if values.len() % 2 == 0 {
+        Some((values[mid - 1] + values[mid] + 1) / 2)
+    } else {
+        Some(values[mid])
+    }
+}
+
+// Aggregate the estimates from all sources, taking the median for each target. Returns `None`
+// if no source gave any estimate.
+fn aggregate(sources: &[FeeEstimates]) -> Option<FeeEstimates> {
+    let estimates: FeeEstimates = FEE_ESTIMATE_TARGETS
+        .iter()
+        .filter_map(|target| {
+            let values = sources
+                .iter()
+                .filter_map(|s| s.get(target).copied())
+                .collect();
+            Some((*target, median(values)?))
+        })
+        .collect();
+    if estimates.is_empty() {
+        None
+    } else {
+        Some(estimates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feerate_btc_per_kvb() {
+        assert_eq!(feerate_from_btc_per_kvb(0.00001), Some(1));
+        assert_eq!(feerate_from_btc_per_kvb(0.000015), Some(2));
+        assert_eq!(feerate_from_btc_per_kvb(0.0001), Some(10));
+        assert_eq!(feerate_from_btc_per_kvb(0.000001), Some(1));
+        assert_eq!(feerate_from_btc_per_kvb(0.0), None);
+        assert_eq!(feerate_from_btc_per_kvb(-1.0), None);
+        assert_eq!(feerate_from_btc_per_kvb(f64::NAN), None);
+    }
+
+    #[test]
+    fn fee_estimates_median() {
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![7]), Some(7));
+        assert_eq!(median(vec![10, 3]), Some(7));
+        assert_eq!(median(vec![10, 1, 3]), Some(3));
+        assert_eq!(median(vec![10, 1, 3, 4]), Some(4));
+
+        assert_eq!(feerate_from_f64(0.2), Some(1));
+        assert_eq!(feerate_from_f64(12.01), Some(13));
+        assert_eq!(feerate_from_f64(-1.0), None);
+        assert_eq!(feerate_from_f64(f64::NAN), None);
+
+        // No source responded.
+        assert_eq!(aggregate(&[]), None);
+        assert_eq!(aggregate(&[FeeEstimates::new()]), None);
+
+        // A single source is used as is.
+        let backend: FeeEstimates = [(1, 20), (6, 5)].iter().copied().collect();
+        assert_eq!(aggregate(&[backend.clone()]), Some(backend.clone()));
+
+        // Otherwise the median is taken for each target, among the sources which provided one.
+        let esplora: FeeEstimates = [(1, 30), (3, 12), (6, 7), (144, 2)]
+            .iter()
+            .copied()
+            .collect();
+        let recommended = FeeEstimates::from(RecommendedFees {
+            fastest_fee: 25,
+            half_hour_fee: 15,
+            hour_fee: 10,
+            economy_fee: 1,
+        });
+        assert_eq!(
+            aggregate(&[backend, esplora, recommended]),
+            Some(
+                [(1, 25), (3, 14), (6, 7), (144, 2)]
+                    .iter()
+                    .copied()
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn fee_oracle_cache() {
+        let oracle = FeeOracle::new(None);
+        let bit = crate::testutils::DummyBitcoind::new();
+        // The dummy backend has no estimate.
+        assert_eq!(oracle.estimates(&bit), None);
+
+        // A fresh estimate is served from the cache.
+        let cached: FeeEstimates = [(1, 42)].iter().copied().collect();
+        *oracle.cache.lock().unwrap() = Some((time::Instant::now(), cached.clone()));
+        assert_eq!(oracle.estimates(&bit), Some(cached.clone()));
+
+        // But never a stale one.
+        *oracle.cache.lock().unwrap() = Some((time::Instant::now() - CACHE_TTL, cached));
+        assert_eq!(oracle.estimates(&bit), None);
+    }
+}
@@ -4,13 +4,18 @@
 
 pub mod d;
 pub mod electrum;
+pub mod fee_oracle;
 pub mod poller;
 
 use crate::bitcoin::d::{BitcoindError, CachedTxGetter, LSBlockEntry};
 pub use d::{MempoolEntry, MempoolEntryFees, SyncProgress};
 use liana::descriptors;
 
-use std::{fmt, sync};
+use std::{
+    fmt,
+    sync::{self, atomic},
+    time,
+};
 
 use miniscript::bitcoin::{self, address, bip32::ChildNumber};
 
@@ -40,6 +45,45 @@ impl fmt::Display for BlockChainTip {
     }
 }
 
+/// Whether the Bitcoin backend could be reached, as of the last request sent to it. It is shared
+/// with the commands so they can report it without querying the backend.
+#[derive(Debug, Default)]
+pub struct BackendHealth {
+    // Timestamp since which the backend is unreachable, or 0 if it is reachable.
+    unreachable_since: atomic::AtomicU32,
+}
+
+impl BackendHealth {
+    /// A request to the backend succeeded.
+    pub fn set_reachable(&self) {
+        self.unreachable_since.store(0, atomic::Ordering::Relaxed);
+    }
+
+    /// A request to the backend failed for lack of connection. This keeps the time of the first
+    /// failure.
+    pub fn set_unreachable(&self) {
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or(1)
+            .max(1);
+        let _ = self.unreachable_since.compare_exchange(
+            0,
+            now,
+            atomic::Ordering::Relaxed,
+            atomic::Ordering::Relaxed,
+        );
+    }
+
+    /// The timestamp of the first failed request since the backend is unreachable, if it is.
+    pub fn unreachable_since(&self) -> Option<u32> {
+        match self.unreachable_since.load(atomic::Ordering::Relaxed) {
+            0 => None,
+            since => Some(since),
+        }
+    }
+}
+
 /// Our Bitcoin backend.
 pub trait BitcoinInterface: Send {
     fn genesis_block_timestamp(&self) -> u32;
@@ -133,6 +177,27 @@ pub trait BitcoinInterface: Send {
     ///
     /// Returns `None` if the transaction is not in the mempool.
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry>;
+
+    /// Get the feerate estimates, in sat/vb, for these confirmation targets in blocks.
+    ///
+    /// Targets for which the backend has no estimate are omitted.
+    fn fee_estimates(&self, targets: &[u16]) -> fee_oracle::FeeEstimates;
+
+    /// Get the lowest feerate, in sat/vb, of the transactions relayed by the backend, if known.
+    fn min_relay_feerate(&self) -> Option<u64>;
+
+    /// Whether any of these addresses ever received coins, including in unconfirmed transactions.
+    fn addresses_have_history(&self, addresses: &[bitcoin::Address]) -> Result<bool, String>;
+
+    /// Release the connection to the backend. Called once on shutdown, after the poller and the
+    /// JSONRPC server stopped, while other handles to the interface may still be alive. It must
+    /// not be used afterwards.
+    fn disconnect(&mut self);
+
+    /// The health of the connection to the backend, if it is tracked.
+    fn health(&self) -> Option<sync::Arc<BackendHealth>> {
+        None
+    }
 }
 
 impl BitcoinInterface for d::BitcoinD {
@@ -385,6 +450,10 @@ impl BitcoinInterface for d::BitcoinD {
         Some(self.get_block_stats(tip.hash)?.time)
     }
 
+    fn health(&self) -> Option<sync::Arc<BackendHealth>> {
+        Some(self.health.clone())
+    }
+
     fn wallet_transaction(
         &self,
         txid: &bitcoin::Txid,
@@ -402,6 +471,26 @@ impl BitcoinInterface for d::BitcoinD {
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
         self.mempool_entry(txid)
     }
+
+    fn fee_estimates(&self, targets: &[u16]) -> fee_oracle::FeeEstimates {
+        targets
+            .iter()
+            .filter_map(|target| Some((*target, self.estimate_feerate(*target)?)))
+            .collect()
+    }
+
+    fn min_relay_feerate(&self) -> Option<u64> {
+        self.min_relay_feerate()
+    }
+
+    fn addresses_have_history(&self, addresses: &[bitcoin::Address]) -> Result<bool, String> {
+        let with_history = self.addresses_with_history().map_err(|e| e.to_string())?;
+        Ok(addresses.iter().any(|addr| with_history.contains(addr)))
+    }
+
+    fn disconnect(&mut self) {
+        self.disconnect()
+    }
 }
 
 impl BitcoinInterface for electrum::Electrum {
@@ -554,6 +643,24 @@ impl BitcoinInterface for electrum::Electrum {
         self.client().mempool_entry(txid).ok()?
     }
 
+    fn fee_estimates(&self, targets: &[u16]) -> fee_oracle::FeeEstimates {
+        targets
+            .iter()
+            .filter_map(|target| Some((*target, self.client().estimate_feerate(*target)?)))
+            .collect()
+    }
+
+    fn min_relay_feerate(&self) -> Option<u64> {
+        self.client().min_relay_feerate()
+    }
+
+    fn addresses_have_history(&self, addresses: &[bitcoin::Address]) -> Result<bool, String> {
+        let scripts: Vec<_> = addresses.iter().map(|addr| addr.script_pubkey()).collect();
+        self.client()
+            .scripts_have_history(&scripts)
+            .map_err(|e| e.to_string())
+    }
+
     fn mempool_spenders(&self, outpoints: &[bitcoin::OutPoint]) -> Vec<MempoolEntry> {
         self.client()
             .mempool_spenders(outpoints)
@@ -589,6 +696,10 @@ impl BitcoinInterface for electrum::Electrum {
     fn tip_time(&self) -> Option<u32> {
         self.client().tip_time().ok()
     }
+
+    fn disconnect(&mut self) {
+        self.disconnect()
+    }
 }
 
 // FIXME: do we need to repeat the entire trait implemenation? Isn't there a nicer way?
@@ -694,6 +805,26 @@ impl BitcoinInterface for sync::Arc<sync::Mutex<dyn BitcoinInterface + 'static>>
     fn mempool_entry(&self, txid: &bitcoin::Txid) -> Option<MempoolEntry> {
         self.lock().unwrap().mempool_entry(txid)
     }
+
+    fn fee_estimates(&self, targets: &[u16]) -> fee_oracle::FeeEstimates {
+        self.lock().unwrap().fee_estimates(targets)
+    }
+
+    fn min_relay_feerate(&self) -> Option<u64> {
+        self.lock().unwrap().min_relay_feerate()
+    }
+
+    fn addresses_have_history(&self, addresses: &[bitcoin::Address]) -> Result<bool, String> {
+        self.lock().unwrap().addresses_have_history(addresses)
+    }
+
+    fn disconnect(&mut self) {
+        self.lock().unwrap().disconnect()
+    }
+
+    fn health(&self) -> Option<sync::Arc<BackendHealth>> {
+        self.lock().unwrap().health()
+    }
 }
 
 // FIXME: We could avoid this type (and all the conversions entailing allocations) if bitcoind
@@ -0,0 +1,111 @@
+//! Spacing out the polls while the Bitcoin backend can't be reached.
+
+use std::time;
+
+/// After this many consecutive polls failing to reach the Bitcoin backend, the interval between
+/// polls is doubled on each new failure.
+pub const BACKOFF_AFTER_FAILURES: u32 = 3;
+
+/// The consecutive polls which failed to reach the Bitcoin backend.
+#[derive(Debug)]
+pub struct Backoff {
+    failures: u32,
+    max_interval: time::Duration,
+}
+
+impl Backoff {
+    pub fn new(max_interval: time::Duration) -> Self {
+        Self {
+            failures: 0,
+            max_interval,
+        }
+    }
+
+    /// Record a poll which failed to reach the Bitcoin backend.
+    pub fn failed(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+    }
+
+    /// Go back to the regular interval. Returns whether the polls were spaced out.
+    pub fn reset(&mut self) -> bool {
+        let was_degraded = self.is_degraded();
+        self.failures = 0;
+        was_degraded
+    }
+
+    /// Whether the polls are currently spaced out.
+    pub fn is_degraded(&self) -> bool {
+        self.failures >= BACKOFF_AFTER_FAILURES
+    }
+
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// The interval until the next poll, given the regular one.
+    pub fn interval(&self, poll_interval: time::Duration) -> time::Duration {
+        if !self.is_degraded() {
+            return poll_interval;
+        }
+        // Cap the exponent, the maximum interval is reached long before anyway.
+        let doublings = (self.failures - BACKOFF_AFTER_FAILURES + 1).min(16);
+        poll_interval
+            .saturating_mul(1 << doublings)
+            .min(self.max_interval)
+            .max(poll_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_interval() {
+        let poll_interval = time::Duration::from_secs(30);
+        let mut backoff = Backoff::new(time::Duration::from_secs(5 * 60));
+        assert_eq!(backoff.interval(poll_interval), poll_interval);
+
+        // The first failures don't change the interval.
+        for _ in 1..BACKOFF_AFTER_FAILURES {
+            backoff.failed();
+            assert_eq!(backoff.interval(poll_interval), poll_interval);
+        }
+
+        // Then it's doubled on each failure, up to the maximum.
+        backoff.failed();
+        assert!(backoff.is_degraded());
+        assert_eq!(
+            backoff.interval(poll_interval),
+            time::Duration::from_secs(60)
+        );
+        backoff.failed();
+        assert_eq!(
+            backoff.interval(poll_interval),
+            time::Duration::from_secs(120)
+        );
+        backoff.failed();
+        assert_eq!(
+            backoff.interval(poll_interval),
+            time::Duration::from_secs(240)
+        );
+        for _ in 0..100 {
+            backoff.failed();
+            assert_eq!(
+                backoff.interval(poll_interval),
+                time::Duration::from_secs(300)
+            );
+        }
+
+        // It never goes below the regular interval, even if the maximum is lower.
+        assert_eq!(
+            backoff.interval(time::Duration::from_secs(600)),
+            time::Duration::from_secs(600)
+        );
+
+        // A successful poll brings back the regular interval.
+        assert!(backoff.reset());
+        assert!(!backoff.reset());
+        assert_eq!(backoff.interval(poll_interval), poll_interval);
+    }
+}
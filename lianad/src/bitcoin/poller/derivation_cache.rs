@@ -0,0 +1,283 @@
+//! A cache of the scriptPubKeys derived from our descriptors.
+//!
+//! On a wallet with many revealed addresses, deriving them is expensive. The cache may be warmed
+//! in a background thread at startup so the poller doesn't have to derive (or query the database
+//! for) the derivation index of the coins it gets from the Bitcoin backend.
+
+use crate::database::sqlite::LOOK_AHEAD_LIMIT;
+
+use liana::descriptors;
+
+use std::{
+    collections::HashMap,
+    sync::{self, atomic},
+    thread,
+};
+
+use miniscript::bitcoin::{bip32, secp256k1, Script, ScriptBuf};
+
+// Number of scriptPubKeys derived between two checks of the stop signal.
+const CHUNK_SIZE: u32 = 100;
+
+// Maximum number of scriptPubKeys cached per descriptor, for the memory usage not to grow with
+// the derivation index.
+const MAX_CACHED_PER_DESC: u32 = 50_000;
+
+/// A map from scriptPubKey to its derivation index and whether it is for change.
+#[derive(Debug)]
+pub struct DerivationCache {
+    spks: sync::RwLock<HashMap<ScriptBuf, (bip32::ChildNumber, bool)>>,
+    max_per_desc: u32,
+}
+
+impl Default for DerivationCache {
+    fn default() -> Self {
+        Self::new(MAX_CACHED_PER_DESC)
+    }
+}
+
+impl DerivationCache {
+    /// A cache holding at most `max_per_desc` scriptPubKeys for each descriptor.
+    pub fn new(max_per_desc: u32) -> Self {
+        Self {
+            spks: sync::RwLock::new(HashMap::new()),
+            max_per_desc,
+        }
+    }
+
+    /// Get the derivation index of this scriptPubKey, and whether it is for change, if it was
+    /// cached.
+    pub fn derivation_index(&self, spk: &Script) -> Option<(bip32::ChildNumber, bool)> {
+        self.spks.read().unwrap().get(spk).copied()
+    }
+
+    /// Number of scriptPubKeys in the cache.
+    pub fn len(&self) -> usize {
+        self.spks.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spks.read().unwrap().is_empty()
+    }
+
+    /// Derive and cache the scriptPubKeys of the receive and change descriptors (in this order)
+    /// up to the given indexes (excluded). Only the last indexes are cached if there are more than
+    /// the maximum per descriptor, since coins are most likely received on the latest addresses.
+    /// The poller looks up the derivation index of the others in database.
+    ///
+    /// The derivation is done in chunks, between which the `stop` signal is checked. Returns
+    /// `false` if it was interrupted.
+    pub fn warm(
+        &self,
+        descs: &[descriptors::SinglePathLianaDesc; 2],
+        up_to: [u32; 2],
+        secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+        stop: &atomic::AtomicBool,
+    ) -> bool {
+        for (is_change, (desc, up_to)) in [false, true].iter().copied().zip(descs.iter().zip(up_to))
+        {
+            let mut start = up_to.saturating_sub(self.max_per_desc);
+            while start < up_to {
+                if stop.load(atomic::Ordering::Relaxed) {
+                    return false;
+                }
+                let end = start.saturating_add(CHUNK_SIZE).min(up_to);
+                // Derive outside of the lock to not block the readers.
+                let chunk: Vec<_> = (start..end)
+                    .filter_map(|index| {
+                        let index = bip32::ChildNumber::from_normal_idx(index).ok()?;
+                        Some((desc.derive(index, secp).script_pubkey(), (index, is_change)))
+                    })
+                    .collect();
+                self.spks.write().unwrap().extend(chunk);
+                start = end;
+                thread::yield_now();
+            }
+        }
+        true
+    }
+}
+
+/// A background thread warming the derivation cache. It is stopped when dropped.
+pub struct CacheWarming {
+    stop: sync::Arc<atomic::AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl CacheWarming {
+    /// Start warming the cache for the revealed range of the descriptors plus the look-ahead.
+    pub fn start(
+        cache: sync::Arc<DerivationCache>,
+        descs: [descriptors::SinglePathLianaDesc; 2],
+        receive_index: bip32::ChildNumber,
+        change_index: bip32::ChildNumber,
+    ) -> CacheWarming {
+        let stop = sync::Arc::from(atomic::AtomicBool::from(false));
+        let up_to = [receive_index, change_index]
+            .map(|index| u32::from(index).saturating_add(LOOK_AHEAD_LIMIT));
+        let handle = thread::Builder::new()
+            .name("Derivation cache warming".to_string())
+            .spawn({
+                let stop = stop.clone();
+                move || {
+                    let secp = secp256k1::Secp256k1::verification_only();
+                    let start = std::time::Instant::now();
+                    if cache.warm(&descs, up_to, &secp, &stop) {
+                        log::info!(
+                            "Derivation cache warmed with {} scriptPubKeys in {:?}.",
+                            cache.len(),
+                            start.elapsed()
+                        );
+                    } else {
+                        log::debug!("Derivation cache warming interrupted.");
+                    }
+                }
+            })
+            .expect("Spawning the cache warming thread must never fail.");
+        CacheWarming {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for CacheWarming {
+    fn drop(&mut self) {
+        self.stop.store(true, atomic::Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                log::error!("Derivation cache warming thread panicked.");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::looper, *};
+    use crate::{
+        bitcoin::{BitcoinInterface, UTxO, UTxOAddress},
+        database::DatabaseInterface,
+        testutils::*,
+    };
+
+    use std::{str::FromStr, time};
+
+    use miniscript::bitcoin::{self, absolute, transaction};
+
+    fn dummy_descriptor() -> descriptors::LianaDescriptor {
+        descriptors::LianaDescriptor::from_str("tr([1dce71b2/48'/1'/0'/2']tpubDEeP3GefjqbaDTTaVAF5JkXWhoFxFDXQ9KuhVrMBViFXXNR2B3Lvme2d2AoyiKfzRFZChq2AGMNbU1qTbkBMfNv7WGVXLt2pnYXY87gXqcs/<0;1>/*,and_v(v:pk([1dce71b2/48'/1'/0'/2']tpubDEeP3GefjqbaDTTaVAF5JkXWhoFxFDXQ9KuhVrMBViFXXNR2B3Lvme2d2AoyiKfzRFZChq2AGMNbU1qTbkBMfNv7WGVXLt2pnYXY87gXqcs/<2;3>/*),older(65535)))").unwrap()
+    }
+
+    #[test]
+    fn warm_derivation_cache() {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let desc = dummy_descriptor();
+        let descs = [
+            desc.receive_descriptor().clone(),
+            desc.change_descriptor().clone(),
+        ];
+        let index = bip32::ChildNumber::from_normal_idx(42).unwrap();
+        let spk = descs[1].derive(index, &secp).script_pubkey();
+
+        // An interrupted warming stops right away.
+        let cache = DerivationCache::default();
+        let stop = atomic::AtomicBool::from(true);
+        assert!(!cache.warm(&descs, [1_000, 1_000], &secp, &stop));
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.derivation_index(&spk), None);
+
+        let stop = atomic::AtomicBool::from(false);
+        assert!(cache.warm(&descs, [100, 50], &secp, &stop));
+        assert_eq!(cache.len(), 150);
+        assert_eq!(cache.derivation_index(&spk), Some((index, true)));
+        let receive_spk = descs[0].derive(index, &secp).script_pubkey();
+        assert_eq!(cache.derivation_index(&receive_spk), Some((index, false)));
+        let beyond = bip32::ChildNumber::from_normal_idx(50).unwrap();
+        let beyond_spk = descs[1].derive(beyond, &secp).script_pubkey();
+        assert_eq!(cache.derivation_index(&beyond_spk), None);
+
+        // Only the last indexes are cached beyond the maximum.
+        let cache = DerivationCache::new(10);
+        assert!(cache.warm(&descs, [100, 0], &secp, &stop));
+        assert_eq!(cache.len(), 10);
+        assert_eq!(cache.derivation_index(&receive_spk), None);
+        let last = bip32::ChildNumber::from_normal_idx(99).unwrap();
+        let last_spk = descs[0].derive(last, &secp).script_pubkey();
+        assert_eq!(cache.derivation_index(&last_spk), Some((last, false)));
+
+        // Dropping the handle stops the thread promptly.
+        let cache = sync::Arc::new(DerivationCache::default());
+        let warming = CacheWarming::start(
+            cache.clone(),
+            descs.clone(),
+            bip32::ChildNumber::from_normal_idx(1_000_000).unwrap(),
+            bip32::ChildNumber::from_normal_idx(1_000_000).unwrap(),
+        );
+        let now = time::Instant::now();
+        drop(warming);
+        assert!(now.elapsed() < time::Duration::from_secs(5));
+        assert!(cache.len() <= 2 * MAX_CACHED_PER_DESC as usize);
+    }
+
+    // The poller resolves the derivation index of the received coins from the warm cache, and
+    // falls back to the database for those beyond it.
+    #[test]
+    fn poll_derivation_cache_fallback() {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let desc = dummy_descriptor();
+        let descs = [
+            desc.receive_descriptor().clone(),
+            desc.change_descriptor().clone(),
+        ];
+        let cache = DerivationCache::new(10);
+        assert!(cache.warm(&descs, [10, 10], &secp, &atomic::AtomicBool::from(false)));
+
+        // A coin received on an address in the cache, one on an address only known to the
+        // database and one on an unknown address.
+        let mut bitcoind = DummyBitcoind::new();
+        let mut db = DummyDatabase::new();
+        for index in [5, 15, 25] {
+            let index = bip32::ChildNumber::from_normal_idx(index).unwrap();
+            let spk = descs[0].derive(index, &secp).script_pubkey();
+            let address = bitcoin::Address::from_script(&spk, bitcoin::Network::Bitcoin).unwrap();
+            if u32::from(index) == 15 {
+                db.insert_address(address.clone(), index, false);
+            }
+            let tx = bitcoin::Transaction {
+                version: transaction::Version::TWO,
+                lock_time: absolute::LockTime::ZERO,
+                input: vec![],
+                output: vec![bitcoin::TxOut {
+                    value: bitcoin::Amount::from_sat(100_000),
+                    script_pubkey: spk,
+                }],
+            };
+            bitcoind.received.push(UTxO {
+                outpoint: bitcoin::OutPoint::new(tx.txid(), 0),
+                amount: bitcoin::Amount::from_sat(100_000),
+                block_height: None,
+                address: UTxOAddress::Address(address.as_unchecked().clone()),
+                is_immature: false,
+            });
+            bitcoind.txs.insert(tx.txid(), (tx, None));
+        }
+
+        let mut bit: sync::Arc<sync::Mutex<dyn BitcoinInterface>> =
+            sync::Arc::from(sync::Mutex::from(bitcoind));
+        let db: sync::Arc<sync::Mutex<dyn DatabaseInterface>> =
+            sync::Arc::from(sync::Mutex::from(db));
+        looper::maybe_initialize_tip(&bit, &db);
+        looper::poll(&mut bit, &db, &secp, &descs, &cache);
+
+        let mut coins: Vec<_> = db
+            .connection()
+            .unwrap()
+            .coins(&[], &[])
+            .into_values()
+            .map(|c| (u32::from(c.derivation_index), c.is_change))
+            .collect();
+        coins.sort_unstable();
+        assert_eq!(coins, vec![(5, false), (15, false)]);
+    }
+}
@@ -1,3 +1,4 @@
+use super::derivation_cache::DerivationCache;
 use crate::{
     bitcoin::{BitcoinInterface, BlockChainTip, UTxO, UTxOAddress},
     database::{Coin, DatabaseConnection, DatabaseInterface},
@@ -29,6 +30,7 @@ fn update_coins(
     db_conn: &mut Box<dyn DatabaseConnection>,
     previous_tip: &BlockChainTip,
     descs: &[descriptors::SinglePathLianaDesc],
+    derivation_cache: &DerivationCache,
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
 ) -> UpdatedCoins {
     let network = db_conn.network();
@@ -55,8 +57,9 @@ fn update_coins(
                         continue;
                     }
                 };
-                if let Some((derivation_index, is_change)) =
-                    db_conn.derivation_index_by_address(&address)
+                if let Some((derivation_index, is_change)) = derivation_cache
+                    .derivation_index(&address.script_pubkey())
+                    .or_else(|| db_conn.derivation_index_by_address(&address))
                 {
                     (derivation_index, is_change)
                 } else {
@@ -243,6 +246,7 @@ fn updates(
     db_conn: &mut Box<dyn DatabaseConnection>,
     bit: &mut impl BitcoinInterface,
     descs: &[descriptors::SinglePathLianaDesc],
+    derivation_cache: &DerivationCache,
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
 ) {
     // Check if there was a new block before we update our state.
@@ -265,7 +269,7 @@ fn updates(
                     // between our former chain and the new one, then restart fresh.
                     db_conn.rollback_tip(&new_tip);
                     log::info!("Tip was rolled back to '{}'.", new_tip);
-                    return updates(db_conn, bit, descs, secp);
+                    return updates(db_conn, bit, descs, derivation_cache, secp);
                 }
             }
         }
@@ -286,23 +290,23 @@ fn updates(
                     &reorg_common_ancestor
                 );
             }
-            return updates(db_conn, bit, descs, secp);
+            return updates(db_conn, bit, descs, derivation_cache, secp);
         }
         Err(e) => {
             log::error!("Error syncing wallet: '{}'.", e);
             thread::sleep(time::Duration::from_secs(2));
-            return updates(db_conn, bit, descs, secp);
+            return updates(db_conn, bit, descs, derivation_cache, secp);
         }
     };
 
     // Then check the state of our coins. Do it even if the tip did not change since last poll, as
     // we may have unconfirmed transactions.
-    let updated_coins = update_coins(bit, db_conn, &current_tip, descs, secp);
+    let updated_coins = update_coins(bit, db_conn, &current_tip, descs, derivation_cache, secp);
 
     // If the tip changed while we were polling our Bitcoin interface, start over.
     if bit.chain_tip() != latest_tip {
         log::info!("Chain tip changed while we were updating our state. Starting over.");
-        return updates(db_conn, bit, descs, secp);
+        return updates(db_conn, bit, descs, derivation_cache, secp);
     }
 
     // Transactions must be added to the DB before coins due to foreign key constraints.
@@ -332,6 +336,7 @@ fn rescan_check(
     db_conn: &mut Box<dyn DatabaseConnection>,
     bit: &mut impl BitcoinInterface,
     descs: &[descriptors::SinglePathLianaDesc],
+    derivation_cache: &DerivationCache,
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
 ) {
     log::debug!("Checking the state of an ongoing rescan if there is any");
@@ -369,7 +374,7 @@ fn rescan_check(
             "Rolling back our internal tip to '{}' to update our internal state with past transactions.",
             rescan_tip
         );
-        updates(db_conn, bit, descs, secp)
+        updates(db_conn, bit, descs, derivation_cache, secp)
     } else {
         log::debug!("No ongoing rescan.");
     }
@@ -377,7 +382,7 @@ fn rescan_check(
 
 /// If the database chain tip is NULL (first startup), initialize it.
 pub fn maybe_initialize_tip(bit: &impl BitcoinInterface, db: &impl DatabaseInterface) {
-    let mut db_conn = db.connection();
+    let mut db_conn = db.connection().expect("Database must be available");
 
     if db_conn.chain_tip().is_none() {
         // TODO: be smarter. We can use the timestamp of the descriptor to get a newer block hash.
@@ -401,10 +406,11 @@ pub fn poll(
     db: &sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
     secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
     descs: &[descriptors::SinglePathLianaDesc],
+    derivation_cache: &DerivationCache,
 ) {
-    let mut db_conn = db.connection();
-    updates(&mut db_conn, bit, descs, secp);
-    rescan_check(&mut db_conn, bit, descs, secp);
+    let mut db_conn = db.connection().expect("Database must be available");
+    updates(&mut db_conn, bit, descs, derivation_cache, secp);
+    rescan_check(&mut db_conn, bit, descs, derivation_cache, secp);
     let now: u32 = time::SystemTime::now()
         .duration_since(time::UNIX_EPOCH)
         .expect("current system time must be later than epoch")
@@ -1,21 +1,159 @@
+mod backoff;
+mod derivation_cache;
 mod looper;
+mod thresholds;
 
-use crate::{bitcoin::BitcoinInterface, database::DatabaseInterface};
+pub use thresholds::RecoveryWarning;
+
+use crate::{
+    bitcoin::{BackendHealth, BitcoinInterface, SyncProgress},
+    config::RecoveryThreshold,
+    database::{CoinStatus, DatabaseInterface, DbSizeInfo},
+};
 use liana::descriptors;
 
 use std::{
-    sync::{self, mpsc},
+    convert::TryFrom,
+    sync::{self, atomic, mpsc},
     time,
 };
 
 use miniscript::bitcoin::secp256k1;
 
+// Set the height of the tip in database to be read by the commands without querying it.
+fn update_latest_tip_height(
+    db: &sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+    latest_tip_height: &atomic::AtomicU32,
+) {
+    if let Some(tip) = db
+        .connection()
+        .expect("Database must be available")
+        .chain_tip()
+    {
+        match u32::try_from(tip.height) {
+            Ok(height) => latest_tip_height.store(height, atomic::Ordering::Relaxed),
+            Err(_) => log::error!("Invalid tip height in database: {}", tip.height),
+        }
+    }
+}
+
+/// The sync progress is shared with the other threads as a fixed-point integer, in millionths.
+const SYNC_PROGRESS_SCALE: f64 = 1_000_000.0;
+/// The value of the shared sync progress until the Bitcoin backend was first queried.
+const SYNC_PROGRESS_UNKNOWN: u32 = u32::MAX;
+
+/// The progress of the Bitcoin backend sync as of the last check of the poller, shared with the
+/// other threads so they can read it without querying the backend.
+#[derive(Debug)]
+pub struct SharedSyncProgress {
+    // Between 0 and 1, see `SYNC_PROGRESS_SCALE`.
+    progress: atomic::AtomicU32,
+    blocks: atomic::AtomicU64,
+    headers: atomic::AtomicU64,
+}
+
+impl Default for SharedSyncProgress {
+    fn default() -> Self {
+        Self {
+            progress: atomic::AtomicU32::new(SYNC_PROGRESS_UNKNOWN),
+            blocks: atomic::AtomicU64::new(0),
+            headers: atomic::AtomicU64::new(0),
+        }
+    }
+}
+
+impl SharedSyncProgress {
+    fn store(&self, progress: &SyncProgress) {
+        self.blocks
+            .store(progress.blocks, atomic::Ordering::Relaxed);
+        self.headers
+            .store(progress.headers, atomic::Ordering::Relaxed);
+        let progress =
+            (progress.rounded_up_progress().clamp(0.0, 1.0) * SYNC_PROGRESS_SCALE).round() as u32;
+        self.progress.store(progress, atomic::Ordering::Relaxed);
+    }
+
+    /// The sync progress, between 0 and 1. `None` if it wasn't measured yet.
+    pub fn progress(&self) -> Option<f32> {
+        match self.progress.load(atomic::Ordering::Relaxed) {
+            SYNC_PROGRESS_UNKNOWN => None,
+            progress => Some((f64::from(progress) / SYNC_PROGRESS_SCALE) as f32),
+        }
+    }
+
+    /// The sync progress as of the last check, if it was checked.
+    pub fn last(&self) -> Option<SyncProgress> {
+        let progress = self.progress()?;
+        let (blocks, headers) = self.heights()?;
+        Some(SyncProgress::new(progress.into(), headers, blocks))
+    }
+
+    /// The number of blocks validated and of headers of the best known chain (in this order).
+    /// `None` if they weren't measured yet.
+    pub fn heights(&self) -> Option<(u64, u64)> {
+        self.progress()?;
+        Some((
+            self.blocks.load(atomic::Ordering::Relaxed),
+            self.headers.load(atomic::Ordering::Relaxed),
+        ))
+    }
+}
+
+/// Signals the completion of each poll to the threads waiting for the wallet state to change.
+#[derive(Debug, Default)]
+pub struct PollSignal {
+    polls: sync::Mutex<u64>,
+    cond: sync::Condvar,
+    stopped: sync::atomic::AtomicBool,
+}
+
+impl PollSignal {
+    fn notify(&self) {
+        *self.polls.lock().unwrap() += 1;
+        self.cond.notify_all();
+    }
+
+    // Wake up the waiters for good, no more polls will be completed.
+    fn stop(&self) {
+        let _polls = self.polls.lock().unwrap();
+        self.stopped.store(true, sync::atomic::Ordering::SeqCst);
+        self.cond.notify_all();
+    }
+
+    /// Whether the poller stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(sync::atomic::Ordering::SeqCst)
+    }
+
+    /// The number of polls completed so far.
+    pub fn polls(&self) -> u64 {
+        *self.polls.lock().unwrap()
+    }
+
+    /// Wait until more than `seen` polls were completed, until `timeout` elapses or until the
+    /// poller stops. Returns the number of polls completed.
+    pub fn wait(&self, seen: u64, timeout: time::Duration) -> u64 {
+        let polls = self.polls.lock().unwrap();
+        let (polls, _) = self
+            .cond
+            .wait_timeout_while(polls, timeout, |polls| *polls <= seen && !self.is_stopped())
+            .unwrap();
+        *polls
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum PollerMessage {
     Shutdown,
     /// Ask the Bitcoin poller to poll immediately, get notified through the passed channel once
     /// it's done.
     PollNow(mpsc::SyncSender<()>),
+    /// Ask the Bitcoin poller to perform maintenance of the database in between two polls. The
+    /// size of the database before and after is sent through the passed channel once done.
+    DbMaintenance(mpsc::SyncSender<(DbSizeInfo, DbSizeInfo)>),
+    /// Go back to the regular poll interval and poll right away, if the polls were spaced out
+    /// because the Bitcoin backend couldn't be reached.
+    ResetBackoff,
 }
 
 /// The Bitcoin poller handler.
@@ -25,33 +163,174 @@ pub struct Poller {
     secp: secp256k1::Secp256k1<secp256k1::VerifyOnly>,
     // The receive and change descriptors (in this order).
     descs: [descriptors::SinglePathLianaDesc; 2],
+    // The number of blocks after which a coin is spendable through the recovery path.
+    first_timelock: u16,
+    recovery_thresholds: Vec<RecoveryThreshold>,
+    // The thresholds reached as of the last poll, shared with the commands.
+    recovery_warnings: sync::Arc<sync::Mutex<Vec<RecoveryWarning>>>,
+    // The height of our tip as of the last poll, shared with the commands.
+    latest_tip_height: sync::Arc<atomic::AtomicU32>,
+    // Signaled after each poll, shared with the commands.
+    poll_signal: sync::Arc<PollSignal>,
+    // Whether the Bitcoin backend can be reached, if tracked.
+    backend_health: Option<sync::Arc<BackendHealth>>,
+    derivation_cache: sync::Arc<derivation_cache::DerivationCache>,
+    // The thread warming the derivation cache, if any. It is stopped along with the poller.
+    _cache_warming: Option<derivation_cache::CacheWarming>,
 }
 
 impl Poller {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         bit: sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
         db: sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
         desc: descriptors::LianaDescriptor,
+        recovery_thresholds: Vec<RecoveryThreshold>,
+        recovery_warnings: sync::Arc<sync::Mutex<Vec<RecoveryWarning>>>,
+        latest_tip_height: sync::Arc<atomic::AtomicU32>,
+        sync_progress: sync::Arc<SharedSyncProgress>,
+        poll_signal: sync::Arc<PollSignal>,
+        warm_derivation_cache: bool,
     ) -> Poller {
         let secp = secp256k1::Secp256k1::verification_only();
         let descs = [
             desc.receive_descriptor().clone(),
             desc.change_descriptor().clone(),
         ];
+        let first_timelock = desc.first_timelock_value();
 
         // On first startup the tip may be NULL. Make sure it's set as the poller relies on it.
         looper::maybe_initialize_tip(&bit, &db);
+        update_latest_tip_height(&db, &latest_tip_height);
+
+        // Pre-derive the scriptPubKeys of the revealed addresses in the background so the first
+        // poll doesn't have to.
+        let derivation_cache = sync::Arc::new(derivation_cache::DerivationCache::default());
+        let cache_warming = if warm_derivation_cache {
+            let mut db_conn = db.connection().expect("Database must be available");
+            Some(derivation_cache::CacheWarming::start(
+                derivation_cache.clone(),
+                descs.clone(),
+                db_conn.receive_index(),
+                db_conn.change_index(),
+            ))
+        } else {
+            None
+        };
 
+        let backend_health = bit.health();
         Poller {
             bit,
             db,
             secp,
             descs,
+            first_timelock,
+            recovery_thresholds,
+            recovery_warnings,
+            latest_tip_height,
+            sync_progress,
+            poll_signal,
+            backend_health,
+            derivation_cache,
+            _cache_warming: cache_warming,
         }
     }
 
+    // Update our state from the Bitcoin backend, then evaluate the recovery thresholds.
+    fn poll(&mut self) {
+        looper::poll(
+            &mut self.bit,
+            &self.db,
+            &self.secp,
+            &self.descs,
+            &self.derivation_cache,
+        );
+        update_latest_tip_height(&self.db, &self.latest_tip_height);
+        self.update_recovery_warnings();
+        self.poll_signal.notify();
+    }
+
+    // Query the sync progress of the Bitcoin backend, log and share it. Returns whether the sync is
+    // complete.
+    fn check_sync_progress(&self) -> bool {
+        let progress = self.bit.sync_progress();
+        log::info!(
+            "Block chain synchronization progress: {:.2}% ({} blocks / {} headers)",
+            progress.rounded_up_progress() * 100.0,
+            progress.blocks,
+            progress.headers
+        );
+        self.sync_progress.store(&progress);
+        progress.is_complete()
+    }
+
+    // Space out the polls if the Bitcoin backend couldn't be reached by the last one, or go back
+    // to the regular interval once it could.
+    fn update_backoff(&self, backoff: &mut backoff::Backoff, poll_interval: time::Duration) {
+        let failed = self
+            .backend_health
+            .as_ref()
+            .map_or(false, |health| health.unreachable_since().is_some());
+        if failed {
+            backoff.failed();
+            if backoff.is_degraded() {
+                log::warn!(
+                    "The Bitcoin backend could not be reached for the last {} polls. Next poll in \
+                     {} seconds.",
+                    backoff.failures(),
+                    backoff.interval(poll_interval).as_secs()
+                );
+            }
+        } else if backoff.reset() {
+            log::info!(
+                "The Bitcoin backend can be reached again. Polling every {} seconds.",
+                poll_interval.as_secs()
+            );
+        }
+    }
+
+    // Check which of the configured recovery thresholds are reached given our current coins and
+    // chain tip. Log those which were newly reached.
+    fn update_recovery_warnings(&mut self) {
+        if self.recovery_thresholds.is_empty() {
+            return;
+        }
+
+        let mut db_conn = self.db.connection().expect("Database must be available");
+        let tip = match db_conn.chain_tip() {
+            Some(tip) => tip,
+            None => return,
+        };
+        let coins: Vec<_> = db_conn
+            .coins(&[CoinStatus::Confirmed], &[])
+            .into_values()
+            .filter_map(|coin| {
+                let height = coin.block_info?.height;
+                Some((coin.amount, height + i32::from(self.first_timelock)))
+            })
+            .collect();
+
+        let mut warnings = self.recovery_warnings.lock().unwrap();
+        let new_warnings =
+            thresholds::recovery_warnings(&self.recovery_thresholds, &coins, tip.height, &warnings);
+        for warning in &new_warnings {
+            if !warnings.iter().any(|w| w.threshold == warning.threshold) {
+                log::warn!(
+                    "Recovery threshold reached: {} of coins are spendable through the recovery \
+                     path within {} blocks (earliest at height {}).",
+                    warning.amount,
+                    warning.threshold.blocks_before_expiry,
+                    warning.earliest_height
+                );
+            }
+        }
+        *warnings = new_warnings;
+    }
+
     /// Continuously update our state from the Bitcoin backend.
     /// - `poll_interval`: how frequently to perform an update.
+    /// - `max_backoff`: how far apart the updates may be spaced out while the Bitcoin backend
+    ///   can't be reached.
     /// - `shutdown`: set to true to stop continuously updating and make this function return.
     ///
     /// Typically this would run for the whole duration of the program in a thread, and the main
@@ -59,23 +338,27 @@ impl Poller {
     pub fn poll_forever(
         &mut self,
         poll_interval: time::Duration,
+        max_backoff: time::Duration,
         receiver: mpsc::Receiver<PollerMessage>,
     ) {
         let mut last_poll = None;
         let mut synced = false;
+        let mut backoff = backoff::Backoff::new(max_backoff);
 
         loop {
+            // Until we are synced we poll less often to avoid harassing bitcoind and impeding
+            // the sync. As a function since it's mocked for the tests.
+            let current_interval = if synced {
+                poll_interval
+            } else {
+                looper::sync_poll_interval()
+            };
             // How long to wait before the next poll.
             let time_before_poll = if let Some(last_poll) = last_poll {
                 let time_since_poll = time::Instant::now().duration_since(last_poll);
-                // Until we are synced we poll less often to avoid harassing bitcoind and impeding
-                // the sync. As a function since it's mocked for the tests.
-                let poll_interval = if synced {
-                    poll_interval
-                } else {
-                    looper::sync_poll_interval()
-                };
-                poll_interval.saturating_sub(time_since_poll)
+                backoff
+                    .interval(current_interval)
+                    .saturating_sub(time_since_poll)
             } else {
                 // Don't wait before doing the first poll.
                 time::Duration::ZERO
@@ -86,6 +369,7 @@ impl Poller {
             match receiver.recv_timeout(time_before_poll) {
                 Ok(PollerMessage::Shutdown) => {
                     log::info!("Bitcoin poller was told to shut down.");
+                    self.poll_signal.stop();
                     return;
                 }
                 Ok(PollerMessage::PollNow(sender)) => {
@@ -95,33 +379,52 @@ impl Poller {
                     // if the height increases before completion, and in any case this is consistent
                     // with regular poller behaviour.
                     if !synced {
-                        let progress = self.bit.sync_progress();
-                        log::info!(
-                            "Block chain synchronization progress: {:.2}% ({} blocks / {} headers)",
-                            progress.rounded_up_progress() * 100.0,
-                            progress.blocks,
-                            progress.headers
-                        );
-                        synced = progress.is_complete();
+                        synced = self.check_sync_progress();
                     }
                     // Update `last_poll` even if we don't poll now so that we don't attempt another
                     // poll too soon.
                     last_poll = Some(time::Instant::now());
                     if synced {
-                        looper::poll(&mut self.bit, &self.db, &self.secp, &self.descs);
+                        self.poll();
                     } else {
                         log::warn!("Skipped poll as block chain is still synchronizing.");
                     }
+                    self.update_backoff(&mut backoff, current_interval);
                     if let Err(e) = sender.send(()) {
                         log::error!("Error sending immediate poll completion signal: {}.", e);
                     }
                     continue;
                 }
+                Ok(PollerMessage::DbMaintenance(sender)) => {
+                    // Performed from the poller thread so it never happens in the middle of a poll.
+                    let mut db_conn = self.db.connection().expect("Database must be available");
+                    let size_before = db_conn.size_info();
+                    log::info!("Performing database maintenance.");
+                    db_conn.maintain();
+                    let size_after = db_conn.size_info();
+                    log::info!(
+                        "Database maintenance done. Size went from {} to {} bytes.",
+                        size_before.size(),
+                        size_after.size()
+                    );
+                    if let Err(e) = sender.send((size_before, size_after)) {
+                        log::error!("Error sending database maintenance result: {}.", e);
+                    }
+                    continue;
+                }
+                Ok(PollerMessage::ResetBackoff) => {
+                    if backoff.reset() {
+                        log::info!("Retrying to reach the Bitcoin backend right away.");
+                        last_poll = None;
+                    }
+                    continue;
+                }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
                     // It's been long enough since the last poll.
                 }
                 Err(mpsc::RecvTimeoutError::Disconnected) => {
                     log::error!("Bitcoin poller communication channel got disconnected. Exiting.");
+                    self.poll_signal.stop();
                     return;
                 }
             }
@@ -129,20 +432,12 @@ impl Poller {
 
             // Don't poll until the Bitcoin backend is fully synced.
             if !synced {
-                let progress = self.bit.sync_progress();
-                log::info!(
-                    "Block chain synchronization progress: {:.2}% ({} blocks / {} headers)",
-                    progress.rounded_up_progress() * 100.0,
-                    progress.blocks,
-                    progress.headers
-                );
-                synced = progress.is_complete();
-                if !synced {
-                    continue;
-                }
+                synced = self.check_sync_progress();
             }
-
-            looper::poll(&mut self.bit, &self.db, &self.secp, &self.descs);
+            if synced {
+                self.poll();
+            }
+            self.update_backoff(&mut backoff, current_interval);
         }
     }
 }
@@ -0,0 +1,127 @@
+//! Evaluation of the recovery thresholds set in our configuration.
+
+use crate::config::RecoveryThreshold;
+
+use miniscript::bitcoin;
+
+/// Once a threshold was reached, its window is extended by this many blocks before the warning
+/// is lifted. This avoids the warning flapping for coins at the boundary (for instance in case of
+/// a shallow reorg).
+pub const HYSTERESIS_BLOCKS: u32 = 6;
+
+/// A recovery threshold which is currently reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryWarning {
+    /// The threshold, as set in our configuration.
+    pub threshold: RecoveryThreshold,
+    /// The total value of the coins whose recovery path becomes available within the threshold.
+    pub amount: bitcoin::Amount,
+    /// The earliest height at which one of these coins is spendable through the recovery path.
+    pub earliest_height: i32,
+}
+
+/// Get the thresholds which are reached by the given coins.
+///
+/// `coins` are the value of each coin along with the height at which it becomes spendable
+/// through the recovery path. `previous` are the warnings returned at the previous evaluation.
+pub fn recovery_warnings(
+    thresholds: &[RecoveryThreshold],
+    coins: &[(bitcoin::Amount, i32)],
+    tip_height: i32,
+    previous: &[RecoveryWarning],
+) -> Vec<RecoveryWarning> {
+    thresholds
+        .iter()
+        .filter_map(|threshold| {
+            let was_reached = previous.iter().any(|w| w.threshold == *threshold);
+            let window = i64::from(threshold.blocks_before_expiry)
+                + if was_reached {
+                    i64::from(HYSTERESIS_BLOCKS)
+                } else {
+                    0
+                };
+            let (amount, earliest_height) = coins
+                .iter()
+                .filter(|(_, height)| i64::from(*height) - i64::from(tip_height) <= window)
+                .fold(
+                    (bitcoin::Amount::ZERO, None),
+                    |(amount, earliest): (_, Option<i32>), (value, height)| {
+                        (
+                            amount + *value,
+                            Some(earliest.map_or(*height, |e| e.min(*height))),
+                        )
+                    },
+                );
+            if amount.to_sat() > threshold.min_amount_sat {
+                Some(RecoveryWarning {
+                    threshold: *threshold,
+                    amount,
+                    earliest_height: earliest_height?,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_thresholds() {
+        let threshold = RecoveryThreshold {
+            blocks_before_expiry: 10,
+            min_amount_sat: 100_000,
+        };
+        let coins = [
+            (bitcoin::Amount::from_sat(60_000), 120),
+            (bitcoin::Amount::from_sat(50_000), 115),
+            (bitcoin::Amount::from_sat(1_000_000), 200),
+        ];
+
+        // Only the second coin is within the window, it's not enough.
+        let warnings = recovery_warnings(&[threshold], &coins, 106, &[]);
+        assert!(warnings.is_empty());
+
+        // Both the first and second coins are within the window, it's enough.
+        let warnings = recovery_warnings(&[threshold], &coins, 110, &[]);
+        assert_eq!(
+            warnings,
+            vec![RecoveryWarning {
+                threshold,
+                amount: bitcoin::Amount::from_sat(110_000),
+                earliest_height: 115,
+            }]
+        );
+
+        // If the tip goes back a few blocks (for instance because of a reorg) the warning
+        // is not lifted.
+        assert_eq!(
+            recovery_warnings(&[threshold], &coins, 108, &warnings),
+            warnings
+        );
+        assert!(recovery_warnings(&[threshold], &coins, 108, &[]).is_empty());
+        // But it is if it goes further than the hysteresis.
+        assert!(recovery_warnings(&[threshold], &coins, 100, &warnings).is_empty());
+
+        // Coins already available through the recovery path are accounted for.
+        let warnings = recovery_warnings(&[threshold], &coins, 190, &[]);
+        assert_eq!(warnings[0].amount, bitcoin::Amount::from_sat(1_110_000));
+        assert_eq!(warnings[0].earliest_height, 115);
+
+        // Each threshold is evaluated independently.
+        let large_threshold = RecoveryThreshold {
+            blocks_before_expiry: 100,
+            min_amount_sat: 1_000_000,
+        };
+        let warnings = recovery_warnings(&[threshold, large_threshold], &coins, 110, &[]);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[1].threshold, large_threshold);
+        assert_eq!(warnings[1].amount, bitcoin::Amount::from_sat(1_110_000));
+
+        // No coin, no warning.
+        assert!(recovery_warnings(&[threshold], &[], 110, &[]).is_empty());
+    }
+}
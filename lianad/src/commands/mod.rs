@@ -6,19 +6,19 @@ mod utils;
 
 use crate::{
     bitcoin::BitcoinInterface,
-    database::{Coin, DatabaseConnection, DatabaseInterface},
+    database::{Coin, DatabaseBusy, DatabaseConnection, DatabaseInterface, DbSizeInfo, SavedPsbt},
     miniscript::bitcoin::absolute::LockTime,
     poller::PollerMessage,
     DaemonControl, VERSION,
 };
 
-pub use crate::database::{CoinStatus, LabelItem};
+pub use crate::database::{CoinStatus, LabelItem, LabelKind};
 
 use liana::{
     descriptors,
     spend::{
-        self, create_spend, AddrInfo, AncestorInfo, CandidateCoin, CreateSpendRes,
-        SpendCreationError, SpendOutputAddress, SpendTxFees, TxGetter,
+        self, create_spend, AddrInfo, AncestorInfo, CandidateCoin, CoinSelectionAlgorithm,
+        CreateSpendRes, SpendCreationError, SpendOutputAddress, SpendTxFees, TxGetter,
     },
 };
 
@@ -28,11 +28,11 @@ use utils::{
 };
 
 use std::{
-    collections::{hash_map, HashMap, HashSet},
+    collections::{hash_map, BTreeMap, HashMap, HashSet},
     convert::TryInto,
     fmt,
-    sync::{self, mpsc},
-    time::SystemTime,
+    sync::{atomic, mpsc},
+    time::{Duration, Instant, SystemTime},
 };
 
 use miniscript::{
@@ -41,6 +41,20 @@ use miniscript::{
 };
 use serde::{Deserialize, Serialize};
 
+// Suggest a database maintenance once at least this many bytes, and a quarter of the database, are
+// unused.
+const DB_MAINTENANCE_MIN_FREE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Maximum number of calls to `wait_for_tx` which may be waiting at the same time. It is lower than
+/// the maximum number of JSONRPC connections so waiting clients can't prevent others to connect.
+const MAX_CONCURRENT_TX_WAITS: u32 = 8;
+
+/// How many indexes past the last revealed one `find_address` derives to look for an address.
+const FIND_ADDRESS_LOOKAHEAD: u32 = 1_000;
+
+/// Maximum duration a call to `wait_for_tx` may wait for.
+pub const MAX_TX_WAIT_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CommandError {
     NoOutpointForSelfSend,
@@ -48,6 +62,8 @@ pub enum CommandError {
     UnknownOutpoint(bitcoin::OutPoint),
     AlreadySpent(bitcoin::OutPoint),
     ImmatureCoinbase(bitcoin::OutPoint),
+    /// The coin is confirmed while it was expected to be unconfirmed.
+    ConfirmedCoin(bitcoin::OutPoint),
     Address(bitcoin::address::Error),
     SpendCreation(SpendCreationError),
     InsufficientFunds(
@@ -55,19 +71,42 @@ pub enum CommandError {
         /* out value */ Option<bitcoin::Amount>,
         /* target feerate */ u64,
     ),
+    /// Coins would have to be spent along with coins of another privacy tag to fund the
+    /// transaction. Contains the value missing to each tag (`None` for untagged coins), in sats.
+    InsufficientFundsPerTag(Vec<(Option<String>, u64)>),
     UnknownSpend(bitcoin::Txid),
+    UnknownSavedPsbt(i64),
     // FIXME: when upgrading Miniscript put the actual error there
     SpendFinalization(String),
-    TxBroadcast(String),
+    TxBroadcast(BroadcastError),
     AlreadyRescanning,
     InsaneRescanTimestamp(u32),
     /// An error that might occur in the racy rescan triggering logic.
     RescanTrigger(String),
     RecoveryNotAvailable,
+    /// The descriptor has no recovery path with this timelock.
+    UnknownRecoveryPath(/* timelock */ u16),
+    /// The coin can't be spent through the recovery path with this timelock at the next block.
+    RecoveryPathNotAvailable(bitcoin::OutPoint, /* timelock */ u16),
     /// Overflowing or unhardened derivation index.
     InvalidDerivationIndex,
+    /// Revealing another address would go past the configured maximum derivation index.
+    DerivationIndexLimit(/* max index */ u32),
+    /// The next receive derivation index is not higher than the requested one.
+    ReceiveIndexNotLower(/* current index */ u32),
+    /// Addresses past the requested receive index were already used.
+    UsedAddresses(/* from index */ u32),
+    /// The Bitcoin backend could not tell whether addresses were used.
+    AddressHistory(String),
     RbfError(RbfErrorInfo),
     EmptyFilterList,
+    FeeEstimateUnavailable,
+    DbMaintenance(String),
+    TooManyTxWaits,
+    /// Less than two coins would be consolidated.
+    NothingToConsolidate,
+    /// No database connection could be obtained in time.
+    DatabaseBusy(DatabaseBusy),
 }
 
 impl fmt::Display for CommandError {
@@ -83,6 +122,11 @@ impl fmt::Display for CommandError {
                 "Coin at '{}' is from an immature coinbase transaction.",
                 op
             ),
+            Self::ConfirmedCoin(op) => write!(
+                f,
+                "Coin at '{}' is already confirmed, there is no transaction to bump.",
+                op
+            ),
             Self::UnknownOutpoint(op) => write!(f, "Unknown outpoint '{}'.", op),
             Self::Address(e) => write!(f, "Address error: {}", e),
             Self::SpendCreation(e) => write!(f, "Creating spend: {}", e),
@@ -101,11 +145,25 @@ impl fmt::Display for CommandError {
                     )
                 }
             }
+            Self::InsufficientFundsPerTag(missing) => write!(
+                f,
+                "No set of coins sharing a privacy tag can fund this transaction without mixing \
+                 tags. Missing {}.",
+                missing
+                    .iter()
+                    .map(|(tag, missing)| match tag {
+                        Some(tag) => format!("{} sats for '{}'", missing, tag),
+                        None => format!("{} sats for untagged coins", missing),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
             Self::UnknownSpend(txid) => write!(f, "Unknown spend transaction '{}'.", txid),
+            Self::UnknownSavedPsbt(id) => write!(f, "Unknown saved PSBT '{}'.", id),
             Self::SpendFinalization(e) => {
                 write!(f, "Failed to finalize the spend transaction PSBT: '{}'.", e)
             }
-            Self::TxBroadcast(e) => write!(f, "Failed to broadcast transaction: '{}'.", e),
+            Self::TxBroadcast(e) => write!(f, "Failed to broadcast transaction: {}.", e),
             Self::AlreadyRescanning => write!(
                 f,
                 "There is already a rescan ongoing. Please wait for it to complete first."
@@ -116,11 +174,99 @@ impl fmt::Display for CommandError {
                 f,
                 "No coin currently spendable through this timelocked recovery path."
             ),
+            Self::UnknownRecoveryPath(timelock) => write!(
+                f,
+                "No recovery path with a timelock of {} blocks in the descriptor.",
+                timelock
+            ),
+            Self::RecoveryPathNotAvailable(op, timelock) => write!(
+                f,
+                "Coin at '{}' cannot be spent yet through the recovery path with a timelock of \
+                 {} blocks.",
+                op, timelock
+            ),
             Self::InvalidDerivationIndex => {
                 write!(f, "Unhardened or overflowing BIP32 derivation index.")
             }
+            Self::DerivationIndexLimit(max) => write!(
+                f,
+                "Refusing to reveal an address past the maximum derivation index of {}. See the \
+                 'max_derivation_index' configuration option.",
+                max
+            ),
+            Self::ReceiveIndexNotLower(index) => write!(
+                f,
+                "The next receive derivation index is {}, it can only be lowered.",
+                index
+            ),
+            Self::UsedAddresses(index) => write!(
+                f,
+                "Some receive addresses from derivation index {} were already used.",
+                index
+            ),
+            Self::AddressHistory(e) => write!(
+                f,
+                "Could not check the history of addresses with the Bitcoin backend: '{}'.",
+                e
+            ),
             Self::RbfError(e) => write!(f, "RBF error: '{}'.", e),
             Self::EmptyFilterList => write!(f, "Filter list is empty, should supply None instead."),
+            Self::FeeEstimateUnavailable => {
+                write!(f, "No source could provide a fee estimate.")
+            }
+            Self::DbMaintenance(e) => write!(f, "Error performing database maintenance: '{}'", e),
+            Self::TooManyTxWaits => write!(
+                f,
+                "Too many clients waiting for a transaction already ({} at most).",
+                MAX_CONCURRENT_TX_WAITS
+            ),
+            Self::NothingToConsolidate => {
+                write!(
+                    f,
+                    "At least two confirmed coins are needed for a consolidation."
+                )
+            }
+            Self::DatabaseBusy(e) => write!(f, "Database busy: {} Please try again.", e),
+        }
+    }
+}
+
+impl CommandError {
+    /// A stable identifier of the error, for clients to handle it without parsing its message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoOutpointForSelfSend => "LIANA_NO_OUTPOINT_FOR_SELF_SEND",
+            Self::InvalidFeerate(..) => "LIANA_INVALID_FEERATE",
+            Self::UnknownOutpoint(..) => "LIANA_UNKNOWN_OUTPOINT",
+            Self::AlreadySpent(..) => "LIANA_COIN_ALREADY_SPENT",
+            Self::ImmatureCoinbase(..) => "LIANA_IMMATURE_COINBASE",
+            Self::ConfirmedCoin(..) => "LIANA_COIN_CONFIRMED",
+            Self::Address(..) => "LIANA_INVALID_ADDRESS",
+            Self::SpendCreation(..) => "LIANA_SPEND_CREATION",
+            Self::InsufficientFunds(..) => "LIANA_INSUFFICIENT_FUNDS",
+            Self::InsufficientFundsPerTag(..) => "LIANA_INSUFFICIENT_FUNDS_PER_TAG",
+            Self::UnknownSpend(..) => "LIANA_UNKNOWN_SPEND",
+            Self::UnknownSavedPsbt(..) => "LIANA_UNKNOWN_SAVED_PSBT",
+            Self::SpendFinalization(..) => "LIANA_SPEND_FINALIZATION",
+            Self::TxBroadcast(..) => "LIANA_BROADCAST_FAILED",
+            Self::AlreadyRescanning => "LIANA_RESCAN_IN_PROGRESS",
+            Self::InsaneRescanTimestamp(..) => "LIANA_INVALID_RESCAN_TIMESTAMP",
+            Self::RescanTrigger(..) => "LIANA_RESCAN_TRIGGER",
+            Self::RecoveryNotAvailable => "LIANA_RECOVERY_NOT_AVAILABLE",
+            Self::UnknownRecoveryPath(..) => "LIANA_UNKNOWN_RECOVERY_PATH",
+            Self::RecoveryPathNotAvailable(..) => "LIANA_RECOVERY_PATH_NOT_AVAILABLE",
+            Self::InvalidDerivationIndex => "LIANA_INVALID_DERIVATION_INDEX",
+            Self::DerivationIndexLimit(..) => "LIANA_DERIVATION_INDEX_LIMIT",
+            Self::ReceiveIndexNotLower(..) => "LIANA_RECEIVE_INDEX_NOT_LOWER",
+            Self::UsedAddresses(..) => "LIANA_USED_ADDRESSES",
+            Self::AddressHistory(..) => "LIANA_ADDRESS_HISTORY",
+            Self::RbfError(..) => "LIANA_RBF",
+            Self::EmptyFilterList => "LIANA_EMPTY_FILTER_LIST",
+            Self::FeeEstimateUnavailable => "LIANA_FEE_ESTIMATE_UNAVAILABLE",
+            Self::DbMaintenance(..) => "LIANA_DB_MAINTENANCE",
+            Self::TooManyTxWaits => "LIANA_TOO_MANY_TX_WAITS",
+            Self::NothingToConsolidate => "LIANA_NOTHING_TO_CONSOLIDATE",
+            Self::DatabaseBusy(..) => "LIANA_DATABASE_BUSY",
         }
     }
 }
@@ -133,6 +279,12 @@ impl From<SpendCreationError> for CommandError {
     }
 }
 
+impl From<DatabaseBusy> for CommandError {
+    fn from(e: DatabaseBusy) -> Self {
+        CommandError::DatabaseBusy(e)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RbfErrorInfo {
     MissingFeerate,
@@ -158,18 +310,113 @@ impl fmt::Display for RbfErrorInfo {
     }
 }
 
+/// Why the Bitcoin backend refused to broadcast a transaction, as far as can be told from its
+/// error message. Both bitcoind and Electrum servers relay the reject reason of the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// The feerate is below the minimum to be relayed, or to replace another transaction.
+    FeeTooLow,
+    /// Some inputs are unknown or already spent.
+    MissingInputs,
+    /// It spends the same coins as a transaction in the mempool and can't replace it.
+    Conflict,
+    /// It is already in the mempool or in the block chain.
+    AlreadyKnown,
+    /// Its absolute or relative timelock isn't satisfied yet.
+    NonFinal,
+    /// It isn't standard, for instance it has a dust output.
+    NonStandard,
+    /// A signature or a script is invalid.
+    InvalidScript,
+    Other,
+}
+
+impl RejectReason {
+    pub fn from_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        let contains_any = |patterns: &[&str]| patterns.iter().any(|p| message.contains(p));
+        if contains_any(&[
+            "txn-already-in-mempool",
+            "txn-already-known",
+            "already in block chain",
+            "already in utxo set",
+        ]) {
+            Self::AlreadyKnown
+        } else if contains_any(&["fee not met", "insufficient fee", "fee too low"]) {
+            Self::FeeTooLow
+        } else if contains_any(&["missingorspent", "missing-inputs", "missing inputs"]) {
+            Self::MissingInputs
+        } else if contains_any(&[
+            "txn-mempool-conflict",
+            "replacement-disallowed",
+            "replacement-adds-unconfirmed",
+            "too many potential replacements",
+        ]) {
+            Self::Conflict
+        } else if contains_any(&["non-final", "non-bip68-final"]) {
+            Self::NonFinal
+        } else if contains_any(&["script-verify-flag", "bad-witness"]) {
+            Self::InvalidScript
+        } else if contains_any(&["dust", "tx-size", "scriptpubkey", "nonstandard"]) {
+            Self::NonStandard
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FeeTooLow => write!(f, "the fee is too low"),
+            Self::MissingInputs => write!(f, "some inputs are missing or already spent"),
+            Self::Conflict => write!(f, "it conflicts with a transaction in the mempool"),
+            Self::AlreadyKnown => write!(f, "it was already broadcast"),
+            Self::NonFinal => write!(f, "its timelock is not satisfied yet"),
+            Self::NonStandard => write!(f, "it is non-standard"),
+            Self::InvalidScript => write!(f, "a signature or a script is invalid"),
+            Self::Other => write!(f, "it was rejected"),
+        }
+    }
+}
+
+/// The refusal of the Bitcoin backend to broadcast a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastError {
+    pub reason: RejectReason,
+    /// The error message of the Bitcoin backend.
+    pub message: String,
+}
+
+impl BroadcastError {
+    pub fn new(message: String) -> Self {
+        Self {
+            reason: RejectReason::from_message(&message),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for BroadcastError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ('{}')", self.reason, self.message)
+    }
+}
+
 /// A wallet transaction getter which fetches the transaction from our database backend with a cache
 /// to avoid needless redundant calls. Note the cache holds an Option<> so we also avoid redundant
-/// calls when the txid isn't known by our database backend.
+/// calls when the txid isn't known by our database backend. It borrows the database connection of
+/// the command, for a command to never hold more than one connection at a time.
 struct DbTxGetter<'a> {
-    db: &'a sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+    db_conn: &'a mut dyn DatabaseConnection,
     cache: HashMap<bitcoin::Txid, Option<bitcoin::Transaction>>,
 }
 
 impl<'a> DbTxGetter<'a> {
-    pub fn new(db: &'a sync::Arc<sync::Mutex<dyn DatabaseInterface>>) -> Self {
+    pub fn new(db_conn: &'a mut dyn DatabaseConnection) -> Self {
         Self {
-            db,
+            db_conn,
             cache: HashMap::new(),
         }
     }
@@ -179,8 +426,7 @@ impl<'a> TxGetter for DbTxGetter<'a> {
     fn get_tx(&mut self, txid: &bitcoin::Txid) -> Option<bitcoin::Transaction> {
         if let hash_map::Entry::Vacant(entry) = self.cache.entry(*txid) {
             let tx = self
-                .db
-                .connection()
+                .db_conn
                 .list_wallet_transactions(&[*txid])
                 .pop()
                 .map(|(tx, _, _)| tx);
@@ -201,12 +447,46 @@ fn coin_to_candidate(
         amount: coin.amount,
         deriv_index: coin.derivation_index,
         is_change: coin.is_change,
+        block_height: coin.block_info.map(|b| b.height),
         must_select,
         sequence,
         ancestor_info,
     }
 }
 
+// Information about the change output at `change_index` in this PSBT, if it pays to one of our
+// addresses.
+fn change_output_info(
+    psbt: &Psbt,
+    change_index: Option<usize>,
+    change_info: Option<AddrInfo>,
+) -> Option<ChangeOutputInfo> {
+    let index = change_index?;
+    let derivation_index = change_info?.index;
+    Some(ChangeOutputInfo {
+        index,
+        amount: psbt.unsigned_tx.output[index].value,
+        derivation_index,
+    })
+}
+
+// The fee paid by this PSBT, whose inputs are all spending some of the given candidates.
+fn spend_fee(psbt: &Psbt, candidates: &[CandidateCoin]) -> bitcoin::Amount {
+    let inputs_value: bitcoin::Amount = psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .filter_map(|txin| {
+            candidates
+                .iter()
+                .find(|c| c.outpoint == txin.previous_output)
+                .map(|c| c.amount)
+        })
+        .sum();
+    let outputs_value: bitcoin::Amount = psbt.unsigned_tx.output.iter().map(|o| o.value).sum();
+    inputs_value - outputs_value
+}
+
 impl DaemonControl {
     // Get the derived descriptor for this coin
     fn derived_desc(&self, coin: &Coin) -> descriptors::DerivedSinglePathLianaDesc {
@@ -292,50 +572,204 @@ impl DaemonControl {
         }
     }
 
-    // Pass relevant values to the spend module function of same name.
+    // Pass relevant values to the spend module function of same name. The tip height is the one
+    // cached by the poller, unless it hasn't polled yet.
     fn anti_fee_sniping_locktime(&self) -> LockTime {
         let now = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .expect("time measured now cannot be before unix epoch");
         let tip_time = self.bitcoin.tip_time();
-        let tip_height: u32 = self
-            .bitcoin
-            .chain_tip()
-            .height
-            .try_into()
-            .expect("block height must fit in u32");
+        let tip_height = match self.latest_tip_height.load(atomic::Ordering::Relaxed) {
+            0 => self
+                .bitcoin
+                .chain_tip()
+                .height
+                .try_into()
+                .expect("block height must fit in u32"),
+            height => height,
+        };
         spend::anti_fee_sniping_locktime(now, tip_height, tip_time)
     }
+
+    // The lowest feerate of the transactions we create: the configured one, or the minimum relay
+    // feerate of the backend if it's higher, for our transactions to be relayed.
+    fn min_feerate(&self) -> u64 {
+        let min_feerate = self.config.min_feerate_sat_vb;
+        self.bitcoin
+            .min_relay_feerate()
+            .map(|relay_feerate| relay_feerate.max(min_feerate))
+            .unwrap_or(min_feerate)
+    }
 }
 
 impl DaemonControl {
     /// Get information about the current state of the daemon
-    pub fn get_info(&self) -> GetInfoResult {
-        let mut db_conn = self.db.connection();
+    pub fn get_info(&self) -> Result<GetInfoResult, CommandError> {
+        let mut db_conn = self.db.connection()?;
         let block_height = db_conn.chain_tip().map(|tip| tip.height).unwrap_or(0);
         let wallet = db_conn.wallet();
         let rescan_progress = wallet
             .rescan_timestamp
             .map(|_| self.bitcoin.rescan_progress().unwrap_or(1.0));
-        GetInfoResult {
+        let db_size = db_conn.size_info();
+        let mut warnings: Vec<GetInfoWarning> = self
+            .recovery_warnings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|w| GetInfoWarning::RecoveryThreshold {
+                blocks_before_expiry: w.threshold.blocks_before_expiry,
+                min_amount_sat: w.threshold.min_amount_sat,
+                amount: w.amount,
+                earliest_height: w.earliest_height,
+            })
+            .collect();
+        if needs_maintenance(&db_size) {
+            warnings.push(GetInfoWarning::DbMaintenance {
+                free_size: db_size.free_size(),
+            });
+        }
+        let unreachable_since = self
+            .backend_health
+            .as_ref()
+            .and_then(|health| health.unreachable_since());
+        if let Some(since) = unreachable_since {
+            warnings.push(GetInfoWarning::BackendUnreachable { since });
+        }
+        let next_index: u32 = wallet.receive_index.max(wallet.change_index).into();
+        let max_index = self.config.max_derivation_index;
+        if next_index > max_index / 2 {
+            warnings.push(GetInfoWarning::DerivationIndexHigh {
+                index: next_index.saturating_sub(1),
+                max_index,
+            });
+        }
+        // Don't wait for an unreachable backend, report the progress as of the last check instead.
+        let sync_progress = match (unreachable_since, self.sync_progress.last()) {
+            (Some(_), Some(last)) => last,
+            _ => self.bitcoin.sync_progress(),
+        };
+        Ok(GetInfoResult {
             version: VERSION.to_string(),
             network: self.config.bitcoin_config.network,
             block_height,
-            sync: self.bitcoin.sync_progress().rounded_up_progress(),
+            sync: sync_progress.rounded_up_progress(),
+            backend_blocks: sync_progress.blocks,
+            backend_headers: sync_progress.headers,
             descriptors: GetInfoDescriptors {
                 main: self.config.main_descriptor.clone(),
             },
             rescan_progress,
             timestamp: wallet.timestamp,
             last_poll_timestamp: wallet.last_poll_timestamp,
+            db_size: db_size.size(),
+            db_free_size: db_size.free_size(),
+            warnings,
+        })
+    }
+
+    /// Rebuild the database to reclaim the space left unused by deleted data, and refresh the
+    /// statistics used to plan queries. This is performed by the poller in between two polls.
+    pub fn db_maintenance(&self) -> Result<DbMaintenanceResult, CommandError> {
+        let (tx, rx) = mpsc::sync_channel(0);
+        self.poller_sender
+            .send(PollerMessage::DbMaintenance(tx))
+            .map_err(|e| CommandError::DbMaintenance(e.to_string()))?;
+        let (before, after) = rx
+            .recv()
+            .map_err(|e| CommandError::DbMaintenance(e.to_string()))?;
+        Ok(DbMaintenanceResult {
+            size_before: before.size(),
+            size_after: after.size(),
+        })
+    }
+
+    /// Retry to reach the Bitcoin backend right away, if the polls were spaced out because it
+    /// couldn't be reached. For instance after the user restarted it.
+    pub fn retry_backend(&self) {
+        if let Err(e) = self.poller_sender.send(PollerMessage::ResetBackoff) {
+            log::error!("Error requesting the poller to reset its backoff: {}", e);
+        }
+    }
+
+    /// Wait until the wallet transaction `txid` has at least `confirmations` confirmations, or
+    /// until `timeout` elapses. The transaction is checked again after each poll of the Bitcoin
+    /// backend. Returns its confirmations as of the last check, whether it timed out or not.
+    ///
+    /// The timeout is capped to `MAX_TX_WAIT_TIMEOUT`.
+    pub fn wait_for_tx(
+        &self,
+        txid: &bitcoin::Txid,
+        confirmations: u32,
+        timeout: Duration,
+    ) -> Result<WaitForTxResult, CommandError> {
+        if self.tx_waits.fetch_add(1, atomic::Ordering::SeqCst) >= MAX_CONCURRENT_TX_WAITS {
+            self.tx_waits.fetch_sub(1, atomic::Ordering::SeqCst);
+            return Err(CommandError::TooManyTxWaits);
+        }
+        // Release our slot however we return.
+        struct WaitSlot<'a>(&'a atomic::AtomicU32);
+        impl Drop for WaitSlot<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, atomic::Ordering::SeqCst);
+            }
+        }
+        let _slot = WaitSlot(&self.tx_waits);
+
+        let deadline = Instant::now() + timeout.min(MAX_TX_WAIT_TIMEOUT);
+        loop {
+            // Get the number of polls before checking the transaction so we don't miss a poll
+            // completed in between.
+            let polls = self.poll_signal.polls();
+            let res = self.tx_confirmations(txid)?;
+            let now = Instant::now();
+            // Don't hold up the shutdown of the daemon, no poll would complete anyways.
+            if res.confirmations >= confirmations
+                || now >= deadline
+                || self.poll_signal.is_stopped()
+            {
+                return Ok(res);
+            }
+            self.poll_signal.wait(polls, deadline - now);
         }
     }
 
+    fn tx_confirmations(&self, txid: &bitcoin::Txid) -> Result<WaitForTxResult, CommandError> {
+        let mut db_conn = self.db.connection()?;
+        let height = db_conn
+            .list_wallet_transactions(&[*txid])
+            .into_iter()
+            .next()
+            .and_then(|(_, height, _)| height);
+        let confirmations = match (height, db_conn.chain_tip()) {
+            (Some(height), Some(tip)) if tip.height >= height => (tip.height - height + 1) as u32,
+            _ => 0,
+        };
+        Ok(WaitForTxResult {
+            confirmations,
+            height,
+        })
+    }
+
+    /// Get feerate estimates for a few confirmation targets, aggregated from the Bitcoin backend
+    /// and the configured external sources.
+    pub fn get_fee_estimates(&self) -> Result<GetFeeEstimatesResult, CommandError> {
+        self.fee_oracle
+            .estimates(&self.bitcoin)
+            .map(|feerates| GetFeeEstimatesResult { feerates })
+            .ok_or(CommandError::FeeEstimateUnavailable)
+    }
+
     /// Get a new deposit address. This will always generate a new deposit address, regardless of
-    /// whether it was actually used.
-    pub fn get_new_address(&self) -> GetAddressResult {
-        let mut db_conn = self.db.connection();
+    /// whether it was actually used. Fails past the configured maximum derivation index.
+    pub fn get_new_address(&self) -> Result<GetAddressResult, CommandError> {
+        let mut db_conn = self.db.connection()?;
         let index = db_conn.receive_index();
+        if u32::from(index) > self.config.max_derivation_index {
+            return Err(CommandError::DerivationIndexLimit(
+                self.config.max_derivation_index,
+            ));
+        }
         let new_index = index
             .increment()
             .expect("Can't get into hardened territory");
@@ -346,7 +780,49 @@ impl DaemonControl {
             .receive_descriptor()
             .derive(index, &self.secp)
             .address(self.config.bitcoin_config.network);
-        GetAddressResult::new(address, index)
+        Ok(GetAddressResult::new(address, index))
+    }
+
+    /// Lower the derivation index for the next receive address back to `index`, to recover from
+    /// revealing too many addresses. None of the receive addresses from `index` may have been
+    /// used, neither according to the database nor to the Bitcoin backend.
+    pub fn lower_receive_index(&self, index: u32) -> Result<(), CommandError> {
+        let index = bip32::ChildNumber::from_normal_idx(index)
+            .map_err(|_| CommandError::InvalidDerivationIndex)?;
+        let mut db_conn = self.db.connection()?;
+        let receive_index = db_conn.receive_index();
+        if index >= receive_index {
+            return Err(CommandError::ReceiveIndexNotLower(receive_index.into()));
+        }
+
+        if let Some(used_index) = db_conn
+            .coins(&[], &[])
+            .into_values()
+            .filter(|coin| !coin.is_change && coin.derivation_index >= index)
+            .map(|coin| coin.derivation_index)
+            .min()
+        {
+            return Err(CommandError::UsedAddresses(used_index.into()));
+        }
+
+        let receive_desc = self.config.main_descriptor.receive_descriptor();
+        let addresses: Vec<_> = (u32::from(index)..receive_index.into())
+            .map(|i| {
+                receive_desc
+                    .derive(i.into(), &self.secp)
+                    .address(self.config.bitcoin_config.network)
+            })
+            .collect();
+        if self
+            .bitcoin
+            .addresses_have_history(&addresses)
+            .map_err(CommandError::AddressHistory)?
+        {
+            return Err(CommandError::UsedAddresses(index.into()));
+        }
+
+        db_conn.lower_receive_index(index);
+        Ok(())
     }
 
     /// list addresses
@@ -355,7 +831,7 @@ impl DaemonControl {
         start_index: Option<u32>,
         count: Option<u32>,
     ) -> Result<ListAddressesResult, CommandError> {
-        let mut db_conn = self.db.connection();
+        let mut db_conn = self.db.connection()?;
         let receive_index: u32 = db_conn.receive_index().into();
         let change_index: u32 = db_conn.change_index().into();
 
@@ -376,6 +852,14 @@ impl DaemonControl {
             receive_index.max(change_index)
         };
 
+        // Count the coins ever received on each address, to tell which ones were already used.
+        let mut coins_count: HashMap<(u32, bool), usize> = HashMap::new();
+        for coin in db_conn.coins(&[], &[]).into_values() {
+            *coins_count
+                .entry((coin.derivation_index.into(), coin.is_change))
+                .or_default() += 1;
+        }
+
         // Derive all receive and change addresses for the queried range.
         let addresses: Result<Vec<AddressInfo>, CommandError> = (start_index_u32..end_index)
             .map(|index| {
@@ -400,19 +884,87 @@ impl DaemonControl {
                     index,
                     receive,
                     change,
+                    receive_coins: coins_count.get(&(index, false)).copied().unwrap_or(0),
+                    change_coins: coins_count.get(&(index, true)).copied().unwrap_or(0),
                 })
             })
             .collect();
         Ok(ListAddressesResult::new(addresses?))
     }
 
+    /// Get the receive or change address at this derivation index, whether it was revealed or not.
+    pub fn derive_address(
+        &self,
+        index: u32,
+        change: bool,
+    ) -> Result<DerivedAddressResult, CommandError> {
+        let index = bip32::ChildNumber::from_normal_idx(index)
+            .map_err(|_| CommandError::InvalidDerivationIndex)?;
+        let desc = if change {
+            self.config.main_descriptor.change_descriptor()
+        } else {
+            self.config.main_descriptor.receive_descriptor()
+        };
+        let address = desc
+            .derive(index, &self.secp)
+            .address(self.config.bitcoin_config.network);
+        Ok(DerivedAddressResult {
+            address,
+            derivation_index: index,
+            change,
+        })
+    }
+
+    /// Get the derivation index of this address, and whether it is a change address. Addresses
+    /// unknown to the database are looked for up to `FIND_ADDRESS_LOOKAHEAD` indexes past the last
+    /// revealed one.
+    pub fn find_address(
+        &self,
+        address: bitcoin::Address<address::NetworkUnchecked>,
+    ) -> Result<Option<DerivedAddressResult>, CommandError> {
+        let address = self.validate_address(address)?;
+        let mut db_conn = self.db.connection()?;
+        if let Some((derivation_index, change)) = db_conn.derivation_index_by_address(&address) {
+            return Ok(Some(DerivedAddressResult {
+                address,
+                derivation_index,
+                change,
+            }));
+        }
+
+        let receive_index: u32 = db_conn.receive_index().into();
+        let change_index: u32 = db_conn.change_index().into();
+        let end_index = receive_index
+            .max(change_index)
+            .saturating_add(FIND_ADDRESS_LOOKAHEAD)
+            .min(1 << 31);
+        for index in 0..end_index {
+            for change in [false, true].iter() {
+                let derived = self.derive_address(index, *change)?;
+                if derived.address == address {
+                    return Ok(Some(derived));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     /// Get a list of all known coins, optionally by status and/or outpoint.
     pub fn list_coins(
         &self,
         statuses: &[CoinStatus],
         outpoints: &[bitcoin::OutPoint],
-    ) -> ListCoinsResult {
-        let mut db_conn = self.db.connection();
+    ) -> Result<ListCoinsResult, CommandError> {
+        let mut db_conn = self.db.connection()?;
+        let recovery_timelocks: Vec<u16> = self
+            .config
+            .main_descriptor
+            .policy()
+            .recovery_paths()
+            .keys()
+            .copied()
+            .collect();
+        let mut tags = db_conn.coin_tags();
         let coins: Vec<ListCoinsEntry> = db_conn
             .coins(statuses, outpoints)
             .into_values()
@@ -434,6 +986,17 @@ impl DaemonControl {
                     height: spend_block.map(|b| b.height),
                 });
                 let block_height = block_info.map(|b| b.height);
+                let recovery_heights = block_height
+                    .map(|height| {
+                        recovery_timelocks
+                            .iter()
+                            .map(|timelock| RecoveryHeight {
+                                timelock: *timelock,
+                                height: height + i32::from(*timelock),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
                 let address = self
                     .derived_desc(&coin)
                     .address(self.config.bitcoin_config.network);
@@ -447,18 +1010,34 @@ impl DaemonControl {
                     is_immature,
                     is_change,
                     is_from_self,
+                    recovery_heights,
+                    tag: tags.remove(&outpoint),
                 }
             })
             .collect();
-        ListCoinsResult { coins }
+        Ok(ListCoinsResult { coins })
     }
 
+    /// Create a transaction paying to the given destinations.
+    ///
+    /// If no `coins_outpoints` are given, coins are selected automatically using the
+    /// `coin_selection` algorithm or, if `None`, the one from our configuration. If
+    /// `avoid_mixing_tags` is set, the automatic selection only spends together coins with
+    /// the same privacy tag (untagged coins being spent together).
+    ///
+    /// The coins are spent through the primary path, unless `spend_path` is the timelock of a
+    /// recovery path. In this case only the confirmed coins for which this path is available at
+    /// the next block can be spent.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_spend(
         &self,
         destinations: &HashMap<bitcoin::Address<bitcoin::address::NetworkUnchecked>, u64>,
         coins_outpoints: &[bitcoin::OutPoint],
         feerate_vb: u64,
         change_address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>>,
+        coin_selection: Option<CoinSelectionAlgorithm>,
+        avoid_mixing_tags: bool,
+        spend_path: Option<u16>,
     ) -> Result<CreateSpendResult, CommandError> {
         let is_self_send = destinations.is_empty();
         // For self-send, the coins must be specified.
@@ -468,8 +1047,44 @@ impl DaemonControl {
         if feerate_vb < 1 {
             return Err(CommandError::InvalidFeerate(feerate_vb));
         }
-        let mut db_conn = self.db.connection();
-        let mut tx_getter = DbTxGetter::new(&self.db);
+        // The timelock of the recovery path along with the current height, if spending through
+        // one.
+        let recovery = spend_path
+            .map(|timelock| {
+                if self
+                    .config
+                    .main_descriptor
+                    .policy()
+                    .recovery_paths()
+                    .contains_key(&timelock)
+                {
+                    Ok((timelock, self.bitcoin.chain_tip().height))
+                } else {
+                    Err(CommandError::UnknownRecoveryPath(timelock))
+                }
+            })
+            .transpose()?;
+        let is_recoverable = |coin: &Coin| match recovery {
+            Some((timelock, current_height)) => coin
+                .block_info
+                .map(|b| current_height + 1 >= b.height + i32::from(timelock))
+                .unwrap_or(false),
+            None => true,
+        };
+        let sequence = recovery.map(|(timelock, _)| bitcoin::Sequence::from_height(timelock));
+        let requested_feerate_vb = feerate_vb;
+        let min_feerate_vb = self.min_feerate();
+        let feerate_vb = if feerate_vb < min_feerate_vb {
+            log::info!(
+                "Raising the feerate of the spend from {} to the minimum of {} sat/vb.",
+                feerate_vb,
+                min_feerate_vb
+            );
+            min_feerate_vb
+        } else {
+            feerate_vb
+        };
+        let mut db_conn = self.db.connection()?;
 
         // Prepare the destination addresses.
         let mut destinations_checked = Vec::with_capacity(destinations.len());
@@ -501,6 +1116,7 @@ impl DaemonControl {
             db_conn
                 .coins(&[CoinStatus::Unconfirmed, CoinStatus::Confirmed], &[])
                 .into_iter()
+                .filter(|(_, c)| is_recoverable(c))
                 .filter_map(|(op, c)| {
                     if c.block_info.is_some() {
                         Some((c, None)) // confirmed coins have no ancestor info
@@ -529,12 +1145,7 @@ impl DaemonControl {
                     }
                 })
                 .map(|(c, ancestor_info)| {
-                    coin_to_candidate(
-                        &c,
-                        /*must_select=*/ false,
-                        /*sequence=*/ None,
-                        ancestor_info,
-                    )
+                    coin_to_candidate(&c, /*must_select=*/ false, sequence, ancestor_info)
                 })
                 .collect()
         } else {
@@ -548,6 +1159,9 @@ impl DaemonControl {
                 if coin.is_immature {
                     return Err(CommandError::ImmatureCoinbase(*op));
                 }
+                if let Some((timelock, _)) = recovery.filter(|_| !is_recoverable(coin)) {
+                    return Err(CommandError::RecoveryPathNotAvailable(*op, timelock));
+                }
             }
             coins
                 .into_iter()
@@ -573,58 +1187,232 @@ impl DaemonControl {
                     } else {
                         None
                     };
-                    coin_to_candidate(
-                        &c,
-                        /*must_select=*/ true,
-                        /*sequence=*/ None,
-                        ancestor_info,
-                    )
+                    coin_to_candidate(&c, /*must_select=*/ true, sequence, ancestor_info)
                 })
                 .collect()
         };
 
-        // Create the PSBT. If there was no error in doing so make sure to update our next
-        // derivation index in case any address in the transaction outputs was ours and from the
-        // future.
+        // When selecting coins automatically without mixing tags, the selection is run
+        // separately on the coins of each tag. Otherwise there is a single set of candidates.
+        let tags = db_conn.coin_tags();
+        let candidate_groups: Vec<(Option<&String>, Vec<CandidateCoin>)> =
+            if coins_outpoints.is_empty() && avoid_mixing_tags && !candidate_coins.is_empty() {
+                let mut groups: BTreeMap<Option<&String>, Vec<CandidateCoin>> = BTreeMap::new();
+                for candidate in &candidate_coins {
+                    groups
+                        .entry(tags.get(&candidate.outpoint))
+                        .or_default()
+                        .push(*candidate);
+                }
+                groups.into_iter().collect()
+            } else {
+                vec![(None, candidate_coins.clone())]
+            };
+
+        // Create the PSBT. Among the groups of candidates for which it succeeded, keep the
+        // transaction paying the lowest fee. If there was no error in doing so make sure to
+        // update our next derivation index in case any address in the transaction outputs was
+        // ours and from the future.
         let change_info = change_address.info;
         let locktime = self.anti_fee_sniping_locktime();
+        let mut tx_getter = DbTxGetter::new(&mut *db_conn);
+        let mut best: Option<(bitcoin::Amount, CreateSpendRes)> = None;
+        let mut missing: Vec<(Option<String>, u64)> = Vec::with_capacity(candidate_groups.len());
+        for (tag, candidates) in &candidate_groups {
+            match create_spend(
+                &self.config.main_descriptor,
+                &self.secp,
+                &mut tx_getter,
+                &destinations_checked,
+                candidates,
+                SpendTxFees::Regular(feerate_vb),
+                change_address.clone(),
+                locktime,
+                coin_selection.unwrap_or(self.config.coin_selection),
+            ) {
+                Ok(res) => {
+                    let fee = spend_fee(&res.psbt, candidates);
+                    if best
+                        .as_ref()
+                        .map(|(best_fee, _)| fee < *best_fee)
+                        .unwrap_or(true)
+                    {
+                        best = Some((fee, res));
+                    }
+                }
+                Err(SpendCreationError::CoinSelection(e)) => {
+                    missing.push((tag.cloned(), e.missing));
+                }
+                Err(e) => {
+                    return Err(e.into());
+                }
+            }
+        }
         let CreateSpendRes {
             psbt,
-            has_change,
+            change_index,
             warnings,
-        } = match create_spend(
-            &self.config.main_descriptor,
-            &self.secp,
-            &mut tx_getter,
-            &destinations_checked,
-            &candidate_coins,
-            SpendTxFees::Regular(feerate_vb),
-            change_address,
-            locktime,
-        ) {
-            Ok(res) => res,
-            Err(SpendCreationError::CoinSelection(e)) => {
-                return Ok(CreateSpendResult::InsufficientFunds { missing: e.missing });
+        } = match best {
+            Some((_, res)) => res,
+            None if missing.len() > 1 => {
+                // No tag can fund the transaction on its own. Tell the caller whether it is
+                // because of not mixing tags or because there isn't enough funds altogether.
+                return match create_spend(
+                    &self.config.main_descriptor,
+                    &self.secp,
+                    &mut tx_getter,
+                    &destinations_checked,
+                    &candidate_coins,
+                    SpendTxFees::Regular(feerate_vb),
+                    change_address,
+                    locktime,
+                    coin_selection.unwrap_or(self.config.coin_selection),
+                ) {
+                    Ok(_) => Err(CommandError::InsufficientFundsPerTag(missing)),
+                    Err(SpendCreationError::CoinSelection(e)) => {
+                        Ok(CreateSpendResult::InsufficientFunds { missing: e.missing })
+                    }
+                    Err(e) => Err(e.into()),
+                };
             }
-            Err(e) => {
-                return Err(e.into());
+            None => {
+                return Ok(CreateSpendResult::InsufficientFunds {
+                    missing: missing
+                        .first()
+                        .expect("There is at least one group of candidates")
+                        .1,
+                });
             }
         };
+        let mut warnings: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+        if feerate_vb > requested_feerate_vb {
+            warnings.push(format!(
+                "Feerate raised from {} to the minimum of {} sat/vb.",
+                requested_feerate_vb, feerate_vb
+            ));
+        }
+        let mut spent_tags: Vec<&String> = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .filter_map(|txin| tags.get(&txin.previous_output))
+            .collect();
+        spent_tags.sort();
+        spent_tags.dedup();
+        let spends_untagged = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .any(|txin| !tags.contains_key(&txin.previous_output));
+        if spent_tags.len() > 1 || (!spent_tags.is_empty() && spends_untagged) {
+            warnings.push(format!(
+                "Coins with different privacy tags are spent together: {}.",
+                spent_tags
+                    .iter()
+                    .map(|t| t.as_str())
+                    .chain(spends_untagged.then_some("(untagged)"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
         for (addr, _) in destinations_checked {
             self.maybe_increase_next_deriv_index(&mut db_conn, &addr.info);
         }
-        if has_change {
+        if change_index.is_some() {
             self.maybe_increase_next_deriv_index(&mut db_conn, &change_info);
         }
 
+        let change_output = change_output_info(&psbt, change_index, change_info);
         Ok(CreateSpendResult::Success {
             psbt,
-            warnings: warnings.iter().map(|w| w.to_string()).collect(),
+            change_output,
+            warnings,
+        })
+    }
+
+    /// Create a transaction spending the given unconfirmed coins back to our wallet (CPFP). Its
+    /// fee is set so that, together with the unconfirmed ancestors of these coins, it forms a
+    /// package with the given feerate.
+    pub fn create_cpfp(
+        &self,
+        coins_outpoints: &[bitcoin::OutPoint],
+        feerate_vb: u64,
+    ) -> Result<CreateCpfpResult, CommandError> {
+        if coins_outpoints.is_empty() {
+            return Err(CommandError::NoOutpointForSelfSend);
+        }
+        let coins = self.db.connection()?.coins(&[], coins_outpoints);
+        let mut parent_txids = HashSet::with_capacity(coins_outpoints.len());
+        for op in coins_outpoints {
+            let coin = coins.get(op).ok_or(CommandError::UnknownOutpoint(*op))?;
+            if coin.block_info.is_some() {
+                return Err(CommandError::ConfirmedCoin(*op));
+            }
+            parent_txids.insert(op.txid);
+        }
+
+        // The ancestors of the coins as accounted for by the coin selection. Parents which
+        // aren't in the mempool (anymore) have none.
+        let (ancestors_vsize, ancestors_fee) = parent_txids
+            .iter()
+            .filter_map(|txid| self.bitcoin.mempool_entry(txid))
+            .fold((0, 0), |(vsize, fee), entry| {
+                (
+                    vsize + entry.ancestor_vsize,
+                    fee + entry.fees.ancestor.to_sat(),
+                )
+            });
+        let spend = self.create_spend(
+            &HashMap::new(),
+            coins_outpoints,
+            feerate_vb,
+            None,
+            None,
+            false,
+            None,
+        )?;
+        Ok(CreateCpfpResult {
+            ancestors_vsize,
+            ancestors_fee,
+            spend,
         })
     }
 
+    /// Create a transaction spending up to `max_inputs` of our confirmed coins to a new change
+    /// address of the wallet. The oldest coins are selected first, and the smallest ones among
+    /// coins of the same age, so that the coins closest to the expiration of the timelocked
+    /// recovery path are refreshed.
+    pub fn create_consolidation(
+        &self,
+        feerate_vb: u64,
+        max_inputs: usize,
+    ) -> Result<CreateSpendResult, CommandError> {
+        let mut coins: Vec<Coin> = self
+            .db
+            .connection()?
+            .coins(&[CoinStatus::Confirmed], &[])
+            .into_values()
+            .filter(|c| !c.is_immature)
+            .collect();
+        coins.sort_by_key(|c| (c.block_info.map(|b| b.height), c.amount));
+        let outpoints: Vec<bitcoin::OutPoint> =
+            coins.iter().take(max_inputs).map(|c| c.outpoint).collect();
+        if outpoints.len() < 2 {
+            return Err(CommandError::NothingToConsolidate);
+        }
+        self.create_spend(
+            &HashMap::new(),
+            &outpoints,
+            feerate_vb,
+            None,
+            None,
+            false,
+            None,
+        )
+    }
+
     pub fn update_spend(&self, mut psbt: Psbt) -> Result<(), CommandError> {
-        let mut db_conn = self.db.connection();
+        let mut db_conn = self.db.connection()?;
         let tx = &psbt.unsigned_tx;
 
         // If the transaction already exists in DB, merge the signatures for each input on a best
@@ -681,16 +1469,44 @@ impl DaemonControl {
         Ok(())
     }
 
-    pub fn update_labels(&self, items: &HashMap<LabelItem, Option<String>>) {
-        let mut db_conn = self.db.connection();
+    pub fn update_labels(
+        &self,
+        items: &HashMap<LabelItem, Option<String>>,
+    ) -> Result<(), CommandError> {
+        let mut db_conn = self.db.connection()?;
         db_conn.update_labels(items);
+        Ok(())
+    }
+
+    /// Set, for a set of outpoints (as key), their privacy tag (as value). A `None` value
+    /// removes the tag.
+    pub fn set_coin_tags(
+        &self,
+        tags: &HashMap<bitcoin::OutPoint, Option<String>>,
+    ) -> Result<(), CommandError> {
+        let mut db_conn = self.db.connection()?;
+        db_conn.update_coin_tags(tags);
+        Ok(())
     }
 
-    pub fn get_labels(&self, items: &HashSet<LabelItem>) -> GetLabelsResult {
-        let mut db_conn = self.db.connection();
-        GetLabelsResult {
+    pub fn get_labels(&self, items: &HashSet<LabelItem>) -> Result<GetLabelsResult, CommandError> {
+        let mut db_conn = self.db.connection()?;
+        Ok(GetLabelsResult {
             labels: db_conn.labels(items),
-        }
+        })
+    }
+
+    /// Get the labels of the items of the given kind, if any, among the given ones. All the
+    /// labels are returned if no kind nor item is given.
+    pub fn list_labels(
+        &self,
+        kind: Option<LabelKind>,
+        items: &HashSet<LabelItem>,
+    ) -> Result<GetLabelsResult, CommandError> {
+        let mut db_conn = self.db.connection()?;
+        Ok(GetLabelsResult {
+            labels: db_conn.list_labels(kind, items),
+        })
     }
 
     pub fn list_spend(
@@ -703,32 +1519,103 @@ impl DaemonControl {
             }
         }
 
-        let mut db_conn = self.db.connection();
+        let mut db_conn = self.db.connection()?;
         let spend_psbts = db_conn.list_spend();
 
         let txids_set: Option<HashSet<_>> = txids.as_ref().map(|list| list.iter().collect());
-        let spend_txs = spend_psbts
+        let spend_psbts: Vec<_> = spend_psbts
             .into_iter()
-            .filter_map(|(psbt, updated_at)| {
-                if let Some(set) = &txids_set {
-                    if !set.contains(&psbt.unsigned_tx.txid()) {
-                        return None;
-                    }
-                }
-                Some(ListSpendEntry { psbt, updated_at })
+            .filter(|(psbt, ..)| {
+                txids_set
+                    .as_ref()
+                    .map(|set| set.contains(&psbt.unsigned_tx.txid()))
+                    .unwrap_or(true)
             })
             .collect();
-        Ok(ListSpendResult { spend_txs })
-    }
-
-    pub fn delete_spend(&self, txid: &bitcoin::Txid) {
-        let mut db_conn = self.db.connection();
-        db_conn.delete_spend(txid);
-    }
+
+        // Spends broadcast before we started recording it have no broadcast time. Fall back to
+        // the time at which they were confirmed, if they were.
+        let outpoints: Vec<_> = spend_psbts
+            .iter()
+            .filter(|(_, _, broadcast_time)| broadcast_time.is_none())
+            .flat_map(|(psbt, ..)| {
+                psbt.unsigned_tx
+                    .input
+                    .iter()
+                    .map(|txin| txin.previous_output)
+            })
+            .collect();
+        let coins = if outpoints.is_empty() {
+            HashMap::new()
+        } else {
+            db_conn.coins_by_outpoints(&outpoints)
+        };
+
+        let spend_txs = spend_psbts
+            .into_iter()
+            .map(|(psbt, updated_at, broadcast_time)| {
+                let txid = psbt.unsigned_tx.txid();
+                let broadcast_time = broadcast_time.or_else(|| {
+                    psbt.unsigned_tx.input.iter().find_map(|txin| {
+                        coins
+                            .get(&txin.previous_output)
+                            .filter(|coin| coin.spend_txid == Some(txid))
+                            .and_then(|coin| coin.spend_block.map(|block| block.time))
+                    })
+                });
+                ListSpendEntry {
+                    psbt,
+                    updated_at,
+                    broadcast_time,
+                }
+            })
+            .collect();
+        Ok(ListSpendResult { spend_txs })
+    }
+
+    pub fn delete_spend(&self, txid: &bitcoin::Txid) -> Result<(), CommandError> {
+        let mut db_conn = self.db.connection()?;
+        db_conn.delete_spend(txid);
+        Ok(())
+    }
+
+    /// Save a PSBT to be completed later, for instance once more signatures were collected.
+    pub fn save_psbt(&self, psbt: &Psbt, label: &str) -> Result<SavePsbtResult, CommandError> {
+        let mut db_conn = self.db.connection()?;
+        let id = db_conn.save_psbt(psbt, label);
+        Ok(SavePsbtResult { id })
+    }
+
+    pub fn list_saved_psbts(&self) -> Result<ListSavedPsbtsResult, CommandError> {
+        let mut db_conn = self.db.connection()?;
+        let psbts = db_conn
+            .list_saved_psbts()
+            .into_iter()
+            .map(SavedPsbtEntry::from)
+            .collect();
+        Ok(ListSavedPsbtsResult { psbts })
+    }
+
+    pub fn get_saved_psbt(&self, id: i64) -> Result<SavedPsbtEntry, CommandError> {
+        let mut db_conn = self.db.connection()?;
+        db_conn
+            .get_saved_psbt(id)
+            .map(SavedPsbtEntry::from)
+            .ok_or(CommandError::UnknownSavedPsbt(id))
+    }
+
+    pub fn delete_saved_psbt(&self, id: i64) -> Result<(), CommandError> {
+        let mut db_conn = self.db.connection()?;
+        if db_conn.delete_saved_psbt(id) {
+            Ok(())
+        } else {
+            Err(CommandError::UnknownSavedPsbt(id))
+        }
+    }
 
     /// Finalize and broadcast this stored Spend transaction.
     pub fn broadcast_spend(&self, txid: &bitcoin::Txid) -> Result<(), CommandError> {
-        let mut db_conn = self.db.connection();
+        let mut db_conn = self.db.connection()?;
 
         // First, try to finalize the spending transaction with the elements contained
         // in the PSBT.
@@ -750,7 +1637,8 @@ impl DaemonControl {
         let final_tx = spend_psbt.extract_tx_unchecked_fee_rate();
         self.bitcoin
             .broadcast_tx(&final_tx)
-            .map_err(CommandError::TxBroadcast)?;
+            .map_err(|e| CommandError::TxBroadcast(BroadcastError::new(e)))?;
+        db_conn.set_spend_broadcast(txid);
 
         // Finally, update our state with the changes from this transaction.
         let (tx, rx) = mpsc::sync_channel(0);
@@ -764,6 +1652,25 @@ impl DaemonControl {
         Ok(())
     }
 
+    /// Get the fee information of an unconfirmed transaction from the mempool of the Bitcoin
+    /// backend, for instance to tell whether a broadcast Spend needs a fee bump. Returns `None`
+    /// if the transaction is not in the mempool, because it was confirmed or dropped.
+    pub fn pending_spend_fee_info(&self, txid: &bitcoin::Txid) -> Option<PendingSpendFeeInfo> {
+        let entry = self.bitcoin.mempool_entry(txid)?;
+        let feerate_vb = entry.fees.base.to_sat() / entry.vsize;
+        let ancestor_feerate_vb = entry.fees.ancestor.to_sat() / entry.ancestor_vsize;
+        Some(PendingSpendFeeInfo {
+            vsize: entry.vsize,
+            fee: entry.fees.base.to_sat(),
+            // Miners consider a transaction along with its unconfirmed ancestors, whose lower
+            // feerate drags it down.
+            effective_feerate_vb: std::cmp::min(feerate_vb, ancestor_feerate_vb),
+            ancestor_vsize: entry.ancestor_vsize,
+            ancestor_fee: entry.fees.ancestor.to_sat(),
+            descendant_fee: entry.fees.descendant.to_sat(),
+        })
+    }
+
     /// Create PSBT to replace the given transaction using RBF.
     ///
     /// `txid` must point to a PSBT in our database.
@@ -791,8 +1698,7 @@ impl DaemonControl {
         is_cancel: bool,
         feerate_vb: Option<u64>,
     ) -> Result<CreateSpendResult, CommandError> {
-        let mut db_conn = self.db.connection();
-        let mut tx_getter = DbTxGetter::new(&self.db);
+        let mut db_conn = self.db.connection()?;
 
         if is_cancel && feerate_vb.is_some() {
             return Err(CommandError::RbfError(RbfErrorInfo::SuperfluousFeerate));
@@ -963,6 +1869,7 @@ impl DaemonControl {
         // RBF rule 4.
         let replaced_fee = descendant_fees.to_sat();
         let locktime = self.anti_fee_sniping_locktime();
+        let mut tx_getter = DbTxGetter::new(&mut *db_conn);
         // This loop can have up to 2 iterations in the case of cancel and otherwise only 1.
         loop {
             match create_spend(
@@ -974,10 +1881,11 @@ impl DaemonControl {
                 SpendTxFees::Rbf(feerate_vb, replaced_fee),
                 change_address.clone(),
                 locktime,
+                self.config.coin_selection,
             ) {
                 Ok(CreateSpendRes {
                     psbt,
-                    has_change,
+                    change_index,
                     warnings,
                 }) => {
                     // In case of success, make sure to update our next derivation index if any address
@@ -985,12 +1893,15 @@ impl DaemonControl {
                     for (addr, _) in destinations {
                         self.maybe_increase_next_deriv_index(&mut db_conn, &addr.info);
                     }
-                    if has_change {
+                    if change_index.is_some() {
                         self.maybe_increase_next_deriv_index(&mut db_conn, &change_address.info);
                     }
 
+                    let change_output =
+                        change_output_info(&psbt, change_index, change_address.info);
                     return Ok(CreateSpendResult::Success {
                         psbt,
+                        change_output,
                         warnings: warnings.iter().map(|w| w.to_string()).collect(),
                     });
                 }
@@ -1019,7 +1930,7 @@ impl DaemonControl {
     /// the given date and the current tip.
     /// The date must be after the genesis block time and before the current tip blocktime.
     pub fn start_rescan(&mut self, timestamp: u32) -> Result<(), CommandError> {
-        let mut db_conn = self.db.connection();
+        let mut db_conn = self.db.connection()?;
         let genesis_timestamp = self.bitcoin.genesis_block_timestamp();
 
         let future_timestamp = self
@@ -1051,23 +1962,25 @@ impl DaemonControl {
         start: u32,
         end: u32,
         limit: u64,
-    ) -> ListTransactionsResult {
-        let mut db_conn = self.db.connection();
+    ) -> Result<ListTransactionsResult, CommandError> {
         // Note the result could in principle be retrieved in a single database query.
-        let txids = db_conn.list_txids(start, end, limit);
+        let txids = self.db.connection()?.list_txids(start, end, limit);
         self.list_transactions(&txids)
     }
 
     /// list_transactions retrieves the transactions with the given txids.
-    pub fn list_transactions(&self, txids: &[bitcoin::Txid]) -> ListTransactionsResult {
+    pub fn list_transactions(
+        &self,
+        txids: &[bitcoin::Txid],
+    ) -> Result<ListTransactionsResult, CommandError> {
         let transactions = self
             .db
-            .connection()
+            .connection()?
             .list_wallet_transactions(txids)
             .into_iter()
             .map(|(tx, height, time)| TransactionInfo { tx, height, time })
             .collect();
-        ListTransactionsResult { transactions }
+        Ok(ListTransactionsResult { transactions })
     }
 
     /// Create a transaction that sweeps all coins for which a timelocked recovery path is
@@ -1086,8 +1999,7 @@ impl DaemonControl {
         if feerate_vb < 1 {
             return Err(CommandError::InvalidFeerate(feerate_vb));
         }
-        let mut tx_getter = DbTxGetter::new(&self.db);
-        let mut db_conn = self.db.connection();
+        let mut db_conn = self.db.connection()?;
         let sweep_addr = self.spend_addr(&mut db_conn, self.validate_address(address)?);
 
         // Query the coins that we can spend through the specified recovery path (if no recovery
@@ -1095,6 +2007,16 @@ impl DaemonControl {
         let current_height = self.bitcoin.chain_tip().height;
         let timelock =
             timelock.unwrap_or_else(|| self.config.main_descriptor.first_timelock_value());
+        // The coins would be swept with an nSequence which doesn't unlock any spending path.
+        if !self
+            .config
+            .main_descriptor
+            .policy()
+            .recovery_paths()
+            .contains_key(&timelock)
+        {
+            return Err(CommandError::UnknownRecoveryPath(timelock));
+        }
         let height_delta: i32 = timelock.into();
         let sweepable_coins: Vec<_> = db_conn
             .coins(&[CoinStatus::Confirmed], &[])
@@ -1122,8 +2044,9 @@ impl DaemonControl {
 
         let sweep_addr_info = sweep_addr.info;
         let locktime = self.anti_fee_sniping_locktime();
+        let mut tx_getter = DbTxGetter::new(&mut *db_conn);
         let CreateSpendRes {
-            psbt, has_change, ..
+            psbt, change_index, ..
         } = create_spend(
             &self.config.main_descriptor,
             &self.secp,
@@ -1133,8 +2056,9 @@ impl DaemonControl {
             SpendTxFees::Regular(feerate_vb),
             sweep_addr,
             locktime,
+            self.config.coin_selection,
         )?;
-        if has_change {
+        if change_index.is_some() {
             self.maybe_increase_next_deriv_index(&mut db_conn, &sweep_addr_info);
         }
 
@@ -1154,6 +2078,13 @@ pub struct GetInfoResult {
     pub network: bitcoin::Network,
     pub block_height: i32,
     pub sync: f64,
+    /// Number of blocks validated by the Bitcoin backend.
+    #[serde(default)]
+    pub backend_blocks: u64,
+    /// Number of headers of the best chain known to the Bitcoin backend, that is an estimate of
+    /// the network tip.
+    #[serde(default)]
+    pub backend_headers: u64,
     pub descriptors: GetInfoDescriptors,
     /// The progress as a percentage (between 0 and 1) of an ongoing rescan if there is any
     pub rescan_progress: Option<f64>,
@@ -1161,6 +2092,74 @@ pub struct GetInfoResult {
     pub timestamp: u32,
     /// Timestamp of last poll, if any.
     pub last_poll_timestamp: Option<u32>,
+    /// Size of the database in bytes.
+    #[serde(default)]
+    pub db_size: u64,
+    /// Size in bytes of the unused part of the database.
+    #[serde(default)]
+    pub db_free_size: u64,
+    /// Conditions which require the attention of the user.
+    #[serde(default)]
+    pub warnings: Vec<GetInfoWarning>,
+}
+
+/// A condition requiring the attention of the user, as reported by `getinfo`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GetInfoWarning {
+    /// One of the configured recovery thresholds is reached.
+    RecoveryThreshold {
+        blocks_before_expiry: u32,
+        min_amount_sat: u64,
+        /// Total value of the coins spendable through the recovery path within
+        /// `blocks_before_expiry` blocks.
+        #[serde(
+            serialize_with = "ser_amount",
+            deserialize_with = "deser_amount_from_sats"
+        )]
+        amount: bitcoin::Amount,
+        /// The earliest height at which one of these coins is spendable through the recovery path.
+        earliest_height: i32,
+    },
+    /// A large part of the database is unused and could be reclaimed with `dbmaintenance`.
+    DbMaintenance {
+        /// The size in bytes of the unused part of the database.
+        free_size: u64,
+    },
+    /// The Bitcoin backend can't be reached. Requests to it are being retried.
+    BackendUnreachable {
+        /// Timestamp of the first failed request.
+        since: u32,
+    },
+    /// More than half of the addresses up to the maximum derivation index were revealed.
+    DerivationIndexHigh {
+        /// The highest derivation index revealed.
+        index: u32,
+        /// The configured maximum derivation index.
+        max_index: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DbMaintenanceResult {
+    /// Size of the database in bytes before the maintenance.
+    pub size_before: u64,
+    /// Size of the database in bytes after the maintenance.
+    pub size_after: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WaitForTxResult {
+    /// Number of confirmations of the transaction, 0 if unconfirmed or unknown.
+    pub confirmations: u32,
+    /// Height of the block the transaction was confirmed in, if any.
+    pub height: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GetFeeEstimatesResult {
+    /// Feerates in sat/vb, by confirmation target in blocks.
+    pub feerates: BTreeMap<u16, u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1179,21 +2178,36 @@ impl GetAddressResult {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DerivedAddressResult {
+    #[serde(deserialize_with = "deser_addr_assume_checked")]
+    pub address: bitcoin::Address,
+    pub derivation_index: bip32::ChildNumber,
+    /// Whether this is a change address.
+    pub change: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetLabelsResult {
     pub labels: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AddressInfo {
-    index: u32,
-    receive: bitcoin::Address,
-    change: bitcoin::Address,
+    pub index: u32,
+    #[serde(deserialize_with = "deser_addr_assume_checked")]
+    pub receive: bitcoin::Address,
+    #[serde(deserialize_with = "deser_addr_assume_checked")]
+    pub change: bitcoin::Address,
+    /// Number of coins ever received on the receive address.
+    pub receive_coins: usize,
+    /// Number of coins ever received on the change address.
+    pub change_coins: usize,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ListAddressesResult {
-    addresses: Vec<AddressInfo>,
+    pub addresses: Vec<AddressInfo>,
 }
 
 impl ListAddressesResult {
@@ -1235,6 +2249,20 @@ pub struct ListCoinsEntry {
     /// this same wallet. If the coin is unconfirmed, it also means that all its
     /// unconfirmed ancestors, if any, are also from self.
     pub is_from_self: bool,
+    /// For a confirmed coin, the height at which each recovery path becomes available.
+    #[serde(default)]
+    pub recovery_heights: Vec<RecoveryHeight>,
+    /// The privacy tag of this coin, if any. Coins with different tags should not be
+    /// spent together.
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+/// The height at which the recovery path with the given timelock becomes available for a coin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryHeight {
+    pub timelock: u16,
+    pub height: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1242,12 +2270,28 @@ pub struct ListCoinsResult {
     pub coins: Vec<ListCoinsEntry>,
 }
 
+/// The change output of a created spend, paying back to one of our addresses.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChangeOutputInfo {
+    /// Index of the change output in the transaction.
+    pub index: usize,
+    #[serde(
+        serialize_with = "ser_amount",
+        deserialize_with = "deser_amount_from_sats"
+    )]
+    pub amount: bitcoin::Amount,
+    /// Derivation index of the change address.
+    pub derivation_index: bip32::ChildNumber,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum CreateSpendResult {
     Success {
         #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_fromstr")]
         psbt: Psbt,
+        /// The change output, if the transaction has one paying to our wallet.
+        change_output: Option<ChangeOutputInfo>,
         warnings: Vec<String>,
     },
     InsufficientFunds {
@@ -1255,11 +2299,41 @@ pub enum CreateSpendResult {
     },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CreateCpfpResult {
+    /// Total virtual size of the unconfirmed ancestors of the coins spent, as reported by the
+    /// mempool.
+    pub ancestors_vsize: u64,
+    /// Total fee paid by these ancestors, in sats.
+    pub ancestors_fee: u64,
+    #[serde(flatten)]
+    pub spend: CreateSpendResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PendingSpendFeeInfo {
+    /// Virtual size of the transaction.
+    pub vsize: u64,
+    /// Fee paid by the transaction, in sats.
+    pub fee: u64,
+    /// Feerate of the transaction in sat/vb, accounting for its unconfirmed ancestors.
+    pub effective_feerate_vb: u64,
+    /// Total virtual size of the transaction and its unconfirmed ancestors.
+    pub ancestor_vsize: u64,
+    /// Total fee paid by the transaction and its unconfirmed ancestors, in sats.
+    pub ancestor_fee: u64,
+    /// Total fee paid by the transaction and its unconfirmed descendants, in sats.
+    pub descendant_fee: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListSpendEntry {
     #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_fromstr")]
     pub psbt: Psbt,
     pub updated_at: Option<u32>,
+    /// When the transaction was broadcast. For those broadcast by older versions, when it was
+    /// confirmed, if it was.
+    pub broadcast_time: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1267,6 +2341,36 @@ pub struct ListSpendResult {
     pub spend_txs: Vec<ListSpendEntry>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavePsbtResult {
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPsbtEntry {
+    pub id: i64,
+    pub created_at: u32,
+    #[serde(serialize_with = "ser_to_string", deserialize_with = "deser_fromstr")]
+    pub psbt: Psbt,
+    pub label: String,
+}
+
+impl From<SavedPsbt> for SavedPsbtEntry {
+    fn from(saved: SavedPsbt) -> Self {
+        Self {
+            id: saved.id,
+            created_at: saved.created_at,
+            psbt: saved.psbt,
+            label: saved.label,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListSavedPsbtsResult {
+    pub psbts: Vec<SavedPsbtEntry>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListTransactionsResult {
     pub transactions: Vec<TransactionInfo>,
@@ -1286,6 +2390,12 @@ pub struct CreateRecoveryResult {
     pub psbt: Psbt,
 }
 
+/// Whether enough of the database is unused for a maintenance to be worth it.
+fn needs_maintenance(size_info: &DbSizeInfo) -> bool {
+    let free_size = size_info.free_size();
+    free_size >= DB_MAINTENANCE_MIN_FREE_SIZE && free_size >= size_info.size() / 4
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1300,11 +2410,127 @@ mod tests {
     };
     use std::{collections::BTreeMap, str::FromStr};
 
+    // The change output is inserted at a random position. Get the output paying to the given
+    // destination and the change output of a transaction with exactly these two outputs.
+    fn dest_and_change<'a>(
+        tx: &'a Transaction,
+        dest_spk: &bitcoin::Script,
+    ) -> (&'a TxOut, &'a TxOut) {
+        assert_eq!(tx.output.len(), 2);
+        let (dest, change): (Vec<_>, Vec<_>) = tx
+            .output
+            .iter()
+            .partition(|txo| txo.script_pubkey.as_script() == dest_spk);
+        assert_eq!(dest.len(), 1);
+        (dest[0], change[0])
+    }
+
+    #[test]
+    fn broadcast_reject_reason() {
+        for (message, reason) in [
+            (
+                "JSON-RPC error: RPC error response: RpcError { code: -26, message: \"min relay fee not met, 110 < 141\", data: None }",
+                RejectReason::FeeTooLow,
+            ),
+            ("mempool min fee not met, 141 < 1000", RejectReason::FeeTooLow),
+            ("insufficient fee, rejecting replacement", RejectReason::FeeTooLow),
+            ("bad-txns-inputs-missingorspent", RejectReason::MissingInputs),
+            ("txn-mempool-conflict", RejectReason::Conflict),
+            ("txn-already-in-mempool", RejectReason::AlreadyKnown),
+            ("Transaction outputs already in utxo set", RejectReason::AlreadyKnown),
+            ("non-BIP68-final", RejectReason::NonFinal),
+            (
+                "the transaction was rejected by network rules.\n\nmandatory-script-verify-flag-failed (Signature must be zero for failed CHECK(MULTI)SIG operation)",
+                RejectReason::InvalidScript,
+            ),
+            ("dust", RejectReason::NonStandard),
+            ("Connection reset by peer", RejectReason::Other),
+        ] {
+            assert_eq!(RejectReason::from_message(message), reason, "{}", message);
+        }
+    }
+
     #[test]
     fn getinfo() {
         let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
         // We can query getinfo
-        ms.control().get_info();
+        ms.control().get_info().unwrap();
+        ms.shutdown();
+    }
+
+    #[test]
+    fn db_maintenance() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let res = control.db_maintenance().unwrap();
+        assert_eq!(res.size_before, res.size_after);
+        assert!(control
+            .get_info()
+            .unwrap()
+            .warnings
+            .iter()
+            .all(|w| !matches!(w, GetInfoWarning::DbMaintenance { .. })));
+        ms.shutdown();
+
+        // Maintenance is only suggested if enough space, in absolute and relative terms, is unused.
+        let size_info = |page_count, free_pages| DbSizeInfo {
+            page_size: 4096,
+            page_count,
+            free_pages,
+        };
+        assert!(!needs_maintenance(&size_info(1_000, 900)));
+        assert!(needs_maintenance(&size_info(100_000, 25_000)));
+        assert!(!needs_maintenance(&size_info(100_000, 24_999)));
+        assert!(!needs_maintenance(&size_info(1_000_000, 20_000)));
+    }
+
+    #[test]
+    fn wait_for_tx() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = ms.control();
+        let txid = bitcoin::Txid::from_str(
+            "f7bd1b2a995b689d326e51eb742eb1088c4a8f110d9cb56128fd553acc9f88e5",
+        )
+        .unwrap();
+        let unconfirmed = WaitForTxResult {
+            confirmations: 0,
+            height: None,
+        };
+
+        // An unknown transaction is unconfirmed, we wait for the whole timeout.
+        let start = Instant::now();
+        let res = control
+            .wait_for_tx(&txid, 1, Duration::from_millis(100))
+            .unwrap();
+        assert_eq!(res, unconfirmed);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        // Unless no confirmation is required.
+        let res = control.wait_for_tx(&txid, 0, MAX_TX_WAIT_TIMEOUT).unwrap();
+        assert_eq!(res, unconfirmed);
+
+        // The number of concurrent waits is bounded.
+        let waiting: Vec<_> = (0..MAX_CONCURRENT_TX_WAITS)
+            .map(|_| {
+                let control = control.clone();
+                std::thread::spawn(move || control.wait_for_tx(&txid, 1, Duration::from_secs(1)))
+            })
+            .collect();
+        while control.tx_waits.load(atomic::Ordering::SeqCst) < MAX_CONCURRENT_TX_WAITS {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(
+            control.wait_for_tx(&txid, 1, Duration::from_millis(10)),
+            Err(CommandError::TooManyTxWaits)
+        ));
+        for handle in waiting {
+            assert_eq!(handle.join().unwrap().unwrap(), unconfirmed);
+        }
+        // The slots were released.
+        assert_eq!(control.tx_waits.load(atomic::Ordering::SeqCst), 0);
+        control
+            .wait_for_tx(&txid, 1, Duration::from_millis(10))
+            .unwrap();
+
         ms.shutdown();
     }
 
@@ -1314,7 +2540,7 @@ mod tests {
 
         let control = &ms.control();
         // We can get an address
-        let addr = control.get_new_address().address;
+        let addr = control.get_new_address().unwrap().address;
         assert_eq!(
             addr,
             bitcoin::Address::from_str(
@@ -1324,12 +2550,150 @@ mod tests {
             .assume_checked()
         );
         // We won't get the same twice.
-        let addr2 = control.get_new_address().address;
+        let addr2 = control.get_new_address().unwrap().address;
         assert_ne!(addr, addr2);
 
         ms.shutdown();
     }
 
+    #[test]
+    fn derivation_index_limit() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let max_index = control.config.max_derivation_index;
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+        let is_index_warning =
+            |w: &GetInfoWarning| matches!(w, GetInfoWarning::DerivationIndexHigh { .. });
+
+        // We only warn once more than half of the addresses up to the maximum were revealed.
+        db_conn.set_receive_index((max_index / 2).into(), &control.secp);
+        assert!(!control
+            .get_info()
+            .unwrap()
+            .warnings
+            .iter()
+            .any(is_index_warning));
+        control.get_new_address().unwrap();
+        assert!(control.get_info().unwrap().warnings.contains(
+            &GetInfoWarning::DerivationIndexHigh {
+                index: max_index / 2,
+                max_index
+            }
+        ));
+
+        // The address at the maximum index can be revealed, but none after it.
+        db_conn.set_receive_index(max_index.into(), &control.secp);
+        let addr = control.get_new_address().unwrap();
+        assert_eq!(u32::from(addr.derivation_index), max_index);
+        assert_eq!(
+            control.get_new_address().unwrap_err(),
+            CommandError::DerivationIndexLimit(max_index)
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn lower_receive_index() {
+        let bitcoind = DummyBitcoind::new();
+        let used_addresses = bitcoind.used_addresses.clone();
+        let ms = DummyLiana::new(bitcoind, DummyDatabase::new());
+        let control = &ms.control();
+        for _ in 0..20 {
+            control.get_new_address().unwrap();
+        }
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+
+        // It can only be lowered.
+        assert_eq!(
+            control.lower_receive_index(20),
+            Err(CommandError::ReceiveIndexNotLower(20))
+        );
+
+        // Not past an address which received a coin, nor past one used according to the backend.
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: bitcoin::OutPoint::from_str(
+                "4753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+            )
+            .unwrap(),
+            is_immature: false,
+            block_info: None,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(12),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        }]);
+        assert_eq!(
+            control.lower_receive_index(10),
+            Err(CommandError::UsedAddresses(12))
+        );
+        used_addresses
+            .lock()
+            .unwrap()
+            .insert(control.derive_address(15, false).unwrap().address);
+        assert_eq!(
+            control.lower_receive_index(13),
+            Err(CommandError::UsedAddresses(13))
+        );
+
+        // Past them, the next address revealed is the one at the lowered index.
+        control.lower_receive_index(16).unwrap();
+        assert_eq!(db_conn.receive_index(), 16.into());
+        assert_eq!(
+            control.get_new_address().unwrap().derivation_index,
+            16.into()
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn derive_and_find_address() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+
+        // The derived addresses are those listed, whether they were revealed or not.
+        let list = control.list_addresses(Some(0), Some(20)).unwrap();
+        for info in &list.addresses {
+            let receive = control.derive_address(info.index, false).unwrap();
+            assert_eq!(receive.address, info.receive);
+            assert_eq!(u32::from(receive.derivation_index), info.index);
+            assert!(!receive.change);
+            let change = control.derive_address(info.index, true).unwrap();
+            assert_eq!(change.address, info.change);
+            assert!(change.change);
+        }
+        assert_eq!(
+            control.derive_address(2u32.pow(31), false).unwrap_err(),
+            CommandError::InvalidDerivationIndex
+        );
+
+        // Addresses are found whether the database knows about them or not.
+        let new_addr = control.get_new_address().unwrap();
+        let found = control
+            .find_address(new_addr.address.as_unchecked().clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.derivation_index, new_addr.derivation_index);
+        assert!(!found.change);
+        let change = control.derive_address(17, true).unwrap();
+        assert_eq!(
+            control
+                .find_address(change.address.as_unchecked().clone())
+                .unwrap(),
+            Some(change)
+        );
+
+        // An address which isn't ours isn't found.
+        let foreign =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        assert_eq!(control.find_address(foreign).unwrap(), None);
+
+        ms.shutdown();
+    }
+
     #[test]
     fn listaddresses() {
         let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
@@ -1341,11 +2705,11 @@ mod tests {
         assert_eq!(list.addresses[0].index, 2);
         assert_eq!(list.addresses.last().unwrap().index, 6);
 
-        let addr0 = control.get_new_address().address;
-        let addr1 = control.get_new_address().address;
-        let _addr2 = control.get_new_address().address;
-        let addr3 = control.get_new_address().address;
-        let addr4 = control.get_new_address().address;
+        let addr0 = control.get_new_address().unwrap().address;
+        let addr1 = control.get_new_address().unwrap().address;
+        let _addr2 = control.get_new_address().unwrap().address;
+        let addr3 = control.get_new_address().unwrap().address;
+        let addr4 = control.get_new_address().unwrap().address;
 
         let list = control.list_addresses(Some(0), None).unwrap();
 
@@ -1368,7 +2732,13 @@ mod tests {
         assert_eq!(list.addresses.last().unwrap().index, 3);
         assert_eq!(list.addresses.last().unwrap().receive, addr3);
 
-        let addr5 = control.get_new_address().address;
+        // No coin was received on these addresses.
+        assert!(list
+            .addresses
+            .iter()
+            .all(|a| a.receive_coins == 0 && a.change_coins == 0));
+
+        let addr5 = control.get_new_address().unwrap().address;
         let list = control.list_addresses(Some(5), None).unwrap();
 
         assert_eq!(list.addresses[0].index, 5);
@@ -1442,6 +2812,48 @@ mod tests {
             .list_addresses(Some(next_deriv_index + 1), None)
             .unwrap();
 
+        // The coins received on each address are counted, whatever their status.
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+        let coin = |vout, derivation_index, is_change, spend_txid| Coin {
+            outpoint: bitcoin::OutPoint::new(
+                Txid::from_str("4753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810")
+                    .unwrap(),
+                vout,
+            ),
+            is_immature: false,
+            block_info: None,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(derivation_index),
+            is_change,
+            spend_txid,
+            spend_block: None,
+            is_from_self: false,
+        };
+        db_conn.new_unspent_coins(&[
+            coin(0, 1, false, None),
+            coin(
+                1,
+                1,
+                false,
+                Some(
+                    Txid::from_str(
+                        "0c62a990d20d54429e70859292e82374ba6b1b951a3ab60f26bb65fee5724ff7",
+                    )
+                    .unwrap(),
+                ),
+            ),
+            coin(2, 1, true, None),
+            coin(3, 3, true, None),
+        ]);
+        let list = control.list_addresses(Some(0), Some(4)).unwrap();
+        assert_eq!(
+            list.addresses
+                .iter()
+                .map(|a| (a.receive_coins, a.change_coins))
+                .collect::<Vec<_>>(),
+            vec![(0, 0), (2, 1), (0, 0), (0, 1)]
+        );
+
         ms.shutdown();
     }
 
@@ -1456,7 +2868,7 @@ mod tests {
         let dummy_op = bitcoin::OutPoint::new(dummy_tx.txid(), 0);
         let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
         let control = &ms.control();
-        let mut db_conn = control.db().lock().unwrap().connection();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
         db_conn.new_txs(&[dummy_tx]);
 
         // Arguments sanity checking
@@ -1465,7 +2877,7 @@ mod tests {
         let dummy_value = 10_000;
         let mut destinations = <HashMap<bitcoin::Address<address::NetworkUnchecked>, u64>>::new();
         assert_eq!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, None, false, None),
             Err(CommandError::NoOutpointForSelfSend)
         );
         destinations = [(dummy_addr.clone(), dummy_value)]
@@ -1474,18 +2886,18 @@ mod tests {
             .collect();
         // Insufficient funds for coin selection.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, None, false, None),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 0, None),
+            control.create_spend(&destinations, &[dummy_op], 0, None, None, false, None),
             Err(CommandError::InvalidFeerate(0))
         );
 
         // The coin doesn't exist. If we create a new unspent one at this outpoint with a much
         // higher value, we'll get a Spend transaction with a change output.
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, None, false, None),
             Err(CommandError::UnknownOutpoint(dummy_op))
         );
         db_conn.new_unspent_coins(&[Coin {
@@ -1502,14 +2914,18 @@ mod tests {
         // If we try to use coin selection, the unconfirmed not-from-self coin will not be used
         // as a candidate and so we get a coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, None, false, None),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        let (psbt, change_output, warnings) = if let CreateSpendResult::Success {
+            psbt,
+            change_output,
+            warnings,
+        } = control
+            .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
             .unwrap()
         {
-            (psbt, warnings)
+            (psbt, change_output, warnings)
         } else {
             panic!("expect successful spend creation")
         };
@@ -1520,11 +2936,33 @@ mod tests {
         assert_eq!(tx.output.len(), 2);
         // It has change so no warnings expected.
         assert!(warnings.is_empty());
+        let dummy_spk = dummy_addr.payload().script_pubkey();
+        let (dest_txo, change_txo) = dest_and_change(&tx, &dummy_spk);
+        assert_eq!(dest_txo.value.to_sat(), dummy_value);
+        // The change output is reported along with the PSBT. It uses the first change address.
+        let change_output = change_output.expect("There is a change output");
+        assert_eq!(tx.output[change_output.index], *change_txo);
+        assert_eq!(change_output.amount, change_txo.value);
+        assert_eq!(change_output.derivation_index, bip32::ChildNumber::from(0));
+
+        // We can't spend through a recovery path the descriptor doesn't have, nor spend an
+        // unconfirmed coin through the one it has.
         assert_eq!(
-            tx.output[0].script_pubkey,
-            dummy_addr.payload().script_pubkey()
+            control.create_spend(&destinations, &[dummy_op], 1, None, None, false, Some(42)),
+            Err(CommandError::UnknownRecoveryPath(42))
+        );
+        assert_eq!(
+            control.create_spend(
+                &destinations,
+                &[dummy_op],
+                1,
+                None,
+                None,
+                false,
+                Some(10_000)
+            ),
+            Err(CommandError::RecoveryPathNotAvailable(dummy_op, 10_000))
         );
-        assert_eq!(tx.output[0].value.to_sat(), dummy_value);
 
         // NOTE: if you are wondering about the usefulness of these tests asserting arbitrary fixed
         // values, that's a belt-and-suspenders check to make sure size and fee calculations do not
@@ -1534,9 +2972,9 @@ mod tests {
 
         // Transaction is 1 in (P2WSH satisfaction), 2 outs. At 1sat/vb, it's 161 sats fees.
         // At 2sats/vb, it's twice that.
-        assert_eq!(tx.output[1].value.to_sat(), 89_839);
+        assert_eq!(change_txo.value.to_sat(), 89_839);
         let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations, &[dummy_op], 2, None)
+            .create_spend(&destinations, &[dummy_op], 2, None, None, false, None)
             .unwrap()
         {
             psbt
@@ -1544,27 +2982,27 @@ mod tests {
             panic!("expect successful spend creation")
         };
         let tx = psbt.unsigned_tx;
-        assert_eq!(tx.output[1].value.to_sat(), 89_678);
+        assert_eq!(dest_and_change(&tx, &dummy_spk).1.value.to_sat(), 89_678);
 
         // A feerate of 555 won't trigger the sanity checks (they were previously not taking the
         // satisfaction size into account and overestimating the feerate).
         control
-            .create_spend(&destinations, &[dummy_op], 555, None)
+            .create_spend(&destinations, &[dummy_op], 555, None, None, false, None)
             .unwrap();
 
         // If we ask for a too high feerate, or a too large/too small output, it'll fail.
         assert!(matches!(
-            control.create_spend(&destinations, &[dummy_op], 10_000, None),
+            control.create_spend(&destinations, &[dummy_op], 10_000, None, None, false, None),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         *destinations.get_mut(&dummy_addr).unwrap() = 100_001;
         assert!(matches!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, None, false, None),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         *destinations.get_mut(&dummy_addr).unwrap() = 4_500;
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, None, false, None),
             Err(CommandError::SpendCreation(
                 SpendCreationError::InvalidOutputValue(bitcoin::Amount::from_sat(4_500))
             ))
@@ -1576,7 +3014,15 @@ mod tests {
         let invalid_destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
             [(invalid_addr, dummy_value)].iter().cloned().collect();
         assert!(matches!(
-            control.create_spend(&invalid_destinations, &[dummy_op], 1, None),
+            control.create_spend(
+                &invalid_destinations,
+                &[dummy_op],
+                1,
+                None,
+                None,
+                false,
+                None
+            ),
             Err(CommandError::Address(
                 address::Error::NetworkValidation { .. }
             ))
@@ -1585,8 +3031,8 @@ mod tests {
         // If we ask for a large, but valid, output we won't get a change output. 95_000 because we
         // won't create an output lower than 5k sats.
         *destinations.get_mut(&dummy_addr).unwrap() = 95_000;
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
             .unwrap()
         {
             (psbt, warnings)
@@ -1614,8 +3060,8 @@ mod tests {
 
         // Increase the target value by the change amount and the warning will disappear.
         *destinations.get_mut(&dummy_addr).unwrap() = 95_000 + 4_839;
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
             .unwrap()
         {
             (psbt, warnings)
@@ -1629,8 +3075,8 @@ mod tests {
         // Now increase target also by the extra fee that was paying for change and we can still create the spend.
         *destinations.get_mut(&dummy_addr).unwrap() =
             95_000 + 4_830 + /* fee for change output */ 43;
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
             .unwrap()
         {
             (psbt, warnings)
@@ -1645,7 +3091,7 @@ mod tests {
         *destinations.get_mut(&dummy_addr).unwrap() =
             95_000 + 4_839 + /* fee for change output */ 43 + 1;
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, None, false, None),
             Ok(CreateSpendResult::InsufficientFunds { missing: 1 }),
         );
 
@@ -1653,7 +3099,7 @@ mod tests {
         *destinations.get_mut(&dummy_addr).unwrap() =
             100_000 - /* fee without change */ 118 - /* extra fee for change output */ 43 - 1;
         let warnings = if let CreateSpendResult::Success { warnings, .. } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
             .unwrap()
         {
             warnings
@@ -1673,8 +3119,8 @@ mod tests {
         // Now decrease the target value so that we have enough for a change output.
         *destinations.get_mut(&dummy_addr).unwrap() =
             95_000 - /* fee without change */ 118 - /* extra fee for change output */ 43;
-        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
             .unwrap()
         {
             (psbt, warnings)
@@ -1682,15 +3128,20 @@ mod tests {
             panic!("expect successful spend creation")
         };
         let tx = psbt.unsigned_tx;
-        assert_eq!(tx.output.len(), 2);
-        assert_eq!(tx.output[1].value.to_sat(), 5_000);
+        assert_eq!(
+            dest_and_change(&tx, &dummy_addr.payload().script_pubkey())
+                .1
+                .value
+                .to_sat(),
+            5_000
+        );
         assert!(warnings.is_empty());
 
         // Now increase the target by 1 and we'll get a warning again, this time for 1 less than the dust threshold.
         *destinations.get_mut(&dummy_addr).unwrap() =
             95_000 - /* fee without change */ 118 - /* extra fee for change output */ 43 + 1;
         let warnings = if let CreateSpendResult::Success { warnings, .. } = control
-            .create_spend(&destinations, &[dummy_op], 1, None)
+            .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
             .unwrap()
         {
             warnings
@@ -1716,13 +3167,13 @@ mod tests {
             .unwrap(),
         )]);
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op], 1, None),
+            control.create_spend(&destinations, &[dummy_op], 1, None, None, false, None),
             Err(CommandError::AlreadySpent(dummy_op))
         );
         // If we try to use coin selection, the spent coin will not be used as a candidate
         // and so we get a coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, None, false, None),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
 
@@ -1743,7 +3194,15 @@ mod tests {
             is_from_self: false,
         }]);
         assert_eq!(
-            control.create_spend(&destinations, &[dummy_op_dup], 1_001, None),
+            control.create_spend(
+                &destinations,
+                &[dummy_op_dup],
+                1_001,
+                None,
+                None,
+                false,
+                None
+            ),
             Err(CommandError::SpendCreation(SpendCreationError::InsaneFees(
                 InsaneFeeInfo::TooHighFeerate(1_001)
             )))
@@ -1768,14 +3227,14 @@ mod tests {
         db_conn.new_unspent_coins(&[unconfirmed_coin]);
         // Coin selection error due to insufficient funds.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, None, false, None),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         // Set destination amount equal to value of confirmed coins.
         *destinations.get_mut(&dummy_addr).unwrap() = 80_000;
         // Coin selection error occurs due to insufficient funds to pay fee.
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&destinations, &[], 1, None, None, false, None),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
         let confirmed_op_2 = bitcoin::OutPoint {
@@ -1798,8 +3257,9 @@ mod tests {
             is_from_self: false,
         }]);
         // First, create a transaction using auto coin selection.
-        let psbt = if let CreateSpendResult::Success { psbt, .. } =
-            control.create_spend(&destinations, &[], 1, None).unwrap()
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(&destinations, &[], 1, None, None, false, None)
+            .unwrap()
         {
             psbt
         } else {
@@ -1815,158 +3275,776 @@ mod tests {
         assert_eq!(tx_auto.input.len(), 2);
         assert_eq!(tx_prev_outpoints, vec![confirmed_op_1, confirmed_op_2]);
         // Output includes change.
-        assert_eq!(tx_auto.output.len(), 2);
-        assert_eq!(
-            tx_auto.output[0].script_pubkey,
-            dummy_addr.payload().script_pubkey()
-        );
-        assert_eq!(tx_auto.output[0].value, Amount::from_sat(80_000));
+        let dummy_spk = dummy_addr.payload().script_pubkey();
+        let (dest_auto, change_auto) = dest_and_change(&tx_auto, &dummy_spk);
+        assert_eq!(dest_auto.value, Amount::from_sat(80_000));
+
+        // Create a second transaction using manual coin selection.
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &destinations,
+                &[confirmed_op_1, confirmed_op_2],
+                1,
+                None,
+                None,
+                false,
+                None,
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx_manual = psbt.unsigned_tx;
+        // Check that manual and auto selection give same outputs (except change address).
+        let (dest_manual, change_manual) = dest_and_change(&tx_manual, &dummy_spk);
+        assert_eq!(dest_auto, dest_manual);
+        assert_eq!(change_auto.value, change_manual.value);
+        assert_ne!(change_auto.script_pubkey, change_manual.script_pubkey);
+        // Check inputs are also the same. Need to sort as order is not guaranteed by `create_spend`.
+        let mut auto_input = tx_auto.clone().input;
+        let mut manual_input = tx_manual.input;
+        auto_input.sort();
+        manual_input.sort();
+        assert_eq!(auto_input, manual_input);
+
+        // Now check that the spend created above using auto-selection only works when the unconfirmed coin
+        // is from self, whether or not it is from change.
+        // 1. not from self and not change
+        db_conn.remove_coins(&[unconfirmed_coin.outpoint]);
+        let mut unconfirmed_coin_2 = unconfirmed_coin;
+        unconfirmed_coin_2.is_from_self = false;
+        unconfirmed_coin_2.is_change = false;
+        db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
+        assert!(matches!(
+            control.create_spend(&destinations, &[], 1, None, None, false, None),
+            Ok(CreateSpendResult::InsufficientFunds { .. }),
+        ));
+        // 2. not from self and change
+        db_conn.remove_coins(&[unconfirmed_coin_2.outpoint]);
+        unconfirmed_coin_2.is_from_self = false;
+        unconfirmed_coin_2.is_change = true;
+        db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
+        assert!(matches!(
+            control.create_spend(&destinations, &[], 1, None, None, false, None),
+            Ok(CreateSpendResult::InsufficientFunds { .. }),
+        ));
+
+        // Re-insert the original unconfirmed coin again.
+        db_conn.remove_coins(&[unconfirmed_coin_2.outpoint]);
+        db_conn.new_unspent_coins(&[unconfirmed_coin]);
+
+        // Now do the same again, but this time specifying the change address to be the same
+        // as for the auto spend.
+        let change_address = bitcoin::Address::from_script(
+            change_auto.script_pubkey.as_script(),
+            bitcoin::Network::Bitcoin,
+        )
+        .unwrap();
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(
+                &destinations,
+                &[confirmed_op_1, confirmed_op_2],
+                1,
+                Some(change_address.as_unchecked().clone()),
+                None,
+                false,
+                None,
+            )
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx_manual = psbt.unsigned_tx;
+        // Now the outputs of each transaction are the same.
+        assert_eq!(
+            dest_and_change(&tx_auto, &dummy_spk),
+            dest_and_change(&tx_manual, &dummy_spk)
+        );
+        // Check again that inputs are still the same.
+        let mut auto_input = tx_auto.input;
+        let mut manual_input = tx_manual.input;
+        auto_input.sort();
+        manual_input.sort();
+        assert_eq!(auto_input, manual_input);
+
+        // Add a confirmed coin with a value near the dust limit and check that
+        // `InsufficientFunds` error is returned if feerate is too high.
+        let confirmed_op_3 = bitcoin::OutPoint {
+            txid: confirmed_op_2.txid,
+            vout: confirmed_op_2.vout + 10,
+        };
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: confirmed_op_3,
+            is_immature: false,
+            block_info: Some(BlockInfo {
+                height: 174500,
+                time: 174500,
+            }),
+            amount: bitcoin::Amount::from_sat(5_250),
+            derivation_index: bip32::ChildNumber::from(56),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        }]);
+        let empty_dest = &HashMap::<bitcoin::Address<address::NetworkUnchecked>, u64>::new();
+        assert!(matches!(
+            control.create_spend(empty_dest, &[confirmed_op_3], 5, None, None, false, None),
+            Ok(CreateSpendResult::InsufficientFunds { .. }),
+        ));
+        // If we use a lower fee, the self-send will succeed.
+        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+            .create_spend(empty_dest, &[confirmed_op_3], 1, None, None, false, None)
+            .unwrap()
+        {
+            psbt
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx = psbt.unsigned_tx;
+        let tx_prev_outpoints = tx
+            .input
+            .iter()
+            .map(|txin| txin.previous_output)
+            .collect::<Vec<OutPoint>>();
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx_prev_outpoints, vec![confirmed_op_3]);
+        assert_eq!(tx.output.len(), 1);
+
+        // Can't create a transaction that spends an immature coinbase deposit.
+        let imma_op = bitcoin::OutPoint::from_str(
+            "4753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+        )
+        .unwrap();
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: imma_op,
+            is_immature: true,
+            block_info: None,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        }]);
+        assert_eq!(
+            control.create_spend(&destinations, &[imma_op], 1_001, None, None, false, None),
+            Err(CommandError::ImmatureCoinbase(imma_op))
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_spend_min_feerate() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let dummy_op = bitcoin::OutPoint::new(dummy_tx.txid(), 0);
+        let mut bitcoind = DummyBitcoind::new();
+        bitcoind.min_relay_feerate = Some(2);
+        let ms = DummyLiana::new(bitcoind, DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+        db_conn.new_txs(&[dummy_tx]);
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: dummy_op,
+            is_immature: false,
+            block_info: None,
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        }]);
+        let dummy_addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        let dummy_spk = dummy_addr.payload().script_pubkey();
+        let destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
+            [(dummy_addr, 10_000)].iter().cloned().collect();
+
+        // A feerate below the minimum relay feerate of the backend is raised to it. Same
+        // transaction as in the `create_spend` test, at 2sats/vb.
+        let (psbt, warnings) = if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
+            .unwrap()
+        {
+            (psbt, warnings)
+        } else {
+            panic!("expect successful spend creation")
+        };
+        let tx = psbt.unsigned_tx;
+        assert_eq!(dest_and_change(&tx, &dummy_spk).1.value.to_sat(), 89_678);
+        assert_eq!(
+            warnings,
+            vec!["Feerate raised from 1 to the minimum of 2 sat/vb.".to_string()]
+        );
+
+        // A higher feerate is left untouched.
+        if let CreateSpendResult::Success { warnings, .. } = control
+            .create_spend(&destinations, &[dummy_op], 3, None, None, false, None)
+            .unwrap()
+        {
+            assert!(warnings.is_empty());
+        } else {
+            panic!("expect successful spend creation")
+        }
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_spend_anti_fee_sniping() {
+        let mut bitcoind = DummyBitcoind::new();
+        bitcoind.tip_time = Some(
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as u32,
+        );
+        let ms = DummyLiana::new(bitcoind, DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+
+        // The poller caches the height of the tip, which is used as the locktime.
+        let tip_height = control.bitcoin.chain_tip().height as u32;
+        let (tx, rx) = mpsc::sync_channel(0);
+        control
+            .poller_sender
+            .send(PollerMessage::PollNow(tx))
+            .unwrap();
+        rx.recv().unwrap();
+        assert_eq!(
+            control.latest_tip_height.load(atomic::Ordering::Relaxed),
+            tip_height
+        );
+
+        let dummy_op = bitcoin::OutPoint::from_str(
+            "3753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
+        )
+        .unwrap();
+        db_conn.new_unspent_coins(&[Coin {
+            outpoint: dummy_op,
+            is_immature: false,
+            block_info: Some(BlockInfo {
+                height: 10,
+                time: 1_000,
+            }),
+            amount: bitcoin::Amount::from_sat(100_000),
+            derivation_index: bip32::ChildNumber::from(13),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        }]);
+        let dummy_addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        let destinations: HashMap<_, _> = [(dummy_addr, 10_000)].iter().cloned().collect();
+
+        // No locktime is given, so it is set to the tip height, or up to 100 blocks before.
+        for _ in 0..20 {
+            let psbt = match control
+                .create_spend(&destinations, &[dummy_op], 1, None, None, false, None)
+                .unwrap()
+            {
+                CreateSpendResult::Success { psbt, .. } => psbt,
+                res => panic!("Unexpected result: {:?}", res),
+            };
+            match psbt.unsigned_tx.lock_time {
+                absolute::LockTime::Blocks(height) => {
+                    let height = height.to_consensus_u32();
+                    assert!(height <= tip_height && height >= tip_height.saturating_sub(100));
+                }
+                locktime => panic!("Unexpected locktime: {}", locktime),
+            }
+        }
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_spend_coin_selection() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+        db_conn.new_txs(&[dummy_tx.clone()]);
+
+        // Three confirmed coins, each of which is enough to fund the spend on its own.
+        let coins: Vec<Coin> = [(50_000, 100), (200_000, 200), (30_000, 300)]
+            .iter()
+            .enumerate()
+            .map(|(i, (amount, height))| Coin {
+                outpoint: bitcoin::OutPoint::new(dummy_tx.txid(), i as u32),
+                is_immature: false,
+                block_info: Some(BlockInfo {
+                    height: *height,
+                    time: *height as u32,
+                }),
+                amount: bitcoin::Amount::from_sat(*amount),
+                derivation_index: bip32::ChildNumber::from(i as u32),
+                is_change: false,
+                spend_txid: None,
+                spend_block: None,
+                is_from_self: false,
+            })
+            .collect();
+        db_conn.new_unspent_coins(&coins);
+
+        let dummy_addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        let destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
+            [(dummy_addr, 25_000)].iter().cloned().collect();
+        let spent_outpoint = |algo: CoinSelectionAlgorithm| {
+            if let CreateSpendResult::Success { psbt, .. } = control
+                .create_spend(&destinations, &[], 1, None, Some(algo), false, None)
+                .unwrap()
+            {
+                assert_eq!(psbt.unsigned_tx.input.len(), 1);
+                psbt.unsigned_tx.input[0].previous_output
+            } else {
+                panic!("expect successful spend creation")
+            }
+        };
+
+        // The oldest coin, the largest coin and the smallest coin respectively.
+        assert_eq!(
+            spent_outpoint(CoinSelectionAlgorithm::Fifo),
+            coins[0].outpoint
+        );
+        assert_eq!(
+            spent_outpoint(CoinSelectionAlgorithm::LargestFirst),
+            coins[1].outpoint
+        );
+        assert_eq!(
+            spent_outpoint(CoinSelectionAlgorithm::SmallestFirst),
+            coins[2].outpoint
+        );
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_spend_avoid_mixing_tags() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+        db_conn.new_txs(&[dummy_tx.clone()]);
+
+        // Three confirmed coins, none of which is enough to fund the spend on its own.
+        let coins: Vec<Coin> = [20_000, 15_000, 16_000]
+            .iter()
+            .enumerate()
+            .map(|(i, amount)| Coin {
+                outpoint: bitcoin::OutPoint::new(dummy_tx.txid(), i as u32),
+                is_immature: false,
+                block_info: Some(BlockInfo {
+                    height: 100,
+                    time: 100,
+                }),
+                amount: bitcoin::Amount::from_sat(*amount),
+                derivation_index: bip32::ChildNumber::from(i as u32),
+                is_change: false,
+                spend_txid: None,
+                spend_block: None,
+                is_from_self: false,
+            })
+            .collect();
+        db_conn.new_unspent_coins(&coins);
+        // The first two coins have different tags, the last one is untagged.
+        control
+            .set_coin_tags(
+                &vec![
+                    (coins[0].outpoint, Some("exchange".to_string())),
+                    (coins[1].outpoint, Some("salary".to_string())),
+                ]
+                .into_iter()
+                .collect(),
+            )
+            .unwrap();
+        let listed = control
+            .list_coins(&[], &[coins[0].outpoint, coins[2].outpoint])
+            .unwrap();
+        for entry in listed.coins {
+            if entry.outpoint == coins[0].outpoint {
+                assert_eq!(entry.tag.as_deref(), Some("exchange"));
+            } else {
+                assert_eq!(entry.tag, None);
+            }
+        }
 
-        // Create a second transaction using manual coin selection.
-        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations, &[confirmed_op_1, confirmed_op_2], 1, None)
+        let dummy_addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+        let destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> =
+            [(dummy_addr, 30_000)].iter().cloned().collect();
+
+        // Mixing the tags is necessary to fund the spend. It's done, with a warning, unless
+        // the caller asks not to.
+        if let CreateSpendResult::Success { warnings, .. } = control
+            .create_spend(&destinations, &[], 1, None, None, false, None)
             .unwrap()
         {
-            psbt
+            assert!(warnings.iter().any(|w| w.contains("privacy tags")));
         } else {
             panic!("expect successful spend creation")
-        };
-        let tx_manual = psbt.unsigned_tx;
-        // Check that manual and auto selection give same outputs (except change address).
-        assert_ne!(tx_auto.output, tx_manual.output);
-        assert_eq!(tx_auto.output.len(), tx_manual.output.len());
-        assert_eq!(tx_auto.output[0], tx_manual.output[0]);
-        assert_eq!(tx_auto.output[1].value, tx_manual.output[1].value);
-        assert_ne!(
-            tx_auto.output[1].script_pubkey,
-            tx_manual.output[1].script_pubkey
-        );
-        // Check inputs are also the same. Need to sort as order is not guaranteed by `create_spend`.
-        let mut auto_input = tx_auto.clone().input;
-        let mut manual_input = tx_manual.input;
-        auto_input.sort();
-        manual_input.sort();
-        assert_eq!(auto_input, manual_input);
-
-        // Now check that the spend created above using auto-selection only works when the unconfirmed coin
-        // is from self, whether or not it is from change.
-        // 1. not from self and not change
-        db_conn.remove_coins(&[unconfirmed_coin.outpoint]);
-        let mut unconfirmed_coin_2 = unconfirmed_coin;
-        unconfirmed_coin_2.is_from_self = false;
-        unconfirmed_coin_2.is_change = false;
-        db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
-        assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
-            Ok(CreateSpendResult::InsufficientFunds { .. }),
+        }
+        let err = control
+            .create_spend(&destinations, &[], 1, None, None, true, None)
+            .unwrap_err();
+        if let CommandError::InsufficientFundsPerTag(ref missing) = err {
+            assert_eq!(
+                missing
+                    .iter()
+                    .map(|(tag, _)| tag.as_deref())
+                    .collect::<Vec<_>>(),
+                vec![None, Some("exchange"), Some("salary")]
+            );
+            assert!(missing.iter().all(|(_, m)| *m > 0));
+        } else {
+            panic!("expect insufficient funds per tag");
+        }
+        let msg = err.to_string();
+        assert!(msg.starts_with(
+            "No set of coins sharing a privacy tag can fund this transaction without mixing tags."
         ));
-        // 2. not from self and change
-        db_conn.remove_coins(&[unconfirmed_coin_2.outpoint]);
-        unconfirmed_coin_2.is_from_self = false;
-        unconfirmed_coin_2.is_change = true;
-        db_conn.new_unspent_coins(&[unconfirmed_coin_2]);
+        assert!(msg.contains(" sats for untagged coins, "));
+        assert!(msg.contains(" sats for 'exchange', "));
+        assert!(msg.contains(" sats for 'salary'."));
+
+        // If the coins aren't enough altogether, it's not about the tags.
+        let too_much: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> = destinations
+            .keys()
+            .map(|addr| (addr.clone(), 60_000))
+            .collect();
         assert!(matches!(
-            control.create_spend(&destinations, &[], 1, None),
+            control.create_spend(&too_much, &[], 1, None, None, true, None),
             Ok(CreateSpendResult::InsufficientFunds { .. }),
         ));
 
-        // Re-insert the original unconfirmed coin again.
-        db_conn.remove_coins(&[unconfirmed_coin_2.outpoint]);
-        db_conn.new_unspent_coins(&[unconfirmed_coin]);
-
-        // Now do the same again, but this time specifying the change address to be the same
-        // as for the auto spend.
-        let change_address = bitcoin::Address::from_script(
-            tx_auto.output[1].script_pubkey.as_script(),
-            bitcoin::Network::Bitcoin,
-        )
-        .unwrap();
-        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(
-                &destinations,
-                &[confirmed_op_1, confirmed_op_2],
-                1,
-                Some(change_address.as_unchecked().clone()),
+        // Once the untagged coin shares the tag of the first one, both can be spent together.
+        control
+            .set_coin_tags(
+                &vec![(coins[2].outpoint, Some("exchange".to_string()))]
+                    .into_iter()
+                    .collect(),
             )
+            .unwrap();
+        if let CreateSpendResult::Success { psbt, warnings, .. } = control
+            .create_spend(&destinations, &[], 1, None, None, true, None)
             .unwrap()
         {
-            psbt
+            let mut spent: Vec<_> = psbt
+                .unsigned_tx
+                .input
+                .iter()
+                .map(|txin| txin.previous_output)
+                .collect();
+            spent.sort();
+            assert_eq!(spent, vec![coins[0].outpoint, coins[2].outpoint]);
+            assert!(!warnings.iter().any(|w| w.contains("privacy tags")));
         } else {
             panic!("expect successful spend creation")
-        };
-        let tx_manual = psbt.unsigned_tx;
-        // Now the outputs of each transaction are the same.
-        assert_eq!(tx_auto.output, tx_manual.output);
-        // Check again that inputs are still the same.
-        let mut auto_input = tx_auto.input;
-        let mut manual_input = tx_manual.input;
-        auto_input.sort();
-        manual_input.sort();
-        assert_eq!(auto_input, manual_input);
+        }
 
-        // Add a confirmed coin with a value near the dust limit and check that
-        // `InsufficientFunds` error is returned if feerate is too high.
-        let confirmed_op_3 = bitcoin::OutPoint {
-            txid: confirmed_op_2.txid,
-            vout: confirmed_op_2.vout + 10,
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_spend_change_position() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
         };
-        db_conn.new_unspent_coins(&[Coin {
-            outpoint: confirmed_op_3,
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+        db_conn.new_txs(&[dummy_tx.clone()]);
+        let coin = Coin {
+            outpoint: bitcoin::OutPoint::new(dummy_tx.txid(), 0),
             is_immature: false,
             block_info: Some(BlockInfo {
-                height: 174500,
-                time: 174500,
+                height: 100,
+                time: 100,
             }),
-            amount: bitcoin::Amount::from_sat(5_250),
-            derivation_index: bip32::ChildNumber::from(56),
+            amount: bitcoin::Amount::from_sat(1_000_000),
+            derivation_index: bip32::ChildNumber::from(0),
             is_change: false,
             spend_txid: None,
             spend_block: None,
             is_from_self: false,
-        }]);
-        let empty_dest = &HashMap::<bitcoin::Address<address::NetworkUnchecked>, u64>::new();
-        assert!(matches!(
-            control.create_spend(empty_dest, &[confirmed_op_3], 5, None),
-            Ok(CreateSpendResult::InsufficientFunds { .. }),
-        ));
-        // If we use a lower fee, the self-send will succeed.
-        let psbt = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(empty_dest, &[confirmed_op_3], 1, None)
-            .unwrap()
-        {
-            psbt
-        } else {
-            panic!("expect successful spend creation")
         };
-        let tx = psbt.unsigned_tx;
-        let tx_prev_outpoints = tx
-            .input
-            .iter()
-            .map(|txin| txin.previous_output)
-            .collect::<Vec<OutPoint>>();
-        assert_eq!(tx.input.len(), 1);
-        assert_eq!(tx_prev_outpoints, vec![confirmed_op_3]);
-        assert_eq!(tx.output.len(), 1);
+        db_conn.new_unspent_coins(&[coin]);
 
-        // Can't create a transaction that spends an immature coinbase deposit.
-        let imma_op = bitcoin::OutPoint::from_str(
-            "4753a1d74c0af8dd0a0f3b763c14faf3bd9ed03cbdf33337a074fb0e9f6c7810:0",
-        )
-        .unwrap();
-        db_conn.new_unspent_coins(&[Coin {
-            outpoint: imma_op,
-            is_immature: true,
-            block_info: None,
+        // Two external recipients, and a change output.
+        let destinations: HashMap<bitcoin::Address<address::NetworkUnchecked>, u64> = [
+            (
+                bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap(),
+                100_000,
+            ),
+            (
+                bitcoin::Address::from_str("bc1q39srgatmkp6k2ne3l52yhkjprdvunvspqydmkx").unwrap(),
+                200_000,
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        // Wherever the change output ends up, the PSBT output at the same index must contain
+        // the derivation information for its address.
+        let mut change_positions = HashSet::new();
+        for _ in 0..50 {
+            let psbt = if let CreateSpendResult::Success { psbt, .. } = control
+                .create_spend(&destinations, &[coin.outpoint], 1, None, None, false, None)
+                .unwrap()
+            {
+                psbt
+            } else {
+                panic!("expect successful spend creation")
+            };
+            assert_eq!(psbt.unsigned_tx.output.len(), 3);
+            assert_eq!(psbt.outputs.len(), 3);
+            let change_positions_psbt: Vec<usize> = psbt
+                .outputs
+                .iter()
+                .enumerate()
+                .filter(|(_, psbt_out)| !psbt_out.bip32_derivation.is_empty())
+                .map(|(i, _)| i)
+                .collect();
+            assert_eq!(change_positions_psbt.len(), 1);
+            let change_index = change_positions_psbt[0];
+            let change_txo = &psbt.unsigned_tx.output[change_index];
+            assert!(![100_000, 200_000].contains(&change_txo.value.to_sat()));
+
+            let (_, (_, der_path)) = psbt.outputs[change_index]
+                .bip32_derivation
+                .iter()
+                .next()
+                .unwrap();
+            let der_index = *der_path.into_iter().last().unwrap();
+            let change_desc = control
+                .config
+                .main_descriptor
+                .change_descriptor()
+                .derive(der_index, &control.secp);
+            assert_eq!(change_txo.script_pubkey, change_desc.script_pubkey());
+            let mut expected_psbt_out = bitcoin::psbt::Output::default();
+            change_desc.update_change_psbt_out(&mut expected_psbt_out);
+            assert_eq!(psbt.outputs[change_index], expected_psbt_out);
+
+            change_positions.insert(change_index);
+        }
+        // The change output isn't always at the same position.
+        assert!(change_positions.len() > 1);
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_cpfp() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+        db_conn.new_txs(&[dummy_tx.clone()]);
+
+        // An unconfirmed and a confirmed coin.
+        let coins: Vec<Coin> = vec![
+            None,
+            Some(BlockInfo {
+                height: 10,
+                time: 1_000,
+            }),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, block_info)| Coin {
+            outpoint: bitcoin::OutPoint::new(dummy_tx.txid(), i as u32),
+            is_immature: false,
+            block_info,
             amount: bitcoin::Amount::from_sat(100_000),
-            derivation_index: bip32::ChildNumber::from(13),
+            derivation_index: bip32::ChildNumber::from(i as u32),
             is_change: false,
             spend_txid: None,
             spend_block: None,
             is_from_self: false,
-        }]);
+        })
+        .collect();
+        db_conn.new_unspent_coins(&coins);
+
         assert_eq!(
-            control.create_spend(&destinations, &[imma_op], 1_001, None),
-            Err(CommandError::ImmatureCoinbase(imma_op))
+            control.create_cpfp(&[], 10),
+            Err(CommandError::NoOutpointForSelfSend)
+        );
+        let unknown_op = bitcoin::OutPoint::new(dummy_tx.txid(), 2);
+        assert_eq!(
+            control.create_cpfp(&[unknown_op], 10),
+            Err(CommandError::UnknownOutpoint(unknown_op))
+        );
+        assert_eq!(
+            control.create_cpfp(&[coins[0].outpoint, coins[1].outpoint], 10),
+            Err(CommandError::ConfirmedCoin(coins[1].outpoint))
+        );
+
+        // The parent isn't in the mempool of the dummy bitcoind, so it has no ancestor.
+        let res = control.create_cpfp(&[coins[0].outpoint], 10).unwrap();
+        assert_eq!((res.ancestors_vsize, res.ancestors_fee), (0, 0));
+        match res.spend {
+            CreateSpendResult::Success {
+                psbt,
+                change_output,
+                ..
+            } => {
+                assert_eq!(psbt.unsigned_tx.input.len(), 1);
+                assert_eq!(psbt.unsigned_tx.input[0].previous_output, coins[0].outpoint);
+                // The coin is sent back to us.
+                assert_eq!(psbt.unsigned_tx.output.len(), 1);
+                assert_eq!(change_output.map(|c| c.index), Some(0));
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_consolidation() {
+        let dummy_tx = bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![],
+            output: vec![],
+        };
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
+        db_conn.new_txs(&[dummy_tx.clone()]);
+
+        // An unconfirmed coin and confirmed coins of various ages and values.
+        let coins: Vec<Coin> = vec![
+            (None, 100_000),
+            (Some(20), 50_000),
+            (Some(10), 80_000),
+            (Some(10), 60_000),
+            (Some(30), 10_000),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (height, amount))| Coin {
+            outpoint: bitcoin::OutPoint::new(dummy_tx.txid(), i as u32),
+            is_immature: false,
+            block_info: height.map(|height| BlockInfo {
+                height,
+                time: 1_000,
+            }),
+            amount: bitcoin::Amount::from_sat(amount),
+            derivation_index: bip32::ChildNumber::from(i as u32),
+            is_change: false,
+            spend_txid: None,
+            spend_block: None,
+            is_from_self: false,
+        })
+        .collect();
+        db_conn.new_unspent_coins(&coins[..2]);
+
+        // A single confirmed coin can't be consolidated.
+        assert_eq!(
+            control.create_consolidation(2, 10),
+            Err(CommandError::NothingToConsolidate)
+        );
+
+        db_conn.new_unspent_coins(&coins[2..]);
+        assert_eq!(
+            control.create_consolidation(2, 1),
+            Err(CommandError::NothingToConsolidate)
+        );
+
+        // The oldest coins are selected, the smallest first among those of the same age.
+        match control.create_consolidation(2, 3).unwrap() {
+            CreateSpendResult::Success {
+                psbt,
+                change_output,
+                ..
+            } => {
+                let mut spent: Vec<_> = psbt
+                    .unsigned_tx
+                    .input
+                    .iter()
+                    .map(|txin| txin.previous_output)
+                    .collect();
+                spent.sort();
+                assert_eq!(
+                    spent,
+                    vec![coins[1].outpoint, coins[2].outpoint, coins[3].outpoint]
+                );
+                // Everything is sent back to us.
+                assert_eq!(psbt.unsigned_tx.output.len(), 1);
+                assert_eq!(change_output.map(|c| c.index), Some(0));
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn create_recovery_timelock() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+        let addr =
+            bitcoin::Address::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv").unwrap();
+
+        // The dummy descriptor has a single recovery path, available after 10'000 blocks. We
+        // can't sweep coins with a timelock that doesn't correspond to a recovery path.
+        assert_eq!(
+            control
+                .create_recovery(addr.clone(), 2, Some(42))
+                .unwrap_err(),
+            CommandError::UnknownRecoveryPath(42)
+        );
+        assert_eq!(
+            control
+                .create_recovery(addr.clone(), 2, Some(10_001))
+                .unwrap_err(),
+            CommandError::UnknownRecoveryPath(10_001)
+        );
+
+        // With the right timelock, or none, it fails because there is no coin to sweep.
+        assert_eq!(
+            control
+                .create_recovery(addr.clone(), 2, Some(10_000))
+                .unwrap_err(),
+            CommandError::RecoveryNotAvailable
+        );
+        assert_eq!(
+            control.create_recovery(addr, 2, None).unwrap_err(),
+            CommandError::RecoveryNotAvailable
         );
 
         ms.shutdown();
@@ -1995,7 +4073,7 @@ mod tests {
         dummy_bitcoind.txs.insert(dummy_op_b.txid, (dummy_tx, None));
         let ms = DummyLiana::new(dummy_bitcoind, DummyDatabase::new());
         let control = &ms.control();
-        let mut db_conn = control.db().lock().unwrap().connection();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
 
         // Add two (unconfirmed) coins in DB
         db_conn.new_unspent_coins(&[
@@ -2046,7 +4124,7 @@ mod tests {
                 .cloned()
                 .collect();
         let mut psbt_a = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_a, &[dummy_op_a], 1, None)
+            .create_spend(&destinations_a, &[dummy_op_a], 1, None, None, false, None)
             .unwrap()
         {
             psbt
@@ -2055,7 +4133,7 @@ mod tests {
         };
         let txid_a = psbt_a.unsigned_tx.txid();
         let psbt_b = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_b, &[dummy_op_b], 10, None)
+            .create_spend(&destinations_b, &[dummy_op_b], 10, None, None, false, None)
             .unwrap()
         {
             psbt
@@ -2064,7 +4142,15 @@ mod tests {
         };
         let txid_b = psbt_b.unsigned_tx.txid();
         let psbt_c = if let CreateSpendResult::Success { psbt, .. } = control
-            .create_spend(&destinations_c, &[dummy_op_a, dummy_op_b], 100, None)
+            .create_spend(
+                &destinations_c,
+                &[dummy_op_a, dummy_op_b],
+                100,
+                None,
+                None,
+                false,
+                None,
+            )
             .unwrap()
         {
             psbt
@@ -2111,6 +4197,42 @@ mod tests {
         ms.shutdown();
     }
 
+    #[test]
+    fn saved_psbts() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let control = &ms.control();
+
+        let psbt = Psbt::from_unsigned_tx(bitcoin::Transaction {
+            version: TxVersion::TWO,
+            lock_time: absolute::LockTime::Blocks(absolute::Height::ZERO),
+            input: vec![bitcoin::TxIn::default()],
+            output: vec![],
+        })
+        .unwrap();
+        assert!(control.list_saved_psbts().unwrap().psbts.is_empty());
+        let id = control.save_psbt(&psbt, "waiting for Bob").unwrap().id;
+        let saved = control.get_saved_psbt(id).unwrap();
+        assert_eq!(saved.psbt, psbt);
+        assert_eq!(saved.label, "waiting for Bob");
+        assert_eq!(control.list_saved_psbts().unwrap().psbts.len(), 1);
+
+        // Saving a PSBT doesn't make it a Spend transaction.
+        assert!(control.list_spend(None).unwrap().spend_txs.is_empty());
+
+        control.delete_saved_psbt(id).unwrap();
+        assert_eq!(
+            control.get_saved_psbt(id).unwrap_err(),
+            CommandError::UnknownSavedPsbt(id)
+        );
+        assert_eq!(
+            control.delete_saved_psbt(id),
+            Err(CommandError::UnknownSavedPsbt(id))
+        );
+        assert!(control.list_saved_psbts().unwrap().psbts.is_empty());
+
+        ms.shutdown();
+    }
+
     #[test]
     fn rbf_psbt() {
         let dummy_op_a = bitcoin::OutPoint::from_str(
@@ -2143,7 +4265,7 @@ mod tests {
         dummy_bitcoind.txs.insert(dummy_txid_a, (dummy_tx_a, None));
         let ms = DummyLiana::new(dummy_bitcoind, DummyDatabase::new());
         let control = &ms.control();
-        let mut db_conn = control.db().lock().unwrap().connection();
+        let mut db_conn = control.db().lock().unwrap().connection().unwrap();
         // The spend needs to be in DB before using RBF.
         assert_eq!(
             control.rbf_psbt(&dummy_txid_a, true, None),
@@ -2386,11 +4508,14 @@ mod tests {
         let ms = DummyLiana::new(DummyBitcoind::new(), db);
 
         let control = &ms.control();
-        let mut db_conn = control.db.connection();
+        let mut db_conn = control.db.connection().unwrap();
         let txs: Vec<_> = txs_map.values().map(|(tx, _)| tx.clone()).collect();
         db_conn.new_txs(&txs);
 
-        let mut transactions = control.list_confirmed_transactions(0, 4, 10).transactions;
+        let mut transactions = control
+            .list_confirmed_transactions(0, 4, 10)
+            .unwrap()
+            .transactions;
         transactions.sort_by(|tx1, tx2| tx2.height.cmp(&tx1.height));
         assert_eq!(transactions.len(), 4);
 
@@ -2406,7 +4531,10 @@ mod tests {
         assert_eq!(transactions[3].time, Some(1));
         assert_eq!(transactions[3].tx, deposit1);
 
-        let mut transactions = control.list_confirmed_transactions(2, 3, 10).transactions;
+        let mut transactions = control
+            .list_confirmed_transactions(2, 3, 10)
+            .unwrap()
+            .transactions;
         transactions.sort_by(|tx1, tx2| tx2.height.cmp(&tx1.height));
         assert_eq!(transactions.len(), 2);
 
@@ -2414,7 +4542,10 @@ mod tests {
         assert_eq!(transactions[1].time, Some(2));
         assert_eq!(transactions[1].tx, deposit2);
 
-        let transactions = control.list_confirmed_transactions(2, 3, 1).transactions;
+        let transactions = control
+            .list_confirmed_transactions(2, 3, 1)
+            .unwrap()
+            .transactions;
         assert_eq!(transactions.len(), 1);
 
         assert_eq!(transactions[0].time, Some(3));
@@ -2522,7 +4653,7 @@ mod tests {
 
         let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
         let control = &ms.control();
-        let mut db_conn = control.db.connection();
+        let mut db_conn = control.db.connection().unwrap();
         let txs: Vec<_> = txs_map.values().map(|(tx, _)| tx.clone()).collect();
         db_conn.new_txs(&txs);
         // We need coins in the DB in order to get the block info for the transactions.
@@ -2547,12 +4678,16 @@ mod tests {
             }
         }
 
-        let transactions = control.list_transactions(&[tx1.txid()]).transactions;
+        let transactions = control
+            .list_transactions(&[tx1.txid()])
+            .unwrap()
+            .transactions;
         assert_eq!(transactions.len(), 1);
         assert_eq!(transactions[0].tx, tx1);
 
         let transactions = control
             .list_transactions(&[tx1.txid(), tx2.txid(), tx3.txid()])
+            .unwrap()
             .transactions;
         assert_eq!(transactions.len(), 3);
 
@@ -1,8 +1,17 @@
-use liana::descriptors::LianaDescriptor;
+use liana::{
+    descriptors::LianaDescriptor,
+    spend::{CoinSelectionAlgorithm, MAX_FEERATE},
+};
 
-use std::{fmt, net::SocketAddr, path::PathBuf, str::FromStr, time::Duration};
+use std::{
+    fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use miniscript::bitcoin::Network;
+use miniscript::bitcoin::{bip32, Network};
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
@@ -80,6 +89,35 @@ fn default_poll_interval() -> Duration {
     Duration::from_secs(30)
 }
 
+fn default_max_backoff() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
+/// The default highest derivation index up to which addresses may be revealed.
+pub const DEFAULT_MAX_DERIVATION_INDEX: u32 = 1_000_000;
+
+fn default_max_derivation_index() -> u32 {
+    DEFAULT_MAX_DERIVATION_INDEX
+}
+
+fn is_default_max_derivation_index(index: &u32) -> bool {
+    *index == DEFAULT_MAX_DERIVATION_INDEX
+}
+
+/// The default lowest feerate, in sat/vb, of the transactions we create.
+pub const DEFAULT_MIN_FEERATE: u64 = 1;
+
+fn default_min_feerate() -> u64 {
+    DEFAULT_MIN_FEERATE
+}
+
+fn is_default_min_feerate(feerate: &u64) -> bool {
+    *feerate == DEFAULT_MIN_FEERATE
+}
+
+/// Above this poll interval, the wallet would be slow to notice new transactions and blocks.
+const MAX_SANE_POLL_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
 /// Bitcoin backend config.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum BitcoinBackend {
@@ -128,6 +166,13 @@ pub struct ElectrumConfig {
     /// Include "ssl://" for SSL. otherwise TCP will be assumed.
     /// Can optionally prefix with "tcp://".
     pub addr: String,
+    /// The "host:port" of a SOCKS5 proxy to connect to the server through, for instance Tor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socks5_proxy: Option<String>,
+    /// Do not validate the TLS certificate of the server. Needed for servers using a
+    /// self-signed certificate, at the cost of not authenticating the server.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_certificate_validation: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -141,6 +186,35 @@ pub struct BitcoinConfig {
         default = "default_poll_interval"
     )]
     pub poll_interval_secs: Duration,
+    /// The maximum interval between polls while the Bitcoin backend can't be reached
+    #[serde(
+        deserialize_with = "deserialize_duration",
+        serialize_with = "serialize_duration",
+        default = "default_max_backoff"
+    )]
+    pub max_backoff_secs: Duration,
+}
+
+/// A threshold on the coins whose recovery path is about to become available. It is reached when
+/// the total value of the coins which are spendable through the recovery path within
+/// `blocks_before_expiry` blocks exceeds `min_amount_sat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RecoveryThreshold {
+    /// How many blocks before the recovery path becomes available to consider a coin.
+    pub blocks_before_expiry: u32,
+    /// The value, in satoshis, above which the threshold is reached.
+    pub min_amount_sat: u64,
+}
+
+/// Sources of fee estimates to query in addition to the Bitcoin backend.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FeeSourcesConfig {
+    /// Base URLs of Esplora servers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub esplora_urls: Vec<String>,
+    /// A URL returning fee estimates in the format of `mempool.space/api/v1/fees/recommended`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recommended_fees_url: Option<String>,
 }
 
 /// Static informations we require to operate
@@ -161,11 +235,40 @@ pub struct Config {
         serialize_with = "serialize_to_string"
     )]
     pub main_descriptor: LianaDescriptor,
+    /// The algorithm to use for selecting coins when none are specified for a spend. Can be
+    /// overridden for each `createspend` call.
+    #[serde(default, skip_serializing_if = "CoinSelectionAlgorithm::is_default")]
+    pub coin_selection: CoinSelectionAlgorithm,
     /// Settings for the Bitcoin interface
     pub bitcoin_config: BitcoinConfig,
     /// Settings specific to the Bitcoin backend.
     #[serde(flatten)]
     pub bitcoin_backend: Option<BitcoinBackend>,
+    /// Thresholds on the coins about to be spendable through the recovery path, for which to
+    /// raise a warning.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recovery_thresholds: Vec<RecoveryThreshold>,
+    /// Whether to pre-derive the addresses of the wallet in the background at startup, so the
+    /// first poll is faster on wallets with many revealed addresses.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub warm_derivation_cache: bool,
+    /// Sources of fee estimates to query in addition to the Bitcoin backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_sources: Option<FeeSourcesConfig>,
+    /// The highest derivation index up to which new addresses may be revealed. Revealing too many
+    /// addresses slows down every poll and rescan.
+    #[serde(
+        default = "default_max_derivation_index",
+        skip_serializing_if = "is_default_max_derivation_index"
+    )]
+    pub max_derivation_index: u32,
+    /// The lowest feerate, in sat/vb, of the transactions we create. A lower requested feerate
+    /// is raised to it, as well as to the minimum relay feerate of the Bitcoin backend.
+    #[serde(
+        default = "default_min_feerate",
+        skip_serializing_if = "is_default_min_feerate"
+    )]
+    pub min_feerate_sat_vb: u64,
 }
 
 impl Config {
@@ -180,6 +283,13 @@ pub enum ConfigError {
     FileNotFound,
     ReadingFile(String),
     UnexpectedDescriptor(Box<LianaDescriptor>),
+    InvalidDatadir(PathBuf, String),
+    InvalidPollInterval,
+    InvalidMaxDerivationIndex(u32),
+    InvalidMinFeerate(u64),
+    MissingBitcoinBackend,
+    InvalidElectrumAddress(String),
+    InvalidProxyAddress(String),
     Unexpected(String),
 }
 
@@ -194,6 +304,34 @@ impl std::fmt::Display for ConfigError {
                 "Unexpected descriptor '{}'. We only support wsh() descriptors for now.",
                 desc
             ),
+            Self::InvalidDatadir(path, e) => {
+                write!(f, "Invalid data directory '{}': {}", path.display(), e)
+            }
+            Self::InvalidPollInterval => write!(f, "The poll interval must not be zero."),
+            Self::InvalidMaxDerivationIndex(index) => write!(
+                f,
+                "Invalid maximum derivation index {}. It must be an unhardened index.",
+                index
+            ),
+            Self::InvalidMinFeerate(feerate) => write!(
+                f,
+                "Invalid minimum feerate {} sat/vb. It must be between 1 and {}.",
+                feerate, MAX_FEERATE
+            ),
+            Self::MissingBitcoinBackend => write!(
+                f,
+                "No Bitcoin backend: one of 'bitcoind_config' or 'electrum_config' must be set."
+            ),
+            Self::InvalidElectrumAddress(addr) => write!(
+                f,
+                "Invalid Electrum server address '{}'. Expected '[ssl://|tcp://]host:port'.",
+                addr
+            ),
+            Self::InvalidProxyAddress(addr) => write!(
+                f,
+                "Invalid SOCKS5 proxy address '{}'. Expected 'host:port'.",
+                addr
+            ),
             Self::Unexpected(e) => write!(f, "Configuration error: {}", e),
         }
     }
@@ -210,6 +348,42 @@ impl From<std::io::Error> for ConfigError {
 
 impl std::error::Error for ConfigError {}
 
+/// An unusual setting, which doesn't prevent the daemon from starting.
+#[derive(PartialEq, Eq, Debug)]
+pub enum ConfigWarning {
+    DefaultDatadir(PathBuf),
+    LongPollInterval(Duration),
+    MissingCookieFile(PathBuf),
+    CertificateNotValidated,
+}
+
+impl std::fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self {
+            Self::DefaultDatadir(path) => write!(
+                f,
+                "No data directory set, the default one at '{}' will be used.",
+                path.display()
+            ),
+            Self::LongPollInterval(interval) => write!(
+                f,
+                "The poll interval of {} seconds is unusually long, new transactions and blocks \
+                 would be noticed late.",
+                interval.as_secs()
+            ),
+            Self::MissingCookieFile(path) => write!(
+                f,
+                "The bitcoind cookie file '{}' does not exist (yet?).",
+                path.display()
+            ),
+            Self::CertificateNotValidated => write!(
+                f,
+                "The TLS certificate of the Electrum server is not validated."
+            ),
+        }
+    }
+}
+
 /// Get the absolute path to the liana configuration folder.
 ///
 /// It's a "liana/<network>/" directory in the XDG standard configuration directory for
@@ -262,31 +436,166 @@ impl Config {
         Ok(config)
     }
 
+    /// Check the xpubs of the main descriptor are for our network, returning what they are for
+    /// otherwise. Only mainnet keys can be told apart from keys for the test networks, which all
+    /// share the same version bytes.
+    pub fn descriptor_network_mismatch(&self) -> Option<String> {
+        let (keys_net, other_keys_net) = match self.bitcoin_config.network {
+            Network::Bitcoin => (Network::Bitcoin, Network::Testnet),
+            _ => (Network::Testnet, Network::Bitcoin),
+        };
+        if self.main_descriptor.all_xpubs_net_is(keys_net) {
+            None
+        } else if self.main_descriptor.all_xpubs_net_is(other_keys_net) {
+            Some(format!("a descriptor for '{}'", other_keys_net))
+        } else {
+            Some("a descriptor with keys for different networks".to_string())
+        }
+    }
+
     /// Make sure the settings are sane.
     pub fn check(&self) -> Result<(), ConfigError> {
-        // Check the network of the xpubs in the descriptors
-        let expected_network = match self.bitcoin_config.network {
-            Network::Bitcoin => Network::Bitcoin,
-            _ => Network::Testnet,
-        };
-        if !self.main_descriptor.all_xpubs_net_is(expected_network) {
+        if let Some(found) = self.descriptor_network_mismatch() {
             return Err(ConfigError::Unexpected(format!(
-                "Our bitcoin network is {} but one xpub is not for network {}",
-                self.bitcoin_config.network, expected_network
+                "Our bitcoin network is {} but the configuration has {}",
+                self.bitcoin_config.network, found
             )));
         }
 
+        if bip32::ChildNumber::from_normal_idx(self.max_derivation_index).is_err() {
+            return Err(ConfigError::InvalidMaxDerivationIndex(
+                self.max_derivation_index,
+            ));
+        }
+
+        if !(1..=MAX_FEERATE).contains(&self.min_feerate_sat_vb) {
+            return Err(ConfigError::InvalidMinFeerate(self.min_feerate_sat_vb));
+        }
+
         // TODO: check the semantics of the main descriptor
 
         Ok(())
     }
+
+    /// Check all the settings without starting the daemon: nothing is created and no connection
+    /// is attempted. Returns the unusual settings to warn about, or all the errors which would
+    /// prevent the daemon from starting.
+    pub fn validate_without_starting(&self) -> Result<Vec<ConfigWarning>, Vec<ConfigError>> {
+        let mut warnings = Vec::new();
+        let mut errors = Vec::new();
+
+        if let Err(e) = self.check() {
+            errors.push(e);
+        }
+
+        match self.data_dir() {
+            None => errors.push(ConfigError::DatadirNotFound),
+            Some(data_dir) => {
+                if self.data_dir.is_none() {
+                    warnings.push(ConfigWarning::DefaultDatadir(data_dir.clone()));
+                }
+                // The data directory is created at startup if it doesn't exist yet, in which case
+                // its parent must.
+                let dir = if data_dir.exists() {
+                    Some(data_dir.as_path())
+                } else {
+                    data_dir.parent().map(|p| {
+                        if p == Path::new("") {
+                            Path::new(".")
+                        } else {
+                            p
+                        }
+                    })
+                };
+                match dir {
+                    Some(dir) => {
+                        if let Err(e) = check_writable_dir(dir) {
+                            errors.push(ConfigError::InvalidDatadir(data_dir.clone(), e));
+                        }
+                    }
+                    None => errors.push(ConfigError::InvalidDatadir(
+                        data_dir.clone(),
+                        "no parent directory".to_string(),
+                    )),
+                }
+            }
+        }
+
+        let poll_interval = self.bitcoin_config.poll_interval_secs;
+        if poll_interval.as_secs() == 0 {
+            errors.push(ConfigError::InvalidPollInterval);
+        } else if poll_interval > MAX_SANE_POLL_INTERVAL {
+            warnings.push(ConfigWarning::LongPollInterval(poll_interval));
+        }
+
+        match &self.bitcoin_backend {
+            None => errors.push(ConfigError::MissingBitcoinBackend),
+            Some(BitcoinBackend::Bitcoind(bitcoind_config)) => {
+                // The address was already parsed. The cookie file is only created by bitcoind
+                // once it's running.
+                if let BitcoindRpcAuth::CookieFile(path) = &bitcoind_config.rpc_auth {
+                    if !path.exists() {
+                        warnings.push(ConfigWarning::MissingCookieFile(path.clone()));
+                    }
+                }
+            }
+            Some(BitcoinBackend::Electrum(electrum_config)) => {
+                let addr = &electrum_config.addr;
+                let host_port = addr
+                    .strip_prefix("ssl://")
+                    .or_else(|| addr.strip_prefix("tcp://"))
+                    .unwrap_or(addr);
+                if !is_host_port(host_port) {
+                    errors.push(ConfigError::InvalidElectrumAddress(addr.clone()));
+                }
+                if let Some(proxy) = &electrum_config.socks5_proxy {
+                    if !is_host_port(proxy) {
+                        errors.push(ConfigError::InvalidProxyAddress(proxy.clone()));
+                    }
+                }
+                if electrum_config.skip_certificate_validation {
+                    warnings.push(ConfigWarning::CertificateNotValidated);
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(warnings)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+// Make sure the path is a directory we can write into.
+fn check_writable_dir(path: &Path) -> Result<(), String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("'{}': {}", path.display(), e))?;
+    if !metadata.is_dir() {
+        Err(format!("'{}' is not a directory", path.display()))
+    } else if metadata.permissions().readonly() {
+        Err(format!("'{}' is not writable", path.display()))
+    } else {
+        Ok(())
+    }
+}
+
+// Whether the string is a "host:port" with a non-empty host.
+fn is_host_port(s: &str) -> bool {
+    s.rsplit_once(':')
+        .map(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+        .unwrap_or(false)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{path::PathBuf, time::Duration};
+
+    use miniscript::bitcoin::Network;
 
-    use super::{config_file_path, BitcoindConfig, BitcoindRpcAuth, Config};
+    use super::{
+        config_file_path, BitcoinBackend, BitcoindConfig, BitcoindRpcAuth, CoinSelectionAlgorithm,
+        Config, ConfigError, ConfigWarning, ElectrumConfig, RecoveryThreshold,
+    };
 
     // Test the format of the configuration file
     #[test]
@@ -347,6 +656,64 @@ mod tests {
             assert_eq!(toml_str, serialized);
         }
 
+        // A valid, round-tripping, config with a non-default coin selection algorithm.
+        {
+            let toml_str = r#"
+            data_dir = '/home/wizardsardine/custom/folder/'
+            log_level = 'TRACE'
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs'
+            coin_selection = 'fifo'
+
+            [bitcoin_config]
+            network = 'bitcoin'
+            poll_interval_secs = 18
+
+            [bitcoind_config]
+            cookie_path = '/home/user/.bitcoin/.cookie'
+            addr = '127.0.0.1:8332'
+            "#.trim_start().replace("            ", "");
+            let parsed = toml::from_str::<Config>(&toml_str).expect("Deserializing toml_str");
+            assert_eq!(parsed.coin_selection, CoinSelectionAlgorithm::Fifo);
+            let serialized = toml::to_string_pretty(&parsed).expect("Serializing to toml");
+            assert_eq!(toml_str, serialized);
+        }
+
+        // A valid config with recovery thresholds.
+        {
+            let toml_str = r#"
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs'
+
+            [bitcoin_config]
+            network = 'bitcoin'
+
+            [bitcoind_config]
+            cookie_path = '/home/user/.bitcoin/.cookie'
+            addr = '127.0.0.1:8332'
+
+            [[recovery_thresholds]]
+            blocks_before_expiry = 144
+            min_amount_sat = 100000
+
+            [[recovery_thresholds]]
+            blocks_before_expiry = 1008
+            min_amount_sat = 10000000
+            "#.trim_start().replace("            ", "");
+            let parsed = toml::from_str::<Config>(&toml_str).expect("Deserializing toml_str");
+            assert_eq!(
+                parsed.recovery_thresholds,
+                vec![
+                    RecoveryThreshold {
+                        blocks_before_expiry: 144,
+                        min_amount_sat: 100_000,
+                    },
+                    RecoveryThreshold {
+                        blocks_before_expiry: 1008,
+                        min_amount_sat: 10_000_000,
+                    }
+                ]
+            );
+        }
+
         // A valid, round-tripping, config with `auth` instead of `cookie_path`
         {
             let toml_str = r#"
@@ -479,6 +846,59 @@ mod tests {
             .contains("`auth` must be 'user:password'"));
     }
 
+    #[test]
+    fn validate_config() {
+        let data_dir = std::env::temp_dir().join("lianad-validate-config");
+        let toml_str = format!(
+            r#"
+            data_dir = '{}'
+            main_descriptor = 'wsh(andor(pk([aabbccdd]tpubDEN9WSToTyy9ZQfaYqSKfmVqmq1VVLNtYfj3Vkqh67et57eJ5sTKZQBkHqSwPUsoSskJeaYnPttHe2VrkCsKA27kUaN9SDc5zhqeLzKa1rr/<0;1>/*),older(10000),pk([aabbccdd]tpubD8LYfn6njiA2inCoxwM7EuN3cuLVcaHAwLYeups13dpevd3nHLRdK9NdQksWXrhLQVxcUZRpnp5CkJ1FhE61WRAsHxDNAkvGkoQkAeWDYjV/<0;1>/*)))#dw4ulnrs'
+
+            [bitcoin_config]
+            network = 'testnet'
+
+            [bitcoind_config]
+            auth = 'my_user:my_password'
+            addr = '127.0.0.1:18332'
+            "#,
+            data_dir.display()
+        );
+        let mut config = toml::from_str::<Config>(&toml_str).expect("Deserializing toml_str");
+        assert_eq!(config.validate_without_starting(), Ok(vec![]));
+
+        config.bitcoin_config.poll_interval_secs = Duration::from_secs(3600);
+        config.bitcoin_backend = Some(BitcoinBackend::Bitcoind(BitcoindConfig {
+            rpc_auth: BitcoindRpcAuth::CookieFile(data_dir.join("missing_cookie")),
+            addr: "127.0.0.1:18332".parse().unwrap(),
+        }));
+        assert_eq!(
+            config.validate_without_starting(),
+            Ok(vec![
+                ConfigWarning::LongPollInterval(Duration::from_secs(3600)),
+                ConfigWarning::MissingCookieFile(data_dir.join("missing_cookie"))
+            ])
+        );
+
+        // All the errors are reported at once.
+        config.data_dir = Some(data_dir.join("missing_parent").join("datadir"));
+        config.bitcoin_config.network = Network::Bitcoin;
+        config.bitcoin_config.poll_interval_secs = Duration::from_secs(0);
+        config.bitcoin_backend = Some(BitcoinBackend::Electrum(ElectrumConfig {
+            addr: "ssl://electrum.blockstream.info".to_string(),
+            socks5_proxy: Some("127.0.0.1:9050".to_string()),
+            skip_certificate_validation: false,
+        }));
+        let errors = config.validate_without_starting().unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(errors[0], ConfigError::Unexpected(_)));
+        assert!(matches!(errors[1], ConfigError::InvalidDatadir(..)));
+        assert_eq!(errors[2], ConfigError::InvalidPollInterval);
+        assert_eq!(
+            errors[3],
+            ConfigError::InvalidElectrumAddress("ssl://electrum.blockstream.info".to_string())
+        );
+    }
+
     #[test]
     fn config_directory() {
         let filepath = config_file_path().expect("Getting config file path");
@@ -7,17 +7,17 @@ pub mod sqlite;
 use crate::{
     bitcoin::BlockChainTip,
     database::sqlite::{
-        schema::{DbBlockInfo, DbCoin, DbTip},
-        SqliteConn, SqliteDb,
+        schema::{DbBlockInfo, DbCoin, DbSavedPsbt, DbTip},
+        SqliteConn, SqliteDb, SqliteDbError,
     },
 };
 
 use std::{
     collections::{HashMap, HashSet},
-    fmt::Display,
+    fmt::{self, Display},
     iter::FromIterator,
     str::FromStr,
-    sync,
+    sync, time,
 };
 
 use miniscript::bitcoin::{self, bip32, psbt::Psbt, secp256k1};
@@ -39,19 +39,41 @@ pub struct Wallet {
     pub last_poll_timestamp: Option<u32>,
 }
 
+/// All the connections to the database were in use for longer than the given duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseBusy(pub time::Duration);
+
+impl fmt::Display for DatabaseBusy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "No database connection available after {} seconds.",
+            self.0.as_secs_f64()
+        )
+    }
+}
+
+impl std::error::Error for DatabaseBusy {}
+
 pub trait DatabaseInterface: Send {
-    fn connection(&self) -> Box<dyn DatabaseConnection>;
+    /// Get a connection to the database. Fails if none was available in time, in which case the
+    /// caller may retry later.
+    fn connection(&self) -> Result<Box<dyn DatabaseConnection>, DatabaseBusy>;
 }
 
 impl DatabaseInterface for SqliteDb {
-    fn connection(&self) -> Box<dyn DatabaseConnection> {
-        Box::new(self.connection().expect("Database must be available"))
+    fn connection(&self) -> Result<Box<dyn DatabaseConnection>, DatabaseBusy> {
+        match self.connection() {
+            Ok(conn) => Ok(Box::new(conn)),
+            Err(SqliteDbError::PoolTimeout(timeout)) => Err(DatabaseBusy(timeout)),
+            Err(e) => panic!("Database must be available: {}", e),
+        }
     }
 }
 
 // FIXME: do we need to repeat the entire trait implemenation? Isn't there a nicer way?
 impl DatabaseInterface for sync::Arc<sync::Mutex<dyn DatabaseInterface>> {
-    fn connection(&self) -> Box<dyn DatabaseConnection> {
+    fn connection(&self) -> Result<Box<dyn DatabaseConnection>, DatabaseBusy> {
         self.lock().unwrap().connection()
     }
 }
@@ -82,6 +104,10 @@ pub trait DatabaseConnection {
         secp: &secp256k1::Secp256k1<secp256k1::VerifyOnly>,
     );
 
+    /// Set a lower derivation index for the next receiving address, if it is lower than the
+    /// current one. The addresses already derived are kept in the database.
+    fn lower_receive_index(&mut self, index: bip32::ChildNumber);
+
     /// Get the derivation index for the next change address
     fn change_index(&mut self) -> bip32::ChildNumber;
 
@@ -157,18 +183,49 @@ pub trait DatabaseConnection {
     /// Insert a new Spend transaction or replace an existing one.
     fn store_spend(&mut self, psbt: &Psbt);
 
-    /// List all existing Spend transactions, along with an optional last update timestamp.
-    fn list_spend(&mut self) -> Vec<(Psbt, Option<u32>)>;
+    /// Record a Spend transaction was just broadcast.
+    fn set_spend_broadcast(&mut self, txid: &bitcoin::Txid);
+
+    /// List all existing Spend transactions, along with an optional last update timestamp and
+    /// an optional broadcast timestamp.
+    fn list_spend(&mut self) -> Vec<(Psbt, Option<u32>, Option<u32>)>;
 
     /// Delete a Spend transaction from database.
     fn delete_spend(&mut self, txid: &bitcoin::Txid);
 
+    /// Save a PSBT to be completed later. Returns the identifier of the saved PSBT.
+    fn save_psbt(&mut self, psbt: &Psbt, label: &str) -> i64;
+
+    /// List all saved PSBTs, ordered by insertion.
+    fn list_saved_psbts(&mut self) -> Vec<SavedPsbt>;
+
+    /// Get a saved PSBT by its identifier.
+    fn get_saved_psbt(&mut self, id: i64) -> Option<SavedPsbt>;
+
+    /// Delete a saved PSBT. Returns false if there was no PSBT with this identifier.
+    fn delete_saved_psbt(&mut self, id: i64) -> bool;
+
     /// Update, for a set of items (as key), their label (as value). A `None` value deletes the
     /// label.
     fn update_labels(&mut self, items: &HashMap<LabelItem, Option<String>>);
 
     fn labels(&mut self, labels: &HashSet<LabelItem>) -> HashMap<String, String>;
 
+    /// Get the labels of the items of the given kind, if any, among the given ones. An empty set
+    /// of items stands for all the labelled items.
+    fn list_labels(
+        &mut self,
+        kind: Option<LabelKind>,
+        items: &HashSet<LabelItem>,
+    ) -> HashMap<String, String>;
+
+    /// Update, for a set of outpoints (as key), their privacy tag (as value). A `None` value
+    /// removes the tag.
+    fn update_coin_tags(&mut self, tags: &HashMap<bitcoin::OutPoint, Option<String>>);
+
+    /// Get the privacy tags of all the tagged outpoints.
+    fn coin_tags(&mut self) -> HashMap<bitcoin::OutPoint, String>;
+
     /// Mark the given tip as the new best seen block. Update stored data accordingly.
     fn rollback_tip(&mut self, new_tip: &BlockChainTip);
 
@@ -190,6 +247,13 @@ pub trait DatabaseConnection {
         &mut self,
         txids: &[bitcoin::Txid],
     ) -> Vec<(bitcoin::Transaction, Option<i32>, Option<u32>)>;
+
+    /// Get the size of the database and how much of it is unused.
+    fn size_info(&mut self) -> DbSizeInfo;
+
+    /// Rebuild the database to reclaim the unused space, and refresh the statistics used to plan
+    /// queries. Waits for other connections to be done.
+    fn maintain(&mut self);
 }
 
 impl DatabaseConnection for SqliteConn {
@@ -239,6 +303,10 @@ impl DatabaseConnection for SqliteConn {
         self.set_derivation_index(index, false, secp)
     }
 
+    fn lower_receive_index(&mut self, index: bip32::ChildNumber) {
+        self.lower_deposit_derivation_index(index)
+    }
+
     fn change_index(&mut self) -> bip32::ChildNumber {
         self.wallet().change_index
     }
@@ -347,10 +415,14 @@ impl DatabaseConnection for SqliteConn {
         self.store_spend(psbt)
     }
 
-    fn list_spend(&mut self) -> Vec<(Psbt, Option<u32>)> {
+    fn set_spend_broadcast(&mut self, txid: &bitcoin::Txid) {
+        self.set_spend_broadcast(txid)
+    }
+
+    fn list_spend(&mut self) -> Vec<(Psbt, Option<u32>, Option<u32>)> {
         self.list_spend()
             .into_iter()
-            .map(|db_spend| (db_spend.psbt, db_spend.updated_at))
+            .map(|db_spend| (db_spend.psbt, db_spend.updated_at, db_spend.broadcast_time))
             .collect()
     }
 
@@ -358,6 +430,25 @@ impl DatabaseConnection for SqliteConn {
         self.delete_spend(txid)
     }
 
+    fn save_psbt(&mut self, psbt: &Psbt, label: &str) -> i64 {
+        self.save_psbt(psbt, label)
+    }
+
+    fn list_saved_psbts(&mut self) -> Vec<SavedPsbt> {
+        self.list_saved_psbts()
+            .into_iter()
+            .map(SavedPsbt::from)
+            .collect()
+    }
+
+    fn get_saved_psbt(&mut self, id: i64) -> Option<SavedPsbt> {
+        self.db_saved_psbt(id).map(SavedPsbt::from)
+    }
+
+    fn delete_saved_psbt(&mut self, id: i64) -> bool {
+        self.delete_saved_psbt(id)
+    }
+
     fn update_labels(&mut self, items: &HashMap<LabelItem, Option<String>>) {
         self.update_labels(items)
     }
@@ -367,6 +458,26 @@ impl DatabaseConnection for SqliteConn {
         HashMap::from_iter(labels.into_iter().map(|label| (label.item, label.value)))
     }
 
+    fn list_labels(
+        &mut self,
+        kind: Option<LabelKind>,
+        items: &HashSet<LabelItem>,
+    ) -> HashMap<String, String> {
+        let labels = self.db_list_labels(kind, items);
+        HashMap::from_iter(labels.into_iter().map(|label| (label.item, label.value)))
+    }
+
+    fn update_coin_tags(&mut self, tags: &HashMap<bitcoin::OutPoint, Option<String>>) {
+        self.update_coin_tags(tags)
+    }
+
+    fn coin_tags(&mut self) -> HashMap<bitcoin::OutPoint, String> {
+        self.db_coin_tags()
+            .into_iter()
+            .map(|t| (t.outpoint, t.tag))
+            .collect()
+    }
+
     fn rollback_tip(&mut self, new_tip: &BlockChainTip) {
         self.rollback_tip(new_tip)
     }
@@ -403,6 +514,37 @@ impl DatabaseConnection for SqliteConn {
             })
             .collect()
     }
+
+    fn size_info(&mut self) -> DbSizeInfo {
+        self.db_size_info()
+    }
+
+    fn maintain(&mut self) {
+        self.vacuum()
+    }
+}
+
+/// The size of the database, as a number of pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbSizeInfo {
+    /// Size of a page in bytes.
+    pub page_size: u64,
+    /// Total number of pages.
+    pub page_count: u64,
+    /// Number of unused pages, which can be reclaimed through maintenance.
+    pub free_pages: u64,
+}
+
+impl DbSizeInfo {
+    /// The total size in bytes.
+    pub fn size(&self) -> u64 {
+        self.page_size * self.page_count
+    }
+
+    /// The size in bytes of the unused pages.
+    pub fn free_size(&self) -> u64 {
+        self.page_size * self.free_pages
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -420,6 +562,34 @@ impl From<DbBlockInfo> for BlockInfo {
     }
 }
 
+/// A PSBT saved by the user to be completed later.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedPsbt {
+    pub id: i64,
+    /// Timestamp at which the PSBT was saved.
+    pub created_at: u32,
+    pub psbt: Psbt,
+    pub label: String,
+}
+
+impl From<DbSavedPsbt> for SavedPsbt {
+    fn from(db_saved: DbSavedPsbt) -> SavedPsbt {
+        let DbSavedPsbt {
+            id,
+            created_at,
+            psbt,
+            label,
+            ..
+        } = db_saved;
+        SavedPsbt {
+            id,
+            created_at,
+            psbt,
+            label,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Coin {
     pub outpoint: bitcoin::OutPoint,
@@ -507,6 +677,35 @@ impl CoinStatus {
     }
 }
 
+/// The kind of item a label is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LabelKind {
+    Address,
+    Txid,
+    OutPoint,
+}
+
+impl LabelKind {
+    pub fn from_arg(s: &str) -> Option<LabelKind> {
+        match s {
+            "address" => Some(LabelKind::Address),
+            "tx" => Some(LabelKind::Txid),
+            "outpoint" => Some(LabelKind::OutPoint),
+            _ => None,
+        }
+    }
+
+    /// Converts a `LabelKind` to its equivalent argument name as used in the `listlabels` RPC
+    /// command.
+    pub fn to_arg(&self) -> &'static str {
+        match self {
+            LabelKind::Address => "address",
+            LabelKind::Txid => "tx",
+            LabelKind::OutPoint => "outpoint",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LabelItem {
     Address(bitcoin::Address),
@@ -543,6 +742,14 @@ impl Display for LabelItem {
 }
 
 impl LabelItem {
+    pub fn kind(&self) -> LabelKind {
+        match self {
+            LabelItem::Address(_) => LabelKind::Address,
+            LabelItem::Txid(_) => LabelKind::Txid,
+            LabelItem::OutPoint(_) => LabelKind::OutPoint,
+        }
+    }
+
     pub fn from_str(s: &str, network: bitcoin::Network) -> Option<LabelItem> {
         if let Ok(addr) = bitcoin::Address::from_str(s) {
             if !addr.is_valid_for_network(network) {
@@ -564,6 +771,14 @@ impl LabelItem {
 mod tests {
     use super::*;
 
+    #[test]
+    fn label_kind_as_arg() {
+        for kind in [LabelKind::Address, LabelKind::Txid, LabelKind::OutPoint] {
+            assert_eq!(LabelKind::from_arg(kind.to_arg()), Some(kind));
+        }
+        assert_eq!(LabelKind::from_arg("txid"), None);
+    }
+
     #[test]
     fn coin_status_as_arg() {
         assert_eq!(
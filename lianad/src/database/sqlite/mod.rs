@@ -7,23 +7,27 @@
 //! We leverage SQLite's `unlock_notify` feature to synchronize writes accross connection. More
 //! about it at https://sqlite.org/unlock_notify.html.
 
+mod pool;
 pub mod schema;
 mod utils;
 
+pub use utils::LOOK_AHEAD_LIMIT;
+
 use crate::{
     bitcoin::BlockChainTip,
     database::{
         sqlite::{
+            pool::{ConnectionPool, PooledConnection},
             schema::{
-                DbAddress, DbCoin, DbLabel, DbLabelledKind, DbSpendTransaction, DbTip, DbWallet,
-                DbWalletTransaction, SCHEMA,
+                DbAddress, DbCoin, DbCoinTag, DbLabel, DbLabelledKind, DbSavedPsbt,
+                DbSpendTransaction, DbTip, DbWallet, DbWalletTransaction, SCHEMA,
             },
             utils::{
-                create_fresh_db, curr_timestamp, db_exec, db_query, db_tx_query, db_version,
-                maybe_apply_migration, LOOK_AHEAD_LIMIT,
+                create_fresh_db, curr_timestamp, db_exec, db_query, db_query_cached, db_tx_query,
+                db_version, maybe_apply_migration,
             },
         },
-        Coin, CoinStatus, LabelItem,
+        Coin, CoinStatus, DbSizeInfo, LabelItem, LabelKind,
     },
 };
 use liana::descriptors::LianaDescriptor;
@@ -33,6 +37,8 @@ use std::{
     collections::{HashMap, HashSet},
     convert::TryInto,
     fmt, io, path,
+    sync::Arc,
+    time,
 };
 
 use miniscript::bitcoin::{
@@ -43,7 +49,13 @@ use miniscript::bitcoin::{
     secp256k1,
 };
 
-const DB_VERSION: i64 = 8;
+const DB_VERSION: i64 = 11;
+
+/// Number of connections shared by the users of the database.
+const POOL_SIZE: usize = 8;
+
+/// How long to wait for a connection to be released when they are all in use.
+const POOL_TIMEOUT: time::Duration = time::Duration::from_secs(60);
 
 /// Last database version for which Bitcoin transactions were not stored in database. In practice
 /// this meant we relied on the bitcoind watchonly wallet to store them for us.
@@ -57,6 +69,8 @@ pub enum SqliteDbError {
     InvalidNetwork(bitcoin::Network),
     DescriptorMismatch(Box<LianaDescriptor>),
     Rusqlite(rusqlite::Error),
+    /// No connection was released in time.
+    PoolTimeout(time::Duration),
 }
 
 impl std::fmt::Display for SqliteDbError {
@@ -78,6 +92,11 @@ impl std::fmt::Display for SqliteDbError {
                 write!(f, "Database descriptor mismatch: '{}'.", desc)
             }
             SqliteDbError::Rusqlite(e) => write!(f, "SQLite error: '{}'", e),
+            SqliteDbError::PoolTimeout(timeout) => write!(
+                f,
+                "No database connection available after {} seconds.",
+                timeout.as_secs_f64()
+            ),
         }
     }
 }
@@ -136,9 +155,10 @@ impl FreshDbOptions {
     }
 }
 
+/// A handle to the database. Clones share the same pool of connections.
 #[derive(Debug, Clone)]
 pub struct SqliteDb {
-    db_path: path::PathBuf,
+    pool: Arc<ConnectionPool>,
 }
 
 impl SqliteDb {
@@ -161,7 +181,17 @@ impl SqliteDb {
 
         log::info!("Checking if the database needs upgrading.");
 
-        Ok(SqliteDb { db_path })
+        Ok(SqliteDb {
+            pool: ConnectionPool::new(db_path, POOL_SIZE, POOL_TIMEOUT),
+        })
+    }
+
+    /// A handle to the same database with its own single connection, for a user which must not
+    /// compete with the others for the shared pool, such as the Bitcoin poller.
+    pub fn dedicated(&self) -> SqliteDb {
+        SqliteDb {
+            pool: ConnectionPool::new(self.pool.db_path().to_path_buf(), 1, POOL_TIMEOUT),
+        }
     }
 
     /// If the database version is older than expected, migrate it to the current version. If
@@ -171,14 +201,15 @@ impl SqliteDb {
         &self,
         bitcoin_txs: &[bitcoin::Transaction],
     ) -> Result<(), SqliteDbError> {
-        maybe_apply_migration(&self.db_path, bitcoin_txs)
+        maybe_apply_migration(self.pool.db_path(), bitcoin_txs)
     }
 
-    /// Get a new connection to the database.
+    /// Get a connection to the database from the pool, waiting for one to be released if they
+    /// are all in use. It is returned to the pool once dropped.
     pub fn connection(&self) -> Result<SqliteConn, SqliteDbError> {
-        let conn = rusqlite::Connection::open(&self.db_path)?;
-        conn.busy_timeout(std::time::Duration::from_secs(60))?;
-        Ok(SqliteConn { conn })
+        Ok(SqliteConn {
+            conn: self.pool.get()?,
+        })
     }
 
     /// Perform startup sanity checks.
@@ -217,7 +248,7 @@ impl SqliteDb {
 const WALLET_ID: i64 = 1;
 
 pub struct SqliteConn {
-    conn: rusqlite::Connection,
+    conn: PooledConnection,
 }
 
 impl SqliteConn {
@@ -308,7 +339,8 @@ impl SqliteConn {
 
             // Now if this new index is higher than the highest of our current derivation indexes,
             // populate the addresses mapping for derivation indexes between our previous "gap
-            // limit index" and the new one.
+            // limit index" and the new one. Some may already be there if the receive index was
+            // lowered.
             let curr_highest_index = cmp::max(
                 db_wallet.deposit_derivation_index,
                 db_wallet.change_derivation_index,
@@ -322,7 +354,7 @@ impl SqliteConn {
                     let receive_addr = receive_desc.derive(la_index.into(), secp).address(network);
                     let change_addr = change_desc.derive(la_index.into(), secp).address(network);
                     db_tx.execute(
-                        "INSERT INTO addresses (receive_address, change_address, derivation_index) VALUES (?1, ?2, ?3)",
+                        "INSERT OR IGNORE INTO addresses (receive_address, change_address, derivation_index) VALUES (?1, ?2, ?3)",
                         rusqlite::params![receive_addr.to_string(), change_addr.to_string(), la_index],
                     )?;
                 }
@@ -334,6 +366,21 @@ impl SqliteConn {
         .expect("Database must be available")
     }
 
+    /// Set the derivation index for the next receiving address, if lower than the current one.
+    /// The addresses mapping is left untouched, it's only ever extended.
+    pub fn lower_deposit_derivation_index(&mut self, index: bip32::ChildNumber) {
+        let index_u32: u32 = index.into();
+        db_exec(&mut self.conn, |db_tx| {
+            db_tx
+                .execute(
+                    "UPDATE wallets SET deposit_derivation_index = (?1) WHERE deposit_derivation_index > (?1)",
+                    rusqlite::params![index_u32],
+                )
+                .map(|_| ())
+        })
+        .expect("Database must be available")
+    }
+
     pub fn set_wallet_rescan_timestamp(&mut self, timestamp: u32) {
         db_exec(&mut self.conn, |db_tx| {
             // NOTE: this will need to be updated if we ever implement multi-wallet support
@@ -442,9 +489,17 @@ impl SqliteConn {
             String::new()
         };
         let query = format!("SELECT * FROM coins{}", where_clause);
-        db_query(&mut self.conn, &query, rusqlite::params![], |row| {
-            row.try_into()
-        })
+        // The coins are listed by status all the time, but the queries for a set of outpoints
+        // are all different and would only evict the others from the cache.
+        if outpoints.is_empty() {
+            db_query_cached(&mut self.conn, &query, rusqlite::params![], |row| {
+                row.try_into()
+            })
+        } else {
+            db_query(&mut self.conn, &query, rusqlite::params![], |row| {
+                row.try_into()
+            })
+        }
         .expect("Db must not fail")
     }
 
@@ -624,6 +679,18 @@ impl SqliteConn {
         .expect("Db must not fail");
     }
 
+    /// Record the Spend transaction was just broadcast.
+    pub fn set_spend_broadcast(&mut self, txid: &bitcoin::Txid) {
+        db_exec(&mut self.conn, |db_tx| {
+            db_tx.execute(
+                "UPDATE spend_transactions SET broadcast_time = ?1 WHERE txid = ?2",
+                rusqlite::params![curr_timestamp(), txid[..].to_vec()],
+            )?;
+            Ok(())
+        })
+        .expect("Db must not fail");
+    }
+
     pub fn list_spend(&mut self) -> Vec<DbSpendTransaction> {
         db_query(
             &mut self.conn,
@@ -664,20 +731,80 @@ impl SqliteConn {
     }
 
     pub fn db_labels(&mut self, items: &HashSet<LabelItem>) -> Vec<DbLabel> {
-        let query = format!(
-            "SELECT * FROM labels where item in ({})",
-            items
-                .iter()
-                .map(|a| format!("'{}'", a))
-                .collect::<Vec<String>>()
-                .join(",")
-        );
-        db_query(&mut self.conn, &query, rusqlite::params![], |row| {
-            row.try_into()
+        // Pass the items as a JSON array for the statement to be the same for any set of items.
+        let items =
+            serde_json::to_string(&items.iter().map(|a| a.to_string()).collect::<Vec<String>>())
+                .expect("Serializing a list of strings must not fail");
+        db_query_cached(
+            &mut self.conn,
+            "SELECT * FROM labels WHERE item IN (SELECT value FROM json_each(?1))",
+            rusqlite::params![items],
+            |row| row.try_into(),
+        )
+        .expect("Db must not fail")
+    }
+
+    /// Get the labels of the items of the given kind, if any, among the given ones. An empty set
+    /// of items stands for all the labelled items.
+    pub fn db_list_labels(
+        &mut self,
+        kind: Option<LabelKind>,
+        items: &HashSet<LabelItem>,
+    ) -> Vec<DbLabel> {
+        let kind: Option<i64> = kind
+            .map(|kind| match kind {
+                LabelKind::Address => DbLabelledKind::Address,
+                LabelKind::Txid => DbLabelledKind::Txid,
+                LabelKind::OutPoint => DbLabelledKind::OutPoint,
+            })
+            .map(|kind| kind as i64);
+        let items =
+            serde_json::to_string(&items.iter().map(|a| a.to_string()).collect::<Vec<String>>())
+                .expect("Serializing a list of strings must not fail");
+        db_query_cached(
+            &mut self.conn,
+            "SELECT * FROM labels WHERE wallet_id = ?1 \
+             AND (?2 IS NULL OR item_kind = ?2) \
+             AND (json_array_length(?3) = 0 OR item IN (SELECT value FROM json_each(?3)))",
+            rusqlite::params![WALLET_ID, kind, items],
+            |row| row.try_into(),
+        )
+        .expect("Db must not fail")
+    }
+
+    /// Update, for a set of outpoints (as key), their privacy tag (as value). A `None` value
+    /// removes the tag.
+    pub fn update_coin_tags(&mut self, tags: &HashMap<bitcoin::OutPoint, Option<String>>) {
+        db_exec(&mut self.conn, |db_tx| {
+            for (outpoint, tag) in tags {
+                if let Some(tag) = tag {
+                    db_tx.execute(
+                        "INSERT INTO coin_tags (wallet_id, outpoint, tag) VALUES (?1, ?2, ?3) \
+                        ON CONFLICT DO UPDATE SET tag=excluded.tag",
+                        rusqlite::params![WALLET_ID, outpoint.to_string(), tag],
+                    )?;
+                } else {
+                    db_tx.execute(
+                        "DELETE FROM coin_tags WHERE wallet_id = ?1 AND outpoint = ?2",
+                        rusqlite::params![WALLET_ID, outpoint.to_string()],
+                    )?;
+                }
+            }
+            Ok(())
         })
         .expect("Db must not fail")
     }
 
+    pub fn db_coin_tags(&mut self) -> Vec<DbCoinTag> {
+        db_query(
+            &mut self.conn,
+            "SELECT * FROM coin_tags WHERE wallet_id = ?1",
+            rusqlite::params![WALLET_ID],
+            |row| row.try_into(),
+        )
+        .expect("Db must not fail")
+    }
+
     /// Retrieves a limited and ordered list of transactions ids that happened during the given
     /// range.
     pub fn db_list_txids(&mut self, start: u32, end: u32, limit: u64) -> Vec<bitcoin::Txid> {
@@ -890,6 +1017,81 @@ impl SqliteConn {
         .expect("Db must not fail");
     }
 
+    /// Save a PSBT to be completed later and return its identifier.
+    pub fn save_psbt(&mut self, psbt: &Psbt, label: &str) -> i64 {
+        let mut id = 0;
+        db_exec(&mut self.conn, |db_tx| {
+            db_tx.execute(
+                "INSERT INTO saved_psbts (wallet_id, created_at, psbt, label) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![WALLET_ID, curr_timestamp(), psbt.serialize(), label],
+            )?;
+            id = db_tx.last_insert_rowid();
+            Ok(())
+        })
+        .expect("Db must not fail");
+        id
+    }
+
+    pub fn list_saved_psbts(&mut self) -> Vec<DbSavedPsbt> {
+        db_query(
+            &mut self.conn,
+            "SELECT * FROM saved_psbts WHERE wallet_id = ?1 ORDER BY id",
+            rusqlite::params![WALLET_ID],
+            |row| row.try_into(),
+        )
+        .expect("Db must not fail")
+    }
+
+    pub fn db_saved_psbt(&mut self, id: i64) -> Option<DbSavedPsbt> {
+        db_query(
+            &mut self.conn,
+            "SELECT * FROM saved_psbts WHERE wallet_id = ?1 AND id = ?2",
+            rusqlite::params![WALLET_ID, id],
+            |row| row.try_into(),
+        )
+        .expect("Db must not fail")
+        .pop()
+    }
+
+    /// Delete a saved PSBT. Returns false if it didn't exist.
+    pub fn delete_saved_psbt(&mut self, id: i64) -> bool {
+        let mut deleted = 0;
+        db_exec(&mut self.conn, |db_tx| {
+            deleted = db_tx.execute(
+                "DELETE FROM saved_psbts WHERE wallet_id = ?1 AND id = ?2",
+                rusqlite::params![WALLET_ID, id],
+            )?;
+            Ok(())
+        })
+        .expect("Db must not fail");
+        deleted > 0
+    }
+
+    /// Get the number and size of the pages of the database, and how many are unused.
+    pub fn db_size_info(&mut self) -> DbSizeInfo {
+        let pragma = |name: &str| -> u64 {
+            self.conn
+                .query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0))
+                .expect("Db must not fail")
+                .try_into()
+                .expect("Pragma values are never negative")
+        };
+        DbSizeInfo {
+            page_size: pragma("page_size"),
+            page_count: pragma("page_count"),
+            free_pages: pragma("freelist_count"),
+        }
+    }
+
+    /// Rebuild the database file to reclaim unused pages and gather statistics for the query
+    /// planner. This needs an exclusive lock on the database, it will wait for the other
+    /// connections to release theirs (up to the busy timeout).
+    pub fn vacuum(&mut self) {
+        self.conn
+            .execute_batch("VACUUM; ANALYZE;")
+            .expect("Db must not fail");
+    }
+
     // TODO: mark coinbase deposits that were mature and became immature as such.
     /// Unconfirm all data that was marked as being confirmed *after* the given chain
     /// tip, and set it as our new best block seen.
@@ -934,7 +1136,7 @@ impl SqliteConn {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::database::{BlockInfo, DbBlockInfo};
+    use crate::database::{BlockInfo, DatabaseBusy, DatabaseInterface, DbBlockInfo};
     use crate::testutils::*;
     use std::{
         collections::{HashMap, HashSet},
@@ -1387,6 +1589,61 @@ CREATE TABLE labels (
         fs::remove_dir_all(tmp_dir).unwrap();
     }
 
+    #[test]
+    fn db_list_labels() {
+        let (tmp_dir, _, _, db) = dummy_db();
+
+        {
+            let network = bitcoin::Network::Bitcoin;
+            let txid = LabelItem::from_str(
+                "0c62a990d20d54429e70859292e82374ba6b1b951a3ab60f26bb65fee5724ff7",
+                network,
+            )
+            .unwrap();
+            let outpoint = LabelItem::from_str(
+                "0c62a990d20d54429e70859292e82374ba6b1b951a3ab60f26bb65fee5724ff7:1",
+                network,
+            )
+            .unwrap();
+            let address =
+                LabelItem::from_str("bc1qnsexk3gnuyayu92fc3tczvc7k62u22a22ua2kv", network).unwrap();
+
+            let mut conn = db.connection().unwrap();
+            assert!(conn.db_list_labels(None, &HashSet::new()).is_empty());
+            conn.update_labels(&HashMap::from([
+                (txid.clone(), Some("tx".to_string())),
+                (outpoint.clone(), Some("coin".to_string())),
+                (address.clone(), Some("addr".to_string())),
+            ]));
+
+            // Without filter, all the labels are returned.
+            let labels = conn.db_list_labels(None, &HashSet::new());
+            assert_eq!(labels.len(), 3);
+
+            // They can be filtered by kind, by item, or both.
+            let labels = conn.db_list_labels(Some(LabelKind::OutPoint), &HashSet::new());
+            assert_eq!(labels.len(), 1);
+            assert_eq!(labels[0].item, outpoint.to_string());
+            assert_eq!(labels[0].value, "coin");
+            let items = HashSet::from([txid.clone(), address.clone()]);
+            let mut labels: Vec<String> = conn
+                .db_list_labels(None, &items)
+                .into_iter()
+                .map(|l| l.value)
+                .collect();
+            labels.sort();
+            assert_eq!(labels, vec!["addr".to_string(), "tx".to_string()]);
+            let labels = conn.db_list_labels(Some(LabelKind::Txid), &items);
+            assert_eq!(labels.len(), 1);
+            assert_eq!(labels[0].item, txid.to_string());
+            assert!(conn
+                .db_list_labels(Some(LabelKind::OutPoint), &items)
+                .is_empty());
+        }
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
     #[test]
     fn db_coins() {
         let (tmp_dir, _, _, db) = dummy_db();
@@ -2019,6 +2276,16 @@ CREATE TABLE labels (
             // crash during the second call).
             conn.set_derivation_index(7.into(), true, &secp);
             conn.set_derivation_index(8.into(), true, &secp);
+
+            // The receive index may be lowered, but never raised this way. Raising it again
+            // afterward doesn't insert the addresses already there twice.
+            conn.set_derivation_index(60.into(), false, &secp);
+            conn.lower_deposit_derivation_index(70.into());
+            assert_eq!(conn.db_wallet().deposit_derivation_index, 60.into());
+            conn.lower_deposit_derivation_index(10.into());
+            assert_eq!(conn.db_wallet().deposit_derivation_index, 10.into());
+            conn.set_derivation_index(60.into(), false, &secp);
+            assert_eq!(conn.db_wallet().deposit_derivation_index, 60.into());
         }
 
         fs::remove_dir_all(tmp_dir).unwrap();
@@ -2398,6 +2665,189 @@ CREATE TABLE labels (
         fs::remove_dir_all(tmp_dir).unwrap();
     }
 
+    #[test]
+    fn sqlite_coin_tags() {
+        let (tmp_dir, _, _, db) = dummy_db();
+
+        {
+            let mut conn = db.connection().unwrap();
+            assert!(conn.db_coin_tags().is_empty());
+
+            let op_a = bitcoin::OutPoint::from_str(
+                "5f5e6e0a2b4a4b2b5ea8b2e6f4d2d3c4a5e0f7e2b2c0a6f5e4d3c2b1a0f9e8d7:0",
+            )
+            .unwrap();
+            let op_b = bitcoin::OutPoint::from_str(
+                "5f5e6e0a2b4a4b2b5ea8b2e6f4d2d3c4a5e0f7e2b2c0a6f5e4d3c2b1a0f9e8d7:1",
+            )
+            .unwrap();
+            let mut tags = HashMap::new();
+            tags.insert(op_a, Some("exchange".to_string()));
+            tags.insert(op_b, Some("salary".to_string()));
+            conn.update_coin_tags(&tags);
+            let mut db_tags = conn.db_coin_tags();
+            db_tags.sort_by_key(|t| t.outpoint);
+            assert_eq!(
+                db_tags,
+                vec![
+                    DbCoinTag {
+                        outpoint: op_a,
+                        tag: "exchange".to_string()
+                    },
+                    DbCoinTag {
+                        outpoint: op_b,
+                        tag: "salary".to_string()
+                    }
+                ]
+            );
+
+            // Tags can be updated and removed.
+            let mut tags = HashMap::new();
+            tags.insert(op_a, Some("salary".to_string()));
+            tags.insert(op_b, None);
+            conn.update_coin_tags(&tags);
+            assert_eq!(
+                conn.db_coin_tags(),
+                vec![DbCoinTag {
+                    outpoint: op_a,
+                    tag: "salary".to_string()
+                }]
+            );
+        }
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn sqlite_busy_pool() {
+        let (tmp_dir, _, _, db) = dummy_db();
+        let timeout = time::Duration::from_millis(100);
+        let db = SqliteDb {
+            pool: ConnectionPool::new(db.pool.db_path().to_path_buf(), 1, timeout),
+        };
+
+        // Once all the connections are in use, the callers get an error after the pool timeout
+        // instead of waiting for one to be released.
+        {
+            let _conn = DatabaseInterface::connection(&db).unwrap();
+            assert!(matches!(
+                db.connection(),
+                Err(SqliteDbError::PoolTimeout(t)) if t == timeout
+            ));
+            assert_eq!(
+                DatabaseInterface::connection(&db).err(),
+                Some(DatabaseBusy(timeout))
+            );
+        }
+        DatabaseInterface::connection(&db).unwrap();
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn sqlite_saved_psbts() {
+        let (tmp_dir, _, _, db) = dummy_db();
+
+        {
+            let mut conn = db.connection().unwrap();
+            assert!(conn.list_saved_psbts().is_empty());
+            assert!(conn.db_saved_psbt(1).is_none());
+            assert!(!conn.delete_saved_psbt(1));
+
+            let psbt = Psbt::from_unsigned_tx(bitcoin::Transaction {
+                version: bitcoin::transaction::Version::TWO,
+                lock_time: bitcoin::absolute::LockTime::ZERO,
+                input: vec![bitcoin::TxIn::default()],
+                output: vec![bitcoin::TxOut::minimal_non_dust(ScriptBuf::default())],
+            })
+            .unwrap();
+            let first_id = conn.save_psbt(&psbt, "first");
+            // The same PSBT may be saved more than once.
+            let second_id = conn.save_psbt(&psbt, "");
+            assert_ne!(first_id, second_id);
+
+            let saved = conn.db_saved_psbt(first_id).unwrap();
+            assert_eq!(saved.id, first_id);
+            assert_eq!(saved.wallet_id, WALLET_ID);
+            assert_eq!(saved.psbt, psbt);
+            assert_eq!(saved.label, "first");
+            assert!(saved.created_at > 0);
+
+            let list = conn.list_saved_psbts();
+            assert_eq!(
+                list.iter().map(|s| s.id).collect::<Vec<_>>(),
+                vec![first_id, second_id]
+            );
+            assert_eq!(list[1].label, "");
+
+            assert!(conn.delete_saved_psbt(first_id));
+            assert!(!conn.delete_saved_psbt(first_id));
+            assert!(conn.db_saved_psbt(first_id).is_none());
+            assert_eq!(conn.list_saved_psbts().len(), 1);
+        }
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn sqlite_vacuum() {
+        let (tmp_dir, _, _, db) = dummy_db();
+
+        {
+            let mut conn = db.connection().unwrap();
+            let psbt = Psbt::from_unsigned_tx(bitcoin::Transaction {
+                version: bitcoin::transaction::Version::TWO,
+                lock_time: bitcoin::absolute::LockTime::ZERO,
+                input: vec![bitcoin::TxIn::default()],
+                output: vec![bitcoin::TxOut::minimal_non_dust(ScriptBuf::default())],
+            })
+            .unwrap();
+            // Fill the database and delete most of it to leave unused pages behind.
+            let label = "a".repeat(10_000);
+            let ids: Vec<i64> = (0..100).map(|_| conn.save_psbt(&psbt, &label)).collect();
+            for id in &ids[1..] {
+                assert!(conn.delete_saved_psbt(*id));
+            }
+            conn.update_labels(&HashMap::from([(
+                LabelItem::Txid(psbt.unsigned_tx.txid()),
+                Some("tx label".to_string()),
+            )]));
+            let size_before = conn.db_size_info();
+            assert!(size_before.free_pages > 0);
+
+            // A reader in the middle of a transaction delays the maintenance until it is done.
+            let mut reader = db.connection().unwrap();
+            let read_tx = reader.conn.transaction().unwrap();
+            let count: i64 = read_tx
+                .query_row("SELECT COUNT(*) FROM saved_psbts", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(count, 1);
+            let handle = std::thread::spawn(move || {
+                conn.vacuum();
+                conn
+            });
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            assert!(!handle.is_finished());
+            read_tx.commit().unwrap();
+            let mut conn = handle.join().unwrap();
+
+            // The unused pages were reclaimed, and the data is untouched.
+            let size_after = conn.db_size_info();
+            assert_eq!(size_after.free_pages, 0);
+            assert!(size_after.page_count < size_before.page_count);
+            let saved = conn.db_saved_psbt(ids[0]).unwrap();
+            assert_eq!(saved.psbt, psbt);
+            assert_eq!(saved.label, label);
+            assert_eq!(conn.list_saved_psbts().len(), 1);
+            let labels = conn.db_labels(&HashSet::from([LabelItem::Txid(psbt.unsigned_tx.txid())]));
+            assert_eq!(labels.len(), 1);
+            assert_eq!(labels[0].value, "tx label");
+            assert_eq!(db.connection().unwrap().db_size_info(), size_after);
+        }
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+
     #[test]
     fn sqlite_list_wallet_transactions() {
         let (tmp_dir, _, _, db) = dummy_db();
@@ -3019,7 +3469,7 @@ CREATE TABLE labels (
         {
             let mut conn = db.connection().unwrap();
             let version = conn.db_version();
-            assert_eq!(version, 8);
+            assert_eq!(version, 11);
         }
         // We should now be able to insert another PSBT, to query both, and the first PSBT must
         // have no associated timestamp.
@@ -3032,11 +3482,18 @@ CREATE TABLE labels (
                 .find(|db_spend| db_spend.psbt == first_psbt)
                 .unwrap();
             assert!(first_spend.updated_at.is_none());
+            assert!(first_spend.broadcast_time.is_none());
             let second_spend = db_spends
                 .iter()
                 .find(|db_spend| db_spend.psbt == second_psbt)
                 .unwrap();
             assert!(second_spend.updated_at.is_some());
+            assert!(second_spend.broadcast_time.is_none());
+
+            // Once broadcast, the Spend has a broadcast time.
+            conn.set_spend_broadcast(&first_psbt.unsigned_tx.txid());
+            let first_spend = conn.db_spend(&first_psbt.unsigned_tx.txid()).unwrap();
+            assert!(first_spend.broadcast_time.is_some());
         }
 
         // We should now be able to store an immature coin, query all of them, and the first two
@@ -3244,10 +3701,18 @@ CREATE TABLE labels (
 
             // Migrate the DB.
             maybe_apply_migration(&db_path, &bitcoin_txs).unwrap();
-            assert_eq!(conn.db_version(), 8);
+            assert_eq!(conn.db_version(), 11);
             // Migrating twice will be a no-op. No need to pass `bitcoin_txs` second time.
             maybe_apply_migration(&db_path, &[]).unwrap();
-            assert!(conn.db_version() == 8);
+            assert!(conn.db_version() == 11);
+            // The broadcast time column was added to the Spend transactions table.
+            db_query(
+                &mut conn.conn,
+                "SELECT broadcast_time FROM spend_transactions",
+                rusqlite::params![],
+                |row| row.get::<_, Option<u32>>(0),
+            )
+            .unwrap();
 
             // Compare the `DbCoin`s with the expected values.
             let coins_post = conn.coins(&[], &[]);
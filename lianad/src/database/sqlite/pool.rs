@@ -0,0 +1,208 @@
+//! A fixed-size pool of connections to the SQLite database, for the commands not to pay the cost
+//! of opening and setting up a new connection each time they access it.
+
+use crate::database::sqlite::SqliteDbError;
+
+use std::{
+    fmt, ops, path,
+    sync::{self, Arc},
+    time,
+};
+
+/// How long to wait for a busy database before failing a query.
+const BUSY_TIMEOUT: time::Duration = time::Duration::from_secs(60);
+
+/// How many prepared statements are kept per connection.
+const STATEMENT_CACHE_CAPACITY: usize = 32;
+
+#[derive(Debug, Default)]
+struct PoolState {
+    idle: Vec<rusqlite::Connection>,
+    // Number of connections opened, idle or checked out.
+    opened: usize,
+}
+
+pub struct ConnectionPool {
+    db_path: path::PathBuf,
+    size: usize,
+    timeout: time::Duration,
+    state: sync::Mutex<PoolState>,
+    released: sync::Condvar,
+}
+
+impl fmt::Debug for ConnectionPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("db_path", &self.db_path)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl ConnectionPool {
+    /// A pool of at most `size` connections, waiting at most `timeout` for one to be released
+    /// when they are all checked out. Connections are only opened once they are needed.
+    pub fn new(db_path: path::PathBuf, size: usize, timeout: time::Duration) -> Arc<Self> {
+        assert!(size > 0, "The pool must have at least one connection.");
+        Arc::new(ConnectionPool {
+            db_path,
+            size,
+            timeout,
+            state: sync::Mutex::new(PoolState::default()),
+            released: sync::Condvar::new(),
+        })
+    }
+
+    pub fn db_path(&self) -> &path::Path {
+        &self.db_path
+    }
+
+    fn open(&self) -> Result<rusqlite::Connection, SqliteDbError> {
+        let conn = rusqlite::Connection::open(&self.db_path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        Ok(conn)
+    }
+
+    /// Check out a connection, opening a new one if none is idle and the pool isn't full yet.
+    pub fn get(self: &Arc<Self>) -> Result<PooledConnection, SqliteDbError> {
+        let deadline = time::Instant::now() + self.timeout;
+        let mut state = self.state.lock().expect("Pool lock must not be poisoned");
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledConnection {
+                    conn: Some(conn),
+                    pool: self.clone(),
+                });
+            }
+            if state.opened < self.size {
+                state.opened += 1;
+                drop(state);
+                return match self.open() {
+                    Ok(conn) => Ok(PooledConnection {
+                        conn: Some(conn),
+                        pool: self.clone(),
+                    }),
+                    Err(e) => {
+                        self.state
+                            .lock()
+                            .expect("Pool lock must not be poisoned")
+                            .opened -= 1;
+                        self.released.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+            let now = time::Instant::now();
+            if now >= deadline {
+                return Err(SqliteDbError::PoolTimeout(self.timeout));
+            }
+            state = self
+                .released
+                .wait_timeout(state, deadline - now)
+                .expect("Pool lock must not be poisoned")
+                .0;
+        }
+    }
+
+    fn release(&self, conn: rusqlite::Connection) {
+        let mut state = self.state.lock().expect("Pool lock must not be poisoned");
+        // A connection left in the middle of a transaction would block the others.
+        if conn.is_autocommit() {
+            state.idle.push(conn);
+        } else {
+            log::error!("Dropping a database connection left in the middle of a transaction.");
+            state.opened -= 1;
+        }
+        drop(state);
+        self.released.notify_one();
+    }
+}
+
+/// A connection checked out from the pool, returned to it when dropped.
+pub struct PooledConnection {
+    // Only taken when dropped.
+    conn: Option<rusqlite::Connection>,
+    pool: Arc<ConnectionPool>,
+}
+
+impl ops::Deref for PooledConnection {
+    type Target = rusqlite::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn.as_ref().expect("Only taken when dropped")
+    }
+}
+
+impl ops::DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn.as_mut().expect("Only taken when dropped")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.release(conn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, thread};
+
+    #[test]
+    fn connection_pool() {
+        let tmp_dir = crate::testutils::tmp_dir();
+        fs::create_dir_all(&tmp_dir).unwrap();
+        let db_path = tmp_dir.join("pool.sqlite3");
+        let pool = ConnectionPool::new(db_path, 2, time::Duration::from_millis(200));
+
+        // Connections are reused once released.
+        {
+            let conn = pool.get().unwrap();
+            conn.execute("CREATE TABLE t (a INTEGER)", []).unwrap();
+        }
+        {
+            let _a = pool.get().unwrap();
+            let _b = pool.get().unwrap();
+            assert_eq!(pool.state.lock().unwrap().opened, 2);
+            // The pool is full.
+            assert!(matches!(pool.get(), Err(SqliteDbError::PoolTimeout(_))));
+        }
+        assert_eq!(pool.state.lock().unwrap().idle.len(), 2);
+
+        // A waiting thread gets the connection as soon as it is released.
+        let a = pool.get().unwrap();
+        let b = pool.get().unwrap();
+        let handle = thread::spawn({
+            let pool = pool.clone();
+            move || {
+                let conn = pool.get().unwrap();
+                conn.execute("INSERT INTO t (a) VALUES (1)", []).unwrap();
+            }
+        });
+        thread::sleep(time::Duration::from_millis(50));
+        drop(a);
+        handle.join().unwrap();
+        let count: i64 = b
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        drop(b);
+
+        // A connection left in a transaction isn't reused.
+        {
+            let mut conn = pool.get().unwrap();
+            let tx = conn.transaction().unwrap();
+            std::mem::forget(tx);
+        }
+        let state = pool.state.lock().unwrap();
+        assert_eq!((state.opened, state.idle.len()), (1, 1));
+        drop(state);
+
+        fs::remove_dir_all(tmp_dir).unwrap();
+    }
+}
@@ -103,7 +103,23 @@ CREATE TABLE spend_transactions (
     id INTEGER PRIMARY KEY NOT NULL,
     psbt BLOB UNIQUE NOT NULL,
     txid BLOB UNIQUE NOT NULL,
-    updated_at INTEGER
+    updated_at INTEGER,
+    broadcast_time INTEGER
+);
+
+/* PSBTs saved by the user to be completed later, for instance once more signatures
+ * were collected. Unlike Spend transactions, several versions of the same transaction
+ * may be saved. The 'created_at' field is the timestamp of insertion.
+ */
+CREATE TABLE saved_psbts (
+    id INTEGER PRIMARY KEY NOT NULL,
+    wallet_id INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    psbt BLOB NOT NULL,
+    label TEXT NOT NULL,
+    FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+        ON UPDATE RESTRICT
+        ON DELETE RESTRICT
 );
 
 /* Labels applied on addresses (0), outpoints (1), txids (2) */
@@ -114,6 +130,17 @@ CREATE TABLE labels (
     item TEXT UNIQUE NOT NULL,
     value TEXT NOT NULL
 );
+
+/* Privacy tags applied on outpoints. Coins with different tags should not be spent
+ * together. Like labels, they are kept independently from the coins table so they
+ * survive a coin being dropped and found again.
+ */
+CREATE TABLE coin_tags (
+    id INTEGER PRIMARY KEY NOT NULL,
+    wallet_id INTEGER NOT NULL,
+    outpoint TEXT UNIQUE NOT NULL,
+    tag TEXT NOT NULL
+);
 ";
 
 /// A row in the "tip" table.
@@ -309,6 +336,8 @@ pub struct DbSpendTransaction {
     pub psbt: Psbt,
     pub txid: bitcoin::Txid,
     pub updated_at: Option<u32>,
+    /// When the transaction was broadcast by us, if ever.
+    pub broadcast_time: Option<u32>,
 }
 
 impl TryFrom<&rusqlite::Row<'_>> for DbSpendTransaction {
@@ -325,12 +354,47 @@ impl TryFrom<&rusqlite::Row<'_>> for DbSpendTransaction {
         assert_eq!(txid, psbt.unsigned_tx.txid());
 
         let updated_at = row.get(3)?;
+        let broadcast_time = row.get(4)?;
 
         Ok(DbSpendTransaction {
             id,
             psbt,
             txid,
             updated_at,
+            broadcast_time,
+        })
+    }
+}
+
+/// A row in the "saved_psbts" table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DbSavedPsbt {
+    pub id: i64,
+    pub wallet_id: i64,
+    pub created_at: u32,
+    pub psbt: Psbt,
+    pub label: String,
+}
+
+impl TryFrom<&rusqlite::Row<'_>> for DbSavedPsbt {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &rusqlite::Row) -> Result<Self, Self::Error> {
+        let id: i64 = row.get(0)?;
+        let wallet_id: i64 = row.get(1)?;
+        let created_at: u32 = row.get(2)?;
+
+        let psbt: Vec<u8> = row.get(3)?;
+        let psbt = Psbt::deserialize(&psbt).expect("We only store valid PSBTs");
+
+        let label: String = row.get(4)?;
+
+        Ok(DbSavedPsbt {
+            id,
+            wallet_id,
+            created_at,
+            psbt,
+            label,
         })
     }
 }
@@ -386,6 +450,26 @@ impl TryFrom<&rusqlite::Row<'_>> for DbLabel {
     }
 }
 
+/// A row in the "coin_tags" table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbCoinTag {
+    pub outpoint: bitcoin::OutPoint,
+    pub tag: String,
+}
+
+impl TryFrom<&rusqlite::Row<'_>> for DbCoinTag {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &rusqlite::Row) -> Result<Self, Self::Error> {
+        let outpoint: String = row.get(2)?;
+        let outpoint =
+            bitcoin::OutPoint::from_str(&outpoint).expect("We only store valid outpoints");
+        let tag: String = row.get(3)?;
+
+        Ok(DbCoinTag { outpoint, tag })
+    }
+}
+
 /// A transaction together with its block info.
 #[derive(Clone, Debug, PartialEq)]
 pub struct DbWalletTransaction {
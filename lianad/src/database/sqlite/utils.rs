@@ -4,6 +4,8 @@ use std::{convert::TryInto, fs, path, time};
 
 use miniscript::bitcoin::{self, secp256k1};
 
+/// Number of addresses stored beyond the revealed ones, for the coins received on them to be
+/// recognized.
 pub const LOOK_AHEAD_LIMIT: u32 = 200;
 
 /// Perform a set of modifications to the database inside a single transaction
@@ -50,6 +52,24 @@ where
         .collect::<rusqlite::Result<Vec<T>>>()
 }
 
+/// Same as [`db_query`], but keeps the prepared statement in the connection cache to reuse it.
+/// Only for the frequent queries, as the cache is limited.
+pub fn db_query_cached<P, F, T>(
+    conn: &mut rusqlite::Connection,
+    stmt_str: &str,
+    params: P,
+    f: F,
+) -> Result<Vec<T>, rusqlite::Error>
+where
+    P: IntoIterator + rusqlite::Params,
+    P::Item: rusqlite::ToSql,
+    F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+{
+    conn.prepare_cached(stmt_str)?
+        .query_map(params, f)?
+        .collect::<rusqlite::Result<Vec<T>>>()
+}
+
 /// Internal helper for queries boilerplate
 pub fn db_query_row<P, F, T>(
     conn: &mut rusqlite::Connection,
@@ -482,6 +502,62 @@ fn migrate_v7_to_v8(conn: &mut rusqlite::Connection) -> Result<(), SqliteDbError
     Ok(())
 }
 
+fn migrate_v8_to_v9(conn: &mut rusqlite::Connection) -> Result<(), SqliteDbError> {
+    db_exec(conn, |db_tx| {
+        db_tx.execute_batch(
+            "
+            CREATE TABLE saved_psbts (
+                id INTEGER PRIMARY KEY NOT NULL,
+                wallet_id INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                psbt BLOB NOT NULL,
+                label TEXT NOT NULL,
+                FOREIGN KEY (wallet_id) REFERENCES wallets (id)
+                    ON UPDATE RESTRICT
+                    ON DELETE RESTRICT
+            );
+
+            UPDATE version SET version = 9;
+            ",
+        )?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn migrate_v9_to_v10(conn: &mut rusqlite::Connection) -> Result<(), SqliteDbError> {
+    db_exec(conn, |db_tx| {
+        db_tx.execute_batch(
+            "
+            CREATE TABLE coin_tags (
+                id INTEGER PRIMARY KEY NOT NULL,
+                wallet_id INTEGER NOT NULL,
+                outpoint TEXT UNIQUE NOT NULL,
+                tag TEXT NOT NULL
+            );
+
+            UPDATE version SET version = 10;
+            ",
+        )?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn migrate_v10_to_v11(conn: &mut rusqlite::Connection) -> Result<(), SqliteDbError> {
+    db_exec(conn, |db_tx| {
+        db_tx.execute_batch(
+            "
+            ALTER TABLE spend_transactions ADD COLUMN broadcast_time INTEGER;
+
+            UPDATE version SET version = 11;
+            ",
+        )?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
 /// Check the database version and if necessary apply the migrations to upgrade it to the current
 /// one. The `bitcoin_txs` parameter is here for the migration from versions 4 and earlier, which
 /// did not store the Bitcoin transactions in database, to versions 5 and later, which do. For a
@@ -545,6 +621,21 @@ pub fn maybe_apply_migration(
                 migrate_v7_to_v8(&mut conn)?;
                 log::warn!("Migration from database version 7 to version 8 successful.");
             }
+            8 => {
+                log::warn!("Upgrading database from version 8 to version 9.");
+                migrate_v8_to_v9(&mut conn)?;
+                log::warn!("Migration from database version 8 to version 9 successful.");
+            }
+            9 => {
+                log::warn!("Upgrading database from version 9 to version 10.");
+                migrate_v9_to_v10(&mut conn)?;
+                log::warn!("Migration from database version 9 to version 10 successful.");
+            }
+            10 => {
+                log::warn!("Upgrading database from version 10 to version 11.");
+                migrate_v10_to_v11(&mut conn)?;
+                log::warn!("Migration from database version 10 to version 11 successful.");
+            }
             _ => return Err(SqliteDbError::UnsupportedVersion(version)),
         }
     }
@@ -1,6 +1,9 @@
 use crate::{
-    commands::{CoinStatus, LabelItem},
-    jsonrpc::rpc::{Error, Params, Request, Response},
+    commands::{CoinStatus, LabelItem, LabelKind},
+    jsonrpc::{
+        rpc::{Error, Params, Request, Response},
+        server::{method_docs, method_names, ServedWallets},
+    },
     DaemonControl,
 };
 
@@ -8,8 +11,10 @@ use std::{
     collections::{HashMap, HashSet},
     convert::TryInto,
     str::FromStr,
+    time::Duration,
 };
 
+use liana::spend::CoinSelectionAlgorithm;
 use miniscript::bitcoin::{self, psbt::Psbt, Txid};
 
 fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
@@ -48,6 +53,8 @@ fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::V
         .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?;
     let change_address: Option<bitcoin::Address<bitcoin::address::NetworkUnchecked>> = params
         .get(3, "change_address")
+        // Allow to skip this optional parameter to set the next ones.
+        .filter(|v| !v.is_null())
         .map(|addr| {
             let addr_str = addr.as_str().ok_or_else(|| {
                 Error::invalid_params("Invalid 'change_address' parameter: must be a string.")
@@ -57,8 +64,96 @@ fn create_spend(control: &DaemonControl, params: Params) -> Result<serde_json::V
             })
         })
         .transpose()?;
+    let coin_selection: Option<CoinSelectionAlgorithm> = params
+        .get(4, "coin_selection")
+        .filter(|v| !v.is_null())
+        .map(|algo| {
+            algo.as_str()
+                .ok_or_else(|| {
+                    Error::invalid_params("Invalid 'coin_selection' parameter: must be a string.")
+                })?
+                .parse()
+                .map_err(|e| {
+                    Error::invalid_params(format!("Invalid 'coin_selection' parameter: {}", e))
+                })
+        })
+        .transpose()?;
+    let avoid_mixing_tags: bool = params
+        .get(5, "avoid_mixing_tags")
+        .filter(|v| !v.is_null())
+        .map(|avoid| {
+            avoid.as_bool().ok_or_else(|| {
+                Error::invalid_params("Invalid 'avoid_mixing_tags' parameter: must be a boolean.")
+            })
+        })
+        .transpose()?
+        .unwrap_or(false);
+    let spend_path: Option<u16> = params
+        .get(6, "spend_path")
+        .filter(|v| !v.is_null())
+        .map(|timelock| {
+            timelock
+                .as_u64()
+                .and_then(|t| t.try_into().ok())
+                .ok_or_else(|| Error::invalid_params("Invalid 'spend_path' parameter."))
+        })
+        .transpose()?;
 
-    let res = control.create_spend(&destinations, &outpoints, feerate, change_address)?;
+    let res = control.create_spend(
+        &destinations,
+        &outpoints,
+        feerate,
+        change_address,
+        coin_selection,
+        avoid_mixing_tags,
+        spend_path,
+    )?;
+    Ok(serde_json::json!(&res))
+}
+
+fn create_cpfp(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let outpoints = params
+        .get(0, "outpoints")
+        .ok_or_else(|| Error::invalid_params("Missing 'outpoints' parameter."))?
+        .as_array()
+        .and_then(|arr| {
+            arr.iter()
+                .map(|entry| {
+                    entry
+                        .as_str()
+                        .and_then(|e| bitcoin::OutPoint::from_str(e).ok())
+                })
+                .collect::<Option<Vec<bitcoin::OutPoint>>>()
+        })
+        .ok_or_else(|| Error::invalid_params("Invalid 'outpoints' parameter."))?;
+    let feerate: u64 = params
+        .get(1, "feerate")
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))?
+        .as_u64()
+        .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?;
+
+    let res = control.create_cpfp(&outpoints, feerate)?;
+    Ok(serde_json::json!(&res))
+}
+
+fn create_consolidation(
+    control: &DaemonControl,
+    params: Params,
+) -> Result<serde_json::Value, Error> {
+    let feerate: u64 = params
+        .get(0, "feerate")
+        .ok_or_else(|| Error::invalid_params("Missing 'feerate' parameter."))?
+        .as_u64()
+        .ok_or_else(|| Error::invalid_params("Invalid 'feerate' parameter."))?;
+    let max_inputs: usize = params
+        .get(1, "max_inputs")
+        .ok_or_else(|| Error::invalid_params("Missing 'max_inputs' parameter."))?
+        .as_u64()
+        .and_then(|m| m.try_into().ok())
+        .filter(|m| *m >= 2)
+        .ok_or_else(|| Error::invalid_params("Invalid 'max_inputs' parameter."))?;
+
+    let res = control.create_consolidation(feerate, max_inputs)?;
     Ok(serde_json::json!(&res))
 }
 
@@ -81,7 +176,44 @@ fn delete_spend(control: &DaemonControl, params: Params) -> Result<serde_json::V
         .as_str()
         .and_then(|s| bitcoin::Txid::from_str(s).ok())
         .ok_or_else(|| Error::invalid_params("Invalid 'txid' parameter."))?;
-    control.delete_spend(&txid);
+    control.delete_spend(&txid)?;
+
+    Ok(serde_json::json!({}))
+}
+
+fn save_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let psbt: Psbt = params
+        .get(0, "psbt")
+        .ok_or_else(|| Error::invalid_params("Missing 'psbt' parameter."))?
+        .as_str()
+        .and_then(|s| Psbt::from_str(s).ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'psbt' parameter."))?;
+    let label = match params.get(1, "label") {
+        Some(label) => label
+            .as_str()
+            .ok_or_else(|| Error::invalid_params("Invalid 'label' parameter."))?,
+        None => "",
+    };
+
+    Ok(serde_json::json!(&control.save_psbt(&psbt, label)?))
+}
+
+fn saved_psbt_id(params: &Params) -> Result<i64, Error> {
+    params
+        .get(0, "id")
+        .ok_or_else(|| Error::invalid_params("Missing 'id' parameter."))?
+        .as_i64()
+        .ok_or_else(|| Error::invalid_params("Invalid 'id' parameter."))
+}
+
+fn get_saved_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let id = saved_psbt_id(&params)?;
+    Ok(serde_json::json!(&control.get_saved_psbt(id)?))
+}
+
+fn delete_saved_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let id = saved_psbt_id(&params)?;
+    control.delete_saved_psbt(id)?;
 
     Ok(serde_json::json!({}))
 }
@@ -98,6 +230,20 @@ fn broadcast_spend(control: &DaemonControl, params: Params) -> Result<serde_json
     Ok(serde_json::json!({}))
 }
 
+fn get_pending_spend_fee_info(
+    control: &DaemonControl,
+    params: Params,
+) -> Result<serde_json::Value, Error> {
+    let txid = params
+        .get(0, "txid")
+        .ok_or_else(|| Error::invalid_params("Missing 'txid' parameter."))?
+        .as_str()
+        .and_then(|s| bitcoin::Txid::from_str(s).ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'txid' parameter."))?;
+
+    Ok(serde_json::json!(&control.pending_spend_fee_info(&txid)))
+}
+
 fn rbf_psbt(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
     let txid = params
         .get(0, "txid")
@@ -168,7 +314,7 @@ fn list_coins(control: &DaemonControl, params: Option<Params>) -> Result<serde_j
     } else {
         Vec::new()
     };
-    let res = control.list_coins(&statuses, &outpoints);
+    let res = control.list_coins(&statuses, &outpoints)?;
     Ok(serde_json::json!(&res))
 }
 
@@ -188,6 +334,53 @@ where
     )
 }
 
+fn derive_address(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let index: u32 = params
+        .get(0, "index")
+        .ok_or_else(|| Error::invalid_params("Missing 'index' parameter."))?
+        .as_u64()
+        .and_then(|i| i.try_into().ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'index' parameter."))?;
+    let change = params
+        .get(1, "change")
+        .map(|change| {
+            change
+                .as_bool()
+                .ok_or_else(|| Error::invalid_params("Invalid 'change' parameter."))
+        })
+        .transpose()?
+        .unwrap_or(false);
+
+    let res = control.derive_address(index, change)?;
+    Ok(serde_json::json!(&res))
+}
+
+fn lower_receive_index(
+    control: &DaemonControl,
+    params: Params,
+) -> Result<serde_json::Value, Error> {
+    let index: u32 = params
+        .get(0, "index")
+        .ok_or_else(|| Error::invalid_params("Missing 'index' parameter."))?
+        .as_u64()
+        .and_then(|i| i.try_into().ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'index' parameter."))?;
+    control.lower_receive_index(index)?;
+    Ok(serde_json::json!({}))
+}
+
+fn find_address(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let address = params
+        .get(0, "address")
+        .ok_or_else(|| Error::invalid_params("Missing 'address' parameter."))?
+        .as_str()
+        .and_then(|s| bitcoin::Address::from_str(s).ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'address' parameter."))?;
+
+    let res = control.find_address(address)?;
+    Ok(serde_json::json!(&res))
+}
+
 fn list_addresses(
     control: &DaemonControl,
     params: Option<Params>,
@@ -222,7 +415,7 @@ fn list_confirmed(control: &DaemonControl, params: Params) -> Result<serde_json:
         .ok_or_else(|| Error::invalid_params("Invalid 'limit' parameter."))?;
 
     Ok(serde_json::json!(
-        &control.list_confirmed_transactions(start, end, limit)
+        &control.list_confirmed_transactions(start, end, limit)?
     ))
 }
 
@@ -263,7 +456,30 @@ fn list_transactions(control: &DaemonControl, params: Params) -> Result<serde_js
                 .collect()
         })
         .ok_or_else(|| Error::invalid_params("Invalid 'txids' parameter."))?;
-    Ok(serde_json::json!(&control.list_transactions(&txids)))
+    Ok(serde_json::json!(&control.list_transactions(&txids)?))
+}
+
+fn wait_for_tx(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let txid = params
+        .get(0, "txid")
+        .ok_or_else(|| Error::invalid_params("Missing 'txid' parameter."))?
+        .as_str()
+        .and_then(|s| bitcoin::Txid::from_str(s).ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'txid' parameter."))?;
+    let confirmations: u32 = params
+        .get(1, "confirmations")
+        .ok_or_else(|| Error::invalid_params("Missing 'confirmations' parameter."))?
+        .as_u64()
+        .and_then(|c| c.try_into().ok())
+        .ok_or_else(|| Error::invalid_params("Invalid 'confirmations' parameter."))?;
+    let timeout_secs = params
+        .get(2, "timeout_secs")
+        .ok_or_else(|| Error::invalid_params("Missing 'timeout_secs' parameter."))?
+        .as_u64()
+        .ok_or_else(|| Error::invalid_params("Invalid 'timeout_secs' parameter."))?;
+    let res = control.wait_for_tx(&txid, confirmations, Duration::from_secs(timeout_secs))?;
+
+    Ok(serde_json::json!(&res))
 }
 
 fn start_rescan(control: &mut DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
@@ -331,7 +547,46 @@ fn update_labels(control: &DaemonControl, params: Params) -> Result<serde_json::
         items.insert(item, value);
     }
 
-    control.update_labels(&items);
+    control.update_labels(&items)?;
+    Ok(serde_json::json!({}))
+}
+
+fn set_coin_tags(control: &DaemonControl, params: Params) -> Result<serde_json::Value, Error> {
+    let mut tags = HashMap::new();
+    for (outpoint, tag) in params
+        .get(0, "tags")
+        .ok_or_else(|| Error::invalid_params("Missing 'tags' parameter."))?
+        .as_object()
+        .ok_or_else(|| Error::invalid_params("Invalid 'tags' parameter."))?
+        .iter()
+    {
+        let tag = if tag.is_null() {
+            None
+        } else {
+            let tag = tag.as_str().ok_or_else(|| {
+                Error::invalid_params(format!(
+                    "Invalid 'tags.{}' value: must be a string or null",
+                    outpoint
+                ))
+            })?;
+            if tag.is_empty() || tag.len() > 100 {
+                return Err(Error::invalid_params(format!(
+                    "Invalid 'tags.{}' value length: must be between 1 and 100 characters",
+                    outpoint
+                )));
+            }
+            Some(tag.to_string())
+        };
+        let outpoint = bitcoin::OutPoint::from_str(outpoint).map_err(|_| {
+            Error::invalid_params(format!(
+                "Invalid 'tags.{}' parameter: must be an outpoint",
+                outpoint
+            ))
+        })?;
+        tags.insert(outpoint, tag);
+    }
+
+    control.set_coin_tags(&tags)?;
     Ok(serde_json::json!({}))
 }
 
@@ -361,7 +616,76 @@ fn get_labels(control: &DaemonControl, params: Params) -> Result<serde_json::Val
         items.insert(item);
     }
 
-    Ok(serde_json::json!(control.get_labels(&items)))
+    Ok(serde_json::json!(control.get_labels(&items)?))
+}
+
+fn list_labels(
+    control: &DaemonControl,
+    params: Option<Params>,
+) -> Result<serde_json::Value, Error> {
+    let kind = params
+        .as_ref()
+        .and_then(|p| p.get(0, "kind"))
+        .filter(|v| !v.is_null())
+        .map(|kind| {
+            kind.as_str().and_then(LabelKind::from_arg).ok_or_else(|| {
+                Error::invalid_params(format!(
+                    "Invalid 'kind' parameter {}: must be one of 'address', 'tx' or 'outpoint'.",
+                    kind
+                ))
+            })
+        })
+        .transpose()?;
+    let mut items = HashSet::new();
+    if let Some(refs) = params
+        .as_ref()
+        .and_then(|p| p.get(1, "refs"))
+        .filter(|v| !v.is_null())
+    {
+        for item in refs
+            .as_array()
+            .ok_or_else(|| Error::invalid_params("Invalid 'refs' parameter."))?
+        {
+            let item = item
+                .as_str()
+                .and_then(|item| LabelItem::from_str(item, control.config.bitcoin_config.network))
+                .ok_or_else(|| {
+                    Error::invalid_params(format!(
+                        "Invalid item {} format: must be an address, a txid or an outpoint",
+                        item
+                    ))
+                })?;
+            items.insert(item);
+        }
+    }
+
+    Ok(serde_json::json!(control.list_labels(kind, &items)?))
+}
+
+fn help(params: Option<Params>) -> Result<serde_json::Value, Error> {
+    let method = params
+        .as_ref()
+        .and_then(|p| p.get(0, "method"))
+        .ok_or_else(|| Error::invalid_params("Missing 'method' parameter."))?
+        .as_str()
+        .ok_or_else(|| Error::invalid_params("Invalid 'method' parameter."))?;
+    let doc = method_docs()
+        .remove(method)
+        .ok_or_else(|| Error::invalid_params(format!("Unknown method '{}'.", method)))?;
+    Ok(serde_json::json!(doc))
+}
+
+fn list_methods() -> serde_json::Value {
+    let docs = method_docs();
+    let methods: Vec<serde_json::Value> = method_names()
+        .map(|name| {
+            serde_json::json!({
+                "name": name,
+                "description": docs[name].description,
+            })
+        })
+        .collect();
+    serde_json::json!({ "methods": methods })
 }
 
 /// Handle an incoming JSONRPC2 request.
@@ -373,6 +697,18 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
                 .ok_or_else(|| Error::invalid_params("Missing 'txid' parameter."))?;
             broadcast_spend(control, params)?
         }
+        "createconsolidation" => {
+            let params = req.params.ok_or_else(|| {
+                Error::invalid_params("Missing 'feerate' and 'max_inputs' parameters.")
+            })?;
+            create_consolidation(control, params)?
+        }
+        "createcpfp" => {
+            let params = req.params.ok_or_else(|| {
+                Error::invalid_params("Missing 'outpoints' and 'feerate' parameters.")
+            })?;
+            create_cpfp(control, params)?
+        }
         "createrecovery" => {
             let params = req.params.ok_or_else(|| {
                 Error::invalid_params("Missing 'address' and 'feerate' parameters.")
@@ -387,20 +723,59 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
             })?;
             create_spend(control, params)?
         }
+        "dbmaintenance" => serde_json::json!(&control.db_maintenance()?),
+        "deletesavedpsbt" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'id' parameter."))?;
+            delete_saved_psbt(control, params)?
+        }
         "delspendtx" => {
             let params = req
                 .params
                 .ok_or_else(|| Error::invalid_params("Missing 'txid' parameter."))?;
             delete_spend(control, params)?
         }
+        "lowerreceiveindex" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'index' parameter."))?;
+            lower_receive_index(control, params)?
+        }
         "rbfpsbt" => {
             let params = req.params.ok_or_else(|| {
                 Error::invalid_params("Missing 'txid', 'feerate' and 'is_cancel' parameters.")
             })?;
             rbf_psbt(control, params)?
         }
-        "getinfo" => serde_json::json!(&control.get_info()),
-        "getnewaddress" => serde_json::json!(&control.get_new_address()),
+        "deriveaddress" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'index' parameter."))?;
+            derive_address(control, params)?
+        }
+        "findaddress" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'address' parameter."))?;
+            find_address(control, params)?
+        }
+        "getfeeestimates" => serde_json::json!(&control.get_fee_estimates()?),
+        "getinfo" => serde_json::json!(&control.get_info()?),
+        "getnewaddress" => serde_json::json!(&control.get_new_address()?),
+        "getpendingspendfeeinfo" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'txid' parameter."))?;
+            get_pending_spend_fee_info(control, params)?
+        }
+        "getsavedpsbt" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'id' parameter."))?;
+            get_saved_psbt(control, params)?
+        }
+        "help" => help(req.params)?,
         "listcoins" => {
             let params = req.params;
             list_coins(control, params)?
@@ -417,6 +792,8 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
             })?;
             list_confirmed(control, params)?
         }
+        "listmethods" => list_methods(),
+        "listsavedpsbts" => serde_json::json!(&control.list_saved_psbts()?),
         "listspendtxs" => list_spendtxs(control, req.params)?,
         "listtransactions" => {
             let params = req.params.ok_or_else(|| {
@@ -426,6 +803,18 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
             })?;
             list_transactions(control, params)?
         }
+        "savepsbt" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'psbt' parameter."))?;
+            save_psbt(control, params)?
+        }
+        "setcointags" => {
+            let params = req
+                .params
+                .ok_or_else(|| Error::invalid_params("Missing 'tags' parameter."))?;
+            set_coin_tags(control, params)?
+        }
         "startrescan" => {
             let params = req
                 .params
@@ -439,6 +828,15 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
                 .ok_or_else(|| Error::invalid_params("Missing 'psbt' parameter."))?;
             update_spend(control, params)?
         }
+        "waitfortx" => {
+            let params = req.params.ok_or_else(|| {
+                Error::invalid_params(
+                    "The 'waitfortx' command requires 3 parameters: 'txid', 'confirmations' and \
+                     'timeout_secs'",
+                )
+            })?;
+            wait_for_tx(control, params)?
+        }
         "updatelabels" => {
             let params = req
                 .params
@@ -451,6 +849,7 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
                 .ok_or_else(|| Error::invalid_params("Missing 'items' parameter."))?;
             get_labels(control, params)?
         }
+        "listlabels" => list_labels(control, req.params)?,
         _ => {
             return Err(Error::method_not_found());
         }
@@ -458,3 +857,43 @@ pub fn handle_request(control: &mut DaemonControl, req: Request) -> Result<Respo
 
     Ok(Response::success(req.id, result))
 }
+
+/// Handle an incoming JSONRPC2 request, dispatching it to the wallet it is meant for.
+pub fn handle_wallets_request(
+    wallets: &mut ServedWallets,
+    mut req: Request,
+) -> Result<Response, Error> {
+    let wallets = match wallets {
+        ServedWallets::Single(control) => return handle_request(control, req),
+        ServedWallets::Multi(wallets) => wallets,
+    };
+
+    let method = req.method.clone();
+    let result = match method.as_str() {
+        "listwallets" => {
+            let names: Vec<&str> = wallets.iter().map(|(name, _)| name.as_str()).collect();
+            serde_json::json!({ "wallets": names })
+        }
+        // These don't depend on the wallet.
+        "help" => help(req.params.take())?,
+        "listmethods" => list_methods(),
+        "stop" => serde_json::json!({}),
+        _ => {
+            let (control, wallet_method) = wallets
+                .iter_mut()
+                .find_map(|(name, control)| {
+                    let wallet_method = method.strip_prefix(name.as_str())?.strip_prefix('_')?;
+                    Some((control, wallet_method))
+                })
+                .ok_or_else(Error::method_not_found)?;
+            // The daemon is stopped as a whole, not per wallet.
+            if wallet_method == "stop" {
+                return Err(Error::method_not_found());
+            }
+            req.method = wallet_method.to_string();
+            return handle_request(control, req);
+        }
+    };
+
+    Ok(Response::success(req.id, result))
+}
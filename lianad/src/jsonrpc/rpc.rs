@@ -147,31 +147,57 @@ impl error::Error for Error {}
 
 impl From<commands::CommandError> for Error {
     fn from(e: commands::CommandError) -> Error {
-        match e {
+        // The message is kept for humans, the code in the data is for clients to branch on.
+        let mut data = serde_json::json!({
+            "code": e.code(),
+            "message": e.to_string(),
+        });
+        if let commands::CommandError::TxBroadcast(broadcast_error) = &e {
+            data["reason"] = serde_json::json!(broadcast_error.reason);
+        }
+        let error = match e {
             commands::CommandError::NoOutpointForSelfSend
             | commands::CommandError::UnknownOutpoint(..)
             | commands::CommandError::InvalidFeerate(..)
             | commands::CommandError::AlreadySpent(..)
             | commands::CommandError::ImmatureCoinbase(..)
+            | commands::CommandError::ConfirmedCoin(..)
             | commands::CommandError::Address(..)
             | commands::CommandError::SpendCreation(..)
             | commands::CommandError::InsufficientFunds(..)
+            | commands::CommandError::InsufficientFundsPerTag(..)
             | commands::CommandError::UnknownSpend(..)
+            | commands::CommandError::UnknownSavedPsbt(..)
             | commands::CommandError::SpendFinalization(..)
             | commands::CommandError::InsaneRescanTimestamp(..)
             | commands::CommandError::AlreadyRescanning
             | commands::CommandError::InvalidDerivationIndex
+            | commands::CommandError::DerivationIndexLimit(..)
+            | commands::CommandError::ReceiveIndexNotLower(..)
+            | commands::CommandError::UsedAddresses(..)
             | commands::CommandError::RbfError(..)
             | commands::CommandError::EmptyFilterList
-            | commands::CommandError::RecoveryNotAvailable => {
+            | commands::CommandError::RecoveryNotAvailable
+            | commands::CommandError::UnknownRecoveryPath(..)
+            | commands::CommandError::RecoveryPathNotAvailable(..)
+            | commands::CommandError::NothingToConsolidate => {
                 Error::new(ErrorCode::InvalidParams, e.to_string())
             }
-            commands::CommandError::RescanTrigger(..) => {
+            commands::CommandError::RescanTrigger(..)
+            | commands::CommandError::FeeEstimateUnavailable
+            | commands::CommandError::DbMaintenance(..)
+            | commands::CommandError::AddressHistory(..)
+            | commands::CommandError::TooManyTxWaits
+            | commands::CommandError::DatabaseBusy(..) => {
                 Error::new(ErrorCode::InternalError, e.to_string())
             }
             commands::CommandError::TxBroadcast(_) => {
                 Error::new(ErrorCode::ServerError(BROADCAST_ERROR), e.to_string())
             }
+        };
+        Error {
+            data: Some(data),
+            ..error
         }
     }
 }
@@ -210,3 +236,59 @@ impl Response {
         Response::new(id, None, Some(error))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_error_code() {
+        let error: Error = commands::CommandError::AlreadyRescanning.into();
+        assert_eq!(error.code, ErrorCode::InvalidParams);
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": -32602,
+                "message": "There is already a rescan ongoing. Please wait for it to complete first.",
+                "data": {
+                    "code": "LIANA_RESCAN_IN_PROGRESS",
+                    "message": "There is already a rescan ongoing. Please wait for it to complete first.",
+                },
+            })
+        );
+
+        let error: Error = commands::CommandError::TxBroadcast(commands::BroadcastError::new(
+            "min relay fee not met, 110 < 141".to_string(),
+        ))
+        .into();
+        assert_eq!(error.code, ErrorCode::ServerError(BROADCAST_ERROR));
+        let data = error.data.unwrap();
+        assert_eq!(data["code"], serde_json::json!("LIANA_BROADCAST_FAILED"));
+        assert_eq!(data["reason"], serde_json::json!("fee_too_low"));
+
+        let error: Error = commands::CommandError::RecoveryPathNotAvailable(
+            miniscript::bitcoin::OutPoint::null(),
+            52560,
+        )
+        .into();
+        assert_eq!(error.code, ErrorCode::InvalidParams);
+        assert_eq!(
+            error.data.unwrap()["code"],
+            serde_json::json!("LIANA_RECOVERY_PATH_NOT_AVAILABLE")
+        );
+
+        let error: Error = commands::CommandError::DatabaseBusy(crate::database::DatabaseBusy(
+            std::time::Duration::from_secs(60),
+        ))
+        .into();
+        assert_eq!(error.code, ErrorCode::InternalError);
+        assert_eq!(
+            error.message,
+            "Database busy: No database connection available after 60 seconds. Please try again."
+        );
+        assert_eq!(
+            error.data.unwrap()["code"],
+            serde_json::json!("LIANA_DATABASE_BUSY")
+        );
+    }
+}
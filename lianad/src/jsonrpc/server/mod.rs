@@ -2,21 +2,432 @@
 mod unix;
 
 use std::{
+    collections::HashMap,
     io, path,
     sync::{atomic::AtomicBool, Arc},
 };
 
 use crate::DaemonControl;
 
+use serde::Serialize;
+
+/// The wallets served through the JSONRPC server.
+#[derive(Clone)]
+pub enum ServedWallets {
+    /// A single wallet, whose methods are called directly.
+    Single(DaemonControl),
+    /// Several wallets by name. The methods of a wallet are called by prefixing them with its name
+    /// and an underscore, as in `mywallet_listcoins`.
+    Multi(Vec<(String, DaemonControl)>),
+}
+
+/// Documentation of a parameter of a JSONRPC method.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParamDoc {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub optional: bool,
+}
+
+/// Documentation of a JSONRPC method exposed by the daemon.
+#[derive(Debug, Clone, Serialize)]
+pub struct MethodDoc {
+    pub description: &'static str,
+    /// The parameters, in the order they must be passed when given as an array.
+    pub params: &'static [ParamDoc],
+    pub returns: &'static str,
+}
+
+const fn param(name: &'static str, description: &'static str) -> ParamDoc {
+    ParamDoc {
+        name,
+        description,
+        optional: false,
+    }
+}
+
+const fn opt_param(name: &'static str, description: &'static str) -> ParamDoc {
+    ParamDoc {
+        name,
+        description,
+        optional: true,
+    }
+}
+
+const METHODS: &[(&str, MethodDoc)] = &[
+    (
+        "broadcastspend",
+        MethodDoc {
+            description: "Finalize a stored Spend PSBT and broadcast it.",
+            params: &[param("txid", "Txid of the Spend transaction to broadcast.")],
+            returns: "An empty object.",
+        },
+    ),
+    (
+        "createconsolidation",
+        MethodDoc {
+            description: "Create a transaction spending the oldest confirmed coins back to the \
+                          wallet, as a single coin.",
+            params: &[
+                param("feerate", "Target feerate, in sat/vb."),
+                param("max_inputs", "Maximum number of coins to spend, at least 2."),
+            ],
+            returns: "The base64-encoded Spend PSBT, or the missing amount if not enough funds.",
+        },
+    ),
+    (
+        "createcpfp",
+        MethodDoc {
+            description: "Create a transaction spending unconfirmed coins back to the wallet to \
+                          bump the feerate of their parent (CPFP).",
+            params: &[
+                param("outpoints", "Unconfirmed coins to spend, as txid:vout."),
+                param("feerate", "Target feerate of the package, in sat/vb."),
+            ],
+            returns: "The base64-encoded CPFP PSBT along with the size and fee of its \
+                      unconfirmed ancestors, or the missing amount if not enough funds.",
+        },
+    ),
+    (
+        "createrecovery",
+        MethodDoc {
+            description:
+                "Create a transaction sweeping all coins for which a recovery path is available.",
+            params: &[
+                param("address", "The Bitcoin address to sweep the coins to."),
+                param("feerate", "Target feerate, in sat/vb."),
+                opt_param("timelock", "Number of blocks of the recovery path to use."),
+            ],
+            returns: "The base64-encoded recovery PSBT.",
+        },
+    ),
+    (
+        "createspend",
+        MethodDoc {
+            description: "Create a transaction spending coins to the given destinations.",
+            params: &[
+                param("destinations", "Map from Bitcoin address to value in sats."),
+                param(
+                    "outpoints",
+                    "Coins to spend, as txid:vout. Empty for automated selection.",
+                ),
+                param("feerate", "Target feerate, in sat/vb."),
+                opt_param("change_address", "Address to send the change to, if any."),
+                opt_param("coin_selection", "Coin selection algorithm to use."),
+                opt_param(
+                    "avoid_mixing_tags",
+                    "Whether the automated selection must not spend together coins with different privacy tags.",
+                ),
+                opt_param(
+                    "spend_path",
+                    "Timelock of the recovery path to spend through, instead of the primary path.",
+                ),
+            ],
+            returns: "The base64-encoded Spend PSBT, or the missing amount if not enough funds.",
+        },
+    ),
+    (
+        "dbmaintenance",
+        MethodDoc {
+            description: "Reclaim the space left unused in the database by deleted data.",
+            params: &[],
+            returns: "The size of the database before and after the maintenance.",
+        },
+    ),
+    (
+        "deletesavedpsbt",
+        MethodDoc {
+            description: "Delete a saved PSBT.",
+            params: &[param("id", "Identifier of the saved PSBT.")],
+            returns: "An empty object.",
+        },
+    ),
+    (
+        "delspendtx",
+        MethodDoc {
+            description: "Delete a stored Spend PSBT.",
+            params: &[param("txid", "Txid of the Spend transaction to delete.")],
+            returns: "An empty object.",
+        },
+    ),
+    (
+        "deriveaddress",
+        MethodDoc {
+            description: "Get the address at a derivation index, whether it was revealed or not.",
+            params: &[
+                param("index", "The derivation index."),
+                opt_param("change", "Whether to get the change address. Defaults to false."),
+            ],
+            returns: "The address along with its derivation index and whether it is change.",
+        },
+    ),
+    (
+        "findaddress",
+        MethodDoc {
+            description: "Get the derivation index of an address of the wallet.",
+            params: &[param("address", "The Bitcoin address to look for.")],
+            returns: "The address along with its derivation index and whether it is change, or \
+                      null if it was not found.",
+        },
+    ),
+    (
+        "getfeeestimates",
+        MethodDoc {
+            description: "Get feerate estimates for various confirmation targets.",
+            params: &[],
+            returns: "The feerate estimates, in sat/vb.",
+        },
+    ),
+    (
+        "getinfo",
+        MethodDoc {
+            description: "Get general information about the daemon.",
+            params: &[],
+            returns: "The version, network, block height, sync progress and descriptors.",
+        },
+    ),
+    (
+        "getlabels",
+        MethodDoc {
+            description: "Get the labels of the given items.",
+            params: &[param(
+                "items",
+                "Addresses, txids or outpoints to get the label of.",
+            )],
+            returns: "A mapping from item to label.",
+        },
+    ),
+    (
+        "getnewaddress",
+        MethodDoc {
+            description: "Get a new receiving address.",
+            params: &[],
+            returns: "The address and its derivation index.",
+        },
+    ),
+    (
+        "getpendingspendfeeinfo",
+        MethodDoc {
+            description: "Get the fee information of an unconfirmed transaction from the mempool.",
+            params: &[param("txid", "Txid of the unconfirmed transaction.")],
+            returns: "Its size, fee and effective feerate along with the fees of its ancestors \
+                      and descendants, or null if it is not in the mempool.",
+        },
+    ),
+    (
+        "getsavedpsbt",
+        MethodDoc {
+            description: "Get a saved PSBT.",
+            params: &[param("id", "Identifier of the saved PSBT.")],
+            returns: "The saved PSBT and its label.",
+        },
+    ),
+    (
+        "help",
+        MethodDoc {
+            description: "Get the documentation of a method.",
+            params: &[param("method", "Name of the method.")],
+            returns: "The description, parameters and result of the method.",
+        },
+    ),
+    (
+        "listaddresses",
+        MethodDoc {
+            description: "List receive and change addresses.",
+            params: &[
+                opt_param("start_index", "Index of the first address to list."),
+                opt_param("count", "Number of addresses to list."),
+            ],
+            returns: "The list of addresses.",
+        },
+    ),
+    (
+        "listcoins",
+        MethodDoc {
+            description: "List the coins of the wallet.",
+            params: &[
+                opt_param("statuses", "Statuses to filter coins by."),
+                opt_param("outpoints", "Outpoints to filter coins by, as txid:vout."),
+            ],
+            returns: "The list of coins.",
+        },
+    ),
+    (
+        "listconfirmed",
+        MethodDoc {
+            description: "List the transactions confirmed within a time window.",
+            params: &[
+                param("start", "Inclusive lower bound of the time window."),
+                param("end", "Inclusive upper bound of the time window."),
+                param("limit", "Maximum number of transactions to list."),
+            ],
+            returns: "The list of transactions.",
+        },
+    ),
+    (
+        "listlabels",
+        MethodDoc {
+            description: "List the labels, optionally only those of a kind or of given items.",
+            params: &[
+                opt_param("kind", "Kind of the labelled items: address, tx or outpoint."),
+                opt_param("refs", "Addresses, txids or outpoints to get the label of."),
+            ],
+            returns: "A mapping from item to label.",
+        },
+    ),
+    (
+        "listmethods",
+        MethodDoc {
+            description: "List the available methods.",
+            params: &[],
+            returns: "The name and description of each method.",
+        },
+    ),
+    (
+        "listsavedpsbts",
+        MethodDoc {
+            description: "List the saved PSBTs.",
+            params: &[],
+            returns: "The list of saved PSBTs.",
+        },
+    ),
+    (
+        "listspendtxs",
+        MethodDoc {
+            description: "List the stored Spend PSBTs.",
+            params: &[opt_param(
+                "txids",
+                "Txids of the Spend transactions to list.",
+            )],
+            returns: "The list of Spend PSBTs.",
+        },
+    ),
+    (
+        "listtransactions",
+        MethodDoc {
+            description: "List the wallet transactions with the given txids.",
+            params: &[param("txids", "Txids of the transactions to list.")],
+            returns: "The list of transactions.",
+        },
+    ),
+    (
+        "lowerreceiveindex",
+        MethodDoc {
+            description: "Lower the derivation index for the next receive address, after too \
+                          many addresses were revealed. Refused if any of the receive addresses \
+                          from this index was used.",
+            params: &[param("index", "The new derivation index for the next receive address.")],
+            returns: "Nothing.",
+        },
+    ),
+    (
+        "rbfpsbt",
+        MethodDoc {
+            description: "Create a PSBT replacing an unconfirmed Spend transaction.",
+            params: &[
+                param("txid", "Txid of the Spend transaction to replace."),
+                param(
+                    "is_cancel",
+                    "Whether to cancel the transaction or only bump its fee.",
+                ),
+                opt_param("feerate", "Target feerate, in sat/vb."),
+            ],
+            returns: "The base64-encoded replacement PSBT.",
+        },
+    ),
+    (
+        "savepsbt",
+        MethodDoc {
+            description: "Save an arbitrary PSBT.",
+            params: &[
+                param("psbt", "Base64-encoded PSBT to save."),
+                opt_param("label", "A description of the PSBT."),
+            ],
+            returns: "The identifier of the saved PSBT.",
+        },
+    ),
+    (
+        "setcointags",
+        MethodDoc {
+            description: "Set or remove the privacy tag of coins.",
+            params: &[param(
+                "tags",
+                "Map from outpoint (txid:vout) to tag, or null to remove it.",
+            )],
+            returns: "An empty object.",
+        },
+    ),
+    (
+        "startrescan",
+        MethodDoc {
+            description: "Rescan the block chain for wallet transactions.",
+            params: &[param(
+                "timestamp",
+                "Date to start rescanning from, as a UNIX timestamp.",
+            )],
+            returns: "An empty object.",
+        },
+    ),
+    (
+        "stop",
+        MethodDoc {
+            description: "Stop the daemon.",
+            params: &[],
+            returns: "An empty object.",
+        },
+    ),
+    (
+        "updatelabels",
+        MethodDoc {
+            description: "Set or remove the labels of addresses, txids or outpoints.",
+            params: &[param(
+                "labels",
+                "Mapping from item to label, or null to remove it.",
+            )],
+            returns: "An empty object.",
+        },
+    ),
+    (
+        "updatespend",
+        MethodDoc {
+            description: "Store a Spend PSBT, merging signatures with an existing one.",
+            params: &[param("psbt", "Base64-encoded Spend PSBT.")],
+            returns: "An empty object.",
+        },
+    ),
+    (
+        "waitfortx",
+        MethodDoc {
+            description: "Wait for a wallet transaction to reach a number of confirmations.",
+            params: &[
+                param("txid", "Txid of the transaction to wait for."),
+                param("confirmations", "Number of confirmations to wait for."),
+                param("timeout_secs", "Maximum number of seconds to wait for, up to a day."),
+            ],
+            returns: "The confirmations and the block height of the transaction, once reached or on timeout.",
+        },
+    ),
+];
+
+/// The documentation of all the JSONRPC methods, by name.
+pub fn method_docs() -> HashMap<&'static str, MethodDoc> {
+    METHODS.iter().cloned().collect()
+}
+
+/// The names of all the JSONRPC methods, in alphabetical order.
+pub fn method_names() -> impl Iterator<Item = &'static str> {
+    METHODS.iter().map(|(name, _)| *name)
+}
+
 #[cfg(unix)]
 pub fn run(
     socket_path: &path::Path,
-    daemon_control: DaemonControl,
+    wallets: ServedWallets,
     shutdown: Arc<AtomicBool>,
 ) -> Result<(), io::Error> {
     let listener = unix::rpcserver_setup(socket_path)?;
     log::info!("JSONRPC server started.");
-    let res = unix::rpcserver_loop(listener, daemon_control, shutdown);
+    let res = unix::rpcserver_loop(listener, wallets, shutdown);
     log::info!("JSONRPC server stopped.");
     res
 }
@@ -24,8 +435,61 @@ pub fn run(
 #[cfg(windows)]
 pub fn run(
     _socket_path: &path::Path,
-    _daemon_control: DaemonControl,
+    _wallets: ServedWallets,
     _shutdown: Arc<AtomicBool>,
 ) -> Result<(), io::Error> {
     todo!("Implement a json rpc server over Named pipe");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        jsonrpc::{
+            api,
+            rpc::{ReqId, Request},
+        },
+        testutils::*,
+    };
+
+    #[test]
+    fn methods_table() {
+        // Names must be unique and sorted, as they are listed in this order.
+        let names: Vec<_> = method_names().collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(names, sorted);
+
+        // Required parameters must come before the optional ones.
+        for (name, doc) in method_docs() {
+            let first_opt = doc.params.iter().position(|p| p.optional);
+            if let Some(i) = first_opt {
+                assert!(doc.params[i..].iter().all(|p| p.optional), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn wallet_prefixed_methods() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+        let mut wallets =
+            ServedWallets::Multi(vec![("mywallet".to_string(), ms.control().clone())]);
+        let req = |method: &str| Request {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: None,
+            id: ReqId::Num(0),
+        };
+
+        assert!(api::handle_wallets_request(&mut wallets, req("mywallet_getinfo")).is_ok());
+        assert!(api::handle_wallets_request(&mut wallets, req("getinfo")).is_err());
+        assert!(api::handle_wallets_request(&mut wallets, req("otherwallet_getinfo")).is_err());
+        // The daemon can only be stopped as a whole.
+        assert!(api::handle_wallets_request(&mut wallets, req("mywallet_stop")).is_err());
+        assert!(api::handle_wallets_request(&mut wallets, req("stop")).is_ok());
+
+        drop(wallets);
+        ms.shutdown();
+    }
+}
@@ -3,12 +3,10 @@
 //! This module implements the connections and streams handling logic for receiving
 //! JSONRPC2 requests on a Unix Domain Socket.
 
-use crate::{
-    jsonrpc::{
-        api,
-        rpc::{Request, Response},
-    },
-    DaemonControl,
+use crate::jsonrpc::{
+    api,
+    rpc::{Request, Response},
+    server::ServedWallets,
 };
 
 use std::{
@@ -22,6 +20,9 @@ use std::{
 // Maximum number of concurrent RPC connections we may accept.
 const MAX_CONNECTIONS: u32 = 16;
 
+// For how long a connection handler may wait for a request before checking for a shutdown.
+const CONNECTION_READ_TIMEOUT: time::Duration = time::Duration::from_millis(500);
+
 // Read a command from the stream.
 //
 // In order to both treat commands separately (respond as soon as we read one), and support
@@ -84,7 +85,7 @@ fn read_command(
 
 // Handle all messages from this connection.
 fn connection_handler(
-    mut control: DaemonControl,
+    mut wallets: ServedWallets,
     mut stream: net::UnixStream,
     shutdown: sync::Arc<atomic::AtomicBool>,
 ) -> Result<(), io::Error> {
@@ -92,13 +93,24 @@ fn connection_handler(
     let mut end = 0;
     let mut cursor = 0;
 
+    // Don't block on reading forever, to notice the shutdown of the server even if the client
+    // keeps an idle connection open.
+    stream.set_read_timeout(Some(CONNECTION_READ_TIMEOUT))?;
+
     while !shutdown.load(atomic::Ordering::Relaxed) {
-        let req = match read_command(&mut stream, &mut buf, &mut end, &mut cursor)? {
-            Some(req) => req,
-            None => {
+        let req = match read_command(&mut stream, &mut buf, &mut end, &mut cursor) {
+            Ok(Some(req)) => req,
+            Ok(None) => {
                 // Connection closed.
                 return Ok(());
             }
+            // The context is kept in the buffer, resume reading after checking for the shutdown.
+            Err(e)
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                continue
+            }
+            Err(e) => return Err(e),
         };
 
         let req_id = req.id.clone();
@@ -108,8 +120,8 @@ fn connection_handler(
         }
 
         log::trace!("JSONRPC request: {:?}", serde_json::to_string(&req));
-        let response =
-            api::handle_request(&mut control, req).unwrap_or_else(|e| Response::error(req_id, e));
+        let response = api::handle_wallets_request(&mut wallets, req)
+            .unwrap_or_else(|e| Response::error(req_id, e));
         log::trace!("JSONRPC response: {:?}", serde_json::to_string(&response));
         if let Err(e) = serde_json::to_writer(&stream, &response) {
             log::error!("Error writing response: '{}'", e);
@@ -124,12 +136,15 @@ fn connection_handler(
 /// The main event loop. Wait for connections, and treat requests sent through them.
 pub fn rpcserver_loop(
     listener: net::UnixListener,
-    daemon_control: DaemonControl,
+    wallets: ServedWallets,
     shutdown: sync::Arc<atomic::AtomicBool>,
 ) -> Result<(), io::Error> {
     // Keep it simple. We don't need great performances so just treat each connection in
     // its thread, with a given maximum number of connections.
     let connections_counter = sync::Arc::from(atomic::AtomicU32::new(0));
+    // The connection handlers are joined on shutdown, for none of them to hold on to a clone of
+    // the `DaemonControl` past the server's lifetime.
+    let mut handlers = Vec::new();
 
     listener.set_nonblocking(true)?;
     while !shutdown.load(atomic::Ordering::Relaxed) {
@@ -148,15 +163,16 @@ pub fn rpcserver_loop(
         connections_counter.fetch_add(1, atomic::Ordering::Relaxed);
 
         let handler_id = connections_counter.load(atomic::Ordering::Relaxed);
-        thread::Builder::new()
+        handlers.retain(|h: &thread::JoinHandle<()>| !h.is_finished());
+        let handler = thread::Builder::new()
             .name(format!("liana-jsonrpc-{}", handler_id))
             .spawn({
-                let control = daemon_control.clone();
+                let wallets = wallets.clone();
                 let counter = connections_counter.clone();
                 let shutdown = shutdown.clone();
 
                 move || {
-                    if let Err(e) = connection_handler(control, connection, shutdown) {
+                    if let Err(e) = connection_handler(wallets, connection, shutdown) {
                         log::error!("Error while handling connection {}: '{}'", handler_id, e);
                     } else {
                         log::trace!("Connection {} terminated without error.", handler_id);
@@ -164,6 +180,13 @@ pub fn rpcserver_loop(
                     counter.fetch_sub(1, atomic::Ordering::Relaxed);
                 }
             })?;
+        handlers.push(handler);
+    }
+
+    for handler in handlers {
+        if handler.join().is_err() {
+            log::error!("A JSONRPC connection handler panicked.");
+        }
     }
 
     Ok(())
@@ -15,9 +15,11 @@ pub use crate::bitcoin::{
     electrum::{Electrum, ElectrumError},
 };
 
+pub use crate::jsonrpc::server::{method_docs, method_names, MethodDoc, ParamDoc};
+
 use crate::jsonrpc::server;
 use crate::{
-    bitcoin::{poller, BitcoinInterface},
+    bitcoin::{fee_oracle, poller, BackendHealth, BitcoinInterface},
     config::Config,
     database::{
         sqlite::{FreshDbOptions, SqliteDb, SqliteDbError, MAX_DB_VERSION_NO_TX_DB},
@@ -28,10 +30,10 @@ use crate::{
 use std::{
     collections, error, fmt, fs, io, path,
     sync::{self, mpsc},
-    thread,
+    thread, time,
 };
 
-use miniscript::bitcoin::{constants::ChainHash, hashes::Hash, secp256k1, BlockHash};
+use miniscript::bitcoin::{constants::ChainHash, hashes::Hash, secp256k1, BlockHash, Network};
 
 #[cfg(not(test))]
 use std::panic;
@@ -97,6 +99,13 @@ pub enum StartupError {
     Electrum(ElectrumError),
     #[cfg(windows)]
     NoWatchonlyInDatadir,
+    NoWallet,
+    InvalidWalletName(String),
+    DuplicateWalletDatadir(path::PathBuf),
+    NetworkMismatch {
+        expected: Network,
+        found: String,
+    },
 }
 
 impl fmt::Display for StartupError {
@@ -130,6 +139,20 @@ impl fmt::Display for StartupError {
             Self::Database(e) => write!(f, "Error initializing database: '{}'.", e),
             Self::Bitcoind(e) => write!(f, "Error setting up bitcoind interface: '{}'.", e),
             Self::Electrum(e) => write!(f, "Error setting up Electrum interface: '{}'.", e),
+            Self::NoWallet => write!(f, "No wallet to serve."),
+            Self::InvalidWalletName(name) => write!(
+                f,
+                "Invalid or duplicate wallet name '{}'. Names may only contain alphanumeric characters and dashes.",
+                name
+            ),
+            Self::DuplicateWalletDatadir(dir_path) => write!(
+                f,
+                "Several wallets use the data directory at '{}'.", dir_path.display()
+            ),
+            Self::NetworkMismatch { expected, found } => write!(
+                f,
+                "Network mismatch: we are configured for '{}' but found {}.", expected, found
+            ),
             #[cfg(windows)]
             Self::NoWatchonlyInDatadir => {
                 write!(
@@ -147,6 +170,22 @@ impl fmt::Display for StartupError {
 
 impl error::Error for StartupError {}
 
+/// The daemon did not stop within the given duration.
+#[derive(Debug)]
+pub struct StopTimeout(pub time::Duration);
+
+impl fmt::Display for StopTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The daemon did not stop within {} seconds.",
+            self.0.as_secs()
+        )
+    }
+}
+
+impl error::Error for StopTimeout {}
+
 impl From<io::Error> for StartupError {
     fn from(e: io::Error) -> Self {
         Self::Io(e)
@@ -187,6 +226,40 @@ fn create_datadir(datadir_path: &path::Path) -> Result<(), StartupError> {
     };
 }
 
+fn genesis_block_hash(network: Network) -> BlockHash {
+    let chain_hash = ChainHash::using_genesis_block(network);
+    BlockHash::from_byte_array(*chain_hash.as_bytes())
+}
+
+// A bitcoind on another network than ours is reported as a network mismatch, like any backend.
+fn bitcoind_sanity_error(config: &Config, error: BitcoindError) -> StartupError {
+    match error {
+        BitcoindError::NetworkMismatch(_, bitcoind_net) => StartupError::NetworkMismatch {
+            expected: config.bitcoin_config.network,
+            found: format!("bitcoind running on '{}'", bitcoind_net),
+        },
+        e => e.into(),
+    }
+}
+
+// An Electrum server whose genesis block isn't the one of our network.
+fn electrum_network_mismatch(config: &Config, server_hash: BlockHash) -> StartupError {
+    let found = [
+        Network::Bitcoin,
+        Network::Testnet,
+        Network::Signet,
+        Network::Regtest,
+    ]
+    .iter()
+    .find(|net| genesis_block_hash(**net) == server_hash)
+    .map(|net| format!("an Electrum server on '{}'", net))
+    .unwrap_or_else(|| format!("an Electrum server with genesis block '{}'", server_hash));
+    StartupError::NetworkMismatch {
+        expected: config.bitcoin_config.network,
+        found,
+    }
+}
+
 // Connect to the SQLite database. Create it if starting fresh, and do some sanity checks.
 // If all went well, returns the interface to the SQLite database.
 fn setup_sqlite(
@@ -270,10 +343,12 @@ fn setup_bitcoind(
         _ => Err(StartupError::MissingBitcoindConfig)?,
     };
     let bitcoind = BitcoinD::new(bitcoind_config, wo_path_str)?;
-    bitcoind.node_sanity_checks(
-        config.bitcoin_config.network,
-        config.main_descriptor.is_taproot(),
-    )?;
+    bitcoind
+        .node_sanity_checks(
+            config.bitcoin_config.network,
+            config.main_descriptor.is_taproot(),
+        )
+        .map_err(|e| bitcoind_sanity_error(config, e))?;
     if fresh_data_dir {
         log::info!("Creating a new watchonly wallet on bitcoind.");
         bitcoind.create_watchonly_wallet(&config.main_descriptor)?;
@@ -292,6 +367,37 @@ fn setup_bitcoind(
     Ok(bitcoind)
 }
 
+/// Check the connection to the Bitcoin backend of this configuration, with the same sanity checks
+/// as at startup except those of the wallet. Nothing is written, neither to the data directory
+/// nor to the backend, so it can be used to test a configuration before applying it.
+pub fn check_bitcoin_backend(config: &Config) -> Result<(), StartupError> {
+    match config.bitcoin_backend.as_ref() {
+        Some(config::BitcoinBackend::Bitcoind(bitcoind_config)) => {
+            // The watchonly wallet isn't loaded, only the node's endpoint is used.
+            let bitcoind = BitcoinD::new(bitcoind_config, String::new())?;
+            bitcoind
+                .node_sanity_checks(
+                    config.bitcoin_config.network,
+                    config.main_descriptor.is_taproot(),
+                )
+                .map_err(|e| bitcoind_sanity_error(config, e))?;
+        }
+        Some(config::BitcoinBackend::Electrum(electrum_config)) => {
+            let client = electrum::client::Client::new(electrum_config)
+                .map_err(|e| StartupError::Electrum(ElectrumError::Client(e)))?;
+            let server_hash = client
+                .genesis_block()
+                .map_err(|e| StartupError::Electrum(ElectrumError::Client(e)))?
+                .hash;
+            if server_hash != genesis_block_hash(config.bitcoin_config.network) {
+                return Err(electrum_network_mismatch(config, server_hash));
+            }
+        }
+        None => return Err(StartupError::MissingBitcoinBackendConfig),
+    }
+    Ok(())
+}
+
 // Create an Electrum interface from a client and BDK-based wallet, and do some sanity checks.
 // If all went well, returns the interface to Electrum.
 fn setup_electrum(
@@ -306,7 +412,7 @@ fn setup_electrum(
     let client = electrum::client::Client::new(electrum_config)
         .map_err(|e| StartupError::Electrum(ElectrumError::Client(e)))?;
     // Then create the BDK-based wallet and populate it with DB data.
-    let mut db_conn = db.connection();
+    let mut db_conn = db.connection().expect("Database must be available");
     let tip = db_conn.chain_tip();
     let coins: Vec<_> = db_conn
         .coins(&[], &[])
@@ -336,10 +442,7 @@ fn setup_electrum(
         .map(|(tx, _, _)| tx)
         .collect();
     let (receive_index, change_index) = (db_conn.receive_index(), db_conn.change_index());
-    let genesis_hash = {
-        let chain_hash = ChainHash::using_genesis_block(config.bitcoin_config.network);
-        BlockHash::from_byte_array(*chain_hash.as_bytes())
-    };
+    let genesis_hash = genesis_block_hash(config.bitcoin_config.network);
     let bdk_wallet = electrum::wallet::BdkWallet::new(
         &config.main_descriptor,
         genesis_hash,
@@ -351,9 +454,12 @@ fn setup_electrum(
     );
     let full_scan = db_conn.rescan_timestamp().is_some();
     let electrum = Electrum::new(client, bdk_wallet, full_scan).map_err(StartupError::Electrum)?;
-    electrum
-        .sanity_checks(&genesis_hash)
-        .map_err(StartupError::Electrum)?;
+    electrum.sanity_checks(&genesis_hash).map_err(|e| match e {
+        ElectrumError::GenesisHashMismatch(_, server_hash, _) if server_hash != genesis_hash => {
+            electrum_network_mismatch(config, server_hash)
+        }
+        e => StartupError::Electrum(e),
+    })?;
     Ok(electrum)
 }
 
@@ -365,22 +471,47 @@ pub struct DaemonControl {
     // FIXME: Should we require Sync on DatabaseInterface rather than using a Mutex?
     db: sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
     secp: secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+    // The recovery thresholds reached as of the last poll.
+    recovery_warnings: sync::Arc<sync::Mutex<Vec<poller::RecoveryWarning>>>,
+    // The height of our tip as of the last poll, or 0 if we haven't polled yet.
+    latest_tip_height: sync::Arc<sync::atomic::AtomicU32>,
+    // Signaled by the poller after each poll.
+    poll_signal: sync::Arc<poller::PollSignal>,
+    // The progress of the Bitcoin backend sync as of the last check of the poller.
+    sync_progress: sync::Arc<poller::SharedSyncProgress>,
+    // Whether the Bitcoin backend can be reached, if tracked.
+    backend_health: Option<sync::Arc<BackendHealth>>,
+    // The number of commands currently waiting for a transaction to confirm.
+    tx_waits: sync::Arc<sync::atomic::AtomicU32>,
+    fee_oracle: fee_oracle::FeeOracle,
 }
 
 impl DaemonControl {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         config: Config,
         bitcoin: sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
         poller_sender: mpsc::SyncSender<poller::PollerMessage>,
         db: sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
         secp: secp256k1::Secp256k1<secp256k1::VerifyOnly>,
+        recovery_warnings: sync::Arc<sync::Mutex<Vec<poller::RecoveryWarning>>>,
+        latest_tip_height: sync::Arc<sync::atomic::AtomicU32>,
+        poll_signal: sync::Arc<poller::PollSignal>,
+        sync_progress: sync::Arc<poller::SharedSyncProgress>,
     ) -> DaemonControl {
         DaemonControl {
+            backend_health: bitcoin.health(),
+            fee_oracle: fee_oracle::FeeOracle::new(config.fee_sources.as_ref()),
             config,
             bitcoin,
             poller_sender,
             db,
             secp,
+            recovery_warnings,
+            latest_tip_height,
+            poll_signal,
+            sync_progress,
+            tx_waits: sync::Arc::from(sync::atomic::AtomicU32::from(0)),
         }
     }
 
@@ -393,15 +524,23 @@ impl DaemonControl {
 
 /// The handle to a Liana daemon. It might either be the handle for a daemon which exposes a
 /// JSONRPC server or one which exposes its API through a `DaemonControl`.
+///
+/// Stopping the daemon doesn't require exclusive ownership of its resources: other clones of the
+/// `DaemonControl` may still be alive. The poller and the JSONRPC server release their own on
+/// shutdown, and the Bitcoin interface is explicitly disconnected once they stopped.
 pub enum DaemonHandle {
     Controller {
         poller_sender: mpsc::SyncSender<poller::PollerMessage>,
         poller_handle: thread::JoinHandle<()>,
+        bit: sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
+        sync_progress: sync::Arc<poller::SharedSyncProgress>,
         control: DaemonControl,
     },
     Server {
         poller_sender: mpsc::SyncSender<poller::PollerMessage>,
         poller_handle: thread::JoinHandle<()>,
+        bit: sync::Arc<sync::Mutex<dyn BitcoinInterface>>,
+        sync_progress: sync::Arc<poller::SharedSyncProgress>,
         rpcserver_shutdown: sync::Arc<sync::atomic::AtomicBool>,
         rpcserver_handle: thread::JoinHandle<Result<(), io::Error>>,
     },
@@ -429,6 +568,20 @@ impl DaemonHandle {
 
         let secp = secp256k1::Secp256k1::verification_only();
 
+        // The errors, if any, are surfaced below when actually setting things up.
+        for warning in config.validate_without_starting().unwrap_or_default() {
+            log::warn!("{}", warning);
+        }
+
+        // Fail with a clear error before setting anything up if the descriptor isn't for our
+        // network.
+        if let Some(found) = config.descriptor_network_mismatch() {
+            return Err(StartupError::NetworkMismatch {
+                expected: config.bitcoin_config.network,
+                found,
+            });
+        }
+
         // First, check the data directory
         let mut data_dir = config
             .data_dir()
@@ -452,16 +605,24 @@ impl DaemonHandle {
             None
         };
 
-        // Then set up the database backend.
-        let db = match db {
-            Some(db) => sync::Arc::from(sync::Mutex::from(db)),
-            None => sync::Arc::from(sync::Mutex::from(setup_sqlite(
-                &config,
-                &data_dir,
-                fresh_data_dir,
-                &secp,
-                &bitcoind,
-            )?)) as sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+        // Then set up the database backend. The poller gets its own SQLite connection, not to
+        // compete with the commands for the shared ones.
+        let (db, poller_db) = match db {
+            Some(db) => {
+                let db: sync::Arc<sync::Mutex<dyn DatabaseInterface>> =
+                    sync::Arc::from(sync::Mutex::from(db));
+                (db.clone(), db)
+            }
+            None => {
+                let sqlite = setup_sqlite(&config, &data_dir, fresh_data_dir, &secp, &bitcoind)?;
+                let poller_db = sync::Arc::from(sync::Mutex::from(sqlite.dedicated()))
+                    as sync::Arc<sync::Mutex<dyn DatabaseInterface>>;
+                (
+                    sync::Arc::from(sync::Mutex::from(sqlite))
+                        as sync::Arc<sync::Mutex<dyn DatabaseInterface>>,
+                    poller_db,
+                )
+            }
         };
 
         // Finally set up the Bitcoin backend.
@@ -479,16 +640,30 @@ impl DaemonHandle {
 
         // Start the poller thread. Keep the thread handle to be able to check if it crashed. Store
         // an atomic to be able to stop it.
-        let mut bitcoin_poller =
-            poller::Poller::new(bit.clone(), db.clone(), config.main_descriptor.clone());
+        let recovery_warnings = sync::Arc::from(sync::Mutex::from(Vec::new()));
+        let latest_tip_height = sync::Arc::from(sync::atomic::AtomicU32::from(0));
+        let sync_progress = sync::Arc::new(poller::SharedSyncProgress::default());
+        let poll_signal = sync::Arc::new(poller::PollSignal::default());
+        let mut bitcoin_poller = poller::Poller::new(
+            bit.clone(),
+            poller_db,
+            config.main_descriptor.clone(),
+            config.recovery_thresholds.clone(),
+            recovery_warnings.clone(),
+            latest_tip_height.clone(),
+            sync_progress.clone(),
+            poll_signal.clone(),
+            config.warm_derivation_cache,
+        );
         let (poller_sender, poller_receiver) = mpsc::sync_channel(0);
         let poller_handle = thread::Builder::new()
             .name("Bitcoin Network poller".to_string())
             .spawn({
                 let poll_interval = config.bitcoin_config.poll_interval_secs;
+                let max_backoff = config.bitcoin_config.max_backoff_secs;
                 move || {
                     log::info!("Bitcoin poller started.");
-                    bitcoin_poller.poll_forever(poll_interval, poller_receiver);
+                    bitcoin_poller.poll_forever(poll_interval, max_backoff, poller_receiver);
                     log::info!("Bitcoin poller stopped.");
                 }
             })
@@ -496,7 +671,17 @@ impl DaemonHandle {
 
         // Create the API the external world will use to talk to us, either directly through the Rust
         // structure or through the JSONRPC server we may setup below.
-        let control = DaemonControl::new(config, bit, poller_sender.clone(), db, secp);
+        let control = DaemonControl::new(
+            config,
+            bit.clone(),
+            poller_sender.clone(),
+            db,
+            secp,
+            recovery_warnings,
+            latest_tip_height,
+            poll_signal,
+            sync_progress.clone(),
+        );
 
         if with_rpc_server {
             let rpcserver_shutdown = sync::Arc::from(sync::atomic::AtomicBool::from(false));
@@ -507,7 +692,11 @@ impl DaemonHandle {
                     move || {
                         let mut rpc_socket = data_dir;
                         rpc_socket.push("lianad_rpc");
-                        server::run(&rpc_socket, control, shutdown)?;
+                        server::run(
+                            &rpc_socket,
+                            server::ServedWallets::Single(control),
+                            shutdown,
+                        )?;
                         Ok(())
                     }
                 })
@@ -516,6 +705,8 @@ impl DaemonHandle {
             return Ok(DaemonHandle::Server {
                 poller_sender,
                 poller_handle,
+                bit,
+                sync_progress,
                 rpcserver_shutdown,
                 rpcserver_handle,
             });
@@ -524,6 +715,8 @@ impl DaemonHandle {
         Ok(DaemonHandle::Controller {
             poller_sender,
             poller_handle,
+            bit,
+            sync_progress,
             control,
         })
     }
@@ -558,38 +751,229 @@ impl DaemonHandle {
         }
     }
 
+    // The sync progress shared by the poller, unless it stopped.
+    fn shared_sync_progress(&self) -> Option<&poller::SharedSyncProgress> {
+        match self {
+            Self::Controller {
+                ref poller_handle,
+                ref sync_progress,
+                ..
+            }
+            | Self::Server {
+                ref poller_handle,
+                ref sync_progress,
+                ..
+            } => {
+                if poller_handle.is_finished() {
+                    None
+                } else {
+                    Some(sync_progress)
+                }
+            }
+        }
+    }
+
+    /// The progress of the Bitcoin backend sync, between 0 and 1, as of the last check of the
+    /// poller. It is `1.0` once synced. This is `None` if it wasn't checked yet or if the poller
+    /// stopped.
+    ///
+    /// Unlike the `getinfo` command this doesn't query the Bitcoin backend.
+    pub fn sync_progress(&self) -> Option<f32> {
+        self.shared_sync_progress()?.progress()
+    }
+
+    /// The number of blocks validated by the Bitcoin backend and the number of headers of the best
+    /// chain it knows of, in this order, as of the last check of the poller. This is `None` if it
+    /// wasn't checked yet or if the poller stopped.
+    pub fn sync_heights(&self) -> Option<(u64, u64)> {
+        self.shared_sync_progress()?.heights()
+    }
+
     /// Stop the Liana daemon. This returns any error which may have occurred.
+    ///
+    /// The poller and the JSONRPC server are stopped and joined first, so they don't use the
+    /// Bitcoin interface anymore once it gets disconnected.
     pub fn stop(self) -> Result<(), Box<dyn error::Error>> {
         match self {
             Self::Controller {
                 poller_sender,
                 poller_handle,
+                mut bit,
+                control,
                 ..
             } => {
+                // Our own clone is dropped, the caller may still hold others.
+                drop(control);
                 poller_sender
                     .send(poller::PollerMessage::Shutdown)
                     .expect("The other end should never have hung up before this.");
                 poller_handle.join().expect("Poller thread must not panic");
+                bit.disconnect();
                 Ok(())
             }
             Self::Server {
                 poller_sender,
                 poller_handle,
+                mut bit,
                 rpcserver_shutdown,
                 rpcserver_handle,
+                ..
             } => {
                 poller_sender
                     .send(poller::PollerMessage::Shutdown)
                     .expect("The other end should never have hung up before this.");
                 rpcserver_shutdown.store(true, sync::atomic::Ordering::Relaxed);
-                rpcserver_handle
+                let rpcserver_res = rpcserver_handle
                     .join()
-                    .expect("Poller thread must not panic")?;
+                    .expect("JSONRPC server thread must not panic");
                 poller_handle.join().expect("Poller thread must not panic");
+                bit.disconnect();
+                rpcserver_res?;
                 Ok(())
             }
         }
     }
+
+    /// Stop the Liana daemon like `stop`, but give up waiting after `timeout`, for instance if
+    /// the Bitcoin backend hangs. The daemon keeps stopping in the background in this case.
+    pub fn stop_with_timeout(self, timeout: time::Duration) -> Result<(), Box<dyn error::Error>> {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        thread::Builder::new()
+            .name("Liana daemon shutdown".to_string())
+            .spawn(move || {
+                let res = self.stop().map_err(|e| e.to_string());
+                // The caller may have given up already.
+                let _ = sender.send(res);
+            })
+            .expect("Spawning the shutdown thread should never fail.");
+        match receiver.recv_timeout(timeout) {
+            Ok(res) => res.map_err(|e| e.into()),
+            Err(_) => Err(StopTimeout(timeout).into()),
+        }
+    }
+}
+
+/// Several wallets served by a single daemon. Each wallet has its own data directory, database
+/// and poller, while a single JSONRPC server dispatches the requests to them by name.
+pub struct MultiDaemonHandle {
+    wallets: Vec<(String, DaemonHandle)>,
+    rpcserver_shutdown: sync::Arc<sync::atomic::AtomicBool>,
+    rpcserver_handle: thread::JoinHandle<Result<(), io::Error>>,
+}
+
+// Wallet names are used as a prefix of the JSONRPC methods, followed by an underscore.
+fn is_valid_wallet_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+impl MultiDaemonHandle {
+    /// Start the wallets with the given names and configurations, using the default Bitcoin and
+    /// database interfaces, along with a JSONRPC server listening at `rpc_socket`. The methods of a
+    /// wallet are called by prefixing them with its name and an underscore, as in
+    /// `mywallet_listcoins`.
+    ///
+    /// Names may only contain alphanumeric characters and dashes, and each wallet must have its
+    /// own data directory.
+    pub fn start(
+        wallets: Vec<(String, Config)>,
+        rpc_socket: path::PathBuf,
+    ) -> Result<Self, StartupError> {
+        if wallets.is_empty() {
+            return Err(StartupError::NoWallet);
+        }
+        let mut names = collections::HashSet::new();
+        let mut data_dirs = collections::HashSet::new();
+        for (name, config) in &wallets {
+            if !is_valid_wallet_name(name) || !names.insert(name.clone()) {
+                return Err(StartupError::InvalidWalletName(name.clone()));
+            }
+            let mut data_dir = config
+                .data_dir()
+                .ok_or(StartupError::DefaultDataDirNotFound)?;
+            data_dir.push(config.bitcoin_config.network.to_string());
+            if !data_dirs.insert(data_dir.clone()) {
+                return Err(StartupError::DuplicateWalletDatadir(data_dir));
+            }
+        }
+
+        let mut handles: Vec<(String, DaemonHandle)> = Vec::with_capacity(wallets.len());
+        for (name, config) in wallets {
+            log::info!("Starting wallet '{}'.", name);
+            match DaemonHandle::start_default(config, false) {
+                Ok(handle) => handles.push((name, handle)),
+                Err(e) => {
+                    // Don't leave the wallets started so far running.
+                    for (_, handle) in handles {
+                        if let Err(e) = handle.stop() {
+                            log::error!("Error stopping wallet: {}", e);
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        let controls = handles
+            .iter()
+            .map(|(name, handle)| match handle {
+                DaemonHandle::Controller { control, .. } => (name.clone(), control.clone()),
+                DaemonHandle::Server { .. } => unreachable!("Started without a JSONRPC server."),
+            })
+            .collect();
+        let rpcserver_shutdown = sync::Arc::from(sync::atomic::AtomicBool::from(false));
+        let rpcserver_handle = thread::Builder::new()
+            .name("JSONRPC server".to_string())
+            .spawn({
+                let shutdown = rpcserver_shutdown.clone();
+                move || {
+                    server::run(
+                        &rpc_socket,
+                        server::ServedWallets::Multi(controls),
+                        shutdown,
+                    )
+                }
+            })
+            .expect("Spawning the RPC server thread should never fail.");
+
+        Ok(MultiDaemonHandle {
+            wallets: handles,
+            rpcserver_shutdown,
+            rpcserver_handle,
+        })
+    }
+
+    /// The names of the wallets served, in the order they were given.
+    pub fn wallet_names(&self) -> impl Iterator<Item = &str> {
+        self.wallets.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Check whether all the wallets and the JSONRPC server are still up and running. If this
+    /// returns `false`, collect the error using the `stop` method.
+    pub fn is_alive(&self) -> bool {
+        !self.rpcserver_handle.is_finished()
+            && self.wallets.iter().all(|(_, handle)| handle.is_alive())
+    }
+
+    /// Stop the JSONRPC server then all the wallets. This returns the first error which occurred,
+    /// if any.
+    pub fn stop(self) -> Result<(), Box<dyn error::Error>> {
+        self.rpcserver_shutdown
+            .store(true, sync::atomic::Ordering::Relaxed);
+        let mut res: Result<(), Box<dyn error::Error>> = self
+            .rpcserver_handle
+            .join()
+            .expect("JSONRPC server thread must not panic")
+            .map_err(|e| e.into());
+        for (name, handle) in self.wallets {
+            if let Err(e) = handle.stop() {
+                log::error!("Error stopping wallet '{}': {}", name, e);
+                if res.is_ok() {
+                    res = Err(e);
+                }
+            }
+        }
+        res
+    }
 }
 
 #[cfg(all(test, unix))]
@@ -808,6 +1192,7 @@ mod tests {
         let bitcoin_config = BitcoinConfig {
             network,
             poll_interval_secs: time::Duration::from_secs(2),
+            max_backoff_secs: time::Duration::from_secs(2),
         };
         let bitcoind_config = BitcoindConfig {
             addr,
@@ -825,6 +1210,12 @@ mod tests {
             data_dir: Some(data_dir),
             log_level: log::LevelFilter::Debug,
             main_descriptor: desc,
+            coin_selection: Default::default(),
+            recovery_thresholds: Vec::new(),
+            warm_derivation_cache: false,
+            fee_sources: None,
+            max_derivation_index: config::DEFAULT_MAX_DERIVATION_INDEX,
+            min_feerate_sat_vb: config::DEFAULT_MIN_FEERATE,
         };
 
         // Start the daemon in a new thread so the current one acts as the bitcoind server.
@@ -865,6 +1256,79 @@ mod tests {
         // before checking the bitcoind sync status.
         t.join().unwrap();
 
+        // A mainnet descriptor can't be used on a test network. We fail before even connecting
+        // to bitcoind.
+        let mut testnet_config = config.clone();
+        testnet_config.bitcoin_config.network = bitcoin::Network::Testnet;
+        match DaemonHandle::start_default(testnet_config, false) {
+            Err(StartupError::NetworkMismatch { expected, .. }) => {
+                assert_eq!(expected, bitcoin::Network::Testnet)
+            }
+            _ => panic!("Must fail with a network mismatch"),
+        }
+
         fs::remove_dir_all(&tmp_dir).unwrap();
     }
+
+    #[test]
+    fn stop_with_control_clones() {
+        let bitcoind = DummyBitcoind::new();
+        let disconnected = bitcoind.disconnected.clone();
+        let ms = DummyLiana::new(bitcoind, DummyDatabase::new());
+
+        // Another thread holds on to a clone of the control, and keeps using it after the daemon
+        // stopped.
+        let (stopped_sender, stopped_receiver) = mpsc::channel();
+        let t = thread::spawn({
+            let control = ms.control().clone();
+            move || {
+                stopped_receiver.recv().unwrap();
+                control.get_info().unwrap();
+            }
+        });
+
+        ms.shutdown();
+        assert!(disconnected.load(sync::atomic::Ordering::Relaxed));
+        stopped_sender.send(()).unwrap();
+        t.join().unwrap();
+    }
+
+    #[test]
+    fn stop_with_timeout() {
+        let bitcoind = DummyBitcoind::new();
+        let disconnected = bitcoind.disconnected.clone();
+        let ms = DummyLiana::new(bitcoind, DummyDatabase::new());
+        ms.handle
+            .stop_with_timeout(time::Duration::from_secs(30))
+            .unwrap();
+        assert!(disconnected.load(sync::atomic::Ordering::Relaxed));
+        fs::remove_dir_all(ms.tmp_dir).unwrap();
+    }
+
+    #[test]
+    fn sync_progress() {
+        let ms = DummyLiana::new(DummyBitcoind::new(), DummyDatabase::new());
+
+        // The progress is shared by the poller once it checked the sync of the Bitcoin backend.
+        let start = time::Instant::now();
+        while ms.handle.sync_progress().is_none() {
+            assert!(start.elapsed() < time::Duration::from_secs(10));
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        assert_eq!(ms.handle.sync_progress(), Some(1.0));
+        assert_eq!(ms.handle.sync_heights(), Some((1_000, 1_000)));
+
+        ms.shutdown();
+    }
+
+    #[test]
+    fn wallet_names() {
+        assert!(is_valid_wallet_name("mywallet"));
+        assert!(is_valid_wallet_name("my-wallet-2"));
+        assert!(!is_valid_wallet_name(""));
+        // Underscores separate the name from the method.
+        assert!(!is_valid_wallet_name("my_wallet"));
+        assert!(!is_valid_wallet_name("my wallet"));
+        assert!(!is_valid_wallet_name("wallet/1"));
+    }
 }
@@ -1,12 +1,15 @@
 use crate::{
-    bitcoin::{BitcoinInterface, Block, BlockChainTip, MempoolEntry, SyncProgress, UTxO},
-    config::{BitcoinConfig, Config},
+    bitcoin::{
+        fee_oracle, BitcoinInterface, Block, BlockChainTip, MempoolEntry, SyncProgress, UTxO,
+    },
+    config::{BitcoinConfig, Config, DEFAULT_MAX_DERIVATION_INDEX, DEFAULT_MIN_FEERATE},
     database::{
-        BlockInfo, Coin, CoinStatus, DatabaseConnection, DatabaseInterface, LabelItem, Wallet,
+        BlockInfo, Coin, CoinStatus, DatabaseBusy, DatabaseConnection, DatabaseInterface,
+        DbSizeInfo, LabelItem, LabelKind, SavedPsbt, Wallet,
     },
     DaemonControl, DaemonHandle,
 };
-use liana::descriptors;
+use liana::{descriptors, spend::CoinSelectionAlgorithm};
 
 use std::convert::TryInto;
 use std::{
@@ -24,6 +27,15 @@ use miniscript::{
 
 pub struct DummyBitcoind {
     pub txs: HashMap<Txid, (Transaction, Option<Block>)>,
+    pub tip_time: Option<u32>,
+    /// The minimum relay feerate reported by the backend, in sat/vb.
+    pub min_relay_feerate: Option<u64>,
+    /// The addresses which received coins according to the backend.
+    pub used_addresses: sync::Arc<sync::Mutex<HashSet<bitcoin::Address>>>,
+    /// Set once the daemon disconnected from this interface.
+    pub disconnected: sync::Arc<sync::atomic::AtomicBool>,
+    /// The coins received according to the backend, returned at each poll.
+    pub received: Vec<UTxO>,
 }
 
 impl DummyBitcoind {}
@@ -32,6 +44,11 @@ impl DummyBitcoind {
     pub fn new() -> Self {
         Self {
             txs: HashMap::new(),
+            tip_time: None,
+            min_relay_feerate: None,
+            used_addresses: sync::Arc::new(sync::Mutex::new(HashSet::new())),
+            disconnected: sync::Arc::new(sync::atomic::AtomicBool::new(false)),
+            received: Vec::new(),
         }
     }
 }
@@ -80,7 +97,7 @@ impl BitcoinInterface for DummyBitcoind {
         _: &BlockChainTip,
         _: &[descriptors::SinglePathLianaDesc],
     ) -> Vec<UTxO> {
-        Vec::new()
+        self.received.clone()
     }
 
     fn confirmed_coins(
@@ -125,7 +142,7 @@ impl BitcoinInterface for DummyBitcoind {
     }
 
     fn tip_time(&self) -> Option<u32> {
-        None
+        self.tip_time
     }
 
     fn wallet_transaction(
@@ -142,6 +159,24 @@ impl BitcoinInterface for DummyBitcoind {
     fn mempool_entry(&self, _: &bitcoin::Txid) -> Option<MempoolEntry> {
         None
     }
+
+    fn fee_estimates(&self, _: &[u16]) -> fee_oracle::FeeEstimates {
+        fee_oracle::FeeEstimates::new()
+    }
+
+    fn min_relay_feerate(&self) -> Option<u64> {
+        self.min_relay_feerate
+    }
+
+    fn addresses_have_history(&self, addresses: &[bitcoin::Address]) -> Result<bool, String> {
+        let used_addresses = self.used_addresses.lock().unwrap();
+        Ok(addresses.iter().any(|addr| used_addresses.contains(addr)))
+    }
+
+    fn disconnect(&mut self) {
+        self.disconnected
+            .store(true, sync::atomic::Ordering::Relaxed);
+    }
 }
 
 struct DummyDbState {
@@ -150,7 +185,10 @@ struct DummyDbState {
     curr_tip: Option<BlockChainTip>,
     coins: HashMap<bitcoin::OutPoint, Coin>,
     txs: HashMap<bitcoin::Txid, bitcoin::Transaction>,
-    spend_txs: HashMap<bitcoin::Txid, (Psbt, Option<u32>)>,
+    spend_txs: HashMap<bitcoin::Txid, (Psbt, Option<u32>, Option<u32>)>,
+    saved_psbts: Vec<SavedPsbt>,
+    coin_tags: HashMap<bitcoin::OutPoint, String>,
+    addresses: HashMap<bitcoin::Address, (bip32::ChildNumber, bool)>,
     timestamp: u32,
     rescan_timestamp: Option<u32>,
     last_poll_timestamp: Option<u32>,
@@ -161,10 +199,10 @@ pub struct DummyDatabase {
 }
 
 impl DatabaseInterface for DummyDatabase {
-    fn connection(&self) -> Box<dyn DatabaseConnection> {
-        Box::new(DummyDatabase {
+    fn connection(&self) -> Result<Box<dyn DatabaseConnection>, DatabaseBusy> {
+        Ok(Box::new(DummyDatabase {
             db: self.db.clone(),
-        })
+        }))
     }
 }
 
@@ -185,6 +223,9 @@ impl DummyDatabase {
                 coins: HashMap::new(),
                 txs: HashMap::new(),
                 spend_txs: HashMap::new(),
+                saved_psbts: Vec::new(),
+                coin_tags: HashMap::new(),
+                addresses: HashMap::new(),
                 timestamp: now,
                 rescan_timestamp: None,
                 last_poll_timestamp: None,
@@ -197,6 +238,20 @@ impl DummyDatabase {
             self.db.write().unwrap().coins.insert(coin.outpoint, coin);
         }
     }
+
+    /// Make this address known, at this derivation index and whether it is for change.
+    pub fn insert_address(
+        &mut self,
+        address: bitcoin::Address,
+        index: bip32::ChildNumber,
+        is_change: bool,
+    ) {
+        self.db
+            .write()
+            .unwrap()
+            .addresses
+            .insert(address, (index, is_change));
+    }
 }
 
 impl DatabaseConnection for DummyDatabase {
@@ -239,6 +294,13 @@ impl DatabaseConnection for DummyDatabase {
         self.db.write().unwrap().deposit_index = index;
     }
 
+    fn lower_receive_index(&mut self, index: bip32::ChildNumber) {
+        let mut db = self.db.write().unwrap();
+        if index < db.deposit_index {
+            db.deposit_index = index;
+        }
+    }
+
     fn change_index(&mut self) -> bip32::ChildNumber {
         self.db.read().unwrap().change_index
     }
@@ -360,9 +422,9 @@ impl DatabaseConnection for DummyDatabase {
 
     fn derivation_index_by_address(
         &mut self,
-        _: &bitcoin::Address,
+        address: &bitcoin::Address,
     ) -> Option<(bip32::ChildNumber, bool)> {
-        None
+        self.db.read().unwrap().addresses.get(address).copied()
     }
 
     fn coins_by_outpoints(
@@ -386,7 +448,7 @@ impl DatabaseConnection for DummyDatabase {
             .write()
             .unwrap()
             .spend_txs
-            .insert(txid, (psbt.clone(), None));
+            .insert(txid, (psbt.clone(), None, None));
     }
 
     fn spend_tx(&mut self, txid: &bitcoin::Txid) -> Option<Psbt> {
@@ -399,7 +461,15 @@ impl DatabaseConnection for DummyDatabase {
             .map(|x| x.0)
     }
 
-    fn list_spend(&mut self) -> Vec<(Psbt, Option<u32>)> {
+    fn set_spend_broadcast(&mut self, txid: &bitcoin::Txid) {
+        let mut db = self.db.write().unwrap();
+        let now = db.timestamp;
+        if let Some(spend) = db.spend_txs.get_mut(txid) {
+            spend.2 = Some(now);
+        }
+    }
+
+    fn list_spend(&mut self) -> Vec<(Psbt, Option<u32>, Option<u32>)> {
         self.db
             .read()
             .unwrap()
@@ -413,6 +483,40 @@ impl DatabaseConnection for DummyDatabase {
         self.db.write().unwrap().spend_txs.remove(txid);
     }
 
+    fn save_psbt(&mut self, psbt: &Psbt, label: &str) -> i64 {
+        let mut db = self.db.write().unwrap();
+        let id = db.saved_psbts.last().map(|s| s.id + 1).unwrap_or(1);
+        let created_at = db.timestamp;
+        db.saved_psbts.push(SavedPsbt {
+            id,
+            created_at,
+            psbt: psbt.clone(),
+            label: label.to_string(),
+        });
+        id
+    }
+
+    fn list_saved_psbts(&mut self) -> Vec<SavedPsbt> {
+        self.db.read().unwrap().saved_psbts.clone()
+    }
+
+    fn get_saved_psbt(&mut self, id: i64) -> Option<SavedPsbt> {
+        self.db
+            .read()
+            .unwrap()
+            .saved_psbts
+            .iter()
+            .find(|s| s.id == id)
+            .cloned()
+    }
+
+    fn delete_saved_psbt(&mut self, id: i64) -> bool {
+        let mut db = self.db.write().unwrap();
+        let len = db.saved_psbts.len();
+        db.saved_psbts.retain(|s| s.id != id);
+        db.saved_psbts.len() < len
+    }
+
     fn rollback_tip(&mut self, _: &BlockChainTip) {
         todo!()
     }
@@ -445,6 +549,29 @@ impl DatabaseConnection for DummyDatabase {
         todo!()
     }
 
+    fn list_labels(
+        &mut self,
+        _kind: Option<LabelKind>,
+        _items: &HashSet<LabelItem>,
+    ) -> HashMap<String, String> {
+        todo!()
+    }
+
+    fn update_coin_tags(&mut self, tags: &HashMap<bitcoin::OutPoint, Option<String>>) {
+        let mut db = self.db.write().unwrap();
+        for (outpoint, tag) in tags {
+            if let Some(tag) = tag {
+                db.coin_tags.insert(*outpoint, tag.clone());
+            } else {
+                db.coin_tags.remove(outpoint);
+            }
+        }
+    }
+
+    fn coin_tags(&mut self) -> HashMap<bitcoin::OutPoint, String> {
+        self.db.read().unwrap().coin_tags.clone()
+    }
+
     fn list_txids(&mut self, start: u32, end: u32, limit: u64) -> Vec<bitcoin::Txid> {
         let mut txids_and_time = Vec::new();
         let coins = &self.db.read().unwrap().coins;
@@ -519,6 +646,18 @@ impl DatabaseConnection for DummyDatabase {
         }
         wallet_txs
     }
+
+    fn size_info(&mut self) -> DbSizeInfo {
+        DbSizeInfo {
+            page_size: 4096,
+            page_count: 1,
+            free_pages: 0,
+        }
+    }
+
+    fn maintain(&mut self) {
+        // noop
+    }
 }
 
 pub struct DummyLiana {
@@ -560,6 +699,7 @@ impl DummyLiana {
         let bitcoin_config = BitcoinConfig {
             network,
             poll_interval_secs: time::Duration::from_secs(2),
+            max_backoff_secs: time::Duration::from_secs(2),
         };
 
         let owner_key = descriptors::PathInfo::Single(descriptor::DescriptorPublicKey::from_str("[aabbccdd]xpub68JJTXc1MWK8KLW4HGLXZBJknja7kDUJuFHnM424LbziEXsfkh1WQCiEjjHw4zLqSUm4rvhgyGkkuRowE9tCJSgt3TQB5J3SKAbZ2SdcKST/<0;1>/*").unwrap());
@@ -576,6 +716,12 @@ impl DummyLiana {
             data_dir: Some(data_dir),
             log_level: log::LevelFilter::Debug,
             main_descriptor: desc,
+            coin_selection: CoinSelectionAlgorithm::default(),
+            recovery_thresholds: Vec::new(),
+            warm_derivation_cache: false,
+            fee_sources: None,
+            max_derivation_index: DEFAULT_MAX_DERIVATION_INDEX,
+            min_feerate_sat_vb: DEFAULT_MIN_FEERATE,
         };
 
         let handle =
@@ -358,6 +358,62 @@ def multipath_desc(multi_signer, csv_values, is_taproot):
         return f"wsh(or_d({prim_multi},or_i(and_v(v:{first_recov_multi},older({csv_values[0]})),and_v(v:{second_recov_multi},older({csv_values[1]})))))"
 
 
+def many_recovery_paths_desc(multi_signer, csv_values, is_taproot):
+    prim_multi = multi_expression(3, multi_signer.prim_hds, is_taproot)
+    first_recov_multi = multi_expression(
+        2, multi_signer.recov_hds[csv_values[0]], is_taproot
+    )
+    second_recov_multi = multi_expression(
+        1, multi_signer.recov_hds[csv_values[1]], is_taproot
+    )
+    third_recov_multi = multi_expression(
+        1, multi_signer.recov_hds[csv_values[2]], is_taproot
+    )
+    recov_branches = f"or_i(and_v(v:{first_recov_multi},older({csv_values[0]})),or_i(and_v(v:{second_recov_multi},older({csv_values[1]})),and_v(v:{third_recov_multi},older({csv_values[2]}))))"
+    if is_taproot:
+        all_xpubs = multi_signer.prim_hds + [
+            hd for csv in csv_values for hd in multi_signer.recov_hds[csv]
+        ]
+        internal_key = unspendable_internal_xpub(all_xpubs).get_xpub()
+        return f"tr([00000000]{internal_key}/<0;1>/*,or_d({prim_multi},{recov_branches}))"
+    else:
+        return f"wsh(or_d({prim_multi},{recov_branches}))"
+
+
+@pytest.fixture
+def lianad_many_recovery_paths(bitcoin_backend, directory):
+    datadir = os.path.join(directory, "lianad")
+    os.makedirs(datadir, exist_ok=True)
+
+    # A 3-of-5 that degrades into a 2-of-3 after 10 blocks, into a 1-of-2 after 20 blocks
+    # and into a single key after 30 blocks.
+    csv_values = [10, 20, 30]
+    signer = MultiSigner(
+        5,
+        {csv_values[0]: 3, csv_values[1]: 2, csv_values[2]: 1},
+        is_taproot=USE_TAPROOT,
+    )
+    main_desc = Descriptor.from_str(
+        many_recovery_paths_desc(signer, csv_values, is_taproot=USE_TAPROOT)
+    )
+
+    lianad = Lianad(
+        datadir,
+        signer,
+        main_desc,
+        bitcoin_backend,
+    )
+
+    try:
+        lianad.start()
+        yield lianad
+    except Exception:
+        lianad.cleanup()
+        raise
+
+    lianad.cleanup()
+
+
 @pytest.fixture
 def lianad_multipath(bitcoin_backend, directory):
     datadir = os.path.join(directory, "lianad")
@@ -200,6 +200,44 @@ def test_multipath(lianad_multipath, bitcoind):
     # lianad_multipath.rpc.broadcastspend(txid)
 
 
+def test_third_recovery_path(lianad_many_recovery_paths, bitcoind):
+    """Sweep coins through the third recovery path of a descriptor with a multisig primary
+    path and three recovery paths."""
+    lianad = lianad_many_recovery_paths
+    receive_and_send(lianad, bitcoind)
+
+    # Make the third recovery path (30 blocks) available for all coins.
+    bitcoind.generate_block(30)
+    wait_for(
+        lambda: lianad.rpc.getinfo()["block_height"] == bitcoind.rpc.getblockcount()
+    )
+
+    # A timelock which doesn't correspond to any recovery path is refused.
+    with pytest.raises(
+        RpcError,
+        match="No recovery path with a timelock of 15 blocks in the descriptor",
+    ):
+        lianad.rpc.createrecovery(bitcoind.rpc.getnewaddress(), 2, 15)
+
+    # Sweep all coins through the third recovery path.
+    res = lianad.rpc.createrecovery(bitcoind.rpc.getnewaddress(), 2, 30)
+    reco_psbt = PSBT.from_base64(res["psbt"])
+    txid = reco_psbt.tx.txid().hex()
+    assert all(txin.nSequence == 30 for txin in reco_psbt.tx.vin)
+
+    # It can be signed using the single key of the third recovery path.
+    signed_psbt = lianad.signer.sign_psbt(reco_psbt, {30: [0]})
+    lianad.rpc.updatespend(signed_psbt.to_base64())
+    lianad.rpc.broadcastspend(txid)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(
+        lambda: all(
+            c["spend_info"] is not None and c["spend_info"]["height"] is not None
+            for c in lianad.rpc.listcoins()["coins"]
+        )
+    )
+
+
 def test_coinbase_deposit(lianad, bitcoind):
     """Check we detect deposits from (mature) coinbase transactions."""
     wait_for_sync = lambda: wait_for(
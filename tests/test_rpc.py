@@ -3,6 +3,8 @@ import random
 import re
 import time
 
+from concurrent import futures
+
 from fixtures import *
 from test_framework.serializations import (
     PSBT,
@@ -38,6 +40,64 @@ def test_getinfo(lianad):
     assert res["last_poll_timestamp"] > last_poll_timestamp
 
 
+def test_getinfo_recovery_thresholds(lianad, bitcoind):
+    """Test the warnings for configured recovery thresholds reported in getinfo."""
+    lianad.stop()
+    with open(lianad.conf_file, "a") as f:
+        f.write("\n[[recovery_thresholds]]\n")
+        f.write("blocks_before_expiry = 5\n")
+        f.write("min_amount_sat = 100000\n")
+    lianad.start()
+    assert lianad.rpc.getinfo()["warnings"] == []
+
+    # Receive a coin and get it confirmed. Its recovery path isn't available for another 10 blocks.
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.01)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    coin_height = bitcoind.rpc.getblockcount()
+    wait_for(lambda: lianad.rpc.listcoins()["coins"][0]["block_height"] == coin_height)
+    assert lianad.rpc.getinfo()["warnings"] == []
+
+    # Right before the threshold, no warning.
+    bitcoind.generate_block(4)
+    wait_for(lambda: lianad.rpc.getinfo()["block_height"] == coin_height + 4)
+    assert lianad.rpc.getinfo()["warnings"] == []
+
+    # Once the recovery path is within 5 blocks of being available, we get a warning.
+    bitcoind.generate_block(1)
+    wait_for(lambda: len(lianad.rpc.getinfo()["warnings"]) == 1)
+    assert lianad.rpc.getinfo()["warnings"][0] == {
+        "type": "recovery_threshold",
+        "blocks_before_expiry": 5,
+        "min_amount_sat": 100000,
+        "amount": 1000000,
+        "earliest_height": coin_height + 10,
+    }
+
+    # The warning persists once the recovery path is available.
+    bitcoind.generate_block(10)
+    wait_for(lambda: lianad.rpc.getinfo()["block_height"] == coin_height + 15)
+    assert len(lianad.rpc.getinfo()["warnings"]) == 1
+
+
+@pytest.mark.skipif(
+    BITCOIN_BACKEND_TYPE is not BitcoinBackendType.Bitcoind,
+    reason="Fee estimates availability depends on the backend.",
+)
+def test_getfeeestimates(lianad):
+    """Unreachable external sources are ignored, and no stale estimate is ever returned."""
+    lianad.stop()
+    with open(lianad.conf_file, "a") as f:
+        f.write("\n[fee_sources]\n")
+        f.write('esplora_urls = ["http://127.0.0.1:1"]\n')
+        f.write('recommended_fees_url = "http://127.0.0.1:1/api/v1/fees/recommended"\n')
+    lianad.start()
+
+    # There is no fee estimate on a fresh regtest chain, and the external sources are down.
+    with pytest.raises(RpcError, match="No source could provide a fee estimate."):
+        lianad.rpc.getfeeestimates()
+
+
 def test_getaddress(lianad):
     res = lianad.rpc.getnewaddress()
     assert "address" in res
@@ -63,6 +123,10 @@ def test_listaddresses(lianad):
     assert len(list4["addresses"]) == len(list3["addresses"]) + 2 == 2
     list5 = lianad.rpc.listaddresses(0)
     assert list4 == list5
+    # No coin was received on any of them.
+    assert all(
+        a["receive_coins"] == 0 and a["change_coins"] == 0 for a in list4["addresses"]
+    )
 
     # Will explicitly error on invalid start_index.
     with pytest.raises(
@@ -133,6 +197,11 @@ def test_listcoins(lianad, bitcoind):
     )
 
     assert lianad.rpc.listcoins()["coins"][0]["is_from_self"] is False
+    # Once confirmed, the coin reports when its recovery path becomes available.
+    assert res[0]["recovery_heights"] == []
+    assert lianad.rpc.listcoins()["coins"][0]["recovery_heights"] == [
+        {"timelock": 10, "height": block_height + 10}
+    ]
 
     # Same if the coin gets spent.
     spend_tx = spend_coins(lianad, bitcoind, (res[0],))
@@ -390,6 +459,47 @@ def test_jsonrpc_server(lianad, bitcoind):
     assert "psbt" in res
 
 
+def test_db_maintenance(lianad):
+    """Test reclaiming the unused space of the database."""
+    res = lianad.rpc.getinfo()
+    assert res["db_size"] > 0
+    assert all(w["type"] != "db_maintenance" for w in res["warnings"])
+
+    res = lianad.rpc.dbmaintenance()
+    assert res["size_after"] <= res["size_before"]
+    info = lianad.rpc.getinfo()
+    assert info["db_size"] == res["size_after"]
+    assert info["db_free_size"] == 0
+    # The daemon keeps working as usual afterward.
+    assert "address" in lianad.rpc.getnewaddress()
+
+
+def test_help(lianad):
+    """Test the method discovery commands."""
+    methods = lianad.rpc.listmethods()["methods"]
+    names = [m["name"] for m in methods]
+    assert names == sorted(names)
+    assert "createspend" in names and "help" in names
+    assert all(len(m["description"]) > 0 for m in methods)
+
+    # All the listed methods are documented.
+    for name in names:
+        doc = lianad.rpc.help(name)
+        assert doc["description"] == next(
+            m["description"] for m in methods if m["name"] == name
+        )
+    doc = lianad.rpc.help(method="createspend")
+    assert [(p["name"], p["optional"]) for p in doc["params"]] == [
+        ("destinations", False),
+        ("outpoints", False),
+        ("feerate", False),
+        ("change_address", True),
+        ("coin_selection", True),
+    ]
+    with pytest.raises(RpcError, match="Unknown method 'nonexistent'."):
+        lianad.rpc.help("nonexistent")
+
+
 def test_create_spend(lianad, bitcoind):
     # Receive a number of coins in different blocks on different addresses, and
     # one more on the same address.
@@ -590,6 +700,44 @@ def test_update_spend(lianad, bitcoind):
     assert psbt_merged.i[0].map[PSBT_IN_PARTIAL_SIG][dummy_pk_b] == dummy_sig_b
 
 
+def test_saved_psbts(lianad, bitcoind):
+    addr = lianad.rpc.getnewaddress()["address"]
+    bitcoind.rpc.sendtoaddress(addr, 0.2567)
+    wait_for(lambda: len(lianad.rpc.listcoins()["coins"]) > 0)
+    outpoints = [c["outpoint"] for c in lianad.rpc.listcoins()["coins"]]
+    destinations = {
+        bitcoind.rpc.getnewaddress(): 200_000,
+    }
+    psbt = lianad.rpc.createspend(destinations, outpoints, 6)["psbt"]
+
+    # Save it, then save a partially signed version of it.
+    assert lianad.rpc.listsavedpsbts()["psbts"] == []
+    first_id = lianad.rpc.savepsbt(psbt, "waiting for signatures")["id"]
+    signed_psbt = lianad.signer.sign_psbt(PSBT.from_base64(psbt)).to_base64()
+    second_id = lianad.rpc.savepsbt(signed_psbt)["id"]
+    assert first_id != second_id
+
+    saved = lianad.rpc.listsavedpsbts()["psbts"]
+    assert [s["id"] for s in saved] == [first_id, second_id]
+    assert saved[0]["psbt"] == psbt
+    assert saved[0]["label"] == "waiting for signatures"
+    assert saved[1]["label"] == ""
+    assert lianad.rpc.getsavedpsbt(second_id)["psbt"] == signed_psbt
+    # Saving a PSBT doesn't store a Spend transaction.
+    assert len(lianad.rpc.listspendtxs()["spend_txs"]) == 0
+
+    # They can be deleted, and are persisted across restarts.
+    lianad.rpc.deletesavedpsbt(first_id)
+    with pytest.raises(RpcError, match="Unknown saved PSBT.*"):
+        lianad.rpc.getsavedpsbt(first_id)
+    with pytest.raises(RpcError, match="Unknown saved PSBT.*"):
+        lianad.rpc.deletesavedpsbt(first_id)
+    lianad.stop()
+    lianad.start()
+    saved = lianad.rpc.listsavedpsbts()["psbts"]
+    assert [s["id"] for s in saved] == [second_id]
+
+
 def test_broadcast_spend(lianad, bitcoind):
     # Create a new coin and a spending tx for it.
     addr = lianad.rpc.getnewaddress()["address"]
@@ -614,9 +762,29 @@ def test_broadcast_spend(lianad, bitcoind):
     signed_psbt = lianad.signer.sign_psbt(PSBT.from_base64(res["psbt"]))
     lianad.rpc.updatespend(signed_psbt.to_base64())
 
+    spend_txs = lianad.rpc.listspendtxs(txids=[txid])["spend_txs"]
+    assert spend_txs[0]["broadcast_time"] is None
+
     # Now we've signed and stored it, the daemon will take care of finalizing
     # the PSBT before broadcasting the transaction.
     lianad.rpc.broadcastspend(txid)
+    spend_txs = lianad.rpc.listspendtxs(txids=[txid])["spend_txs"]
+    assert spend_txs[0]["broadcast_time"] is not None
+
+    # While it's unconfirmed we can get its fee information from the mempool. Its parent
+    # is unconfirmed too and accounted for in the ancestor fields.
+    fee_info = lianad.rpc.getpendingspendfeeinfo(txid)
+    entry = bitcoind.rpc.getmempoolentry(txid)
+    assert fee_info["vsize"] == entry["vsize"]
+    assert fee_info["fee"] == int(entry["fees"]["base"] * COIN)
+    assert fee_info["ancestor_vsize"] > fee_info["vsize"]
+    assert fee_info["ancestor_fee"] > fee_info["fee"]
+    assert fee_info["descendant_fee"] == fee_info["fee"]
+    assert fee_info["effective_feerate_vb"] <= fee_info["fee"] // fee_info["vsize"]
+
+    # Once confirmed it's not in the mempool anymore.
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: lianad.rpc.getpendingspendfeeinfo(txid) is None)
 
 
 # Use a descriptor that includes hardened derivation paths so that we can check
@@ -731,6 +899,40 @@ def test_start_rescan(lianad, bitcoind):
     assert lianad.rpc.getnewaddress() not in (first_address, second_address)
 
 
+def test_waitfortx(lianad, bitcoind):
+    """Test waiting for a transaction to reach a number of confirmations."""
+    addr = lianad.rpc.getnewaddress()["address"]
+    txid = bitcoind.rpc.sendtoaddress(addr, 0.01)
+
+    # It times out while the transaction is unconfirmed, or unknown.
+    start = time.time()
+    assert lianad.rpc.waitfortx(txid, 1, 1) == {"confirmations": 0, "height": None}
+    assert time.time() - start >= 1
+    unknown_txid = "00" * 32
+    res = lianad.rpc.waitfortx(unknown_txid, 0, 1)
+    assert res == {"confirmations": 0, "height": None}
+
+    # It returns as soon as the transaction reaches the confirmations, not waiting for the
+    # whole timeout.
+    with futures.ThreadPoolExecutor(max_workers=1) as executor:
+        waiting = executor.submit(lianad.rpc.waitfortx, txid, 2, 60)
+        bitcoind.generate_block(1, wait_for_mempool=txid)
+        height = bitcoind.rpc.getblockcount()
+        time.sleep(1)
+        assert not waiting.done()
+        bitcoind.generate_block(1)
+        assert waiting.result(timeout=30) == {"confirmations": 2, "height": height}
+
+    # Already reached, it returns immediately.
+    start = time.time()
+    assert lianad.rpc.waitfortx(txid, 1, 60)["confirmations"] >= 1
+    assert time.time() - start < 30
+
+    for params in [[txid, 1], [txid, -1, 1], ["notatxid", 1, 1]]:
+        with pytest.raises(RpcError, match="Invalid params"):
+            lianad.rpc.waitfortx(*params)
+
+
 def test_listtransactions(lianad, bitcoind):
     """Test listing of transactions by txid and timespan"""
 
@@ -1080,6 +1282,22 @@ def test_labels(lianad, bitcoind):
     assert sec_addr not in res
     assert res[random_address] == "this address is random"
 
+    # We can list the labels by kind of item, and by item.
+    res = lianad.rpc.listlabels()["labels"]
+    assert len(res) == 8
+    res = lianad.rpc.listlabels("address")["labels"]
+    assert res == {random_address: "this address is random"}
+    res = lianad.rpc.listlabels("tx")["labels"]
+    assert len(res) == 4
+    assert res[spend_txid] == "spend-tx-1"
+    assert res[inexistent_txid] == "inex_txid"
+    res = lianad.rpc.listlabels("outpoint", [coin["outpoint"], txid])["labels"]
+    assert res == {coin["outpoint"]: "first-coin-1"}
+    res = lianad.rpc.listlabels(None, [coin["outpoint"], txid])["labels"]
+    assert len(res) == 2
+    with pytest.raises(RpcError, match="Invalid params.*"):
+        lianad.rpc.listlabels("coin")
+
 
 def test_rbfpsbt_bump_fee(lianad, bitcoind):
     """Test the use of RBF to bump the fee of a transaction."""
@@ -1157,11 +1375,26 @@ def test_rbfpsbt_bump_fee(lianad, bitcoind):
         i.prevout.serialize() for i in rbf_1_psbt.tx.vin
     )
     # Check non-change output is the same in both.
-    assert first_psbt.tx.vout[0].nValue == rbf_1_psbt.tx.vout[0].nValue
-    assert first_psbt.tx.vout[0].scriptPubKey == rbf_1_psbt.tx.vout[0].scriptPubKey
+    first_change = first_res["change_output"]["index"]
+    rbf_1_change = rbf_1_res["change_output"]["index"]
+    first_dest, rbf_1_dest = 1 - first_change, 1 - rbf_1_change
+    assert (
+        first_psbt.tx.vout[first_dest].nValue
+        == rbf_1_psbt.tx.vout[rbf_1_dest].nValue
+    )
+    assert (
+        first_psbt.tx.vout[first_dest].scriptPubKey
+        == rbf_1_psbt.tx.vout[rbf_1_dest].scriptPubKey
+    )
     # Change address is the same but change amount will be lower in the replacement to pay higher fee.
-    assert first_psbt.tx.vout[1].nValue > rbf_1_psbt.tx.vout[1].nValue
-    assert first_psbt.tx.vout[1].scriptPubKey == rbf_1_psbt.tx.vout[1].scriptPubKey
+    assert (
+        first_psbt.tx.vout[first_change].nValue
+        > rbf_1_psbt.tx.vout[rbf_1_change].nValue
+    )
+    assert (
+        first_psbt.tx.vout[first_change].scriptPubKey
+        == rbf_1_psbt.tx.vout[rbf_1_change].scriptPubKey
+    )
     # Broadcast the replacement and wait for it to be detected.
     rbf_1_txid = sign_and_broadcast_psbt(lianad, rbf_1_psbt)
     wait_for(
@@ -1246,11 +1479,26 @@ def test_rbfpsbt_bump_fee(lianad, bitcoind):
         i.prevout.serialize() for i in rbf_2_psbt.tx.vin
     )
     # Check non-change output is the same in both.
-    assert rbf_1_psbt.tx.vout[0].nValue == rbf_2_psbt.tx.vout[0].nValue
-    assert rbf_1_psbt.tx.vout[0].scriptPubKey == rbf_2_psbt.tx.vout[0].scriptPubKey
+    rbf_1_change = rbf_1_res["change_output"]["index"]
+    rbf_2_change = rbf_2_res["change_output"]["index"]
+    rbf_1_dest, rbf_2_dest = 1 - rbf_1_change, 1 - rbf_2_change
+    assert (
+        rbf_1_psbt.tx.vout[rbf_1_dest].nValue
+        == rbf_2_psbt.tx.vout[rbf_2_dest].nValue
+    )
+    assert (
+        rbf_1_psbt.tx.vout[rbf_1_dest].scriptPubKey
+        == rbf_2_psbt.tx.vout[rbf_2_dest].scriptPubKey
+    )
     # Change address is the same but change amount will be lower in the replacement to pay higher fee.
-    assert rbf_1_psbt.tx.vout[1].nValue > rbf_2_psbt.tx.vout[1].nValue
-    assert rbf_1_psbt.tx.vout[1].scriptPubKey == rbf_2_psbt.tx.vout[1].scriptPubKey
+    assert (
+        rbf_1_psbt.tx.vout[rbf_1_change].nValue
+        > rbf_2_psbt.tx.vout[rbf_2_change].nValue
+    )
+    assert (
+        rbf_1_psbt.tx.vout[rbf_1_change].scriptPubKey
+        == rbf_2_psbt.tx.vout[rbf_2_change].scriptPubKey
+    )
 
     # Broadcast the replacement and wait for it to be detected.
     rbf_2_txid = sign_and_broadcast_psbt(lianad, rbf_2_psbt)
@@ -1383,9 +1631,15 @@ def test_rbfpsbt_cancel(lianad, bitcoind):
     ]
     # The replacement only has a change output.
     assert len(rbf_1_psbt.tx.vout) == 1
+    assert rbf_1_res["change_output"]["index"] == 0
+    assert rbf_1_res["change_output"]["amount"] == rbf_1_psbt.tx.vout[0].nValue
     # Change address is the same but change amount will be higher in the replacement as it is the only output.
-    assert first_psbt.tx.vout[1].nValue < rbf_1_psbt.tx.vout[0].nValue
-    assert first_psbt.tx.vout[1].scriptPubKey == rbf_1_psbt.tx.vout[0].scriptPubKey
+    first_change = first_res["change_output"]["index"]
+    assert first_psbt.tx.vout[first_change].nValue < rbf_1_psbt.tx.vout[0].nValue
+    assert (
+        first_psbt.tx.vout[first_change].scriptPubKey
+        == rbf_1_psbt.tx.vout[0].scriptPubKey
+    )
     # Broadcast the replacement and wait for it to be detected.
     rbf_1_txid = sign_and_broadcast_psbt(lianad, rbf_1_psbt)
     # The spend info of the coin used in the replacement will be updated.
@@ -42,6 +42,10 @@ def test_spend_change(lianad, bitcoind):
     assert len(spend_psbt.tx.vout) == 3
     # Since the transaction contains a change output there is no warning.
     assert len(res["warnings"]) == 0
+    # The change output is reported, and it pays to our first change address.
+    change = res["change_output"]
+    assert change["derivation_index"] == 0
+    assert spend_psbt.tx.vout[change["index"]].nValue == change["amount"]
 
     # Sign and broadcast this first Spend transaction.
     signed_psbt = lianad.signer.sign_psbt(spend_psbt)
@@ -290,6 +294,41 @@ def test_send_to_self(lianad, bitcoind):
     # Therefore, the DB receive index is now 4.
 
 
+def test_cpfp(lianad, bitcoind):
+    """Test we can bump the feerate of an incoming transaction using createcpfp."""
+    # A low-fee deposit, and a confirmed one.
+    addr = lianad.rpc.getnewaddress()["address"]
+    deposit_txid = bitcoind.rpc.sendtoaddress(
+        addr, 0.01, "", "", False, True, None, "unset", False, 1
+    )
+    bitcoind.generate_block(1, wait_for_mempool=deposit_txid)
+    addr = lianad.rpc.getnewaddress()["address"]
+    deposit_txid = bitcoind.rpc.sendtoaddress(
+        addr, 0.01, "", "", False, True, None, "unset", False, 1
+    )
+    wait_for(lambda: len(lianad.rpc.listcoins()["coins"]) == 2)
+    coins = lianad.rpc.listcoins()["coins"]
+    confirmed = next(c["outpoint"] for c in coins if c["block_height"] is not None)
+    unconfirmed = next(c["outpoint"] for c in coins if c["block_height"] is None)
+
+    # Only unconfirmed coins can be used.
+    with pytest.raises(RpcError, match="is already confirmed"):
+        lianad.rpc.createcpfp([confirmed], 10)
+
+    # The child pays for its parent so that the package reaches the target feerate.
+    res = lianad.rpc.createcpfp([unconfirmed], 10)
+    parent_entry = bitcoind.rpc.getmempoolentry(deposit_txid)
+    assert res["ancestors_vsize"] == parent_entry["ancestorsize"]
+    assert res["ancestors_fee"] == int(parent_entry["fees"]["ancestor"] * COIN)
+    psbt = PSBT.from_base64(res["psbt"])
+    assert len(psbt.tx.vin) == len(psbt.tx.vout) == 1
+    child_txid = sign_and_broadcast_psbt(lianad, psbt)
+    child_entry = bitcoind.rpc.getmempoolentry(child_txid)
+    package_fee = child_entry["fees"]["ancestor"] * COIN
+    package_feerate = package_fee / child_entry["ancestorsize"]
+    assert 9.5 < package_feerate < 10.5
+
+
 def test_coin_selection(lianad, bitcoind):
     """We can create a spend using coin selection."""
     # Send to an (external) address.
@@ -510,11 +549,26 @@ def test_coin_selection(lianad, bitcoind):
     psbt_manual = PSBT.from_base64(res_manual["psbt"])
 
     # Recipient details are the same for both.
-    assert spend_psbt_4.tx.vout[0].nValue == psbt_manual.tx.vout[0].nValue
-    assert spend_psbt_4.tx.vout[0].scriptPubKey == psbt_manual.tx.vout[0].scriptPubKey
+    auto_change = spend_res_4["change_output"]["index"]
+    manual_change = res_manual["change_output"]["index"]
+    auto_dest, manual_dest = 1 - auto_change, 1 - manual_change
+    assert (
+        spend_psbt_4.tx.vout[auto_dest].nValue
+        == psbt_manual.tx.vout[manual_dest].nValue
+    )
+    assert (
+        spend_psbt_4.tx.vout[auto_dest].scriptPubKey
+        == psbt_manual.tx.vout[manual_dest].scriptPubKey
+    )
     # Change amount is the same (change address will be different).
-    assert spend_psbt_4.tx.vout[1].nValue == psbt_manual.tx.vout[1].nValue
-    assert spend_psbt_4.tx.vout[1].scriptPubKey != psbt_manual.tx.vout[1].scriptPubKey
+    assert (
+        spend_psbt_4.tx.vout[auto_change].nValue
+        == psbt_manual.tx.vout[manual_change].nValue
+    )
+    assert (
+        spend_psbt_4.tx.vout[auto_change].scriptPubKey
+        != psbt_manual.tx.vout[manual_change].scriptPubKey
+    )
 
 
 def test_coin_selection_changeless(lianad, bitcoind):
@@ -534,6 +588,53 @@ def test_coin_selection_changeless(lianad, bitcoind):
     assert psbt.tx.vin[0].prevout.hash == txid_a
 
 
+def test_coin_selection_avoid_mixing_tags(lianad, bitcoind):
+    """Automated coin selection can be restricted to coins sharing a privacy tag."""
+    destinations = {
+        lianad.rpc.getnewaddress()["address"]: 0.0002,
+        lianad.rpc.getnewaddress()["address"]: 0.00015,
+        lianad.rpc.getnewaddress()["address"]: 0.00016,
+    }
+    txid = bitcoind.rpc.sendmany("", destinations)
+    bitcoind.generate_block(1, wait_for_mempool=txid)
+    wait_for(lambda: len(lianad.rpc.listcoins(["confirmed"])["coins"]) == 3)
+    coins = sorted(lianad.rpc.listcoins()["coins"], key=lambda c: c["amount"])
+    assert all(c["tag"] is None for c in coins)
+    lianad.rpc.setcointags(
+        {coins[0]["outpoint"]: "salary", coins[2]["outpoint"]: "exchange"}
+    )
+    tags = {c["outpoint"]: c["tag"] for c in lianad.rpc.listcoins()["coins"]}
+    assert tags[coins[0]["outpoint"]] == "salary"
+    assert tags[coins[1]["outpoint"]] is None
+    assert tags[coins[2]["outpoint"]] == "exchange"
+    with pytest.raises(RpcError, match="must be between 1 and 100 characters"):
+        lianad.rpc.setcointags({coins[0]["outpoint"]: ""})
+
+    # Funding this spend needs two coins, which would mix different tags.
+    dest = {bitcoind.rpc.getnewaddress(): 30_000}
+    res = lianad.rpc.createspend(dest, [], 1, None, None, False)
+    assert any("privacy tags" in w for w in res["warnings"])
+    with pytest.raises(
+        RpcError, match="No set of coins sharing a privacy tag can fund this transaction"
+    ):
+        lianad.rpc.createspend(dest, [], 1, None, None, True)
+    # If all the coins together can't fund it either, mixing tags wouldn't help.
+    too_much = {bitcoind.rpc.getnewaddress(): 60_000}
+    assert "missing" in lianad.rpc.createspend(too_much, [], 1, None, None, True)
+
+    # Once the untagged coin is tagged like the largest one, they can be spent together.
+    lianad.rpc.setcointags({coins[1]["outpoint"]: "exchange"})
+    res = lianad.rpc.createspend(dest, [], 1, None, None, True)
+    assert not any("privacy tags" in w for w in res["warnings"])
+    psbt = PSBT.from_base64(res["psbt"])
+    spent = sorted(f"{i.prevout.hash:064x}:{i.prevout.n}" for i in psbt.tx.vin)
+    assert spent == sorted([coins[1]["outpoint"], coins[2]["outpoint"]])
+
+    # Removing the tags allows to spend all the coins together again.
+    lianad.rpc.setcointags({c["outpoint"]: None for c in coins})
+    assert all(c["tag"] is None for c in lianad.rpc.listcoins()["coins"])
+
+
 def test_sweep(lianad, bitcoind):
     """
     Test we can leverage the change_address parameter to partially or completely sweep
@@ -561,6 +662,8 @@ def test_sweep(lianad, bitcoind):
     psbt = PSBT.from_base64(res["psbt"])
     assert len(psbt.tx.vout) == 1
     assert psbt.tx.vout[0].nValue > balance - 500
+    # The sweep output pays to an external address, it's not a change output.
+    assert res["change_output"] is None
     sign_and_broadcast_psbt(lianad, psbt)
     wait_for(
         lambda: all(